@@ -1,29 +1,58 @@
 // src/app.rs - Main application struct with Ratatui integration
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
 use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use ratatui::{layout::Rect, Frame};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    Frame,
+};
 use tokio::task::JoinHandle;
 
 use crate::components::{
-    chat_panel::ChatPanel, command_palette::CommandPalette, confirmation::Confirmation,
-    diff_view::DiffView, header::Header, help::HelpOverlay, prompt_input::PromptInput,
-    provider_select::ProviderSelect, sidebar::Sidebar, status_bar::StatusBar, Component,
+    chat_panel::ChatPanel, command_palette::CommandPalette, diff_view::DiffView, header::Header,
+    help::HelpOverlay, prompt_input::PromptInput, provider_select::ProviderSelect,
+    sidebar::Sidebar, status_bar::StatusBar, Component,
 };
 use crate::events::{AppEvent, EventHandler};
-use crate::executor::{execute_provider_detection, execute_provider_prompt, CommandResult};
-use crate::input::keymap::KeymapRegistry;
+use crate::executor::{
+    execute_provider_detection, execute_provider_prompt, execute_shell_command,
+    execute_test_command, CommandResult, OutputSource,
+};
+use crate::file_ops::WritePolicy;
+use crate::i18n::{t, tf};
+use crate::input::editing;
+use crate::input::keymap::{describe, KeymapRegistry};
 use crate::input::modes::InputMode;
-use crate::input::parser::{KeyParseOutcome, KeySequenceParser};
+use crate::input::parser::{key_to_token, token_to_key, KeyParseOutcome, KeySequenceParser};
+use crate::macros::MacroEvent;
 use crate::message::{Direction, Message};
 use crate::model::AppModel;
+use crate::sandbox::Sandbox;
+use crate::scheduler::{Scheduler, TaskKind};
 use crate::state::{
-    ChatMessage, DetectionState, ExecutionState, MessageStatus, Mode, ProviderInfo,
+    ChatMessage, DetectionState, DialogScroll, ExecutionState, FileChange, HunkStatus, LoginRetry,
+    MessageDetail, MessageStatus, Mode, OutputViewer, PromptMode, ProviderInfo,
 };
-use crate::ui::layout::{AppLayout, LayoutBreakpoints, LayoutManager};
+use crate::ui::layout::{saved_layout_path, AppLayout, LayoutManager, Pane, FOCUS_RATIO};
+use crate::watcher::FileWatcher;
+
+/// Most steps shown in the activity panel while a prompt runs
+const ACTIVITY_STEPS: usize = 8;
+
+/// Built-in providers to detect: id, display name, default command, config key
+const BUILTIN_PROVIDERS: [(&str, &str, &str, &str); 5] = [
+    ("claude", "Claude Code", "claude", "claude"),
+    ("aider", "Aider", "aider", "aider"),
+    ("copilot", "GitHub Copilot CLI", "copilot", "copilot"),
+    ("kiro", "Kiro CLI", "kiro", "q"),
+    // Talks to the server with curl
+    ("ollama", "Ollama", "curl", "ollama"),
+];
 
 pub struct App {
     pub model: AppModel,
@@ -41,21 +70,45 @@ pub struct App {
     sidebar: Sidebar,
     diff_view: DiffView,
     provider_select: ProviderSelect,
-    confirmation: Confirmation,
     help: HelpOverlay,
     status_bar: StatusBar,
     command_palette: CommandPalette,
+    review_watcher: Option<FileWatcher>,
+    /// Reloads the config when its files are saved
+    config_watcher: Option<FileWatcher>,
+    watched_files: BTreeSet<PathBuf>,
+    // Until the first provider is chosen, pick the preferred one as soon as it is detected
+    auto_select_provider: bool,
+    // Last terminal title written, to only write it when it changes
+    title: Option<String>,
+    // Input mode the cursor shape was last set for
+    cursor_mode: Option<InputMode>,
+    /// Provider detections from earlier runs, trusted for `detection_cache_hours`
+    detection_cache: crate::detection_cache::DetectionCache,
+    // Last accept or reject and its count, done again by `.`
+    last_verdict: Option<(usize, Message)>,
+    /// Limits how much background work runs at once
+    scheduler: Scheduler,
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
-        let model = AppModel::new()?;
+    pub fn new(options: &BTreeMap<String, String>) -> Result<Self> {
+        let model = AppModel::new(options)?;
+        let key_parser = KeySequenceParser::new(sequence_timeout(&model.state.config.display));
+        let mut layout = LayoutManager::from_config(&model.state.config.layout);
+        let scheduler = Scheduler::new(model.state.config.general.max_concurrent_tasks);
+        let keymap = KeymapRegistry::from_config(&model.state.config.keybindings);
+        // A split chosen with Ctrl+←/→ in an earlier run wins over the config
+        if let Some(ratio) = crate::ui::layout::load_chat_ratio(&saved_layout_path()) {
+            let current = layout.chat_ratio() as i16;
+            layout.adjust_chat_ratio(ratio as i16 - current);
+        }
         Ok(Self {
             model,
             event_handler: EventHandler::new(Duration::from_millis(16)),
-            keymap: KeymapRegistry::default_vim(),
-            key_parser: KeySequenceParser::new(Duration::from_millis(500)),
-            layout: LayoutManager::new(LayoutBreakpoints::default()),
+            keymap,
+            key_parser,
+            layout,
             pending_tasks: HashMap::new(),
             show_splash: true,
             splash_timer: 30,
@@ -65,47 +118,118 @@ impl App {
             sidebar: Sidebar::new(),
             diff_view: DiffView::new(),
             provider_select: ProviderSelect::new(),
-            confirmation: Confirmation::new(),
             help: HelpOverlay::new(),
             status_bar: StatusBar::new(),
             command_palette: CommandPalette::new(),
+            review_watcher: None,
+            config_watcher: None,
+            watched_files: BTreeSet::new(),
+            auto_select_provider: true,
+            title: None,
+            cursor_mode: None,
+            detection_cache: crate::detection_cache::DetectionCache::load(
+                &crate::detection_cache::cache_path(),
+            ),
+            last_verdict: None,
+            scheduler,
         })
     }
 
+    /// Pre-fill the prompt with text sent by `zcode pipe`, appending to any draft.
+    /// The prompt is focused unless a run or review is in progress.
+    fn receive_piped(&mut self, text: String) {
+        let state = &mut self.model.state;
+        if !state.prompt_buffer.trim().is_empty() {
+            state.prompt_buffer.push_str("\n\n");
+        }
+        state.prompt_buffer.push_str(&text);
+        state.cursor_position = state.prompt_buffer.len();
+
+        let lines = text.lines().count();
+        if matches!(
+            state.mode,
+            Mode::PromptEntry
+                | Mode::ChatHistory
+                | Mode::Output
+                | Mode::Usage
+                | Mode::Perf
+                | Mode::History
+                | Mode::MessageDetail
+                | Mode::Sessions
+                | Mode::Grep
+                | Mode::ConfigErrors
+                | Mode::TemplateForm
+                | Mode::Help
+        ) {
+            state.mode = Mode::PromptEntry;
+            state.status_info.current_task = tf("status.piped", &[("count", &lines)]);
+        } else {
+            state.status_info.current_task = tf("status.piped_added", &[("count", &lines)]);
+        }
+    }
+
     pub async fn run(
         &mut self,
         terminal: &mut ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>,
     ) -> Result<()> {
         // Start provider detection once splash ends
-        self.start_provider_detection();
+        self.start_provider_detection(false);
+        // Best effort, like the review watcher; `:config edit` still reloads
+        self.config_watcher = FileWatcher::new(
+            crate::config::Config::source_paths().into(),
+            self.event_handler.task_sender(),
+            AppEvent::ConfigChanged,
+        )
+        .ok();
+        crate::pipe::spawn_inbox_reader(
+            &crate::lock::instances_dir(),
+            self.event_handler.task_sender(),
+        );
 
         loop {
+            let started = std::time::Instant::now();
             terminal.draw(|f| self.view(f))?;
+            self.model.state.perf.record_frame(started.elapsed());
+            self.update_title();
+            self.update_cursor_shape();
 
             if let Some(evt) = self.event_handler.next().await {
                 if let Some(msg) = self.handle_event(evt).await? {
-                    // Handle OpenEditor specially to access terminal
-                    if let Message::OpenEditor { path, line } = msg {
-                        self.open_file_in_editor(terminal, path, line).await?;
-                    } else {
-                        self.handle_message(msg).await?;
+                    // Messages that suspend the TUI need the terminal
+                    match msg {
+                        Message::OpenEditor { path, line } => {
+                            self.open_file_in_editor(terminal, path, line).await?;
+                        }
+                        Message::Login => self.run_provider_login(terminal),
+                        Message::ApplyHunkAndEdit => self.apply_hunk_and_edit(terminal),
+                        msg => self.handle_message(msg).await?,
                     }
                 }
             }
 
             self.poll_async_tasks().await;
+            self.model.state.sync_instance_session();
+            self.sync_review_watcher();
+            self.start_diff_jobs();
+            self.model.state.telemetry.export_due();
 
             if self.model.should_quit {
                 break;
             }
         }
 
+        self.model.state.telemetry.flush();
         Ok(())
     }
 
     fn view(&mut self, frame: &mut Frame) {
         let area = frame.area();
 
+        if crate::ui::layout::too_small(area) {
+            crate::ui::renderers::render_too_small(frame, &self.model.theme);
+            return;
+        }
+
         if self.show_splash && self.splash_timer > 0 {
             self.splash_timer = self.splash_timer.saturating_sub(1);
             crate::ui::renderers::render_splash(frame, &self.model.theme);
@@ -121,22 +245,156 @@ impl App {
             }
             Mode::Confirmation => {
                 self.render_main_layout(frame, area);
-                let dialog_area = crate::ui::layout::centered_rect_percent(area, 60, 40);
-                self.confirmation.view(frame, dialog_area, &self.model);
+                let (rows, visible, width) = crate::ui::renderers::render_confirmation(
+                    frame,
+                    &self.model.state,
+                    &self.model.theme,
+                );
+                self.model
+                    .state
+                    .confirmation_scroll
+                    .fit(rows, visible, width);
             }
             Mode::Help => {
                 self.render_main_layout(frame, area);
                 let dialog_area = crate::ui::layout::centered_rect_percent(area, 80, 80);
                 self.help.view(frame, dialog_area, &self.model);
             }
+            Mode::Error => {
+                self.render_main_layout(frame, area);
+                let (rows, visible, width) =
+                    crate::ui::renderers::render_error(frame, &self.model.state, &self.model.theme);
+                self.model.state.error_scroll.fit(rows, visible, width);
+            }
+            Mode::Output => {
+                self.render_main_layout(frame, area);
+                if let Some(viewer) = &self.model.state.failed_output {
+                    let dialog_area = crate::ui::layout::centered_rect_percent(area, 90, 85);
+                    crate::ui::output_viewer::render_output_viewer(
+                        frame,
+                        dialog_area,
+                        viewer,
+                        &self.model.theme,
+                    );
+                }
+            }
+            Mode::History => {
+                self.render_main_layout(frame, area);
+                if let Some(history) = &self.model.state.history {
+                    let dialog_area = crate::ui::layout::centered_rect_percent(area, 90, 80);
+                    crate::ui::history::render_history(
+                        frame,
+                        dialog_area,
+                        history,
+                        &self.model.theme,
+                    );
+                }
+            }
+            Mode::MessageDetail => {
+                self.render_main_layout(frame, area);
+                let state = &self.model.state;
+                let message = state
+                    .message_detail
+                    .as_ref()
+                    .and_then(|d| Some((d, state.chat_history.get_message(d.message_id)?)));
+                if let Some((detail, message)) = message {
+                    let dialog_area = crate::ui::layout::centered_rect_percent(area, 90, 85);
+                    crate::ui::message_detail::render_message_detail(
+                        frame,
+                        dialog_area,
+                        detail,
+                        message,
+                        &self.model.theme,
+                    );
+                }
+            }
+            Mode::Sessions => {
+                self.render_main_layout(frame, area);
+                if let Some(switcher) = &self.model.state.session_switcher {
+                    let dialog_area = crate::ui::layout::centered_rect_percent(area, 70, 50);
+                    crate::ui::sessions::render_session_switcher(
+                        frame,
+                        dialog_area,
+                        switcher,
+                        &self.model.theme,
+                    );
+                }
+            }
+            Mode::Grep => {
+                self.render_main_layout(frame, area);
+                if let Some(grep) = &self.model.state.grep {
+                    let dialog_area = crate::ui::layout::centered_rect_percent(area, 80, 70);
+                    crate::ui::grep::render_grep(frame, dialog_area, grep, &self.model.theme);
+                }
+            }
+            Mode::TemplateForm => {
+                self.render_main_layout(frame, area);
+                if let Some(form) = &self.model.state.template_form {
+                    let dialog_area = crate::ui::layout::centered_rect_percent(area, 70, 50);
+                    crate::ui::template_form::render_template_form(
+                        frame,
+                        dialog_area,
+                        form,
+                        &self.model.theme,
+                    );
+                }
+            }
+            Mode::ConfigErrors => {
+                self.render_main_layout(frame, area);
+                let dialog_area = crate::ui::layout::centered_rect_percent(area, 80, 70);
+                crate::ui::config_errors::render_config_errors(
+                    frame,
+                    dialog_area,
+                    &self.model.state.config_problems,
+                    self.model.state.config_problem_selected,
+                    &crate::config::Config::config_path(),
+                    &self.model.theme,
+                );
+            }
+            Mode::Perf => {
+                self.render_main_layout(frame, area);
+                let dialog_area = crate::ui::layout::centered_rect_percent(area, 70, 70);
+                crate::ui::perf::render_perf(
+                    frame,
+                    dialog_area,
+                    &self.model.state.perf,
+                    &self.scheduler,
+                    &self.model.theme,
+                );
+            }
+            Mode::Usage => {
+                self.render_main_layout(frame, area);
+                let dialog_area = crate::ui::layout::centered_rect_percent(area, 90, 80);
+                crate::ui::usage::render_usage(
+                    frame,
+                    dialog_area,
+                    &self.model.state.sessions,
+                    &self.model.theme,
+                );
+            }
             _ => self.render_main_layout(frame, area),
         }
+
+        if let Some(reindent) = self
+            .model
+            .state
+            .reindent
+            .as_ref()
+            .filter(|_| self.model.state.mode == Mode::DiffReview)
+        {
+            crate::ui::reindent::render_reindent(frame, area, reindent, &self.model.theme);
+        }
+
+        if let Some(which_key) = &self.model.state.which_key {
+            crate::ui::which_key::render_which_key(frame, area, which_key, &self.model.theme);
+        }
     }
 
     fn render_main_layout(&mut self, frame: &mut Frame, area: Rect) {
+        let input_lines = self.model.state.prompt_buffer.lines().count() as u16;
         let layout = self
             .layout
-            .compute(area, self.model.state.sidebar_state.visible);
+            .compute(area, self.model.state.sidebar_state.visible, input_lines);
 
         match layout {
             AppLayout::Compact {
@@ -176,8 +434,16 @@ impl App {
                 sidebar,
             } => {
                 self.header.view(frame, header, &self.model);
-                self.chat.view(frame, chat, &self.model);
-                self.diff_view.view(frame, diff, &self.model);
+                // The focused pane is drawn with the focused border
+                let theme = &self.model.theme;
+                let border = |pane| match self.layout.focus() {
+                    Some(focused) if focused == pane => theme.focused_border(),
+                    _ => theme.border_style,
+                };
+                let (chat_border, diff_border) = (border(Pane::Chat), border(Pane::Diff));
+                self.chat
+                    .view_with_border(frame, chat, &self.model, chat_border);
+                self.render_diff(frame, diff, diff_border);
                 self.sidebar.view(frame, sidebar, &self.model);
                 self.render_input(frame, input);
                 self.status_bar.view(frame, status, &self.model);
@@ -187,12 +453,58 @@ impl App {
 
     fn render_content(&mut self, frame: &mut Frame, area: Rect) {
         match self.model.state.mode {
-            Mode::DiffReview => self.diff_view.view(frame, area, &self.model),
+            Mode::DiffReview => self.render_diff(frame, area, self.model.theme.border_style),
             Mode::Confirmation => {}
+            Mode::Processing => {
+                // Chat above, then the provider's steps so far and the command line
+                let state = &self.model.state;
+                let timeline = &state.activity;
+                let activity_height = if timeline.is_empty() {
+                    0
+                } else {
+                    crate::ui::activity::panel_height(timeline, ACTIVITY_STEPS).min(area.height / 2)
+                };
+                let command = state
+                    .last_command
+                    .as_ref()
+                    .filter(|_| state.config.general.command_preview);
+                let [chat_area, activity_area, command_area] = Layout::vertical([
+                    Constraint::Min(3),
+                    Constraint::Length(activity_height),
+                    Constraint::Length(command.is_some() as u16),
+                ])
+                .areas(area);
+                self.chat.view(frame, chat_area, &self.model);
+                if activity_height > 0 {
+                    crate::ui::activity::render_activity(
+                        frame,
+                        activity_area,
+                        timeline,
+                        &self.model.theme,
+                    );
+                }
+                if let Some(command) = command {
+                    crate::ui::activity::render_command_preview(
+                        frame,
+                        command_area,
+                        command,
+                        &self.model.theme,
+                    );
+                }
+            }
             _ => self.chat.view(frame, area, &self.model),
         }
     }
 
+    fn render_diff(&mut self, frame: &mut Frame, area: Rect, border: Style) {
+        // Scrolling needs the visible height; the block border takes two rows
+        let overlay = &mut self.model.state.overlay_diff_state;
+        overlay.viewport_height = area.height.saturating_sub(2) as usize;
+        overlay.viewport_width = area.width.saturating_sub(2) as usize;
+        self.diff_view
+            .view_with_border(frame, area, &self.model, border);
+    }
+
     fn render_input(&mut self, frame: &mut Frame, area: Rect) {
         match self.model.state.mode {
             Mode::CommandMode => self.command_palette.view(frame, area, &self.model),
@@ -209,12 +521,78 @@ impl App {
                 self.model.state.viewport_rows = h as usize;
                 Ok(None)
             }
-            AppEvent::Tick => Ok(None),
+            AppEvent::Tick => {
+                self.update_which_key(false);
+                self.layout.step();
+                Ok(None)
+            }
             AppEvent::PromptResult(res) => {
                 self.handle_command_result(res);
                 Ok(None)
             }
             AppEvent::ProviderDetected(_) => Ok(None),
+            AppEvent::ProviderLine(line) => {
+                if let Some(provider) = &self.model.state.provider {
+                    let now = std::time::Instant::now();
+                    for event in provider.parse_activity(&line) {
+                        self.model.state.activity.apply(event, now);
+                    }
+                }
+                Ok(None)
+            }
+            AppEvent::Piped(text) => {
+                self.receive_piped(text);
+                Ok(None)
+            }
+            AppEvent::ConfigChanged(_) => {
+                self.reload_config(true);
+                Ok(None)
+            }
+            AppEvent::FileChanged(path) => {
+                let disk_content = crate::file_cache::read_string(&path);
+                let state = &mut self.model.state;
+                if state.refresh_from_disk(&path, disk_content) {
+                    state.status_info.current_task = if state.is_stale(&path) {
+                        tf("status.changed_on_disk_stale", &[("path", &path.display())])
+                    } else {
+                        tf("status.changed_on_disk", &[("path", &path.display())])
+                    };
+                }
+                Ok(None)
+            }
+            AppEvent::SandboxProgress { copied, total } => {
+                let status = &mut self.model.state.status_info;
+                if status.is_working {
+                    status.current_task = if copied < total {
+                        tf(
+                            "status.sandbox_copying",
+                            &[("copied", &copied), ("total", &total)],
+                        )
+                    } else {
+                        t("status.processing").to_string()
+                    };
+                }
+                Ok(None)
+            }
+            AppEvent::DiffReady {
+                key,
+                hunks,
+                elapsed,
+            } => {
+                let state = &mut self.model.state;
+                if let Some(path) = state.diff_ready(key, hunks, elapsed) {
+                    let left = state.diffs_computing();
+                    state.status_info.current_task = if left == 0 {
+                        tf("status.diff_ready", &[("path", &path.display())])
+                    } else {
+                        tf(
+                            "status.diff_ready_more",
+                            &[("path", &path.display()), ("count", &left)],
+                        )
+                    };
+                }
+                Ok(None)
+            }
             AppEvent::Error(e) => {
                 eprintln!("event error: {e}");
                 Ok(None)
@@ -230,22 +608,120 @@ impl App {
             return Ok(None);
         }
 
+        // The which-key popup goes with any key; Esc also drops the sequence
+        let showing_which_key = self.model.state.which_key.take().is_some();
+        if showing_which_key && key.code == KeyCode::Esc {
+            self.key_parser.clear();
+            return Ok(None);
+        }
+
+        // Macros see review keys before the keymap, which has no `q` or `@`
+        if let Some(outcome) = self.handle_macro_key(key) {
+            return Ok(outcome);
+        }
+
+        // Error dialog is modal: j/k and PgUp/PgDn scroll the message, s selects
+        // rows of it and y copies, L logs in (when offered), V/C view or copy the
+        // failed run's output, anything else dismisses it
+        if self.model.state.mode == Mode::Error {
+            let state = &self.model.state;
+            let has_output = state.failed_output.is_some();
+            let selecting = state.error_scroll.selection.is_some();
+            return Ok(Some(match key.code {
+                KeyCode::Char('j') | KeyCode::Down => Message::ScrollDialog(Direction::Down),
+                KeyCode::Char('k') | KeyCode::Up => Message::ScrollDialog(Direction::Up),
+                KeyCode::PageDown => Message::PageDialog(Direction::Down),
+                KeyCode::PageUp => Message::PageDialog(Direction::Up),
+                KeyCode::Char('s') => Message::ToggleDialogSelection,
+                KeyCode::Esc if selecting => Message::ToggleDialogSelection,
+                KeyCode::Char('y') => Message::CopyError,
+                KeyCode::Char('l' | 'L') if state.login_retry.is_some() => Message::Login,
+                KeyCode::Char('v' | 'V') if has_output => Message::SetMode(Mode::Output),
+                KeyCode::Char('c' | 'C') if has_output => Message::CopyOutput,
+                _ => Message::DismissError,
+            }));
+        }
+
+        // While rows of the confirmation are selected, y copies them and Esc
+        // drops the selection instead of applying or leaving
+        if self.model.state.mode == Mode::Confirmation
+            && self.model.state.confirmation_scroll.selection.is_some()
+        {
+            match key.code {
+                KeyCode::Char('y') => return Ok(Some(Message::CopyConfirmation)),
+                KeyCode::Esc => return Ok(Some(Message::ToggleDialogSelection)),
+                _ => {}
+            }
+        }
+
+        if self.model.state.mode == Mode::Output {
+            return Ok(self.handle_output_key(key));
+        }
+
+        if self.model.state.mode == Mode::ProviderSelect {
+            return Ok(self.handle_provider_select_key(key));
+        }
+
+        if self.model.state.mode == Mode::History {
+            return Ok(self.handle_history_key(key));
+        }
+
+        if self.model.state.mode == Mode::MessageDetail {
+            return Ok(self.handle_message_detail_key(key));
+        }
+
+        if self.model.state.mode == Mode::Sessions {
+            return Ok(self.handle_session_switcher_key(key));
+        }
+
+        if self.model.state.mode == Mode::Grep {
+            return Ok(self.handle_grep_key(key));
+        }
+
+        if self.model.state.mode == Mode::ConfigErrors {
+            return Ok(self.handle_config_errors_key(key));
+        }
+
+        if self.model.state.mode == Mode::TemplateForm {
+            return Ok(self.handle_template_form_key(key));
+        }
+
+        // Usage dashboard and timings: any key closes them
+        if matches!(self.model.state.mode, Mode::Usage | Mode::Perf) {
+            return Ok(Some(Message::SetMode(Mode::PromptEntry)));
+        }
+
+        // A reindent preview waits for `=`; Esc drops it, other keys go on
+        if self.model.state.mode == Mode::DiffReview && self.model.state.reindent.is_some() {
+            let token = key_to_token(key);
+            if token != "=" {
+                self.model.state.reindent = None;
+            }
+            if key.code == KeyCode::Esc {
+                self.model.state.status_info.current_task =
+                    t("status.reindent_dropped").to_string();
+                return Ok(None);
+            }
+        }
+
         // Let keymap run first
         match self
             .key_parser
-            .process(key, &self.keymap, self.model.input_mode)
+            .process(key, &self.keymap, self.keymap_mode())
         {
-            KeyParseOutcome::Matched(msg) => return Ok(Some(msg)),
-            KeyParseOutcome::Pending => return Ok(None),
+            KeyParseOutcome::Matched(msg) => return Ok(Some(self.track_repeat(msg))),
+            KeyParseOutcome::Pending => {
+                // Once shown, the popup follows the sequence without waiting again
+                self.update_which_key(showing_which_key);
+                return Ok(None);
+            }
             KeyParseOutcome::NoMatch => {}
         }
 
         // Fallback to focused mode handlers
         match self.model.state.mode {
-            Mode::CommandMode => {
-                self.handle_command_buffer(key);
-                Ok(None)
-            }
+            Mode::CommandMode => Ok(self.handle_command_buffer(key)),
+            Mode::DiffReview | Mode::Confirmation => Ok(None),
             _ => {
                 self.handle_prompt_input(key)?;
                 Ok(None)
@@ -253,13 +729,206 @@ impl App {
         }
     }
 
+    /// Start, stop or play a review macro, and record keys while one is
+    /// recording. Returns `None` when the key goes on to the keymap.
+    fn handle_macro_key(&mut self, key: KeyEvent) -> Option<Option<Message>> {
+        let token = key_to_token(key);
+        let state = &mut self.model.state;
+        if state.mode == Mode::DiffReview && !state.macros.replaying {
+            if let Some(event) = state.macros.review_key(&token) {
+                state.status_info.recording_macro = state.macros.recording();
+                let task = match event {
+                    MacroEvent::Pending | MacroEvent::Cancelled => return Some(None),
+                    MacroEvent::Play(register) => {
+                        return Some(Some(Message::ReplayMacro(register)))
+                    }
+                    MacroEvent::Started(register) => {
+                        tf("status.macro_recording", &[("register", &register)])
+                    }
+                    MacroEvent::Stopped(register, count) => {
+                        state.save_macros();
+                        tf(
+                            "status.macro_recorded",
+                            &[("count", &count), ("register", &register)],
+                        )
+                    }
+                    MacroEvent::Empty(register) => {
+                        tf("status.macro_empty", &[("register", &register)])
+                    }
+                };
+                state.status_info.current_task = task;
+                return Some(None);
+            }
+        }
+        // A recording keeps the review's dialogs and commands, not what follows it
+        if matches!(
+            state.mode,
+            Mode::DiffReview | Mode::Confirmation | Mode::CommandMode
+        ) {
+            state.macros.record(&token);
+        }
+        None
+    }
+
+    /// Play the keys in `register` as if they were pressed again
+    async fn replay_macro(&mut self, register: char) -> Result<()> {
+        let Some(keys) = self.model.state.macros.registers.get(&register).cloned() else {
+            return Ok(());
+        };
+        self.model.state.macros.replaying = true;
+        let result = self.replay_keys(&keys).await;
+        self.model.state.macros.replaying = false;
+        result
+    }
+
+    async fn replay_keys(&mut self, tokens: &[String]) -> Result<()> {
+        for key in tokens.iter().filter_map(|token| token_to_key(token)) {
+            if !matches!(
+                self.model.state.mode,
+                Mode::DiffReview | Mode::Confirmation | Mode::CommandMode
+            ) {
+                break;
+            }
+            match self.handle_key(key)? {
+                // These need the terminal, which a replay doesn't hold
+                Some(Message::OpenEditor { .. } | Message::Login | Message::ApplyHunkAndEdit) => {}
+                Some(msg) => Box::pin(self.handle_message(msg)).await?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Show what can follow an unfinished key sequence once it has waited
+    /// `display.which_key_delay_ms` (or right away when `now`), holding the
+    /// sequence open while the popup is up
+    fn update_which_key(&mut self, now: bool) {
+        let display = &self.model.state.config.display;
+        let prefix = self.key_parser.pending();
+        if !display.which_key || prefix.is_empty() || self.model.state.which_key.is_some() {
+            return;
+        }
+        if !now && self.key_parser.idle() < Duration::from_millis(display.which_key_delay_ms) {
+            return;
+        }
+        let entries = self
+            .keymap
+            .continuations(self.keymap_mode(), prefix)
+            .into_iter()
+            .map(|(key, message)| {
+                let action = message.map_or_else(|| "…".to_string(), |m| describe(&m));
+                (key, action)
+            })
+            .collect();
+        self.model.state.which_key = Some(crate::state::WhichKey {
+            prefix: prefix.to_vec(),
+            entries,
+        });
+        self.key_parser.hold();
+    }
+
+    /// Keymap layer for the current screen; review dialogs have their own bindings
+    fn keymap_mode(&self) -> InputMode {
+        match self.model.state.mode {
+            Mode::DiffReview => InputMode::DiffReview,
+            Mode::Confirmation => InputMode::Confirmation,
+            _ => self.model.input_mode,
+        }
+    }
+
+    /// Remember an accept or reject (with its count) for `.`, and turn `.`
+    /// into the one remembered; a count given to `.` replaces its count
+    fn track_repeat(&mut self, msg: Message) -> Message {
+        let (count, msg) = match msg {
+            Message::Repeat(count, msg) => (Some(count), *msg),
+            msg => (None, msg),
+        };
+        let (count, msg) = match msg {
+            Message::RepeatLast => match &self.last_verdict {
+                Some((last, verdict)) => (count.unwrap_or(*last), verdict.clone()),
+                None => return Message::RepeatLast,
+            },
+            Message::AcceptHunk(_)
+            | Message::RejectHunk(_)
+            | Message::AcceptGroup
+            | Message::RejectGroup
+            | Message::AcceptAll
+            | Message::RejectAll => {
+                let count = count.unwrap_or(1);
+                self.last_verdict = Some((count, msg.clone()));
+                (count, msg)
+            }
+            msg => (count.unwrap_or(1), msg),
+        };
+        // The run loop does these once, with the terminal; a count can't repeat them
+        if count > 1 && msg.needs_terminal() {
+            self.model.state.status_info.current_task =
+                tf("status.count_not_repeated", &[("count", &count)]);
+            return self.resolve_selection(msg);
+        }
+        if count > 1 {
+            Message::Repeat(count, Box::new(msg))
+        } else {
+            self.resolve_selection(msg)
+        }
+    }
+
+    /// Point hunk messages from the keymap at the selected hunk
+    fn resolve_selection(&self, msg: Message) -> Message {
+        match msg {
+            Message::AcceptHunk(_) => Message::AcceptHunk(self.model.state.selected_hunk),
+            Message::RejectHunk(_) => Message::RejectHunk(self.model.state.selected_hunk),
+            other => other,
+        }
+    }
+
     async fn handle_message(&mut self, msg: Message) -> Result<()> {
+        // An applied review only stays open to show test results
+        if self.model.state.applied_review
+            && matches!(
+                msg,
+                Message::AcceptHunk(_)
+                    | Message::RejectHunk(_)
+                    | Message::AcceptAll
+                    | Message::RejectAll
+                    | Message::ApplyChanges
+                    | Message::ConfirmApply
+                    | Message::ToggleIgnoreOverride
+                    | Message::AcceptGroup
+                    | Message::RejectGroup
+                    | Message::ReindentHunk
+            )
+        {
+            self.model.state.status_info.current_task = t("status.already_applied").to_string();
+            return Ok(());
+        }
+
         match msg {
+            Message::Repeat(count, msg) => {
+                // Each time acts on the hunk the last one moved to
+                let mode = self.model.state.mode.clone();
+                for _ in 0..count {
+                    let msg = self.resolve_selection((*msg).clone());
+                    Box::pin(self.handle_message(msg)).await?;
+                    if self.model.state.mode != mode {
+                        break;
+                    }
+                }
+            }
+            Message::RepeatLast => {
+                self.model.state.status_info.current_task =
+                    t("status.nothing_to_repeat").to_string();
+            }
             Message::Navigate(dir) => self.navigate(dir),
             Message::ScrollTo(idx) => {
                 self.model.state.scroll_offset = idx;
             }
-            Message::SetMode(mode) => self.model.state.mode = mode,
+            Message::SetMode(mode) => {
+                if mode == Mode::PromptEntry && self.model.state.applied_review {
+                    self.model.state.clear_review();
+                }
+                self.model.state.mode = mode;
+            }
             Message::SetInputMode(mode) => self.model.input_mode = mode,
             Message::PushInputMode(mode) => self.model.mode_stack.push(mode),
             Message::PopInputMode => {
@@ -267,135 +936,1171 @@ impl App {
                     self.model.input_mode = mode;
                 }
             }
-            Message::SelectProvider(idx) => {
-                if idx < self.model.state.available_providers.len() {
-                    let provider_info = &self.model.state.available_providers[idx];
-                    let config = self
-                        .model
-                        .state
-                        .config
-                        .providers
-                        .get(&provider_info.config_key);
-                    self.model.state.provider =
-                        crate::providers::create_provider(&provider_info.name, config);
-                    self.model.state.mode = Mode::PromptEntry;
-                }
-            }
-            Message::DetectProviders => self.start_provider_detection(),
+            Message::SelectProvider(idx) => self.select_provider(idx),
+            Message::DetectProviders => self.start_provider_detection(true),
             Message::SubmitPrompt(text) => self.execute_prompt(text),
+            Message::RunBatch { pattern, prompt } => self.run_batch(pattern, prompt),
             Message::CancelPrompt => {
                 self.model.state.prompt_buffer.clear();
             }
-            Message::AcceptHunk(_) => {}
-            Message::RejectHunk(_) => {}
-            Message::AcceptAll => {}
-            Message::RejectAll => {}
-            Message::ApplyChanges => self.model.state.mode = Mode::Confirmation,
-            Message::ToggleSidebar => {
-                self.model.state.sidebar_state.visible = !self.model.state.sidebar_state.visible
-            }
-            Message::ToggleHelp => self.model.state.mode = Mode::Help,
-            Message::Search(_) => {}
-            Message::OpenEditor { .. } => {
-                // Handled in run() loop before calling handle_message
+            Message::AcceptHunk(idx) => {
+                self.set_hunk_or_folded_group(idx, HunkStatus::Accepted);
+                self.navigate(Direction::Down);
             }
-            Message::Quit => self.model.should_quit = true,
-            Message::Resize(w, h) => {
-                self.model.state.viewport_cols = w as usize;
-                self.model.state.viewport_rows = h as usize;
+            Message::RejectHunk(idx) => {
+                self.set_hunk_or_folded_group(idx, HunkStatus::Rejected);
+                self.navigate(Direction::Down);
             }
-            Message::Tick => {}
-        }
-        Ok(())
-    }
-
-    fn navigate(&mut self, dir: Direction) {
-        match dir {
-            Direction::Down => {
-                self.model.state.scroll_offset = self.model.state.scroll_offset.saturating_add(1);
+            Message::JumpToDefinition => {
+                self.model.state.status_info.current_task =
+                    match self.model.state.show_definition_for_hunk() {
+                        Some(symbol) => tf(
+                            "status.definition",
+                            &[
+                                ("kind", &symbol.kind),
+                                ("name", &symbol.name),
+                                ("path", &symbol.path.display()),
+                                ("line", &symbol.line),
+                            ],
+                        ),
+                        None => t("status.no_definition").to_string(),
+                    };
             }
-            Direction::Up => {
-                self.model.state.scroll_offset = self.model.state.scroll_offset.saturating_sub(1);
+            Message::CommentHunk => {
+                let state = &mut self.model.state;
+                if let Some(hunk) = state.hunks.get(state.selected_hunk) {
+                    // Prefilled so an existing comment can be edited
+                    state.command_buffer =
+                        format!("comment {}", hunk.comment.as_deref().unwrap_or(""));
+                    state.mode = Mode::CommandMode;
+                }
             }
-            Direction::Left | Direction::Right => {}
-        }
-    }
-
-    fn handle_command_buffer(&mut self, key: KeyEvent) {
-        use crate::input::command_mode::{execute_command, parse_command};
-        match key.code {
-            KeyCode::Enter => {
-                if let Ok(cmd) = parse_command(&self.model.state.command_buffer) {
-                    if let Err(e) = execute_command(&cmd, &mut self.model.state) {
-                        self.model.state.last_error = Some(crate::error::ErrorDisplay {
-                            title: "Command Error".into(),
-                            message: e.to_string(),
-                            help_url: None,
-                        });
+            Message::ReindentHunk => {
+                let state = &mut self.model.state;
+                let previewed = state
+                    .reindent
+                    .as_ref()
+                    .is_some_and(|r| r.hunk_id == state.selected_hunk);
+                state.status_info.current_task = if previewed {
+                    match state.accept_reindent() {
+                        Ok(path) => tf("status.reindent_accepted", &[("path", &path.display())]),
+                        Err(e) => format!("{:#}", e),
+                    }
+                } else {
+                    match state.preview_reindent() {
+                        Ok(reindent) => {
+                            tf("status.reindent_preview", &[("count", &reindent.changed())])
+                        }
+                        Err(e) => format!("{:#}", e),
                     }
+                };
+            }
+            Message::RenameTarget => {
+                let state = &mut self.model.state;
+                if let Some(hunk) = state.hunks.get(state.selected_hunk) {
+                    state.command_buffer = format!("rename {}", hunk.file_path.display());
+                    state.mode = Mode::CommandMode;
                 }
-                self.model.state.command_buffer.clear();
-                self.model.state.mode = Mode::PromptEntry;
             }
-            KeyCode::Esc => {
-                self.model.state.command_buffer.clear();
-                self.model.state.mode = Mode::PromptEntry;
+            Message::AdjustChatSplit(delta) => {
+                let ratio = self.layout.adjust_chat_ratio(delta);
+                self.model.state.status_info.current_task =
+                    match crate::ui::layout::save_chat_ratio(&saved_layout_path(), ratio) {
+                        Ok(()) => tf(
+                            "status.chat_split",
+                            &[("chat", &ratio), ("diff", &(100 - ratio))],
+                        ),
+                        Err(e) => tf(
+                            "status.chat_split_not_saved",
+                            &[("chat", &ratio), ("error", &e)],
+                        ),
+                    };
             }
-            KeyCode::Backspace => {
-                self.model.state.command_buffer.pop();
+            Message::FocusPane(focus) => {
+                self.layout.set_focus(focus);
+                self.model.state.status_info.current_task = focus_note(focus);
             }
-            KeyCode::Char(c) => {
-                self.model.state.command_buffer.push(c);
+            Message::CycleFocus => {
+                let focus = self.layout.cycle_focus();
+                self.model.state.status_info.current_task = focus_note(focus);
             }
-            _ => {}
-        }
-    }
-
-    fn handle_prompt_input(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Char(c) => {
-                self.model
-                    .state
-                    .prompt_buffer
-                    .insert(self.model.state.cursor_position, c);
-                self.model.state.cursor_position += 1;
+            Message::HunkMotion(action) => {
+                crate::input::modes::DiffReviewHandler::apply_action(
+                    &action,
+                    &mut self.model.state,
+                );
             }
-            KeyCode::Backspace => {
-                if self.model.state.cursor_position > 0 {
-                    self.model.state.cursor_position -= 1;
-                    self.model
-                        .state
-                        .prompt_buffer
-                        .remove(self.model.state.cursor_position);
+            Message::RequestFixes => match self.model.state.fix_request_prompt() {
+                Ok(prompt) => {
+                    self.model.state.refining_review = true;
+                    self.execute_prompt(prompt);
                 }
+                Err(e) => self.model.state.status_info.current_task = e.to_string(),
+            },
+            Message::AcceptGroup => {
+                let count = self.model.state.set_group_status(HunkStatus::Accepted);
+                self.model.state.status_info.current_task =
+                    tf("status.accepted_hunks", &[("count", &count)]);
+                self.navigate(Direction::Down);
             }
-            KeyCode::Left => {
-                if self.model.state.cursor_position > 0 {
-                    self.model.state.cursor_position -= 1;
+            Message::RejectGroup => {
+                let count = self.model.state.set_group_status(HunkStatus::Rejected);
+                self.model.state.status_info.current_task =
+                    tf("status.rejected_hunks", &[("count", &count)]);
+                self.navigate(Direction::Down);
+            }
+            Message::ToggleDiffWrap => {
+                let overlay = &mut self.model.state.overlay_diff_state;
+                overlay.wrap_lines = !overlay.wrap_lines;
+                overlay.keep_cursor_visible();
+                self.model.state.status_info.current_task = if overlay.wrap_lines {
+                    t("status.wrap_on").to_string()
+                } else {
+                    t("status.wrap_off").to_string()
+                };
+            }
+            Message::ToggleLineNumbers => {
+                let overlay = &mut self.model.state.overlay_diff_state;
+                overlay.line_numbers = !overlay.line_numbers;
+                overlay.keep_cursor_visible();
+                self.model.state.status_info.current_task = if overlay.line_numbers {
+                    t("status.line_numbers_on").to_string()
+                } else {
+                    t("status.line_numbers_off").to_string()
+                };
+            }
+            Message::ToggleSideBySide => {
+                let overlay = &mut self.model.state.overlay_diff_state;
+                overlay.side_by_side = !overlay.side_by_side;
+                self.model.state.status_info.current_task = if overlay.side_by_side {
+                    t("status.side_by_side").to_string()
+                } else {
+                    t("status.unified").to_string()
+                };
+            }
+            Message::ToggleDiffBase => {
+                let state = &mut self.model.state;
+                let rev = state.overlay_diff_state.base.is_none().then_some("HEAD");
+                state.status_info.current_task = match state.diff_against(rev) {
+                    Ok(note) => note,
+                    Err(e) => format!("{:#}", e),
+                };
+            }
+            Message::ReplayMacro(register) => self.replay_macro(register).await?,
+            Message::ToggleGroupCollapsed => {
+                if self.model.state.toggle_group_collapsed().is_none() {
+                    self.model.state.status_info.current_task = t("status.not_grouped").to_string();
                 }
             }
-            KeyCode::Right => {
-                if self.model.state.cursor_position < self.model.state.prompt_buffer.len() {
-                    self.model.state.cursor_position += 1;
+            Message::AcceptAll => self.model.state.set_all_hunks_status(HunkStatus::Accepted),
+            Message::RejectAll => self.model.state.set_all_hunks_status(HunkStatus::Rejected),
+            Message::ApplyChanges => {
+                let state = &mut self.model.state;
+                if let Err(e) = state.check_writable() {
+                    state.status_info.current_task = e.to_string();
+                } else {
+                    state.refresh_syntax_problems();
+                    if state.confirmation_reasons().is_empty()
+                        && state.config.checklist.items.is_empty()
+                        && state.syntax_problems.is_empty()
+                    {
+                        // Routine edits that match no `confirm_on` rule skip the dialog
+                        self.apply_review();
+                    } else {
+                        state.refresh_checklist();
+                        state.confirmation_scroll = DialogScroll::default();
+                        state.guard_confirmed = false;
+                        state.mode = Mode::Confirmation;
+                    }
                 }
             }
-            KeyCode::Enter => {
-                if !self.model.state.prompt_buffer.is_empty() {
-                    let text = std::mem::take(&mut self.model.state.prompt_buffer);
-                    self.model.state.cursor_position = 0;
-                    self.execute_prompt(text);
+            Message::ConfirmApply => {
+                // Hunks flagged by a guard rule take a second confirmation
+                let state = &mut self.model.state;
+                let unchecked = state.unchecked_required();
+                if unchecked > 0 {
+                    state.status_info.current_task =
+                        tf("status.checklist_unchecked", &[("count", &unchecked)]);
+                } else if !state.guard_confirmed && !state.flagged_accepted().is_empty() {
+                    state.guard_confirmed = true;
+                } else {
+                    self.apply_review();
                 }
             }
-            KeyCode::Esc => {
-                self.model.state.prompt_buffer.clear();
-                self.model.state.cursor_position = 0;
-                self.model.state.mode = Mode::ProviderSelect;
+            Message::ToggleSkipFile => {
+                let state = &mut self.model.state;
+                state.status_info.current_task = match state.toggle_skip_file() {
+                    Some((path, true)) => tf("status.file_skipped", &[("path", &path.display())]),
+                    Some((path, false)) => tf("status.file_included", &[("path", &path.display())]),
+                    None => t("status.no_file_to_skip").to_string(),
+                };
+                state.refresh_checklist();
+                state.refresh_syntax_problems();
             }
-            _ => {}
+            Message::ToggleIgnoreOverride => {
+                let state = &mut self.model.state;
+                state.status_info.current_task = match state.toggle_ignore_override() {
+                    Some((path, true)) => {
+                        tf("status.ignore_overridden", &[("path", &path.display())])
+                    }
+                    Some((path, false)) => tf("status.ignored_again", &[("path", &path.display())]),
+                    None => t("status.not_ignored").to_string(),
+                };
+            }
+            Message::ToggleSidebar => {
+                self.model.state.sidebar_state.visible = !self.model.state.sidebar_state.visible
+            }
+            Message::TogglePromptMode => {
+                self.model.state.set_prompt_mode(None);
+            }
+            Message::CyclePin(delta) => {
+                let state = &mut self.model.state;
+                state.status_info.current_task =
+                    match crate::ui::sidebar::cycle_pin(&mut state.sidebar_state, delta) {
+                        Some(path) => tf("status.sidebar_shows", &[("path", &path.display())]),
+                        None => t("status.no_pins").to_string(),
+                    };
+            }
+            Message::CycleSidebarView => {
+                let state = &mut self.model.state;
+                let view = crate::ui::sidebar::cycle_view(&mut state.sidebar_state);
+                state.status_info.current_task = if state.pinned_change().is_some() {
+                    tf("status.sidebar_view", &[("view", &view.label())])
+                } else {
+                    tf("status.sidebar_view_later", &[("view", &view.label())])
+                };
+            }
+            Message::ToggleHelp => self.model.state.mode = Mode::Help,
+            Message::ScrollChat(dir) => {
+                let history = &mut self.model.state.chat_history;
+                match dir {
+                    // Scrolling past the oldest loaded message reads the page before it
+                    Direction::Up
+                        if history.visible_index() == Some(0) && history.older_available =>
+                    {
+                        self.model.state.status_info.current_task =
+                            match self.model.state.load_older_messages() {
+                                Ok(count) => tf("status.older_loaded", &[("count", &count)]),
+                                Err(e) => {
+                                    tf("status.older_not_loaded", &[("error", &format!("{:#}", e))])
+                                }
+                            };
+                    }
+                    Direction::Up => history.scroll_up(),
+                    Direction::Down => history.scroll_down(),
+                    Direction::Left | Direction::Right => {}
+                }
+            }
+            Message::ScrollDiff(dir) => {
+                let overlay = &mut self.model.state.overlay_diff_state;
+                let page = overlay.viewport_height.max(2) as isize / 2;
+                match dir {
+                    Direction::Up => overlay.scroll_by(-page),
+                    Direction::Down => overlay.scroll_by(page),
+                    Direction::Left | Direction::Right => {}
+                }
+            }
+            Message::ScrollDialog(dir) => {
+                if let Some(scroll) = self.model.state.dialog_scroll_mut() {
+                    scroll.scroll_by(dir.delta());
+                }
+            }
+            Message::PageDialog(dir) => {
+                if let Some(scroll) = self.model.state.dialog_scroll_mut() {
+                    scroll.page_by(dir.delta());
+                }
+            }
+            Message::ToggleDialogSelection => {
+                if let Some(scroll) = self.model.state.dialog_scroll_mut() {
+                    scroll.toggle_selection();
+                }
+            }
+            Message::ToggleChecklistItem(index) => {
+                let state = &mut self.model.state;
+                if crate::checklist::toggle(&mut state.checklist, index).is_none() {
+                    state.status_info.current_task =
+                        tf("status.no_checklist_item", &[("number", &(index + 1))]);
+                }
+            }
+            Message::ToggleReplyExpanded => {
+                self.model.state.chat_history.toggle_latest_reply();
+            }
+            Message::RunSuggestion => {
+                let state = &mut self.model.state;
+                match state.command_suggestions.first() {
+                    None => {
+                        state.status_info.current_task = t("status.no_suggestion").to_string();
+                    }
+                    // The first press shows what would run, the second runs it
+                    Some(command) if !state.suggestion_armed => {
+                        state.status_info.current_task =
+                            tf("status.suggestion_armed", &[("command", &command)]);
+                        state.suggestion_armed = true;
+                    }
+                    Some(_) => self.run_suggestion(0),
+                }
+            }
+            Message::RunSuggested(number) => self.run_suggestion(number - 1),
+            Message::OpenSessionSwitcher => {
+                let state = &mut self.model.state;
+                if state.open_session_switcher() == 0 {
+                    state.status_info.current_task = t("status.no_sessions").to_string();
+                }
+            }
+            Message::SwitchSession(id) => {
+                let state = &mut self.model.state;
+                state.session_switcher = None;
+                state.status_info.current_task = match state.switch_session(&id) {
+                    Ok(note) => note,
+                    Err(e) => {
+                        state.mode = Mode::PromptEntry;
+                        format!("{:#}", e)
+                    }
+                };
+                self.sync_review_watcher();
+            }
+            Message::OpenGrepHit {
+                session_id,
+                message_id,
+            } => {
+                let state = &mut self.model.state;
+                state.grep = None;
+                if state.sessions.current_session_id.as_deref() != Some(&session_id) {
+                    if let Err(e) = state.switch_session(&session_id) {
+                        state.mode = Mode::PromptEntry;
+                        state.status_info.current_task = format!("{:#}", e);
+                        return Ok(());
+                    }
+                    self.sync_review_watcher();
+                }
+                let state = &mut self.model.state;
+                state.mode = Mode::PromptEntry;
+                state.status_info.current_task = match state.show_chat_message(message_id) {
+                    Ok(true) => t("status.grep_hit_shown").to_string(),
+                    Ok(false) => t("status.grep_hit_gone").to_string(),
+                    Err(e) => tf(
+                        "status.chat_history_error",
+                        &[("error", &format!("{:#}", e))],
+                    ),
+                };
+            }
+            Message::OpenMessageDetail => {
+                let state = &mut self.model.state;
+                let history = &state.chat_history;
+                match history.visible_index().map(|idx| &history.messages[idx]) {
+                    Some(message) => {
+                        state.message_detail = Some(MessageDetail::new(message));
+                        state.mode = Mode::MessageDetail;
+                    }
+                    None => state.status_info.current_task = t("status.no_messages").to_string(),
+                }
+            }
+            Message::OpenLink => {
+                let state = &mut self.model.state;
+                if let Some(url) = state
+                    .message_detail
+                    .as_ref()
+                    .and_then(|d| d.selected_link())
+                {
+                    let opener = state.config.general.url_opener.as_deref();
+                    state.status_info.current_task = match crate::links::open(url, opener) {
+                        Ok(()) => tf("status.link_opened", &[("url", &url)]),
+                        // Without a working opener the link is copied instead
+                        Err(e) => match crate::clipboard::copy(url) {
+                            Ok(_) => tf(
+                                "status.link_copied_instead",
+                                &[("error", &format!("{:#}", e)), ("url", &url)],
+                            ),
+                            Err(_) => format!("{:#}", e),
+                        },
+                    };
+                }
+            }
+            Message::DiffCodeBlock(path) => {
+                let state = &mut self.model.state;
+                let result = WritePolicy::discover()
+                    .and_then(|policy| state.diff_code_block(path.clone(), policy));
+                match result {
+                    Ok(()) => {
+                        state.status_info.current_task =
+                            tf("status.code_block_review", &[("path", &path.display())]);
+                    }
+                    Err(e) => {
+                        if let Some(detail) = state.message_detail.as_mut() {
+                            detail.diff_path = None;
+                        }
+                        state.status_info.current_task = format!("{:#}", e);
+                    }
+                }
+            }
+            Message::CopyLink => {
+                let state = &mut self.model.state;
+                if let Some(url) = state
+                    .message_detail
+                    .as_ref()
+                    .and_then(|d| d.selected_link())
+                {
+                    state.status_info.current_task = match crate::clipboard::copy(url) {
+                        Ok(_) => tf("status.link_copied", &[("url", &url)]),
+                        Err(e) => tf("status.copy_failed", &[("error", &format!("{:#}", e))]),
+                    };
+                }
+            }
+            Message::Search(_) => {}
+            Message::OpenEditor { .. } | Message::Login | Message::ApplyHunkAndEdit => {
+                // Handled in run() loop before calling handle_message
+            }
+            Message::CopyOutput => {
+                let state = &mut self.model.state;
+                if let Some(viewer) = &state.failed_output {
+                    state.status_info.current_task = match crate::clipboard::copy(&viewer.text()) {
+                        Ok(_) => tf("status.output_copied", &[("count", &viewer.lines.len())]),
+                        Err(e) => tf("status.copy_failed", &[("error", &e)]),
+                    };
+                }
+            }
+            Message::CopyError => {
+                let state = &mut self.model.state;
+                if let Some(error) = &state.last_error {
+                    let scroll = &mut state.error_scroll;
+                    let (text, lines) = match scroll.selected() {
+                        Some(range) => {
+                            let rows =
+                                crate::ui::renderers::error_rows(&error.message, scroll.width);
+                            let rows =
+                                &rows[range.start.min(rows.len())..range.end.min(rows.len())];
+                            (rows.join("\n"), rows.len())
+                        }
+                        None => (
+                            format!("{}\n{}", error.title, error.message),
+                            error.message.lines().count() + 1,
+                        ),
+                    };
+                    scroll.selection = None;
+                    state.status_info.current_task = match crate::clipboard::copy(&text) {
+                        Ok(_) => tf("status.error_copied", &[("count", &lines)]),
+                        Err(e) => tf("status.copy_failed", &[("error", &e)]),
+                    };
+                }
+            }
+            Message::CopyConfirmation => {
+                let state = &mut self.model.state;
+                let width = state.confirmation_scroll.width;
+                let (text, _) =
+                    crate::ui::renderers::confirmation_text(state, &self.model.theme, width);
+                let rows: Vec<String> = text
+                    .iter()
+                    .map(|line| {
+                        line.spans
+                            .iter()
+                            .map(|span| span.content.as_ref())
+                            .collect::<String>()
+                            .trim()
+                            .to_string()
+                    })
+                    .collect();
+                let scroll = &mut state.confirmation_scroll;
+                let rows = match scroll.selected() {
+                    Some(range) => &rows[range.start.min(rows.len())..range.end.min(rows.len())],
+                    None => &rows[..],
+                };
+                scroll.selection = None;
+                state.status_info.current_task = match crate::clipboard::copy(&rows.join("\n")) {
+                    Ok(_) => tf("status.confirmation_copied", &[("count", &rows.len())]),
+                    Err(e) => tf("status.copy_failed", &[("error", &e)]),
+                };
+            }
+            Message::RestoreHistoryEntry => {
+                let state = &mut self.model.state;
+                let journal = crate::journal::journal_path();
+                state.status_info.current_task = match state.restore_history_entry(&journal) {
+                    Ok(Some(result)) => tf("status.restored", &[("result", &result)]),
+                    Ok(None) => return Ok(()),
+                    Err(e) => tf("status.restore_failed", &[("error", &format!("{:#}", e))]),
+                };
+            }
+            Message::DismissError => {
+                self.model.state.last_error = None;
+                self.model.state.error_scroll = DialogScroll::default();
+                self.model.state.failed_output = None;
+                self.model.state.login_retry = None;
+                self.model.state.mode = Mode::PromptEntry;
+            }
+            Message::Quit => self.model.should_quit = true,
+            Message::Resize(w, h) => {
+                self.model.state.viewport_cols = w as usize;
+                self.model.state.viewport_rows = h as usize;
+            }
+            Message::Tick => {}
         }
         Ok(())
     }
 
+    fn navigate(&mut self, dir: Direction) {
+        if self.model.state.mode == Mode::DiffReview {
+            match dir {
+                Direction::Down => self.model.state.step_hunk(true),
+                Direction::Up => self.model.state.step_hunk(false),
+                Direction::Left | Direction::Right => {}
+            }
+            return;
+        }
+
+        match dir {
+            Direction::Down => {
+                self.model.state.scroll_offset = self.model.state.scroll_offset.saturating_add(1);
+            }
+            Direction::Up => {
+                self.model.state.scroll_offset = self.model.state.scroll_offset.saturating_sub(1);
+            }
+            Direction::Left | Direction::Right => {}
+        }
+    }
+
+    /// A folded group shows as one hunk, so y/n apply to all of it
+    fn set_hunk_or_folded_group(&mut self, idx: usize, status: HunkStatus) {
+        let state = &mut self.model.state;
+        if state.group_of(idx).is_some_and(|g| g.collapsed) {
+            state.set_group_status(status);
+        } else {
+            state.set_hunk_status(idx, status);
+        }
+    }
+
+    /// Keys for the provider list: j/k to move, Enter to use, Esc to keep the current one
+    fn handle_provider_select_key(&mut self, key: KeyEvent) -> Option<Message> {
+        let state = &mut self.model.state;
+        let last = state.available_providers.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                state.selected_provider_idx = (state.selected_provider_idx + 1).min(last);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                state.selected_provider_idx = state.selected_provider_idx.saturating_sub(1);
+            }
+            KeyCode::Char('g') | KeyCode::Home => state.selected_provider_idx = 0,
+            KeyCode::Char('G') | KeyCode::End => state.selected_provider_idx = last,
+            KeyCode::Char('r') => return Some(Message::DetectProviders),
+            KeyCode::Enter => return Some(Message::SelectProvider(state.selected_provider_idx)),
+            KeyCode::Esc if state.provider.is_some() => {
+                return Some(Message::SetMode(Mode::PromptEntry))
+            }
+            KeyCode::Char('q') => return Some(Message::Quit),
+            _ => {}
+        }
+        None
+    }
+
+    fn select_provider(&mut self, idx: usize) {
+        if self.model.state.select_provider(idx) {
+            self.auto_select_provider = false;
+        }
+    }
+
+    /// Skip the provider list at startup when the resumed session's provider (or the
+    /// configured default) is available
+    fn select_preferred_provider(&mut self) {
+        if !self.auto_select_provider {
+            return;
+        }
+        // Wait until the config problems were seen
+        if self.model.state.mode == Mode::ConfigErrors {
+            return;
+        }
+        if self.model.state.mode != Mode::ProviderSelect {
+            self.auto_select_provider = false;
+            return;
+        }
+
+        let cwd = std::env::current_dir().unwrap_or_default();
+        if let Some(idx) = self.model.state.preferred_provider(&cwd) {
+            self.select_provider(idx);
+            let name = &self.model.state.available_providers[idx].name;
+            self.model.state.status_info.current_task =
+                tf("status.using_provider", &[("provider", name)]);
+        } else if self.model.state.detection_state == DetectionState::Completed {
+            self.auto_select_provider = false;
+        }
+    }
+
+    /// Keys for the raw output viewer: scrolling, `/` search, copy and close
+    fn handle_output_key(&mut self, key: KeyEvent) -> Option<Message> {
+        let viewer = self.model.state.failed_output.as_mut()?;
+
+        if let Some(input) = viewer.input.as_mut() {
+            match key.code {
+                KeyCode::Enter => {
+                    let query = std::mem::take(input);
+                    viewer.input = None;
+                    viewer.search(&query);
+                }
+                KeyCode::Esc => viewer.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return None;
+        }
+
+        let page = self.model.state.viewport_rows.max(4) as isize / 2;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => viewer.scroll_by(1),
+            KeyCode::Char('k') | KeyCode::Up => viewer.scroll_by(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => viewer.scroll_by(page),
+            KeyCode::PageUp => viewer.scroll_by(-page),
+            KeyCode::Char('g') | KeyCode::Home => viewer.scroll = 0,
+            KeyCode::Char('G') | KeyCode::End => viewer.scroll_by(isize::MAX),
+            KeyCode::Char('/') => viewer.input = Some(String::new()),
+            KeyCode::Char('n') => viewer.next_match(true),
+            KeyCode::Char('N') => viewer.next_match(false),
+            KeyCode::Char('c') => return Some(Message::CopyOutput),
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Message::SetMode(Mode::Error)),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_history_key(&mut self, key: KeyEvent) -> Option<Message> {
+        let history = self.model.state.history.as_mut()?;
+        let page = self.model.state.viewport_rows.max(4) as isize / 2;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => history.select_by(1),
+            KeyCode::Char('k') | KeyCode::Up => history.select_by(-1),
+            KeyCode::PageDown => history.select_by(page),
+            KeyCode::PageUp => history.select_by(-page),
+            KeyCode::Char('g') | KeyCode::Home => history.selected = 0,
+            KeyCode::Char('G') | KeyCode::End => history.select_by(isize::MAX),
+            KeyCode::Char('r') | KeyCode::Enter => return Some(Message::RestoreHistoryEntry),
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.model.state.history = None;
+                return Some(Message::SetMode(Mode::PromptEntry));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_session_switcher_key(&mut self, key: KeyEvent) -> Option<Message> {
+        let switcher = self.model.state.session_switcher.as_mut()?;
+        match key.code {
+            KeyCode::Tab | KeyCode::Char('j') | KeyCode::Down => switcher.select_by(1),
+            KeyCode::BackTab | KeyCode::Char('k') | KeyCode::Up => switcher.select_by(-1),
+            KeyCode::Char(c @ '1'..='9') => {
+                let idx = c as usize - '1' as usize;
+                let entry = switcher.entries.get(idx)?;
+                return Some(Message::SwitchSession(entry.id.clone()));
+            }
+            KeyCode::Enter => {
+                let entry = switcher.selected_entry()?;
+                return Some(Message::SwitchSession(entry.id.clone()));
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.model.state.session_switcher = None;
+                return Some(Message::SetMode(Mode::PromptEntry));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_grep_key(&mut self, key: KeyEvent) -> Option<Message> {
+        let grep = self.model.state.grep.as_mut()?;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => grep.select_by(1),
+            KeyCode::Char('k') | KeyCode::Up => grep.select_by(-1),
+            KeyCode::Enter => {
+                let hit = grep.selected_hit()?;
+                return Some(Message::OpenGrepHit {
+                    session_id: hit.session_id.clone(),
+                    message_id: hit.message_id,
+                });
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.model.state.grep = None;
+                return Some(Message::SetMode(Mode::PromptEntry));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_template_form_key(&mut self, key: KeyEvent) -> Option<Message> {
+        let form = self.model.state.template_form.as_mut()?;
+        match key.code {
+            KeyCode::Tab | KeyCode::Down => form.select_by(1),
+            KeyCode::BackTab | KeyCode::Up => form.select_by(-1),
+            KeyCode::Right => form.cycle_choice(true),
+            KeyCode::Left => form.cycle_choice(false),
+            KeyCode::Backspace => {
+                if let Some(field) = form.selected_field() {
+                    field.value.pop();
+                    field.error = None;
+                }
+            }
+            KeyCode::Char(c) => {
+                if let Some(field) = form.selected_field() {
+                    field.value.push(c);
+                    field.error = None;
+                }
+            }
+            KeyCode::Enter => {
+                let prompt = form.submit()?;
+                self.model.state.template_form = None;
+                self.model.state.mode = Mode::PromptEntry;
+                return Some(Message::SubmitPrompt(prompt));
+            }
+            KeyCode::Esc => {
+                self.model.state.template_form = None;
+                return Some(Message::SetMode(Mode::PromptEntry));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_config_errors_key(&mut self, key: KeyEvent) -> Option<Message> {
+        let state = &mut self.model.state;
+        let last = state.config_problems.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                state.config_problem_selected = (state.config_problem_selected + 1).min(last);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                state.config_problem_selected = state.config_problem_selected.saturating_sub(1);
+            }
+            KeyCode::Enter | KeyCode::Char('e') => {
+                return Some(Message::OpenEditor {
+                    path: state.config_problem_path(),
+                    line: state.config_problem_line(),
+                });
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                let next = if state.provider.is_some() {
+                    Mode::PromptEntry
+                } else {
+                    Mode::ProviderSelect
+                };
+                return Some(Message::SetMode(next));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_message_detail_key(&mut self, key: KeyEvent) -> Option<Message> {
+        let detail = self.model.state.message_detail.as_mut()?;
+        let page = self.model.state.viewport_rows.max(4) / 2;
+
+        // Typing the file to diff the selected code block against
+        if let Some(path) = detail.diff_path.as_mut() {
+            match key.code {
+                KeyCode::Enter if !path.trim().is_empty() => {
+                    return Some(Message::DiffCodeBlock(PathBuf::from(path.trim())));
+                }
+                KeyCode::Tab => *path = crate::state::complete_path(path),
+                KeyCode::Backspace => {
+                    path.pop();
+                }
+                KeyCode::Char(c) => path.push(c),
+                KeyCode::Esc => detail.diff_path = None,
+                _ => {}
+            }
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Tab => detail.cycle_link(true),
+            KeyCode::BackTab => detail.cycle_link(false),
+            KeyCode::Char('b') => detail.cycle_block(true),
+            KeyCode::Char('B') => detail.cycle_block(false),
+            KeyCode::Char('d') if !detail.start_diff(&self.model.state.renamed_files) => {
+                self.model.state.status_info.current_task = t("status.no_code_blocks").to_string();
+            }
+            KeyCode::Enter => return Some(Message::OpenLink),
+            KeyCode::Char('c') => return Some(Message::CopyLink),
+            KeyCode::Char('j') | KeyCode::Down => detail.scroll += 1,
+            KeyCode::Char('k') | KeyCode::Up => detail.scroll = detail.scroll.saturating_sub(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => detail.scroll += page,
+            KeyCode::PageUp => detail.scroll = detail.scroll.saturating_sub(page),
+            KeyCode::Char('g') | KeyCode::Home => detail.scroll = 0,
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.model.state.message_detail = None;
+                return Some(Message::SetMode(Mode::PromptEntry));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Run the `idx`th suggested command in the background, taking it off the list
+    fn run_suggestion(&mut self, idx: usize) {
+        let state = &mut self.model.state;
+        state.suggestion_armed = false;
+        if idx >= state.command_suggestions.len() {
+            return;
+        }
+        let command = state.command_suggestions.remove(idx);
+        state.status_info.current_task = tf("status.running_command", &[("command", &command)]);
+        let task =
+            tokio::spawn(async move { execute_shell_command(&command, "suggestion_run").await });
+        self.pending_tasks
+            .insert("suggestion_run".to_string(), task);
+    }
+
+    fn handle_command_buffer(&mut self, key: KeyEvent) -> Option<Message> {
+        use crate::input::command_mode::{
+            execute_command, parse_command, Command, ConfigSubcommand, PreambleSubcommand,
+        };
+        let mut follow_up = None;
+        match key.code {
+            KeyCode::Enter => {
+                if let Ok(cmd) = parse_command(&self.model.state.command_buffer) {
+                    match execute_command(&cmd, &mut self.model.state) {
+                        Ok(_) if cmd == Command::Config(ConfigSubcommand::Edit) => {
+                            follow_up = Some(Message::OpenEditor {
+                                path: self.model.state.config_problem_path(),
+                                line: self.model.state.config_problem_line(),
+                            });
+                        }
+                        Ok(_) if cmd == Command::Preamble(PreambleSubcommand::Edit) => {
+                            let cwd = std::env::current_dir().unwrap_or_default();
+                            follow_up = Some(Message::OpenEditor {
+                                path: crate::preamble::project_path(&cwd),
+                                line: None,
+                            });
+                        }
+                        Ok(_) => match cmd {
+                            Command::Foreach { pattern, prompt } => {
+                                follow_up = Some(Message::RunBatch { pattern, prompt });
+                            }
+                            Command::Run(number) => {
+                                follow_up = Some(Message::RunSuggested(number));
+                            }
+                            Command::ApplyBranch(_) => follow_up = Some(Message::ApplyChanges),
+                            Command::Provider(provider) if provider == "refresh" => {
+                                follow_up = Some(Message::DetectProviders);
+                            }
+                            _ => {}
+                        },
+                        Err(e) => {
+                            self.model.state.last_error = Some(crate::error::ErrorDisplay {
+                                title: "Command Error".into(),
+                                message: e.to_string(),
+                                help_url: None,
+                            });
+                        }
+                    }
+                }
+                self.model.state.command_buffer.clear();
+                // Commands such as :git-review switch modes themselves
+                if self.model.state.mode == Mode::CommandMode {
+                    self.model.state.mode = Mode::PromptEntry;
+                }
+            }
+            KeyCode::Esc => {
+                self.model.state.command_buffer.clear();
+                self.model.state.mode = Mode::PromptEntry;
+            }
+            KeyCode::Backspace => {
+                self.model.state.command_buffer.pop();
+            }
+            KeyCode::Char(c) => {
+                self.model.state.command_buffer.push(c);
+            }
+            _ => {}
+        }
+        follow_up
+    }
+
+    fn handle_prompt_input(&mut self, key: KeyEvent) -> Result<()> {
+        let state = &mut self.model.state;
+        // `#symbol` completion takes Tab/Up/Down/Esc while suggestions are showing
+        if !state.symbol_suggestions.is_empty() {
+            let count = state.symbol_suggestions.len();
+            match key.code {
+                KeyCode::Tab => {
+                    state.accept_symbol_suggestion();
+                    return Ok(());
+                }
+                KeyCode::Down => {
+                    state.symbol_selected = (state.symbol_selected + 1) % count;
+                    return Ok(());
+                }
+                KeyCode::Up => {
+                    state.symbol_selected = (state.symbol_selected + count - 1) % count;
+                    return Ok(());
+                }
+                KeyCode::Esc => {
+                    state.symbol_suggestions.clear();
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        match key.code {
+            KeyCode::Char(c) => {
+                editing::insert(&mut state.prompt_buffer, &mut state.cursor_position, c);
+                state.refresh_symbol_suggestions();
+            }
+            KeyCode::Backspace => {
+                editing::backspace(&mut state.prompt_buffer, &mut state.cursor_position);
+                state.refresh_symbol_suggestions();
+            }
+            KeyCode::Delete => {
+                editing::delete(&mut state.prompt_buffer, &mut state.cursor_position);
+                state.refresh_symbol_suggestions();
+            }
+            KeyCode::Left => {
+                state.cursor_position =
+                    editing::prev_boundary(&state.prompt_buffer, state.cursor_position);
+                state.refresh_symbol_suggestions();
+            }
+            KeyCode::Right => {
+                state.cursor_position =
+                    editing::next_boundary(&state.prompt_buffer, state.cursor_position);
+                state.refresh_symbol_suggestions();
+            }
+            KeyCode::Enter if !self.model.state.prompt_buffer.is_empty() => {
+                let text = std::mem::take(&mut self.model.state.prompt_buffer);
+                self.model.state.cursor_position = 0;
+                self.model.state.symbol_suggestions.clear();
+                self.execute_prompt(text);
+            }
+            KeyCode::Esc => {
+                self.model.state.prompt_buffer.clear();
+                self.model.state.cursor_position = 0;
+                self.model.state.mode = Mode::ProviderSelect;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Write the reviewed hunks: to disk for provider changes, or back into git
+    /// for a `:git-review`
+    fn apply_review(&mut self) {
+        let state = &mut self.model.state;
+        if let Err(e) = state.check_writable() {
+            state.status_info.current_task = e.to_string();
+            state.mode = Mode::DiffReview;
+            return;
+        }
+        let computing = state.diffs_computing();
+        if computing > 0 {
+            state.status_info.current_task = tf("status.diffs_computing", &[("count", &computing)]);
+            state.mode = Mode::DiffReview;
+            return;
+        }
+        if let Some(base) = &state.overlay_diff_state.base {
+            state.status_info.current_task = tf("status.diff_base_shown", &[("rev", &base.rev)]);
+            state.mode = Mode::DiffReview;
+            return;
+        }
+        if let Some((path, alternatives)) = state.edit_conflicts.iter().next() {
+            state.status_info.current_task = tf(
+                "status.edit_conflict",
+                &[("path", &path.display()), ("count", &alternatives.len())],
+            );
+            state.mode = Mode::DiffReview;
+            return;
+        }
+        let accepted = state.accepted_hunks().count();
+        let reviewed = state
+            .hunks
+            .iter()
+            .filter(|h| h.status != HunkStatus::Pending)
+            .filter(|h| !state.skipped_files.contains(&h.file_path))
+            .count();
+
+        // `:apply-branch` or `apply_to_branch`: the files are written on a new branch
+        let template = state.apply_branch.take().or_else(|| {
+            let general = &state.config.general;
+            general.apply_to_branch.then(|| general.branch_name.clone())
+        });
+        let mut branch = None;
+        if let Some(template) = template.filter(|_| state.git_review.is_none()) {
+            let name = crate::git::branch_name(
+                &template,
+                state.last_prompt.as_deref(),
+                state.sessions.current_session_id.as_deref(),
+            );
+            let cwd = std::env::current_dir().unwrap_or_default();
+            match crate::git::create_apply_branch(&cwd, &name) {
+                Ok(created) => branch = Some(created),
+                Err(e) => {
+                    state.last_error = Some(crate::error::ErrorDisplay {
+                        title: "Failed to Create Branch".to_string(),
+                        message: format!("{:#}", e),
+                        help_url: None,
+                    });
+                    state.mode = Mode::Error;
+                    return;
+                }
+            }
+        }
+
+        let mut journal_error = None;
+        let outcome = match &state.git_review {
+            Some(review) => crate::git::apply_review(
+                review,
+                &state
+                    .hunks
+                    .iter()
+                    .filter(|h| !state.skipped_files.contains(&h.file_path))
+                    .cloned()
+                    .collect::<Vec<_>>(),
+                &state.pending_changes,
+                &state.config,
+                &state.write_policy,
+            )
+            .map(|r| {
+                tf(
+                    "status.git_review_applied",
+                    &[
+                        ("staged", &r.hunks_staged),
+                        ("reverted", &r.hunks_reverted),
+                        ("unstaged", &r.hunks_unstaged),
+                    ],
+                )
+            }),
+            None => {
+                let accepted_hunks: Vec<_> = state.accepted_hunks().collect();
+                let entry = crate::journal::JournalEntry::new(
+                    state.sessions.current_session_id.clone(),
+                    state.provider.as_ref().map(|p| p.name().to_string()),
+                    state.last_prompt.as_deref(),
+                    &accepted_hunks,
+                );
+                let outcome = state.apply_changes();
+                let entry = match &outcome {
+                    Ok(r) => entry.succeeded(r.summary(), &r.backups_created),
+                    Err(e) => entry.failed(e),
+                };
+                journal_error =
+                    crate::journal::append(&crate::journal::journal_path(), &entry).err();
+                outcome
+            }
+            .map(|r| {
+                let summary = r.summary();
+                state
+                    .sessions
+                    .record_review(r.files_modified.len(), accepted, reviewed);
+                state.follow_renames(&r.files_renamed);
+                state.last_apply_result = Some(r);
+                summary
+            }),
+        };
+
+        // The apply stands even if it could not be journaled
+        let outcome = outcome.map(|summary| match journal_error {
+            Some(e) => format!("{} (not journaled: {:#})", summary, e),
+            None => summary,
+        });
+
+        let outcome = match (outcome, branch) {
+            (Ok(summary), Some(branch)) => {
+                let files: Vec<_> = state
+                    .last_apply_result
+                    .iter()
+                    .flat_map(|r| {
+                        let renamed_from = r.files_renamed.iter().map(|(old, _)| old);
+                        r.files_modified
+                            .iter()
+                            .chain(&r.files_deleted)
+                            .chain(renamed_from)
+                    })
+                    .cloned()
+                    .collect();
+                let message = state
+                    .last_prompt
+                    .as_deref()
+                    .and_then(|p| p.lines().next())
+                    .map(|line| line.trim().chars().take(72).collect::<String>())
+                    .filter(|line| !line.is_empty())
+                    .unwrap_or_else(|| "Apply reviewed changes".to_string());
+                let message = match &state.overlay_diff_state.provenance {
+                    Some(provenance) => format!("{}\n\n{}", message, provenance.trailers()),
+                    None => message,
+                };
+                Ok(match branch.commit(&files, &message) {
+                    Ok(()) => format!("{}; {}", summary, branch.hint()),
+                    Err(e) => format!(
+                        "{}; left uncommitted on branch {}: {:#}",
+                        summary, branch.name, e
+                    ),
+                })
+            }
+            (Err(e), Some(branch)) => {
+                // Nothing landed on the branch, so go back to where we were
+                let _ = branch.abandon();
+                Err(e)
+            }
+            (outcome, None) => outcome,
+        };
+
+        state.telemetry.record_apply(outcome.is_ok());
+        if outcome.is_ok() && state.git_review.is_none() {
+            state.record_apply_summary();
+        }
+
+        // Per-file mode: the written files stay, the failed ones are listed
+        let partial_failure = state
+            .last_apply_result
+            .as_ref()
+            .filter(|r| state.git_review.is_none() && !r.files_failed.is_empty())
+            .map(|r| r.report());
+        if let (Ok(summary), Some(report)) = (&outcome, partial_failure) {
+            state.telemetry.record_error("apply_partial");
+            state.clear_review();
+            state.status_info.current_task = summary.clone();
+            state.last_error = Some(crate::error::ErrorDisplay {
+                title: "Some Files Were Not Applied".to_string(),
+                message: format!("{}\n\n{}", summary, report),
+                help_url: None,
+            });
+            state.mode = Mode::Error;
+            self.persist_sessions();
+            return;
+        }
+
+        // With a test command, the applied review stays open to show failures
+        let test_command = state
+            .config
+            .general
+            .test_command
+            .clone()
+            .filter(|_| state.git_review.is_none() && accepted > 0);
+
+        match outcome {
+            Ok(summary) => {
+                match test_command {
+                    Some(command) => {
+                        state.applied_review = true;
+                        state.mode = Mode::DiffReview;
+                        state.status_info.current_task = tf(
+                            "status.applied_running_tests",
+                            &[("summary", &summary), ("command", &command)],
+                        );
+                        let task = tokio::spawn(self.scheduler.run(TaskKind::Hook, async move {
+                            execute_test_command(&command).await
+                        }));
+                        self.pending_tasks.insert("test_run".to_string(), task);
+                    }
+                    None => {
+                        state.clear_review();
+                        state.status_info.current_task = summary;
+                        state.mode = Mode::PromptEntry;
+                    }
+                }
+                self.persist_sessions();
+            }
+            Err(e) => apply_error(state, &e),
+        }
+    }
+
     /// Open a file in external editor, suspending the TUI
     pub async fn open_file_in_editor(
         &mut self,
@@ -405,6 +2110,19 @@ impl App {
     ) -> Result<()> {
         crate::ui::editor::open_file_in_editor(terminal, &path, line)?;
 
+        if crate::config::Config::source_paths().contains(&path) {
+            self.reload_config(false);
+        }
+
+        let cwd = std::env::current_dir().unwrap_or_default();
+        if path == crate::preamble::project_path(&cwd) {
+            self.model.state.reload_preamble();
+            self.model.state.status_info.current_task = match &self.model.state.preamble {
+                Some(preamble) => tf("status.using_preamble", &[("preamble", &preamble.label())]),
+                None => t("status.no_preamble").to_string(),
+            };
+        }
+
         // Reload file changes if it's a pending change
         if self.model.state.pending_changes.contains_key(&path) {
             if let Ok(new_content) = std::fs::read_to_string(&path) {
@@ -414,24 +2132,130 @@ impl App {
                 }
             }
         }
-
-        Ok(())
+
+        Ok(())
+    }
+
+    /// Apply the selected hunk, then open its file at the hunk in the editor to
+    /// fine-tune it. The rest of the review stays open and follows the edit.
+    fn apply_hunk_and_edit(
+        &mut self,
+        terminal: &mut ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>,
+    ) {
+        let state = &mut self.model.state;
+        let Some(hunk) = state.hunks.get(state.selected_hunk).cloned() else {
+            return;
+        };
+        let entry = crate::journal::JournalEntry::new(
+            state.sessions.current_session_id.clone(),
+            state.provider.as_ref().map(|p| p.name().to_string()),
+            state.last_prompt.as_deref(),
+            &[&hunk],
+        );
+        let (path, line) = match state.apply_hunk(hunk.id) {
+            Ok((path, line, result)) => {
+                let entry = entry.succeeded(result.summary(), &result.backups_created);
+                if let Err(e) = crate::journal::append(&crate::journal::journal_path(), &entry) {
+                    state.status_info.current_task = tf(
+                        "status.hunk_not_journaled",
+                        &[("error", &format!("{:#}", e))],
+                    );
+                }
+                state.sessions.record_review(1, 1, 1);
+                (path, line)
+            }
+            Err(e) if e.is::<crate::pre_apply::Veto>() => {
+                apply_error(state, &e);
+                return;
+            }
+            Err(e) => {
+                state.status_info.current_task = format!("{:#}", e);
+                return;
+            }
+        };
+
+        let before = std::fs::read_to_string(&path).unwrap_or_default();
+        if let Err(e) = crate::ui::editor::open_file_in_editor(terminal, &path, Some(line)) {
+            state.status_info.current_task = tf(
+                "status.hunk_applied_no_editor",
+                &[("error", &format!("{:#}", e))],
+            );
+            return;
+        }
+        let after = std::fs::read_to_string(&path).unwrap_or_default();
+        state.status_info.current_task = if after == before {
+            tf("status.hunk_applied", &[("path", &path.display())])
+        } else if state.rebase_on_edit(&path, &before, &after) {
+            tf("status.hunk_applied_rebased", &[("path", &path.display())])
+        } else {
+            tf("status.hunk_applied_stale", &[("path", &path.display())])
+        };
+
+        // Nothing left to review once every hunk is written
+        if state.hunks.is_empty() {
+            state.clear_review();
+            state.mode = Mode::PromptEntry;
+        }
+        self.persist_sessions();
+    }
+
+    /// Run the pending provider login in the foreground, then retry the prompt
+    /// that failed with an auth error
+    fn run_provider_login(
+        &mut self,
+        terminal: &mut ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>,
+    ) {
+        let Some(retry) = self.model.state.login_retry.take() else {
+            return;
+        };
+        let Some((program, args)) = retry.command.split_first() else {
+            return;
+        };
+
+        match crate::ui::editor::run_interactive_command(terminal, program, args) {
+            Ok(true) => {
+                self.model.state.last_error = None;
+                self.execute_prompt(retry.prompt);
+            }
+            Ok(false) => {
+                self.model.state.last_error = Some(crate::error::ErrorDisplay {
+                    title: "Login Failed".to_string(),
+                    message: format!(
+                        "`{}` did not complete successfully",
+                        retry.command.join(" ")
+                    ),
+                    help_url: None,
+                });
+                self.model.state.login_retry = Some(retry);
+                self.model.state.mode = Mode::Error;
+            }
+            Err(e) => {
+                self.model.state.last_error = Some(crate::error::ErrorDisplay {
+                    title: "Login Failed".to_string(),
+                    message: e.to_string(),
+                    help_url: None,
+                });
+                self.model.state.mode = Mode::Error;
+            }
+        }
     }
 
-    pub fn start_provider_detection(&mut self) {
+    /// Find the installed provider CLIs. Unless `force`d, a detection from an
+    /// earlier run within `detection_cache_hours` is used straight away and
+    /// checked again in the background.
+    pub fn start_provider_detection(&mut self, force: bool) {
+        let ttl_hours = self.model.state.config.general.detection_cache_hours;
+        let now = chrono::Utc::now();
         self.model.state.available_providers.clear();
         self.model.state.pending_detections.clear();
         self.model.state.detection_state = DetectionState::InProgress;
 
-        let providers_to_check = vec![
-            ("claude", "Claude Code", "claude", "claude"),
-            ("aider", "Aider", "aider", "aider"),
-            ("copilot", "GitHub Copilot CLI", "copilot", "copilot"),
-            ("kiro", "Kiro CLI", "kiro", "q"),
-        ];
-
-        for (provider_id, display_name, default_cmd, config_key) in providers_to_check {
-            if let Some(provider_config) = self.model.state.config.providers.get(config_key) {
+        for (provider_id, display_name, default_cmd, config_key) in BUILTIN_PROVIDERS {
+            let provider_config = self.model.state.config.providers.get(config_key);
+            let args = crate::providers::create_provider(provider_id, provider_config)
+                .map(|p| p.detection_args())
+                .unwrap_or_default();
+            if let Some(provider_config) = provider_config {
                 if !provider_config.enabled {
                     continue;
                 }
@@ -447,13 +2271,35 @@ impl App {
                 }
             }
 
-            let task = tokio::spawn(execute_provider_detection(
-                default_cmd,
-                provider_id,
-                display_name,
-                config_key,
+            let task = tokio::spawn(self.scheduler.run(
+                TaskKind::Detection,
+                execute_provider_detection(
+                    default_cmd,
+                    args,
+                    provider_id,
+                    display_name,
+                    config_key,
+                ),
             ));
 
+            let cached = self
+                .detection_cache
+                .fresh(provider_id, ttl_hours, now)
+                .filter(|_| !force);
+            if let Some(detection) = cached {
+                if detection.available {
+                    self.model.state.available_providers.push(ProviderInfo {
+                        name: display_name.to_string(),
+                        available: true,
+                        cli_command: default_cmd.to_string(),
+                        config_key: config_key.to_string(),
+                    });
+                }
+                self.pending_tasks
+                    .insert(format!("revalidate_{}", provider_id), task);
+                continue;
+            }
+
             self.pending_tasks
                 .insert(format!("detect_{}", provider_id), task);
             self.model
@@ -463,7 +2309,7 @@ impl App {
         }
 
         for (key, provider_config) in &self.model.state.config.providers {
-            if matches!(key.as_str(), "claude" | "aider" | "copilot" | "q" | "kiro") {
+            if crate::providers::BUILTIN_CONFIG_KEYS.contains(&key.as_str()) {
                 continue;
             }
 
@@ -482,9 +2328,34 @@ impl App {
         if self.model.state.pending_detections.is_empty() {
             self.model.state.detection_state = DetectionState::Completed;
         }
+        self.select_preferred_provider();
     }
 
     pub fn execute_prompt(&mut self, prompt: String) {
+        // A sandboxed provider edits a copy; its edits come back for review.
+        // The copy is made by the prompt's task, off the UI thread.
+        let sandbox = match self.model.state.sandbox_profile() {
+            Some(profile) if self.model.state.provider.is_some() => {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                match Sandbox::new(&cwd, profile) {
+                    Ok(sandbox) => Some(sandbox),
+                    Err(e) => {
+                        self.model.state.last_error = Some(crate::error::ErrorDisplay {
+                            title: "Sandbox Error".to_string(),
+                            message: format!("{:#}", e),
+                            help_url: None,
+                        });
+                        self.model.state.mode = Mode::Error;
+                        return;
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        // `#Name` references attach the symbol's definition as context
+        let (attached, missing) = self.model.state.attach_symbol_references(&prompt);
+
         if let Some(provider) = &self.model.state.provider {
             if self.model.state.sessions.current_session_id.is_none() {
                 let cwd = std::env::current_dir().unwrap_or_default();
@@ -492,7 +2363,16 @@ impl App {
                     .model
                     .state
                     .sessions
-                    .start_session(&provider.name().to_string(), &cwd);
+                    .start_session(provider.name(), &cwd);
+                // Files pinned and macros recorded before the first prompt
+                // belong to the new session
+                let sidebar = &self.model.state.sidebar_state;
+                self.model
+                    .state
+                    .sessions
+                    .set_sidebar_files(&sidebar.pins, &sidebar.recent_files);
+                let macros = &self.model.state.macros.registers;
+                self.model.state.sessions.set_macros(macros);
             }
 
             let user_message = ChatMessage {
@@ -504,44 +2384,165 @@ impl App {
                 cost: None,
                 status: MessageStatus::Pending,
                 associated_files: vec![],
+                apply_summary: None,
             };
-            self.model.state.chat_history.next_id += 1;
-            self.model.state.chat_history.add_message(user_message);
-            if let Some(current_id) = self.model.state.sessions.current_session_id.clone() {
-                if let Some(session) = self.model.state.sessions.sessions.get_mut(&current_id) {
-                    session.messages.push(
-                        self.model
-                            .state
-                            .chat_history
-                            .messages
-                            .last()
-                            .cloned()
-                            .unwrap(),
-                    );
-                }
+            self.model.state.sessions.name_after(&prompt);
+            let session = self.model.state.sessions.current_session_id.as_deref();
+            if let Err(e) = self.model.state.chat_history.record(user_message, session) {
+                self.model.state.status_info.current_task =
+                    tf("status.chat_not_saved", &[("error", &format!("{:#}", e))]);
             }
 
             self.model.state.status_info.is_working = true;
-            self.model.state.status_info.current_task = "Processing prompt...".to_string();
+            self.model.state.status_info.current_task = t("status.processing").to_string();
             self.model.state.status_info.start_time = Some(std::time::Instant::now());
 
+            if !attached.is_empty() || !missing.is_empty() {
+                let mut note = String::from(t("status.processing"));
+                if !attached.is_empty() {
+                    note.push_str(&tf("status.attached", &[("files", &attached.join(", "))]));
+                }
+                if !missing.is_empty() {
+                    note.push_str(&tf(
+                        "status.no_definition_for",
+                        &[("symbols", &missing.join(", #"))],
+                    ));
+                }
+                self.model.state.status_info.current_task = note;
+            }
+
+            self.model.state.last_prompt = Some(prompt.clone());
+
+            let mut context_files: Vec<PathBuf> = Vec::new();
+            for attachment in &self.model.state.context_attachments {
+                if !context_files.contains(&attachment.path) {
+                    context_files.push(attachment.path.clone());
+                }
+            }
+            let prompt = if provider.accepts_context_files()
+                || self.model.state.context_attachments.is_empty()
+            {
+                prompt
+            } else {
+                use crate::context::{estimate_tokens, fit_to_budget, render_context_block};
+                let budget = self
+                    .model
+                    .state
+                    .context_token_limit()
+                    .saturating_sub(estimate_tokens(&prompt));
+                let fitted = fit_to_budget(
+                    &self.model.state.context_attachments,
+                    budget,
+                    self.model.state.config.general.context_truncation,
+                );
+                format!("{}{}", prompt, render_context_block(&fitted))
+            };
+
+            // Providers that don't explore the repository are told what it is
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let general = &self.model.state.config.general;
+            let prompt = if general.project_summary && !provider.reads_repository() {
+                let summary = self
+                    .model
+                    .state
+                    .project_summary
+                    .get(&cwd, general.project_summary_tokens);
+                format!("{}\n\n{}", summary, prompt)
+            } else {
+                prompt
+            };
+
+            // Providers without a system prompt hook get the preamble inline
+            let preamble = self.model.state.preamble.clone();
+            let prompt = match &preamble {
+                Some(preamble) if !provider.supports_preamble() => {
+                    format!("{}\n\n{}", preamble.text, prompt)
+                }
+                _ => prompt,
+            };
+
+            // Providers without an ask mode are asked in the prompt not to edit;
+            // a fix request always wants edits
+            let mode = if self.model.state.refining_review {
+                PromptMode::Edit
+            } else {
+                self.model.state.prompt_mode
+            };
+            let prompt = if mode == PromptMode::Ask && !provider.supports_ask() {
+                format!("{}\n\n{}", crate::providers::ASK_INSTRUCTION, prompt)
+            } else {
+                prompt
+            };
+
+            // Resume the provider's own conversation when it supports that
+            let session_id = if provider.supports_sessions() {
+                self.model
+                    .state
+                    .sessions
+                    .current_session()
+                    .and_then(|s| s.provider_session_id.clone())
+            } else {
+                None
+            };
             let request = crate::state::PromptRequest {
                 prompt,
-                context_files: vec![],
-                session_id: None,
-                working_directory: std::env::current_dir().unwrap_or_default(),
+                context_files,
+                session_id,
+                working_directory: sandbox.as_ref().map_or(cwd, |s| s.path().to_path_buf()),
+                preamble: preamble.filter(|_| provider.supports_preamble()),
+                mode,
             };
+            self.model.state.last_prompt_tokens = crate::context::estimate_tokens(&request.prompt);
+            self.model.state.sessions.record_prompt();
 
-            let args = provider.build_execute_args(&request);
-            let cmd = provider.cli_command().to_string();
+            let command = provider.command_line(&request);
             let provider_name = provider.name().to_string();
+            let capture = provider.output_source().capture_mode();
 
             self.model.state.status_info.provider = provider_name.clone();
 
-            let task =
-                tokio::spawn(
-                    async move { execute_provider_prompt(&cmd, args, &provider_name).await },
+            // Output lines are forwarded as events so the activity timeline
+            // updates while the provider runs
+            self.model.state.activity.clear();
+            let (line_tx, mut line_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+            let events = self.event_handler.task_sender();
+            tokio::spawn(async move {
+                while let Some(line) = line_rx.recv().await {
+                    if events.send(AppEvent::ProviderLine(line)).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            self.model.state.last_command = Some(command.clone());
+
+            let events = self.event_handler.task_sender();
+            let task = tokio::spawn(self.scheduler.run(TaskKind::Prompt, async move {
+                let sandbox = match sandbox {
+                    Some(sandbox) => Some(fill_sandbox(sandbox, events).await?),
+                    None => None,
+                };
+                let started = std::time::Instant::now();
+                let mut result = execute_provider_prompt(
+                    &command,
+                    &provider_name,
+                    capture,
+                    Some(line_tx),
+                    sandbox.as_ref().map(Sandbox::path),
+                )
+                .await?;
+                result.context.insert(
+                    "elapsed_ms".to_string(),
+                    started.elapsed().as_millis().to_string(),
                 );
+                if mode == PromptMode::Ask {
+                    result
+                        .context
+                        .insert("prompt_mode".to_string(), "ask".to_string());
+                }
+                record_sandbox_edits(&mut result, sandbox.as_ref())?;
+                Ok(result)
+            }));
 
             self.pending_tasks
                 .insert("prompt_execution".to_string(), task);
@@ -557,6 +2558,417 @@ impl App {
         }
     }
 
+    /// Start a `:foreach`: queue one run of `prompt` per file matching `pattern`
+    fn run_batch(&mut self, pattern: String, prompt: String) {
+        let state = &mut self.model.state;
+        let Some(provider) = &state.provider else {
+            state.last_error = Some(crate::error::ErrorDisplay {
+                title: "No Provider".to_string(),
+                message: "Please select a provider first".to_string(),
+                help_url: None,
+            });
+            state.mode = Mode::Error;
+            return;
+        };
+        if state.batch.is_some() || state.execution_state != ExecutionState::Idle {
+            state.status_info.current_task = t("status.prompt_running").to_string();
+            return;
+        }
+
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let files = match crate::batch::matching_files(&cwd, &pattern) {
+            Ok(files) if files.is_empty() => {
+                state.status_info.current_task =
+                    tf("status.no_files_match", &[("pattern", &pattern)]);
+                return;
+            }
+            Ok(files) => files,
+            Err(e) => {
+                state.last_error = Some(crate::error::ErrorDisplay {
+                    title: "Batch Error".to_string(),
+                    message: format!("{:#}", e),
+                    help_url: None,
+                });
+                state.mode = Mode::Error;
+                return;
+            }
+        };
+
+        if state.sessions.current_session_id.is_none() {
+            let _ = state.sessions.start_session(provider.name(), &cwd);
+            state
+                .sessions
+                .set_sidebar_files(&state.sidebar_state.pins, &state.sidebar_state.recent_files);
+            state.sessions.set_macros(&state.macros.registers);
+        }
+        let user_message = ChatMessage {
+            id: state.chat_history.next_id,
+            timestamp: chrono::Utc::now(),
+            is_user: true,
+            content: format!(":foreach {} {}", pattern, prompt),
+            token_count: None,
+            cost: None,
+            status: MessageStatus::Pending,
+            associated_files: files.clone(),
+            apply_summary: None,
+        };
+        state.sessions.name_after(&user_message.content);
+        let session = state.sessions.current_session_id.as_deref();
+        if let Err(e) = state.chat_history.record(user_message, session) {
+            state.status_info.current_task =
+                tf("status.chat_not_saved", &[("error", &format!("{:#}", e))]);
+        }
+
+        state.batch = Some(crate::batch::BatchRun::new(&pattern, &prompt, files));
+        state.activity.clear();
+        state.status_info.is_working = true;
+        state.status_info.start_time = Some(std::time::Instant::now());
+        state.status_info.provider = provider.name().to_string();
+        state.execution_state = ExecutionState::WaitingForResult;
+        state.mode = Mode::Processing;
+        self.start_batch_runs();
+    }
+
+    /// Start queued `:foreach` runs up to the concurrency limit
+    fn start_batch_runs(&mut self) {
+        let limit = self.model.state.config.general.batch_concurrency;
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let sandbox_profile = self.model.state.sandbox_profile();
+        let events = self.event_handler.task_sender();
+        loop {
+            let state = &mut self.model.state;
+            let (Some(batch), Some(provider)) = (state.batch.as_mut(), &state.provider) else {
+                return;
+            };
+            let Some(file) = batch.next_file(limit) else {
+                break;
+            };
+            let content = match std::fs::read_to_string(&file) {
+                Ok(content) => content,
+                Err(e) => {
+                    batch.finish(&file, Err(format!("could not read it: {}", e)));
+                    continue;
+                }
+            };
+
+            let prompt = crate::batch::file_prompt(&batch.prompt, &file, &content);
+            let general = &state.config.general;
+            let prompt = if general.project_summary && !provider.reads_repository() {
+                let summary = state
+                    .project_summary
+                    .get(&cwd, general.project_summary_tokens);
+                format!("{}\n\n{}", summary, prompt)
+            } else {
+                prompt
+            };
+            let preamble = state.preamble.clone();
+            let prompt = match &preamble {
+                Some(preamble) if !provider.supports_preamble() => {
+                    format!("{}\n\n{}", preamble.text, prompt)
+                }
+                _ => prompt,
+            };
+            let sandbox = match sandbox_profile {
+                Some(profile) => match Sandbox::new(&cwd, profile) {
+                    Ok(sandbox) => Some(sandbox),
+                    Err(e) => {
+                        batch.finish(&file, Err(format!("no sandbox: {:#}", e)));
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            // Each file is its own conversation; none resumes the session
+            let request = crate::state::PromptRequest {
+                prompt,
+                context_files: Vec::new(),
+                session_id: None,
+                working_directory: sandbox
+                    .as_ref()
+                    .map_or_else(|| cwd.clone(), |s| s.path().to_path_buf()),
+                preamble: preamble.filter(|_| provider.supports_preamble()),
+                mode: PromptMode::Edit,
+            };
+            let tokens = crate::context::estimate_tokens(&request.prompt);
+            let command = provider.command_line(&request);
+            let provider_name = provider.name().to_string();
+            let capture = provider.output_source().capture_mode();
+            state.sessions.record_prompt();
+
+            let batch_file = file.display().to_string();
+            let events = events.clone();
+            let task = tokio::spawn(self.scheduler.run(TaskKind::Prompt, async move {
+                let sandbox = match sandbox {
+                    Some(sandbox) => Some(fill_sandbox(sandbox, events).await?),
+                    None => None,
+                };
+                let dir = sandbox.as_ref().map(Sandbox::path);
+                let mut result =
+                    execute_provider_prompt(&command, &provider_name, capture, None, dir).await?;
+                record_sandbox_edits(&mut result, sandbox.as_ref())?;
+                result
+                    .context
+                    .insert("request_type".to_string(), "batch_prompt".to_string());
+                result.context.insert("batch_file".to_string(), batch_file);
+                result
+                    .context
+                    .insert("prompt_tokens".to_string(), tokens.to_string());
+                Ok(result)
+            }));
+            self.pending_tasks
+                .insert(format!("batch:{}", file.display()), task);
+        }
+        self.finish_batch_if_done();
+    }
+
+    /// Collect one `:foreach` run's changes and start the next queued one
+    fn handle_batch_result(&mut self, result: &CommandResult) {
+        let state = &mut self.model.state;
+        let file = PathBuf::from(
+            result
+                .context
+                .get("batch_file")
+                .cloned()
+                .unwrap_or_default(),
+        );
+        let outcome = match (&state.provider, result.exit_code) {
+            (Some(provider), Some(0)) => {
+                let output = result.output_marked(provider.output_source());
+                let usage = provider.extract_usage(&output).unwrap_or_default();
+                let tokens_in = usage.tokens_in.unwrap_or_else(|| {
+                    result
+                        .context
+                        .get("prompt_tokens")
+                        .and_then(|t| t.parse().ok())
+                        .unwrap_or(0)
+                });
+                let tokens_out = usage
+                    .tokens_out
+                    .unwrap_or_else(|| crate::context::estimate_tokens(&output));
+                state
+                    .sessions
+                    .record_response(tokens_in, tokens_out, usage.cost);
+                state.status_info.tokens_sent += tokens_in;
+                state.status_info.session_cost += usage.cost.unwrap_or(0.0);
+
+                let reply = provider.response_text(&output);
+                provider
+                    .parse_file_changes(&output)
+                    .map(|changes| {
+                        let changes = crate::sandbox::merge_changes(changes, sandbox_edits(result));
+                        let mut validation = crate::parsers::validate_changes(changes);
+                        let notes =
+                            crate::parsers::check_extensions(&mut validation.changes, &reply);
+                        if let Some(batch) = state.batch.as_mut() {
+                            if validation.changed_anything() {
+                                batch.warnings.push((file.clone(), validation.summary()));
+                            }
+                            batch.warnings.extend(notes);
+                            batch.conflicts.append(&mut validation.conflicts);
+                        }
+                        validation.changes
+                    })
+                    .map_err(|e| format!("could not parse the reply: {}", e))
+            }
+            (Some(_), code) => {
+                let stderr = String::from_utf8_lossy(&result.stderr);
+                let reason = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+                Err(format!("exit {}: {}", code.unwrap_or(-1), reason.trim()))
+            }
+            (None, _) => Err("no provider selected".to_string()),
+        };
+        if let Some(batch) = state.batch.as_mut() {
+            batch.finish(&file, outcome);
+        }
+        self.start_batch_runs();
+    }
+
+    /// A `:foreach` run that could not be started or crashed
+    fn fail_batch_run(&mut self, task_id: &str, error: String) {
+        let file = task_id.strip_prefix("batch:").unwrap_or(task_id);
+        if let Some(batch) = self.model.state.batch.as_mut() {
+            batch.finish(std::path::Path::new(file), Err(error));
+        }
+        self.start_batch_runs();
+    }
+
+    /// Once every `:foreach` run is in, review all their changes together
+    fn finish_batch_if_done(&mut self) {
+        let state = &mut self.model.state;
+        let Some(batch) = state.batch.take_if(|b| b.is_done()) else {
+            if let Some(batch) = &state.batch {
+                state.status_info.current_task = batch.summary();
+            }
+            return;
+        };
+
+        state.execution_state = ExecutionState::Idle;
+        state.status_info.is_working = false;
+        state.status_info.current_task = batch.summary();
+
+        let mut report = batch.summary();
+        if !batch.failures.is_empty() {
+            report.push_str("\n\n");
+            report.push_str(&batch.failure_report());
+        }
+        let reply = ChatMessage {
+            id: state.chat_history.next_id,
+            timestamp: chrono::Utc::now(),
+            is_user: false,
+            content: report.clone(),
+            token_count: None,
+            cost: None,
+            status: if batch.changes.is_empty() {
+                MessageStatus::Error
+            } else {
+                MessageStatus::Success
+            },
+            associated_files: batch.changes.iter().map(|c| c.path.clone()).collect(),
+            apply_summary: None,
+        };
+        let session = state.sessions.current_session_id.as_deref();
+        if let Err(e) = state.chat_history.record(reply, session) {
+            state.status_info.current_task =
+                tf("status.chat_not_saved", &[("error", &format!("{:#}", e))]);
+        }
+
+        if batch.changes.is_empty() {
+            state.last_error = Some(crate::error::ErrorDisplay {
+                title: "Batch Produced No Changes".to_string(),
+                message: batch.failure_report(),
+                help_url: None,
+            });
+            state.mode = Mode::Error;
+            return;
+        }
+        match WritePolicy::discover() {
+            Ok(policy) => {
+                state.path_warnings = batch.warnings.into_iter().collect();
+                state.edit_conflicts = batch.conflicts.into_iter().collect();
+                state.load_review(batch.changes, policy);
+                state.record_provenance(&batch.prompt);
+            }
+            Err(e) => {
+                state.last_error = Some(crate::error::ErrorDisplay {
+                    title: "Invalid .zcodeignore".to_string(),
+                    message: format!("{:#}", e),
+                    help_url: None,
+                });
+                state.mode = Mode::Error;
+            }
+        }
+    }
+
+    /// Keep the file watcher in step with the files currently under review
+    /// Diff the queued large files on the blocking pool; each result comes
+    /// back as an event and fills in its placeholder
+    fn start_diff_jobs(&mut self) {
+        for job in std::mem::take(&mut self.model.state.diff_jobs) {
+            self.model.state.diffs_running.insert(job.key);
+            let events = self.event_handler.task_sender();
+            // The blocking pool only gets the diff on its turn
+            tokio::spawn(self.scheduler.run(TaskKind::Diff, async move {
+                let _ = tokio::task::spawn_blocking(move || {
+                    let started = std::time::Instant::now();
+                    let hunks = crate::diff::compute_hunks(&job.path, &job.original, &job.proposed);
+                    let _ = events.send(AppEvent::DiffReady {
+                        key: job.key,
+                        hunks,
+                        elapsed: started.elapsed(),
+                    });
+                })
+                .await;
+            }));
+        }
+    }
+
+    /// Write the terminal title when the activity it describes changed
+    fn update_title(&mut self) {
+        if !self.model.state.config.display.update_titles {
+            return;
+        }
+        let title = self.model.state.title();
+        if self.title.as_ref() != Some(&title) {
+            let _ = crossterm::execute!(std::io::stdout(), crossterm::terminal::SetTitle(&title));
+            self.title = Some(title);
+        }
+    }
+
+    /// Shape the cursor for the input that has it (see [`crate::ui::cursor::shape`])
+    /// when that input's mode changed
+    fn update_cursor_shape(&mut self) {
+        let state = &self.model.state;
+        let mode = match state.mode {
+            Mode::CommandMode => InputMode::Command,
+            Mode::Output
+                if state
+                    .failed_output
+                    .as_ref()
+                    .is_some_and(|v| v.input.is_some()) =>
+            {
+                InputMode::Search
+            }
+            _ if state.prompt_focused() => self.model.input_mode,
+            _ => return,
+        };
+        if self.cursor_mode != Some(mode) {
+            let _ = crossterm::execute!(std::io::stdout(), crate::ui::cursor::shape(mode));
+            self.cursor_mode = Some(mode);
+        }
+    }
+
+    /// Reload the config (see [`crate::state::State::reload_config`]) and put
+    /// the theme, key bindings and the providers it names into effect
+    fn reload_config(&mut self, live: bool) {
+        let providers = self.model.state.config.providers.clone();
+        if !self.model.state.reload_config(live) {
+            return;
+        }
+        self.model.theme = crate::ui::colors::Theme::for_display(&self.model.state.config.display);
+        self.key_parser =
+            KeySequenceParser::new(sequence_timeout(&self.model.state.config.display));
+        self.keymap = KeymapRegistry::from_config(&self.model.state.config.keybindings);
+        let limit = self.model.state.config.general.max_concurrent_tasks;
+        if limit != self.scheduler.limit() {
+            self.scheduler.set_limit(limit);
+        }
+        if self.model.state.config.providers != providers {
+            self.start_provider_detection(false);
+        }
+    }
+
+    fn sync_review_watcher(&mut self) {
+        // Once applied, the files on disk are expected to differ from the review
+        let files: BTreeSet<PathBuf> = if self.model.state.applied_review {
+            BTreeSet::new()
+        } else {
+            // A renamed file's original is still at its old path
+            self.model
+                .state
+                .pending_changes
+                .values()
+                .flat_map(|c| std::iter::once(&c.path).chain(&c.renamed_from))
+                .cloned()
+                .collect()
+        };
+        if files == self.watched_files {
+            return;
+        }
+
+        self.review_watcher = None;
+        self.watched_files = files.clone();
+        if files.is_empty() {
+            return;
+        }
+        // Watching is best effort; the review still works without it
+        self.review_watcher = FileWatcher::new(
+            files,
+            self.event_handler.task_sender(),
+            AppEvent::FileChanged,
+        )
+        .ok();
+    }
+
     pub async fn poll_async_tasks(&mut self) {
         let mut completed_tasks = Vec::new();
 
@@ -573,9 +2985,12 @@ impl App {
                         self.handle_command_result(result);
                     }
                     Ok(Err(e)) => {
-                        if task_id.starts_with("detect_") {
+                        if let Some(provider_id) = task_id.strip_prefix("revalidate_") {
+                            self.record_detection(provider_id, "", None);
+                        } else if task_id.starts_with("detect_") {
                             let provider_id = task_id.strip_prefix("detect_").unwrap_or(&task_id);
                             self.model.state.pending_detections.remove(provider_id);
+                            self.record_detection(provider_id, "", None);
 
                             let err_msg = e.to_string();
                             if !err_msg.contains("not found") && !err_msg.contains("NotFound") {
@@ -585,10 +3000,33 @@ impl App {
                             if self.model.state.pending_detections.is_empty() {
                                 self.model.state.detection_state = DetectionState::Completed;
                             }
+                        } else if task_id == "test_run" {
+                            self.model.state.status_info.current_task =
+                                tf("status.tests_not_run", &[("error", &e)]);
+                        } else if task_id == "suggestion_run" {
+                            self.model.state.status_info.current_task =
+                                tf("status.command_not_run", &[("error", &e)]);
+                        } else if task_id.starts_with("batch:") {
+                            self.fail_batch_run(&task_id, format!("{:#}", e));
+                        } else if task_id == "prompt_execution" {
+                            // The provider never ran, e.g. its sandbox couldn't be made
+                            let state = &mut self.model.state;
+                            state.execution_state = ExecutionState::Idle;
+                            state.status_info.is_working = false;
+                            state.refining_review = false;
+                            state.last_error = Some(crate::error::ErrorDisplay {
+                                title: "Prompt Failed".to_string(),
+                                message: format!("{:#}", e),
+                                help_url: None,
+                            });
+                            state.mode = Mode::Error;
                         } else {
                             eprintln!("Command execution error: {}", e);
                         }
                     }
+                    Err(e) if task_id.starts_with("batch:") => {
+                        self.fail_batch_run(&task_id, e.to_string());
+                    }
                     Err(e) => {
                         if task_id.starts_with("detect_") {
                             let provider_id = task_id.strip_prefix("detect_").unwrap_or(&task_id);
@@ -603,160 +3041,317 @@ impl App {
                 }
             }
         }
+
+        self.select_preferred_provider();
+    }
+
+    /// Show which applied hunks the failing tests point into
+    fn handle_test_result(&mut self, result: &CommandResult) {
+        let state = &mut self.model.state;
+        let command = result
+            .context
+            .get("test_command")
+            .cloned()
+            .unwrap_or_default();
+        if result.exit_code == Some(0) {
+            state.status_info.current_task = tf("status.tests_passed", &[("command", &command)]);
+            return;
+        }
+
+        let output = result.output(OutputSource::Merged);
+        let failures = crate::test_results::parse_failures(&output);
+        let marked = if state.applied_review {
+            state.record_test_failures(&failures)
+        } else {
+            0
+        };
+        state.status_info.current_task = if marked > 0 {
+            tf(
+                "status.tests_failed_marked",
+                &[
+                    ("command", &command),
+                    ("count", &marked),
+                    (
+                        "icon",
+                        &crate::ui::icons::Icons::for_set(state.config.display.icons).rejected,
+                    ),
+                ],
+            )
+        } else {
+            tf("status.tests_failed", &[("command", &command)])
+        };
+    }
+
+    /// The diagnostic for `output` when it is in another format than
+    /// `provider`'s parsers read, with the version of its CLI that wrote it
+    fn format_change_error(
+        &self,
+        provider: &dyn crate::providers::AIProvider,
+        output: &str,
+    ) -> Option<crate::error::ErrorDisplay> {
+        let change = crate::providers::format::detect_format_change(provider, output)?;
+        let config_key = self
+            .model
+            .state
+            .available_providers
+            .get(self.model.state.selected_provider_idx)
+            .map_or(provider.cli_command(), |info| info.config_key.as_str());
+        let version = BUILTIN_PROVIDERS
+            .iter()
+            .find(|(_, _, _, key)| *key == config_key)
+            .and_then(|(id, ..)| self.detection_cache.providers.get(*id))
+            .and_then(|detection| detection.version.as_deref());
+        Some(crate::error::format_change_error(
+            provider.name(),
+            version,
+            config_key,
+            &change,
+        ))
+    }
+
+    /// Remember a provider detection for later starts; `output` is what
+    /// `--version` printed, `None` if it couldn't run or failed
+    fn record_detection(&mut self, provider_id: &str, command: &str, output: Option<&str>) {
+        self.detection_cache
+            .record(provider_id, command, output, chrono::Utc::now());
+        // Only saves time on the next start, so a failure isn't reported
+        let _ = self
+            .detection_cache
+            .save(&crate::detection_cache::cache_path());
     }
 
     fn handle_command_result(&mut self, result: CommandResult) {
         if let Some(provider_id) = result.context.get("provider_id") {
-            if self.model.state.pending_detections.contains(provider_id) {
-                self.model.state.pending_detections.remove(provider_id);
-
-                if result.exit_code == Some(0) {
-                    if let (Some(display_name), Some(cli_command), Some(config_key)) = (
-                        result.context.get("display_name"),
-                        result.context.get("cli_command"),
-                        result.context.get("config_key"),
-                    ) {
-                        self.model.state.available_providers.push(ProviderInfo {
-                            name: display_name.clone(),
-                            available: true,
-                            cli_command: cli_command.clone(),
-                            config_key: config_key.clone(),
-                        });
-                    }
+            let found = result.exit_code == Some(0);
+            let cli_command = result
+                .context
+                .get("cli_command")
+                .cloned()
+                .unwrap_or_default();
+            let version = result.output(OutputSource::Stdout);
+            self.record_detection(provider_id, &cli_command, found.then_some(version.as_str()));
+
+            // A background check of a cached detection only adds what was
+            // newly installed; the list isn't reordered under the user
+            let pending = self.model.state.pending_detections.remove(provider_id);
+            let config_key = result.context.get("config_key");
+            let listed = self
+                .model
+                .state
+                .available_providers
+                .iter()
+                .any(|p| Some(&p.config_key) == config_key);
+            if found && (pending || !listed) {
+                if let (Some(display_name), Some(config_key)) =
+                    (result.context.get("display_name"), config_key)
+                {
+                    self.model.state.available_providers.push(ProviderInfo {
+                        name: display_name.clone(),
+                        available: true,
+                        cli_command,
+                        config_key: config_key.clone(),
+                    });
                 }
+            }
 
-                if self.model.state.pending_detections.is_empty() {
-                    self.model.state.detection_state = DetectionState::Completed;
-                }
+            if pending && self.model.state.pending_detections.is_empty() {
+                self.model.state.detection_state = DetectionState::Completed;
             }
         }
 
+        if result.context.get("request_type").map(|s| s.as_str()) == Some("test_run") {
+            self.handle_test_result(&result);
+            return;
+        }
+
+        if result.context.get("request_type").map(|s| s.as_str()) == Some("suggestion_run") {
+            self.model.state.record_suggestion_run(&result);
+            return;
+        }
+
+        if result.context.get("request_type").map(|s| s.as_str()) == Some("batch_prompt") {
+            self.handle_batch_result(&result);
+            return;
+        }
+
         if result.context.get("request_type").map(|s| s.as_str()) == Some("prompt_execution") {
+            let refining = std::mem::take(&mut self.model.state.refining_review);
+            let asked = result.context.get("prompt_mode").map(String::as_str) == Some("ask");
+            self.model.state.activity.apply(
+                crate::activity::ActivityEvent::Done,
+                std::time::Instant::now(),
+            );
             self.model.state.execution_state = ExecutionState::Idle;
             self.model.state.status_info.is_working = false;
+            if let (Some(provider), Some(ms)) = (
+                &self.model.state.provider,
+                result
+                    .context
+                    .get("elapsed_ms")
+                    .and_then(|ms| ms.parse().ok()),
+            ) {
+                self.model
+                    .state
+                    .telemetry
+                    .record_prompt(provider.name(), Duration::from_millis(ms));
+            }
 
             if let Some(exit_code) = result.exit_code {
                 if exit_code == 0 {
                     if let Some(provider) = &self.model.state.provider {
-                        let output = String::from_utf8_lossy(&result.stdout);
+                        // Marked so files aren't written from bytes that weren't UTF-8;
+                        // the chat shows those bytes as U+FFFD
+                        let output = result.output_marked(provider.output_source());
+                        self.model.state.last_provider_output =
+                            Some(crate::encoding::for_display(&output).into_owned());
+
+                        let usage = provider.extract_usage(&output).unwrap_or_default();
+                        let tokens_in = usage
+                            .tokens_in
+                            .unwrap_or(self.model.state.last_prompt_tokens);
+                        let tokens_out = usage
+                            .tokens_out
+                            .unwrap_or_else(|| crate::context::estimate_tokens(&output));
+                        self.model
+                            .state
+                            .sessions
+                            .record_response(tokens_in, tokens_out, usage.cost);
+                        if let Some(id) = provider.extract_session_id(&output) {
+                            self.model.state.sessions.set_provider_session(id);
+                        }
+                        self.model.state.status_info.tokens_sent += tokens_in;
+                        self.model.state.status_info.session_cost += usage.cost.unwrap_or(0.0);
+                        if let Some(ms) = result
+                            .context
+                            .get("elapsed_ms")
+                            .and_then(|ms| ms.parse().ok())
+                        {
+                            self.model.state.perf.provider = Some(crate::perf::ProviderRun {
+                                provider: provider.name().to_string(),
+                                wall: std::time::Duration::from_millis(ms),
+                                output_bytes: output.len(),
+                                tokens_out,
+                            });
+                        }
 
                         let assistant_message = ChatMessage {
                             id: self.model.state.chat_history.next_id,
                             timestamp: chrono::Utc::now(),
                             is_user: false,
-                            content: output.to_string(),
-                            token_count: None,
-                            cost: None,
+                            content: crate::encoding::for_display(&provider.response_text(&output))
+                                .into_owned(),
+                            token_count: Some(tokens_out),
+                            cost: usage.cost,
                             status: MessageStatus::Success,
                             associated_files: vec![],
+                            apply_summary: None,
                         };
-                        self.model.state.chat_history.next_id += 1;
-                        self.model.state.chat_history.add_message(assistant_message);
-                        if let Some(current_id) =
-                            self.model.state.sessions.current_session_id.clone()
+                        let session = self.model.state.sessions.current_session_id.as_deref();
+                        if let Err(e) = self
+                            .model
+                            .state
+                            .chat_history
+                            .record(assistant_message, session)
                         {
-                            if let Some(session) =
-                                self.model.state.sessions.sessions.get_mut(&current_id)
-                            {
-                                if let Some(last) =
-                                    self.model.state.chat_history.messages.last().cloned()
-                                {
-                                    session.messages.push(last);
-                                }
-                            }
+                            self.model.state.status_info.current_task =
+                                tf("status.chat_not_saved", &[("error", &format!("{:#}", e))]);
                         }
 
-                        match provider.parse_file_changes(&output) {
-                            Ok(changes) => {
-                                self.model.state.pending_changes.clear();
-                                self.model.state.hunks.clear();
-                                self.model.state.overlay_diff_state.proposed_changes.clear();
-
-                                use crate::diff::{extract_hunks, generate_diff};
-
-                                for change in changes {
-                                    self.model
-                                        .state
-                                        .pending_changes
-                                        .insert(change.path.clone(), change.clone());
-
-                                    let original = change.original_content.as_deref().unwrap_or("");
-                                    let proposed = &change.proposed_content;
-                                    let diff = generate_diff(original, proposed);
-                                    let hunks = extract_hunks(&change.path, &diff);
-
-                                    let mut line_decorations = Vec::new();
-                                    for hunk in &hunks {
-                                        for line_change in &hunk.changes {
-                                            let decoration_type = match line_change.tag {
-                                                crate::state::ChangeTag::Insert => {
-                                                    crate::state::DecorationType::Addition
-                                                }
-                                                crate::state::ChangeTag::Delete => {
-                                                    crate::state::DecorationType::Deletion
-                                                }
-                                                crate::state::ChangeTag::Equal => {
-                                                    crate::state::DecorationType::Context
-                                                }
-                                            };
-
-                                            let line_num = line_change
-                                                .new_line_num
-                                                .or(line_change.old_line_num)
-                                                .unwrap_or(0);
-
-                                            let decoration = crate::state::LineDecoration {
-                                                line_number: line_num,
-                                                decoration_type,
-                                                original_text: if matches!(
-                                                    line_change.tag,
-                                                    crate::state::ChangeTag::Delete
-                                                        | crate::state::ChangeTag::Equal
-                                                ) {
-                                                    Some(line_change.content.clone())
-                                                } else {
-                                                    None
-                                                },
-                                                new_text: if matches!(
-                                                    line_change.tag,
-                                                    crate::state::ChangeTag::Insert
-                                                        | crate::state::ChangeTag::Equal
-                                                ) {
-                                                    Some(line_change.content.clone())
-                                                } else {
-                                                    None
-                                                },
-                                                accepted: None,
-                                            };
-
-                                            line_decorations.push(decoration);
-                                        }
-                                    }
-
-                                    let proposed_change = crate::state::ProposedChange {
-                                        id: self
+                        // New files are checked against their code block's language
+                        let reply = provider.response_text(&output);
+                        let started = std::time::Instant::now();
+                        let parsed = provider.parse_file_changes(&output).map(|changes| {
+                            let changes =
+                                crate::sandbox::merge_changes(changes, sandbox_edits(&result));
+                            let mut validation = crate::parsers::validate_changes(changes);
+                            let notes =
+                                crate::parsers::check_extensions(&mut validation.changes, &reply);
+                            (validation, notes)
+                        });
+                        self.model.state.perf.parse = Some(started.elapsed());
+                        // No changes from a reply the parser doesn't recognise,
+                        // in a format another parser reads
+                        let format_error = match &parsed {
+                            Ok((validation, _)) if !validation.changes.is_empty() => None,
+                            _ if asked || refining => None,
+                            _ => self
+                                .format_change_error(provider.as_ref(), &output)
+                                .map(|error| (error, format!("{} output", provider.name()))),
+                        };
+                        let suggestions = provider.command_suggestions(&output);
+                        let parsed = parsed.map(|(validation, notes)| {
+                            if validation.changed_anything() && !asked {
+                                self.model.state.note_validation(&validation);
+                            }
+                            (validation.changes, notes, validation.conflicts)
+                        });
+                        if !suggestions.is_empty() {
+                            self.model.state.suggest_commands(suggestions);
+                        }
+                        match (parsed, format_error) {
+                            // An answer, not edits: shown in full, never reviewed
+                            _ if asked => self.model.state.show_explanation(),
+                            (Ok((changes, notes, conflicts)), _) if refining => {
+                                self.model.state.path_warnings.extend(notes);
+                                // The answer settles earlier overlapping edits of its files
+                                for change in &changes {
+                                    self.model.state.edit_conflicts.remove(&change.path);
+                                }
+                                self.model.state.edit_conflicts.extend(conflicts);
+                                let updated = self.model.state.merge_review(changes);
+                                self.model.state.status_info.current_task =
+                                    tf("status.fixes_merged", &[("count", &updated)]);
+                            }
+                            (_, Some((error, title))) => {
+                                self.model.state.telemetry.record_error("output_format");
+                                self.model.state.failed_output =
+                                    Some(OutputViewer::new(title, &output));
+                                self.model.state.last_error = Some(error);
+                                self.model.state.mode = Mode::Error;
+                            }
+                            // Nothing to review, only commands to run
+                            (Ok((changes, _, _)), None)
+                                if changes.is_empty()
+                                    && !self.model.state.command_suggestions.is_empty() =>
+                            {
+                                self.model.state.mode = Mode::PromptEntry;
+                            }
+                            // Nothing to review at all: an explanation
+                            (Ok((changes, _, _)), None) if changes.is_empty() => {
+                                self.model.state.status_info.current_task =
+                                    t("status.no_file_changes").to_string();
+                                self.model.state.show_explanation();
+                            }
+                            (Ok((changes, notes, conflicts)), None) => {
+                                match WritePolicy::discover() {
+                                    Ok(policy) => {
+                                        self.model.state.path_warnings =
+                                            notes.into_iter().collect();
+                                        self.model.state.edit_conflicts =
+                                            conflicts.into_iter().collect();
+                                        self.model.state.load_review(changes, policy);
+                                        let prompt = self
                                             .model
                                             .state
-                                            .overlay_diff_state
-                                            .proposed_changes
-                                            .len(),
-                                        file_path: change.path.clone(),
-                                        original_content: original.to_string(),
-                                        proposed_content: proposed.clone(),
-                                        line_decorations,
-                                        status: crate::state::ChangeStatus::Pending,
-                                    };
-
-                                    self.model
-                                        .state
-                                        .overlay_diff_state
-                                        .proposed_changes
-                                        .push(proposed_change);
+                                            .last_prompt
+                                            .clone()
+                                            .unwrap_or_default();
+                                        self.model.state.record_provenance(&prompt);
+                                    }
+                                    Err(e) => {
+                                        self.model.state.telemetry.record_error("ignore_file");
+                                        self.model.state.last_error =
+                                            Some(crate::error::ErrorDisplay {
+                                                title: "Invalid .zcodeignore".to_string(),
+                                                message: format!("{:#}", e),
+                                                help_url: None,
+                                            });
+                                        self.model.state.mode = Mode::Error;
+                                    }
                                 }
-
-                                self.model.state.mode = Mode::DiffReview;
                             }
-                            Err(e) => {
+                            (Err(e), None) => {
+                                self.model.state.telemetry.record_error("parse");
                                 self.model.state.last_error = Some(crate::error::ErrorDisplay {
                                     title: "Parse Error".to_string(),
                                     message: format!("Failed to parse provider output: {}", e),
@@ -768,6 +3363,16 @@ impl App {
                     }
                 } else {
                     let stderr_str = String::from_utf8_lossy(&result.stderr);
+                    let provider_name = self
+                        .model
+                        .state
+                        .provider
+                        .as_ref()
+                        .map_or("Provider", |p| p.name());
+                    self.model.state.failed_output = Some(OutputViewer::new(
+                        format!("{} output (exit {})", provider_name, exit_code),
+                        &result.output(OutputSource::Merged),
+                    ));
 
                     let error_message = ChatMessage {
                         id: self.model.state.chat_history.next_id,
@@ -778,24 +3383,179 @@ impl App {
                         cost: None,
                         status: MessageStatus::Error,
                         associated_files: vec![],
+                        apply_summary: None,
+                    };
+                    let session = self.model.state.sessions.current_session_id.as_deref();
+                    if let Err(e) = self.model.state.chat_history.record(error_message, session) {
+                        self.model.state.status_info.current_task =
+                            tf("status.chat_not_saved", &[("error", &format!("{:#}", e))]);
+                    }
+
+                    let stdout_str = String::from_utf8_lossy(&result.stdout);
+                    let combined = format!("{}\n{}", stderr_str, stdout_str);
+                    self.model.state.login_retry = self
+                        .model
+                        .state
+                        .provider
+                        .as_ref()
+                        .filter(|p| p.is_auth_error(&combined))
+                        .and_then(|p| p.login_command(&combined))
+                        .zip(self.model.state.last_prompt.clone())
+                        .map(|(command, prompt)| LoginRetry { command, prompt });
+                    let category = if self.model.state.login_retry.is_some() {
+                        "login"
+                    } else {
+                        "provider"
                     };
-                    self.model.state.chat_history.next_id += 1;
-                    self.model.state.chat_history.add_message(error_message);
+                    self.model.state.telemetry.record_error(category);
 
                     self.model.state.last_error = Some(crate::error::ErrorDisplay {
-                        title: "Provider Error".to_string(),
-                        message: format!("Command failed (exit {}): {}", exit_code, stderr_str),
+                        title: if self.model.state.login_retry.is_some() {
+                            "Login Required".to_string()
+                        } else {
+                            "Provider Error".to_string()
+                        },
+                        message: format!(
+                            "Command failed (exit {}): {}",
+                            exit_code,
+                            summarize_output(&stderr_str)
+                        ),
                         help_url: None,
                     });
                     self.model.state.mode = Mode::Error;
                 }
             }
+
+            self.persist_sessions();
+        }
+    }
+
+    /// Write session history (and usage counters) to disk
+    fn persist_sessions(&mut self) {
+        if let Err(e) = self.model.state.sessions.save() {
+            self.model.state.status_info.current_task =
+                tf("status.sessions_not_saved", &[("error", &e)]);
         }
     }
 }
 
+/// Copy the project into `sandbox` on the blocking pool, sending
+/// [`AppEvent::SandboxProgress`] as it goes
+async fn fill_sandbox(
+    mut sandbox: Sandbox,
+    events: tokio::sync::mpsc::UnboundedSender<AppEvent>,
+) -> Result<Sandbox> {
+    tokio::task::spawn_blocking(move || {
+        sandbox
+            .fill(|copied, total| {
+                // An event per file would keep the UI busy redrawing
+                if copied % 200 == 0 || copied == total {
+                    let _ = events.send(AppEvent::SandboxProgress { copied, total });
+                }
+            })
+            .map(|()| sandbox)
+    })
+    .await?
+    .context("Failed to set up the sandbox")
+}
+
+/// Keep the files a sandboxed run edited with its result, as JSON under
+/// `sandbox_changes`, before the sandbox is removed
+fn record_sandbox_edits(result: &mut CommandResult, sandbox: Option<&Sandbox>) -> Result<()> {
+    if let (Some(sandbox), Some(0)) = (sandbox, result.exit_code) {
+        let edits = serde_json::to_string(&sandbox.changes()?)?;
+        result.context.insert("sandbox_changes".to_string(), edits);
+    }
+    Ok(())
+}
+
+/// The files a sandboxed run edited ([`record_sandbox_edits`])
+fn sandbox_edits(result: &CommandResult) -> Vec<FileChange> {
+    result
+        .context
+        .get("sandbox_changes")
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+/// First non-empty line of a failed run's output, noting how much more there is
+fn summarize_output(output: &str) -> String {
+    let mut lines = output.lines().map(str::trim).filter(|l| !l.is_empty());
+    let Some(first) = lines.next() else {
+        return "no output".to_string();
+    };
+    match lines.count() {
+        0 => first.to_string(),
+        more => format!("{} (+{} more lines)", first, more),
+    }
+}
+
+/// Status line after the wide layout's focus changes
+fn focus_note(focus: Option<Pane>) -> String {
+    match focus {
+        Some(Pane::Chat) => tf("status.chat_focused", &[("ratio", &FOCUS_RATIO)]),
+        Some(Pane::Diff) => tf("status.diff_focused", &[("ratio", &FOCUS_RATIO)]),
+        None => t("status.panes_unfocused").to_string(),
+    }
+}
+
+/// Show a failed apply. One the pre-apply hook refused keeps the hook's
+/// output for `V`.
+fn apply_error(state: &mut crate::state::State, e: &anyhow::Error) {
+    let title = match e.downcast_ref::<crate::pre_apply::Veto>() {
+        Some(veto) => {
+            state.telemetry.record_error("pre_apply");
+            state.failed_output = Some(OutputViewer::new(
+                format!("{} output", veto.command),
+                &veto.output,
+            ));
+            "Apply Stopped by Pre-Apply Hook"
+        }
+        None => {
+            state.telemetry.record_error("apply");
+            "Failed to Apply Changes"
+        }
+    };
+    state.last_error = Some(crate::error::ErrorDisplay {
+        title: title.to_string(),
+        message: format!("{:#}", e),
+        help_url: None,
+    });
+    state.mode = Mode::Error;
+}
+
+/// How long a key sequence waits for its next key: long enough for the
+/// which-key popup to come up first
+fn sequence_timeout(display: &crate::config::DisplayConfig) -> Duration {
+    Duration::from_millis(display.which_key_delay_ms.max(500))
+}
+
 impl Default for App {
     fn default() -> Self {
-        Self::new().unwrap()
+        Self::new(&BTreeMap::new()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_count_before_a_terminal_message_runs_it_once() {
+        let mut app = App::default();
+        let msg = app.track_repeat(Message::Repeat(2, Box::new(Message::ApplyHunkAndEdit)));
+        assert!(matches!(msg, Message::ApplyHunkAndEdit));
+        assert!(app
+            .model
+            .state
+            .status_info
+            .current_task
+            .contains("done once instead of 2"));
+
+        let msg = app.track_repeat(Message::Repeat(
+            3,
+            Box::new(Message::Navigate(Direction::Down)),
+        ));
+        assert!(matches!(msg, Message::Repeat(3, _)));
     }
 }
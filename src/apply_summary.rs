@@ -0,0 +1,247 @@
+//! What an apply did, posted to the chat
+//!
+//! After the accepted hunks are written, a summary (files changed, hunks
+//! applied and rejected, where the backups went, how long it took and what it
+//! cost) is recorded as a chat message, so the session's conversation shows
+//! what came of each reply. `:copy-summary` copies the latest one as plain
+//! text, laid out as a commit message.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::file_ops::ApplyResult;
+use crate::state::{ChangeTag, ChangeType, FileChange, Hunk, HunkStatus};
+
+/// One file an apply wrote or deleted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SummaryFile {
+    pub path: PathBuf,
+    /// `created`, `modified`, `deleted` or `renamed from <old path>`
+    pub change: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// One apply, as kept with its chat message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApplySummary {
+    /// First line of the prompt the changes answered
+    pub prompt: Option<String>,
+    pub files: Vec<SummaryFile>,
+    pub hunks_applied: usize,
+    pub hunks_rejected: usize,
+    /// Files that could not be written and why
+    #[serde(default)]
+    pub files_failed: Vec<(PathBuf, String)>,
+    /// Directory the files' previous contents were copied to
+    pub backup_dir: Option<PathBuf>,
+    /// Where deleted files were moved (restorable with `:undo`)
+    pub trash_dir: Option<PathBuf>,
+    /// How long the provider took to reply, in milliseconds
+    pub provider_ms: Option<u64>,
+    /// How long the review was open, in milliseconds
+    pub review_ms: Option<u64>,
+    pub cost: Option<f64>,
+}
+
+impl ApplySummary {
+    /// Summarize `result`, counting lines from the review's `hunks`
+    pub fn new(
+        result: &ApplyResult,
+        hunks: &[Hunk],
+        changes: &HashMap<PathBuf, FileChange>,
+        prompt: Option<&str>,
+    ) -> Self {
+        let written = result.files_modified.iter().chain(&result.files_deleted);
+        let files = written
+            .map(|path| {
+                let lines = |tag: ChangeTag| {
+                    hunks
+                        .iter()
+                        .filter(|h| &h.file_path == path && h.status == HunkStatus::Accepted)
+                        .flat_map(|h| &h.changes)
+                        .filter(|c| c.tag == tag)
+                        .count()
+                };
+                let renamed_from = result
+                    .files_renamed
+                    .iter()
+                    .find(|(_, new)| new == path)
+                    .map(|(old, _)| old);
+                let change = match (renamed_from, changes.get(path).map(|c| &c.change_type)) {
+                    _ if result.files_deleted.contains(path) => "deleted".to_string(),
+                    (Some(old), _) => format!("renamed from {}", old.display()),
+                    (None, Some(ChangeType::Create)) => "created".to_string(),
+                    _ => "modified".to_string(),
+                };
+                SummaryFile {
+                    path: path.clone(),
+                    change,
+                    added: lines(ChangeTag::Insert),
+                    removed: lines(ChangeTag::Delete),
+                }
+            })
+            .collect();
+
+        Self {
+            prompt: prompt
+                .and_then(|p| p.lines().map(str::trim).find(|l| !l.is_empty()))
+                .map(str::to_string),
+            files,
+            hunks_applied: result.hunks_applied,
+            hunks_rejected: hunks
+                .iter()
+                .filter(|h| h.status == HunkStatus::Rejected)
+                .count(),
+            files_failed: result.files_failed.clone(),
+            backup_dir: result
+                .backups_created
+                .first()
+                .and_then(|(_, backup)| backup.parent())
+                .map(PathBuf::from),
+            trash_dir: result
+                .trash_dir
+                .clone()
+                .filter(|_| !result.files_deleted.is_empty()),
+            provider_ms: None,
+            review_ms: None,
+            cost: None,
+        }
+    }
+
+    /// One line: hunks and files, e.g. "Applied 5 hunks to 3 files, 2 rejected"
+    pub fn headline(&self) -> String {
+        let mut line = format!(
+            "Applied {} hunks to {} files",
+            self.hunks_applied,
+            self.files.len()
+        );
+        if self.hunks_rejected > 0 {
+            line.push_str(&format!(", {} rejected", self.hunks_rejected));
+        }
+        line
+    }
+
+    /// The chat message: the headline, a line per file, then where the
+    /// backups are, the time taken and the cost
+    pub fn markdown(&self) -> String {
+        let mut text = format!("**{}**\n", self.headline());
+        if !self.files.is_empty() {
+            text.push('\n');
+        }
+        for file in &self.files {
+            text.push_str(&format!(
+                "- `{}` {} ({})\n",
+                file.path.display(),
+                file.change,
+                line_counts(file)
+            ));
+        }
+        for (path, reason) in &self.files_failed {
+            text.push_str(&format!("- `{}` not written: {}\n", path.display(), reason));
+        }
+
+        let mut details = Vec::new();
+        if let Some(dir) = &self.backup_dir {
+            details.push(format!("Backups in `{}`", dir.display()));
+        }
+        if let Some(dir) = &self.trash_dir {
+            details.push(format!("deleted files in `{}`", dir.display()));
+        }
+        let timings: Vec<String> = [("provider", self.provider_ms), ("review", self.review_ms)]
+            .into_iter()
+            .filter_map(|(what, ms)| Some(format!("{} {}", what, duration(ms?))))
+            .collect();
+        if !timings.is_empty() {
+            details.push(timings.join(", "));
+        }
+        if let Some(cost) = self.cost {
+            details.push(format!("${:.4}", cost));
+        }
+        if !details.is_empty() {
+            text.push_str(&format!("\n{}\n", details.join(" · ")));
+        }
+        text.push_str("\n`:copy-summary` copies this as a commit message.");
+        text
+    }
+
+    /// Plain text for a commit message: the prompt as the subject (cut to 72
+    /// columns), then the files and hunk counts
+    pub fn plain_text(&self) -> String {
+        let subject = self
+            .prompt
+            .as_deref()
+            .map(|p| p.chars().take(72).collect::<String>())
+            .unwrap_or_else(|| "Apply reviewed changes".to_string());
+        let mut text = format!("{}\n\n", subject);
+        for file in &self.files {
+            text.push_str(&format!(
+                "- {}: {} ({})\n",
+                file.path.display(),
+                file.change,
+                line_counts(file)
+            ));
+        }
+        text.push_str(&format!("\n{}\n", self.headline()));
+        text
+    }
+}
+
+fn line_counts(file: &SummaryFile) -> String {
+    format!("+{} -{}", file.added, file.removed)
+}
+
+/// `850ms`, `12.3s` or `4m 05s`
+fn duration(ms: u64) -> String {
+    let time = Duration::from_millis(ms);
+    match time.as_secs() {
+        0 => format!("{}ms", ms),
+        1..=59 => format!("{:.1}s", time.as_secs_f64()),
+        secs => format!("{}m {:02}s", secs / 60, secs % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_counts_lines_and_formats_commit_message() {
+        let path = PathBuf::from("src/lib.rs");
+        let mut hunks = crate::diff::compute_hunks(&path, "a\nb\n", "a\nB\nc\n");
+        for hunk in &mut hunks {
+            hunk.status = HunkStatus::Accepted;
+        }
+        let result = ApplyResult {
+            files_modified: vec![path.clone()],
+            backups_created: vec![(path.clone(), PathBuf::from("/b/1_lib.rs"))],
+            hunks_applied: hunks.len(),
+            files_ignored: Vec::new(),
+            files_failed: Vec::new(),
+            files_deleted: Vec::new(),
+            trash_dir: None,
+            files_renamed: Vec::new(),
+        };
+        let mut summary = ApplySummary::new(
+            &result,
+            &hunks,
+            &HashMap::new(),
+            Some("\n  Rename b  \nmore detail"),
+        );
+        summary.provider_ms = Some(12_300);
+        summary.review_ms = Some(245_000);
+
+        assert_eq!(summary.files[0].change, "modified");
+        assert_eq!((summary.files[0].added, summary.files[0].removed), (2, 1));
+        assert_eq!(summary.backup_dir, Some(PathBuf::from("/b")));
+        assert!(summary
+            .markdown()
+            .contains("Backups in `/b` · provider 12.3s, review 4m 05s"));
+        assert_eq!(
+            summary.plain_text(),
+            "Rename b\n\n- src/lib.rs: modified (+2 -1)\n\nApplied 1 hunks to 1 files\n"
+        );
+    }
+}
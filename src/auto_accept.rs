@@ -0,0 +1,196 @@
+//! Auto-accepting trivial hunks
+//!
+//! The `[auto_accept]` rules of the config accept hunks that need no review
+//! as the review opens: whitespace-only changes, reordered imports and changes
+//! to generated files. Such hunks are marked with the rule that accepted them
+//! and can be rejected like any other.
+
+use anyhow::{anyhow, Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+use crate::config::AutoAcceptConfig;
+use crate::state::{ChangeTag, Hunk};
+
+/// Lines that only import something, in the languages commonly reviewed
+const IMPORT_LINE: &str = r#"^(pub(\([a-z]+\))? use |use |import |from \S+ import |#include |@import |require |(const|let|var) \S+ = require\()"#;
+
+/// The compiled auto-accept rules
+#[derive(Default)]
+pub struct AutoAccept {
+    root: PathBuf,
+    whitespace: bool,
+    imports: Option<Regex>,
+    paths: Option<Gitignore>,
+}
+
+impl std::fmt::Debug for AutoAccept {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AutoAccept")
+            .field("whitespace", &self.whitespace)
+            .field("imports", &self.imports.is_some())
+            .field("paths", &self.paths.is_some())
+            .finish()
+    }
+}
+
+impl AutoAccept {
+    /// Compile `config`, with path patterns relative to `root`
+    pub fn new(root: &Path, config: &AutoAcceptConfig) -> Result<Self> {
+        let paths = if config.paths.is_empty() {
+            None
+        } else {
+            let mut builder = GitignoreBuilder::new(root);
+            for pattern in &config.paths {
+                builder
+                    .add_line(None, pattern)
+                    .map_err(|e| anyhow!("auto_accept.paths: {}", e))?;
+            }
+            Some(builder.build().context("auto_accept.paths")?)
+        };
+        let imports = config
+            .imports
+            .then(|| Regex::new(IMPORT_LINE))
+            .transpose()?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            whitespace: config.whitespace,
+            imports,
+            paths,
+        })
+    }
+
+    /// Name of the rule that accepts `hunk`, if any
+    pub fn rule_for(&self, hunk: &Hunk) -> Option<&'static str> {
+        let removed: Vec<&str> = lines(hunk, ChangeTag::Delete).collect();
+        let added: Vec<&str> = lines(hunk, ChangeTag::Insert).collect();
+        if removed.is_empty() && added.is_empty() {
+            return None;
+        }
+
+        if self.generated(&hunk.file_path) {
+            return Some("generated");
+        }
+        // Both sides in order with their context, so a moved line isn't whitespace
+        let old_side: Vec<&str> = side(hunk, ChangeTag::Insert).collect();
+        let new_side: Vec<&str> = side(hunk, ChangeTag::Delete).collect();
+        if self.whitespace && squeeze(&old_side) == squeeze(&new_side) {
+            return Some("whitespace");
+        }
+        if let Some(import) = &self.imports {
+            let is_import = |line: &&str| line.trim().is_empty() || import.is_match(line.trim());
+            let mut removed: Vec<&str> = removed.iter().map(|l| l.trim()).collect();
+            let mut added: Vec<&str> = added.iter().map(|l| l.trim()).collect();
+            removed.retain(|l| !l.is_empty());
+            added.retain(|l| !l.is_empty());
+            removed.sort_unstable();
+            added.sort_unstable();
+            let only_imports = removed.iter().chain(&added).all(is_import);
+            if only_imports && !added.is_empty() && removed == added {
+                return Some("imports");
+            }
+        }
+        None
+    }
+
+    fn generated(&self, path: &Path) -> bool {
+        let Some(paths) = &self.paths else {
+            return false;
+        };
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        !relative.is_absolute()
+            && paths
+                .matched_path_or_any_parents(relative, false)
+                .is_ignore()
+    }
+}
+
+/// The hunk's lines with tag `tag`
+fn lines(hunk: &Hunk, tag: ChangeTag) -> impl Iterator<Item = &str> {
+    hunk.changes
+        .iter()
+        .filter(move |c| c.tag == tag)
+        .map(|c| c.content.as_str())
+}
+
+/// The hunk's lines except those tagged `other`
+fn side(hunk: &Hunk, other: ChangeTag) -> impl Iterator<Item = &str> {
+    hunk.changes
+        .iter()
+        .filter(move |c| c.tag != other)
+        .map(|c| c.content.as_str())
+}
+
+/// All of `lines` with the whitespace taken out
+fn squeeze(lines: &[&str]) -> String {
+    lines
+        .iter()
+        .flat_map(|l| l.chars())
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{HunkRange, HunkStatus, LineChange};
+
+    fn hunk(path: &str, changes: &[(ChangeTag, &str)]) -> Hunk {
+        Hunk {
+            id: 0,
+            file_path: PathBuf::from(path),
+            old: HunkRange::default(),
+            new: HunkRange::default(),
+            changes: changes
+                .iter()
+                .map(|(tag, content)| LineChange {
+                    tag: tag.clone(),
+                    content: (*content).into(),
+                    old_line_num: None,
+                    new_line_num: None,
+                })
+                .collect(),
+            status: HunkStatus::Pending,
+            drifted: false,
+            comment: None,
+            auto_accepted: None,
+        }
+    }
+
+    #[test]
+    fn test_rules_accept_only_trivial_hunks() {
+        let config = AutoAcceptConfig {
+            whitespace: true,
+            imports: true,
+            paths: vec!["generated/".to_string()],
+        };
+        let rules = AutoAccept::new(Path::new("/project"), &config).unwrap();
+        use ChangeTag::{Delete, Equal, Insert};
+
+        let reindent = [(Delete, "  let x = 1;\n"), (Insert, "    let x = 1;\n")];
+        assert_eq!(
+            rules.rule_for(&hunk("src/a.rs", &reindent)),
+            Some("whitespace")
+        );
+
+        let reorder = [
+            (Delete, "use std::fs;\n"),
+            (Equal, "use std::io;\n"),
+            (Insert, "use std::fs;\n"),
+        ];
+        assert_eq!(rules.rule_for(&hunk("src/a.rs", &reorder)), Some("imports"));
+        let new_import = [(Insert, "use std::fs;\n")];
+        assert_eq!(rules.rule_for(&hunk("src/a.rs", &new_import)), None);
+
+        let edit = [(Delete, "let x = 1;\n"), (Insert, "let x = 2;\n")];
+        assert_eq!(rules.rule_for(&hunk("src/a.rs", &edit)), None);
+        assert_eq!(
+            rules.rule_for(&hunk("/project/generated/api.rs", &edit)),
+            Some("generated")
+        );
+
+        let off = AutoAccept::new(Path::new("/project"), &AutoAcceptConfig::default()).unwrap();
+        assert_eq!(off.rule_for(&hunk("src/a.rs", &reindent)), None);
+    }
+}
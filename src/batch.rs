@@ -0,0 +1,219 @@
+//! Batch prompts over several files (`:foreach <glob> <prompt>`)
+//!
+//! The prompt is run once per file matching the glob, with that file's
+//! content attached. Runs are queued and at most `general.batch_concurrency`
+//! execute at once. The changes of every run are collected into one combined
+//! review; runs that fail or produce nothing usable are reported per file.
+
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use crate::context::{estimate_tokens, render_context_block, BudgetedContext};
+use crate::state::FileChange;
+
+/// Most files one `:foreach` runs over
+pub const MAX_FILES: usize = 100;
+
+/// Files under `root` matching the gitignore-style `pattern` (e.g. `src/**/*.rs`),
+/// relative to `root` and sorted. Ignored files are skipped as everywhere else.
+pub fn matching_files(root: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let overrides = ignore::overrides::OverrideBuilder::new(root)
+        .add(pattern)
+        .and_then(|builder| builder.build())
+        .map_err(|e| anyhow!("Invalid pattern '{}': {}", pattern, e))?;
+    let mut files: Vec<PathBuf> = ignore::WalkBuilder::new(root)
+        .overrides(overrides)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| {
+            entry
+                .path()
+                .strip_prefix(root)
+                .unwrap_or(entry.path())
+                .to_path_buf()
+        })
+        .collect();
+    files.sort();
+    if files.len() > MAX_FILES {
+        return Err(anyhow!(
+            "'{}' matches {} files; narrow it to at most {}",
+            pattern,
+            files.len(),
+            MAX_FILES
+        ));
+    }
+    Ok(files)
+}
+
+/// `prompt` with `path`'s `content` attached the way context files are
+pub fn file_prompt(prompt: &str, path: &Path, content: &str) -> String {
+    let context = BudgetedContext {
+        path: path.to_path_buf(),
+        lines: None,
+        content: content.to_string(),
+        tokens: estimate_tokens(content),
+        truncated: false,
+    };
+    format!("{}{}", prompt, render_context_block(&[context]))
+}
+
+/// Progress of one `:foreach`
+#[derive(Debug, Clone, Default)]
+pub struct BatchRun {
+    pub pattern: String,
+    pub prompt: String,
+    /// Files not started yet
+    pub queue: VecDeque<PathBuf>,
+    pub running: usize,
+    pub total: usize,
+    /// Changes collected so far, at most one per path
+    pub changes: Vec<FileChange>,
+    /// Files whose run failed, with the reason
+    pub failures: Vec<(PathBuf, String)>,
+    /// File name notes from the parsers, shown on the review like a single run's
+    pub warnings: Vec<(PathBuf, String)>,
+    /// Files a reply edited more than once in the same lines, see
+    /// [`crate::parsers::Validation::conflicts`]
+    pub conflicts: Vec<(PathBuf, Vec<String>)>,
+}
+
+impl BatchRun {
+    pub fn new(pattern: &str, prompt: &str, files: Vec<PathBuf>) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            prompt: prompt.to_string(),
+            total: files.len(),
+            queue: files.into(),
+            ..Self::default()
+        }
+    }
+
+    /// The next file to start, if fewer than `limit` runs are in flight
+    pub fn next_file(&mut self, limit: usize) -> Option<PathBuf> {
+        if self.running >= limit.max(1) {
+            return None;
+        }
+        let file = self.queue.pop_front()?;
+        self.running += 1;
+        Some(file)
+    }
+
+    /// Record the outcome of `file`'s run. A path changed by an earlier run
+    /// keeps those changes; the later ones are reported as a conflict.
+    pub fn finish(&mut self, file: &Path, outcome: Result<Vec<FileChange>, String>) {
+        self.running = self.running.saturating_sub(1);
+        match outcome {
+            Ok(changes) if changes.is_empty() => {
+                self.failures
+                    .push((file.to_path_buf(), "no changes proposed".to_string()));
+            }
+            Ok(changes) => {
+                for change in changes {
+                    if self.changes.iter().any(|c| c.path == change.path) {
+                        self.failures.push((
+                            file.to_path_buf(),
+                            format!(
+                                "{} was already changed by another run; kept the first",
+                                change.path.display()
+                            ),
+                        ));
+                    } else {
+                        self.changes.push(change);
+                    }
+                }
+            }
+            Err(reason) => self.failures.push((file.to_path_buf(), reason)),
+        }
+    }
+
+    pub fn finished(&self) -> usize {
+        self.total - self.queue.len() - self.running
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.queue.is_empty() && self.running == 0
+    }
+
+    /// One-line progress or result for the status bar
+    pub fn summary(&self) -> String {
+        if !self.is_done() {
+            return format!(
+                "foreach {}: {}/{} files done, {} running",
+                self.pattern,
+                self.finished(),
+                self.total,
+                self.running
+            );
+        }
+        let mut summary = format!(
+            "foreach {}: {} files changed from {} runs",
+            self.pattern,
+            self.changes.len(),
+            self.total
+        );
+        if !self.failures.is_empty() {
+            summary.push_str(&format!("; {} problems", self.failures.len()));
+        }
+        summary
+    }
+
+    /// The per-file problems, one per line
+    pub fn failure_report(&self) -> String {
+        self.failures
+            .iter()
+            .map(|(file, reason)| format!("{}: {}", file.display(), reason))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::ChangeType;
+
+    fn change(path: &str) -> FileChange {
+        FileChange {
+            path: PathBuf::from(path),
+            original_content: Some("old\n".to_string()),
+            proposed_content: "new\n".to_string(),
+            change_type: ChangeType::Modify,
+            renamed_from: None,
+        }
+    }
+
+    #[test]
+    fn test_batch_queues_with_limit_and_combines_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        for file in ["src/a.rs", "src/nested/b.rs", "src/c.txt", "d.rs"] {
+            std::fs::write(dir.path().join(file), "").unwrap();
+        }
+        let files = matching_files(dir.path(), "src/**/*.rs").unwrap();
+        assert_eq!(
+            files,
+            vec![PathBuf::from("src/a.rs"), PathBuf::from("src/nested/b.rs")]
+        );
+
+        let mut batch = BatchRun::new("*.rs", "add docs", vec!["a".into(), "b".into(), "c".into()]);
+        assert_eq!(batch.next_file(2), Some(PathBuf::from("a")));
+        assert_eq!(batch.next_file(2), Some(PathBuf::from("b")));
+        assert_eq!(batch.next_file(2), None);
+
+        batch.finish(Path::new("a"), Ok(vec![change("a"), change("shared")]));
+        batch.finish(Path::new("b"), Err("exit 1".to_string()));
+        assert_eq!(batch.next_file(2), Some(PathBuf::from("c")));
+        batch.finish(Path::new("c"), Ok(vec![change("c"), change("shared")]));
+
+        assert!(batch.is_done());
+        let paths: Vec<_> = batch.changes.iter().map(|c| c.path.clone()).collect();
+        assert_eq!(paths, vec![PathBuf::from("a"), "shared".into(), "c".into()]);
+        assert_eq!(batch.failures.len(), 2);
+        assert_eq!(
+            batch.summary(),
+            "foreach *.rs: 3 files changed from 3 runs; 2 problems"
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! Per-session chat logs
+//!
+//! Each session's messages are appended, one JSON line per message, to
+//! `chats/<session id>.jsonl` as they arrive instead of being rewritten with
+//! `sessions.json` on every save. Only the newest page is loaded at startup;
+//! older pages are read on demand when the chat is scrolled past the top.
+//! Logs of sessions other than the current one are compacted to their newest
+//! messages so the directory stays bounded. `:grep` streams through all the
+//! logs to find messages across sessions.
+
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::file_ops::atomic_write;
+use crate::state::ChatMessage;
+
+/// Messages loaded at startup and per "load older" step
+pub const PAGE_SIZE: usize = 200;
+
+/// Messages kept when an old session's log is compacted
+pub const COMPACT_KEEP: usize = 1000;
+
+/// Where the chat logs are kept
+pub fn chats_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("chats")
+}
+
+/// The log of session `session_id` in `dir`
+pub fn log_path(dir: &Path, session_id: &str) -> PathBuf {
+    dir.join(format!("{}.jsonl", session_id))
+}
+
+/// A run of consecutive messages from a log
+#[derive(Debug, Default)]
+pub struct Page {
+    pub messages: Vec<ChatMessage>,
+    /// Whether the log has messages before this page
+    pub more: bool,
+}
+
+/// Add `message` to the end of the log at `path`
+pub fn append(path: &Path, message: &ChatMessage) -> Result<()> {
+    append_all(path, std::slice::from_ref(message))
+}
+
+/// Add `messages` to the end of the log at `path`
+pub fn append_all(path: &Path, messages: &[ChatMessage]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let _lock = crate::lock::lock_exclusive(path)?;
+    let mut text = String::new();
+    for message in messages {
+        text.push_str(&serde_json::to_string(message)?);
+        text.push('\n');
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(text.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Up to `limit` of the newest messages in the log at `path` with an id below
+/// `before` (all of them when `None`), oldest first. Lines that don't parse are
+/// skipped.
+pub fn load_page(path: &Path, before: Option<usize>, limit: usize) -> Result<Page> {
+    let mut messages = read(path)?;
+    if let Some(before) = before {
+        messages.retain(|m| m.id < before);
+    }
+    let start = messages.len().saturating_sub(limit);
+    Ok(Page {
+        more: start > 0,
+        messages: messages.split_off(start),
+    })
+}
+
+fn read(path: &Path) -> Result<Vec<ChatMessage>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let _lock = crate::lock::lock_shared(path)?;
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// A message of some session's log that matched a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub session_id: String,
+    pub message_id: usize,
+    pub is_user: bool,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// The first matching line of the message, cut around the match
+    pub snippet: String,
+}
+
+/// Messages of every log in `dir` that contain `query`, ignoring case; at most
+/// `limit` of them, in log order per session. The logs are read a line at a
+/// time, so large histories are not loaded whole.
+pub fn search(dir: &Path, query: &str, limit: usize) -> Result<Vec<Hit>> {
+    let needle = query.to_lowercase();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut logs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    logs.sort();
+
+    let mut hits = Vec::new();
+    for path in logs {
+        let Some(session_id) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let _lock = crate::lock::lock_shared(&path)?;
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        for line in BufReader::new(file).lines() {
+            let Ok(message) = serde_json::from_str::<ChatMessage>(&line?) else {
+                continue;
+            };
+            let Some(snippet) = snippet(&message.content, &needle) else {
+                continue;
+            };
+            hits.push(Hit {
+                session_id: session_id.clone(),
+                message_id: message.id,
+                is_user: message.is_user,
+                timestamp: message.timestamp,
+                snippet,
+            });
+            if hits.len() == limit {
+                return Ok(hits);
+            }
+        }
+    }
+    Ok(hits)
+}
+
+/// Characters of context kept before a match in a snippet
+const SNIPPET_LEAD: usize = 30;
+
+/// Characters a snippet is cut to
+const SNIPPET_WIDTH: usize = 100;
+
+/// The first line of `content` containing `needle` (lowercase), cut so the
+/// match is in view
+fn snippet(content: &str, needle: &str) -> Option<String> {
+    let line = content
+        .lines()
+        .find(|line| line.to_lowercase().contains(needle))?
+        .trim();
+    let lower = line.to_lowercase();
+    let at = lower[..lower.find(needle)?].chars().count();
+    let skip = at.saturating_sub(SNIPPET_LEAD);
+    let mut cut: String = line.chars().skip(skip).take(SNIPPET_WIDTH).collect();
+    if skip > 0 {
+        cut.insert(0, '…');
+    }
+    if line.chars().count() > skip + SNIPPET_WIDTH {
+        cut.push('…');
+    }
+    Some(cut)
+}
+
+/// Trim every log in `dir` except `current`'s to its newest `keep` messages.
+/// Returns how many logs were trimmed.
+pub fn compact(dir: &Path, keep: usize, current: Option<&str>) -> Result<usize> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(0);
+    };
+    let current = current.map(|id| log_path(dir, id));
+    let mut compacted = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "jsonl") || Some(&path) == current.as_ref() {
+            continue;
+        }
+
+        let _lock = crate::lock::lock_exclusive(&path)?;
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.len() <= keep {
+            continue;
+        }
+        let mut kept = lines[lines.len() - keep..].join("\n");
+        kept.push('\n');
+        atomic_write(&path, &kept)?;
+        compacted += 1;
+    }
+    Ok(compacted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::MessageStatus;
+
+    fn message(id: usize) -> ChatMessage {
+        ChatMessage {
+            id,
+            timestamp: chrono::Utc::now(),
+            is_user: id % 2 == 1,
+            content: format!("message {}", id),
+            token_count: None,
+            cost: None,
+            status: MessageStatus::Success,
+            associated_files: vec![],
+            apply_summary: None,
+        }
+    }
+
+    fn ids(messages: &[ChatMessage]) -> Vec<usize> {
+        messages.iter().map(|m| m.id).collect()
+    }
+
+    #[test]
+    fn test_pages_load_newest_first_and_compact_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = log_path(dir.path(), "old");
+        for id in 1..=5 {
+            append(&path, &message(id)).unwrap();
+        }
+
+        let latest = load_page(&path, None, 2).unwrap();
+        assert_eq!(ids(&latest.messages), vec![4, 5]);
+        assert!(latest.more);
+        let older = load_page(&path, Some(4), 2).unwrap();
+        assert_eq!(ids(&older.messages), vec![2, 3]);
+        let oldest = load_page(&path, Some(2), 2).unwrap();
+        assert_eq!(ids(&oldest.messages), vec![1]);
+        assert!(!oldest.more);
+
+        // The current session's log is left alone
+        let current = log_path(dir.path(), "current");
+        append_all(&current, &[message(1), message(2), message(3)]).unwrap();
+        assert_eq!(compact(dir.path(), 2, Some("current")).unwrap(), 1);
+        assert_eq!(
+            ids(&load_page(&path, None, 10).unwrap().messages),
+            vec![4, 5]
+        );
+        assert_eq!(load_page(&current, None, 10).unwrap().messages.len(), 3);
+
+        assert!(load_page(&log_path(dir.path(), "missing"), None, 10)
+            .unwrap()
+            .messages
+            .is_empty());
+    }
+
+    #[test]
+    fn test_search_finds_messages_across_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut first = message(1);
+        first.content = "Fix the parser\nIt drops the Trailing newline".to_string();
+        let mut second = message(2);
+        second.content = format!("{}trailing newline at the end", "x".repeat(60));
+        append_all(&log_path(dir.path(), "a"), &[first, message(3)]).unwrap();
+        append_all(&log_path(dir.path(), "b"), &[message(4), second]).unwrap();
+
+        let hits = search(dir.path(), "TRAILING newline", 10).unwrap();
+        let found: Vec<_> = hits
+            .iter()
+            .map(|h| (h.session_id.as_str(), h.message_id))
+            .collect();
+        assert_eq!(found, vec![("a", 1), ("b", 2)]);
+        assert_eq!(hits[0].snippet, "It drops the Trailing newline");
+        assert!(hits[1].snippet.starts_with('…'));
+        assert!(hits[1].snippet.ends_with("trailing newline at the end"));
+
+        assert_eq!(search(dir.path(), "newline", 1).unwrap().len(), 1);
+        assert!(search(dir.path(), "absent", 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+//! Headless checks for CI
+//!
+//! `zcode check` takes a provider's reply (a file, stdin, or a prompt it runs
+//! itself) through the parsing and validation a review starts with, then
+//! checks every file it would write against `.zcodeignore`, the guard rules and
+//! the syntax check. Nothing is written; a JSON report goes to stdout and the
+//! exit code says whether the changes are fit to hand to a reviewer.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::file_ops::WritePolicy;
+use crate::guard::Guard;
+use crate::providers::AIProvider;
+use crate::sandbox::{Sandbox, SandboxProfile};
+use crate::state::{ChangeTag, ChangeType, FileChange, PromptMode, PromptRequest};
+
+/// Exit code when every change passed
+pub const EXIT_PASSED: i32 = 0;
+/// Exit code when a change has problems
+pub const EXIT_PROBLEMS: i32 = 1;
+/// Exit code when there was nothing to check: the provider failed, or its
+/// reply held no file changes
+pub const EXIT_UNCHECKED: i32 = 2;
+
+/// What `zcode check` prints
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    /// Whether every change passed
+    pub ok: bool,
+    pub provider: Option<String>,
+    /// Why nothing could be checked
+    pub error: Option<String>,
+    /// What validation kept and dropped, e.g. "3 changes, 1 skipped as no-op"
+    pub summary: String,
+    pub files: Vec<FileReport>,
+}
+
+/// One file the changes would write
+#[derive(Debug, Serialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub change: &'static str,
+    pub hunks: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub problems: Vec<Finding>,
+}
+
+/// A reason not to apply a file as it is
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    /// `ignored`, `guard`, `syntax`, `conflict` or `extension`
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl Report {
+    /// A report for a reply that couldn't be checked
+    fn unchecked(provider: Option<String>, error: String) -> Self {
+        Self {
+            provider,
+            error: Some(error),
+            ..Self::default()
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match (&self.error, self.ok) {
+            (Some(_), _) => EXIT_UNCHECKED,
+            (None, true) => EXIT_PASSED,
+            (None, false) => EXIT_PROBLEMS,
+        }
+    }
+}
+
+/// `zcode check [--provider NAME] [--prompt TEXT | --input FILE]`: check a
+/// reply and print the report. Returns the exit code.
+pub async fn run(args: &[String]) -> Result<i32> {
+    let mut provider_name = None;
+    let mut prompt = None;
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| anyhow!("{} needs a value", flag))
+        };
+        match arg.as_str() {
+            "--provider" => provider_name = Some(value("--provider")?),
+            "--prompt" => prompt = Some(value("--prompt")?),
+            "--input" => input = Some(value("--input")?),
+            other => return Err(anyhow!("Unknown option for zcode check: {}", other)),
+        }
+    }
+
+    let config = Config::load().unwrap_or_default();
+    let root = std::env::current_dir().context("Failed to get current directory")?;
+    let provider_name = provider_name.or_else(|| config.general.default_provider.clone());
+    let provider = match &provider_name {
+        Some(name) => Some(
+            crate::providers::create_provider(name, config.providers.get(name))
+                .ok_or_else(|| anyhow!("Unknown provider: {}", name))?,
+        ),
+        None => None,
+    };
+
+    let report = match (prompt, input) {
+        (Some(_), Some(_)) => return Err(anyhow!("Pass either --prompt or --input, not both")),
+        (Some(prompt), None) => {
+            let provider = provider
+                .as_deref()
+                .ok_or_else(|| anyhow!("--prompt needs --provider or a default_provider"))?;
+            let sandbox = provider_name
+                .as_ref()
+                .and_then(|name| config.providers.get(name))
+                .and_then(|c| c.sandbox);
+            match run_prompt(provider, prompt, &root, sandbox).await {
+                Ok((output, edited)) => {
+                    check_output(&output, edited, Some(provider), &config, &root)
+                }
+                Err(e) => Report::unchecked(provider_name, format!("{:#}", e)),
+            }
+        }
+        (None, input) => {
+            let output = read_input(input.as_deref())?;
+            check_output(&output, Vec::new(), provider.as_deref(), &config, &root)
+        }
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(report.exit_code())
+}
+
+/// The reply to check: the file, or stdin for `-` or no file
+fn read_input(path: Option<&str>) -> Result<String> {
+    match path {
+        Some(path) if path != "-" => std::fs::read(path)
+            .map(|bytes| crate::encoding::decode_marked(&bytes))
+            .with_context(|| format!("Failed to read {}", path)),
+        _ => {
+            if atty::is(atty::Stream::Stdin) {
+                return Err(anyhow!(
+                    "Nothing to check: pass --prompt, --input FILE, or pipe a reply in"
+                ));
+            }
+            let mut input = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut input)
+                .context("Failed to read stdin")?;
+            Ok(crate::encoding::decode_marked(&input))
+        }
+    }
+}
+
+/// Run `prompt` through `provider` in `root` (or a sandbox of it) and return
+/// its output, with the files it edited in the sandbox
+async fn run_prompt(
+    provider: &dyn AIProvider,
+    prompt: String,
+    root: &Path,
+    sandbox: Option<SandboxProfile>,
+) -> Result<(String, Vec<FileChange>)> {
+    let sandbox = sandbox
+        .map(|profile| Sandbox::create(root, profile))
+        .transpose()?;
+    let dir = sandbox.as_ref().map(Sandbox::path);
+    let request = PromptRequest {
+        prompt,
+        context_files: Vec::new(),
+        session_id: None,
+        working_directory: dir.unwrap_or(root).to_path_buf(),
+        preamble: None,
+        mode: PromptMode::Edit,
+    };
+    let result = crate::executor::execute_provider_prompt(
+        &provider.command_line(&request),
+        provider.name(),
+        provider.output_source().capture_mode(),
+        None,
+        dir,
+    )
+    .await?;
+    match result.exit_code {
+        Some(0) => {
+            let edited = match &sandbox {
+                Some(sandbox) => sandbox.changes()?,
+                None => Vec::new(),
+            };
+            Ok((result.output_marked(provider.output_source()), edited))
+        }
+        code => {
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            let reason = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+            Err(anyhow!(
+                "{} exited with {}: {}",
+                provider.name(),
+                code.unwrap_or(-1),
+                reason.trim()
+            ))
+        }
+    }
+}
+
+/// Parse `output` with the provider's parser (a unified diff, then fenced code
+/// blocks, without one), add the files `edited` in a sandbox, and check each
+/// change against the project in `root`
+pub fn check_output(
+    output: &str,
+    edited: Vec<FileChange>,
+    provider: Option<&dyn AIProvider>,
+    config: &Config,
+    root: &Path,
+) -> Report {
+    let provider_name = provider.map(|p| p.name().to_string());
+    let parsed = match provider {
+        Some(provider) => provider.parse_file_changes(output),
+        None => crate::parsers::parse_unified_diff(output).and_then(|changes| {
+            if changes.is_empty() {
+                crate::parsers::parse_code_blocks(output)
+            } else {
+                Ok(changes)
+            }
+        }),
+    };
+    let changes = match parsed {
+        Ok(changes) => crate::sandbox::merge_changes(changes, edited),
+        Err(e) => {
+            return Report::unchecked(provider_name, format!("Could not parse the reply: {:#}", e))
+        }
+    };
+
+    let mut validation = crate::parsers::validate_changes(changes);
+    let reply = provider.map_or_else(|| output.to_string(), |p| p.response_text(output));
+    let notes = crate::parsers::check_extensions(&mut validation.changes, &reply);
+    if validation.changes.is_empty() {
+        return Report::unchecked(
+            provider_name,
+            format!("No file changes in the reply ({})", validation.summary()),
+        );
+    }
+
+    let policy = WritePolicy::load(root);
+    let guard = Guard::new(root, &config.guard.rules);
+    let mut files = Vec::new();
+    for change in &validation.changes {
+        let original = match change.change_type {
+            ChangeType::Create => None,
+            _ => change
+                .original_content
+                .clone()
+                .or_else(|| std::fs::read_to_string(root.join(&change.path)).ok()),
+        };
+        let proposed = match change.change_type {
+            ChangeType::Delete => "",
+            _ => change.proposed_content.as_str(),
+        };
+        let hunks =
+            crate::diff::compute_hunks(&change.path, original.as_deref().unwrap_or(""), proposed);
+        let count = |tag: ChangeTag| {
+            hunks
+                .iter()
+                .flat_map(|h| &h.changes)
+                .filter(|c| c.tag == tag)
+                .count()
+        };
+
+        let mut problems = Vec::new();
+        let mut flag = |kind, message| problems.push(Finding { kind, message });
+        match &policy {
+            Ok(policy) if policy.is_ignored(&change.path) => flag(
+                "ignored",
+                format!("ignored by {}", crate::file_ops::policy::IGNORE_FILE),
+            ),
+            Ok(_) => {}
+            Err(e) => flag("ignored", format!("{:#}", e)),
+        }
+        match &guard {
+            Ok(guard) => {
+                let mut rules: Vec<String> = hunks.iter().flat_map(|h| guard.check(h)).collect();
+                rules.dedup();
+                for rule in rules {
+                    flag("guard", rule);
+                }
+            }
+            Err(e) => flag("guard", format!("{:#}", e)),
+        }
+        if change.change_type != ChangeType::Delete {
+            let path = root.join(&change.path);
+            if let Some(problem) = crate::syntax::check_file(
+                &path,
+                original.as_deref(),
+                proposed,
+                &config.syntax_check,
+            ) {
+                flag("syntax", problem.message);
+            }
+        }
+        if let Some((_, alternatives)) = validation
+            .conflicts
+            .iter()
+            .find(|(path, _)| *path == change.path)
+        {
+            flag(
+                "conflict",
+                format!("{} overlapping edits to pick from", alternatives.len()),
+            );
+        }
+        for (_, note) in notes.iter().filter(|(path, _)| *path == change.path) {
+            flag("extension", note.clone());
+        }
+
+        files.push(FileReport {
+            path: change.path.clone(),
+            change: match change.change_type {
+                ChangeType::Create => "create",
+                ChangeType::Modify => "modify",
+                ChangeType::Delete => "delete",
+            },
+            hunks: hunks.len(),
+            added: count(ChangeTag::Insert),
+            removed: count(ChangeTag::Delete),
+            problems,
+        });
+    }
+
+    Report {
+        ok: files.iter().all(|f| f.problems.is_empty()),
+        provider: provider_name,
+        error: None,
+        summary: validation.summary(),
+        files,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_output_reports_problems_per_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".zcodeignore"), "vendor/\n").unwrap();
+        std::fs::write(dir.path().join("app.json"), "{\"a\": 1}\n").unwrap();
+        let config = Config::default();
+
+        let patch = "--- a/app.json\n+++ b/app.json\n@@ -1 +1 @@\n-{\"a\": 1}\n+{\"a\": 2}\n";
+        let report = check_output(patch, Vec::new(), None, &config, dir.path());
+        assert!(report.ok, "{:?}", report);
+        assert_eq!(report.exit_code(), EXIT_PASSED);
+        assert_eq!(report.files[0].change, "modify");
+        assert_eq!((report.files[0].added, report.files[0].removed), (1, 1));
+
+        let patch = "--- a/app.json\n+++ b/app.json\n@@ -1 +1 @@\n-{\"a\": 1}\n+{\"a\": \n\
+                     --- /dev/null\n+++ b/vendor/lib.js\n@@ -0,0 +1 @@\n+x\n";
+        let report = check_output(patch, Vec::new(), None, &config, dir.path());
+        assert_eq!(report.exit_code(), EXIT_PROBLEMS);
+        let kinds: Vec<Vec<&str>> = report
+            .files
+            .iter()
+            .map(|f| f.problems.iter().map(|p| p.kind).collect())
+            .collect();
+        assert_eq!(kinds, [vec!["syntax"], vec!["ignored"]]);
+
+        let report = check_output(
+            "Sorry, I can't help with that.",
+            Vec::new(),
+            None,
+            &config,
+            dir.path(),
+        );
+        assert_eq!(report.exit_code(), EXIT_UNCHECKED);
+        assert!(report.error.is_some());
+    }
+}
@@ -0,0 +1,179 @@
+//! The review checklist
+//!
+//! The `[[checklist.items]]` of the config ("tests updated", "no secrets
+//! added") are listed in the apply confirmation. An item with an `auto`
+//! heuristic is checked when the accepted hunks pass it, and any item can be
+//! toggled by its number. With `required = true` applying waits until every
+//! item is checked.
+
+use regex::Regex;
+use std::path::Path;
+
+use crate::config::{ChecklistCheck, ChecklistConfig};
+use crate::state::{ChangeTag, Hunk};
+
+/// Added lines that look like credentials
+const SECRET: &str = r#"AKIA[0-9A-Z]{16}|-----BEGIN [A-Z ]*PRIVATE KEY-----|gh[pousr]_[A-Za-z0-9]{36}|xox[abprs]-[A-Za-z0-9-]{10,}|(?i:(api[_-]?key|secret|passw(or)?d|token)["']?\s*[:=]\s*["'][^"'\s]{8,}["'])"#;
+
+/// Changed lines that declare public API in the common languages
+const PUBLIC_API: &str = r"^\s*(pub (async )?(fn|struct|enum|trait|type|const|mod) |export |public |func (\([^)]*\) )?[A-Z]|def [a-zA-Z]|class )";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub label: String,
+    pub auto: Option<ChecklistCheck>,
+    pub checked: bool,
+    /// Toggled by hand, so the heuristic no longer decides
+    pub manual: bool,
+}
+
+/// Bring `entries` up to date with `config` and the accepted `hunks`. Items
+/// toggled by hand keep their state while the config lists the same items.
+pub fn refresh(entries: &mut Vec<Entry>, config: &ChecklistConfig, hunks: &[&Hunk]) {
+    let same_items = entries.len() == config.items.len()
+        && entries
+            .iter()
+            .zip(&config.items)
+            .all(|(entry, item)| entry.label == item.label && entry.auto == item.auto);
+    if !same_items {
+        *entries = config
+            .items
+            .iter()
+            .map(|item| Entry {
+                label: item.label.clone(),
+                auto: item.auto,
+                checked: false,
+                manual: false,
+            })
+            .collect();
+    }
+    for entry in entries.iter_mut().filter(|e| !e.manual) {
+        entry.checked = entry.auto.is_some_and(|check| passes(check, hunks));
+    }
+}
+
+/// Check or uncheck item `index`, returning its new state
+pub fn toggle(entries: &mut [Entry], index: usize) -> Option<bool> {
+    let entry = entries.get_mut(index)?;
+    entry.checked = !entry.checked;
+    entry.manual = true;
+    Some(entry.checked)
+}
+
+/// Whether the accepted `hunks` pass `check`
+pub fn passes(check: ChecklistCheck, hunks: &[&Hunk]) -> bool {
+    let changed = |tag: ChangeTag| {
+        hunks
+            .iter()
+            .flat_map(|h| &h.changes)
+            .filter(move |c| c.tag == tag)
+            .map(|c| c.content.as_str())
+    };
+    match check {
+        ChecklistCheck::TouchesTests => {
+            hunks.iter().any(|h| is_test_file(&h.file_path))
+                || changed(ChangeTag::Insert).any(|l| l.trim().starts_with("#[test]"))
+        }
+        ChecklistCheck::NoSecrets => {
+            let secret = Regex::new(SECRET).expect("valid regex");
+            !changed(ChangeTag::Insert).any(|l| secret.is_match(l))
+        }
+        ChecklistCheck::DocsIfApi => {
+            let api = Regex::new(PUBLIC_API).expect("valid regex");
+            hunks.iter().any(|h| is_doc_file(&h.file_path))
+                || !changed(ChangeTag::Insert)
+                    .chain(changed(ChangeTag::Delete))
+                    .any(|l| api.is_match(l))
+        }
+    }
+}
+
+fn is_test_file(path: &Path) -> bool {
+    let in_test_dir = path.components().any(|c| {
+        matches!(
+            c.as_os_str().to_str(),
+            Some("tests" | "test" | "__tests__" | "spec")
+        )
+    });
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let stem = name.split('.').next().unwrap_or("");
+    in_test_dir
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+}
+
+fn is_doc_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    path.components()
+        .any(|c| matches!(c.as_os_str().to_str(), Some("docs" | "doc")))
+        || name.starts_with("README")
+        || name.starts_with("CHANGELOG")
+        || [".md", ".rst", ".adoc"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChecklistItem;
+    use crate::state::{HunkRange, HunkStatus, LineChange};
+    use std::path::PathBuf;
+
+    fn hunk(path: &str, added: &str) -> Hunk {
+        Hunk {
+            id: 0,
+            file_path: PathBuf::from(path),
+            old: HunkRange::default(),
+            new: HunkRange::default(),
+            changes: vec![LineChange {
+                tag: ChangeTag::Insert,
+                content: format!("{}\n", added).into(),
+                old_line_num: None,
+                new_line_num: None,
+            }],
+            status: HunkStatus::Accepted,
+            drifted: false,
+            comment: None,
+            auto_accepted: None,
+        }
+    }
+
+    #[test]
+    fn test_heuristics_check_items_until_toggled() {
+        let item = |label: &str, auto| ChecklistItem {
+            label: label.to_string(),
+            auto: Some(auto),
+        };
+        let config = ChecklistConfig {
+            required: true,
+            items: vec![
+                item("Tests updated", ChecklistCheck::TouchesTests),
+                item("No secrets added", ChecklistCheck::NoSecrets),
+                item("Docs touched if API changed", ChecklistCheck::DocsIfApi),
+            ],
+        };
+        let api = hunk("src/lib.rs", "pub fn parse(input: &str) -> Ast {");
+        let key = hunk("src/config.ts", r#"const apiKey = "sk-live-12345678abcd";"#);
+        let mut entries = Vec::new();
+
+        refresh(&mut entries, &config, &[&api, &key]);
+        let checked: Vec<bool> = entries.iter().map(|e| e.checked).collect();
+        assert_eq!(checked, [false, false, false]);
+
+        let test = hunk("tests/parse.rs", "assert!(parse(\"\").is_empty());");
+        let docs = hunk("README.md", "Call `parse` on the input.");
+        refresh(&mut entries, &config, &[&api, &test, &docs]);
+        let checked: Vec<bool> = entries.iter().map(|e| e.checked).collect();
+        assert_eq!(checked, [true, true, true]);
+
+        // A hand toggle sticks when the hunks change
+        assert_eq!(toggle(&mut entries, 0), Some(false));
+        refresh(&mut entries, &config, &[&test]);
+        assert!(!entries[0].checked);
+        assert_eq!(toggle(&mut entries, 3), None);
+    }
+}
@@ -0,0 +1,49 @@
+//! Copying text to the system clipboard
+//!
+//! The native clipboard is tried first. Without one (SSH sessions, headless
+//! Linux) the text is sent with the OSC 52 escape sequence, which most
+//! terminals forward to the clipboard of the machine the user sits at.
+
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+
+/// How the text reached the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyMethod {
+    Native,
+    Terminal,
+}
+
+/// Copy `text` to the clipboard
+pub fn copy(text: &str) -> Result<CopyMethod> {
+    if arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .is_ok()
+    {
+        return Ok(CopyMethod::Native);
+    }
+
+    let mut stdout = std::io::stdout();
+    stdout
+        .write_all(osc52(text).as_bytes())
+        .and_then(|_| stdout.flush())
+        .context("Failed to write to the terminal")?;
+    Ok(CopyMethod::Terminal)
+}
+
+fn osc52(text: &str) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    format!("\x1b]52;c;{}\x07", encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52("hi"), "\x1b]52;c;aGk=\x07");
+    }
+}
@@ -1,4 +1,4 @@
-use ratatui::{layout::Rect, Frame};
+use ratatui::{layout::Rect, style::Style, Frame};
 
 use crate::components::Component;
 use crate::model::AppModel;
@@ -10,16 +10,27 @@ impl ChatPanel {
     pub fn new() -> Self {
         Self
     }
+
+    /// Render with `border` around the pane, e.g. the focused border
+    pub fn view_with_border(&self, frame: &mut Frame, area: Rect, model: &AppModel, border: Style) {
+        let history = &model.state.chat_history;
+        if history.messages.is_empty() {
+            crate::ui::session_turn::render_empty_chat(frame, area, &model.theme, border);
+        } else {
+            crate::ui::session_turn::render_session_turns(
+                frame,
+                area,
+                history,
+                &model.theme,
+                border,
+            );
+        }
+    }
 }
 
 impl Component for ChatPanel {
     fn view(&self, frame: &mut Frame, area: Rect, model: &AppModel) {
-        let messages = &model.state.chat_history.messages;
-        if messages.is_empty() {
-            crate::ui::session_turn::render_empty_chat(frame, area, &model.theme);
-        } else {
-            crate::ui::session_turn::render_session_turns(frame, area, messages, &model.theme);
-        }
+        self.view_with_border(frame, area, model, model.theme.border_style);
     }
 }
 
@@ -18,15 +18,21 @@ impl Component for CommandPalette {
         let text = format!(":{}", model.state.command_buffer);
         use ratatui::style::Style;
         use ratatui::widgets::{Block, BorderType, Borders, Paragraph};
+        use unicode_width::UnicodeWidthStr;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(model.theme.border_style)
+            .title(" Command ");
+        let inner = block.inner(area);
+        // The command is typed at its end, after what scrolls off the left
+        let width = UnicodeWidthStr::width(text.as_str());
+        let skip = (width + 1).saturating_sub(inner.width as usize);
         let paragraph = Paragraph::new(text)
             .style(Style::default().fg(ratatui::style::Color::White))
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Rounded)
-                    .border_style(model.theme.border_style)
-                    .title(" Command "),
-            );
+            .scroll((0, skip as u16))
+            .block(block);
         frame.render_widget(paragraph, area);
+        frame.set_cursor_position(crate::ui::cursor::position(inner, 0, 0, width - skip));
     }
 }
@@ -1,4 +1,4 @@
-use ratatui::{layout::Rect, Frame};
+use ratatui::{layout::Rect, style::Style, Frame};
 
 use crate::components::Component;
 use crate::model::AppModel;
@@ -9,15 +9,21 @@ impl DiffView {
     pub fn new() -> Self {
         Self
     }
-}
 
-impl Component for DiffView {
-    fn view(&self, frame: &mut Frame, area: Rect, model: &AppModel) {
+    /// Render with `border` around the pane, e.g. the focused border
+    pub fn view_with_border(&self, frame: &mut Frame, area: Rect, model: &AppModel, border: Style) {
         crate::ui::overlay_diff::render_overlay_diff(
             frame,
             area,
             &model.state.overlay_diff_state,
             &model.theme,
+            border,
         );
     }
 }
+
+impl Component for DiffView {
+    fn view(&self, frame: &mut Frame, area: Rect, model: &AppModel) {
+        self.view_with_border(frame, area, model, model.theme.border_style);
+    }
+}
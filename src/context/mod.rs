@@ -0,0 +1,279 @@
+//! Prompt context attachments and token budgeting
+//!
+//! Files attached with `:attach` are sent along with the next prompt. Token counts
+//! are estimated (roughly four characters per token) and, when the prompt plus its
+//! attachments would exceed the provider's limit, the largest files are truncated
+//! first so every attachment keeps a fair share of the budget.
+//!
+//! [`summary`] describes the working directory for providers that don't read
+//! the repository themselves.
+
+pub mod summary;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Token limit used when a provider has no `context_token_limit` configured
+pub const DEFAULT_CONTEXT_TOKEN_LIMIT: usize = 32_000;
+
+/// How oversized attachments are cut down to fit the budget
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    /// Keep the beginning and end of the file
+    #[default]
+    HeadTail,
+    /// Keep definition lines (functions, types, classes) as an outline
+    Symbols,
+}
+
+/// A file attached to the prompt, with its estimated size
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextAttachment {
+    pub path: PathBuf,
+    /// 1-based inclusive line range when only a snippet is attached
+    pub lines: Option<(usize, usize)>,
+    pub content: String,
+    pub tokens: usize,
+}
+
+impl ContextAttachment {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read context file {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            lines: None,
+            tokens: estimate_tokens(&content),
+            content,
+        })
+    }
+
+    /// `path`, or `path:start-end` for a snippet
+    pub fn label(&self) -> String {
+        label(&self.path, self.lines)
+    }
+}
+
+fn label(path: &Path, lines: Option<(usize, usize)>) -> String {
+    match lines {
+        Some((start, end)) => format!("{}:{}-{}", path.display(), start, end),
+        None => path.display().to_string(),
+    }
+}
+
+/// An attachment after budgeting, ready to embed in a prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetedContext {
+    pub path: PathBuf,
+    pub lines: Option<(usize, usize)>,
+    pub content: String,
+    pub tokens: usize,
+    pub truncated: bool,
+}
+
+/// Rough token estimate (~4 characters per token)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Format a token count compactly, e.g. `950` or `12.3k`
+pub fn format_tokens(tokens: usize) -> String {
+    if tokens < 1000 {
+        tokens.to_string()
+    } else {
+        format!("{:.1}k", tokens as f64 / 1000.0)
+    }
+}
+
+/// Fit attachments into `budget` tokens.
+///
+/// Small files are kept whole; the budget left over is split evenly between the
+/// files that don't fit, which are truncated with `strategy`. Output order matches
+/// the input.
+pub fn fit_to_budget(
+    attachments: &[ContextAttachment],
+    budget: usize,
+    strategy: TruncationStrategy,
+) -> Vec<BudgetedContext> {
+    let mut order: Vec<usize> = (0..attachments.len()).collect();
+    order.sort_by_key(|&i| attachments[i].tokens);
+
+    let mut fitted: Vec<Option<BudgetedContext>> = vec![None; attachments.len()];
+    let mut remaining = budget;
+
+    for (rank, &idx) in order.iter().enumerate() {
+        let attachment = &attachments[idx];
+        let share = remaining / (order.len() - rank);
+
+        let budgeted = if attachment.tokens <= share {
+            BudgetedContext {
+                path: attachment.path.clone(),
+                lines: attachment.lines,
+                content: attachment.content.clone(),
+                tokens: attachment.tokens,
+                truncated: false,
+            }
+        } else {
+            let content = truncate_to_tokens(&attachment.content, share, strategy);
+            BudgetedContext {
+                path: attachment.path.clone(),
+                lines: attachment.lines,
+                tokens: estimate_tokens(&content),
+                content,
+                truncated: true,
+            }
+        };
+
+        remaining = remaining.saturating_sub(budgeted.tokens);
+        fitted[idx] = Some(budgeted);
+    }
+
+    fitted.into_iter().flatten().collect()
+}
+
+/// Cut `content` down to roughly `max_tokens`
+pub fn truncate_to_tokens(
+    content: &str,
+    max_tokens: usize,
+    strategy: TruncationStrategy,
+) -> String {
+    if estimate_tokens(content) <= max_tokens {
+        return content.to_string();
+    }
+
+    match strategy {
+        TruncationStrategy::HeadTail => head_tail(content, max_tokens),
+        TruncationStrategy::Symbols => {
+            let outline = symbol_outline(content);
+            if outline.is_empty() {
+                head_tail(content, max_tokens)
+            } else {
+                head_tail(&outline, max_tokens)
+            }
+        }
+    }
+}
+
+fn head_tail(content: &str, max_tokens: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    // Leave room for the omission marker
+    let half = max_tokens.saturating_sub(8) / 2;
+
+    let mut head = Vec::new();
+    let mut used = 0;
+    for line in &lines {
+        let cost = estimate_tokens(line) + 1;
+        if used + cost > half {
+            break;
+        }
+        used += cost;
+        head.push(*line);
+    }
+
+    let mut tail = Vec::new();
+    used = 0;
+    for line in lines[head.len()..].iter().rev() {
+        let cost = estimate_tokens(line) + 1;
+        if used + cost > half {
+            break;
+        }
+        used += cost;
+        tail.push(*line);
+    }
+    tail.reverse();
+
+    let omitted = lines.len() - head.len() - tail.len();
+    let mut out = head.join("\n");
+    if omitted > 0 {
+        out.push_str(&format!("\n… {} lines omitted …\n", omitted));
+    }
+    out.push_str(&tail.join("\n"));
+    out
+}
+
+/// Definition lines for common languages, used as a compact outline
+fn symbol_outline(content: &str) -> String {
+    let Ok(re) = Regex::new(
+        r"^\s*(pub(\([^)]*\))?\s+)?(async\s+)?(fn|struct|enum|trait|impl|mod|type|const|class|def|interface|export|function|func)\b",
+    ) else {
+        return String::new();
+    };
+
+    content
+        .lines()
+        .filter(|line| re.is_match(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render budgeted attachments as a block appended to the prompt
+pub fn render_context_block(contexts: &[BudgetedContext]) -> String {
+    let mut block = String::from("\n\nContext files:\n");
+    for ctx in contexts {
+        let note = if ctx.truncated { " (truncated)" } else { "" };
+        block.push_str(&format!(
+            "\n--- {}{} ---\n{}\n",
+            label(&ctx.path, ctx.lines),
+            note,
+            ctx.content
+        ));
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(name: &str, content: String) -> ContextAttachment {
+        ContextAttachment {
+            path: PathBuf::from(name),
+            lines: None,
+            tokens: estimate_tokens(&content),
+            content,
+        }
+    }
+
+    #[test]
+    fn test_estimate_and_format_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(format_tokens(950), "950");
+        assert_eq!(format_tokens(12_345), "12.3k");
+    }
+
+    #[test]
+    fn test_fit_to_budget_keeps_small_files_whole() {
+        let small = attachment("small.rs", "fn a() {}\n".to_string());
+        let large = attachment(
+            "large.rs",
+            (0..500).map(|i| format!("let x{} = {};\n", i, i)).collect(),
+        );
+
+        let fitted = fit_to_budget(&[small.clone(), large], 300, TruncationStrategy::HeadTail);
+        assert_eq!(fitted[0].path, small.path);
+        assert!(!fitted[0].truncated);
+        assert!(fitted[1].truncated);
+        assert!(fitted.iter().map(|c| c.tokens).sum::<usize>() <= 300);
+        assert!(fitted[1].content.contains("lines omitted"));
+        assert!(fitted[1].content.starts_with("let x0 = 0;"));
+        assert!(fitted[1].content.ends_with("let x499 = 499;"));
+    }
+
+    #[test]
+    fn test_symbols_strategy_keeps_definitions() {
+        let body: String = (0..200)
+            .map(|i| format!("    let v{} = {};\n", i, i))
+            .collect();
+        let content = format!("pub fn first() {{\n{}}}\n\nstruct Second;\n", body);
+
+        let truncated = truncate_to_tokens(&content, 50, TruncationStrategy::Symbols);
+        assert!(truncated.contains("pub fn first()"));
+        assert!(truncated.contains("struct Second;"));
+        assert!(!truncated.contains("let v100"));
+    }
+}
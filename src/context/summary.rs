@@ -0,0 +1,261 @@
+//! A compact summary of the working directory for prompts
+//!
+//! Providers that don't read the repository themselves can be sent a few lines
+//! about the project ahead of the prompt: its languages and frameworks, taken
+//! from the manifests at the top level, the top-level layout and the checked
+//! out git branch. The summary is cached and only built again when a manifest,
+//! the top-level directory or the git HEAD changes.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::estimate_tokens;
+
+/// Manifests looked for at the top level, with the language they imply
+const MANIFESTS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Rust"),
+    ("package.json", "JavaScript"),
+    ("pyproject.toml", "Python"),
+    ("requirements.txt", "Python"),
+    ("go.mod", "Go"),
+    ("Gemfile", "Ruby"),
+    ("pom.xml", "Java"),
+    ("build.gradle", "Java"),
+    ("build.gradle.kts", "Kotlin"),
+    ("composer.json", "PHP"),
+    ("mix.exs", "Elixir"),
+    ("CMakeLists.txt", "C/C++"),
+];
+
+/// Dependencies worth naming, by the text that identifies them in a manifest
+const FRAMEWORKS: &[(&str, &str)] = &[
+    ("\"next\"", "Next.js"),
+    ("\"react\"", "React"),
+    ("\"vue\"", "Vue"),
+    ("\"svelte\"", "Svelte"),
+    ("\"@angular/core\"", "Angular"),
+    ("\"express\"", "Express"),
+    ("\"@nestjs/core\"", "NestJS"),
+    ("\"vite\"", "Vite"),
+    ("\"typescript\"", "TypeScript"),
+    ("axum", "Axum"),
+    ("actix-web", "Actix Web"),
+    ("rocket", "Rocket"),
+    ("tokio", "Tokio"),
+    ("ratatui", "Ratatui"),
+    ("bevy", "Bevy"),
+    ("tauri", "Tauri"),
+    ("django", "Django"),
+    ("flask", "Flask"),
+    ("fastapi", "FastAPI"),
+    ("github.com/gin-gonic/gin", "Gin"),
+    ("github.com/labstack/echo", "Echo"),
+    ("github.com/gofiber/fiber", "Fiber"),
+    ("rails", "Rails"),
+    ("sinatra", "Sinatra"),
+    ("spring-boot", "Spring Boot"),
+    ("laravel/framework", "Laravel"),
+    ("phoenix", "Phoenix"),
+];
+
+/// Top-level entries left out of the layout
+const SKIPPED: &[&str] = &[
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    "vendor",
+    "__pycache__",
+];
+
+/// Top-level entries listed before the rest are counted
+const LAYOUT_ENTRIES: usize = 20;
+
+#[derive(Debug, Default)]
+pub struct SummaryCache {
+    root: PathBuf,
+    fingerprint: Vec<Option<SystemTime>>,
+    text: Option<String>,
+}
+
+impl SummaryCache {
+    /// The summary of `root` within `max_tokens`, built again only when the
+    /// project changed since the last call
+    pub fn get(&mut self, root: &Path, max_tokens: usize) -> String {
+        let fingerprint = fingerprint(root);
+        if self.text.is_none() || self.root != root || self.fingerprint != fingerprint {
+            self.root = root.to_path_buf();
+            self.fingerprint = fingerprint;
+            self.text = Some(summarize(root));
+        }
+        cap_tokens(self.text.as_deref().unwrap_or_default(), max_tokens)
+    }
+}
+
+/// The project summary of `root`, one fact per line
+pub fn summarize(root: &Path) -> String {
+    let mut languages: Vec<String> = Vec::new();
+    let mut frameworks: Vec<&str> = Vec::new();
+    for (manifest, language) in MANIFESTS {
+        let Ok(content) = std::fs::read_to_string(root.join(manifest)) else {
+            continue;
+        };
+        let language = match *language {
+            "JavaScript" if root.join("tsconfig.json").exists() => "TypeScript",
+            language => language,
+        };
+        let entry = format!("{} ({})", language, manifest);
+        if !languages.iter().any(|l| l.starts_with(language)) {
+            languages.push(entry);
+        }
+        for (needle, name) in FRAMEWORKS {
+            if content.contains(needle) && !frameworks.contains(name) {
+                frameworks.push(name);
+            }
+        }
+    }
+
+    let mut lines = vec!["Project summary:".to_string()];
+    if !languages.is_empty() {
+        lines.push(format!("- Languages: {}", languages.join(", ")));
+    }
+    if !frameworks.is_empty() {
+        lines.push(format!("- Frameworks: {}", frameworks.join(", ")));
+    }
+    let layout = layout(root);
+    if !layout.is_empty() {
+        lines.push(format!("- Layout: {}", layout));
+    }
+    if let Some(branch) = git_branch(root) {
+        lines.push(format!("- Git branch: {}", branch));
+    }
+    lines.join("\n")
+}
+
+/// Top-level directories (with a trailing `/`) and files, directories first
+fn layout(root: &Path) -> String {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return String::new();
+    };
+    let mut names: Vec<(bool, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let is_dir = entry.file_type().ok()?.is_dir();
+            (!name.starts_with('.') && !SKIPPED.contains(&name.as_str())).then_some((!is_dir, name))
+        })
+        .collect();
+    names.sort();
+
+    let mut listed: Vec<String> = names
+        .iter()
+        .take(LAYOUT_ENTRIES)
+        .map(|(is_file, name)| {
+            if *is_file {
+                name.clone()
+            } else {
+                format!("{}/", name)
+            }
+        })
+        .collect();
+    if names.len() > LAYOUT_ENTRIES {
+        listed.push(format!("(+{} more)", names.len() - LAYOUT_ENTRIES));
+    }
+    listed.join(", ")
+}
+
+/// The `.git` directory of the repository `root` is in, following the
+/// `gitdir:` file of a worktree
+fn git_dir(root: &Path) -> Option<PathBuf> {
+    let dot_git = root
+        .ancestors()
+        .map(|dir| dir.join(".git"))
+        .find(|p| p.exists())?;
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let content = std::fs::read_to_string(&dot_git).ok()?;
+    let dir = PathBuf::from(content.strip_prefix("gitdir:")?.trim());
+    Some(match dot_git.parent() {
+        Some(parent) if dir.is_relative() => parent.join(dir),
+        _ => dir,
+    })
+}
+
+/// The checked out branch, or the short commit of a detached HEAD
+fn git_branch(root: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir(root)?.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(reference.trim_start_matches("refs/heads/").to_string()),
+        None => Some(format!("detached at {}", head.get(..8)?)),
+    }
+}
+
+/// Modification times of what the summary is built from
+fn fingerprint(root: &Path) -> Vec<Option<SystemTime>> {
+    let modified = |path: PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut times: Vec<Option<SystemTime>> = MANIFESTS
+        .iter()
+        .map(|(manifest, _)| modified(root.join(manifest)))
+        .collect();
+    times.push(modified(root.to_path_buf()));
+    times.push(git_dir(root).and_then(|dir| modified(dir.join("HEAD"))));
+    times
+}
+
+/// `text` cut to whole lines within `max_tokens`, the last line shortened if
+/// even that doesn't fit
+fn cap_tokens(text: &str, max_tokens: usize) -> String {
+    let mut capped = String::new();
+    for line in text.lines() {
+        let next = if capped.is_empty() {
+            line.to_string()
+        } else {
+            format!("{}\n{}", capped, line)
+        };
+        if estimate_tokens(&next) > max_tokens {
+            if capped.is_empty() {
+                capped = line.chars().take(max_tokens * 4).collect();
+            }
+            break;
+        }
+        capped = next;
+    }
+    capped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_names_language_frameworks_layout_and_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n\n[dependencies]\ntokio = \"1\"\naxum = \"0.7\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".git/HEAD"), "ref: refs/heads/feature/login\n").unwrap();
+        std::fs::write(root.join("README.md"), "# Demo\n").unwrap();
+
+        let mut cache = SummaryCache::default();
+        assert_eq!(
+            cache.get(root, 400),
+            "Project summary:\n\
+             - Languages: Rust (Cargo.toml)\n\
+             - Frameworks: Axum, Tokio\n\
+             - Layout: src/, Cargo.toml, README.md\n\
+             - Git branch: feature/login"
+        );
+        assert_eq!(
+            cache.get(root, 12),
+            "Project summary:\n- Languages: Rust (Cargo.toml)"
+        );
+    }
+}
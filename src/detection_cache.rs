@@ -0,0 +1,133 @@
+//! Provider detection results kept between runs
+//!
+//! Detecting a provider runs its CLI with `--version`, which for some tools
+//! takes a noticeable part of a second. Each result (whether the CLI ran, where
+//! it was found and the version it printed) is saved to `providers.json` in
+//! the data directory; a start within `detection_cache_hours` of it trusts the
+//! saved result and checks again in the background. `:provider refresh`
+//! detects everything again.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Where detection results are kept
+pub fn cache_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("providers.json")
+}
+
+/// One provider's last detection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Detection {
+    /// Whether `--version` ran and succeeded
+    pub available: bool,
+    /// The CLI found on `PATH`
+    pub path: Option<PathBuf>,
+    /// First line `--version` printed
+    pub version: Option<String>,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Detections by provider id (`claude`, `kiro`, ...)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DetectionCache {
+    pub providers: BTreeMap<String, Detection>,
+}
+
+impl DetectionCache {
+    /// The saved results, or none when the file is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        crate::file_ops::atomic_write(path, &json)
+    }
+
+    /// The result for `provider_id` if it is less than `ttl_hours` old at `now`
+    pub fn fresh(
+        &self,
+        provider_id: &str,
+        ttl_hours: u64,
+        now: DateTime<Utc>,
+    ) -> Option<&Detection> {
+        let ttl = Duration::hours(ttl_hours.min(i64::MAX as u64 / 3600) as i64);
+        self.providers
+            .get(provider_id)
+            .filter(|d| now.signed_duration_since(d.detected_at) < ttl)
+    }
+
+    /// Record that `command` did (or didn't) run for `provider_id`, with what
+    /// it printed
+    pub fn record(
+        &mut self,
+        provider_id: &str,
+        command: &str,
+        output: Option<&str>,
+        now: DateTime<Utc>,
+    ) {
+        let version = output
+            .and_then(|out| out.lines().map(str::trim).find(|l| !l.is_empty()))
+            .map(str::to_string);
+        self.providers.insert(
+            provider_id.to_string(),
+            Detection {
+                available: output.is_some(),
+                path: output.and_then(|_| find_in_path(command)),
+                version,
+                detected_at: now,
+            },
+        );
+    }
+}
+
+/// Where `command` would be run from: itself when it names a path, otherwise
+/// the first match in `PATH`
+fn find_in_path(command: &str) -> Option<PathBuf> {
+    if command.contains(std::path::MAIN_SEPARATOR) {
+        return Some(PathBuf::from(command));
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(command))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_are_fresh_within_ttl_and_round_trip() {
+        let now = Utc::now();
+        let mut cache = DetectionCache::default();
+        cache.record("claude", "claude", Some("\n1.0.3 (Claude Code)\n"), now);
+        cache.record("aider", "aider", None, now - Duration::hours(30));
+
+        let claude = cache.fresh("claude", 24, now).unwrap();
+        assert!(claude.available);
+        assert_eq!(claude.version.as_deref(), Some("1.0.3 (Claude Code)"));
+        assert!(cache.fresh("aider", 24, now).is_none());
+        assert!(cache.fresh("claude", 0, now).is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("providers.json");
+        cache.save(&path).unwrap();
+        assert_eq!(DetectionCache::load(&path), cache);
+        assert_eq!(
+            DetectionCache::load(&dir.path().join("missing.json")),
+            DetectionCache::default()
+        );
+    }
+}
@@ -49,3 +49,42 @@ pub fn get_install_url(provider: &str) -> String {
         _ => format!("https://www.google.com/search?q={} CLI install", provider),
     }
 }
+
+/// Where parser modes are documented
+pub const PARSER_MODES_URL: &str = "https://github.com/kodyberry23/zcode#parser-modes";
+
+/// The diagnostic for a reply in a format the provider's parser doesn't read,
+/// naming the `parser` setting that reads it
+pub fn format_change_error(
+    provider: &str,
+    version: Option<&str>,
+    config_key: &str,
+    change: &crate::providers::format::FormatChange,
+) -> ErrorDisplay {
+    let provider = match version {
+        Some(version) => format!("{} ({})", provider, version),
+        None => provider.to_string(),
+    };
+    let found = match change.changes {
+        0 => String::new(),
+        1 => " with 1 file change".to_string(),
+        n => format!(" with {} file changes", n),
+    };
+    ErrorDisplay {
+        title: "Output Format Changed".to_string(),
+        message: format!(
+            "{} output format detected: {}{}, where its {} parser reads {}. \
+             Update the parser mode in config.toml:\n\n\
+             [providers.{}]\nparser = \"{}\"\n\nSee {}",
+            provider,
+            change.detected.format(),
+            found,
+            change.configured.name(),
+            change.configured.format(),
+            config_key,
+            change.detected.name(),
+            PARSER_MODES_URL
+        ),
+        help_url: Some(PARSER_MODES_URL.to_string()),
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
 use crossterm::event::{Event as CrosstermEvent, EventStream, KeyEvent, KeyEventKind, MouseEvent};
@@ -16,7 +17,22 @@ pub enum AppEvent {
     Tick,
     ProviderDetected(ProviderInfo),
     PromptResult(CommandResult),
+    ProviderLine(String), // A line of output from the running prompt
+    Piped(String),        // Text sent from another terminal with `zcode pipe`
     Error(String),
+    FileChanged(PathBuf),   // A file under review was modified on disk
+    ConfigChanged(PathBuf), // The config file or `.zcode.toml` was saved
+    /// Files copied into a sandbox so far, of `total`
+    SandboxProgress {
+        copied: usize,
+        total: usize,
+    },
+    /// A large file's diff, computed on the blocking pool
+    DiffReady {
+        key: crate::diff::DiffKey,
+        hunks: Vec<crate::state::Hunk>,
+        elapsed: Duration,
+    },
 }
 
 /// Asynchronous event handler built on Crossterm's EventStream.
@@ -0,0 +1,751 @@
+//! Git integration for reviewing changes made outside ZCode
+//!
+//! `git diff` (or `git diff --staged`) output is loaded into the same hunk review
+//! pipeline used for provider changes. Applying the review builds a partial commit:
+//!
+//! - **Working tree review**: accepted hunks are staged, rejected hunks are reverted
+//!   in the working tree.
+//! - **Staged review**: rejected hunks are removed from the index; the working tree
+//!   is left untouched.
+//!
+//! Provider reviews can also be shown against a ref (`HEAD`, `main`, ...) instead
+//! of the working tree, using the file contents from [`files_at_ref`], and applied
+//! on a new branch of their own ([`ApplyBranch`]).
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::Config;
+use crate::file_ops::{BackupSet, WritePolicy};
+use crate::state::{ChangeTag, ChangeType, FileChange, Hunk, HunkStatus};
+
+/// Which side of the repository a review was loaded from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffSource {
+    /// Unstaged changes (`git diff`)
+    WorkingTree,
+    /// Staged changes (`git diff --staged`)
+    Staged,
+}
+
+/// Context for a review loaded from git
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitReview {
+    pub root: PathBuf,
+    pub source: DiffSource,
+    /// File modes reported by `git diff`, keyed by absolute path
+    pub file_modes: HashMap<PathBuf, String>,
+}
+
+/// Outcome of applying a git review
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GitApplyResult {
+    pub hunks_staged: usize,
+    pub hunks_reverted: usize,
+    pub hunks_unstaged: usize,
+}
+
+/// One file entry from `git diff` output
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffEntry {
+    pub path: String,
+    pub mode: Option<String>,
+    pub new_file: bool,
+    pub deleted: bool,
+    pub binary: bool,
+}
+
+/// Run git in `root`, optionally feeding `stdin`, and return stdout
+fn git(root: &Path, args: &[&str], stdin: Option<&str>) -> Result<String> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["-c", "core.quotepath=off"])
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                anyhow!("Command 'git' not found in PATH")
+            } else {
+                anyhow!("Failed to execute 'git': {}", e)
+            }
+        })?;
+
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Top-level directory of the repository containing `cwd`
+pub fn repo_root(cwd: &Path) -> Result<PathBuf> {
+    let root =
+        git(cwd, &["rev-parse", "--show-toplevel"], None).context("Not inside a git repository")?;
+    Ok(PathBuf::from(root.trim()))
+}
+
+/// Content of each of `paths` at `rev`, `None` where the file doesn't exist
+/// there. Relative paths are taken from `cwd`.
+pub fn files_at_ref(
+    cwd: &Path,
+    rev: &str,
+    paths: &[PathBuf],
+) -> Result<HashMap<PathBuf, Option<String>>> {
+    let root = repo_root(cwd)?;
+    git(
+        &root,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ],
+        None,
+    )
+    .map_err(|_| anyhow!("Unknown git ref '{}'", rev))?;
+
+    let mut contents = HashMap::new();
+    for path in paths {
+        let absolute = cwd.join(path);
+        let relative = absolute
+            .strip_prefix(&root)
+            .map_err(|_| anyhow!("{} is outside the repository", path.display()))?;
+        let spec = format!("{}:{}", rev, relative.to_string_lossy().replace('\\', "/"));
+        let content = if git(&root, &["cat-file", "-e", &spec], None).is_ok() {
+            Some(git(&root, &["show", &spec], None)?)
+        } else {
+            None
+        };
+        contents.insert(path.clone(), content);
+    }
+    Ok(contents)
+}
+
+/// Load the current `git diff` as file changes ready for review
+pub fn collect_changes(cwd: &Path, source: DiffSource) -> Result<(GitReview, Vec<FileChange>)> {
+    let root = repo_root(cwd)?;
+
+    // Prefixes are given so `diff.noprefix` and `diff.mnemonicPrefix` don't
+    // change the headers parse_diff_headers reads
+    let mut args = vec![
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "--no-renames",
+        "--src-prefix=a/",
+        "--dst-prefix=b/",
+    ];
+    if source == DiffSource::Staged {
+        args.push("--staged");
+    }
+    let diff = git(&root, &args, None)?;
+
+    let mut review = GitReview {
+        root: root.clone(),
+        source,
+        file_modes: HashMap::new(),
+    };
+    let mut changes = Vec::new();
+
+    for entry in parse_diff_headers(&diff) {
+        if entry.binary {
+            continue;
+        }
+
+        let original = if entry.new_file {
+            None
+        } else {
+            let spec = match source {
+                DiffSource::WorkingTree => format!(":{}", entry.path),
+                DiffSource::Staged => format!("HEAD:{}", entry.path),
+            };
+            Some(git(&root, &["show", &spec], None)?)
+        };
+
+        let path = root.join(&entry.path);
+        let proposed = if entry.deleted {
+            String::new()
+        } else {
+            match source {
+                DiffSource::WorkingTree => std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+                DiffSource::Staged => git(&root, &["show", &format!(":{}", entry.path)], None)?,
+            }
+        };
+
+        let change_type = if entry.new_file {
+            ChangeType::Create
+        } else if entry.deleted {
+            ChangeType::Delete
+        } else {
+            ChangeType::Modify
+        };
+
+        if let Some(mode) = entry.mode {
+            review.file_modes.insert(path.clone(), mode);
+        }
+        changes.push(FileChange {
+            path,
+            original_content: original,
+            proposed_content: proposed,
+            change_type,
+            renamed_from: None,
+        });
+    }
+
+    Ok((review, changes))
+}
+
+/// Extract the per-file headers from `git diff --no-renames --src-prefix=a/
+/// --dst-prefix=b/` output
+pub fn parse_diff_headers(diff: &str) -> Vec<DiffEntry> {
+    let mut entries: Vec<DiffEntry> = Vec::new();
+
+    for line in diff.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            let Some(path) = header_path(rest) else {
+                continue;
+            };
+            entries.push(DiffEntry {
+                path,
+                mode: None,
+                new_file: false,
+                deleted: false,
+                binary: false,
+            });
+            continue;
+        }
+
+        let Some(entry) = entries.last_mut() else {
+            continue;
+        };
+
+        if let Some(mode) = line.strip_prefix("new file mode ") {
+            entry.new_file = true;
+            entry.mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+            entry.deleted = true;
+            entry.mode = Some(mode.to_string());
+        } else if let Some(index) = line.strip_prefix("index ") {
+            if let Some((_, mode)) = index.split_once(' ') {
+                entry.mode.get_or_insert_with(|| mode.to_string());
+            }
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            entry.binary = true;
+        }
+    }
+
+    entries
+}
+
+/// The path of a `diff --git` line after its command: `a/<path> b/<path>`,
+/// each side in C quotes when the path has a quote, backslash or control
+/// character. Without renames both sides name the same path, so an unquoted
+/// one (which may hold spaces) is the first half.
+fn header_path(rest: &str) -> Option<String> {
+    if rest.starts_with('"') {
+        let (path, _) = unquote(rest)?;
+        return path.strip_prefix("a/").map(str::to_string);
+    }
+    let rest = rest.strip_prefix("a/")?;
+    let path = rest.get(..rest.len().checked_sub(3)? / 2)?;
+    (rest[path.len()..] == format!(" b/{}", path)).then(|| path.to_string())
+}
+
+/// The C-quoted string `text` starts with, unescaped, and what follows it
+fn unquote(text: &str) -> Option<(String, &str)> {
+    let mut bytes = Vec::new();
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let rest = &text[1 + i + 1..];
+                return Some((String::from_utf8_lossy(&bytes).into_owned(), rest));
+            }
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                match escaped {
+                    'n' => bytes.push(b'\n'),
+                    't' => bytes.push(b'\t'),
+                    '0'..='7' => {
+                        // Three octal digits for each byte of a non-ASCII character
+                        let mut byte = escaped.to_digit(8)?;
+                        for _ in 0..2 {
+                            byte = byte * 8 + chars.next()?.1.to_digit(8)?;
+                        }
+                        bytes.push(byte as u8);
+                    }
+                    other => {
+                        let mut buf = [0; 4];
+                        bytes.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+                    }
+                }
+            }
+            c => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    None
+}
+
+/// Render hunks as a patch that `git apply` understands
+pub fn build_patch(
+    review: &GitReview,
+    hunks: &[&Hunk],
+    changes: &HashMap<PathBuf, FileChange>,
+) -> String {
+    let mut by_file: BTreeMap<&Path, Vec<&Hunk>> = BTreeMap::new();
+    for hunk in hunks {
+        by_file.entry(&hunk.file_path).or_default().push(hunk);
+    }
+
+    let mut patch = String::new();
+    for (path, mut file_hunks) in by_file {
+        file_hunks.sort_by_key(|h| h.old.start);
+
+        let rel = path.strip_prefix(&review.root).unwrap_or(path);
+        let rel = rel.to_string_lossy();
+        let mode = review
+            .file_modes
+            .get(path)
+            .map(String::as_str)
+            .unwrap_or("100644");
+        let change_type = changes.get(path).map(|c| &c.change_type);
+
+        patch.push_str(&format!("diff --git a/{} b/{}\n", rel, rel));
+        match change_type {
+            Some(ChangeType::Create) => {
+                patch.push_str(&format!("new file mode {}\n", mode));
+                patch.push_str(&format!("--- /dev/null\n+++ b/{}\n", rel));
+            }
+            Some(ChangeType::Delete) => {
+                patch.push_str(&format!("deleted file mode {}\n", mode));
+                patch.push_str(&format!("--- a/{}\n+++ /dev/null\n", rel));
+            }
+            _ => patch.push_str(&format!("--- a/{}\n+++ b/{}\n", rel, rel)),
+        }
+
+        for hunk in file_hunks {
+            push_hunk(&mut patch, hunk);
+        }
+    }
+
+    patch
+}
+
+fn push_hunk(patch: &mut String, hunk: &Hunk) {
+    patch.push_str(&format!("@@ -{} +{} @@\n", hunk.old, hunk.new));
+
+    for change in &hunk.changes {
+        let prefix = match change.tag {
+            ChangeTag::Equal => ' ',
+            ChangeTag::Delete => '-',
+            ChangeTag::Insert => '+',
+        };
+        patch.push(prefix);
+        patch.push_str(&change.content);
+        if !change.content.ends_with('\n') {
+            patch.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+/// Apply a reviewed `git diff` back to the repository
+pub fn apply_review(
+    review: &GitReview,
+    hunks: &[Hunk],
+    changes: &HashMap<PathBuf, FileChange>,
+    config: &Config,
+    policy: &WritePolicy,
+) -> Result<GitApplyResult> {
+    // Files refused by the write policy are neither staged nor reverted
+    let allowed = |h: &&Hunk| policy.resolve(&h.file_path).is_ok();
+    let accepted: Vec<&Hunk> = hunks
+        .iter()
+        .filter(|h| h.status == HunkStatus::Accepted)
+        .filter(allowed)
+        .collect();
+    let rejected: Vec<&Hunk> = hunks
+        .iter()
+        .filter(|h| h.status == HunkStatus::Rejected)
+        .filter(allowed)
+        .collect();
+
+    let mut result = GitApplyResult::default();
+
+    match review.source {
+        DiffSource::WorkingTree => {
+            if accepted.is_empty() && rejected.is_empty() {
+                bail!("No accepted or rejected hunks to apply");
+            }
+
+            if !accepted.is_empty() {
+                let patch = build_patch(review, &accepted, changes);
+                git(&review.root, &["apply", "--cached", "-"], Some(&patch))
+                    .context("Failed to stage accepted hunks")?;
+                result.hunks_staged = accepted.len();
+            }
+
+            if !rejected.is_empty() {
+                let files: Vec<PathBuf> = rejected
+                    .iter()
+                    .map(|h| h.file_path.clone())
+                    .filter(|f| f.exists())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect();
+                if config.general.create_backups {
+                    BackupSet::create(&files).context("Failed to create backups")?;
+                }
+
+                let patch = build_patch(review, &rejected, changes);
+                git(&review.root, &["apply", "-R", "-"], Some(&patch))
+                    .context("Failed to revert rejected hunks")?;
+                result.hunks_reverted = rejected.len();
+            }
+        }
+        DiffSource::Staged => {
+            if rejected.is_empty() {
+                bail!("No rejected hunks to unstage");
+            }
+
+            let patch = build_patch(review, &rejected, changes);
+            git(
+                &review.root,
+                &["apply", "--cached", "-R", "-"],
+                Some(&patch),
+            )
+            .context("Failed to unstage rejected hunks")?;
+            result.hunks_unstaged = rejected.len();
+        }
+    }
+
+    Ok(result)
+}
+
+/// A branch created for applying a review, committed to once the files are
+/// written
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplyBranch {
+    pub root: PathBuf,
+    pub name: String,
+    /// The branch (or, detached, the commit) checked out before
+    pub previous: String,
+}
+
+/// Branch name from `template`, filling in `{prompt}` (the prompt's first
+/// words), `{session}` and `{date}`
+pub fn branch_name(template: &str, prompt: Option<&str>, session: Option<&str>) -> String {
+    let mut slug = String::new();
+    for word in prompt
+        .unwrap_or_default()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .take(6)
+    {
+        if slug.len() + word.len() > 40 {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_lowercase());
+    }
+    if slug.is_empty() {
+        slug.push_str("changes");
+    }
+    let session: String = session.unwrap_or("session").chars().take(8).collect();
+    template
+        .replace("{prompt}", &slug)
+        .replace("{session}", &session)
+        .replace("{date}", &chrono::Local::now().format("%Y%m%d").to_string())
+}
+
+/// Create branch `name` (or `name-2`, `name-3`... when that is taken) at HEAD
+/// of the repository containing `cwd` and check it out. Uncommitted changes
+/// come along, as with `git switch -c`.
+pub fn create_apply_branch(cwd: &Path, name: &str) -> Result<ApplyBranch> {
+    let root = repo_root(cwd)?;
+    git(&root, &["rev-parse", "--verify", "-q", "HEAD"], None)
+        .context("The repository has no commits to branch from")?;
+    let previous = match git(&root, &["symbolic-ref", "--short", "-q", "HEAD"], None) {
+        Ok(branch) => branch.trim().to_string(),
+        Err(_) => git(&root, &["rev-parse", "--short", "HEAD"], None)?
+            .trim()
+            .to_string(),
+    };
+
+    let taken = |branch: &str| {
+        let reference = format!("refs/heads/{}", branch);
+        git(&root, &["rev-parse", "--verify", "-q", &reference], None).is_ok()
+    };
+    let name = (1..)
+        .map(|n| match n {
+            1 => name.to_string(),
+            n => format!("{}-{}", name, n),
+        })
+        .find(|candidate| !taken(candidate))
+        .expect("some name is free");
+    git(&root, &["switch", "-q", "-c", &name], None)
+        .with_context(|| format!("Failed to create branch {}", name))?;
+    Ok(ApplyBranch {
+        root,
+        name,
+        previous,
+    })
+}
+
+impl ApplyBranch {
+    /// Commit `files` as they are now, leaving anything else staged alone
+    pub fn commit(&self, files: &[PathBuf], message: &str) -> Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        let paths: Vec<&str> = files.iter().filter_map(|f| f.to_str()).collect();
+        let mut add = vec!["add", "-A", "--"];
+        add.extend(&paths);
+        git(&self.root, &add, None).context("Failed to stage the applied files")?;
+        let mut commit = vec!["commit", "-q", "-m", message, "--"];
+        commit.extend(&paths);
+        git(&self.root, &commit, None).context("Failed to commit the applied files")?;
+        Ok(())
+    }
+
+    /// Go back to the previous branch and delete this one, after an apply
+    /// that wrote nothing
+    pub fn abandon(&self) -> Result<()> {
+        git(&self.root, &["checkout", "-q", &self.previous], None)?;
+        git(&self.root, &["branch", "-q", "-D", &self.name], None)?;
+        Ok(())
+    }
+
+    /// How to look at and merge the branch
+    pub fn hint(&self) -> String {
+        format!(
+            "on branch {name}: `git diff {prev}...{name}` to compare, `git switch {prev} && git merge {name}` to merge",
+            name = self.name,
+            prev = self.previous
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{extract_hunks, generate_diff};
+    use std::fs;
+
+    fn init_repo(dir: &Path) {
+        for args in [
+            vec!["init", "-q"],
+            vec!["config", "user.email", "zcode@example.com"],
+            vec!["config", "user.name", "zcode"],
+        ] {
+            git(dir, &args, None).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_diff_headers() {
+        let diff = "diff --git a/src/a b.rs b/src/a b.rs\n\
+index 1234567..89abcde 100755\n\
+--- a/src/a b.rs\n\
++++ b/src/a b.rs\n\
+@@ -1 +1 @@\n\
+-x\n\
++y\n\
+diff --git a/new.txt b/new.txt\n\
+new file mode 100644\n\
+index 0000000..1111111\n\
+diff --git a/logo.png b/logo.png\n\
+index 2222222..3333333 100644\n\
+Binary files a/logo.png and b/logo.png differ\n";
+
+        let entries = parse_diff_headers(diff);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].path, "src/a b.rs");
+        assert_eq!(entries[0].mode.as_deref(), Some("100755"));
+        assert!(entries[1].new_file);
+        assert_eq!(entries[1].mode.as_deref(), Some("100644"));
+        assert!(entries[2].binary);
+
+        // Quoted paths, escaped octal bytes included
+        let quoted = "diff --git \"a/say \\\"hi\\\".txt\" \"b/say \\\"hi\\\".txt\"\n\
+diff --git \"a/caf\\303\\251\\tx\" \"b/caf\\303\\251\\tx\"\n";
+        let paths: Vec<String> = parse_diff_headers(quoted)
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(paths, ["say \"hi\".txt", "caf\u{e9}\tx"]);
+    }
+
+    #[test]
+    fn test_diff_is_read_whatever_the_prefix_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        fs::write(root.join("long name.txt"), "one\n").unwrap();
+        fs::write(root.join("caf\u{e9}.txt"), "one\n").unwrap();
+        git(root, &["add", "."], None).unwrap();
+        git(root, &["commit", "-qm", "init"], None).unwrap();
+        fs::write(root.join("long name.txt"), "two\n").unwrap();
+        fs::write(root.join("caf\u{e9}.txt"), "two\n").unwrap();
+
+        for (key, value) in [("diff.mnemonicPrefix", "true"), ("diff.noprefix", "true")] {
+            git(root, &["config", key, value], None).unwrap();
+            let (_, changes) = collect_changes(root, DiffSource::WorkingTree).unwrap();
+            let mut paths: Vec<PathBuf> = changes.into_iter().map(|c| c.path).collect();
+            paths.sort();
+            let root = repo_root(root).unwrap();
+            assert_eq!(
+                paths,
+                [root.join("caf\u{e9}.txt"), root.join("long name.txt")],
+                "with {}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_patch_headers() {
+        let root = PathBuf::from("/repo");
+        let path = root.join("file.txt");
+        let diff = generate_diff("a\nb\nc\n", "a\nB\nc\n");
+        let hunks = extract_hunks(&path, &diff);
+        let review = GitReview {
+            root,
+            source: DiffSource::WorkingTree,
+            file_modes: HashMap::new(),
+        };
+
+        let refs: Vec<&Hunk> = hunks.iter().collect();
+        let patch = build_patch(&review, &refs, &HashMap::new());
+        assert!(patch.starts_with("diff --git a/file.txt b/file.txt\n--- a/file.txt\n"));
+        assert!(patch.contains("@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"));
+    }
+
+    #[test]
+    fn test_working_tree_review_stages_accepted_and_reverts_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+
+        let original: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        fs::write(root.join("file.txt"), &original).unwrap();
+        git(root, &["add", "file.txt"], None).unwrap();
+        git(root, &["commit", "-q", "-m", "init"], None).unwrap();
+
+        let edited = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 19\n", "line nineteen\n");
+        fs::write(root.join("file.txt"), &edited).unwrap();
+
+        let (review, changes) = collect_changes(root, DiffSource::WorkingTree).unwrap();
+        assert_eq!(changes.len(), 1);
+
+        let change = &changes[0];
+        let diff = generate_diff(
+            change.original_content.as_deref().unwrap(),
+            &change.proposed_content,
+        );
+        let mut hunks = extract_hunks(&change.path, &diff);
+        assert_eq!(hunks.len(), 2);
+        hunks[0].status = HunkStatus::Accepted;
+        hunks[1].status = HunkStatus::Rejected;
+
+        let pending: HashMap<PathBuf, FileChange> = changes
+            .iter()
+            .map(|c| (c.path.clone(), c.clone()))
+            .collect();
+        let result = apply_review(
+            &review,
+            &hunks,
+            &pending,
+            &Config::default(),
+            &WritePolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(result.hunks_staged, 1);
+        assert_eq!(result.hunks_reverted, 1);
+
+        let staged = git(root, &["show", ":file.txt"], None).unwrap();
+        assert!(staged.contains("line two\n"));
+        assert!(staged.contains("line 19\n"));
+
+        let worktree = fs::read_to_string(root.join("file.txt")).unwrap();
+        assert!(worktree.contains("line two\n"));
+        assert!(!worktree.contains("line nineteen"));
+    }
+
+    #[test]
+    fn test_files_at_ref() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "committed\n").unwrap();
+        git(root, &["add", "."], None).unwrap();
+        git(root, &["commit", "-q", "-m", "init"], None).unwrap();
+        fs::write(root.join("src/lib.rs"), "uncommitted\n").unwrap();
+
+        let paths = vec![root.join("src/lib.rs"), PathBuf::from("src/new.rs")];
+        let contents = files_at_ref(root, "HEAD", &paths).unwrap();
+        assert_eq!(contents[&paths[0]].as_deref(), Some("committed\n"));
+        assert_eq!(contents[&paths[1]], None);
+
+        assert!(files_at_ref(root, "no-such-branch", &paths).is_err());
+    }
+
+    #[test]
+    fn test_apply_branch_takes_a_free_name_and_commits_there() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        git(root, &["switch", "-q", "-c", "main"], None).unwrap();
+        fs::write(root.join("lib.rs"), "fn a() {}\n").unwrap();
+        git(root, &["add", "."], None).unwrap();
+        git(root, &["commit", "-q", "-m", "init"], None).unwrap();
+        git(root, &["branch", "zcode/add-b"], None).unwrap();
+
+        let name = branch_name("zcode/{prompt}", Some("Add b!"), None);
+        assert_eq!(name, "zcode/add-b");
+        let branch = create_apply_branch(root, &name).unwrap();
+        assert_eq!(branch.name, "zcode/add-b-2");
+        assert_eq!(branch.previous, "main");
+
+        fs::write(root.join("lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        branch.commit(&[root.join("lib.rs")], "Add b").unwrap();
+        let log = git(root, &["log", "--format=%s", "main..zcode/add-b-2"], None).unwrap();
+        assert_eq!(log, "Add b\n");
+        assert_eq!(git(root, &["status", "--porcelain"], None).unwrap(), "");
+
+        // A failed apply leaves nothing behind
+        let branch = create_apply_branch(root, "zcode/failed").unwrap();
+        branch.abandon().unwrap();
+        let head = git(root, &["symbolic-ref", "--short", "HEAD"], None).unwrap();
+        assert_eq!(head.trim(), "zcode/add-b-2");
+        assert!(git(root, &["rev-parse", "--verify", "-q", "zcode/failed"], None).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Guard rails for risky changes
+//!
+//! Proposed hunks are checked against the `[[guard.rules]]` of the config:
+//! mass deletions, edits to CI or secrets files, lockfile changes, `rm -rf`
+//! in scripts and the like. Flagged hunks carry a warning badge in the review,
+//! and applying any of them takes a second confirmation.
+
+use anyhow::{anyhow, Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::GuardRule;
+use crate::state::{ChangeTag, Hunk};
+
+/// The rules used when the config has no `[guard]` section
+pub fn default_rules() -> Vec<GuardRule> {
+    let paths = |paths: &[&str]| paths.iter().map(|p| p.to_string()).collect();
+    vec![
+        GuardRule {
+            name: "mass deletion".to_string(),
+            paths: Vec::new(),
+            added: None,
+            removed_lines: Some(50),
+        },
+        GuardRule {
+            name: "CI or secrets file".to_string(),
+            paths: paths(&[
+                ".github/workflows/",
+                ".gitlab-ci.yml",
+                ".circleci/",
+                ".env",
+                ".env.*",
+                "*secret*",
+                "*.pem",
+                "*.key",
+            ]),
+            added: None,
+            removed_lines: None,
+        },
+        GuardRule {
+            name: "lockfile".to_string(),
+            paths: paths(&[
+                "Cargo.lock",
+                "package-lock.json",
+                "yarn.lock",
+                "pnpm-lock.yaml",
+                "poetry.lock",
+                "Gemfile.lock",
+                "composer.lock",
+                "go.sum",
+            ]),
+            added: None,
+            removed_lines: None,
+        },
+        GuardRule {
+            name: "rm -rf".to_string(),
+            paths: Vec::new(),
+            added: Some(r"\brm\s+-[a-zA-Z]*([rR]f|f[rR])".to_string()),
+            removed_lines: None,
+        },
+    ]
+}
+
+struct CompiledRule {
+    name: String,
+    paths: Option<Gitignore>,
+    added: Option<Regex>,
+    removed_lines: Option<usize>,
+}
+
+/// The compiled guard rules
+#[derive(Default)]
+pub struct Guard {
+    root: PathBuf,
+    rules: Vec<CompiledRule>,
+}
+
+impl std::fmt::Debug for Guard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.rules.iter().map(|r| r.name.as_str()).collect();
+        f.debug_struct("Guard").field("rules", &names).finish()
+    }
+}
+
+impl Guard {
+    /// Compile `rules`, with path patterns relative to `root`
+    pub fn new(root: &Path, rules: &[GuardRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| compile(root, rule))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            rules,
+        })
+    }
+
+    /// Names of the rules `hunk` trips
+    pub fn check(&self, hunk: &Hunk) -> Vec<String> {
+        let relative = self.relative(&hunk.file_path);
+        self.rules
+            .iter()
+            .filter(|rule| {
+                let path_matches = match (&rule.paths, &relative) {
+                    (None, _) => true,
+                    (Some(paths), Some(relative)) => paths
+                        .matched_path_or_any_parents(relative, false)
+                        .is_ignore(),
+                    (Some(_), None) => false,
+                };
+                let added_matches = rule.added.as_ref().is_none_or(|pattern| {
+                    hunk.changes
+                        .iter()
+                        .any(|c| c.tag == ChangeTag::Insert && pattern.is_match(&c.content))
+                });
+                let removed_matches = rule.removed_lines.is_none_or(|least| {
+                    hunk.changes
+                        .iter()
+                        .filter(|c| c.tag == ChangeTag::Delete)
+                        .count()
+                        >= least
+                });
+                path_matches && added_matches && removed_matches
+            })
+            .map(|rule| rule.name.clone())
+            .collect()
+    }
+
+    /// Path relative to the root, or `None` if it lies outside it
+    fn relative(&self, path: &Path) -> Option<PathBuf> {
+        let relative: PathBuf = if path.is_absolute() {
+            path.strip_prefix(&self.root).ok()?.to_path_buf()
+        } else {
+            path.components()
+                .filter(|c| !matches!(c, Component::CurDir))
+                .collect()
+        };
+        let outside = relative
+            .components()
+            .any(|c| matches!(c, Component::ParentDir));
+        (!outside && !relative.as_os_str().is_empty()).then_some(relative)
+    }
+}
+
+fn compile(root: &Path, rule: &GuardRule) -> Result<CompiledRule> {
+    let paths = if rule.paths.is_empty() {
+        None
+    } else {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in &rule.paths {
+            builder
+                .add_line(None, pattern)
+                .map_err(|e| anyhow!("Guard rule '{}': {}", rule.name, e))?;
+        }
+        Some(
+            builder
+                .build()
+                .with_context(|| format!("Guard rule '{}'", rule.name))?,
+        )
+    };
+    let added = rule
+        .added
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .with_context(|| format!("Guard rule '{}' has an invalid `added` regex", rule.name))?;
+    Ok(CompiledRule {
+        name: rule.name.clone(),
+        paths,
+        added,
+        removed_lines: rule.removed_lines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{HunkRange, HunkStatus, LineChange};
+
+    fn hunk(path: &str, changes: &[(ChangeTag, &str)]) -> Hunk {
+        Hunk {
+            id: 0,
+            file_path: PathBuf::from(path),
+            old: HunkRange::default(),
+            new: HunkRange::default(),
+            changes: changes
+                .iter()
+                .map(|(tag, content)| LineChange {
+                    tag: tag.clone(),
+                    content: (*content).into(),
+                    old_line_num: None,
+                    new_line_num: None,
+                })
+                .collect(),
+            status: HunkStatus::Pending,
+            drifted: false,
+            comment: None,
+            auto_accepted: None,
+        }
+    }
+
+    #[test]
+    fn test_default_rules_flag_risky_hunks() {
+        let root = Path::new("/project");
+        let guard = Guard::new(root, &default_rules()).unwrap();
+
+        let edit = [(ChangeTag::Delete, "a\n"), (ChangeTag::Insert, "b\n")];
+        assert!(guard.check(&hunk("src/main.rs", &edit)).is_empty());
+        assert_eq!(
+            guard.check(&hunk(".github/workflows/ci.yml", &edit)),
+            vec!["CI or secrets file"]
+        );
+        assert_eq!(
+            guard.check(&hunk("/project/web/package-lock.json", &edit)),
+            vec!["lockfile"]
+        );
+        assert_eq!(
+            guard.check(&hunk(
+                "scripts/clean.sh",
+                &[(ChangeTag::Insert, "rm -rf \"$BUILD\"/\n")]
+            )),
+            vec!["rm -rf"]
+        );
+
+        let deletion: Vec<_> = (0..50).map(|_| (ChangeTag::Delete, "x\n")).collect();
+        assert_eq!(
+            guard.check(&hunk("src/lib.rs", &deletion)),
+            vec!["mass deletion"]
+        );
+
+        let bad = GuardRule {
+            name: "bad".to_string(),
+            paths: Vec::new(),
+            added: Some("(".to_string()),
+            removed_lines: None,
+        };
+        assert!(Guard::new(root, &[bad]).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! Translated UI strings
+//!
+//! Strings shown by the help overlay, dialogs and status bar are looked up by
+//! key (`help.title`, `confirm.question`, ...). English is built in from
+//! `locales/en.toml`; `display.language` picks a locale file from
+//! `~/.config/zcode/locales/<language>.toml` whose strings replace the English
+//! ones. Keys a locale leaves out fall back to English.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+const ENGLISH: &str = include_str!("../locales/en.toml");
+
+/// The locale in use, replaced when `display.language` changes. Each locale
+/// loaded stays allocated, so strings handed out before stay valid.
+static LOCALE: RwLock<Option<&'static Locale>> = RwLock::new(None);
+
+/// UI strings by dotted key
+#[derive(Debug, Clone, Default)]
+pub struct Locale {
+    strings: HashMap<String, String>,
+}
+
+impl Locale {
+    /// The built-in English strings
+    pub fn english() -> Self {
+        let mut locale = Self::default();
+        // Checked by the tests; a broken file would only cost the translations
+        let _ = locale.merge_toml(ENGLISH);
+        locale
+    }
+
+    /// English overlaid with `language`'s strings from `dir`. A regional
+    /// language like `pt-BR` falls back to `pt.toml` when it has no file of its own.
+    pub fn load(dir: &Path, language: &str) -> Result<Self> {
+        let mut locale = Self::english();
+        if language == "en" {
+            return Ok(locale);
+        }
+        let base = language.split(['-', '_']).next().unwrap_or(language);
+        let path = [language, base]
+            .iter()
+            .map(|name| dir.join(format!("{}.toml", name)))
+            .find(|path| path.is_file())
+            .with_context(|| format!("No locale file for '{}' in {}", language, dir.display()))?;
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        locale
+            .merge_toml(&text)
+            .with_context(|| format!("Invalid locale file {}", path.display()))?;
+        Ok(locale)
+    }
+
+    /// Add the strings of a locale file, replacing those with the same key
+    fn merge_toml(&mut self, text: &str) -> Result<()> {
+        let table: toml::Table = toml::from_str(text)?;
+        flatten("", &table, &mut self.strings);
+        Ok(())
+    }
+
+    /// The string for `key`, or the key itself when no locale has it
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key).map_or(key, String::as_str)
+    }
+}
+
+fn flatten(prefix: &str, table: &toml::Table, strings: &mut HashMap<String, String>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match value {
+            toml::Value::String(text) => {
+                strings.insert(key, text.clone());
+            }
+            toml::Value::Table(inner) => flatten(&key, inner, strings),
+            _ => {}
+        }
+    }
+}
+
+/// Where user locale files are kept
+pub fn locales_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("locales")
+}
+
+/// Load the locale for `language` (English when `None`). Called at startup
+/// and when a reloaded config changes the language; on error the UI is in
+/// English.
+pub fn init(language: Option<&str>) -> Result<()> {
+    let locale = match language {
+        Some(language) => Locale::load(&locales_dir(), language),
+        None => Ok(Locale::english()),
+    };
+    let (locale, result) = match locale {
+        Ok(locale) => (locale, Ok(())),
+        Err(e) => (Locale::english(), Err(e)),
+    };
+    if let Ok(mut current) = LOCALE.write() {
+        *current = Some(Box::leak(Box::new(locale)));
+    }
+    result
+}
+
+fn locale() -> &'static Locale {
+    static BUILT_IN: OnceLock<Locale> = OnceLock::new();
+    LOCALE
+        .read()
+        .ok()
+        .and_then(|current| *current)
+        .unwrap_or_else(|| BUILT_IN.get_or_init(Locale::english))
+}
+
+/// The UI string for `key` in the configured language
+pub fn t(key: &'static str) -> &'static str {
+    locale().get(key)
+}
+
+/// The UI string for `key` with each `{name}` placeholder replaced by its value
+pub fn tf(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    fill(t(key), args)
+}
+
+fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = template.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_overrides_english_and_falls_back() {
+        let english = Locale::english();
+        assert_eq!(english.get("help.title"), " Help ");
+        assert_eq!(english.get("no.such.key"), "no.such.key");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("de.toml"),
+            "[help]\ntitle = \" Hilfe \"\n\n[confirm]\nfiles_to_write = \"{count} Dateien\"\n",
+        )
+        .unwrap();
+
+        // A regional variant without its own file uses the base language
+        let german = Locale::load(dir.path(), "de-AT").unwrap();
+        assert_eq!(german.get("help.title"), " Hilfe ");
+        assert_eq!(german.get("help.quit"), "Quit");
+        assert_eq!(
+            fill(german.get("confirm.files_to_write"), &[("count", &3)]),
+            "3 Dateien"
+        );
+
+        assert!(Locale::load(dir.path(), "fr").is_err());
+    }
+
+    #[test]
+    fn test_every_key_looked_up_is_in_english() {
+        let english = Locale::english();
+        let lookup = regex::Regex::new(r#"\btf?\(\s*"([a-z_]+\.[a-z_.]+)""#).unwrap();
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut missing = Vec::new();
+        for entry in ignore::Walk::new(&src).flatten() {
+            if entry.path().extension().is_none_or(|ext| ext != "rs") {
+                continue;
+            }
+            let text = std::fs::read_to_string(entry.path()).unwrap();
+            for key in lookup.captures_iter(&text).map(|c| c[1].to_string()) {
+                if !english.strings.contains_key(&key) {
+                    missing.push(key);
+                }
+            }
+        }
+        assert!(missing.is_empty(), "not in locales/en.toml: {:?}", missing);
+    }
+}
@@ -1,6 +1,7 @@
 // src/input/command_mode.rs - Command mode parser and executor
 
-use crate::state::{MessageFilter, State};
+use crate::i18n::{t, tf};
+use crate::state::{MessageFilter, PromptMode, State};
 use anyhow::{Context, Result};
 use std::path::PathBuf;
 
@@ -13,6 +14,8 @@ pub enum Command {
     Jump(usize),
     Filter(MessageFilter),
     Pin(PathBuf),
+    /// Take the file shown in the sidebar off the pin list
+    Unpin,
     Search(String),
     Neovim(NeovimSubcommand),
     Help,
@@ -21,6 +24,45 @@ pub enum Command {
     Load(String),
     Clear,
     Export,
+    ReportParseFailure,
+    GitReview {
+        staged: bool,
+    },
+    Attach(PathBuf),
+    Detach(Option<PathBuf>),
+    Usage,
+    Perf,
+    History,
+    Sessions,
+    Session(SessionSubcommand),
+    /// Search the chats of all sessions
+    Grep(String),
+    Undo,
+    Preamble(PreambleSubcommand),
+    Comment(String),
+    Rename(PathBuf),
+    /// Review this one of the shown file's overlapping edits
+    Pick(usize),
+    /// Apply the review on a new git branch, named by `branch_name` unless given
+    ApplyBranch(Option<String>),
+    /// Compare the review with a git ref; `None` goes back to the working tree
+    DiffBase(Option<String>),
+    /// Show the provider command line while prompts run; `None` toggles
+    ProviderPreview(Option<bool>),
+    /// Ask for edits or only for answers; `None` toggles
+    PromptMode(Option<PromptMode>),
+    CopyInvocation,
+    /// Copy the latest apply summary as a commit message
+    CopySummary,
+    /// Run the suggested command with this number
+    Run(usize),
+    /// Fill in and send a prompt template; `None` lists the templates
+    Template(Option<String>),
+    /// Run `prompt` once per file matching `pattern` and review the results together
+    Foreach {
+        pattern: String,
+        prompt: String,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -30,6 +72,19 @@ pub enum ConfigSubcommand {
     Edit,
 }
 
+/// `:session export <file>` / `:session import <file>`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionSubcommand {
+    Export(PathBuf),
+    Import(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreambleSubcommand {
+    Show,
+    Edit,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum NeovimSubcommand {
     Connect,
@@ -59,7 +114,7 @@ impl std::error::Error for CommandError {}
 
 /// Parse a command string into a Command enum
 pub fn parse_command(input: &str) -> Result<Command, CommandError> {
-    let parts: Vec<&str> = input.trim().split_whitespace().collect();
+    let parts: Vec<&str> = input.split_whitespace().collect();
 
     if parts.is_empty() {
         return Err(CommandError::UnknownCommand);
@@ -96,6 +151,7 @@ pub fn parse_command(input: &str) -> Result<Command, CommandError> {
             let file_str = parts.get(1).ok_or(CommandError::MissingArgument)?;
             Ok(Command::Pin(PathBuf::from(file_str)))
         }
+        "unpin" => Ok(Command::Unpin),
         "search" => {
             let query = parts[1..].join(" ");
             Ok(Command::Search(query))
@@ -110,6 +166,93 @@ pub fn parse_command(input: &str) -> Result<Command, CommandError> {
         }
         "clear" => Ok(Command::Clear),
         "export" => Ok(Command::Export),
+        "report-parse-failure" => Ok(Command::ReportParseFailure),
+        "usage" => Ok(Command::Usage),
+        "perf" => Ok(Command::Perf),
+        "history" => Ok(Command::History),
+        "sessions" => Ok(Command::Sessions),
+        "session" => {
+            let file = parts.get(2).ok_or(CommandError::MissingArgument)?;
+            match parts.get(1).copied() {
+                Some("export") => Ok(Command::Session(SessionSubcommand::Export(PathBuf::from(
+                    file,
+                )))),
+                Some("import") => Ok(Command::Session(SessionSubcommand::Import(PathBuf::from(
+                    file,
+                )))),
+                _ => Err(CommandError::InvalidArguments),
+            }
+        }
+        "grep" => {
+            let query = parts[1..].join(" ");
+            if query.is_empty() {
+                return Err(CommandError::MissingArgument);
+            }
+            Ok(Command::Grep(query))
+        }
+        "template" => Ok(Command::Template(parts.get(1).map(|s| s.to_string()))),
+        "run" => {
+            let num_str = parts.get(1).ok_or(CommandError::MissingArgument)?;
+            match num_str.parse() {
+                Ok(num) if num > 0 => Ok(Command::Run(num)),
+                _ => Err(CommandError::InvalidArguments),
+            }
+        }
+        "undo" => Ok(Command::Undo),
+        "preamble" => match parts.get(1).copied() {
+            None | Some("show") => Ok(Command::Preamble(PreambleSubcommand::Show)),
+            Some("edit") => Ok(Command::Preamble(PreambleSubcommand::Edit)),
+            Some(_) => Err(CommandError::InvalidArguments),
+        },
+        "comment" => Ok(Command::Comment(parts[1..].join(" "))),
+        "command-preview" => match parts.get(1).copied() {
+            None => Ok(Command::ProviderPreview(None)),
+            Some("on") => Ok(Command::ProviderPreview(Some(true))),
+            Some("off") => Ok(Command::ProviderPreview(Some(false))),
+            Some(_) => Err(CommandError::InvalidArguments),
+        },
+        "mode" => match parts.get(1).copied() {
+            None => Ok(Command::PromptMode(None)),
+            Some("edit") => Ok(Command::PromptMode(Some(PromptMode::Edit))),
+            Some("ask") => Ok(Command::PromptMode(Some(PromptMode::Ask))),
+            Some(_) => Err(CommandError::InvalidArguments),
+        },
+        "copy-command" => Ok(Command::CopyInvocation),
+        "copy-summary" => Ok(Command::CopySummary),
+        "foreach" => {
+            let pattern = parts.get(1).ok_or(CommandError::MissingArgument)?;
+            let prompt = parts[2..].join(" ");
+            if prompt.is_empty() {
+                return Err(CommandError::MissingArgument);
+            }
+            Ok(Command::Foreach {
+                pattern: pattern.to_string(),
+                prompt,
+            })
+        }
+        "rename" => {
+            let path = parts.get(1).ok_or(CommandError::MissingArgument)?;
+            Ok(Command::Rename(PathBuf::from(path)))
+        }
+        "pick" => {
+            let num_str = parts.get(1).ok_or(CommandError::MissingArgument)?;
+            match num_str.parse() {
+                Ok(num) if num > 0 => Ok(Command::Pick(num)),
+                _ => Err(CommandError::InvalidArguments),
+            }
+        }
+        "diff-base" => Ok(Command::DiffBase(parts.get(1).map(|s| s.to_string()))),
+        "apply-branch" => Ok(Command::ApplyBranch(parts.get(1).map(|s| s.to_string()))),
+        "attach" => {
+            let file_str = parts.get(1).ok_or(CommandError::MissingArgument)?;
+            Ok(Command::Attach(PathBuf::from(file_str)))
+        }
+        "detach" => Ok(Command::Detach(parts.get(1).map(PathBuf::from))),
+        "git-review" => match parts.get(1).copied() {
+            None => Ok(Command::GitReview { staged: false }),
+            Some("staged") | Some("--staged") => Ok(Command::GitReview { staged: true }),
+            Some(_) => Err(CommandError::InvalidArguments),
+        },
         _ => Err(CommandError::UnknownCommand),
     }
 }
@@ -135,7 +278,7 @@ fn parse_config_command(parts: &[&str]) -> Result<Command, CommandError> {
 }
 
 fn parse_neovim_command(parts: &[&str]) -> Result<Command, CommandError> {
-    let subcmd = parts.get(0).ok_or(CommandError::MissingArgument)?;
+    let subcmd = parts.first().ok_or(CommandError::MissingArgument)?;
     match *subcmd {
         "connect" => Ok(Command::Neovim(NeovimSubcommand::Connect)),
         "push" => Ok(Command::Neovim(NeovimSubcommand::Push)),
@@ -148,68 +291,349 @@ fn parse_neovim_command(parts: &[&str]) -> Result<Command, CommandError> {
 /// Execute a command on the state
 pub fn execute_command(command: &Command, state: &mut State) -> Result<String> {
     match command {
-        Command::Config(ConfigSubcommand::Show) => Ok(format!("Config: {:?}", state.config)),
+        Command::Config(ConfigSubcommand::Show) => {
+            let layout: Vec<String> = state
+                .layout_options
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            Ok(format!(
+                "Precedence: {} ({})\nLayout options: {}\nConfig: {:?}",
+                crate::config::PRECEDENCE,
+                crate::config::Config::config_path().display(),
+                if layout.is_empty() {
+                    "none".to_string()
+                } else {
+                    layout.join(", ")
+                },
+                state.config
+            ))
+        }
         Command::Config(ConfigSubcommand::Set { key, value }) => {
             // TODO: Implement config setting
-            Ok(format!("Setting {} = {}", key, value))
+            Ok(tf("command.setting", &[("key", key), ("value", value)]))
         }
-        Command::Config(ConfigSubcommand::Edit) => Ok("Opening config editor...".to_string()),
+        Command::Config(ConfigSubcommand::Edit) => Ok(tf(
+            "command.editing",
+            &[("path", &state.config_problem_path().display())],
+        )),
         Command::Model(model) => {
             state.status_info.model = model.clone();
-            Ok(format!("Switched to model: {}", model))
+            Ok(tf("command.model", &[("model", model)]))
+        }
+        Command::Provider(provider) if provider == "refresh" => {
+            Ok(t("command.detecting_providers").to_string())
         }
         Command::Provider(provider) => {
             // TODO: Switch provider
-            Ok(format!("Switching to provider: {}", provider))
+            Ok(tf("command.provider", &[("provider", provider)]))
         }
         Command::Jump(message_id) => {
             use crate::ui::search::jump_to_message;
             if jump_to_message(&mut state.chat_history, *message_id) {
-                Ok(format!("Jumped to message {}", message_id))
+                Ok(tf("command.jumped", &[("id", message_id)]))
             } else {
-                Ok(format!("Message {} not found", message_id))
+                Ok(tf("command.no_message", &[("id", message_id)]))
             }
         }
         Command::Filter(filter) => {
             use crate::ui::search::apply_filter;
             apply_filter(&mut state.chat_history, filter.clone());
-            Ok(format!("Filter applied: {:?}", filter))
+            Ok(tf(
+                "command.filter",
+                &[("filter", &format!("{:?}", filter))],
+            ))
         }
         Command::Pin(file_path) => {
-            use crate::ui::sidebar::pin_file;
-            pin_file(&mut state.sidebar_state, file_path.clone());
-            Ok(format!("Pinned file: {}", file_path.display()))
+            use crate::ui::sidebar::add_pin;
+            add_pin(&mut state.sidebar_state, file_path.clone());
+            state.save_sidebar_files();
+            Ok(tf("command.pinned", &[("path", &file_path.display())]))
+        }
+        Command::Unpin => {
+            let path = crate::ui::sidebar::remove_pin(&mut state.sidebar_state)
+                .context("The sidebar doesn't show a pinned file")?;
+            state.save_sidebar_files();
+            Ok(tf("command.unpinned", &[("path", &path.display())]))
         }
         Command::Search(query) => {
             state.chat_history.search_query = Some(query.clone());
-            Ok(format!("Searching for: {}", query))
+            Ok(tf("command.searching", &[("query", query)]))
         }
         Command::Neovim(subcmd) => match subcmd {
-            NeovimSubcommand::Connect => Ok("Connecting to Neovim...".to_string()),
-            NeovimSubcommand::Push => Ok("Pushing overlays to Neovim...".to_string()),
-            NeovimSubcommand::Clear => Ok("Clearing Neovim overlays...".to_string()),
-            NeovimSubcommand::Status => Ok("Neovim status: Not connected".to_string()),
+            NeovimSubcommand::Connect => Ok(t("command.neovim_connect").to_string()),
+            NeovimSubcommand::Push => Ok(t("command.neovim_push").to_string()),
+            NeovimSubcommand::Clear => Ok(t("command.neovim_clear").to_string()),
+            NeovimSubcommand::Status => Ok(t("command.neovim_status").to_string()),
         },
-        Command::Help => Ok("Help: Press ? for help screen".to_string()),
+        Command::Help => Ok(t("command.help").to_string()),
         Command::Quit => {
             state.mode = crate::state::Mode::ProviderSelect;
-            Ok("Quitting...".to_string())
+            Ok(t("command.quitting").to_string())
         }
         Command::Save => {
             // TODO: Save session
-            Ok("Session saved".to_string())
+            Ok(t("command.session_saved").to_string())
         }
         Command::Load(session_id) => {
             // TODO: Load session
-            Ok(format!("Loading session: {}", session_id))
+            Ok(tf("command.loading_session", &[("id", session_id)]))
         }
         Command::Clear => {
             state.chat_history.messages.clear();
-            Ok("Chat history cleared".to_string())
+            Ok(t("command.chat_cleared").to_string())
         }
         Command::Export => {
             // TODO: Export config
-            Ok("Config exported".to_string())
+            Ok(t("command.config_exported").to_string())
+        }
+        Command::ReportParseFailure => {
+            let output = state
+                .last_provider_output
+                .as_deref()
+                .context("No provider output to report")?;
+            let provider_name = state.provider.as_ref().map_or("unknown", |p| p.name());
+            let path = crate::parsers::report_parse_failure(
+                &crate::parsers::fixture_corpus_dir(),
+                provider_name,
+                output,
+            )?;
+            Ok(tf(
+                "command.parse_failure_saved",
+                &[("path", &path.display())],
+            ))
+        }
+        Command::Attach(path) => {
+            use crate::context::format_tokens;
+            let tokens = state.attach_context(path)?;
+            Ok(tf(
+                "command.attached",
+                &[
+                    ("path", &path.display()),
+                    ("tokens", &format_tokens(tokens)),
+                    ("used", &format_tokens(state.context_tokens_used())),
+                    ("limit", &format_tokens(state.context_token_limit())),
+                ],
+            ))
+        }
+        Command::Detach(path) => {
+            let removed = state.detach_context(path.as_deref());
+            Ok(tf("command.detached", &[("count", &removed)]))
+        }
+        Command::Usage => {
+            state.mode = crate::state::Mode::Usage;
+            Ok(t("command.showing_usage").to_string())
+        }
+        Command::Perf => {
+            state.mode = crate::state::Mode::Perf;
+            Ok(t("command.showing_timings").to_string())
+        }
+        Command::History => {
+            let entries = crate::journal::load(&crate::journal::journal_path())?;
+            let note = tf("command.journal_entries", &[("count", &entries.len())]);
+            state.history = Some(crate::state::HistoryView {
+                entries,
+                selected: 0,
+            });
+            state.mode = crate::state::Mode::History;
+            Ok(note)
+        }
+        Command::Session(SessionSubcommand::Export(path)) => {
+            crate::session_archive::export(state, path)
+        }
+        Command::Session(SessionSubcommand::Import(path)) => {
+            crate::session_archive::import(state, path)
+        }
+        Command::Sessions => match state.open_session_switcher() {
+            0 => Ok(t("command.no_sessions").to_string()),
+            count => Ok(tf("command.recent_sessions", &[("count", &count)])),
+        },
+        Command::Grep(query) => match state.open_grep(query)? {
+            0 => Ok(tf("command.no_grep_matches", &[("query", query)])),
+            count => Ok(tf(
+                "command.grep_matches",
+                &[("count", &count), ("query", query)],
+            )),
+        },
+        Command::Undo => {
+            let cwd = std::env::current_dir()?;
+            let restored = state.undo_delete(&crate::file_ops::trash::trash_root(), &cwd)?;
+            let files = restored
+                .iter()
+                .map(|p| p.strip_prefix(&cwd).unwrap_or(p).display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let note = tf(
+                "command.restored",
+                &[("count", &restored.len()), ("files", &files)],
+            );
+            state.status_info.current_task = note.clone();
+            Ok(note)
+        }
+        Command::Preamble(PreambleSubcommand::Show) => {
+            let note = match &state.preamble {
+                Some(preamble) => tf(
+                    "command.preamble",
+                    &[
+                        ("label", &preamble.label()),
+                        ("lines", &preamble.text.lines().count()),
+                    ],
+                ),
+                None => t("command.no_preamble").to_string(),
+            };
+            state.status_info.current_task = note.clone();
+            Ok(note)
+        }
+        Command::Preamble(PreambleSubcommand::Edit) => {
+            // The app opens the file in the editor once it exists
+            let cwd = std::env::current_dir()?;
+            let path = crate::preamble::ensure_project_file(&cwd)?;
+            Ok(tf("command.editing", &[("path", &path.display())]))
+        }
+        Command::Comment(text) => {
+            let hunk = state
+                .set_hunk_comment(text)
+                .context("No hunk selected to comment on")?;
+            let note = match &hunk.comment {
+                Some(_) => tf("command.commented", &[("path", &hunk.file_path.display())]),
+                None => tf(
+                    "command.comment_removed",
+                    &[("path", &hunk.file_path.display())],
+                ),
+            };
+            state.status_info.current_task = note.clone();
+            // Back to the review the comment was written from
+            state.mode = crate::state::Mode::DiffReview;
+            Ok(note)
+        }
+        Command::Rename(path) => {
+            state.mode = crate::state::Mode::DiffReview;
+            let old = state.rename_review_file(path.clone())?;
+            let note = tf(
+                "command.renamed",
+                &[("old", &old.display()), ("path", &path.display())],
+            );
+            state.status_info.current_task = note.clone();
+            Ok(note)
+        }
+        Command::Pick(choice) => {
+            state.mode = crate::state::Mode::DiffReview;
+            let path = state.pick_edit(*choice)?;
+            let note = tf(
+                "command.picked",
+                &[("choice", choice), ("path", &path.display())],
+            );
+            state.status_info.current_task = note.clone();
+            Ok(note)
+        }
+        Command::DiffBase(rev) => {
+            state.mode = crate::state::Mode::DiffReview;
+            let note = state.diff_against(rev.as_deref())?;
+            state.status_info.current_task = note.clone();
+            Ok(note)
+        }
+        Command::ApplyBranch(name) => {
+            state.mode = crate::state::Mode::DiffReview;
+            if state.hunks.is_empty() {
+                anyhow::bail!("No review to apply");
+            }
+            if state.git_review.is_some() {
+                anyhow::bail!("A git review is staged, not applied on a branch");
+            }
+            let template = name
+                .clone()
+                .unwrap_or_else(|| state.config.general.branch_name.clone());
+            state.apply_branch = Some(template);
+            Ok(t("command.apply_branch").to_string())
+        }
+        Command::ProviderPreview(show) => {
+            let general = &mut state.config.general;
+            general.command_preview = show.unwrap_or(!general.command_preview);
+            let note = if general.command_preview {
+                t("command.preview_on")
+            } else {
+                t("command.preview_off")
+            };
+            state.status_info.current_task = note.to_string();
+            Ok(note.to_string())
+        }
+        Command::PromptMode(mode) => Ok(state.set_prompt_mode(*mode)),
+        Command::CopyInvocation => {
+            let command = state
+                .last_command
+                .as_ref()
+                .context("No provider command has run yet")?;
+            crate::clipboard::copy(&command.to_shell())?;
+            let note = tf(
+                "command.invocation_copied",
+                &[("program", &command.program)],
+            );
+            state.status_info.current_task = note.clone();
+            Ok(note)
+        }
+        Command::CopySummary => {
+            let summary = state
+                .latest_apply_summary()
+                .context("Nothing has been applied in this session")?;
+            crate::clipboard::copy(&summary.plain_text())?;
+            let note = tf(
+                "command.summary_copied",
+                &[("summary", &summary.headline())],
+            );
+            state.status_info.current_task = note.clone();
+            Ok(note)
+        }
+        // Started by the app once the command line closes
+        Command::Foreach { pattern, .. } => Ok(tf("command.foreach", &[("pattern", pattern)])),
+        Command::Template(None) => {
+            let cwd = std::env::current_dir()?;
+            let names = crate::templates::list(&cwd, &crate::templates::global_dir());
+            if names.is_empty() {
+                return Ok(tf(
+                    "command.no_templates",
+                    &[
+                        ("project", &crate::templates::PROJECT_DIR),
+                        ("global", &crate::templates::global_dir().display()),
+                    ],
+                ));
+            }
+            let note = tf("command.templates", &[("names", &names.join(", "))]);
+            state.status_info.current_task = note.clone();
+            Ok(note)
+        }
+        Command::Template(Some(name)) => {
+            let count = state.open_template(name)?;
+            Ok(tf(
+                "command.template_values",
+                &[("name", name), ("count", &count)],
+            ))
+        }
+        Command::Run(number) => {
+            let command = state
+                .command_suggestions
+                .get(number - 1)
+                .with_context(|| format!("No suggested command [{}]", number))?;
+            Ok(tf("command.running", &[("command", command)]))
+        }
+        Command::GitReview { staged } => {
+            use crate::git::{collect_changes, DiffSource};
+            let source = if *staged {
+                DiffSource::Staged
+            } else {
+                DiffSource::WorkingTree
+            };
+            let cwd = std::env::current_dir()?;
+            let (review, changes) = collect_changes(&cwd, source)?;
+            if changes.is_empty() {
+                return Ok(t("command.no_changes").to_string());
+            }
+
+            let count = changes.len();
+            let policy = crate::file_ops::WritePolicy::load(&review.root)?;
+            state.path_warnings.clear();
+            state.load_review(changes, policy);
+            state.git_review = Some(review);
+            Ok(tf("command.git_review", &[("count", &count)]))
         }
     }
 }
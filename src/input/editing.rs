@@ -0,0 +1,111 @@
+// src/input/editing.rs - Cursor movement and editing in a text buffer
+//
+// The cursor is a byte offset that always sits on a grapheme cluster boundary,
+// so CJK, emoji (including ZWJ sequences and flags) and combining marks move
+// and delete as the single character the user sees.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Start of the grapheme before `cursor` (or 0)
+pub fn prev_boundary(text: &str, cursor: usize) -> usize {
+    let cursor = clamp(text, cursor);
+    text[..cursor]
+        .grapheme_indices(true)
+        .next_back()
+        .map_or(0, |(idx, _)| idx)
+}
+
+/// End of the grapheme after `cursor` (or the end of `text`)
+pub fn next_boundary(text: &str, cursor: usize) -> usize {
+    let cursor = clamp(text, cursor);
+    text[cursor..]
+        .graphemes(true)
+        .next()
+        .map_or(cursor, |g| cursor + g.len())
+}
+
+/// `cursor` moved back onto a char boundary within `text`
+pub fn clamp(text: &str, cursor: usize) -> usize {
+    let mut cursor = cursor.min(text.len());
+    while !text.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+    cursor
+}
+
+/// Insert `ch` at the cursor and move past it
+pub fn insert(text: &mut String, cursor: &mut usize, ch: char) {
+    *cursor = clamp(text, *cursor);
+    text.insert(*cursor, ch);
+    *cursor += ch.len_utf8();
+}
+
+/// Insert `s` at the cursor and move past it
+pub fn insert_str(text: &mut String, cursor: &mut usize, s: &str) {
+    *cursor = clamp(text, *cursor);
+    text.insert_str(*cursor, s);
+    *cursor += s.len();
+}
+
+/// Delete the grapheme before the cursor; false when at the start
+pub fn backspace(text: &mut String, cursor: &mut usize) -> bool {
+    let start = prev_boundary(text, *cursor);
+    let end = clamp(text, *cursor);
+    if start == end {
+        return false;
+    }
+    text.replace_range(start..end, "");
+    *cursor = start;
+    true
+}
+
+/// Delete the grapheme under the cursor; false when at the end
+pub fn delete(text: &mut String, cursor: &mut usize) -> bool {
+    *cursor = clamp(text, *cursor);
+    let end = next_boundary(text, *cursor);
+    if end == *cursor {
+        return false;
+    }
+    text.replace_range(*cursor..end, "");
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editing_moves_by_grapheme() {
+        let mut text = String::new();
+        let mut cursor = 0;
+        for ch in "a漢".chars() {
+            insert(&mut text, &mut cursor, ch);
+        }
+        // Family emoji: several code points joined into one grapheme
+        insert_str(&mut text, &mut cursor, "👨‍👩‍👧");
+        insert(&mut text, &mut cursor, 'e');
+        insert(&mut text, &mut cursor, '\u{301}');
+        assert_eq!(cursor, text.len());
+
+        // "é" is e + combining accent, removed together
+        assert!(backspace(&mut text, &mut cursor));
+        assert_eq!(text, "a漢👨‍👩‍👧");
+
+        cursor = prev_boundary(&text, cursor);
+        assert_eq!(cursor, "a漢".len());
+        cursor = prev_boundary(&text, cursor);
+        assert_eq!(cursor, 1);
+        assert_eq!(next_boundary(&text, cursor), "a漢".len());
+
+        assert!(delete(&mut text, &mut cursor));
+        assert_eq!(text, "a👨‍👩‍👧");
+        insert(&mut text, &mut cursor, 'b');
+        assert_eq!(text, "ab👨‍👩‍👧");
+
+        // A cursor left inside a character is pulled back to its start
+        assert_eq!(clamp("漢", 2), 0);
+        assert!(!backspace(&mut text, &mut 0));
+        let mut end = text.len();
+        assert!(!delete(&mut text, &mut end));
+    }
+}
@@ -1,6 +1,6 @@
 // src/input/handler.rs - Input handler trait and result types
 
-use crate::state::{Mode, State};
+use crate::state::{ChangeTag, Hunk, HunkStatus, Mode, State};
 use crossterm::event::KeyEvent;
 
 /// Result of handling a key event
@@ -59,10 +59,43 @@ pub enum Action {
     PageUp,
     /// Page down
     PageDown,
+    /// Jump to the next (or previous) hunk of a kind, wrapping around
+    JumpToHunk { kind: HunkKind, forward: bool },
     /// Quit application
     Quit,
 }
 
+/// Hunks the `]`/`[` motions stop at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkKind {
+    /// Only adds lines
+    Addition,
+    /// Only removes lines
+    Deletion,
+    /// Not accepted or rejected yet
+    Pending,
+}
+
+impl HunkKind {
+    pub fn matches(self, hunk: &Hunk) -> bool {
+        let has = |tag| hunk.changes.iter().any(|c| c.tag == tag);
+        match self {
+            HunkKind::Addition => has(ChangeTag::Insert) && !has(ChangeTag::Delete),
+            HunkKind::Deletion => has(ChangeTag::Delete) && !has(ChangeTag::Insert),
+            HunkKind::Pending => hunk.status == HunkStatus::Pending,
+        }
+    }
+
+    /// Plural name for status messages
+    pub fn describe(self) -> &'static str {
+        match self {
+            HunkKind::Addition => "addition-only hunks",
+            HunkKind::Deletion => "deletion-only hunks",
+            HunkKind::Pending => "pending hunks",
+        }
+    }
+}
+
 /// Trait for handling keyboard input in different modes
 pub trait InputHandler {
     /// Handle a key event and return the result
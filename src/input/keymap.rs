@@ -1,7 +1,13 @@
 use std::collections::HashMap;
 
+use crate::config::KeybindingsConfig;
+use crate::input::handler::{Action, HunkKind};
 use crate::input::modes::InputMode;
 use crate::message::Message;
+use crate::ui::layout::Pane;
+
+/// Percent the chat/diff split moves per `Ctrl+←/→`
+const CHAT_SPLIT_STEP: i16 = 5;
 
 /// Registry mapping key sequences (vim-style) to messages per input mode.
 pub struct KeymapRegistry {
@@ -34,6 +40,67 @@ impl KeymapRegistry {
             .unwrap_or(false)
     }
 
+    /// The keys that can follow `prefix` in `mode`, each with what it does or
+    /// `None` when it only leads on to longer sequences, sorted by key
+    pub fn continuations(
+        &self,
+        mode: InputMode,
+        prefix: &[String],
+    ) -> Vec<(String, Option<Message>)> {
+        let Some(bindings) = self.bindings.get(&mode) else {
+            return Vec::new();
+        };
+        let mut next: Vec<(String, Option<Message>)> = Vec::new();
+        for (sequence, message) in bindings {
+            let Some(rest) = sequence.strip_prefix(prefix) else {
+                continue;
+            };
+            let Some(key) = rest.first() else {
+                continue;
+            };
+            let action = (rest.len() == 1).then(|| message.clone());
+            match next.iter_mut().find(|(k, _)| k == key) {
+                Some(entry) => {
+                    if action.is_some() {
+                        entry.1 = action;
+                    }
+                }
+                None => next.push((key.clone(), action)),
+            }
+        }
+        next.sort_by(|a, b| a.0.cmp(&b.0));
+        next
+    }
+
+    /// The default bindings with the keys `[keybindings]` changes moved. An
+    /// empty setting keeps the default key.
+    pub fn from_config(config: &KeybindingsConfig) -> Self {
+        let mut registry = Self::default_vim();
+        for (mode, default, key) in [
+            (InputMode::DiffReview, "j", &config.next_hunk),
+            (InputMode::DiffReview, "k", &config.prev_hunk),
+            (InputMode::DiffReview, "y", &config.accept_hunk),
+            (InputMode::DiffReview, "n", &config.reject_hunk),
+            (InputMode::DiffReview, "<Enter>", &config.apply_changes),
+            (InputMode::Normal, "q", &config.quit),
+        ] {
+            if !key.is_empty() && key != default {
+                registry.rebind(mode, default, key);
+            }
+        }
+        registry
+    }
+
+    /// Move the action on `from` in `mode` to `to`
+    fn rebind(&mut self, mode: InputMode, from: &str, to: &str) {
+        let Some(bindings) = self.bindings.get_mut(&mode) else {
+            return;
+        };
+        if let Some(message) = bindings.remove(&vec![from.to_string()]) {
+            bindings.insert(vec![to.to_string()], message);
+        }
+    }
+
     /// Default vim-like bindings across modes.
     pub fn default_vim() -> Self {
         use Message::*;
@@ -72,6 +139,43 @@ impl KeymapRegistry {
         registry.bind(InputMode::Normal, &["?"], ToggleHelp);
         registry.bind(InputMode::Normal, &["q"], Quit);
         registry.bind(InputMode::Normal, &["<C-b>"], ToggleSidebar);
+        for mode in [InputMode::Normal, InputMode::Insert] {
+            registry.bind(mode, &["<C-a>"], TogglePromptMode);
+        }
+        for mode in [InputMode::Normal, InputMode::DiffReview] {
+            registry.bind(mode, &["<C-v>"], CycleSidebarView);
+            registry.bind(mode, &["("], CyclePin(-1));
+            registry.bind(mode, &[")"], CyclePin(1));
+        }
+        registry.bind(InputMode::Normal, &["<C-o>"], ToggleReplyExpanded);
+        registry.bind(InputMode::Normal, &["<C-l>"], OpenMessageDetail);
+        registry.bind(InputMode::Normal, &["<C-Tab>"], OpenSessionSwitcher);
+        registry.bind(InputMode::Normal, &["<C-r>"], RunSuggestion);
+        registry.bind(
+            InputMode::Normal,
+            &["<C-p>"],
+            SetMode(crate::state::Mode::ProviderSelect),
+        );
+        registry.bind(
+            InputMode::Normal,
+            &["<PageUp>"],
+            ScrollChat(crate::message::Direction::Up),
+        );
+        registry.bind(
+            InputMode::Normal,
+            &["<PageDown>"],
+            ScrollChat(crate::message::Direction::Down),
+        );
+
+        // Chat/diff split in the wide layout, from any screen with the panes
+        for mode in [InputMode::Normal, InputMode::Insert, InputMode::DiffReview] {
+            registry.bind(mode, &["<C-Left>"], AdjustChatSplit(-CHAT_SPLIT_STEP));
+            registry.bind(mode, &["<C-Right>"], AdjustChatSplit(CHAT_SPLIT_STEP));
+            registry.bind(mode, &["<C-w>", "h"], FocusPane(Some(Pane::Chat)));
+            registry.bind(mode, &["<C-w>", "l"], FocusPane(Some(Pane::Diff)));
+            registry.bind(mode, &["<C-w>", "="], FocusPane(None));
+            registry.bind(mode, &["<F2>"], CycleFocus);
+        }
 
         // Insert mode exits
         registry.bind(
@@ -90,7 +194,118 @@ impl KeymapRegistry {
         registry.bind(InputMode::DiffReview, &["n"], RejectHunk(0));
         registry.bind(InputMode::DiffReview, &["Y"], AcceptAll);
         registry.bind(InputMode::DiffReview, &["N"], RejectAll);
+        registry.bind(InputMode::DiffReview, &["."], RepeatLast);
         registry.bind(InputMode::DiffReview, &["<Enter>"], ApplyChanges);
+        registry.bind(InputMode::DiffReview, &["o"], ApplyHunkAndEdit);
+        registry.bind(InputMode::DiffReview, &["="], ReindentHunk);
+        registry.bind(InputMode::DiffReview, &["O"], ToggleIgnoreOverride);
+        registry.bind(InputMode::DiffReview, &["X"], ToggleSkipFile);
+        registry.bind(InputMode::DiffReview, &["g", "d"], JumpToDefinition);
+        registry.bind(InputMode::DiffReview, &["c"], CommentHunk);
+        registry.bind(InputMode::DiffReview, &["R"], RequestFixes);
+        registry.bind(InputMode::DiffReview, &["r"], RenameTarget);
+        registry.bind(InputMode::DiffReview, &["w"], ToggleDiffWrap);
+        registry.bind(InputMode::DiffReview, &["b"], ToggleDiffBase);
+        registry.bind(InputMode::DiffReview, &["s"], ToggleSideBySide);
+        registry.bind(InputMode::DiffReview, &["l"], ToggleLineNumbers);
+        registry.bind(InputMode::DiffReview, &["g", "y"], AcceptGroup);
+        registry.bind(InputMode::DiffReview, &["g", "n"], RejectGroup);
+        registry.bind(InputMode::DiffReview, &["z", "a"], ToggleGroupCollapsed);
+        for (key, kind) in [
+            ("a", HunkKind::Addition),
+            ("d", HunkKind::Deletion),
+            ("p", HunkKind::Pending),
+        ] {
+            for (prefix, forward) in [("]", true), ("[", false)] {
+                registry.bind(
+                    InputMode::DiffReview,
+                    &[prefix, key],
+                    HunkMotion(Action::JumpToHunk { kind, forward }),
+                );
+            }
+        }
+        registry.bind(
+            InputMode::DiffReview,
+            &["<PageUp>"],
+            ScrollDiff(crate::message::Direction::Up),
+        );
+        registry.bind(
+            InputMode::DiffReview,
+            &["<PageDown>"],
+            ScrollDiff(crate::message::Direction::Down),
+        );
+        registry.bind(
+            InputMode::DiffReview,
+            &["j"],
+            Navigate(crate::message::Direction::Down),
+        );
+        registry.bind(
+            InputMode::DiffReview,
+            &["k"],
+            Navigate(crate::message::Direction::Up),
+        );
+        registry.bind(
+            InputMode::DiffReview,
+            &[":"],
+            SetMode(crate::state::Mode::CommandMode),
+        );
+        registry.bind(
+            InputMode::DiffReview,
+            &["<Esc>"],
+            SetMode(crate::state::Mode::PromptEntry),
+        );
+
+        // Apply confirmation
+        registry.bind(InputMode::Confirmation, &["y"], ConfirmApply);
+        registry.bind(InputMode::Confirmation, &["<Enter>"], ConfirmApply);
+        registry.bind(
+            InputMode::Confirmation,
+            &["n"],
+            SetMode(crate::state::Mode::DiffReview),
+        );
+        registry.bind(
+            InputMode::Confirmation,
+            &["<Esc>"],
+            SetMode(crate::state::Mode::DiffReview),
+        );
+        for (index, key) in ["1", "2", "3", "4", "5", "6", "7", "8", "9"]
+            .iter()
+            .enumerate()
+        {
+            registry.bind(InputMode::Confirmation, &[key], ToggleChecklistItem(index));
+        }
+        registry.bind(
+            InputMode::Confirmation,
+            &["j"],
+            ScrollDialog(crate::message::Direction::Down),
+        );
+        registry.bind(
+            InputMode::Confirmation,
+            &["<Down>"],
+            ScrollDialog(crate::message::Direction::Down),
+        );
+        registry.bind(
+            InputMode::Confirmation,
+            &["k"],
+            ScrollDialog(crate::message::Direction::Up),
+        );
+        registry.bind(
+            InputMode::Confirmation,
+            &["<Up>"],
+            ScrollDialog(crate::message::Direction::Up),
+        );
+        registry.bind(
+            InputMode::Confirmation,
+            &["<PageUp>"],
+            PageDialog(crate::message::Direction::Up),
+        );
+        registry.bind(
+            InputMode::Confirmation,
+            &["<PageDown>"],
+            PageDialog(crate::message::Direction::Down),
+        );
+        registry.bind(InputMode::Confirmation, &["s"], ToggleDialogSelection);
+        registry.bind(InputMode::Confirmation, &["c"], CopyConfirmation);
 
         // Command/help escape
         registry.bind(
@@ -108,6 +323,38 @@ impl KeymapRegistry {
     }
 }
 
+/// What `message` does, in a few words, for the which-key popup
+pub fn describe(message: &Message) -> String {
+    use crate::i18n::t;
+    use Message::*;
+
+    let key = match message {
+        ScrollTo(0) => "which_key.top",
+        ScrollTo(_) => "which_key.bottom",
+        JumpToDefinition => "which_key.definition",
+        AcceptGroup => "which_key.accept_group",
+        RejectGroup => "which_key.reject_group",
+        ToggleGroupCollapsed => "which_key.fold_group",
+        RepeatLast => "which_key.repeat_last",
+        ReindentHunk => "which_key.reindent",
+        TogglePromptMode => "which_key.prompt_mode",
+        FocusPane(Some(Pane::Chat)) => "which_key.focus_chat",
+        FocusPane(Some(Pane::Diff)) => "which_key.focus_diff",
+        FocusPane(None) => "which_key.unfocus",
+        CycleFocus => "which_key.cycle_focus",
+        HunkMotion(Action::JumpToHunk { kind, forward }) => match (kind, forward) {
+            (HunkKind::Addition, true) => "which_key.next_addition",
+            (HunkKind::Addition, false) => "which_key.prev_addition",
+            (HunkKind::Deletion, true) => "which_key.next_deletion",
+            (HunkKind::Deletion, false) => "which_key.prev_deletion",
+            (HunkKind::Pending, true) => "which_key.next_pending",
+            (HunkKind::Pending, false) => "which_key.prev_pending",
+        },
+        other => return format!("{:?}", other),
+    };
+    t(key).to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,6 +368,107 @@ mod tests {
             km.lookup(InputMode::Normal, &seq),
             Some(Message::Navigate(Direction::Down))
         ));
+        assert!(matches!(
+            km.lookup(InputMode::Normal, &["<C-o>".to_string()]),
+            Some(Message::ToggleReplyExpanded)
+        ));
+    }
+
+    #[test]
+    fn test_diff_review_bindings() {
+        let km = KeymapRegistry::default_vim();
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["j".to_string()]),
+            Some(Message::Navigate(Direction::Down))
+        ));
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["O".to_string()]),
+            Some(Message::ToggleIgnoreOverride)
+        ));
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["o".to_string()]),
+            Some(Message::ApplyHunkAndEdit)
+        ));
+        assert!(matches!(
+            km.lookup(InputMode::Confirmation, &["y".to_string()]),
+            Some(Message::ConfirmApply)
+        ));
+        assert!(matches!(
+            km.lookup(InputMode::Confirmation, &["s".to_string()]),
+            Some(Message::ToggleDialogSelection)
+        ));
+        assert!(matches!(
+            km.lookup(InputMode::Confirmation, &["c".to_string()]),
+            Some(Message::CopyConfirmation)
+        ));
+        assert!(km.has_prefix(InputMode::DiffReview, &["g".to_string()]));
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["z".to_string(), "a".to_string()]),
+            Some(Message::ToggleGroupCollapsed)
+        ));
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["g".to_string(), "d".to_string()]),
+            Some(Message::JumpToDefinition)
+        ));
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["]".to_string(), "p".to_string()]),
+            Some(Message::HunkMotion(Action::JumpToHunk {
+                kind: HunkKind::Pending,
+                forward: true
+            }))
+        ));
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["R".to_string()]),
+            Some(Message::RequestFixes)
+        ));
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["=".to_string()]),
+            Some(Message::ReindentHunk)
+        ));
+        let keys = ["<C-w>".to_string(), "l".to_string()];
+        assert!(matches!(
+            km.lookup(InputMode::Insert, &keys),
+            Some(Message::FocusPane(Some(Pane::Diff)))
+        ));
+    }
+
+    #[test]
+    fn test_keybindings_config_moves_keys() {
+        let config = KeybindingsConfig {
+            accept_hunk: "a".to_string(),
+            quit: "<C-q>".to_string(),
+            ..KeybindingsConfig::default()
+        };
+        let km = KeymapRegistry::from_config(&config);
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["a".to_string()]),
+            Some(Message::AcceptHunk(0))
+        ));
+        assert!(km
+            .lookup(InputMode::DiffReview, &["y".to_string()])
+            .is_none());
+        assert!(matches!(
+            km.lookup(InputMode::Normal, &["<C-q>".to_string()]),
+            Some(Message::Quit)
+        ));
+        // Unset settings keep their default keys
+        assert!(matches!(
+            km.lookup(InputMode::DiffReview, &["n".to_string()]),
+            Some(Message::RejectHunk(0))
+        ));
+    }
+
+    #[test]
+    fn test_continuations_of_prefix() {
+        let km = KeymapRegistry::default_vim();
+        let next = km.continuations(InputMode::DiffReview, &["g".to_string()]);
+        let keys: Vec<&str> = next.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["d", "n", "y"]);
+        assert!(matches!(next[0].1, Some(Message::JumpToDefinition)));
+        assert_eq!(describe(next[0].1.as_ref().unwrap()), "go to definition");
+        assert!(km
+            .continuations(InputMode::Normal, &["j".to_string()])
+            .is_empty());
     }
 
     #[test]
@@ -13,11 +13,13 @@
 //!
 //! # Submodules
 //!
+//! - [`editing`]: Grapheme-aware cursor movement and editing of the prompt
 //! - [`handler`]: The `InputHandler` trait and `Action` enum
 //! - [`keybindings`]: Key binding definitions and management
 //! - [`modes`]: Mode-specific input handlers
 
 pub mod command_mode;
+pub mod editing;
 pub mod handler;
 pub mod keybindings;
 pub mod keymap;
@@ -25,7 +27,7 @@ pub mod modes;
 pub mod parser;
 
 pub use command_mode::{execute_command, parse_command, Command, CommandError};
-pub use handler::{Action, InputHandler, InputResult};
+pub use handler::{Action, HunkKind, InputHandler, InputResult};
 pub use keybindings::{KeyCombo, KeybindingMap};
 pub use modes::{
     ConfirmationHandler, DiffReviewHandler, PromptEntryHandler, ProviderSelectHandler,
@@ -1,6 +1,6 @@
 // src/input/modes/diff_review.rs - Keyboard input handling for diff review mode
 
-use crate::input::handler::{key_helpers, Action, InputHandler, InputResult};
+use crate::input::handler::{key_helpers, Action, HunkKind, InputHandler, InputResult};
 use crate::state::{HunkStatus, Mode, State};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
@@ -95,30 +95,24 @@ impl DiffReviewHandler {
     /// Apply navigation actions to state
     pub fn apply_action(action: &Action, state: &mut State) -> bool {
         match action {
-            Action::Next => {
-                if state.selected_hunk < state.hunks.len().saturating_sub(1) {
-                    state.selected_hunk += 1;
-                    // Scroll to keep selected hunk visible
-                    let content_rows = state.viewport_rows.saturating_sub(4);
-                    if state.selected_hunk >= state.scroll_offset + content_rows {
-                        state.scroll_offset = state.selected_hunk.saturating_sub(content_rows / 2);
-                    }
-                    true
-                } else {
-                    false
+            Action::Next if state.selected_hunk < state.hunks.len().saturating_sub(1) => {
+                state.selected_hunk += 1;
+                // Scroll to keep selected hunk visible
+                let content_rows = state.viewport_rows.saturating_sub(4);
+                if state.selected_hunk >= state.scroll_offset + content_rows {
+                    state.scroll_offset = state.selected_hunk.saturating_sub(content_rows / 2);
                 }
+                true
             }
-            Action::Previous => {
-                if state.selected_hunk > 0 {
-                    state.selected_hunk -= 1;
-                    if state.selected_hunk < state.scroll_offset {
-                        state.scroll_offset = state.selected_hunk;
-                    }
-                    true
-                } else {
-                    false
+            Action::Next => false,
+            Action::Previous if state.selected_hunk > 0 => {
+                state.selected_hunk -= 1;
+                if state.selected_hunk < state.scroll_offset {
+                    state.scroll_offset = state.selected_hunk;
                 }
+                true
             }
+            Action::Previous => false,
             Action::AcceptCurrent => {
                 if let Some(hunk) = state.hunks.get_mut(state.selected_hunk) {
                     hunk.status = HunkStatus::Accepted;
@@ -167,14 +161,11 @@ impl DiffReviewHandler {
                     false
                 }
             }
-            Action::ScrollUp => {
-                if state.scroll_offset > 0 {
-                    state.scroll_offset -= 1;
-                    true
-                } else {
-                    false
-                }
+            Action::ScrollUp if state.scroll_offset > 0 => {
+                state.scroll_offset -= 1;
+                true
             }
+            Action::ScrollUp => false,
             Action::PageDown => {
                 let content_rows = state.viewport_rows.saturating_sub(4);
                 state.scroll_offset = (state.scroll_offset + content_rows)
@@ -187,18 +178,122 @@ impl DiffReviewHandler {
                 true
             }
             Action::ToggleLineNumbers => {
-                // This would be set in DisplayConfig
+                let overlay = &mut state.overlay_diff_state;
+                overlay.line_numbers = !overlay.line_numbers;
+                overlay.keep_cursor_visible();
                 true
             }
+            Action::JumpToHunk { kind, forward } => Self::jump_to_hunk(state, *kind, *forward),
             _ => false,
         }
     }
+
+    /// Select the nearest hunk of `kind` after (or before) the selected one,
+    /// across files, wrapping around at the ends. Reports the outcome in the
+    /// status bar.
+    fn jump_to_hunk(state: &mut State, kind: HunkKind, forward: bool) -> bool {
+        let matching: Vec<usize> = state
+            .hunks
+            .iter()
+            .filter(|h| kind.matches(h))
+            .map(|h| h.id)
+            .collect();
+        let current = state.selected_hunk;
+        let target = if forward {
+            matching.iter().find(|&&id| id > current)
+        } else {
+            matching.iter().rev().find(|&&id| id < current)
+        };
+        let wrapped = target.is_none();
+        let target = target.or(if forward {
+            matching.first()
+        } else {
+            matching.last()
+        });
+
+        let Some(&target) = target else {
+            state.status_info.current_task = format!("No {}", kind.describe());
+            return false;
+        };
+        state.select_hunk(target);
+
+        let position = matching.iter().position(|&id| id == target).unwrap_or(0) + 1;
+        let file = state.hunks[target].file_path.display();
+        let mut note = format!(
+            "{} {} of {}: {}",
+            kind.describe(),
+            position,
+            matching.len(),
+            file
+        );
+        if wrapped && target != current {
+            note.push_str(if forward {
+                " (wrapped to the first)"
+            } else {
+                " (wrapped to the last)"
+            });
+        }
+        state.status_info.current_task = note;
+        target != current
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn review() -> State {
+        let original: String = (0..30).map(|i| format!("line {}\n", i)).collect();
+        let proposed = original
+            .replace("line 2\n", "line 2\nadded\n")
+            .replace("line 12\n", "")
+            .replace("line 24\n", "line twenty-four\n");
+        let mut state = State::default();
+        state.load_review(
+            vec![crate::state::FileChange {
+                path: "src/lib.rs".into(),
+                original_content: Some(original),
+                proposed_content: proposed,
+                change_type: crate::state::ChangeType::Modify,
+                renamed_from: None,
+            }],
+            crate::file_ops::WritePolicy::default(),
+        );
+        state
+    }
+
+    #[test]
+    fn test_jump_to_hunk_by_kind_wraps() {
+        let mut state = review();
+        assert_eq!(state.hunks.len(), 3);
+        let jump = |state: &mut State, kind, forward| {
+            DiffReviewHandler::apply_action(&Action::JumpToHunk { kind, forward }, state);
+            state.selected_hunk
+        };
+
+        assert_eq!(jump(&mut state, HunkKind::Deletion, true), 1);
+        assert_eq!(jump(&mut state, HunkKind::Addition, true), 0);
+        assert!(state.status_info.current_task.contains("wrapped"));
+        assert_eq!(jump(&mut state, HunkKind::Addition, false), 0);
+
+        state.set_hunk_status(1, HunkStatus::Accepted);
+        assert_eq!(jump(&mut state, HunkKind::Pending, true), 2);
+        assert_eq!(
+            state.status_info.current_task,
+            "pending hunks 2 of 2: src/lib.rs"
+        );
+
+        state.set_all_hunks_status(HunkStatus::Rejected);
+        assert!(!DiffReviewHandler::apply_action(
+            &Action::JumpToHunk {
+                kind: HunkKind::Pending,
+                forward: true
+            },
+            &mut state
+        ));
+        assert_eq!(state.status_info.current_task, "No pending hunks");
+    }
+
     #[test]
     fn test_handler_creation() {
         let handler = DiffReviewHandler::new();
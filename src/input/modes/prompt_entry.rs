@@ -1,5 +1,6 @@
 // src/input/modes/prompt_entry.rs - Keyboard input handling for prompt entry mode
 
+use crate::input::editing;
 use crate::input::handler::{key_helpers, Action, InputHandler, InputResult};
 use crate::state::{Mode, State};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
@@ -14,21 +15,21 @@ impl InputHandler for PromptEntryHandler {
         // Text input - regular characters
         if let KeyCode::Char(ch) = key.code {
             if !has_any_modifier(key) || is_printable(ch) {
-                state.prompt_buffer.insert(state.cursor_position, ch);
-                state.cursor_position += 1;
+                editing::insert(&mut state.prompt_buffer, &mut state.cursor_position, ch);
                 return InputResult::Consumed;
             }
         }
 
         // Handle special keys
-        if is_key(key, KeyCode::Backspace) && state.cursor_position > 0 {
-            state.cursor_position -= 1;
-            state.prompt_buffer.remove(state.cursor_position);
+        if is_key(key, KeyCode::Backspace)
+            && editing::backspace(&mut state.prompt_buffer, &mut state.cursor_position)
+        {
             return InputResult::Consumed;
         }
 
-        if is_key(key, KeyCode::Delete) && state.cursor_position < state.prompt_buffer.len() {
-            state.prompt_buffer.remove(state.cursor_position);
+        if is_key(key, KeyCode::Delete)
+            && editing::delete(&mut state.prompt_buffer, &mut state.cursor_position)
+        {
             return InputResult::Consumed;
         }
 
@@ -47,12 +48,14 @@ impl InputHandler for PromptEntryHandler {
 
         // Arrow keys for cursor movement
         if is_key(key, KeyCode::Left) && state.cursor_position > 0 {
-            state.cursor_position -= 1;
+            state.cursor_position =
+                editing::prev_boundary(&state.prompt_buffer, state.cursor_position);
             return InputResult::Consumed;
         }
 
         if is_key(key, KeyCode::Right) && state.cursor_position < state.prompt_buffer.len() {
-            state.cursor_position += 1;
+            state.cursor_position =
+                editing::next_boundary(&state.prompt_buffer, state.cursor_position);
             return InputResult::Consumed;
         }
 
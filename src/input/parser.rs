@@ -6,6 +6,9 @@ use crate::input::keymap::KeymapRegistry;
 use crate::input::modes::InputMode;
 use crate::message::Message;
 
+/// Largest count prefix; more is taken as this
+const MAX_COUNT: usize = 999;
+
 /// Result of processing a key against the keymap.
 pub enum KeyParseOutcome {
     Matched(Message),
@@ -14,21 +17,52 @@ pub enum KeyParseOutcome {
 }
 
 /// Parses multi-key sequences (e.g., `gg`, `<C-b>`) with a timeout.
+///
+/// In review, digits typed before a sequence are a count: `5j` comes out as
+/// `Message::Repeat(5, Navigate(Down))`.
 pub struct KeySequenceParser {
     buffer: Vec<String>,
+    /// Count typed ahead of the sequence
+    count: Option<usize>,
     last_key_time: Instant,
     timeout: Duration,
+    /// Keep the buffer past the timeout, while its continuations are shown
+    held: bool,
 }
 
 impl KeySequenceParser {
     pub fn new(timeout: Duration) -> Self {
         Self {
             buffer: Vec::new(),
+            count: None,
             last_key_time: Instant::now(),
             timeout,
+            held: false,
         }
     }
 
+    /// The keys of the unfinished sequence, if any
+    pub fn pending(&self) -> &[String] {
+        &self.buffer
+    }
+
+    /// How long ago the last key was pressed
+    pub fn idle(&self) -> Duration {
+        self.last_key_time.elapsed()
+    }
+
+    /// Keep the unfinished sequence past the timeout, until the next key
+    pub fn hold(&mut self) {
+        self.held = !self.buffer.is_empty();
+    }
+
+    /// Drop the unfinished sequence
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.count = None;
+        self.held = false;
+    }
+
     pub fn process(
         &mut self,
         key: KeyEvent,
@@ -36,17 +70,35 @@ impl KeySequenceParser {
         mode: InputMode,
     ) -> KeyParseOutcome {
         // Clear buffer on timeout
-        if self.last_key_time.elapsed() > self.timeout {
+        if !self.held && self.last_key_time.elapsed() > self.timeout {
             self.buffer.clear();
+            self.count = None;
         }
+        self.held = false;
 
         self.last_key_time = Instant::now();
+
+        // Count prefix? A leading 0 isn't one
+        if mode == InputMode::DiffReview && self.buffer.is_empty() && key.modifiers.is_empty() {
+            if let KeyCode::Char(c @ '0'..='9') = key.code {
+                if c != '0' || self.count.is_some() {
+                    let digit = c.to_digit(10).unwrap_or(0) as usize;
+                    let count = self.count.unwrap_or(0).saturating_mul(10) + digit;
+                    self.count = Some(count.min(MAX_COUNT));
+                    return KeyParseOutcome::Pending;
+                }
+            }
+        }
+
         self.buffer.push(key_to_token(key));
 
         // Exact match?
         if let Some(msg) = keymap.lookup(mode, &self.buffer) {
             self.buffer.clear();
-            return KeyParseOutcome::Matched(msg);
+            return KeyParseOutcome::Matched(match self.count.take() {
+                Some(count) => Message::Repeat(count, Box::new(msg)),
+                None => msg,
+            });
         }
 
         // Prefix match?
@@ -56,16 +108,18 @@ impl KeySequenceParser {
 
         // No match: clear buffer
         self.buffer.clear();
+        self.count = None;
         KeyParseOutcome::NoMatch
     }
 }
 
-fn key_to_token(key: KeyEvent) -> String {
+/// The keymap token for `key`, such as `y`, `<Enter>` or `<C-b>`
+pub fn key_to_token(key: KeyEvent) -> String {
     let modifiers = key.modifiers;
     match key.code {
         KeyCode::Char(c) => {
             if modifiers.contains(KeyModifiers::CONTROL) {
-                format!("<C-{}>", c.to_ascii_uppercase())
+                format!("<C-{}>", c.to_ascii_lowercase())
             } else if modifiers.contains(KeyModifiers::ALT) {
                 format!("<A-{}>", c)
             } else {
@@ -74,12 +128,129 @@ fn key_to_token(key: KeyEvent) -> String {
         }
         KeyCode::Enter => "<Enter>".to_string(),
         KeyCode::Esc => "<Esc>".to_string(),
+        KeyCode::Tab if modifiers.contains(KeyModifiers::CONTROL) => "<C-Tab>".to_string(),
         KeyCode::Tab => "<Tab>".to_string(),
         KeyCode::Backspace => "<Backspace>".to_string(),
+        KeyCode::Left if modifiers.contains(KeyModifiers::CONTROL) => "<C-Left>".to_string(),
+        KeyCode::Right if modifiers.contains(KeyModifiers::CONTROL) => "<C-Right>".to_string(),
         KeyCode::Left => "<Left>".to_string(),
         KeyCode::Right => "<Right>".to_string(),
         KeyCode::Up => "<Up>".to_string(),
         KeyCode::Down => "<Down>".to_string(),
+        KeyCode::F(n) => format!("<F{}>", n),
         other => format!("<{:?}>", other),
     }
 }
+
+/// The key a token from [`key_to_token`] stands for
+pub fn token_to_key(token: &str) -> Option<KeyEvent> {
+    let mut chars = token.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    }
+    let name = token.strip_prefix('<')?.strip_suffix('>')?;
+    let (modifiers, name) = if let Some(rest) = name.strip_prefix("C-") {
+        (KeyModifiers::CONTROL, rest)
+    } else if let Some(rest) = name.strip_prefix("A-") {
+        (KeyModifiers::ALT, rest)
+    } else {
+        (KeyModifiers::NONE, name)
+    };
+    let mut chars = name.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match name {
+            "Enter" => KeyCode::Enter,
+            "Esc" => KeyCode::Esc,
+            "Tab" => KeyCode::Tab,
+            "Backspace" => KeyCode::Backspace,
+            "Left" => KeyCode::Left,
+            "Right" => KeyCode::Right,
+            "Up" => KeyCode::Up,
+            "Down" => KeyCode::Down,
+            "Home" => KeyCode::Home,
+            "End" => KeyCode::End,
+            "PageUp" => KeyCode::PageUp,
+            "PageDown" => KeyCode::PageDown,
+            "Delete" => KeyCode::Delete,
+            "BackTab" => KeyCode::BackTab,
+            _ => KeyCode::F(name.strip_prefix('F')?.parse().ok()?),
+        },
+    };
+    Some(KeyEvent::new(code, modifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Direction;
+
+    #[test]
+    fn test_prefix_stays_pending_until_completed() {
+        let keymap = KeymapRegistry::default_vim();
+        let mut parser = KeySequenceParser::new(Duration::from_secs(60));
+        let g = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE);
+        assert!(matches!(
+            parser.process(g, &keymap, InputMode::Normal),
+            KeyParseOutcome::Pending
+        ));
+        assert_eq!(parser.pending(), ["g"]);
+        assert!(matches!(
+            parser.process(g, &keymap, InputMode::Normal),
+            KeyParseOutcome::Matched(Message::ScrollTo(0))
+        ));
+        assert!(parser.pending().is_empty());
+    }
+
+    #[test]
+    fn test_count_prefix_wraps_the_message() {
+        let keymap = KeymapRegistry::default_vim();
+        let mut parser = KeySequenceParser::new(Duration::from_secs(60));
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let mut press = |c| parser.process(key(c), &keymap, InputMode::DiffReview);
+
+        assert!(matches!(press('1'), KeyParseOutcome::Pending));
+        assert!(matches!(press('2'), KeyParseOutcome::Pending));
+        match press('j') {
+            KeyParseOutcome::Matched(Message::Repeat(12, message)) => {
+                assert!(matches!(*message, Message::Navigate(Direction::Down)))
+            }
+            _ => panic!("expected a repeated move"),
+        }
+        assert!(matches!(
+            press('y'),
+            KeyParseOutcome::Matched(Message::AcceptHunk(_))
+        ));
+        assert!(matches!(
+            press('.'),
+            KeyParseOutcome::Matched(Message::RepeatLast)
+        ));
+        // 0 only counts after another digit
+        assert!(matches!(press('0'), KeyParseOutcome::NoMatch));
+
+        // Digits keep their bindings where they have them
+        assert!(matches!(
+            parser.process(key('3'), &keymap, InputMode::Confirmation),
+            KeyParseOutcome::Matched(Message::ToggleChecklistItem(2))
+        ));
+    }
+
+    #[test]
+    fn test_tokens_name_their_keys() {
+        let keys = [
+            KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('G'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL),
+            KeyEvent::new(KeyCode::Char('j'), KeyModifiers::ALT),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Right, KeyModifiers::CONTROL),
+            KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::F(2), KeyModifiers::NONE),
+        ];
+        for key in keys {
+            assert_eq!(token_to_key(&key_to_token(key)), Some(key));
+        }
+        assert_eq!(key_to_token(keys[7]), "<F2>");
+        assert_eq!(token_to_key("<Nonsense>"), None);
+    }
+}
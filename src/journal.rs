@@ -0,0 +1,290 @@
+//! Append-only journal of applied reviews
+//!
+//! Every apply (successful or not) is recorded as one JSON line in
+//! `journal.jsonl`: when it happened, which session, provider and prompt produced
+//! it, the files and hunks it touched, the backups it made and how it ended. The
+//! `:history` view lists the entries and can restore any entry's backups.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::file_ops::{atomic_write, BackupSet};
+use crate::state::Hunk;
+
+/// Where the journal is kept
+pub fn journal_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("journal.jsonl")
+}
+
+/// A file's content as it was before an apply
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupRecord {
+    pub file: PathBuf,
+    pub backup: PathBuf,
+}
+
+/// One apply, as written to the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub session: Option<String>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Hash of the prompt that produced the changes (the prompt itself stays private)
+    #[serde(default)]
+    pub prompt_hash: Option<String>,
+    pub files: Vec<PathBuf>,
+    /// Hash of each applied hunk's file and lines
+    #[serde(default)]
+    pub hunks: Vec<String>,
+    #[serde(default)]
+    pub backups: Vec<BackupRecord>,
+    /// Summary on success, or the error
+    pub result: String,
+    #[serde(default)]
+    pub success: bool,
+}
+
+impl JournalEntry {
+    /// Entry for applying `hunks`, before its outcome is known
+    pub fn new(
+        session: Option<String>,
+        provider: Option<String>,
+        prompt: Option<&str>,
+        hunks: &[&Hunk],
+    ) -> Self {
+        let mut files: Vec<PathBuf> = hunks.iter().map(|h| h.file_path.clone()).collect();
+        files.sort();
+        files.dedup();
+        Self {
+            timestamp: Utc::now(),
+            session,
+            provider,
+            prompt_hash: prompt.map(hash_text),
+            files,
+            hunks: hunks.iter().map(|h| hunk_hash(h)).collect(),
+            backups: Vec::new(),
+            result: String::new(),
+            success: false,
+        }
+    }
+
+    /// Record a successful apply and the backups it made
+    pub fn succeeded(mut self, summary: String, backups: &[(PathBuf, PathBuf)]) -> Self {
+        self.backups = backups
+            .iter()
+            .map(|(file, backup)| BackupRecord {
+                file: file.clone(),
+                backup: backup.clone(),
+            })
+            .collect();
+        self.result = summary;
+        self.success = true;
+        self
+    }
+
+    /// Record a failed apply; its changes were rolled back
+    pub fn failed(mut self, error: &anyhow::Error) -> Self {
+        self.result = format!("{:#}", error);
+        self.success = false;
+        self
+    }
+
+    /// Whether the backups this entry points to are still all there
+    pub fn restorable(&self) -> bool {
+        !self.backups.is_empty() && self.backups.iter().all(|b| b.backup.is_file())
+    }
+}
+
+/// Stable 64-bit FNV-1a hash, hex encoded (the same across builds and platforms)
+fn hash_text(text: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Hash identifying a hunk by its file and changed lines
+fn hunk_hash(hunk: &Hunk) -> String {
+    let mut text = hunk.file_path.display().to_string();
+    for change in &hunk.changes {
+        text.push('\n');
+        text.push_str(&format!("{:?} {}", change.tag, change.content));
+    }
+    hash_text(&text)
+}
+
+/// Add `entry` to the end of the journal at `path`
+pub fn append(path: &Path, entry: &JournalEntry) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let _lock = crate::lock::lock_exclusive(path)?;
+    let line = serde_json::to_string(entry)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// All entries in the journal at `path`, newest first. Lines that don't parse
+/// (e.g. from a newer version) are skipped.
+pub fn load(path: &Path) -> Result<Vec<JournalEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let _lock = crate::lock::lock_shared(path)?;
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut entries: Vec<JournalEntry> = text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Put back the files of `entry` as they were before it was applied.
+///
+/// The current contents are backed up first and the restore is journaled at
+/// `journal` as an entry of its own, so it can be undone from `:history` too.
+pub fn restore(journal: &Path, entry: &JournalEntry) -> Result<JournalEntry> {
+    if entry.backups.is_empty() {
+        return Err(anyhow!("This apply made no backups to restore"));
+    }
+    if let Some(missing) = entry.backups.iter().find(|b| !b.backup.is_file()) {
+        return Err(anyhow!(
+            "Backup of {} is gone: {}",
+            missing.file.display(),
+            missing.backup.display()
+        ));
+    }
+
+    let current: Vec<PathBuf> = entry
+        .backups
+        .iter()
+        .map(|b| b.file.clone())
+        .filter(|f| f.is_file())
+        .collect();
+    let before = BackupSet::create(&current).context("Failed to back up current files")?;
+
+    for record in &entry.backups {
+        let content = std::fs::read_to_string(&record.backup)
+            .with_context(|| format!("Failed to read backup {}", record.backup.display()))?;
+        if let Err(e) = atomic_write(&record.file, &content) {
+            let _ = before.restore_all();
+            return Err(e).context(format!("Failed to restore {}", record.file.display()));
+        }
+    }
+
+    let restored = JournalEntry {
+        timestamp: Utc::now(),
+        session: entry.session.clone(),
+        provider: entry.provider.clone(),
+        prompt_hash: None,
+        files: entry.backups.iter().map(|b| b.file.clone()).collect(),
+        hunks: Vec::new(),
+        backups: Vec::new(),
+        result: String::new(),
+        success: false,
+    }
+    .succeeded(
+        format!(
+            "Restored {} files from {}",
+            entry.backups.len(),
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S")
+        ),
+        &before.pairs(),
+    );
+    append(journal, &restored)?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{ChangeTag, HunkRange, HunkStatus, LineChange};
+
+    fn hunk(path: &Path, line: &str) -> Hunk {
+        Hunk {
+            id: 0,
+            file_path: path.to_path_buf(),
+            old: HunkRange::default(),
+            new: HunkRange::default(),
+            changes: vec![LineChange {
+                tag: ChangeTag::Insert,
+                content: line.into(),
+                old_line_num: None,
+                new_line_num: Some(1),
+            }],
+            status: HunkStatus::Accepted,
+            drifted: false,
+            comment: None,
+            auto_accepted: None,
+        }
+    }
+
+    #[test]
+    fn test_journal_round_trip_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("journal.jsonl");
+        let file = dir.path().join("main.rs");
+        let backup = dir.path().join("main.rs.bak");
+        std::fs::write(&backup, "old\n").unwrap();
+        std::fs::write(&file, "new\n").unwrap();
+
+        let hunks = [hunk(&file, "new")];
+        let refs: Vec<&Hunk> = hunks.iter().collect();
+        let entry = JournalEntry::new(
+            Some("s1".into()),
+            Some("Claude".into()),
+            Some("add main"),
+            &refs,
+        )
+        .succeeded(
+            "Applied 1 hunks to 1 files".into(),
+            &[(file.clone(), backup.clone())],
+        );
+        append(&journal, &entry).unwrap();
+        append(
+            &journal,
+            &JournalEntry::new(None, None, None, &refs).failed(&anyhow!("boom")),
+        )
+        .unwrap();
+        std::fs::write(
+            &journal,
+            std::fs::read_to_string(&journal).unwrap() + "garbage\n",
+        )
+        .unwrap();
+
+        let entries = load(&journal).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].result, "boom");
+        assert_eq!(entries[1], entry);
+        assert_eq!(
+            entry.prompt_hash.as_deref(),
+            Some(hash_text("add main").as_str())
+        );
+        assert_eq!(entry.hunks, vec![hunk_hash(&hunks[0])]);
+
+        let restored = restore(&journal, &entry).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "old\n");
+        assert_eq!(load(&journal).unwrap()[0], restored);
+
+        // The restore can itself be undone
+        restore(&journal, &restored).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new\n");
+    }
+}
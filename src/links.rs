@@ -0,0 +1,120 @@
+//! URLs in chat messages
+//!
+//! Links are found in a message's text, listed in the message view and opened
+//! with the configured `url_opener` (the platform's opener by default). They are
+//! also written as OSC 8 hyperlinks, so terminals that support them open a link
+//! on click without going through ZCode.
+
+use anyhow::{anyhow, Result};
+use std::process::{Command, Stdio};
+
+/// The `http`/`https` URLs in `text`, in order of first appearance
+pub fn find_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = find_scheme(rest) {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '`'))
+            .unwrap_or(candidate.len());
+        let url = trim_url(&candidate[..end]);
+        if url
+            .split_once("://")
+            .is_some_and(|(_, host)| !host.is_empty())
+            && !urls.iter().any(|u| u == url)
+        {
+            urls.push(url.to_string());
+        }
+        rest = &candidate[end..];
+    }
+    urls
+}
+
+fn find_scheme(text: &str) -> Option<usize> {
+    let http = text.find("http://");
+    let https = text.find("https://");
+    match (http, https) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Drop punctuation that ends the sentence or markup around a URL, keeping
+/// closing parentheses that belong to it (e.g. Wikipedia links)
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let Some(last) = url.chars().last() else {
+            return url;
+        };
+        let unbalanced = match last {
+            ')' => url.matches(')').count() > url.matches('(').count(),
+            ']' => url.matches(']').count() > url.matches('[').count(),
+            '.' | ',' | ';' | ':' | '!' | '?' | '\'' | '*' | '_' => true,
+            _ => false,
+        };
+        if !unbalanced {
+            return url;
+        }
+        url = &url[..url.len() - last.len_utf8()];
+    }
+}
+
+/// `text` wrapped in an OSC 8 hyperlink to `url`
+pub fn osc8(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// The platform's command for opening a URL in the default browser
+fn default_opener() -> &'static [&'static str] {
+    if cfg!(target_os = "macos") {
+        &["open"]
+    } else if cfg!(windows) {
+        &["cmd", "/C", "start", ""]
+    } else {
+        &["xdg-open"]
+    }
+}
+
+/// Open `url` with `opener` (a command and its arguments, split on whitespace;
+/// the URL is added last), or with the platform's opener when `None`
+pub fn open(url: &str, opener: Option<&str>) -> Result<()> {
+    let parts: Vec<&str> = match opener {
+        Some(opener) => opener.split_whitespace().collect(),
+        None => default_opener().to_vec(),
+    };
+    let (program, args) = parts
+        .split_first()
+        .ok_or_else(|| anyhow!("url_opener is empty"))?;
+    Command::new(program)
+        .args(args)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run {}: {}", program, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_urls_trims_surrounding_punctuation() {
+        let text = "See https://docs.rs/ratatui. Also (https://github.com/a/b/pull/12), \
+                    [docs](https://example.com/x_(y)) and <http://localhost:8080/>; \
+                    again https://docs.rs/ratatui!";
+        assert_eq!(
+            find_urls(text),
+            vec![
+                "https://docs.rs/ratatui",
+                "https://github.com/a/b/pull/12",
+                "https://example.com/x_(y)",
+                "http://localhost:8080/",
+            ]
+        );
+        assert!(find_urls("no links, just http:// and text").is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+//! Coordination between concurrently running ZCode instances
+//!
+//! Several instances (e.g. one per terminal tab) share `sessions.json` and the
+//! backup directory. Each instance registers a PID lockfile so others can tell it
+//! is alive, and writes to shared files happen under an advisory lock on a
+//! sibling `.lock` file.
+//!
+//! The lockfiles double as the list of active instances: each also records the
+//! session its instance works in, so two instances never continue the same
+//! session. Inside Zellij, each pane remembers its own last session.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File, TryLockError};
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+
+pub use zcode_core::lock::{lock_exclusive, lock_shared};
+
+/// Directory holding one lockfile per running instance
+pub fn instances_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("instances")
+}
+
+/// The Zellij pane this process runs in, unique across Zellij sessions
+pub fn pane_id() -> Option<String> {
+    let pane = std::env::var("ZELLIJ_PANE_ID").ok()?;
+    let session = std::env::var("ZELLIJ_SESSION_NAME").unwrap_or_default();
+    Some(format!("{}-{}", session, pane))
+}
+
+/// Marks this process as a running instance for as long as it is held
+#[derive(Debug)]
+pub struct InstanceLock {
+    dir: PathBuf,
+    path: PathBuf,
+    file: File,
+    pane: Option<String>,
+    session: Option<String>,
+}
+
+impl InstanceLock {
+    /// Register this process in `dir` as `instance-<pid>.lock`
+    pub fn acquire(dir: &Path) -> Result<Self> {
+        Self::acquire_in_pane(dir, pane_id())
+    }
+
+    /// Register this process, running in `pane` when inside Zellij
+    pub fn acquire_in_pane(dir: &Path, pane: Option<String>) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let pid = std::process::id();
+        let path = instance_path(dir, pid);
+        let mut file = File::create(&path)
+            .with_context(|| format!("Failed to create instance lock {}", path.display()))?;
+        file.lock()
+            .with_context(|| format!("Failed to lock {}", path.display()))?;
+        writeln!(file, "{}", pid)?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            path,
+            file,
+            pane,
+            session: None,
+        })
+    }
+
+    /// The session this instance's pane last worked in
+    pub fn pane_session(&self) -> Option<String> {
+        let pane = self.pane.as_ref()?;
+        let id = fs::read_to_string(pane_session_path(&self.dir, pane)).ok()?;
+        Some(id.trim().to_string()).filter(|id| !id.is_empty())
+    }
+
+    /// Record `session` as the one this instance (and its pane) works in
+    pub fn set_session(&mut self, session: Option<&str>) -> Result<()> {
+        if self.session.as_deref() == session {
+            return Ok(());
+        }
+        self.session = session.map(str::to_string);
+
+        self.file.set_len(0)?;
+        self.file.rewind()?;
+        writeln!(self.file, "{}", std::process::id())?;
+        if let Some(session) = session {
+            writeln!(self.file, "{}", session)?;
+        }
+        if let (Some(pane), Some(session)) = (&self.pane, session) {
+            fs::write(pane_session_path(&self.dir, pane), session)?;
+        }
+        Ok(())
+    }
+
+    /// Sessions other live instances are working in
+    pub fn other_sessions(&self) -> Vec<String> {
+        self.other_instances()
+            .into_iter()
+            .filter_map(|pid| {
+                let content = fs::read_to_string(instance_path(&self.dir, pid)).ok()?;
+                content.lines().nth(1).map(str::to_string)
+            })
+            .collect()
+    }
+
+    /// PIDs of other live instances; lockfiles left behind by crashed ones are removed
+    pub fn other_instances(&self) -> Vec<u32> {
+        let own = std::process::id();
+        let mut pids: Vec<u32> = live_instances(&self.dir)
+            .into_iter()
+            .filter(|&pid| pid != own)
+            .collect();
+        pids.sort_unstable();
+        pids
+    }
+}
+
+fn instance_path(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("instance-{}.lock", pid))
+}
+
+/// File remembering the last session of a Zellij pane
+fn pane_session_path(dir: &Path, pane: &str) -> PathBuf {
+    let name: String = pane
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("pane-{}.session", name))
+}
+
+/// PIDs of the instances registered in `dir` that are still running, oldest
+/// first. Lockfiles left behind by crashed instances are removed.
+pub fn live_instances(dir: &Path) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut live = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(pid) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_prefix("instance-"))
+            .and_then(|n| n.strip_suffix(".lock"))
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            continue;
+        };
+
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        match file.try_lock() {
+            // Nobody holds it: the owning process is gone
+            Ok(()) => {
+                let _ = fs::remove_file(&path);
+                let _ = fs::remove_dir_all(crate::pipe::inbox_dir(dir, pid));
+            }
+            Err(TryLockError::WouldBlock) => {
+                let started = file.metadata().and_then(|m| m.modified()).ok();
+                live.push((started, pid));
+            }
+            Err(TryLockError::Error(_)) => {}
+        }
+    }
+    live.sort_unstable();
+    live.into_iter().map(|(_, pid)| pid).collect()
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_other_instances_skips_self_and_clears_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = InstanceLock::acquire(dir.path()).unwrap();
+
+        // A lockfile nobody holds, as left by a crashed instance
+        let stale = dir.path().join("instance-999999.lock");
+        fs::write(&stale, "999999\n").unwrap();
+
+        // A live instance (held through a separate handle)
+        let live = dir.path().join("instance-4242.lock");
+        let live_file = File::create(&live).unwrap();
+        live_file.lock().unwrap();
+
+        assert_eq!(lock.other_instances(), vec![4242]);
+        assert!(!stale.exists());
+
+        let own = lock.path.clone();
+        drop(lock);
+        assert!(!own.exists());
+    }
+
+    #[test]
+    fn test_instances_record_their_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut lock = InstanceLock::acquire_in_pane(dir.path(), Some("main-3".into())).unwrap();
+        assert_eq!(lock.pane_session(), None);
+
+        lock.set_session(Some("20250101_a")).unwrap();
+        assert_eq!(lock.pane_session().as_deref(), Some("20250101_a"));
+        let own = fs::read_to_string(&lock.path).unwrap();
+        assert_eq!(own, format!("{}\n20250101_a\n", std::process::id()));
+
+        // Another live instance working in a session
+        let other = dir.path().join("instance-4242.lock");
+        let other_file = File::create(&other).unwrap();
+        other_file.lock().unwrap();
+        fs::write(&other, "4242\n20250101_b\n").unwrap();
+        assert_eq!(lock.other_sessions(), vec!["20250101_b".to_string()]);
+    }
+}
@@ -0,0 +1,138 @@
+//! Keyboard macros for the diff review
+//!
+//! As in Vim, `q<reg>` starts recording the keys pressed into register
+//! `<reg>` (a-z or 0-9), `q` stops, `@<reg>` replays them and `@@` replays
+//! the register played last. Keys are kept as keymap tokens (`a`, `<C-d>`,
+//! `<Enter>`) so registers can be stored with the session.
+
+use std::collections::BTreeMap;
+
+/// What a key did to the macro state in the review
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroEvent {
+    /// `q` or `@` is waiting for its register
+    Pending,
+    Started(char),
+    /// Recording into the register stopped, with the number of keys kept
+    Stopped(char, usize),
+    Play(char),
+    /// `@` named a register that holds nothing
+    Empty(char),
+    /// `q` or `@` was followed by something that isn't a register
+    Cancelled,
+}
+
+#[derive(Debug, Default)]
+pub struct Macros {
+    pub registers: BTreeMap<char, Vec<String>>,
+    recording: Option<(char, Vec<String>)>,
+    /// `q` or `@` waiting for its register
+    pending: Option<char>,
+    last_played: Option<char>,
+    /// Set while a register plays, so its keys don't start or play macros
+    pub replaying: bool,
+}
+
+fn is_register(token: &str) -> Option<char> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_lowercase() || c.is_ascii_digit() => Some(c),
+        _ => None,
+    }
+}
+
+impl Macros {
+    /// The register being recorded into, if any
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    /// Handle `token` pressed in the review. Returns `None` when the key
+    /// isn't for the macros and should go to the keymap.
+    pub fn review_key(&mut self, token: &str) -> Option<MacroEvent> {
+        if let Some(prefix) = self.pending.take() {
+            let register = if prefix == '@' && token == "@" {
+                self.last_played
+            } else {
+                is_register(token)
+            };
+            let Some(register) = register else {
+                return Some(MacroEvent::Cancelled);
+            };
+            if prefix == 'q' {
+                self.recording = Some((register, Vec::new()));
+                return Some(MacroEvent::Started(register));
+            }
+            if self.registers.get(&register).is_none_or(Vec::is_empty) {
+                return Some(MacroEvent::Empty(register));
+            }
+            self.last_played = Some(register);
+            return Some(MacroEvent::Play(register));
+        }
+
+        match token {
+            "q" => match self.recording.take() {
+                Some((register, keys)) => {
+                    let count = keys.len();
+                    self.registers.insert(register, keys);
+                    Some(MacroEvent::Stopped(register, count))
+                }
+                None => {
+                    self.pending = Some('q');
+                    Some(MacroEvent::Pending)
+                }
+            },
+            "@" => {
+                self.pending = Some('@');
+                Some(MacroEvent::Pending)
+            }
+            _ => None,
+        }
+    }
+
+    /// Add `token` to the register being recorded, if any
+    pub fn record(&mut self, token: &str) {
+        if let Some((_, keys)) = &mut self.recording {
+            keys.push(token.to_string());
+        }
+    }
+
+    /// Forget a recording in progress and any half-typed `q` or `@`
+    pub fn reset(&mut self) {
+        self.recording = None;
+        self.pending = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay_registers() {
+        let mut macros = Macros::default();
+        assert_eq!(macros.review_key("q"), Some(MacroEvent::Pending));
+        assert_eq!(macros.review_key("a"), Some(MacroEvent::Started('a')));
+        assert_eq!(macros.recording(), Some('a'));
+
+        for token in ["j", "y", "<C-d>"] {
+            assert_eq!(macros.review_key(token), None);
+            macros.record(token);
+        }
+        assert_eq!(macros.review_key("q"), Some(MacroEvent::Stopped('a', 3)));
+        assert_eq!(macros.recording(), None);
+        assert_eq!(macros.registers[&'a'], ["j", "y", "<C-d>"]);
+
+        assert_eq!(macros.review_key("@"), Some(MacroEvent::Pending));
+        assert_eq!(macros.review_key("b"), Some(MacroEvent::Empty('b')));
+        macros.review_key("@");
+        assert_eq!(macros.review_key("a"), Some(MacroEvent::Play('a')));
+        macros.review_key("@");
+        assert_eq!(macros.review_key("@"), Some(MacroEvent::Play('a')));
+
+        // Only a-z and 0-9 name registers
+        macros.review_key("q");
+        assert_eq!(macros.review_key("<Esc>"), Some(MacroEvent::Cancelled));
+        assert_eq!(macros.recording(), None);
+    }
+}
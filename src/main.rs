@@ -5,48 +5,98 @@
 #![allow(unused_variables)]
 
 mod app;
+mod apply_summary;
+mod auto_accept;
+mod batch;
+mod chat_log;
+mod check;
+mod checklist;
+mod clipboard;
 mod components;
 mod config;
-mod diff;
+mod context;
+mod detection_cache;
 mod error;
 mod events;
-mod executor;
-mod file_ops;
+mod git;
+mod guard;
+mod i18n;
 mod input;
+mod journal;
+mod links;
+mod lock;
+mod macros;
 mod message;
 mod model;
 mod neovim;
-mod parsers;
-mod providers;
+mod perf;
+mod pipe;
+mod pre_apply;
+mod provenance;
+mod reindent;
+mod scheduler;
 mod session;
+mod session_archive;
 mod state;
+mod symbols;
+mod syntax;
+mod telemetry;
+mod templates;
+mod test_results;
 mod ui;
+mod watcher;
+
+// The diff/parse/apply pipeline lives in the `zcode-core` library
+use zcode_core::{
+    activity, diff, encoding, executor, file_cache, file_ops, parsers, preamble, providers,
+    sandbox, sections,
+};
 
 use anyhow::Result;
 use crossterm::{
     cursor,
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers,
+        KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    },
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
+use std::collections::BTreeMap;
 use std::io::{self, Write};
 use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use app::App;
 
+/// Save the terminal title on the terminal's title stack (xterm; ignored elsewhere)
+const PUSH_TITLE: &str = "\x1b[22;0t";
+/// Restore the title saved by `PUSH_TITLE`
+const POP_TITLE: &str = "\x1b[23;0t";
+/// Whether `run` pushed keyboard enhancement flags that have to be popped
+static KEYBOARD_FLAGS_PUSHED: AtomicBool = AtomicBool::new(false);
+
 /// Restore terminal to normal state
 /// This is called on normal exit and on panic
 fn restore_terminal() {
     // Best effort to restore terminal - ignore errors
     let _ = disable_raw_mode();
+    if KEYBOARD_FLAGS_PUSHED.swap(false, Ordering::SeqCst) {
+        let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+    }
     let _ = execute!(
         io::stdout(),
         LeaveAlternateScreen,
         DisableMouseCapture,
+        cursor::SetCursorStyle::DefaultUserShape,
         cursor::Show
     );
+    // Give back the title zcode replaced while running
+    let _ = io::stdout().write_all(POP_TITLE.as_bytes());
     // Flush stdout to ensure all escape sequences are sent
     let _ = io::stdout().flush();
 }
@@ -67,6 +117,26 @@ async fn main() -> Result<()> {
     // Install panic hook early to catch any panics during setup
     install_panic_hook();
 
+    // `zcode pipe` hands text to a running instance instead of starting one
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("pipe") {
+        if let Err(err) = run_pipe(&args[1..]) {
+            eprintln!("Error: {:#}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    // `zcode check` validates a reply for CI and prints a JSON report
+    if args.first().map(String::as_str) == Some("check") {
+        match check::run(&args[1..]).await {
+            Ok(code) => std::process::exit(code),
+            Err(err) => {
+                eprintln!("Error: {:#}", err);
+                std::process::exit(check::EXIT_UNCHECKED);
+            }
+        }
+    }
+
     // Check if we're running in a terminal
     if !atty::is(atty::Stream::Stdout) {
         eprintln!("Error: zcode must be run in a terminal (TTY)");
@@ -79,8 +149,11 @@ async fn main() -> Result<()> {
         std::process::exit(1);
     }
 
+    // A Zellij layout passes options as `key=value` arguments
+    let options = config::layout_options(&args);
+
     // Run the application with proper terminal handling
-    let result = run().await;
+    let result = run(&options).await;
 
     // Always restore terminal on exit
     restore_terminal();
@@ -94,7 +167,32 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn run() -> Result<()> {
+/// `zcode pipe [--pid PID] [--] [TEXT...]`: send TEXT and/or stdin to a running
+/// instance, which opens a prompt pre-filled with it
+fn run_pipe(args: &[String]) -> Result<()> {
+    let mut pid = None;
+    let mut text = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pid" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--pid needs a PID"))?;
+                pid = Some(value.parse()?);
+            }
+            "--" => text.extend(args.by_ref().cloned()),
+            _ => text.push(arg.clone()),
+        }
+    }
+
+    let text = pipe::collect_text(&text)?;
+    let pid = pipe::send(&lock::instances_dir(), pid, &text)?;
+    eprintln!("Sent {} bytes to ZCode (PID {})", text.len(), pid);
+    Ok(())
+}
+
+async fn run(options: &BTreeMap<String, String>) -> Result<()> {
     // Setup terminal
     enable_raw_mode().map_err(|e| {
         anyhow::anyhow!(
@@ -110,6 +208,15 @@ async fn run() -> Result<()> {
         EnableMouseCapture,
         cursor::Hide
     )?;
+    stdout.write_all(PUSH_TITLE.as_bytes())?;
+    // Lets terminals with the kitty keyboard protocol report keys like Ctrl+Tab
+    if matches!(terminal::supports_keyboard_enhancement(), Ok(true)) {
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )?;
+        KEYBOARD_FLAGS_PUSHED.store(true, Ordering::SeqCst);
+    }
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
@@ -118,7 +225,7 @@ async fn run() -> Result<()> {
     terminal.clear()?;
 
     // Create app
-    let mut app = App::new()?;
+    let mut app = App::new(options)?;
 
     // Run the application
     app.run(&mut terminal).await
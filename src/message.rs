@@ -11,12 +11,30 @@ pub enum Direction {
     Right,
 }
 
+impl Direction {
+    /// One step down (1) or up (-1); left and right don't move vertically
+    pub fn delta(self) -> isize {
+        match self {
+            Direction::Up => -1,
+            Direction::Down => 1,
+            Direction::Left | Direction::Right => 0,
+        }
+    }
+}
+
 /// Messages drive updates to the application model (Elm-style update).
 #[derive(Debug, Clone)]
 pub enum Message {
     // Navigation
     Navigate(Direction),
     ScrollTo(usize),
+    ScrollChat(Direction),
+    ScrollDiff(Direction),
+    /// Scroll the open confirmation or error dialog a row, or a page
+    ScrollDialog(Direction),
+    PageDialog(Direction),
+    /// Start or stop selecting rows of the confirmation or error dialog
+    ToggleDialogSelection,
 
     // Modes
     SetMode(Mode),
@@ -30,6 +48,11 @@ pub enum Message {
 
     // Prompt actions
     SubmitPrompt(String),
+    /// `:foreach`: run the prompt once per file matching the glob
+    RunBatch {
+        pattern: String,
+        prompt: String,
+    },
     CancelPrompt,
 
     // Diff actions
@@ -38,17 +61,114 @@ pub enum Message {
     AcceptAll,
     RejectAll,
     ApplyChanges,
+    ConfirmApply,
+    ToggleIgnoreOverride,
+    /// Leave the selected hunk's file out of the apply, or include it again
+    ToggleSkipFile,
+    /// Show the definition of a symbol used by the selected hunk in the sidebar
+    JumpToDefinition,
+    /// Start writing a comment on the selected hunk (`:comment`)
+    CommentHunk,
+    /// Send the rejected/commented hunks back to the provider to be redone
+    RequestFixes,
+    /// Start changing where the selected hunk's file is written (`:rename`)
+    RenameTarget,
+    /// Cut long lines off in the diff view, or soft-wrap them again
+    ToggleDiffWrap,
+    /// Compare the review with `HEAD`, or with the working tree again
+    ToggleDiffBase,
+    /// Show the current file side by side instead of as a unified diff
+    ToggleSideBySide,
+    /// Show or hide the old and new line numbers in the diff
+    ToggleLineNumbers,
+    /// `]`/`[` motions between hunks of one kind
+    HunkMotion(crate::input::Action),
+    // The selected hunk's group of related hunks
+    AcceptGroup,
+    RejectGroup,
+    ToggleGroupCollapsed,
+    /// Check or uncheck an item of the apply confirmation's checklist
+    ToggleChecklistItem(usize),
+    /// Play the keys recorded in a macro register (`@<reg>`)
+    ReplayMacro(char),
+    /// Do the message this many times, from a count prefix such as `5j`
+    Repeat(usize, Box<Message>),
+    /// `.`: do the last accept or reject again
+    RepeatLast,
 
     // UI actions
     ToggleSidebar,
+    /// Switch prompts between asking for edits and asking for an answer
+    TogglePromptMode,
+    /// Cycle the sidebar preview of a file with pending changes: current, proposed, diff
+    CycleSidebarView,
+    /// Show the next (or, negative, previous) pinned or recent file in the sidebar
+    CyclePin(isize),
+    /// Move the wide layout's chat/diff split by this many percent
+    AdjustChatSplit(i16),
+    /// Widen a pane of the wide layout, or with `None` go back to the split
+    FocusPane(Option<crate::ui::layout::Pane>),
+    /// Focus the chat, then the diff, then neither
+    CycleFocus,
     ToggleHelp,
+    ToggleReplyExpanded,
+    /// Show the message in view in full, with its links selectable
+    OpenMessageDetail,
+    /// Open the link selected in the message view, copying it if that fails
+    OpenLink,
+    CopyLink,
+    /// Review the code block selected in the message view against a file
+    DiffCodeBlock(PathBuf),
     Search(String),
 
     // Editor actions
-    OpenEditor { path: PathBuf, line: Option<usize> },
+    OpenEditor {
+        path: PathBuf,
+        line: Option<usize>,
+    },
+    /// Apply just the selected hunk, then edit its file at the hunk
+    ApplyHunkAndEdit,
+    /// Show the selected hunk's added lines reindented to fit the code around
+    /// them; again, accept them so
+    ReindentHunk,
+
+    // Provider login: suspend the TUI, run the login command, retry the prompt
+    Login,
+    DismissError,
+    // Copy the failed run's full output to the clipboard
+    CopyOutput,
+    // Copy the error message, or the rows selected in it, to the clipboard
+    CopyError,
+    // Copy the confirmation, or the rows selected in it, to the clipboard
+    CopyConfirmation,
+    // Restore the files of the journal entry selected in :history
+    RestoreHistoryEntry,
+    /// Run the shell command the provider suggested (asks on the first press)
+    RunSuggestion,
+    /// Run the suggested command with this number (1-based), as `:run N`
+    RunSuggested(usize),
+    /// List the recent sessions to switch to
+    OpenSessionSwitcher,
+    /// Show this session, keeping the in-progress state of the one left
+    SwitchSession(String),
+    /// Show a message found by `:grep`, switching to its session if needed
+    OpenGrepHit {
+        session_id: String,
+        message_id: usize,
+    },
 
     // System
     Quit,
     Resize(u16, u16),
     Tick,
 }
+
+impl Message {
+    /// Handled by the run loop, which hands it the terminal to suspend
+    pub fn needs_terminal(&self) -> bool {
+        matches!(
+            self,
+            Message::OpenEditor { .. } | Message::ApplyHunkAndEdit | Message::Login
+        )
+    }
+}
@@ -1,6 +1,7 @@
 use crate::input::modes::{InputMode, ModeStack};
 use crate::state::State;
 use crate::ui::colors::Theme;
+use std::collections::BTreeMap;
 
 /// Central application model holding state and UI/input modes.
 pub struct AppModel {
@@ -12,15 +13,12 @@ pub struct AppModel {
 }
 
 impl AppModel {
-    pub fn new() -> anyhow::Result<Self> {
+    /// Model for a run with the layout `options` (see [`crate::config::layout_options`])
+    pub fn new(options: &BTreeMap<String, String>) -> anyhow::Result<Self> {
         let mut state = State::default();
-        state.initialize(&Default::default())?;
+        state.initialize(options)?;
 
-        let theme = if state.config.display.color_scheme == "light" {
-            Theme::light()
-        } else {
-            Theme::dark()
-        };
+        let theme = Theme::for_display(&state.config.display);
 
         Ok(Self {
             state,
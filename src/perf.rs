@@ -0,0 +1,189 @@
+//! Timings of the slow paths (`:perf`)
+//!
+//! The latest provider run, response parse, per-file diff and frame draw times
+//! are kept for the `:perf` overlay. A file whose diff takes longer than
+//! `general.slow_diff_ms` is reported there and appended to `perf.log` in the
+//! data directory, to find the changes that make large reviews sluggish.
+
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Frames averaged for the frame time
+pub const FRAME_SAMPLES: usize = 120;
+
+/// Slow-diff warnings kept for the overlay
+pub const MAX_WARNINGS: usize = 20;
+
+/// Where slow-diff warnings are logged
+pub fn log_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("perf.log")
+}
+
+/// The latest provider run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderRun {
+    pub provider: String,
+    pub wall: Duration,
+    pub output_bytes: usize,
+    pub tokens_out: usize,
+}
+
+impl ProviderRun {
+    /// Output tokens per second of wall time
+    pub fn tokens_per_second(&self) -> f64 {
+        let secs = self.wall.as_secs_f64();
+        if secs > 0.0 {
+            self.tokens_out as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Diffing one file of the review
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffTiming {
+    pub path: PathBuf,
+    /// Lines of the larger side
+    pub lines: usize,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct Perf {
+    pub provider: Option<ProviderRun>,
+    pub parse: Option<Duration>,
+    /// Per file of the current review, in review order
+    pub diffs: Vec<DiffTiming>,
+    frames: VecDeque<Duration>,
+    pub warnings: VecDeque<String>,
+    /// Log file for warnings; unset keeps them in memory only
+    log: Option<PathBuf>,
+}
+
+impl Perf {
+    /// Also append warnings to `path`
+    pub fn log_to(&mut self, path: PathBuf) {
+        self.log = Some(path);
+    }
+
+    pub fn record_frame(&mut self, elapsed: Duration) {
+        if self.frames.len() == FRAME_SAMPLES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(elapsed);
+    }
+
+    /// Latest, average and slowest of the recent frames
+    pub fn frame_stats(&self) -> Option<(Duration, Duration, Duration)> {
+        let latest = *self.frames.back()?;
+        let total: Duration = self.frames.iter().sum();
+        let max = self.frames.iter().max().copied().unwrap_or_default();
+        Some((latest, total / self.frames.len() as u32, max))
+    }
+
+    /// Record how long diffing `path` took, replacing its previous timing.
+    /// Returns the warning if it took longer than `threshold`.
+    pub fn record_diff(
+        &mut self,
+        path: &Path,
+        lines: usize,
+        elapsed: Duration,
+        threshold: Duration,
+    ) -> Option<String> {
+        let timing = DiffTiming {
+            path: path.to_path_buf(),
+            lines,
+            elapsed,
+        };
+        match self.diffs.iter_mut().find(|d| d.path == path) {
+            Some(existing) => *existing = timing,
+            None => self.diffs.push(timing),
+        }
+        if elapsed <= threshold {
+            return None;
+        }
+        let warning = format!(
+            "Slow diff: {} ({} lines) took {} ms",
+            path.display(),
+            lines,
+            elapsed.as_millis()
+        );
+        self.warn(warning.clone());
+        Some(warning)
+    }
+
+    fn warn(&mut self, warning: String) {
+        if let Some(path) = &self.log {
+            // A perf log that can't be written is not worth interrupting the review
+            let _ = append_log(path, &warning);
+        }
+        if self.warnings.len() == MAX_WARNINGS {
+            self.warnings.pop_front();
+        }
+        self.warnings.push_back(warning);
+    }
+}
+
+fn append_log(path: &Path, warning: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(
+        file,
+        "{} {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        warning
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slow_diffs_warn_and_replace_earlier_timings() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("perf.log");
+        let mut perf = Perf::default();
+        perf.log_to(log.clone());
+        let threshold = Duration::from_millis(100);
+
+        let path = Path::new("src/big.rs");
+        assert!(perf
+            .record_diff(path, 10, Duration::from_millis(5), threshold)
+            .is_none());
+        let warning = perf
+            .record_diff(path, 9000, Duration::from_millis(250), threshold)
+            .unwrap();
+        assert_eq!(warning, "Slow diff: src/big.rs (9000 lines) took 250 ms");
+        assert_eq!(perf.diffs.len(), 1);
+        assert_eq!(perf.warnings.len(), 1);
+        assert!(std::fs::read_to_string(&log).unwrap().contains(&warning));
+
+        for ms in [10, 20, 30] {
+            perf.record_frame(Duration::from_millis(ms));
+        }
+        assert_eq!(
+            perf.frame_stats(),
+            Some((
+                Duration::from_millis(30),
+                Duration::from_millis(20),
+                Duration::from_millis(30)
+            ))
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Sending text to a running instance from another terminal
+//!
+//! `zcode pipe "explain this error"` (or `cargo test 2>&1 | zcode pipe`) drops the
+//! text into the inbox of a running instance: a directory next to its lockfile in
+//! [`crate::lock::instances_dir`]. The instance polls its inbox and opens a prompt
+//! pre-filled with whatever arrives, so output selected in another pane can be
+//! handed over without copy and paste.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::events::AppEvent;
+
+/// How often a running instance looks for piped text
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Inbox of the instance with `pid` under `dir`
+pub fn inbox_dir(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("inbox-{}", pid))
+}
+
+/// Text to send: the arguments, then anything piped into stdin
+pub fn collect_text(args: &[String]) -> Result<String> {
+    let mut parts = Vec::new();
+    if !args.is_empty() {
+        parts.push(args.join(" "));
+    }
+    if !atty::is(atty::Stream::Stdin) {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .context("Failed to read stdin")?;
+        if !input.trim().is_empty() {
+            parts.push(input.trim_end().to_string());
+        }
+    }
+    if parts.is_empty() {
+        return Err(anyhow!(
+            "Nothing to send: pass text or pipe it into `zcode pipe`"
+        ));
+    }
+    Ok(parts.join("\n\n"))
+}
+
+/// Deliver `text` to instance `pid`, or to the most recently started one.
+/// Returns the PID it was sent to.
+pub fn send(dir: &Path, pid: Option<u32>, text: &str) -> Result<u32> {
+    let live = crate::lock::live_instances(dir);
+    let pid = match pid {
+        Some(pid) if live.contains(&pid) => pid,
+        Some(pid) => return Err(anyhow!("No running ZCode instance with PID {}", pid)),
+        None => *live.last().context("No running ZCode instance")?,
+    };
+
+    let inbox = inbox_dir(dir, pid);
+    std::fs::create_dir_all(&inbox)
+        .with_context(|| format!("Failed to create {}", inbox.display()))?;
+    // Named by time so several messages are read in the order they were sent
+    static SENT: AtomicU32 = AtomicU32::new(0);
+    let name = format!(
+        "{}_{}_{:04}.txt",
+        chrono::Utc::now().format("%Y%m%d_%H%M%S_%6f"),
+        std::process::id(),
+        SENT.fetch_add(1, Ordering::Relaxed)
+    );
+    crate::file_ops::atomic_write(&inbox.join(name), text)?;
+    Ok(pid)
+}
+
+/// Take every message waiting in `inbox`, oldest first
+pub fn drain_inbox(inbox: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(inbox) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        // Skips the temp files of writes still in progress
+        .filter(|p| p.extension().is_some_and(|e| e == "txt"))
+        .collect();
+    files.sort();
+
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(&path).ok();
+            let _ = std::fs::remove_file(&path);
+            text
+        })
+        .collect()
+}
+
+/// Forward messages arriving in this instance's inbox as `AppEvent::Piped`
+/// until the app stops listening. The inbox is removed when it does.
+pub fn spawn_inbox_reader(dir: &Path, tx: UnboundedSender<AppEvent>) {
+    let inbox = inbox_dir(dir, std::process::id());
+    if std::fs::create_dir_all(&inbox).is_err() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            for text in drain_inbox(&inbox) {
+                if tx.send(AppEvent::Piped(text)).is_err() {
+                    let _ = std::fs::remove_dir_all(&inbox);
+                    return;
+                }
+            }
+            if tx.is_closed() {
+                let _ = std::fs::remove_dir_all(&inbox);
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_send_reaches_latest_live_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(send(dir.path(), None, "hi").is_err());
+
+        let lockfile = File::create(dir.path().join("instance-4242.lock")).unwrap();
+        lockfile.lock().unwrap();
+        assert!(send(dir.path(), Some(7), "hi").is_err());
+
+        assert_eq!(send(dir.path(), None, "first").unwrap(), 4242);
+        assert_eq!(send(dir.path(), Some(4242), "second").unwrap(), 4242);
+
+        let inbox = inbox_dir(dir.path(), 4242);
+        assert_eq!(drain_inbox(&inbox), vec!["first", "second"]);
+        assert!(drain_inbox(&inbox).is_empty());
+    }
+}
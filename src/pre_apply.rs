@@ -0,0 +1,203 @@
+//! The pre-apply hook: a command that can refuse an apply
+//!
+//! With `general.pre_apply_command` set, every apply first runs it through
+//! `sh -c` in the working directory, with the patch about to be written on its
+//! stdin: a unified diff of each file as it is now against the file after the
+//! apply. A non-zero exit stops the apply before any file is written, and the
+//! error dialog shows what the command printed. That leaves policy (license
+//! headers, banned APIs, generated files) to a script of the project's own.
+//!
+//! ```toml
+//! [general]
+//! pre_apply_command = "./scripts/ai-change-lint.sh"
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use similar::TextDiff;
+
+use crate::state::{ChangeType, FileChange, Hunk};
+
+/// An apply the hook refused, with what it printed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Veto {
+    pub command: String,
+    /// Exit code; `None` when it was killed or ran out of time
+    pub code: Option<i32>,
+    pub output: String,
+}
+
+impl fmt::Display for Veto {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "`{}` exited with {}", self.command, code)?,
+            None => write!(f, "`{}` did not finish", self.command)?,
+        }
+        f.write_str("; nothing was written")?;
+        let output = self.output.trim_end();
+        if !output.is_empty() {
+            write!(f, "\n\n{}", output)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Veto {}
+
+/// The patch the accepted `hunks` make, paths relative to `root`. Each file is
+/// diffed as it is on disk (at its old path when renamed) against its content
+/// after the apply.
+pub fn patch(hunks: &[&Hunk], changes: &HashMap<PathBuf, FileChange>, root: &Path) -> String {
+    let mut files: BTreeMap<&Path, Vec<&Hunk>> = BTreeMap::new();
+    for hunk in hunks {
+        files.entry(&hunk.file_path).or_default().push(hunk);
+    }
+
+    let mut patch = String::new();
+    for (path, hunks) in files {
+        let change = changes.get(path);
+        let from = change
+            .and_then(|c| c.renamed_from.as_deref())
+            .unwrap_or(path);
+        let original = match change.map(|c| &c.change_type) {
+            Some(ChangeType::Create) => None,
+            _ => std::fs::read_to_string(from).ok(),
+        };
+        let Ok(content) = crate::file_ops::reconstruct_file_content(
+            original.as_deref().unwrap_or_default(),
+            &hunks,
+        ) else {
+            continue;
+        };
+        let deleted = change.is_some_and(|c| c.change_type == ChangeType::Delete)
+            && content.trim().is_empty();
+
+        let relative = |path: &Path| {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        let (old, new) = (relative(from), relative(path));
+        patch.push_str(&format!("diff --git a/{} b/{}\n", old, new));
+        if old != new {
+            patch.push_str(&format!("rename from {}\nrename to {}\n", old, new));
+        }
+        let old_header = match &original {
+            Some(_) => format!("a/{}", old),
+            None => {
+                patch.push_str("new file mode 100644\n");
+                "/dev/null".to_string()
+            }
+        };
+        let new_header = if deleted {
+            patch.push_str("deleted file mode 100644\n");
+            "/dev/null".to_string()
+        } else {
+            format!("b/{}", new)
+        };
+        let original = original.unwrap_or_default();
+        let content = if deleted { String::new() } else { content };
+        patch.push_str(
+            &TextDiff::from_lines(&original, &content)
+                .unified_diff()
+                .header(&old_header, &new_header)
+                .to_string(),
+        );
+    }
+    patch
+}
+
+/// Run `command` in `dir` with `patch` on stdin. A veto when it exits
+/// non-zero, can't be run, or is still running after `timeout`.
+pub fn run(command: &str, patch: &str, dir: &Path, timeout: Duration) -> Result<(), Veto> {
+    let veto = |code, output: String| Veto {
+        command: command.to_string(),
+        code,
+        output,
+    };
+    // Output goes to a file so a chatty hook can't fill a pipe and stall
+    let output_file = tempfile::NamedTempFile::new().map_err(|e| veto(None, e.to_string()))?;
+    let spawned = output_file.reopen().and_then(|stdout| {
+        let stderr = stdout.try_clone()?;
+        Command::new("sh")
+            .args(["-c", command])
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(stdout)
+            .stderr(stderr)
+            .spawn()
+    });
+    let mut child = spawned.map_err(|e| veto(None, format!("Failed to run sh: {}", e)))?;
+
+    // A hook that exits without reading its stdin is fine
+    if let Some(mut stdin) = child.stdin.take() {
+        let patch = patch.to_string();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(patch.as_bytes());
+        });
+    }
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() <= timeout => {
+                std::thread::sleep(Duration::from_millis(10))
+            }
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+        }
+    };
+    let mut output = std::fs::read_to_string(output_file.path()).unwrap_or_default();
+    match status {
+        Some(status) if status.success() => Ok(()),
+        Some(status) => Err(veto(status.code(), output)),
+        None => {
+            output.push_str(&format!("\n(stopped after {} seconds)", timeout.as_secs()));
+            Err(veto(None, output))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::compute_hunks;
+    use crate::state::HunkStatus;
+
+    #[test]
+    fn test_hook_reads_the_patch_and_can_refuse_the_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let path = root.join("src/lib.rs");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "fn a() {}\nfn b() {}\n").unwrap();
+        let mut hunks = compute_hunks(&path, "fn a() {}\nfn b() {}\n", "fn a() {}\nfn c() {}\n");
+        for hunk in &mut hunks {
+            hunk.status = HunkStatus::Accepted;
+        }
+        let hunks: Vec<&Hunk> = hunks.iter().collect();
+
+        let patch = patch(&hunks, &HashMap::new(), root);
+        assert!(patch.starts_with("diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n"));
+        assert!(patch.contains("-fn b() {}\n+fn c() {}\n"));
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(run("grep -q '^+fn c' -", &patch, root, timeout), Ok(()));
+        let veto = run("echo no unsafe here >&2; exit 3", &patch, root, timeout).unwrap_err();
+        assert_eq!(veto.code, Some(3));
+        assert_eq!(veto.output, "no unsafe here\n");
+        // A hook that doesn't answer in time stops the apply too
+        let veto = run("sleep 5", &patch, root, Duration::from_millis(50)).unwrap_err();
+        assert_eq!(veto.code, None);
+    }
+}
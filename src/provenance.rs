@@ -0,0 +1,110 @@
+//! Where a review's changes came from
+//!
+//! A review of a provider's reply records the provider, its model, the start
+//! of the prompt and when the reply came in. The diff header shows it as one
+//! dim line. Patches zcode writes carry it as `#` comments ahead of the first
+//! `diff --git`, which `git apply` and `patch` skip. Commits on an apply
+//! branch carry it as trailers. Either way, changes applied from a provider
+//! can be traced back later.
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Characters of the prompt kept
+const PROMPT_EXCERPT: usize = 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub provider: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// First line of the prompt, cut to a few words
+    pub prompt: String,
+    pub at: DateTime<Utc>,
+}
+
+impl Provenance {
+    pub fn new(provider: &str, model: Option<&str>, prompt: &str, at: DateTime<Utc>) -> Self {
+        let first = prompt
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .unwrap_or_default();
+        let mut excerpt: String = first.chars().take(PROMPT_EXCERPT).collect();
+        if excerpt.len() < first.len() || prompt.trim() != first {
+            excerpt.push('…');
+        }
+        Self {
+            provider: provider.to_string(),
+            model: model.filter(|m| !m.is_empty()).map(str::to_string),
+            prompt: excerpt,
+            at,
+        }
+    }
+
+    fn provider_and_model(&self) -> String {
+        match &self.model {
+            Some(model) => format!("{} ({})", self.provider, model),
+            None => self.provider.clone(),
+        }
+    }
+
+    /// One line for the diff header, in local time
+    pub fn summary(&self) -> String {
+        format!(
+            "{} · \"{}\" · {}",
+            self.provider_and_model(),
+            self.prompt,
+            self.at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        )
+    }
+
+    /// `#` lines to put ahead of a patch
+    pub fn patch_comment(&self) -> String {
+        let mut comment = format!("# Changes proposed by {}\n", self.provider_and_model());
+        comment.push_str(&format!("# Prompt: {}\n", self.prompt));
+        comment.push_str(&format!("# Date: {}\n", self.at.to_rfc3339()));
+        comment
+    }
+
+    /// Trailers for a commit message, after a blank line
+    pub fn trailers(&self) -> String {
+        let mut trailers = format!("Zcode-Provider: {}\n", self.provider);
+        if let Some(model) = &self.model {
+            trailers.push_str(&format!("Zcode-Model: {}\n", model));
+        }
+        trailers.push_str(&format!("Zcode-Prompt: {}\n", self.prompt));
+        trailers.push_str(&format!("Zcode-Date: {}\n", self.at.to_rfc3339()));
+        trailers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provenance_is_cut_to_the_first_line_and_rendered() {
+        let at = DateTime::parse_from_rfc3339("2026-10-17T09:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let provenance = Provenance::new(
+            "Claude Code",
+            Some("opus"),
+            "\nfix the parser\nand its tests",
+            at,
+        );
+        assert_eq!(provenance.prompt, "fix the parser…");
+        assert!(provenance
+            .summary()
+            .starts_with("Claude Code (opus) · \"fix the parser…\" · 2026-10-1"));
+        assert_eq!(
+            provenance.patch_comment(),
+            "# Changes proposed by Claude Code (opus)\n# Prompt: fix the parser…\n# Date: 2026-10-17T09:30:00+00:00\n"
+        );
+        assert!(provenance.trailers().contains("Zcode-Model: opus\n"));
+
+        let plain = Provenance::new("Aider", Some(""), "rename it", at);
+        assert_eq!((plain.model, plain.prompt.as_str()), (None, "rename it"));
+    }
+}
@@ -0,0 +1,282 @@
+//! Accepting added lines with their indentation fixed
+//!
+//! Added lines often come back indented for where the provider thought they
+//! went rather than where they land. `=` on a hunk works out where each run of
+//! added lines should start from the code around it: the replaced line's
+//! indentation, or the line above's, one level deeper after an opening bracket
+//! or colon and one shallower for a closing bracket. A run is shifted as a
+//! block, keeping its own nesting, and written with the file's tabs or spaces.
+//! The corrected lines are shown first; `=` again accepts them.
+
+use std::path::PathBuf;
+
+use crate::state::{ChangeTag, Hunk, LineChange};
+
+/// Columns a tab counts for in a file indented with tabs
+const TAB_WIDTH: usize = 4;
+
+/// How a file indents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndentStyle {
+    Tabs,
+    Spaces(usize),
+}
+
+impl IndentStyle {
+    /// The style most of `text`'s indented lines use: tabs, or spaces in the
+    /// step most common between a line and the next. `None` for a file with
+    /// no indented lines.
+    pub fn detect(text: &str) -> Option<Self> {
+        let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+        let tabs = lines.iter().filter(|l| l.starts_with('\t')).count();
+        let spaces = lines.iter().filter(|l| l.starts_with(' ')).count();
+        if tabs == 0 && spaces == 0 {
+            return None;
+        }
+        if tabs > spaces {
+            return Some(Self::Tabs);
+        }
+        let mut steps = [0usize; 9];
+        for pair in lines.windows(2) {
+            let (a, b) = (leading_spaces(pair[0]), leading_spaces(pair[1]));
+            let step = a.abs_diff(b);
+            if (2..=8).contains(&step) {
+                steps[step] += 1;
+            }
+        }
+        // Ties go to the smaller step, which the larger ones are multiples of
+        let step = (2..=8)
+            .filter(|&s| steps[s] > 0)
+            .max_by_key(|&s| (steps[s], std::cmp::Reverse(s)))
+            .unwrap_or(4);
+        Some(Self::Spaces(step))
+    }
+
+    /// Columns of one level
+    fn unit(self) -> usize {
+        match self {
+            Self::Tabs => TAB_WIDTH,
+            Self::Spaces(n) => n,
+        }
+    }
+
+    /// Columns `line`'s leading whitespace takes
+    fn width(self, line: &str) -> usize {
+        line.chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .map(|c| if c == '\t' { self.unit() } else { 1 })
+            .sum()
+    }
+
+    /// Leading whitespace `columns` wide
+    fn indent(self, columns: usize) -> String {
+        match self {
+            Self::Tabs => "\t".repeat(columns / TAB_WIDTH) + &" ".repeat(columns % TAB_WIDTH),
+            Self::Spaces(_) => " ".repeat(columns),
+        }
+    }
+}
+
+fn leading_spaces(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// An added line, as proposed and as reindented
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReindentedLine {
+    /// 0-based line of the proposed file
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// Added lines of a hunk with the indentation the code around them asks for
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reindent {
+    pub hunk_id: usize,
+    pub path: PathBuf,
+    pub style: IndentStyle,
+    /// Every added line of the hunk, in order
+    pub lines: Vec<ReindentedLine>,
+}
+
+impl Reindent {
+    /// Lines whose indentation changes
+    pub fn changed(&self) -> usize {
+        self.lines.iter().filter(|l| l.before != l.after).count()
+    }
+}
+
+/// The hunk's added lines reindented to fit `original`, the file it was diffed
+/// against. `None` when they already fit, or there is nothing to fit them to.
+pub fn reindent(hunk: &Hunk, original: &str) -> Option<Reindent> {
+    let style = IndentStyle::detect(original)?;
+    let original: Vec<&str> = original.lines().collect();
+    let changes = &hunk.changes;
+
+    let mut lines = Vec::new();
+    let mut i = 0;
+    while i < changes.len() {
+        if changes[i].tag != ChangeTag::Insert {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < changes.len() && changes[i].tag == ChangeTag::Insert {
+            i += 1;
+        }
+        let run = &changes[start..i];
+        let shift = target_width(hunk, &original, start, i, style).and_then(|target| {
+            let first = run.iter().find(|c| !c.content.trim().is_empty())?;
+            Some(target as isize - style.width(&first.content) as isize)
+        });
+        for change in run {
+            let before = change.content.to_string();
+            let after = match shift {
+                Some(shift) if !before.trim().is_empty() => {
+                    let body = before.trim_start_matches([' ', '\t']);
+                    let width = style.width(&before) as isize + shift;
+                    style.indent(width.max(0) as usize) + body
+                }
+                _ => before.clone(),
+            };
+            lines.push(ReindentedLine {
+                line: change.new_line_num.unwrap_or_default(),
+                before,
+                after,
+            });
+        }
+    }
+
+    let reindent = Reindent {
+        hunk_id: hunk.id,
+        path: hunk.file_path.clone(),
+        style,
+        lines,
+    };
+    (reindent.changed() > 0).then_some(reindent)
+}
+
+/// Where the first non-blank of the added lines `start..end` of the hunk
+/// should start: at the line it replaces, or from the line above or below
+fn target_width(
+    hunk: &Hunk,
+    original: &[&str],
+    start: usize,
+    end: usize,
+    style: IndentStyle,
+) -> Option<usize> {
+    let changes = &hunk.changes;
+    let first = changes[start..end]
+        .iter()
+        .map(|c| c.content.as_str())
+        .find(|l| !l.trim().is_empty())?;
+    let closes = |line: &str| line.trim_start().starts_with(['}', ')', ']']);
+    let opens = |line: &str| line.trim_end().ends_with(['{', '(', '[', ':']);
+
+    // The line a run replaces is where it belongs
+    let replaced = changes[..start]
+        .iter()
+        .rev()
+        .take_while(|c| c.tag == ChangeTag::Delete)
+        .map(|c| c.content.as_str())
+        .filter(|l| !l.trim().is_empty())
+        .last();
+    if let Some(replaced) = replaced {
+        return Some(style.width(replaced));
+    }
+
+    let context = |c: &&LineChange| c.tag == ChangeTag::Equal && !c.content.trim().is_empty();
+    let hunk_lines = hunk.old.indices();
+    let above = changes[..start]
+        .iter()
+        .rev()
+        .find(context)
+        .map(|c| c.content.as_str())
+        .or_else(|| {
+            original[..hunk_lines.start.min(original.len())]
+                .iter()
+                .rev()
+                .find(|l| !l.trim().is_empty())
+                .copied()
+        });
+    if let Some(above) = above {
+        let mut width = style.width(above);
+        if opens(above) {
+            width += style.unit();
+        }
+        if closes(first) {
+            width = width.saturating_sub(style.unit());
+        }
+        return Some(width);
+    }
+
+    let below = changes[end..]
+        .iter()
+        .find(context)
+        .map(|c| c.content.as_str())
+        .or_else(|| {
+            original
+                .get(hunk_lines.end..)?
+                .iter()
+                .find(|l| !l.trim().is_empty())
+                .copied()
+        })?;
+    let mut width = style.width(below);
+    if closes(below) && !closes(first) {
+        width += style.unit();
+    }
+    Some(width)
+}
+
+/// `proposed` with the reindented lines put in
+pub fn apply(proposed: &str, reindent: &Reindent) -> String {
+    let mut lines: Vec<String> = proposed.split_inclusive('\n').map(String::from).collect();
+    for line in &reindent.lines {
+        if let Some(slot) = lines.get_mut(line.line) {
+            *slot = line.after.clone();
+        }
+    }
+    lines.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::compute_hunks;
+    use std::path::Path;
+
+    #[test]
+    fn test_added_lines_take_the_indentation_around_them() {
+        assert_eq!(IndentStyle::detect("a\n\tb\n"), Some(IndentStyle::Tabs));
+        assert_eq!(
+            IndentStyle::detect("a {\n  b {\n    c\n  }\n}\n"),
+            Some(IndentStyle::Spaces(2))
+        );
+        assert_eq!(IndentStyle::detect("a\nb\n"), None);
+
+        let path = Path::new("src/lib.rs");
+        let original = "fn a() {\n    let x = 1;\n}\n";
+        // Added at the top level, and with a tab in a file of spaces
+        let proposed = "fn a() {\n    let x = 1;\nif x {\n\tcall();\n}\n}\n";
+        let hunk = &compute_hunks(path, original, proposed)[0];
+        let fixed = reindent(hunk, original).unwrap();
+        let after: Vec<&str> = fixed.lines.iter().map(|l| l.after.as_str()).collect();
+        assert_eq!(after, ["    if x {\n", "        call();\n", "    }\n"]);
+        assert_eq!(fixed.changed(), 3);
+        assert_eq!(
+            apply(proposed, &fixed),
+            "fn a() {\n    let x = 1;\n    if x {\n        call();\n    }\n}\n"
+        );
+
+        // A replaced line keeps its place; lines that fit are left alone
+        let replaced = "fn a() {\n        let x = 2;\n}\n";
+        let hunk = &compute_hunks(path, original, replaced)[0];
+        assert_eq!(
+            reindent(hunk, original).unwrap().lines[0].after,
+            "    let x = 2;\n"
+        );
+        let fits = "fn a() {\n    let x = 1;\n    let y = 2;\n}\n";
+        assert!(reindent(&compute_hunks(path, original, fits)[0], original).is_none());
+    }
+}
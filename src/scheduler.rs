@@ -0,0 +1,257 @@
+//! One limit on the background work zcode starts
+//!
+//! Provider detection, prompts, the test command run after an apply and the
+//! diffs of large files all run through a [`Scheduler`]. At most
+//! `general.max_concurrent_tasks` of them run at once; the others wait in the
+//! order they were started, whatever their kind, so the burst of `--version`
+//! probes at startup can't keep a prompt waiting behind later probes. `:perf`
+//! shows how many of each kind run and wait.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// What a scheduled task does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskKind {
+    Detection,
+    Prompt,
+    /// The test command after an apply
+    Hook,
+    Diff,
+}
+
+impl TaskKind {
+    pub const ALL: [TaskKind; 4] = [Self::Detection, Self::Prompt, Self::Hook, Self::Diff];
+}
+
+/// Tasks of one kind
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KindStats {
+    pub queued: usize,
+    pub running: usize,
+    pub finished: usize,
+    /// Longest any of them waited to start
+    pub longest_wait: Duration,
+}
+
+/// Runs futures at most `limit` at a time, first come first served
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    permits: Arc<Semaphore>,
+    /// 0 for no limit
+    limit: usize,
+    /// Turns still to take back after the limit shrank below the running
+    /// count: tasks finishing give theirs up instead of passing them on
+    excess: Arc<AtomicUsize>,
+    stats: Arc<Mutex<[KindStats; 4]>>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Scheduler {
+    /// A scheduler running `limit` tasks at once; 0 runs every task right away
+    pub fn new(limit: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(permits_for(limit))),
+            limit,
+            excess: Arc::default(),
+            stats: Arc::default(),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Change the limit for every clone, tasks already running included: they
+    /// keep counting against it, so none start until fewer than `limit` run
+    pub fn set_limit(&mut self, limit: usize) {
+        let (old, new) = (permits_for(self.limit), permits_for(limit));
+        self.limit = limit;
+        if new > old {
+            // Cancel turns still owed before handing out new ones
+            let mut grow = new - old;
+            let _ = self
+                .excess
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |excess| {
+                    let paid = excess.min(grow);
+                    grow -= paid;
+                    Some(excess - paid)
+                });
+            self.permits.add_permits(grow);
+        } else if old > new {
+            let forgotten = self.permits.forget_permits(old - new);
+            self.excess
+                .fetch_add(old - new - forgotten, Ordering::SeqCst);
+        }
+    }
+
+    /// Wait for a turn, then run `task`. A task dropped (aborted) while it
+    /// waits or runs gives its turn back.
+    pub fn run<F: Future>(&self, kind: TaskKind, task: F) -> impl Future<Output = F::Output> {
+        let scheduler = self.clone();
+        async move {
+            let queued = Counted::new(&scheduler, kind, |s| &mut s.queued);
+            let started = Instant::now();
+            // Never closed, so acquiring only waits
+            let _turn = scheduler.permits.acquire().await.ok().map(|permit| Turn {
+                permit: Some(permit),
+                excess: &scheduler.excess,
+            });
+            drop(queued);
+            let waited = started.elapsed();
+            scheduler.update(kind, |s| s.longest_wait = s.longest_wait.max(waited));
+            let running = Counted::new(&scheduler, kind, |s| &mut s.running);
+            let output = task.await;
+            drop(running);
+            scheduler.update(kind, |s| s.finished += 1);
+            output
+        }
+    }
+
+    /// Counts of each kind, in [`TaskKind::ALL`] order
+    pub fn stats(&self) -> Vec<(TaskKind, KindStats)> {
+        let stats = self.stats.lock().map(|s| *s).unwrap_or_default();
+        TaskKind::ALL.into_iter().zip(stats).collect()
+    }
+
+    fn update(&self, kind: TaskKind, change: impl FnOnce(&mut KindStats)) {
+        if let Ok(mut stats) = self.stats.lock() {
+            change(&mut stats[kind as usize]);
+        }
+    }
+}
+
+/// Semaphore permits for a limit; 0 means no limit
+fn permits_for(limit: usize) -> usize {
+    if limit == 0 {
+        Semaphore::MAX_PERMITS
+    } else {
+        limit
+    }
+}
+
+/// A running task's turn. Passed on when it ends or is aborted, unless the
+/// limit has been lowered since and the turn is owed back.
+struct Turn<'a> {
+    permit: Option<SemaphorePermit<'a>>,
+    excess: &'a AtomicUsize,
+}
+
+impl Drop for Turn<'_> {
+    fn drop(&mut self) {
+        let owed = self
+            .excess
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |excess| {
+                excess.checked_sub(1)
+            })
+            .is_ok();
+        if let (true, Some(permit)) = (owed, self.permit.take()) {
+            permit.forget();
+        }
+    }
+}
+
+/// One task in a count, taken back out when dropped
+struct Counted<'a> {
+    scheduler: &'a Scheduler,
+    kind: TaskKind,
+    field: fn(&mut KindStats) -> &mut usize,
+}
+
+impl<'a> Counted<'a> {
+    fn new(
+        scheduler: &'a Scheduler,
+        kind: TaskKind,
+        field: fn(&mut KindStats) -> &mut usize,
+    ) -> Self {
+        scheduler.update(kind, |s| *field(s) += 1);
+        Self {
+            scheduler,
+            kind,
+            field,
+        }
+    }
+}
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        let field = self.field;
+        self.scheduler.update(self.kind, |s| *field(s) -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tasks_past_the_limit_wait_their_turn() {
+        let scheduler = Scheduler::new(1);
+        let (release, wait) = tokio::sync::oneshot::channel::<()>();
+        let first = tokio::spawn(scheduler.run(TaskKind::Detection, async move {
+            let _ = wait.await;
+            "probe"
+        }));
+        tokio::task::yield_now().await;
+        let second = tokio::spawn(scheduler.run(TaskKind::Prompt, async { "prompt" }));
+        tokio::task::yield_now().await;
+
+        let stats = scheduler.stats();
+        assert_eq!(stats[0].1.running, 1);
+        assert_eq!((stats[1].1.queued, stats[1].1.running), (1, 0));
+
+        release.send(()).unwrap();
+        assert_eq!(first.await.unwrap(), "probe");
+        assert_eq!(second.await.unwrap(), "prompt");
+        let stats = scheduler.stats();
+        assert_eq!((stats[1].1.queued, stats[1].1.finished), (0, 1));
+
+        // An aborted task leaves no count behind
+        let blocked = Scheduler::new(1);
+        let held = tokio::spawn(blocked.run(TaskKind::Diff, std::future::pending::<()>()));
+        tokio::task::yield_now().await;
+        held.abort();
+        let _ = held.await;
+        let diff = blocked.stats()[3].1;
+        assert_eq!((diff.queued, diff.running, diff.finished), (0, 0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_lowering_the_limit_counts_tasks_already_running() {
+        let mut scheduler = Scheduler::new(2);
+        let (release_first, first_wait) = tokio::sync::oneshot::channel::<()>();
+        let (release_second, second_wait) = tokio::sync::oneshot::channel::<()>();
+        let first = tokio::spawn(scheduler.run(TaskKind::Prompt, first_wait));
+        let second = tokio::spawn(scheduler.run(TaskKind::Prompt, second_wait));
+        tokio::task::yield_now().await;
+
+        scheduler.set_limit(1);
+        let third = tokio::spawn(scheduler.run(TaskKind::Diff, async {}));
+        tokio::task::yield_now().await;
+        assert_eq!(scheduler.stats()[3].1.queued, 1);
+
+        // Two running against a limit of one: the first to end gives its turn up
+        release_first.send(()).unwrap();
+        first.await.unwrap().unwrap();
+        tokio::task::yield_now().await;
+        assert_eq!(scheduler.stats()[3].1.queued, 1);
+
+        release_second.send(()).unwrap();
+        second.await.unwrap().unwrap();
+        third.await.unwrap();
+        assert_eq!(scheduler.stats()[3].1.finished, 1);
+
+        // Raising it again hands out the extra turn
+        scheduler.set_limit(2);
+        assert_eq!(scheduler.permits.available_permits(), 2);
+    }
+}
@@ -4,7 +4,7 @@ use crate::state::ChatMessage;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -36,37 +36,123 @@ pub struct Session {
     pub total_cost: f64,
     #[serde(default)]
     pub context_files: Vec<PathBuf>,
+    /// The provider's own session id, used to resume its conversation
+    #[serde(default)]
+    pub provider_session_id: Option<String>,
+
+    // Usage counters for the :usage dashboard
+    #[serde(default)]
+    pub tokens_in: usize,
+    #[serde(default)]
+    pub tokens_out: usize,
+    #[serde(default)]
+    pub files_changed: usize,
+    #[serde(default)]
+    pub hunks_accepted: usize,
+    #[serde(default)]
+    pub hunks_reviewed: usize,
+
+    /// Files pinned to the sidebar, in the order they were pinned
+    #[serde(default)]
+    pub pinned_files: Vec<PathBuf>,
+    /// Files the latest reviews touched, most recent first
+    #[serde(default)]
+    pub recent_files: Vec<PathBuf>,
+    /// Review macros by register, as keymap tokens
+    #[serde(default)]
+    pub macros: BTreeMap<char, Vec<String>>,
+    /// Files skipped with `X` in review, left out of later reviews' applies too
+    #[serde(default)]
+    pub skipped_files: BTreeSet<PathBuf>,
+}
+
+/// Aggregated usage for one row of the :usage dashboard
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageStats {
+    pub label: String,
+    pub sessions: usize,
+    pub prompts: u32,
+    pub tokens_in: usize,
+    pub tokens_out: usize,
+    pub cost: f64,
+    pub files_changed: usize,
+    pub hunks_accepted: usize,
+    pub hunks_reviewed: usize,
+}
+
+impl UsageStats {
+    fn add(&mut self, session: &Session) {
+        self.sessions += 1;
+        self.prompts += session.prompt_count;
+        self.tokens_in += session.tokens_in;
+        self.tokens_out += session.tokens_out;
+        self.cost += session.total_cost;
+        self.files_changed += session.files_changed;
+        self.hunks_accepted += session.hunks_accepted;
+        self.hunks_reviewed += session.hunks_reviewed;
+    }
+
+    /// Share of reviewed hunks that were accepted, if any were reviewed
+    pub fn accept_ratio(&self) -> Option<f64> {
+        (self.hunks_reviewed > 0).then(|| self.hunks_accepted as f64 / self.hunks_reviewed as f64)
+    }
 }
 
 impl SessionManager {
     pub fn load() -> Result<Self> {
-        let path = Self::sessions_path();
+        Self::load_from(&Self::sessions_path())
+    }
+
+    fn load_from(path: &std::path::Path) -> Result<Self> {
+        let _lock = crate::lock::lock_shared(path)?;
+        Self::read(path)
+    }
 
+    fn read(path: &std::path::Path) -> Result<Self> {
         if path.exists() {
-            let content = std::fs::read_to_string(&path)?;
+            let content = std::fs::read_to_string(path)?;
             Ok(serde_json::from_str(&content)?)
         } else {
             Ok(Self::default())
         }
     }
 
+    /// Save sessions, first merging in whatever other instances have written
     pub fn save(&mut self) -> Result<()> {
         if !self.dirty {
             return Ok(());
         }
+        self.save_to(&Self::sessions_path())
+    }
+
+    fn save_to(&mut self, path: &std::path::Path) -> Result<()> {
+        let _lock = crate::lock::lock_exclusive(path)?;
 
-        let path = Self::sessions_path();
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+        // A corrupt file is overwritten rather than blocking every save
+        if let Ok(on_disk) = Self::read(path) {
+            self.merge(on_disk);
         }
 
         let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(&path, content)?;
+        crate::file_ops::atomic_write(path, &content)?;
 
         self.dirty = false;
         Ok(())
     }
 
+    /// Merge sessions saved by another instance; for a session both know about,
+    /// the most recently used copy wins
+    pub fn merge(&mut self, other: SessionManager) {
+        for (id, theirs) in other.sessions {
+            match self.sessions.get(&id) {
+                Some(ours) if ours.last_used >= theirs.last_used => {}
+                _ => {
+                    self.sessions.insert(id, theirs);
+                }
+            }
+        }
+    }
+
     fn sessions_path() -> PathBuf {
         dirs::data_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -75,11 +161,7 @@ impl SessionManager {
     }
 
     pub fn start_session(&mut self, provider: &str, cwd: &std::path::Path) -> String {
-        let id = format!(
-            "{}_{}",
-            Utc::now().format("%Y%m%d_%H%M%S"),
-            uuid_v4_simple()
-        );
+        let id = new_session_id();
 
         let session = Session {
             id: id.clone(),
@@ -94,6 +176,16 @@ impl SessionManager {
             total_tokens: 0,
             total_cost: 0.0,
             context_files: Vec::new(),
+            provider_session_id: None,
+            tokens_in: 0,
+            tokens_out: 0,
+            files_changed: 0,
+            hunks_accepted: 0,
+            hunks_reviewed: 0,
+            pinned_files: Vec::new(),
+            recent_files: Vec::new(),
+            macros: BTreeMap::new(),
+            skipped_files: BTreeSet::new(),
         };
 
         self.sessions.insert(id.clone(), session);
@@ -103,6 +195,15 @@ impl SessionManager {
         id
     }
 
+    /// Add `session`, made elsewhere, under a new id of its own; returns the id
+    pub fn adopt(&mut self, mut session: Session) -> String {
+        session.id = new_session_id();
+        let id = session.id.clone();
+        self.sessions.insert(id.clone(), session);
+        self.dirty = true;
+        id
+    }
+
     pub fn update_session(&mut self, description: Option<&str>) {
         if let Some(ref id) = self.current_session_id {
             if let Some(session) = self.sessions.get_mut(id) {
@@ -116,13 +217,155 @@ impl SessionManager {
         }
     }
 
+    /// The session prompts are currently recorded in
+    pub fn current_session(&self) -> Option<&Session> {
+        self.sessions.get(self.current_session_id.as_ref()?)
+    }
+
+    /// Mark the current session as the most recently used
+    pub fn touch(&mut self) {
+        if let Some(session) = self.current_mut() {
+            session.last_used = Utc::now();
+        }
+    }
+
+    /// Title the current session after its first prompt
+    pub fn name_after(&mut self, prompt: &str) {
+        if let Some(session) = self.current_mut() {
+            if session.description.is_empty() {
+                let first_line = prompt.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+                session.description = first_line.trim().chars().take(60).collect();
+            }
+        }
+    }
+
+    fn current_mut(&mut self) -> Option<&mut Session> {
+        let id = self.current_session_id.as_ref()?;
+        let session = self.sessions.get_mut(id)?;
+        self.dirty = true;
+        Some(session)
+    }
+
+    /// Remember the provider's session id so the next prompt resumes it
+    pub fn set_provider_session(&mut self, id: String) {
+        if let Some(session) = self.current_mut() {
+            session.provider_session_id = Some(id);
+        }
+    }
+
+    /// Count a prompt sent in the current session
+    pub fn record_prompt(&mut self) {
+        if let Some(session) = self.current_mut() {
+            session.last_used = Utc::now();
+            session.prompt_count += 1;
+        }
+    }
+
+    /// Count the tokens and cost of a provider response in the current session
+    pub fn record_response(&mut self, tokens_in: usize, tokens_out: usize, cost: Option<f64>) {
+        if let Some(session) = self.current_mut() {
+            session.last_used = Utc::now();
+            session.tokens_in += tokens_in;
+            session.tokens_out += tokens_out;
+            session.total_tokens += tokens_in + tokens_out;
+            session.total_cost += cost.unwrap_or(0.0);
+        }
+    }
+
+    /// Count the outcome of an applied review in the current session
+    pub fn record_review(&mut self, files_changed: usize, accepted: usize, reviewed: usize) {
+        if let Some(session) = self.current_mut() {
+            session.last_used = Utc::now();
+            session.files_changed += files_changed;
+            session.hunks_accepted += accepted;
+            session.hunks_reviewed += reviewed;
+        }
+    }
+
+    /// Remember the sidebar's pinned and recent files in the current session
+    pub fn set_sidebar_files(&mut self, pinned: &[PathBuf], recent: &[PathBuf]) {
+        if let Some(session) = self.current_mut() {
+            session.pinned_files = pinned.to_vec();
+            session.recent_files = recent.to_vec();
+        }
+    }
+
+    /// Remember the review macros in the current session
+    pub fn set_macros(&mut self, macros: &BTreeMap<char, Vec<String>>) {
+        if let Some(session) = self.current_mut() {
+            session.macros = macros.clone();
+        }
+    }
+
+    pub fn set_skipped_files(&mut self, files: &BTreeSet<PathBuf>) {
+        if let Some(session) = self.current_mut() {
+            session.skipped_files = files.clone();
+        }
+    }
+
+    /// Usage per provider, plus a total row at the end
+    pub fn usage_by_provider(&self) -> Vec<UsageStats> {
+        let mut by_provider: BTreeMap<&str, UsageStats> = BTreeMap::new();
+        let mut total = UsageStats {
+            label: crate::i18n::t("usage.total").to_string(),
+            ..UsageStats::default()
+        };
+
+        for session in self.sessions.values() {
+            by_provider
+                .entry(&session.provider)
+                .or_insert_with(|| UsageStats {
+                    label: session.provider.clone(),
+                    ..UsageStats::default()
+                })
+                .add(session);
+            total.add(session);
+        }
+
+        let mut rows: Vec<UsageStats> = by_provider.into_values().collect();
+        rows.push(total);
+        rows
+    }
+
+    /// Usage of the most recently used sessions, newest first
+    pub fn usage_by_session(&self, limit: usize) -> Vec<UsageStats> {
+        self.recent_sessions(limit)
+            .into_iter()
+            .map(|session| {
+                let label = if session.description.is_empty() {
+                    format!(
+                        "{} {}",
+                        session.last_used.format("%Y-%m-%d %H:%M"),
+                        session.provider
+                    )
+                } else {
+                    session.description.clone()
+                };
+                let mut stats = UsageStats {
+                    label,
+                    ..UsageStats::default()
+                };
+                stats.add(session);
+                stats
+            })
+            .collect()
+    }
+
     pub fn recent_sessions(&self, limit: usize) -> Vec<&Session> {
         let mut sessions: Vec<_> = self.sessions.values().collect();
-        sessions.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.last_used));
         sessions.into_iter().take(limit).collect()
     }
 }
 
+fn new_session_id() -> String {
+    format!(
+        "{}_{}",
+        Utc::now().format("%Y%m%d_%H%M%S"),
+        uuid_v4_simple()
+    )
+}
+
 fn uuid_v4_simple() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let seed = SystemTime::now()
@@ -131,3 +374,75 @@ fn uuid_v4_simple() -> String {
         .as_nanos();
     format!("{:016x}", seed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_aggregates_per_provider() {
+        let mut manager = SessionManager::default();
+        let cwd = std::path::Path::new(".");
+
+        manager.start_session("Claude Code", cwd);
+        manager.record_prompt();
+        manager.record_response(100, 400, Some(0.02));
+        manager.record_review(2, 3, 4);
+
+        manager.start_session("Aider", cwd);
+        manager.record_prompt();
+        manager.record_response(50, 80, None);
+
+        let rows = manager.usage_by_provider();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].label, "Aider");
+        assert_eq!(rows[0].accept_ratio(), None);
+        assert_eq!(rows[1].label, "Claude Code");
+        assert_eq!(rows[1].prompts, 1);
+        assert_eq!(rows[1].tokens_out, 400);
+        assert_eq!(rows[1].accept_ratio(), Some(0.75));
+
+        let total = &rows[2];
+        assert_eq!(total.label, "Total");
+        assert_eq!(total.sessions, 2);
+        assert_eq!(total.tokens_in, 150);
+        assert_eq!(total.files_changed, 2);
+        assert!((total.cost - 0.02).abs() < f64::EPSILON);
+        assert!(manager.dirty);
+    }
+
+    #[test]
+    fn test_save_merges_sessions_from_other_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+        let cwd = std::path::Path::new(".");
+
+        let mut first = SessionManager::default();
+        first.start_session("Claude Code", cwd);
+        let mut second = SessionManager::default();
+        let shared = second.start_session("Aider", cwd);
+
+        second.save_to(&path).unwrap();
+        first.save_to(&path).unwrap();
+
+        // The second instance keeps using its session after the first one saved
+        second.record_prompt();
+        second.save_to(&path).unwrap();
+
+        let merged = SessionManager::load_from(&path).unwrap();
+        assert_eq!(merged.sessions.len(), 2);
+        assert_eq!(merged.sessions[&shared].prompt_count, 1);
+
+        // A response and review recorded after the first instance last saw the
+        // session outlive its next save
+        first.save_to(&path).unwrap();
+        second.record_response(10, 20, None);
+        second.record_review(1, 2, 3);
+        second.save_to(&path).unwrap();
+        first.save_to(&path).unwrap();
+
+        let merged = SessionManager::load_from(&path).unwrap();
+        assert_eq!(merged.sessions[&shared].total_tokens, 30);
+        assert_eq!(merged.sessions[&shared].hunks_reviewed, 3);
+    }
+}
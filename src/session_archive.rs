@@ -0,0 +1,341 @@
+//! Sessions packed into one file to continue a review on another machine
+//!
+//! `:session export <file>` writes the current session (its metadata and whole
+//! chat) and its open review (each file's original and proposed content, and
+//! the verdicts and comments given so far) as one JSON file. `:session import
+//! <file>` adds it as a new session and opens the review where it was left.
+//!
+//! Each file records a hash of the content it was diffed against. A file that
+//! hashes differently in the importing working tree is re-diffed against it, as
+//! if it had changed on disk during the review, and named in a warning.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::file_ops::{atomic_write, WritePolicy};
+use crate::provenance::Provenance;
+use crate::session::Session;
+use crate::state::{ChatMessage, FileChange, HunkRange, HunkStatus, State};
+
+/// Archive format written; newer ones are refused
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionArchive {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// The session, its `messages` holding the whole chat
+    pub session: Session,
+    /// Files of the review, in review order
+    pub files: Vec<ArchivedFile>,
+    /// The provider reply the review came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+/// One file of the review, its path relative to the working directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedFile {
+    pub change: FileChange,
+    /// SHA-256 of the original content; `None` for a file the review creates
+    pub original_hash: Option<String>,
+    pub decisions: Vec<Decision>,
+}
+
+/// The verdict and comment on one hunk, found again by its ranges
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    pub old: HunkRange,
+    pub new: HunkRange,
+    pub status: HunkStatus,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// What opening an archive's review found
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportReport {
+    pub files: usize,
+    /// Decisions put back on their hunks
+    pub decisions: usize,
+    /// Files whose content here isn't what the review was diffed against
+    pub differing: Vec<PathBuf>,
+}
+
+/// Hex SHA-256 of `text`
+pub fn content_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+impl SessionArchive {
+    /// The current session of `state` with `messages` as its chat, and its
+    /// review with paths made relative to `root`
+    pub fn capture(state: &State, messages: Vec<ChatMessage>, root: &Path) -> Result<Self> {
+        let mut session = state
+            .sessions
+            .current_session()
+            .context("No session to export")?
+            .clone();
+        session.messages = messages;
+
+        let files = state
+            .overlay_diff_state
+            .proposed_changes
+            .iter()
+            .filter_map(|file| state.pending_changes.get(&file.file_path))
+            .map(|change| {
+                let decisions = state
+                    .hunks
+                    .iter()
+                    .filter(|h| h.file_path == change.path)
+                    .filter(|h| h.status != HunkStatus::Pending || h.comment.is_some())
+                    .map(|h| Decision {
+                        old: h.old,
+                        new: h.new,
+                        status: h.status.clone(),
+                        comment: h.comment.clone(),
+                    })
+                    .collect();
+                let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_path_buf();
+                ArchivedFile {
+                    change: FileChange {
+                        path: relative(&change.path),
+                        renamed_from: change.renamed_from.as_deref().map(relative),
+                        ..change.clone()
+                    },
+                    original_hash: change.original_content.as_deref().map(content_hash),
+                    decisions,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            version: VERSION,
+            exported_at: Utc::now(),
+            session,
+            files,
+            provenance: state.overlay_diff_state.provenance.clone(),
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        atomic_write(path, &json)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let archive: Self = serde_json::from_str(&json)
+            .with_context(|| format!("{} is not a session archive", path.display()))?;
+        if archive.version > VERSION {
+            bail!(
+                "{} was exported by a newer zcode (format {})",
+                path.display(),
+                archive.version
+            );
+        }
+        Ok(archive)
+    }
+
+    /// Open the archived review in `state` with its decisions, checking each
+    /// file against the working tree at `root`
+    pub fn load_review(&self, state: &mut State, root: &Path, policy: WritePolicy) -> ImportReport {
+        let mut report = ImportReport {
+            files: self.files.len(),
+            ..ImportReport::default()
+        };
+        if self.files.is_empty() {
+            return report;
+        }
+
+        // Diffed here rather than in the background so every decision has
+        // its hunk to go back on
+        let changes: Vec<FileChange> = self.files.iter().map(|f| f.change.clone()).collect();
+        for change in &changes {
+            let original = change.original_content.as_deref().unwrap_or_default();
+            let hunks =
+                crate::diff::compute_hunks(&change.path, original, &change.proposed_content);
+            let key = crate::diff::diff_key(original, &change.proposed_content);
+            state.diff_cache.insert(key, hunks);
+        }
+        state.load_review(changes, policy);
+        state.overlay_diff_state.provenance = self.provenance.clone();
+
+        for file in &self.files {
+            for decision in &file.decisions {
+                let hunk = state.hunks.iter_mut().find(|h| {
+                    h.file_path == file.change.path
+                        && h.old == decision.old
+                        && h.new == decision.new
+                });
+                if let Some(hunk) = hunk {
+                    hunk.status = decision.status.clone();
+                    hunk.comment = decision.comment.clone();
+                    hunk.auto_accepted = None;
+                    report.decisions += 1;
+                }
+            }
+        }
+
+        // Hunks that still fit keep their decisions; the others are drifted.
+        // A renamed file's original is at its old path.
+        for file in &self.files {
+            let source = file
+                .change
+                .renamed_from
+                .as_ref()
+                .unwrap_or(&file.change.path);
+            let disk = std::fs::read_to_string(root.join(source)).ok();
+            if disk.as_deref().map(content_hash) != file.original_hash {
+                report.differing.push(file.change.path.clone());
+                state.refresh_from_disk(source, disk);
+            }
+        }
+        report
+    }
+}
+
+/// `:session export`: the current session, its whole chat and its review
+pub fn export(state: &State, path: &Path) -> Result<String> {
+    let id = state
+        .sessions
+        .current_session_id
+        .as_deref()
+        .context("No session to export")?;
+    let log = crate::chat_log::log_path(&crate::chat_log::chats_dir(), id);
+    let messages = crate::chat_log::load_page(&log, None, usize::MAX)?.messages;
+    let cwd = std::env::current_dir()?;
+    let archive = SessionArchive::capture(state, messages, &cwd)?;
+    archive.write(path)?;
+    let decisions: usize = archive.files.iter().map(|f| f.decisions.len()).sum();
+    Ok(format!(
+        "Exported {} messages, {} files and {} decisions to {}",
+        archive.session.messages.len(),
+        archive.files.len(),
+        decisions,
+        path.display()
+    ))
+}
+
+/// `:session import`: add the archived session as a new one and switch to it
+pub fn import(state: &mut State, path: &Path) -> Result<String> {
+    let archive = SessionArchive::read(path)?;
+    let cwd = std::env::current_dir()?;
+    let policy = WritePolicy::discover()?;
+
+    let mut session = archive.session.clone();
+    let messages = std::mem::take(&mut session.messages);
+    session.working_directory = cwd.clone();
+    // The provider's conversation stayed on the machine that exported it
+    session.provider_session_id = None;
+    session.last_used = Utc::now();
+    let id = state.sessions.adopt(session);
+    let log = crate::chat_log::log_path(&crate::chat_log::chats_dir(), &id);
+    let switched =
+        crate::chat_log::append_all(&log, &messages).and_then(|_| state.switch_session(&id));
+    let note = match switched {
+        Ok(note) => note,
+        Err(e) => {
+            state.sessions.sessions.remove(&id);
+            let _ = std::fs::remove_file(&log);
+            return Err(e);
+        }
+    };
+
+    let report = archive.load_review(state, &cwd, policy);
+    let mut note = format!(
+        "{}: {} messages, {} files, {} decisions",
+        note.replacen("Switched to", "Imported", 1),
+        messages.len(),
+        report.files,
+        report.decisions
+    );
+    if !report.differing.is_empty() {
+        let paths: Vec<String> = report
+            .differing
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        note.push_str(&format!(
+            "; differ from this working tree, re-diffed: {}",
+            paths.join(", ")
+        ));
+    }
+    Ok(note)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::ChangeType;
+
+    #[test]
+    fn test_review_comes_back_with_decisions_and_differing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.rs"), "one\ntwo\n").unwrap();
+        std::fs::write(root.join("b.rs"), "old\n").unwrap();
+        let change = |name: &str, original: &str, proposed: &str| FileChange {
+            path: root.join(name),
+            original_content: Some(original.to_string()),
+            proposed_content: proposed.to_string(),
+            change_type: ChangeType::Modify,
+            renamed_from: None,
+        };
+
+        let mut state = State::default();
+        state.sessions.start_session("Claude Code", root);
+        state.load_review(
+            vec![
+                change("a.rs", "one\ntwo\n", "one\n2\n"),
+                change("b.rs", "old\n", "new\n"),
+            ],
+            WritePolicy::default(),
+        );
+        state.hunks[0].status = HunkStatus::Rejected;
+        state.hunks[0].comment = Some("keep two".to_string());
+        let provenance = Provenance::new("Claude Code", None, "number two", Utc::now());
+        state.overlay_diff_state.provenance = Some(provenance.clone());
+
+        let path = root.join("review.json");
+        SessionArchive::capture(&state, Vec::new(), root)
+            .unwrap()
+            .write(&path)
+            .unwrap();
+        let archive = SessionArchive::read(&path).unwrap();
+        assert_eq!(archive.files[0].change.path, PathBuf::from("a.rs"));
+        assert_eq!(archive.files[0].decisions.len(), 1);
+
+        // b.rs was edited on the importing side since
+        std::fs::write(root.join("b.rs"), "edited\n").unwrap();
+        let mut imported = State::default();
+        let report = archive.load_review(&mut imported, root, WritePolicy::default());
+        assert_eq!(report.files, 2);
+        assert_eq!(report.decisions, 1);
+        assert_eq!(report.differing, [PathBuf::from("b.rs")]);
+        let hunk = &imported.hunks[0];
+        assert_eq!(hunk.status, HunkStatus::Rejected);
+        assert_eq!(hunk.comment.as_deref(), Some("keep two"));
+        assert_eq!(imported.overlay_diff_state.provenance, Some(provenance));
+        // The provenance line sits between the file header and the diff
+        assert_eq!(imported.overlay_diff_state.row_of(0, 0), 2);
+        assert_eq!(
+            imported.pending_changes[Path::new("b.rs")]
+                .original_content
+                .as_deref(),
+            Some("edited\n")
+        );
+
+        let mut newer = archive.clone();
+        newer.version = VERSION + 1;
+        newer.write(&path).unwrap();
+        assert!(SessionArchive::read(&path).is_err());
+    }
+}
@@ -1,20 +1,34 @@
-//! Main plugin state and data structures
+//! Application state and data structures
 //!
-//! This module defines the core data types and state machine for the ZCode plugin.
-//! It manages the application's modes, user interactions, and the flow between
-//! prompting, diff review, and file application.
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::path::PathBuf;
+//! This module defines the core data types and state machine. It manages the
+//! application's modes, user interactions, and the flow between prompting, diff
+//! review, and file application; the Ratatui `App` only translates keys into
+//! calls on it, so behaviour is implemented once here.
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
 
 use crate::config::Config;
+use crate::context::{ContextAttachment, DEFAULT_CONTEXT_TOKEN_LIMIT};
 use crate::error::ErrorDisplay;
+use crate::file_ops::WritePolicy;
+use crate::i18n::{t, tf};
+use crate::macros::Macros;
 use crate::providers::AIProvider;
 use crate::session::SessionManager;
+use crate::symbols::{Symbol, SymbolIndex};
+use crate::telemetry::Telemetry;
 use chrono::{DateTime, Utc};
 use ratatui::widgets::ListState;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
 
+pub use zcode_core::types::{
+    ChangeTag, ChangeType, FileChange, Hunk, HunkRange, HunkStatus, LineChange, LineText,
+    PromptMode, PromptRequest,
+};
+
 /// State of provider detection process
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum DetectionState {
@@ -37,70 +51,18 @@ pub enum ExecutionState {
     WaitingForResult,
 }
 
-/// Request to send to an AI provider
-#[derive(Debug, Clone)]
-pub struct PromptRequest {
-    pub prompt: String,
-    pub context_files: Vec<PathBuf>,
-    pub session_id: Option<String>,
-    pub working_directory: PathBuf,
-}
-
-/// Raw response from an AI provider
-#[derive(Debug, Clone)]
-pub struct ProviderResponse {
-    pub raw_output: String,
-    pub exit_code: i32,
-    pub stderr: String,
-}
-
-/// Parsed file change from provider output
-#[derive(Debug, Clone)]
-pub struct FileChange {
-    pub path: PathBuf,
-    pub original_content: Option<String>,
-    pub proposed_content: String,
-    pub change_type: ChangeType,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum ChangeType {
-    Create,
-    Modify,
-    Delete,
-}
-
-/// A diff hunk for review
-#[derive(Debug, Clone)]
-pub struct Hunk {
-    pub id: usize,
-    pub file_path: PathBuf,
-    pub start_line: usize,
-    pub end_line: usize,
-    pub changes: Vec<LineChange>,
-    pub status: HunkStatus,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum HunkStatus {
-    Pending,
-    Accepted,
-    Rejected,
-}
-
-#[derive(Debug, Clone)]
-pub struct LineChange {
-    pub tag: ChangeTag,
-    pub content: String,
-    pub old_line_num: Option<usize>,
-    pub new_line_num: Option<usize>,
+/// How many hunks of the review are decided on
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReviewProgress {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub pending: usize,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum ChangeTag {
-    Equal,
-    Insert,
-    Delete,
+impl ReviewProgress {
+    pub fn total(&self) -> usize {
+        self.accepted + self.rejected + self.pending
+    }
 }
 
 /// A proposed change to a file (NOT yet applied)
@@ -112,16 +74,89 @@ pub struct ProposedChange {
     pub proposed_content: String, // What AI suggests
     pub line_decorations: Vec<LineDecoration>,
     pub status: ChangeStatus,
+    pub changed_on_disk: bool, // Original was modified externally during review
+    pub drifted_hunks: Vec<usize>, // Hunk ids whose context changed on disk
+    pub stale: bool, // Changed on disk in the lines the proposal changes; can't be applied
+    pub ignored: bool, // Matched by .zcodeignore and not overridden
+    pub skipped: bool, // Left out of the apply with X
+    pub test_failures: Vec<(usize, String)>, // (hunk id, failing test) after applying
+    pub groups: Vec<HunkGroup>, // Hunks reviewed together as one logical change
+    pub comments: Vec<(usize, String)>, // (hunk id, reviewer comment)
+    pub path_warning: Option<String>, // Path doesn't match the code block's language
+    pub rewrite: Option<f32>, // Share of lines kept when the file is rewritten
+    pub computing: Option<crate::diff::DiffKey>, // Diff still running in the background
+    pub flagged_hunks: Vec<(usize, String)>, // (hunk id, guard rule it trips)
+    pub auto_accepted: Vec<(usize, String)>, // (hunk id, auto-accept rule)
+    pub hunk_ranges: Vec<(usize, HunkRange, HunkRange)>, // (hunk id, old range, new range)
+    pub edit_choices: usize, // Overlapping edits of the reply to pick from (0 if none)
+    pub renamed_from: Option<PathBuf>, // Path the file is moved from when applied
+}
+
+/// Nearby hunks of one file reviewed as a single logical change
+#[derive(Debug, Clone, PartialEq)]
+pub struct HunkGroup {
+    pub hunk_ids: Vec<usize>,
+    /// Enclosing definition, or the line range when there is none
+    pub label: String,
+    pub collapsed: bool,
+    /// First and last decoration index the group spans
+    pub first_line: usize,
+    pub last_line: usize,
+}
+
+impl ProposedChange {
+    /// Each decoration's line in the original and in the file the review
+    /// would write: rejected additions are left out of it and rejected
+    /// deletions kept, so the numbers follow partial accepts
+    pub fn line_numbers(&self) -> Vec<(Option<usize>, Option<usize>)> {
+        // Lines the review adds minus those it removes, so far
+        let mut shift: isize = 0;
+        let at = |line: usize, shift: isize| line.checked_add_signed(shift);
+        self.line_decorations
+            .iter()
+            .map(|dec| {
+                let rejected = dec.accepted == Some(false);
+                match dec.decoration_type {
+                    DecorationType::Addition if rejected => (None, None),
+                    DecorationType::Addition => {
+                        shift += 1;
+                        (None, at(dec.old_line, shift))
+                    }
+                    DecorationType::Deletion if rejected => {
+                        (Some(dec.old_line), at(dec.old_line, shift))
+                    }
+                    DecorationType::Deletion => {
+                        shift -= 1;
+                        (Some(dec.old_line), None)
+                    }
+                    DecorationType::Modification | DecorationType::Context => {
+                        (Some(dec.old_line), at(dec.old_line, shift))
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// The group whose decorations include `line_idx`
+    pub fn group_at(&self, line_idx: usize) -> Option<&HunkGroup> {
+        self.groups
+            .iter()
+            .find(|g| (g.first_line..=g.last_line).contains(&line_idx))
+    }
 }
 
 /// Visual decoration for a single line
 #[derive(Debug, Clone)]
 pub struct LineDecoration {
     pub line_number: usize,
+    /// 1-based line in the original; for an added line, the original line it
+    /// follows (0 at the start of the file)
+    pub old_line: usize,
     pub decoration_type: DecorationType,
-    pub original_text: Option<String>, // For deletions/modifications
-    pub new_text: Option<String>,      // For additions/modifications
+    pub original_text: Option<LineText>, // For deletions/modifications
+    pub new_text: Option<LineText>,      // For additions/modifications
     pub accepted: Option<bool>, // None = pending, Some(true) = accepted, Some(false) = rejected
+    pub hunk_id: Option<usize>, // Index into `State::hunks` this line belongs to
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -148,6 +183,45 @@ pub struct OverlayDiffState {
     pub current_line_idx: usize,
     pub show_context_lines: usize,
     pub folded_unchanged: bool, // Collapse unchanged regions
+    /// First visible row per file, kept when a new response replaces the changes
+    pub scroll_offsets: HashMap<PathBuf, usize>,
+    /// Rows available to the diff body, recorded at draw time (0 = not drawn yet)
+    pub viewport_height: usize,
+    /// Columns available to the diff body, recorded at draw time
+    pub viewport_width: usize,
+    /// Soft-wrap lines wider than the view instead of cutting them off (the default)
+    pub wrap_lines: bool,
+    /// Git ref the files are diffed against instead of the working tree
+    pub base: Option<DiffBase>,
+    /// Show the current file's original and proposed content next to each other
+    pub side_by_side: bool,
+    /// Number each line in the original and in the file the review would write
+    pub line_numbers: bool,
+    /// The provider reply the changes came from, shown under the file header
+    pub provenance: Option<crate::provenance::Provenance>,
+}
+
+/// One file in the apply confirmation
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSummary {
+    pub path: PathBuf,
+    pub change_type: ChangeType,
+    /// Accepted hunks
+    pub hunks: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// Matched by `.zcodeignore`, so it will be skipped
+    pub ignored: bool,
+    /// Where the file is moved from before it is written
+    pub renamed_from: Option<PathBuf>,
+}
+
+/// A git ref a provider review is shown against
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffBase {
+    pub rev: String,
+    /// Each file's content at `rev`; `None` where it doesn't exist there
+    pub contents: HashMap<PathBuf, Option<String>>,
 }
 
 impl Default for OverlayDiffState {
@@ -158,7 +232,115 @@ impl Default for OverlayDiffState {
             current_line_idx: 0,
             show_context_lines: 3,
             folded_unchanged: false,
+            scroll_offsets: HashMap::new(),
+            viewport_height: 0,
+            viewport_width: 0,
+            wrap_lines: true,
+            base: None,
+            side_by_side: false,
+            line_numbers: false,
+            provenance: None,
+        }
+    }
+}
+
+impl OverlayDiffState {
+    /// Scroll offset of the file currently shown
+    pub fn scroll_offset(&self) -> usize {
+        self.proposed_changes
+            .get(self.current_change_idx)
+            .and_then(|c| self.scroll_offsets.get(&c.file_path))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Rows above a file's first decoration: its header, and the provenance line
+    pub fn header_rows(&self) -> usize {
+        1 + self.provenance.is_some() as usize
+    }
+
+    /// Row of a decoration within its file's rendered diff (the file header is row 0)
+    pub fn row_of(&self, change_idx: usize, line_idx: usize) -> usize {
+        let Some(change) = self.proposed_changes.get(change_idx) else {
+            return 0;
+        };
+        let own_header = change
+            .groups
+            .iter()
+            .any(|g| g.first_line == line_idx && !g.collapsed);
+        self.header_rows()
+            + (0..line_idx.min(change.line_decorations.len()))
+                .map(|idx| self.rows_at(change, idx))
+                .sum::<usize>()
+            + own_header as usize
+    }
+
+    /// Rows drawn for one decoration, including a group header before it
+    fn rows_at(&self, change: &ProposedChange, idx: usize) -> usize {
+        let header = change.groups.iter().any(|g| g.first_line == idx) as usize;
+        match change.group_at(idx) {
+            Some(group) if group.collapsed => header,
+            _ => header + self.rows_for(&change.line_decorations[idx]),
+        }
+    }
+
+    fn rows_for(&self, decoration: &LineDecoration) -> usize {
+        let rows = |text: &Option<LineText>| self.text_rows(text.as_deref().unwrap_or(""));
+        match decoration.decoration_type {
+            DecorationType::Modification => {
+                rows(&decoration.original_text) + rows(&decoration.new_text)
+            }
+            DecorationType::Context if self.folded_unchanged => 0,
+            DecorationType::Addition => rows(&decoration.new_text),
+            _ => rows(&decoration.original_text),
+        }
+    }
+
+    /// Rows one line of text takes: one, unless wrapping spreads it over more
+    fn text_rows(&self, text: &str) -> usize {
+        if !self.wrap_lines || self.viewport_width == 0 {
+            return 1;
+        }
+        crate::ui::overlay_diff::wrap_text(text, self.text_width()).len()
+    }
+
+    /// Columns left for a line's text after the gutter, line numbers, marker and sign
+    pub fn text_width(&self) -> usize {
+        self.viewport_width
+            .saturating_sub(crate::ui::overlay_diff::text_offset(self.line_numbers))
+            .max(1)
+    }
+
+    /// Scroll the current file by `delta` rows, clamped to its content
+    pub fn scroll_by(&mut self, delta: isize) {
+        let Some(change) = self.proposed_changes.get(self.current_change_idx) else {
+            return;
+        };
+        let last_row = self.row_of(self.current_change_idx, change.line_decorations.len());
+        let max = last_row.saturating_sub(self.viewport_height.saturating_sub(1));
+        let offset = self.scroll_offset().saturating_add_signed(delta).min(max);
+        self.scroll_offsets.insert(change.file_path.clone(), offset);
+    }
+
+    /// Adjust the current file's scroll so the line under the cursor is visible
+    pub fn keep_cursor_visible(&mut self) {
+        let Some(change) = self.proposed_changes.get(self.current_change_idx) else {
+            return;
+        };
+        if self.viewport_height == 0 {
+            return;
         }
+        let row = self.row_of(self.current_change_idx, self.current_line_idx);
+        let offset = self.scroll_offset();
+        // Once scrolled, the top row is covered by the sticky hunk header
+        let offset = if row < offset || (offset > 0 && row == offset) {
+            row.saturating_sub(1)
+        } else if row >= offset + self.viewport_height {
+            row + 1 - self.viewport_height
+        } else {
+            return;
+        };
+        self.scroll_offsets.insert(change.file_path.clone(), offset);
     }
 }
 
@@ -177,6 +359,10 @@ pub struct StatusInfo {
     pub eta_seconds: Option<u64>,
     pub can_cancel: bool,
     pub start_time: Option<Instant>,
+    /// Register a review macro is being recorded into
+    pub recording_macro: Option<char>,
+    /// Usage metrics are being exported
+    pub telemetry: bool,
 }
 
 impl Default for StatusInfo {
@@ -194,6 +380,8 @@ impl Default for StatusInfo {
             model: String::new(),
             eta_seconds: None,
             can_cancel: false,
+            recording_macro: None,
+            telemetry: false,
             start_time: None,
         }
     }
@@ -207,6 +395,44 @@ pub struct SidebarState {
     pub highlighted_lines: Vec<usize>,
     pub syntax_highlighting: bool,
     pub current_file_indicator: Option<String>,
+    /// What the preview shows when the pinned file has pending changes
+    pub view: SidebarView,
+    /// Files pinned with `:pin`, cycled through with `(`/`)`
+    pub pins: Vec<PathBuf>,
+    /// Files the latest reviews touched, most recent first
+    pub recent_files: Vec<PathBuf>,
+}
+
+/// Sidebar preview of a pinned file with pending changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SidebarView {
+    /// The file as it is on disk
+    Current,
+    /// The file as it would be after applying the changes
+    Proposed,
+    /// The proposed file with added lines marked and removed lines struck through
+    #[default]
+    Diff,
+}
+
+impl SidebarView {
+    pub const ALL: [SidebarView; 3] = [Self::Current, Self::Proposed, Self::Diff];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Current => "current",
+            Self::Proposed => "proposed",
+            Self::Diff => "diff",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Current => Self::Proposed,
+            Self::Proposed => Self::Diff,
+            Self::Diff => Self::Current,
+        }
+    }
 }
 
 impl Default for SidebarState {
@@ -218,6 +444,9 @@ impl Default for SidebarState {
             highlighted_lines: Vec::new(),
             syntax_highlighting: true,
             current_file_indicator: None,
+            view: SidebarView::default(),
+            pins: Vec::new(),
+            recent_files: Vec::new(),
         }
     }
 }
@@ -251,6 +480,9 @@ pub struct ChatMessage {
     pub cost: Option<f64>,
     pub status: MessageStatus,
     pub associated_files: Vec<PathBuf>,
+    /// What an apply did, when this message reports one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apply_summary: Option<crate::apply_summary::ApplySummary>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -275,6 +507,14 @@ pub struct ChatHistory {
     pub scroll_state: ListState,
     pub search_query: Option<String>,
     pub filter: Option<MessageFilter>,
+    /// Ids of long messages shown in full instead of collapsed
+    pub expanded: HashSet<usize>,
+    /// Message kept in view after the user scrolled up; `None` follows the latest
+    pub anchor: Option<usize>,
+    /// Messages that arrived while scrolled up
+    pub unseen: usize,
+    /// Whether the session's log has messages before the loaded ones
+    pub older_available: bool,
 }
 
 impl Default for ChatHistory {
@@ -285,6 +525,10 @@ impl Default for ChatHistory {
             scroll_state: ListState::default(),
             search_query: None,
             filter: None,
+            expanded: HashSet::new(),
+            anchor: None,
+            unseen: 0,
+            older_available: false,
         }
     }
 }
@@ -296,34 +540,103 @@ impl ChatHistory {
 
     pub fn add_message(&mut self, message: ChatMessage) {
         self.messages.push(message);
+        if self.anchor.is_some() {
+            self.unseen += 1;
+        }
+    }
+
+    /// Add `message` and append it to the log of session `session_id`; the
+    /// message is shown even when the log can't be written
+    pub fn record(&mut self, message: ChatMessage, session_id: Option<&str>) -> anyhow::Result<()> {
+        self.next_id = self.next_id.max(message.id + 1);
+        let logged = match session_id {
+            Some(id) => {
+                let path = crate::chat_log::log_path(&crate::chat_log::chats_dir(), id);
+                crate::chat_log::append(&path, &message)
+            }
+            None => Ok(()),
+        };
+        self.add_message(message);
+        logged
+    }
+
+    /// Put messages loaded from the log before the loaded ones, keeping the
+    /// view on the message it showed
+    pub fn prepend_older(&mut self, mut older: Vec<ChatMessage>, more: bool) {
+        let count = older.len();
+        older.append(&mut self.messages);
+        self.messages = older;
+        self.older_available = more;
+        if let Some(anchor) = self.anchor.as_mut() {
+            *anchor += count;
+        }
+    }
+
+    /// Index of the message the chat view keeps in view
+    pub fn visible_index(&self) -> Option<usize> {
+        let last = self.messages.len().checked_sub(1)?;
+        Some(self.anchor.map_or(last, |idx| idx.min(last)))
+    }
+
+    /// Move the view one message up, detaching it from the latest message
+    pub fn scroll_up(&mut self) {
+        if let Some(idx) = self.visible_index() {
+            self.anchor = Some(idx.saturating_sub(1));
+        }
+    }
+
+    /// Move the view one message down; reaching the end follows new messages again
+    pub fn scroll_down(&mut self) {
+        let Some(idx) = self.anchor else {
+            return;
+        };
+        if idx + 1 >= self.messages.len().saturating_sub(1) {
+            self.jump_to_latest();
+        } else {
+            self.anchor = Some(idx + 1);
+        }
+    }
+
+    pub fn jump_to_latest(&mut self) {
+        self.anchor = None;
+        self.unseen = 0;
     }
 
     pub fn get_message(&self, id: usize) -> Option<&ChatMessage> {
         self.messages.iter().find(|m| m.id == id)
     }
 
+    pub fn is_expanded(&self, id: usize) -> bool {
+        self.expanded.contains(&id)
+    }
+
+    /// Expand or collapse the latest assistant reply; returns whether it is now expanded
+    pub fn toggle_latest_reply(&mut self) -> Option<bool> {
+        let id = self.messages.iter().rev().find(|m| !m.is_user)?.id;
+        if self.expanded.remove(&id) {
+            Some(false)
+        } else {
+            self.expanded.insert(id);
+            Some(true)
+        }
+    }
+
     pub fn filtered_messages(&self) -> Vec<&ChatMessage> {
         let mut filtered: Vec<&ChatMessage> = self.messages.iter().collect();
 
         // Apply filter
         if let Some(ref filter) = self.filter {
-            filtered = filtered
-                .into_iter()
-                .filter(|msg| match filter {
-                    MessageFilter::Error => msg.status == MessageStatus::Error,
-                    MessageFilter::Success => msg.status == MessageStatus::Success,
-                    MessageFilter::All => true,
-                })
-                .collect();
+            filtered.retain(|msg| match filter {
+                MessageFilter::Error => msg.status == MessageStatus::Error,
+                MessageFilter::Success => msg.status == MessageStatus::Success,
+                MessageFilter::All => true,
+            });
         }
 
         // Apply search query
         if let Some(ref query) = self.search_query {
             let query_lower = query.to_lowercase();
-            filtered = filtered
-                .into_iter()
-                .filter(|msg| msg.content.to_lowercase().contains(&query_lower))
-                .collect();
+            filtered.retain(|msg| msg.content.to_lowercase().contains(&query_lower));
         }
 
         filtered
@@ -331,6 +644,7 @@ impl ChatHistory {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Mode {
     ProviderSelect,
     PromptEntry,
@@ -341,6 +655,15 @@ pub enum Mode {
     ChatHistory,
     CommandMode,
     Help,
+    Usage,
+    Output,
+    History,
+    MessageDetail,
+    Perf,
+    Sessions,
+    Grep,
+    ConfigErrors,
+    TemplateForm,
 }
 
 #[derive(Debug, Clone)]
@@ -352,7 +675,466 @@ pub struct ProviderInfo {
     pub config_key: String,
 }
 
-/// Main plugin state
+/// A prompt that failed because the provider needs a login, kept so it can be
+/// re-run once the login command succeeds
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginRetry {
+    /// Login command followed by its arguments
+    pub command: Vec<String>,
+    pub prompt: String,
+}
+
+/// Scroll position of a dialog whose text can be taller than the dialog, and
+/// the rows selected in it for copying
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DialogScroll {
+    /// First row shown
+    pub offset: usize,
+    /// Rows of text, and how many of them the dialog shows; set when it is drawn
+    pub rows: usize,
+    pub visible: usize,
+    /// Columns the text was wrapped to
+    pub width: usize,
+    /// Row the selection started on (where `s` was pressed) and the row it
+    /// has moved to
+    pub selection: Option<(usize, usize)>,
+}
+
+impl DialogScroll {
+    /// Record the dialog's size as drawn, keeping the view inside the text
+    pub fn fit(&mut self, rows: usize, visible: usize, width: usize) {
+        self.rows = rows;
+        self.visible = visible;
+        self.width = width;
+        self.offset = self.offset.min(rows.saturating_sub(visible));
+        if let Some((start, end)) = self.selection.as_mut() {
+            let last = rows.saturating_sub(1);
+            (*start, *end) = ((*start).min(last), (*end).min(last));
+        }
+    }
+
+    /// Move `delta` rows: the end of the selection while selecting (scrolling
+    /// to keep it in view), otherwise the view
+    pub fn scroll_by(&mut self, delta: isize) {
+        match self.selection.as_mut() {
+            Some((_, end)) => {
+                *end = end
+                    .saturating_add_signed(delta)
+                    .min(self.rows.saturating_sub(1));
+                if *end < self.offset {
+                    self.offset = *end;
+                } else if *end >= self.offset + self.visible.max(1) {
+                    self.offset = *end + 1 - self.visible.max(1);
+                }
+            }
+            None => {
+                let max = self.rows.saturating_sub(self.visible);
+                self.offset = self.offset.saturating_add_signed(delta).min(max);
+            }
+        }
+    }
+
+    /// Move a page (the rows shown) at a time
+    pub fn page_by(&mut self, pages: isize) {
+        self.scroll_by(pages * self.visible.max(1) as isize);
+    }
+
+    /// Start selecting at the first row shown, or stop selecting
+    pub fn toggle_selection(&mut self) {
+        self.selection = match self.selection {
+            Some(_) => None,
+            None => Some((self.offset, self.offset)),
+        };
+    }
+
+    /// The selected rows, first to last
+    pub fn selected(&self) -> Option<std::ops::Range<usize>> {
+        let (start, end) = self.selection?;
+        Some(start.min(end)..start.max(end) + 1)
+    }
+}
+
+/// Raw output of a failed provider run, shown in a scrollable, searchable viewer
+#[derive(Debug, Clone, Default)]
+pub struct OutputViewer {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub scroll: usize,
+    /// Search text being typed after `/`; `None` when not typing
+    pub input: Option<String>,
+    pub query: String,
+    /// Indices of lines matching `query`
+    pub matches: Vec<usize>,
+    pub current_match: usize,
+}
+
+impl OutputViewer {
+    pub fn new(title: String, output: &str) -> Self {
+        Self {
+            title,
+            lines: output.lines().map(str::to_string).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        let max = self.lines.len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// Search case-insensitively and jump to the first match at or after the view
+    pub fn search(&mut self, query: &str) {
+        self.query = query.to_string();
+        let needle = query.to_lowercase();
+        self.matches = if needle.is_empty() {
+            Vec::new()
+        } else {
+            self.lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| line.to_lowercase().contains(&needle))
+                .map(|(idx, _)| idx)
+                .collect()
+        };
+        self.current_match = self
+            .matches
+            .iter()
+            .position(|&line| line >= self.scroll)
+            .unwrap_or(0);
+        self.jump_to_match();
+    }
+
+    /// Move to the next (or previous) match, wrapping around
+    pub fn next_match(&mut self, forward: bool) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let len = self.matches.len();
+        self.current_match = if forward {
+            (self.current_match + 1) % len
+        } else {
+            (self.current_match + len - 1) % len
+        };
+        self.jump_to_match();
+    }
+
+    fn jump_to_match(&mut self) {
+        if let Some(&line) = self.matches.get(self.current_match) {
+            self.scroll = line;
+        }
+    }
+}
+
+/// Past applies listed by `:history`, newest first
+#[derive(Debug, Clone, Default)]
+pub struct HistoryView {
+    pub entries: Vec<crate::journal::JournalEntry>,
+    pub selected: usize,
+}
+
+/// Sessions listed by the quick switcher (`Ctrl+Tab`, `:sessions`)
+pub const SWITCHER_SESSIONS: usize = 9;
+
+/// The quick session switcher
+pub struct SessionSwitcher {
+    pub entries: Vec<SwitcherEntry>,
+    pub selected: usize,
+}
+
+impl SessionSwitcher {
+    /// Move the selection, wrapping around like the switcher it imitates
+    pub fn select_by(&mut self, delta: isize) {
+        let len = self.entries.len().max(1) as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+    }
+
+    pub fn selected_entry(&self) -> Option<&SwitcherEntry> {
+        self.entries.get(self.selected)
+    }
+}
+
+/// One session of the switcher
+#[derive(Debug, Clone)]
+pub struct SwitcherEntry {
+    pub id: String,
+    pub provider: String,
+    pub title: String,
+    pub last_used: chrono::DateTime<chrono::Utc>,
+    pub prompts: u32,
+    /// The session shown now
+    pub current: bool,
+    /// Left with a review or prompt draft that switching back restores
+    pub in_progress: bool,
+}
+
+/// Matches `:grep` lists at most
+pub const GREP_HITS: usize = 200;
+
+/// Messages of all sessions matching a `:grep`, grouped by session
+#[derive(Debug, Clone)]
+pub struct GrepView {
+    pub query: String,
+    pub groups: Vec<GrepGroup>,
+    /// Index into the hits of all groups, in order
+    pub selected: usize,
+}
+
+/// The matches in one session
+#[derive(Debug, Clone)]
+pub struct GrepGroup {
+    pub session_id: String,
+    pub title: String,
+    pub hits: Vec<crate::chat_log::Hit>,
+}
+
+impl GrepView {
+    /// Group `hits` by session, the current session first and the others most
+    /// recently used first; hits of sessions that no longer exist are dropped
+    pub fn new(
+        query: &str,
+        hits: Vec<crate::chat_log::Hit>,
+        sessions: &crate::session::SessionManager,
+    ) -> Self {
+        let mut groups: Vec<GrepGroup> = Vec::new();
+        for hit in hits {
+            if let Some(group) = groups.iter_mut().find(|g| g.session_id == hit.session_id) {
+                group.hits.push(hit);
+            } else if let Some(session) = sessions.sessions.get(&hit.session_id) {
+                groups.push(GrepGroup {
+                    session_id: session.id.clone(),
+                    title: session_title(session),
+                    hits: vec![hit],
+                });
+            }
+        }
+        let current = sessions.current_session_id.as_deref();
+        groups.sort_by_key(|g| {
+            let session = &sessions.sessions[&g.session_id];
+            (
+                current != Some(g.session_id.as_str()),
+                std::cmp::Reverse(session.last_used),
+            )
+        });
+        Self {
+            query: query.to_string(),
+            groups,
+            selected: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.iter().map(|g| g.hits.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn select_by(&mut self, delta: isize) {
+        let last = self.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + delta).clamp(0, last) as usize;
+    }
+
+    pub fn selected_hit(&self) -> Option<&crate::chat_log::Hit> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.hits.iter())
+            .nth(self.selected)
+    }
+}
+
+/// Title of a session: its first prompt, or when it was started
+fn session_title(session: &crate::session::Session) -> String {
+    if session.description.is_empty() {
+        format!(
+            "Session of {}",
+            session
+                .created_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+        )
+    } else {
+        session.description.clone()
+    }
+}
+
+/// The review and prompt draft of a session switched away from
+struct SessionStash {
+    pending_changes: HashMap<PathBuf, FileChange>,
+    hunks: Vec<Hunk>,
+    selected_hunk: usize,
+    overlay_diff_state: OverlayDiffState,
+    git_review: Option<crate::git::GitReview>,
+    applied_review: bool,
+    write_policy: WritePolicy,
+    path_warnings: HashMap<PathBuf, String>,
+    edit_conflicts: HashMap<PathBuf, Vec<String>>,
+    prompt_buffer: String,
+    cursor_position: usize,
+    context_attachments: Vec<ContextAttachment>,
+}
+
+impl HistoryView {
+    pub fn select_by(&mut self, delta: isize) {
+        let max = self.entries.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(max);
+    }
+
+    pub fn selected_entry(&self) -> Option<&crate::journal::JournalEntry> {
+        self.entries.get(self.selected)
+    }
+}
+
+/// The keys that can follow an unfinished key sequence, shown once it has
+/// waited `display.which_key_delay_ms` for the next one
+#[derive(Debug, Clone, Default)]
+pub struct WhichKey {
+    /// The keys pressed so far, such as `["g"]`
+    pub prefix: Vec<String>,
+    /// Each next key with what it does
+    pub entries: Vec<(String, String)>,
+}
+
+/// One chat message shown in full, with its links selectable
+#[derive(Debug, Clone, Default)]
+pub struct MessageDetail {
+    pub message_id: usize,
+    pub links: Vec<String>,
+    /// Index into `links`; `None` until Tab is first pressed
+    pub selected: Option<usize>,
+    pub scroll: usize,
+    /// The message's fenced code blocks
+    pub blocks: Vec<crate::parsers::CodeBlock>,
+    /// Index into `blocks`; `None` until `b` is first pressed
+    pub selected_block: Option<usize>,
+    /// The file the selected block is to be diffed against, while it is typed
+    pub diff_path: Option<String>,
+}
+
+impl MessageDetail {
+    pub fn new(message: &ChatMessage) -> Self {
+        Self {
+            message_id: message.id,
+            links: crate::links::find_urls(&message.content),
+            selected: None,
+            scroll: 0,
+            blocks: crate::parsers::code_blocks(&message.content),
+            selected_block: None,
+            diff_path: None,
+        }
+    }
+
+    /// Select the next code block (or the previous one), wrapping around
+    pub fn cycle_block(&mut self, forward: bool) {
+        let count = self.blocks.len();
+        if count == 0 {
+            return;
+        }
+        self.selected_block = Some(match (self.selected_block, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(idx), true) => (idx + 1) % count,
+            (Some(idx), false) => (idx + count - 1) % count,
+        });
+    }
+
+    pub fn selected_block(&self) -> Option<&crate::parsers::CodeBlock> {
+        self.blocks.get(self.selected_block?)
+    }
+
+    /// Start typing the file to diff the selected block (the first, if none
+    /// is) against, beginning with the path the block names, followed through
+    /// `renames`. `false` when the message has no code blocks.
+    pub fn start_diff(&mut self, renames: &HashMap<PathBuf, PathBuf>) -> bool {
+        if self.selected_block.is_none() {
+            self.cycle_block(true);
+        }
+        let Some(block) = self.selected_block() else {
+            return false;
+        };
+        let path = block
+            .path
+            .as_ref()
+            .map(|p| renamed_path(renames, p).display().to_string());
+        self.diff_path = Some(path.unwrap_or_default());
+        true
+    }
+
+    /// Select the next link (or the previous one), wrapping around
+    pub fn cycle_link(&mut self, forward: bool) {
+        let count = self.links.len();
+        if count == 0 {
+            return;
+        }
+        self.selected = Some(match (self.selected, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(idx), true) => (idx + 1) % count,
+            (Some(idx), false) => (idx + count - 1) % count,
+        });
+    }
+
+    pub fn selected_link(&self) -> Option<&str> {
+        self.links.get(self.selected?).map(String::as_str)
+    }
+}
+
+/// Where `path` is after `renames` (old path to new), following a file
+/// renamed more than once
+pub fn renamed_path(renames: &HashMap<PathBuf, PathBuf>, path: &Path) -> PathBuf {
+    let mut path = path.to_path_buf();
+    // Bounded, so renames that come back around can't loop
+    for _ in 0..renames.len() {
+        match renames.get(&path) {
+            Some(new) => path = new.clone(),
+            None => break,
+        }
+    }
+    path
+}
+
+/// Complete the last component of `partial` as far as the entries of its
+/// directory agree, adding `/` to a directory. Hidden entries are offered
+/// only for a component starting with `.`.
+pub fn complete_path(partial: &str) -> String {
+    let (dir, prefix) = match partial.rfind('/') {
+        Some(idx) => (&partial[..=idx], &partial[idx + 1..]),
+        None => ("", partial),
+    };
+    let Ok(entries) = std::fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return partial.to_string();
+    };
+    let mut matches: Vec<(String, bool)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let shown = !name.starts_with('.') || prefix.starts_with('.');
+            (shown && name.starts_with(prefix)).then(|| (name, entry.path().is_dir()))
+        })
+        .collect();
+    matches.sort();
+    let Some((first, _)) = matches.first() else {
+        return partial.to_string();
+    };
+    let common = matches.iter().fold(first.clone(), |common, (name, _)| {
+        common
+            .chars()
+            .zip(name.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c)
+            .collect()
+    });
+    match matches.as_slice() {
+        [(name, true)] => format!("{}{}/", dir, name),
+        _ => format!("{}{}", dir, common),
+    }
+}
+
+/// Main application state
 pub struct State {
     // Provider management
     pub provider: Option<Box<dyn AIProvider>>,
@@ -374,24 +1156,118 @@ pub struct State {
     pub prompt_buffer: String,
     pub cursor_position: usize,
     pub command_buffer: String,
+    pub context_attachments: Vec<ContextAttachment>,
+    // Built on the first `#` typed in the prompt
+    pub symbol_index: Option<SymbolIndex>,
+    // Completions for the `#symbol` under the cursor
+    pub symbol_suggestions: Vec<Symbol>,
+    pub symbol_selected: usize,
 
     // Session management
     pub sessions: SessionManager,
+    // Held while running so other instances can see this one
+    pub instance_lock: Option<crate::lock::InstanceLock>,
 
     // Pending changes
     pub pending_changes: HashMap<PathBuf, FileChange>,
 
+    // Set while reviewing `git diff` output instead of provider changes
+    pub git_review: Option<crate::git::GitReview>,
+    // The review has been written to disk and stays open to show test results
+    pub applied_review: bool,
+    // When the review was loaded, for the apply summary
+    pub review_started: Option<std::time::Instant>,
+    // The running prompt is a fix request; its answer is merged into the review
+    pub refining_review: bool,
+    // Whether prompts ask for edits or only for an answer
+    pub prompt_mode: PromptMode,
+
+    // Notes on new files whose path and code block language disagree
+    pub path_warnings: HashMap<PathBuf, String>,
+
+    // Files the reply changed more than once in the same lines, with each
+    // change's proposed content; applying waits until one is picked
+    pub edit_conflicts: HashMap<PathBuf, Vec<String>>,
+
+    // `.zcodeignore` rules (plus per-review overrides) for the current review
+    pub write_policy: WritePolicy,
+
+    // Raw stdout of the last successful provider run (for :report-parse-failure)
+    pub last_provider_output: Option<String>,
+
     // Error handling
     pub last_error: Option<ErrorDisplay>,
-
-    // Permissions
-    pub permissions_granted: bool,
+    /// Full output of the last failed provider run
+    pub failed_output: Option<OutputViewer>,
+    pub login_retry: Option<LoginRetry>,
+    pub last_prompt: Option<String>,
+    /// Command line of the last provider run (for the preview and :copy-command)
+    pub last_command: Option<crate::executor::CommandLine>,
+    // Estimated tokens of the last prompt as sent, context included (for :usage)
+    pub last_prompt_tokens: usize,
 
     // Configuration
     pub config: Config,
+    // Global plus project preamble sent with every prompt
+    pub preamble: Option<crate::preamble::Preamble>,
+
+    // What the provider is doing during the current/last prompt
+    pub activity: crate::activity::ActivityTimeline,
 
     // File operations result
     pub last_apply_result: Option<crate::file_ops::ApplyResult>,
+    /// Files renamed by applies this run, old path to new, so that paths
+    /// named before a rename still find the file
+    pub renamed_files: HashMap<PathBuf, PathBuf>,
+    // Journal entries shown by :history
+    pub history: Option<HistoryView>,
+    pub message_detail: Option<MessageDetail>,
+    pub session_switcher: Option<SessionSwitcher>,
+    pub which_key: Option<WhichKey>,
+    pub grep: Option<GrepView>,
+    /// Variables of the template being filled in (`:template`)
+    pub template_form: Option<crate::templates::TemplateForm>,
+    /// Settings of the config files that were left out, shown at startup
+    pub config_problems: Vec<crate::config::ConfigProblem>,
+    pub config_problem_selected: usize,
+    /// Contents of the config file and project file when last read
+    pub config_sources: [Option<String>; 2],
+    /// Options the Zellij layout passed, applied over the config file
+    pub layout_options: BTreeMap<String, String>,
+    /// Shell commands the last reply suggested and that haven't run yet
+    pub command_suggestions: Vec<String>,
+    /// Ctrl+R was pressed once and the next press runs the first suggestion
+    pub suggestion_armed: bool,
+    /// Reviews and prompt drafts of sessions switched away from, by session id
+    session_stashes: HashMap<String, SessionStash>,
+    /// Rows scrolled past in the apply confirmation and the error dialog
+    pub confirmation_scroll: DialogScroll,
+    pub error_scroll: DialogScroll,
+    /// Rules flagging risky hunks
+    pub guard: crate::guard::Guard,
+    pub auto_accept: crate::auto_accept::AutoAccept,
+    /// Set by the first confirmation of an apply with flagged hunks
+    pub guard_confirmed: bool,
+    /// Project summary sent to providers that don't read the repository
+    pub project_summary: crate::context::summary::SummaryCache,
+    /// Branch name (template) the next apply goes to, set by `:apply-branch`
+    pub apply_branch: Option<String>,
+    /// The `[checklist]` items of the apply confirmation
+    pub checklist: Vec<crate::checklist::Entry>,
+    /// Files the apply would leave unparseable, shown in the confirmation
+    pub syntax_problems: Vec<crate::syntax::Problem>,
+    /// Added lines of a hunk shown reindented by `=`, waiting for `=` again
+    pub reindent: Option<crate::reindent::Reindent>,
+    /// Timings shown by `:perf`
+    pub perf: crate::perf::Perf,
+    /// Hunks of recently diffed files, by content
+    pub diff_cache: crate::diff::DiffCache,
+    /// Large files waiting to be diffed in the background
+    pub diff_jobs: Vec<crate::diff::DiffJob>,
+    /// Background diffs started but not finished
+    pub diffs_running: HashSet<crate::diff::DiffKey>,
+    /// The `:foreach` in progress
+    pub batch: Option<crate::batch::BatchRun>,
 
     // Chat history
     pub chat_history: ChatHistory,
@@ -405,10 +1281,35 @@ pub struct State {
     // Sidebar state
     pub sidebar_state: SidebarState,
 
+    // Review macros (`q<reg>` records, `@<reg>` replays)
+    pub macros: Macros,
+
+    // Files left out of the apply and the progress counts (`X`), kept with the session
+    pub skipped_files: BTreeSet<PathBuf>,
+
+    // Opt-in usage metrics (`[telemetry]`)
+    pub telemetry: Telemetry,
+
     // UI preferences
     pub ui_prefs: UIPreferences,
 }
 
+/// Whether two hunks make the same edit, ignoring where in the file they sit
+fn stale_error(path: &std::path::Path) -> String {
+    format!(
+        "{} changed on disk in the lines its proposed changes touch; reject them or ask again",
+        path.display()
+    )
+}
+
+fn same_changes(a: &Hunk, b: &Hunk) -> bool {
+    a.changes.len() == b.changes.len()
+        && a.changes
+            .iter()
+            .zip(&b.changes)
+            .all(|(x, y)| x.tag == y.tag && x.content == y.content)
+}
+
 impl Default for State {
     fn default() -> Self {
         Self {
@@ -427,27 +1328,108 @@ impl Default for State {
             prompt_buffer: String::new(),
             cursor_position: 0,
             command_buffer: String::new(),
+            context_attachments: Vec::new(),
+            symbol_index: None,
+            symbol_suggestions: Vec::new(),
+            symbol_selected: 0,
             sessions: SessionManager::default(),
+            instance_lock: None,
             pending_changes: HashMap::new(),
+            refining_review: false,
+            prompt_mode: PromptMode::default(),
+            path_warnings: HashMap::new(),
+            edit_conflicts: HashMap::new(),
+            git_review: None,
+            applied_review: false,
+            review_started: None,
+            write_policy: WritePolicy::default(),
+            last_provider_output: None,
             last_error: None,
-            permissions_granted: false,
+            failed_output: None,
+            login_retry: None,
+            last_prompt: None,
+            last_command: None,
+            last_prompt_tokens: 0,
             config: Config::default(),
+            preamble: None,
+            activity: crate::activity::ActivityTimeline::default(),
             last_apply_result: None,
+            renamed_files: HashMap::new(),
+            history: None,
+            message_detail: None,
+            session_switcher: None,
+            which_key: None,
+            grep: None,
+            template_form: None,
+            config_problems: Vec::new(),
+            layout_options: BTreeMap::new(),
+            config_problem_selected: 0,
+            config_sources: Default::default(),
+            command_suggestions: Vec::new(),
+            suggestion_armed: false,
+            session_stashes: HashMap::new(),
+            confirmation_scroll: DialogScroll::default(),
+            error_scroll: DialogScroll::default(),
+            guard: crate::guard::Guard::default(),
+            auto_accept: crate::auto_accept::AutoAccept::default(),
+            guard_confirmed: false,
+            checklist: Vec::new(),
+            syntax_problems: Vec::new(),
+            reindent: None,
+            apply_branch: None,
+            project_summary: Default::default(),
+            perf: crate::perf::Perf::default(),
+            diff_cache: crate::diff::DiffCache::default(),
+            diff_jobs: Vec::new(),
+            diffs_running: HashSet::new(),
+            batch: None,
             chat_history: ChatHistory::new(),
             overlay_diff_state: OverlayDiffState::default(),
             status_info: StatusInfo::default(),
             sidebar_state: SidebarState::default(),
+            macros: Macros::default(),
+            skipped_files: BTreeSet::new(),
+            telemetry: Telemetry::default(),
             ui_prefs: UIPreferences::default(),
         }
     }
 }
 
 impl State {
-    pub fn initialize(&mut self, _configuration: &BTreeMap<String, String>) -> anyhow::Result<()> {
-        // Load configuration with error reporting
-        self.config = match Config::load() {
-            Ok(config) => config,
-            Err(e) => {
+    /// Scroll state of the dialog open in the current mode, if any
+    pub fn dialog_scroll_mut(&mut self) -> Option<&mut DialogScroll> {
+        match self.mode {
+            Mode::Confirmation => Some(&mut self.confirmation_scroll),
+            Mode::Error => Some(&mut self.error_scroll),
+            _ => None,
+        }
+    }
+
+    /// Whether keys that aren't bindings type into the prompt, which then
+    /// holds the terminal cursor
+    pub fn prompt_focused(&self) -> bool {
+        matches!(
+            self.mode,
+            Mode::PromptEntry | Mode::ChatHistory | Mode::Processing
+        )
+    }
+
+    pub fn initialize(&mut self, configuration: &BTreeMap<String, String>) -> anyhow::Result<()> {
+        // The layout can point at another config file, and its options win over it
+        if let Some(path) = configuration.get("config") {
+            Config::use_config_path(PathBuf::from(path));
+        }
+        self.layout_options = configuration.clone();
+
+        // Load configuration with error reporting
+        self.config = match Config::read_sources() {
+            Ok(sources) => {
+                let (config, problems) = Self::parse_config_sources(&sources);
+                self.config_sources = sources;
+                self.show_config_problems(problems);
+                config
+            }
+            Err(e) => {
                 self.last_error = Some(ErrorDisplay {
                     title: "Config Warning".to_string(),
                     message: format!("Using defaults: {}", e),
@@ -456,6 +1438,39 @@ impl State {
                 Config::default()
             }
         };
+        let problems = self.config.apply_layout_options(&self.layout_options);
+        self.overlay_diff_state.line_numbers = self.config.display.show_line_numbers;
+        self.start_telemetry();
+        if !problems.is_empty() && self.last_error.is_none() {
+            self.last_error = Some(ErrorDisplay {
+                title: "Layout Option Error".to_string(),
+                message: format!("Ignoring layout options:\n{}", problems.join("\n")),
+                help_url: None,
+            });
+        }
+
+        if let Err(e) = crate::i18n::init(self.config.display.language.as_deref()) {
+            self.status_info.current_task = tf(
+                "status.locale_not_loaded",
+                &[("error", &format!("{:#}", e))],
+            );
+        }
+        self.perf.log_to(crate::perf::log_path());
+
+        // Custom provider specs are checked up front so typos surface at startup
+        let problems =
+            crate::providers::remove_invalid_custom_providers(&mut self.config.providers);
+        if !problems.is_empty() && self.last_error.is_none() {
+            self.last_error = Some(ErrorDisplay {
+                title: "Provider Config Error".to_string(),
+                message: format!("Skipping invalid providers:\n{}", problems.join("\n")),
+                help_url: None,
+            });
+        }
+
+        self.reload_preamble();
+
+        self.compile_rules();
 
         // Load sessions with error reporting
         self.sessions = match SessionManager::load() {
@@ -473,174 +1488,3590 @@ impl State {
             }
         };
 
+        // Register this instance so others know sessions.json is shared
+        match crate::lock::InstanceLock::acquire(&crate::lock::instances_dir()) {
+            Ok(mut lock) => {
+                let others = lock.other_instances();
+                if let Some(pid) = others.first() {
+                    self.status_info.current_task = tf("status.other_instance", &[("pid", pid)]);
+                }
+
+                // Inside Zellij each pane goes back to its own last session
+                if let Some(id) = lock
+                    .pane_session()
+                    .filter(|id| self.sessions.sessions.contains_key(id))
+                {
+                    self.sessions.current_session_id = Some(id);
+                }
+                // A session another instance works in isn't continued here too
+                let current = self.sessions.current_session_id.clone();
+                if current.is_some_and(|id| lock.other_sessions().contains(&id)) {
+                    self.sessions.current_session_id = None;
+                    self.status_info.current_task = t("status.session_in_use").to_string();
+                }
+                if let Err(e) = lock.set_session(self.sessions.current_session_id.as_deref()) {
+                    self.status_info.current_task = tf("status.lock_not_updated", &[("error", &e)]);
+                }
+                self.instance_lock = Some(lock);
+            }
+            Err(e) => {
+                self.status_info.current_task = tf("status.lock_unavailable", &[("error", &e)]);
+            }
+        }
+
+        self.restore_chat();
+        self.load_sidebar_files();
+        self.load_macros();
+        self.load_skipped_files();
+
         Ok(())
     }
 
-    // Provider detection is now handled by App struct
+    /// Open the form for template `name`, returning how many variables it asks for
+    pub fn open_template(&mut self, name: &str) -> anyhow::Result<usize> {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let template =
+            crate::templates::Template::load(name, &cwd, &crate::templates::global_dir())?;
+        let form = crate::templates::TemplateForm::new(template);
+        let count = form.fields.len();
+        self.template_form = Some(form);
+        self.mode = Mode::TemplateForm;
+        Ok(count)
+    }
 
-    // Command result handling is now in App struct
+    /// Open the config-error screen on `problems`, if there are any
+    pub fn show_config_problems(&mut self, problems: Vec<crate::config::ConfigProblem>) {
+        self.config_problem_selected = 0;
+        self.config_problems = problems;
+        if !self.config_problems.is_empty() {
+            self.mode = Mode::ConfigErrors;
+        }
+    }
 
-    /// Apply accepted hunks to files
-    pub fn apply_changes(&mut self) -> anyhow::Result<crate::file_ops::ApplyResult> {
-        let accepted_hunks: Vec<_> = self
-            .hunks
-            .iter()
-            .filter(|h| h.status == HunkStatus::Accepted)
-            .collect();
+    /// Export metrics as `[telemetry]` says
+    fn start_telemetry(&mut self) {
+        let config = &self.config.telemetry;
+        if config.enabled && !crate::telemetry::AVAILABLE {
+            self.status_info.current_task =
+                "Telemetry is enabled in the config, but this build lacks the `telemetry` feature"
+                    .to_string();
+        }
+        self.telemetry.reconfigure(config);
+        self.status_info.telemetry = self.telemetry.enabled();
+    }
 
-        if accepted_hunks.is_empty() {
-            return Err(anyhow::anyhow!("No accepted hunks to apply"));
+    /// The config and its problems from the contents of the config file and
+    /// the project file
+    fn parse_config_sources(
+        sources: &[Option<String>; 2],
+    ) -> (Config, Vec<crate::config::ConfigProblem>) {
+        let project_path = Config::source_paths()[1].clone();
+        Config::parse_layered(
+            sources[0].as_deref(),
+            sources[1]
+                .as_deref()
+                .map(|content| (project_path.as_path(), content)),
+        )
+    }
+
+    /// Compile the guard and auto-accept rules of the config
+    fn compile_rules(&mut self) {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        match crate::guard::Guard::new(&cwd, &self.config.guard.rules) {
+            Ok(guard) => self.guard = guard,
+            Err(e) => {
+                self.status_info.current_task =
+                    tf("status.guard_off", &[("error", &format!("{:#}", e))])
+            }
+        }
+        match crate::auto_accept::AutoAccept::new(&cwd, &self.config.auto_accept) {
+            Ok(rules) => self.auto_accept = rules,
+            Err(e) => {
+                self.status_info.current_task =
+                    tf("status.auto_accept_off", &[("error", &format!("{:#}", e))])
+            }
+        }
+    }
+
+    /// Check the config files again after one was edited. Returns whether the
+    /// settings were taken.
+    ///
+    /// With `live` (the file watcher saw the change) nothing happens while the
+    /// files read the same as last time, and settings with problems are not
+    /// taken: the current ones stay until the file is fixed, and `:config
+    /// edit` goes to the first problem.
+    pub fn reload_config(&mut self, live: bool) -> bool {
+        let sources = match Config::read_sources() {
+            Ok(sources) => sources,
+            Err(e) => {
+                self.status_info.current_task = tf(
+                    "status.config_not_reloaded",
+                    &[("error", &format!("{:#}", e))],
+                );
+                return false;
+            }
+        };
+        if live && sources == self.config_sources {
+            return false;
         }
+        let (config, problems) = Self::parse_config_sources(&sources);
+        self.config_sources = sources;
+        let language = self.config.display.language.clone();
+        if live && !problems.is_empty() {
+            self.status_info.current_task =
+                tf("status.config_problem", &[("problem", &problems[0])]);
+            self.config_problem_selected = 0;
+            self.config_problems = problems;
+            return false;
+        }
+
+        self.config = config;
+        self.config.apply_layout_options(&self.layout_options);
+        let invalid = crate::providers::remove_invalid_custom_providers(&mut self.config.providers);
+        self.overlay_diff_state.line_numbers = self.config.display.show_line_numbers;
+        self.compile_rules();
+        self.status_info.current_task = if !invalid.is_empty() {
+            tf(
+                "status.config_reloaded_invalid",
+                &[("providers", &invalid.join("; "))],
+            )
+        } else if problems.is_empty() {
+            t("status.config_reloaded").to_string()
+        } else {
+            tf(
+                "status.config_reloaded_problems",
+                &[("count", &problems.len())],
+            )
+        };
+        if self.config.display.language != language {
+            if let Err(e) = crate::i18n::init(self.config.display.language.as_deref()) {
+                self.status_info.current_task = tf(
+                    "status.config_reloaded_no_locale",
+                    &[("error", &format!("{:#}", e))],
+                );
+            }
+        }
+        if live {
+            self.config_problems.clear();
+        } else {
+            self.show_config_problems(problems);
+        }
+        self.start_telemetry();
+        if self.config_problems.is_empty() && self.mode == Mode::ConfigErrors {
+            self.mode = if self.provider.is_some() {
+                Mode::PromptEntry
+            } else {
+                Mode::ProviderSelect
+            };
+        }
+        true
+    }
 
-        crate::file_ops::apply_accepted_hunks(&accepted_hunks, &self.pending_changes, &self.config)
+    /// File of the selected config problem, for the editor
+    pub fn config_problem_path(&self) -> PathBuf {
+        self.config_problems
+            .get(self.config_problem_selected)
+            .and_then(|p| p.file.clone())
+            .unwrap_or_else(Config::config_path)
     }
 
-    // Prompt execution is now handled by App struct
+    /// 1-based line of the selected config problem, for the editor
+    pub fn config_problem_line(&self) -> Option<usize> {
+        self.config_problems
+            .get(self.config_problem_selected)
+            .and_then(|p| p.line)
+    }
+
+    /// Record the current session in the instance lock, so other instances
+    /// leave it alone
+    pub fn sync_instance_session(&mut self) {
+        let Some(lock) = &mut self.instance_lock else {
+            return;
+        };
+        if let Err(e) = lock.set_session(self.sessions.current_session_id.as_deref()) {
+            self.status_info.current_task = tf("status.lock_not_updated", &[("error", &e)]);
+        }
+    }
+
+    /// Move messages still embedded in `sessions.json` into their chat logs,
+    /// trim old logs and load the newest page of the current session's chat
+    fn restore_chat(&mut self) {
+        let dir = crate::chat_log::chats_dir();
+        let mut problems = Vec::new();
+
+        for session in self.sessions.sessions.values_mut() {
+            if session.messages.is_empty() {
+                continue;
+            }
+            let path = crate::chat_log::log_path(&dir, &session.id);
+            if !path.exists() {
+                if let Err(e) = crate::chat_log::append_all(&path, &session.messages) {
+                    problems.push(format!("{:#}", e));
+                    continue;
+                }
+            }
+            session.messages.clear();
+            self.sessions.dirty = true;
+        }
+
+        let current = self.sessions.current_session_id.clone();
+        if let Err(e) =
+            crate::chat_log::compact(&dir, crate::chat_log::COMPACT_KEEP, current.as_deref())
+        {
+            problems.push(format!("{:#}", e));
+        }
+
+        if let Some(id) = current {
+            if let Err(e) = self.load_session_chat(&id) {
+                problems.push(format!("{:#}", e));
+            }
+        }
 
-    // Key handling is now in App struct
-    #[allow(dead_code)]
-    pub fn handle_key_legacy(&mut self, key: &crossterm::event::KeyEvent) -> bool {
-        use crate::input::modes::*;
-        use crate::input::{Action, InputHandler, InputResult};
+        if let Some(problem) = problems.first() {
+            self.status_info.current_task = tf("status.chat_history_error", &[("error", problem)]);
+        }
+    }
+
+    /// Replace the chat with the newest page of session `id`'s log
+    fn load_session_chat(&mut self, id: &str) -> anyhow::Result<()> {
+        let path = crate::chat_log::log_path(&crate::chat_log::chats_dir(), id);
+        let page = crate::chat_log::load_page(&path, None, crate::chat_log::PAGE_SIZE)?;
+        let history = &mut self.chat_history;
+        *history = ChatHistory::default();
+        history.next_id = page.messages.last().map_or(1, |m| m.id + 1);
+        history.messages = page.messages;
+        history.older_available = page.more;
+        Ok(())
+    }
 
-        let result = match self.mode {
-            Mode::ProviderSelect => {
-                let mut handler = ProviderSelectHandler::new();
-                handler.handle_key(key, self)
+    /// List the current session's pinned and recent files in the sidebar
+    fn load_sidebar_files(&mut self) {
+        let sidebar = &mut self.sidebar_state;
+        match self.sessions.current_session() {
+            Some(session) => {
+                sidebar.pins = session.pinned_files.clone();
+                sidebar.recent_files = session.recent_files.clone();
             }
-            Mode::PromptEntry => {
-                let mut handler = PromptEntryHandler::new();
-                handler.handle_key(key, self)
+            None => {
+                sidebar.pins.clear();
+                sidebar.recent_files.clear();
             }
-            Mode::DiffReview => {
-                let mut handler = DiffReviewHandler::new();
-                handler.handle_key(key, self)
+        }
+    }
+
+    /// Take the review macros of the current session, dropping a recording
+    /// that was in progress
+    fn load_macros(&mut self) {
+        self.macros.reset();
+        self.status_info.recording_macro = None;
+        self.macros.registers = self
+            .sessions
+            .current_session()
+            .map(|session| session.macros.clone())
+            .unwrap_or_default();
+    }
+
+    /// Remember the review macros in the current session
+    pub fn save_macros(&mut self) {
+        self.sessions.set_macros(&self.macros.registers);
+    }
+
+    /// Take the files skipped in the current session's reviews
+    fn load_skipped_files(&mut self) {
+        self.skipped_files = self
+            .sessions
+            .current_session()
+            .map(|session| session.skipped_files.clone())
+            .unwrap_or_default();
+        for change in &mut self.overlay_diff_state.proposed_changes {
+            change.skipped = self.skipped_files.contains(&change.file_path);
+        }
+    }
+
+    /// Skip the selected hunk's file in this and later reviews of the session,
+    /// or include it again. Returns the file and whether it is now skipped.
+    pub fn toggle_skip_file(&mut self) -> Option<(PathBuf, bool)> {
+        let path = self.hunks.get(self.selected_hunk)?.file_path.clone();
+        let skipped = !self.skipped_files.remove(&path);
+        if skipped {
+            self.skipped_files.insert(path.clone());
+        }
+        for change in &mut self.overlay_diff_state.proposed_changes {
+            if change.file_path == path {
+                change.skipped = skipped;
             }
-            Mode::Confirmation => {
-                let mut handler = ConfirmationHandler::new();
-                handler.handle_key(key, self)
+        }
+        self.sessions.set_skipped_files(&self.skipped_files);
+        Some((path, skipped))
+    }
+
+    /// Accepted hunks of files that aren't skipped: what an apply writes
+    pub fn accepted_hunks(&self) -> impl Iterator<Item = &Hunk> {
+        self.hunks.iter().filter(|h| {
+            h.status == HunkStatus::Accepted && !self.skipped_files.contains(&h.file_path)
+        })
+    }
+
+    /// Remember the sidebar's pinned and recent files in the current session
+    pub fn save_sidebar_files(&mut self) {
+        self.sessions
+            .set_sidebar_files(&self.sidebar_state.pins, &self.sidebar_state.recent_files);
+    }
+
+    /// Open the quick switcher on the most recently used sessions, with the
+    /// one used before the current session selected. Returns how many it lists.
+    pub fn open_session_switcher(&mut self) -> usize {
+        let entries: Vec<SwitcherEntry> = self
+            .sessions
+            .recent_sessions(SWITCHER_SESSIONS)
+            .into_iter()
+            .map(|session| SwitcherEntry {
+                id: session.id.clone(),
+                provider: session.provider.clone(),
+                title: session_title(session),
+                last_used: session.last_used,
+                prompts: session.prompt_count,
+                current: self.sessions.current_session_id.as_deref() == Some(&session.id),
+                in_progress: self.session_stashes.contains_key(&session.id),
+            })
+            .collect();
+        let count = entries.len();
+        let selected = entries.iter().position(|e| !e.current).unwrap_or(0);
+        if count > 0 {
+            self.session_switcher = Some(SessionSwitcher { entries, selected });
+            self.mode = Mode::Sessions;
+        }
+        count
+    }
+
+    /// Show session `id` instead of the current one. The review, prompt draft
+    /// and attachments of the session left stay in memory and come back when
+    /// switching back to it.
+    pub fn switch_session(&mut self, id: &str) -> anyhow::Result<String> {
+        let busy = self.mode == Mode::Processing
+            || self.batch.as_ref().is_some_and(|b| !b.is_done())
+            || self.diffs_computing() > 0;
+        if busy {
+            anyhow::bail!("Wait for the running prompt to finish before switching sessions");
+        }
+        let Some(session) = self.sessions.sessions.get(id) else {
+            anyhow::bail!("Session {} no longer exists", id);
+        };
+        let in_use = self
+            .instance_lock
+            .as_ref()
+            .is_some_and(|lock| lock.other_sessions().iter().any(|other| other == id));
+        if in_use {
+            anyhow::bail!("{} is open in another instance", session_title(session));
+        }
+        let provider = session.provider.clone();
+        let mut note = tf(
+            "status.session_switched",
+            &[("title", &session_title(session))],
+        );
+        if self.sessions.current_session_id.as_deref() == Some(id) {
+            self.mode = self.session_mode();
+            return Ok(t("status.same_session").to_string());
+        }
+
+        if let Some(current) = self.sessions.current_session_id.clone() {
+            let stash = self.take_session_stash();
+            self.session_stashes.insert(current, stash);
+        }
+        self.sessions.current_session_id = Some(id.to_string());
+        self.sessions.touch();
+        if let Err(e) = self.load_session_chat(id) {
+            note.push_str(&tf(
+                "status.session_chat_not_loaded",
+                &[("error", &format!("{:#}", e))],
+            ));
+        }
+        self.load_sidebar_files();
+        self.load_macros();
+        self.load_skipped_files();
+        if let Some(stash) = self.session_stashes.remove(id) {
+            self.restore_session_stash(stash);
+        }
+
+        // The session continues with its own provider when that one is available
+        if self.provider.as_ref().is_none_or(|p| p.name() != provider) {
+            match self
+                .available_providers
+                .iter()
+                .position(|p| p.available && p.name == provider)
+            {
+                Some(idx) => {
+                    self.select_provider(idx);
+                }
+                None => note.push_str(&tf(
+                    "status.session_provider_missing",
+                    &[("provider", &provider)],
+                )),
             }
-            Mode::ChatHistory => {
-                // For now, handle like PromptEntry
-                let mut handler = PromptEntryHandler::new();
-                handler.handle_key(key, self)
+        }
+        self.mode = self.session_mode();
+        Ok(note)
+    }
+
+    /// Where a session just switched to opens: its review, or the prompt
+    fn session_mode(&self) -> Mode {
+        if self.hunks.is_empty() {
+            Mode::PromptEntry
+        } else {
+            Mode::DiffReview
+        }
+    }
+
+    /// Take the review and prompt draft out of the state
+    fn take_session_stash(&mut self) -> SessionStash {
+        SessionStash {
+            pending_changes: std::mem::take(&mut self.pending_changes),
+            hunks: std::mem::take(&mut self.hunks),
+            selected_hunk: std::mem::take(&mut self.selected_hunk),
+            overlay_diff_state: std::mem::take(&mut self.overlay_diff_state),
+            git_review: self.git_review.take(),
+            applied_review: std::mem::take(&mut self.applied_review),
+            write_policy: std::mem::take(&mut self.write_policy),
+            path_warnings: std::mem::take(&mut self.path_warnings),
+            edit_conflicts: std::mem::take(&mut self.edit_conflicts),
+            prompt_buffer: std::mem::take(&mut self.prompt_buffer),
+            cursor_position: std::mem::take(&mut self.cursor_position),
+            context_attachments: std::mem::take(&mut self.context_attachments),
+        }
+    }
+
+    fn restore_session_stash(&mut self, stash: SessionStash) {
+        self.pending_changes = stash.pending_changes;
+        self.hunks = stash.hunks;
+        self.selected_hunk = stash.selected_hunk;
+        self.overlay_diff_state = stash.overlay_diff_state;
+        self.git_review = stash.git_review;
+        self.applied_review = stash.applied_review;
+        self.write_policy = stash.write_policy;
+        self.path_warnings = stash.path_warnings;
+        self.edit_conflicts = stash.edit_conflicts;
+        self.prompt_buffer = stash.prompt_buffer;
+        self.cursor_position = stash.cursor_position;
+        self.context_attachments = stash.context_attachments;
+    }
+
+    /// Search the chats of all sessions for `query` and list the matches.
+    /// Returns how many there are; the list only opens when there are some.
+    pub fn open_grep(&mut self, query: &str) -> anyhow::Result<usize> {
+        let hits = crate::chat_log::search(&crate::chat_log::chats_dir(), query, GREP_HITS)?;
+        let view = GrepView::new(query, hits, &self.sessions);
+        let count = view.len();
+        if count > 0 {
+            self.grep = Some(view);
+            self.mode = Mode::Grep;
+        }
+        Ok(count)
+    }
+
+    /// Bring message `id` of the current session's chat into view, loading
+    /// older pages until it is there. Returns whether it was found.
+    pub fn show_chat_message(&mut self, id: usize) -> anyhow::Result<bool> {
+        loop {
+            let history = &mut self.chat_history;
+            if let Some(idx) = history.messages.iter().position(|m| m.id == id) {
+                history.search_query = None;
+                history.anchor = Some(idx);
+                history.unseen = 0;
+                return Ok(true);
             }
-            Mode::CommandMode => {
-                // Special handling for command mode - consume the key
-                InputResult::Consumed
+            if !history.older_available || self.load_older_messages()? == 0 {
+                return Ok(false);
             }
-            Mode::Help => {
-                // Press any key to close help - return to PromptEntry
-                self.mode = Mode::PromptEntry;
-                return true;
+        }
+    }
+
+    /// Load the page of messages before the oldest loaded one; returns how many
+    pub fn load_older_messages(&mut self) -> anyhow::Result<usize> {
+        let Some(id) = self.sessions.current_session_id.as_ref() else {
+            return Ok(0);
+        };
+        let path = crate::chat_log::log_path(&crate::chat_log::chats_dir(), id);
+        let oldest = self.chat_history.messages.first().map(|m| m.id);
+        let page = crate::chat_log::load_page(&path, oldest, crate::chat_log::PAGE_SIZE)?;
+        let count = page.messages.len();
+        self.chat_history.prepend_older(page.messages, page.more);
+        Ok(count)
+    }
+
+    /// Re-read the global and project preamble (after startup or editing it)
+    pub fn reload_preamble(&mut self) {
+        use crate::preamble::{conventions_dir, Preamble};
+        let cwd = std::env::current_dir().unwrap_or_default();
+        self.preamble = match Preamble::load(self.config.general.preamble.as_deref(), &cwd) {
+            Ok(Some(mut preamble)) => {
+                // Without the file copy, file-based providers inline the text instead
+                if let Err(e) = preamble.write_file(&conventions_dir()) {
+                    self.status_info.current_task = format!("{:#}", e);
+                }
+                Some(preamble)
             }
-            Mode::Error => {
-                self.last_error = None;
-                self.mode = Mode::PromptEntry;
-                return true;
+            Ok(None) => None,
+            Err(e) => {
+                self.status_info.current_task = tf(
+                    "status.preamble_not_loaded",
+                    &[("error", &format!("{:#}", e))],
+                );
+                None
             }
-            Mode::Processing => return false,
         };
+    }
+
+    // Provider detection is now handled by App struct
+
+    // Command result handling is now in App struct
+
+    /// Refuse with `general.read_only`. Everything that writes the project's
+    /// files (applies, restores from the journal, `:undo`) checks this first.
+    pub fn check_writable(&self) -> anyhow::Result<()> {
+        if self.config.general.read_only {
+            anyhow::bail!("Read-only: changes are reviewed but not written");
+        }
+        Ok(())
+    }
+
+    /// Apply accepted hunks to files
+    pub fn apply_changes(&mut self) -> anyhow::Result<crate::file_ops::ApplyResult> {
+        self.check_writable()?;
+        let accepted_hunks: Vec<_> = self.accepted_hunks().collect();
+
+        if accepted_hunks.is_empty() {
+            return Err(anyhow::anyhow!("No accepted hunks to apply"));
+        }
+        if let Some(hunk) = accepted_hunks.iter().find(|h| self.is_stale(&h.file_path)) {
+            anyhow::bail!(stale_error(&hunk.file_path));
+        }
+        self.run_pre_apply_hook(&accepted_hunks)?;
+
+        crate::file_ops::apply_accepted_hunks(
+            &accepted_hunks,
+            &self.pending_changes,
+            &self.config.general.apply_options(),
+            &self.write_policy,
+        )
+    }
+
+    /// Whether `path` changed on disk where the proposal changes it, see
+    /// [`Self::refresh_from_disk`]
+    pub fn is_stale(&self, path: &std::path::Path) -> bool {
+        self.overlay_diff_state
+            .proposed_changes
+            .iter()
+            .any(|c| c.file_path == path && c.stale)
+    }
+
+    /// Let `general.pre_apply_command` refuse writing `hunks`; the error is a
+    /// [`crate::pre_apply::Veto`] when it does
+    fn run_pre_apply_hook(&self, hunks: &[&Hunk]) -> anyhow::Result<()> {
+        let general = &self.config.general;
+        let Some(command) = general.pre_apply_command.as_deref() else {
+            return Ok(());
+        };
+        let cwd = std::env::current_dir()?;
+        let hunks: Vec<&Hunk> = hunks
+            .iter()
+            .copied()
+            .filter(|h| !self.write_policy.is_ignored(&h.file_path))
+            .collect();
+        let mut patch = crate::pre_apply::patch(&hunks, &self.pending_changes, &cwd);
+        if let Some(provenance) = &self.overlay_diff_state.provenance {
+            patch.insert_str(0, &provenance.patch_comment());
+        }
+        let timeout = std::time::Duration::from_secs(general.pre_apply_timeout_secs);
+        crate::pre_apply::run(command, &patch, &cwd, timeout)?;
+        Ok(())
+    }
+
+    /// Write just hunk `id` (with a backup when enabled) and re-diff its file
+    /// against the result, keeping the rest of the review open. Returns the
+    /// file, the line the hunk starts at in it and the apply result.
+    pub fn apply_hunk(
+        &mut self,
+        id: usize,
+    ) -> anyhow::Result<(PathBuf, usize, crate::file_ops::ApplyResult)> {
+        if self.git_review.is_some() {
+            anyhow::bail!("Single hunks can only be applied from provider changes");
+        }
+        self.check_writable()?;
+        if let Some(base) = &self.overlay_diff_state.base {
+            anyhow::bail!(
+                "Showing the diff against {}; press b to return to the working tree first",
+                base.rev
+            );
+        }
+        let Some(hunk) = self.hunks.get(id) else {
+            anyhow::bail!("No hunk selected");
+        };
+        if self.is_stale(&hunk.file_path) {
+            anyhow::bail!(stale_error(&hunk.file_path));
+        }
+        let hunk = hunk.clone();
+        let line = crate::diff::new_line_range(&hunk).map_or(1, |(first, _)| first);
+        let path = hunk.file_path.clone();
+        let accepted = Hunk {
+            status: HunkStatus::Accepted,
+            ..hunk.clone()
+        };
+        self.run_pre_apply_hook(&[&accepted])?;
+
+        let result = crate::file_ops::apply_one_hunk(
+            &hunk,
+            &self.config.general.apply_options(),
+            &self.write_policy,
+        )?;
+        self.reload_written_file(&path);
+        Ok((path, line, result))
+    }
+
+    /// Put back the files of the `:history` entry under the cursor from its
+    /// backups, recording the restore in `journal`. Returns the restore's
+    /// summary, or `None` when no entry is selected.
+    pub fn restore_history_entry(
+        &mut self,
+        journal: &std::path::Path,
+    ) -> anyhow::Result<Option<String>> {
+        self.check_writable()?;
+        let Some(history) = &mut self.history else {
+            return Ok(None);
+        };
+        let Some(entry) = history.selected_entry() else {
+            return Ok(None);
+        };
+        let restored = crate::journal::restore(journal, entry)?;
+        let result = restored.result.clone();
+        // The restore is the newest entry now
+        history.entries.insert(0, restored);
+        history.selected = 0;
+        Ok(Some(result))
+    }
 
-        // Handle the result
-        match result {
-            InputResult::Consumed => true,
-            InputResult::Ignored => false,
-            InputResult::ModeChange(new_mode) => {
-                self.mode = new_mode;
+    /// `:undo`: put back the files of the last apply that deleted some inside
+    /// `project`, from the trash under `trash`
+    pub fn undo_delete(
+        &self,
+        trash: &std::path::Path,
+        project: &std::path::Path,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        self.check_writable()?;
+        crate::file_ops::trash::restore_latest(trash, project)
+    }
+
+    /// Take in an edit made to `path` outside the review after some of its
+    /// hunks were applied. The remaining changes are carried over onto the
+    /// edited content when they don't touch the same lines; otherwise the
+    /// file is marked stale and they can't be applied. Returns whether they
+    /// were carried over.
+    pub fn rebase_on_edit(&mut self, path: &std::path::Path, before: &str, after: &str) -> bool {
+        let Some(change) = self.pending_changes.get_mut(path) else {
+            return false;
+        };
+        let merged = crate::diff::merge3(before, after, &change.proposed_content);
+        match merged {
+            Some(merged) => {
+                change.proposed_content = merged;
+                self.reload_written_file(path);
                 true
             }
-            InputResult::Action(action) => self.handle_action(&action),
-        }
-    }
-
-    fn handle_action(&mut self, action: &crate::input::Action) -> bool {
-        use crate::input::modes::DiffReviewHandler;
-        use crate::input::Action;
-
-        match action {
-            Action::Next
-            | Action::Previous
-            | Action::AcceptCurrent
-            | Action::RejectCurrent
-            | Action::AcceptAll
-            | Action::RejectAll
-            | Action::Beginning
-            | Action::End
-            | Action::ScrollUp
-            | Action::ScrollDown
-            | Action::PageUp
-            | Action::PageDown
-            | Action::ToggleLineNumbers => DiffReviewHandler::apply_action(action, self),
-            Action::NextFile => {
-                // TODO: Implement multi-file navigation
-                false
-            }
-            Action::PreviousFile => {
-                // TODO: Implement multi-file navigation
+            None => {
+                self.refresh_from_disk(path, Some(after.to_string()));
                 false
             }
-            Action::ApplyChanges => {
-                self.mode = Mode::Confirmation;
-                true
-            }
-            Action::SelectProvider(idx) => {
-                if *idx < self.available_providers.len() {
-                    let provider_info = &self.available_providers[*idx];
-                    let config = self.config.providers.get(&provider_info.config_key);
-                    self.provider = crate::providers::create_provider(&provider_info.name, config);
-                    self.selected_provider_idx = *idx;
-                    self.mode = Mode::PromptEntry;
-                    true
-                } else {
-                    false
-                }
+        }
+    }
+
+    /// Re-diff `path` against what was just written to it. Unlike a change
+    /// made behind the review's back, this doesn't mark the file as changed.
+    fn reload_written_file(&mut self, path: &std::path::Path) {
+        self.refresh_file(path, crate::file_cache::read_string(path), false);
+        for change in &mut self.overlay_diff_state.proposed_changes {
+            if change.file_path == path {
+                change.changed_on_disk = false;
             }
-            Action::SubmitPrompt(text) => {
-                if text.is_empty() {
-                    self.last_error = Some(crate::error::ErrorDisplay {
-                        title: "Empty Prompt".to_string(),
-                        message: "Please enter a prompt text".to_string(),
-                        help_url: None,
-                    });
-                    self.mode = Mode::Error;
-                    true
-                } else {
-                    // Prompt execution is now handled by App struct
-                    true
-                }
+        }
+    }
+
+    /// Attach a file to be sent as context with the next prompts
+    pub fn attach_context(&mut self, path: &std::path::Path) -> anyhow::Result<usize> {
+        let attachment = ContextAttachment::load(path)?;
+        Ok(self.add_attachment(attachment))
+    }
+
+    /// Add an attachment, replacing one for the same file and line range
+    fn add_attachment(&mut self, attachment: ContextAttachment) -> usize {
+        let tokens = attachment.tokens;
+        self.context_attachments
+            .retain(|a| a.path != attachment.path || a.lines != attachment.lines);
+        self.context_attachments.push(attachment);
+        tokens
+    }
+
+    /// Update `#symbol` completions for the word under the prompt cursor
+    pub fn refresh_symbol_suggestions(&mut self) {
+        const MAX_SUGGESTIONS: usize = 8;
+
+        let Some((_, word)) =
+            crate::symbols::reference_at(&self.prompt_buffer, self.cursor_position)
+        else {
+            self.symbol_suggestions.clear();
+            return;
+        };
+        let index = self.symbol_index.get_or_insert_with(|| {
+            SymbolIndex::build(&std::env::current_dir().unwrap_or_default())
+        });
+        self.symbol_suggestions = index.complete(word, MAX_SUGGESTIONS);
+        self.symbol_selected = 0;
+    }
+
+    /// Replace the `#word` under the cursor with the selected completion
+    pub fn accept_symbol_suggestion(&mut self) -> bool {
+        let Some(symbol) = self.symbol_suggestions.get(self.symbol_selected) else {
+            return false;
+        };
+        let Some((start, _)) =
+            crate::symbols::reference_at(&self.prompt_buffer, self.cursor_position)
+        else {
+            return false;
+        };
+        let replacement = format!("#{} ", symbol.name);
+        self.prompt_buffer
+            .replace_range(start..self.cursor_position, &replacement);
+        self.cursor_position = start + replacement.len();
+        self.symbol_suggestions.clear();
+        true
+    }
+
+    /// Attach the definition of every `#Name` in `prompt`.
+    ///
+    /// Returns the labels of the attached snippets and the names that could not
+    /// be resolved.
+    pub fn attach_symbol_references(&mut self, prompt: &str) -> (Vec<String>, Vec<String>) {
+        let names = crate::symbols::references(prompt);
+        if names.is_empty() {
+            return (Vec::new(), Vec::new());
+        }
+        let root = std::env::current_dir().unwrap_or_default();
+        let index = self
+            .symbol_index
+            .get_or_insert_with(|| SymbolIndex::build(&root));
+
+        let mut snippets = Vec::new();
+        let mut missing = Vec::new();
+        for name in names {
+            match index
+                .find(&name)
+                .and_then(|symbol| crate::symbols::load_snippet(&root, symbol).ok())
+            {
+                Some(snippet) => snippets.push(snippet),
+                None => missing.push(name),
             }
-            Action::Confirm => match self.apply_changes() {
-                Ok(result) => {
-                    self.last_apply_result = Some(result);
-                    self.mode = Mode::DiffReview;
-                    self.hunks.clear();
-                    self.pending_changes.clear();
-                    self.last_error = Some(crate::error::ErrorDisplay {
-                        title: "Changes Applied Successfully".to_string(),
-                        message: "Files have been updated with the accepted hunks".to_string(),
-                        help_url: None,
-                    });
-                    true
-                }
-                Err(e) => {
-                    self.last_error = Some(crate::error::ErrorDisplay {
-                        title: "Failed to Apply Changes".to_string(),
-                        message: e.to_string(),
-                        help_url: None,
-                    });
-                    self.mode = Mode::Error;
-                    true
-                }
-            },
-            Action::Deny => {
-                self.mode = Mode::DiffReview;
-                true
+        }
+
+        let labels = snippets.iter().map(|s| s.label()).collect();
+        for snippet in snippets {
+            self.add_attachment(snippet);
+        }
+        (labels, missing)
+    }
+
+    /// The pending change to the file pinned in the sidebar (by its path or
+    /// the one it is renamed from), if any
+    pub fn pinned_change(&self) -> Option<&FileChange> {
+        let pinned = self.sidebar_state.pinned_file.as_ref()?;
+        if let Some(change) = self.pending_changes.get(pinned) {
+            return Some(change);
+        }
+        // `:pin` takes paths as typed; compare them resolved against the cwd
+        let cwd = std::env::current_dir().ok()?;
+        let pinned = cwd.join(pinned);
+        self.pending_changes.values().find(|change| {
+            cwd.join(&change.path) == pinned
+                || change
+                    .renamed_from
+                    .as_ref()
+                    .is_some_and(|from| cwd.join(from) == pinned)
+        })
+    }
+
+    /// After an apply, point the sidebar's files at the new paths of those it
+    /// renamed, and remember the renames for paths named before them
+    pub fn follow_renames(&mut self, renames: &[(PathBuf, PathBuf)]) {
+        if renames.is_empty() {
+            return;
+        }
+        crate::ui::sidebar::follow_renames(&mut self.sidebar_state, renames);
+        self.save_sidebar_files();
+        self.renamed_files.extend(renames.iter().cloned());
+    }
+
+    /// Pin the definition of the first symbol the selected hunk uses to the
+    /// sidebar, scrolled to it and highlighted. Calls are tried before other
+    /// names, and a definition inside the hunk itself is skipped.
+    pub fn show_definition_for_hunk(&mut self) -> Option<Symbol> {
+        let hunk = self.hunks.get(self.selected_hunk)?;
+        let changed: Vec<&str> = hunk
+            .changes
+            .iter()
+            .filter(|c| c.tag != ChangeTag::Equal)
+            .map(|c| c.content.as_str())
+            .collect();
+        let text = changed.join("\n");
+        let hunk_range = crate::diff::new_line_range(hunk);
+        let hunk_path = hunk.file_path.clone();
+
+        let root = std::env::current_dir().unwrap_or_default();
+        let index = self
+            .symbol_index
+            .get_or_insert_with(|| SymbolIndex::build(&root));
+        let symbol = crate::symbols::identifiers(&text)
+            .into_iter()
+            .filter_map(|name| index.find(name))
+            .find(|symbol| {
+                let in_hunk = hunk_path.ends_with(&symbol.path)
+                    && hunk_range.is_some_and(|(start, end)| (start..=end).contains(&symbol.line));
+                !in_hunk
+            })?
+            .clone();
+
+        let path = root.join(&symbol.path);
+        let content = std::fs::read_to_string(&path).ok()?;
+        let lines: Vec<&str> = content.lines().collect();
+        let start = symbol
+            .line
+            .saturating_sub(1)
+            .min(lines.len().saturating_sub(1));
+        let end = if lines.is_empty() {
+            start
+        } else {
+            crate::symbols::definition_end(&lines, start)
+        };
+
+        crate::ui::sidebar::pin_file(&mut self.sidebar_state, path);
+        self.sidebar_state.highlighted_lines = (start + 1..=end + 1).collect();
+        // Leave a little of what precedes the definition in view
+        self.sidebar_state.scroll_offset = start.saturating_sub(2);
+        self.sidebar_state.current_file_indicator = Some(format!(
+            "{} {}:{}",
+            symbol.name,
+            symbol.path.display(),
+            symbol.line
+        ));
+        Some(symbol)
+    }
+
+    /// Detach one context file, or all of them when `path` is `None`
+    pub fn detach_context(&mut self, path: Option<&std::path::Path>) -> usize {
+        let before = self.context_attachments.len();
+        match path {
+            Some(path) => self.context_attachments.retain(|a| a.path != path),
+            None => self.context_attachments.clear(),
+        }
+        before - self.context_attachments.len()
+    }
+
+    /// Switch to the provider at `idx` in `available_providers` and go to prompt entry
+    pub fn select_provider(&mut self, idx: usize) -> bool {
+        let Some(provider_info) = self.available_providers.get(idx) else {
+            return false;
+        };
+        let config = self.config.providers.get(&provider_info.config_key);
+        self.provider = crate::providers::create_provider(&provider_info.name, config);
+        self.selected_provider_idx = idx;
+        self.mode = Mode::PromptEntry;
+        true
+    }
+
+    /// Provider to start with instead of asking: the resumed session's, then the
+    /// last one used in `cwd`, then `general.default_provider`.
+    ///
+    /// Returns `None` while a more preferred provider may still be detected.
+    pub fn preferred_provider(&self, cwd: &Path) -> Option<usize> {
+        let resumed = self.sessions.current_session().map(|s| s.provider.clone());
+        let recent = self
+            .sessions
+            .recent_sessions(usize::MAX)
+            .into_iter()
+            .find(|s| s.working_directory == cwd)
+            .map(|s| s.provider.clone());
+        let configured = self.config.general.default_provider.clone();
+
+        for wanted in [resumed, recent, configured].into_iter().flatten() {
+            let found = self.available_providers.iter().position(|p| {
+                p.name.eq_ignore_ascii_case(&wanted) || p.config_key.eq_ignore_ascii_case(&wanted)
+            });
+            if found.is_some() {
+                return found;
             }
-            Action::Quit => {
-                // TODO: Graceful shutdown
-                true
+            if self.detection_state != DetectionState::Completed {
+                return None;
             }
         }
+        None
+    }
+
+    /// Note that the open review is the selected provider's reply to `prompt`
+    pub fn record_provenance(&mut self, prompt: &str) {
+        let Some(provider) = &self.provider else {
+            return;
+        };
+        let model = Some(self.status_info.model.clone())
+            .filter(|m| !m.is_empty())
+            .or_else(|| {
+                self.available_providers
+                    .get(self.selected_provider_idx)
+                    .and_then(|info| self.config.providers.get(&info.config_key))
+                    .and_then(|c| c.model.clone())
+            });
+        self.overlay_diff_state.provenance = Some(crate::provenance::Provenance::new(
+            provider.name(),
+            model.as_deref(),
+            prompt,
+            chrono::Utc::now(),
+        ));
+    }
+
+    pub fn context_token_limit(&self) -> usize {
+        self.available_providers
+            .get(self.selected_provider_idx)
+            .and_then(|info| self.config.providers.get(&info.config_key))
+            .and_then(|c| c.context_token_limit)
+            .unwrap_or(DEFAULT_CONTEXT_TOKEN_LIMIT)
+    }
+
+    /// Sandbox the selected provider runs in (`sandbox` in its config)
+    pub fn sandbox_profile(&self) -> Option<crate::sandbox::SandboxProfile> {
+        self.available_providers
+            .get(self.selected_provider_idx)
+            .and_then(|info| self.config.providers.get(&info.config_key))
+            .and_then(|c| c.sandbox)
+    }
+
+    /// Estimated tokens of the current prompt and all attachments (before truncation)
+    pub fn context_tokens_used(&self) -> usize {
+        crate::context::estimate_tokens(&self.prompt_buffer)
+            + self
+                .context_attachments
+                .iter()
+                .map(|a| a.tokens)
+                .sum::<usize>()
+    }
+
+    /// Tell in the chat what the sanity pass over a reply's changes dropped
+    pub fn note_validation(&mut self, validation: &crate::parsers::Validation) {
+        let note = ChatMessage {
+            id: self.chat_history.next_id,
+            timestamp: chrono::Utc::now(),
+            is_user: false,
+            content: validation.summary(),
+            token_count: None,
+            cost: None,
+            status: MessageStatus::Success,
+            associated_files: validation.changes.iter().map(|c| c.path.clone()).collect(),
+            apply_summary: None,
+        };
+        let session = self.sessions.current_session_id.as_deref();
+        if let Err(e) = self.chat_history.record(note, session) {
+            self.status_info.current_task =
+                tf("status.chat_not_saved", &[("error", &format!("{:#}", e))]);
+        }
+    }
+
+    /// Record the shell commands a reply suggested in the chat, numbered for
+    /// `:run N`; Ctrl+R offers the first
+    pub fn suggest_commands(&mut self, commands: Vec<String>) {
+        let mut content = String::from("Suggested commands:\n");
+        for (i, command) in commands.iter().enumerate() {
+            content.push_str(&format!("\n[{}]\n```sh\n{}\n```\n", i + 1, command));
+        }
+        content.push_str("\nCtrl+R runs [1], `:run N` runs another.");
+        self.record_note(content, MessageStatus::Success);
+
+        self.command_suggestions = commands;
+        self.suggestion_armed = false;
+        self.status_info.current_task = t("status.commands_suggested").to_string();
+    }
+
+    /// Record the output of a suggested command that was run
+    pub fn record_suggestion_run(&mut self, result: &crate::executor::CommandResult) {
+        let command = result.context.get("command").cloned().unwrap_or_default();
+        let output = result.output(crate::executor::OutputSource::Merged);
+        let (status, outcome) = match result.exit_code {
+            Some(code) => (
+                if code == 0 {
+                    MessageStatus::Success
+                } else {
+                    MessageStatus::Error
+                },
+                tf("status.command_exited", &[("code", &code)]),
+            ),
+            None => (MessageStatus::Error, t("status.command_killed").to_string()),
+        };
+        let content = format!(
+            "`{}` {}:\n\n```\n{}\n```",
+            command,
+            outcome,
+            output.trim_end()
+        );
+        self.record_note(content, status);
+        self.status_info.current_task = tf(
+            "status.command_outcome",
+            &[("command", &command), ("outcome", &outcome)],
+        );
+    }
+
+    fn record_note(&mut self, content: String, status: MessageStatus) {
+        let note = ChatMessage {
+            id: self.chat_history.next_id,
+            timestamp: chrono::Utc::now(),
+            is_user: false,
+            content,
+            token_count: None,
+            cost: None,
+            status,
+            associated_files: Vec::new(),
+            apply_summary: None,
+        };
+        let session = self.sessions.current_session_id.as_deref();
+        if let Err(e) = self.chat_history.record(note, session) {
+            self.status_info.current_task =
+                tf("status.chat_not_saved", &[("error", &format!("{:#}", e))]);
+        }
+    }
+
+    /// Post what the last apply did to the chat, with the provider's time
+    /// and the cost of the reply it reviewed
+    pub fn record_apply_summary(&mut self) {
+        let Some(result) = &self.last_apply_result else {
+            return;
+        };
+        let mut summary = crate::apply_summary::ApplySummary::new(
+            result,
+            &self.hunks,
+            &self.pending_changes,
+            self.last_prompt.as_deref(),
+        );
+        summary.provider_ms = self
+            .perf
+            .provider
+            .as_ref()
+            .map(|r| r.wall.as_millis() as u64);
+        summary.review_ms = self
+            .review_started
+            .map(|started| started.elapsed().as_millis() as u64);
+        // Replies are the assistant messages with a token count
+        summary.cost = self
+            .chat_history
+            .messages
+            .iter()
+            .rev()
+            .find(|m| !m.is_user && m.token_count.is_some())
+            .and_then(|m| m.cost);
+
+        let note = ChatMessage {
+            id: self.chat_history.next_id,
+            timestamp: chrono::Utc::now(),
+            is_user: false,
+            content: summary.markdown(),
+            token_count: None,
+            cost: None,
+            status: if summary.files_failed.is_empty() {
+                MessageStatus::Success
+            } else {
+                MessageStatus::Error
+            },
+            associated_files: summary.files.iter().map(|f| f.path.clone()).collect(),
+            apply_summary: Some(summary),
+        };
+        let session = self.sessions.current_session_id.as_deref();
+        if let Err(e) = self.chat_history.record(note, session) {
+            self.status_info.current_task =
+                tf("status.chat_not_saved", &[("error", &format!("{:#}", e))]);
+        }
+    }
+
+    /// Switch prompts to `mode`, or to the other mode when `None`
+    pub fn set_prompt_mode(&mut self, mode: Option<PromptMode>) -> String {
+        self.prompt_mode = mode.unwrap_or(match self.prompt_mode {
+            PromptMode::Edit => PromptMode::Ask,
+            PromptMode::Ask => PromptMode::Edit,
+        });
+        let note = match self.prompt_mode {
+            PromptMode::Edit => t("status.edit_mode"),
+            PromptMode::Ask => t("status.ask_mode"),
+        };
+        self.status_info.current_task = note.to_string();
+        note.to_string()
+    }
+
+    /// Show the latest reply, which has nothing to review, in full as an
+    /// explanation (its code blocks can still be diffed against a file)
+    pub fn show_explanation(&mut self) {
+        match self.chat_history.messages.last().filter(|m| !m.is_user) {
+            Some(message) => {
+                self.message_detail = Some(MessageDetail::new(message));
+                self.mode = Mode::MessageDetail;
+            }
+            None => self.mode = Mode::PromptEntry,
+        }
+    }
+
+    /// The latest apply summary in the chat
+    pub fn latest_apply_summary(&self) -> Option<&crate::apply_summary::ApplySummary> {
+        self.chat_history
+            .messages
+            .iter()
+            .rev()
+            .find_map(|m| m.apply_summary.as_ref())
+    }
+
+    /// Review the code block selected in the message view as the new content
+    /// of `path` (a new file if there is none)
+    pub fn diff_code_block(&mut self, path: PathBuf, policy: WritePolicy) -> anyhow::Result<()> {
+        let block = self
+            .message_detail
+            .as_ref()
+            .and_then(|d| d.selected_block())
+            .ok_or_else(|| anyhow::anyhow!("No code block selected"))?;
+        if path.is_dir() {
+            anyhow::bail!("{} is a directory", path.display());
+        }
+        let original = crate::file_cache::read_string(&path);
+        if original.as_deref() == Some(block.content.as_str()) {
+            anyhow::bail!("The block matches {}", path.display());
+        }
+        let change = FileChange {
+            change_type: if original.is_some() {
+                ChangeType::Modify
+            } else {
+                ChangeType::Create
+            },
+            path,
+            original_content: original,
+            proposed_content: block.content.clone(),
+            renamed_from: None,
+        };
+        self.message_detail = None;
+        self.load_review(vec![change], policy);
+        Ok(())
+    }
+
+    /// Load parsed file changes into the review pipeline and enter diff review.
+    ///
+    /// Hunks are numbered across all files so `hunks[i].id == i`, and every
+    /// overlay decoration records the hunk it came from.
+    pub fn load_review(&mut self, changes: Vec<FileChange>, policy: WritePolicy) {
+        self.applied_review = false;
+        self.review_started = Some(std::time::Instant::now());
+
+        // The file being looked at, so a replacement review stays on it
+        let shown_file = self
+            .overlay_diff_state
+            .proposed_changes
+            .get(self.overlay_diff_state.current_change_idx)
+            .map(|c| c.file_path.clone());
+
+        self.write_policy = policy;
+        self.pending_changes.clear();
+        self.hunks.clear();
+        self.git_review = None;
+        self.perf.diffs.clear();
+        self.overlay_diff_state.base = None;
+        self.overlay_diff_state.provenance = None;
+        self.overlay_diff_state.proposed_changes.clear();
+        self.overlay_diff_state.current_change_idx = 0;
+        self.overlay_diff_state.current_line_idx = 0;
+
+        let paths: Vec<PathBuf> = changes.iter().map(|c| c.path.clone()).collect();
+        crate::ui::sidebar::note_recent_files(&mut self.sidebar_state, &paths);
+        self.save_sidebar_files();
+
+        for change in changes {
+            self.push_review_file(&change, &[], false);
+            self.pending_changes.insert(change.path.clone(), change);
+        }
+
+        let selected = shown_file
+            .and_then(|file| self.first_visible_hunk(&file))
+            .unwrap_or(0);
+        self.selected_hunk = 0;
+        self.scroll_offset = 0;
+        self.select_hunk(selected);
+        self.mode = Mode::DiffReview;
+
+        let auto_accepted = self
+            .hunks
+            .iter()
+            .filter(|h| h.auto_accepted.is_some())
+            .count();
+        if auto_accepted > 0 {
+            self.status_info.current_task =
+                format!("{} trivial hunks auto-accepted", auto_accepted);
+        }
+        let computing = self.diffs_computing();
+        if computing > 0 {
+            self.status_info.current_task =
+                format!("Computing diffs of {} large files…", computing);
+        }
+    }
+
+    /// Fold a provider's answer to a fix request into the open review.
+    ///
+    /// Each returned file replaces that file's proposed content (its original is
+    /// kept); hunks the answer left unchanged keep their status and comment, new
+    /// ones start pending. Files the answer doesn't mention are untouched.
+    /// Returns the number of files updated.
+    pub fn merge_review(&mut self, changes: Vec<FileChange>) -> usize {
+        let mut updated = 0;
+        let mut order: Vec<PathBuf> = self
+            .overlay_diff_state
+            .proposed_changes
+            .iter()
+            .map(|c| c.file_path.clone())
+            .collect();
+        for mut change in changes {
+            match self.pending_changes.get(&change.path) {
+                Some(existing) if existing.proposed_content == change.proposed_content => continue,
+                Some(existing) => change.original_content = existing.original_content.clone(),
+                None => order.push(change.path.clone()),
+            }
+            updated += 1;
+            self.pending_changes.insert(change.path.clone(), change);
+        }
+
+        // The revised files go back to being compared with the working tree
+        self.overlay_diff_state.base = None;
+        self.rediff_review(order);
+        self.applied_review = false;
+        let first_pending = self
+            .hunks
+            .iter()
+            .position(|h| h.status == HunkStatus::Pending)
+            .unwrap_or(0);
+        self.select_hunk(first_pending);
+        self.mode = Mode::DiffReview;
+        updated
+    }
+
+    /// Point the selected hunk's file at `new_path` before it is applied.
+    ///
+    /// The change becomes a modification if `new_path` already exists, so its
+    /// hunks are re-diffed against that file. Returns the previous path.
+    pub fn rename_review_file(&mut self, new_path: PathBuf) -> anyhow::Result<PathBuf> {
+        let old_path = self
+            .hunks
+            .get(self.selected_hunk)
+            .map(|h| h.file_path.clone())
+            .context("No file selected to rename")?;
+        if self.git_review.is_some() || self.applied_review {
+            anyhow::bail!("Only unapplied provider changes can be renamed");
+        }
+        if new_path == old_path {
+            anyhow::bail!("{} is already the target", new_path.display());
+        }
+        if self.pending_changes.contains_key(&new_path) {
+            anyhow::bail!("{} is already part of this review", new_path.display());
+        }
+
+        let (original, change_type) = if new_path.is_file() {
+            let content = std::fs::read_to_string(&new_path)
+                .with_context(|| format!("Failed to read {}", new_path.display()))?;
+            (Some(content), ChangeType::Modify)
+        } else {
+            (None, ChangeType::Create)
+        };
+        let mut change = self
+            .pending_changes
+            .remove(&old_path)
+            .context("File is not part of this review")?;
+        change.path = new_path.clone();
+        change.original_content = original;
+        change.change_type = change_type;
+        self.pending_changes.insert(new_path.clone(), change);
+        // The path was chosen by hand, so whatever was wrong with the old one is moot
+        self.path_warnings.remove(&old_path);
+        if let Some(alternatives) = self.edit_conflicts.remove(&old_path) {
+            self.edit_conflicts.insert(new_path.clone(), alternatives);
+        }
+
+        for hunk in self.hunks.iter_mut().filter(|h| h.file_path == old_path) {
+            hunk.file_path = new_path.clone();
+        }
+        let order = self
+            .overlay_diff_state
+            .proposed_changes
+            .iter()
+            .map(|c| {
+                if c.file_path == old_path {
+                    new_path.clone()
+                } else {
+                    c.file_path.clone()
+                }
+            })
+            .collect();
+        let selected = self.selected_hunk;
+        self.overlay_diff_state.base = None;
+        self.rediff_review(order);
+        self.select_hunk(selected.min(self.hunks.len().saturating_sub(1)));
+        Ok(old_path)
+    }
+
+    /// Number of overlapping edits the reply made to `path` (0 if none)
+    fn edit_choices(&self, path: &Path) -> usize {
+        self.edit_conflicts.get(path).map_or(0, Vec::len)
+    }
+
+    /// Review the shown file's overlapping edit `choice` (1-based) and drop the
+    /// others. Hunks the choice shares with the edit shown before keep their
+    /// status. Returns the file.
+    pub fn pick_edit(&mut self, choice: usize) -> anyhow::Result<PathBuf> {
+        let path = self
+            .overlay_diff_state
+            .proposed_changes
+            .get(self.overlay_diff_state.current_change_idx)
+            .map(|c| c.file_path.clone())
+            .context("No file shown")?;
+        let Some(alternatives) = self.edit_conflicts.get(&path) else {
+            anyhow::bail!("{} has no overlapping edits", path.display());
+        };
+        let Some(proposed) = choice.checked_sub(1).and_then(|i| alternatives.get(i)) else {
+            anyhow::bail!("Pick an edit from 1 to {}", alternatives.len());
+        };
+        let proposed = proposed.clone();
+        let change = self
+            .pending_changes
+            .get_mut(&path)
+            .context("File is not part of this review")?;
+        change.proposed_content = proposed;
+        self.edit_conflicts.remove(&path);
+
+        let order = self
+            .overlay_diff_state
+            .proposed_changes
+            .iter()
+            .map(|c| c.file_path.clone())
+            .collect();
+        self.rediff_review(order);
+        let selected = self.first_visible_hunk(&path).unwrap_or(0);
+        self.select_hunk(selected);
+        Ok(path)
+    }
+
+    /// Work out the selected hunk's added lines reindented to fit the code
+    /// around them, to show before [`Self::accept_reindent`]
+    pub fn preview_reindent(&mut self) -> anyhow::Result<&crate::reindent::Reindent> {
+        if self.git_review.is_some() || self.applied_review {
+            anyhow::bail!("Only unapplied provider changes can be reindented");
+        }
+        if let Some(base) = &self.overlay_diff_state.base {
+            anyhow::bail!(
+                "Showing the diff against {}; press b to return to the working tree first",
+                base.rev
+            );
+        }
+        let hunk = self
+            .hunks
+            .get(self.selected_hunk)
+            .context("No hunk selected")?;
+        let original = self
+            .pending_changes
+            .get(&hunk.file_path)
+            .and_then(|c| c.original_content.as_deref())
+            .unwrap_or_default();
+        let reindent = crate::reindent::reindent(hunk, original)
+            .context("The added lines already fit the indentation around them")?;
+        Ok(self.reindent.insert(reindent))
+    }
+
+    /// Write the previewed indentation into the proposed file and accept the
+    /// hunk. Returns the file.
+    pub fn accept_reindent(&mut self) -> anyhow::Result<PathBuf> {
+        let reindent = self.reindent.take().context("No reindent to accept")?;
+        let hunk = self
+            .hunks
+            .get(reindent.hunk_id)
+            .filter(|h| h.file_path == reindent.path)
+            .context("The hunk changed since the preview")?;
+        let old = hunk.old;
+        let change = self
+            .pending_changes
+            .get_mut(&reindent.path)
+            .context("File is not part of this review")?;
+        change.proposed_content = crate::reindent::apply(&change.proposed_content, &reindent);
+
+        let order = self
+            .overlay_diff_state
+            .proposed_changes
+            .iter()
+            .map(|c| c.file_path.clone())
+            .collect();
+        self.rediff_review(order);
+        // Reindenting moves no lines of the original, so the hunk keeps its old range
+        let id = self
+            .hunks
+            .iter()
+            .position(|h| h.file_path == reindent.path && h.old == old)
+            .unwrap_or(reindent.hunk_id.min(self.hunks.len().saturating_sub(1)));
+        self.select_hunk(id);
+        self.set_hunk_status(id, HunkStatus::Accepted);
+        Ok(reindent.path)
+    }
+
+    /// Show the review against git ref `rev`, or the working tree again when
+    /// `None`. Hunks that are the same against either side keep their status.
+    /// Returns a note for the status bar.
+    pub fn diff_against(&mut self, rev: Option<&str>) -> anyhow::Result<String> {
+        if self.hunks.is_empty() && self.overlay_diff_state.proposed_changes.is_empty() {
+            anyhow::bail!("No review to compare");
+        }
+        if self.git_review.is_some() {
+            anyhow::bail!("Git reviews are already diffed against git");
+        }
+
+        let base = match rev {
+            Some(rev) => {
+                let cwd = std::env::current_dir()?;
+                let paths: Vec<PathBuf> = self.pending_changes.keys().cloned().collect();
+                Some(DiffBase {
+                    rev: rev.to_string(),
+                    contents: crate::git::files_at_ref(&cwd, rev, &paths)?,
+                })
+            }
+            None => None,
+        };
+        let order: Vec<PathBuf> = self
+            .overlay_diff_state
+            .proposed_changes
+            .iter()
+            .map(|c| c.file_path.clone())
+            .collect();
+        self.overlay_diff_state.base = base;
+        let selected = self.selected_hunk;
+        self.rediff_review(order);
+        self.select_hunk(selected.min(self.hunks.len().saturating_sub(1)));
+
+        Ok(match rev {
+            Some(rev) => tf("status.comparing_with", &[("rev", &rev)]),
+            None => t("status.comparing_with_worktree").to_string(),
+        })
+    }
+
+    /// Re-diff the files of the review in `order` after their changes were
+    /// replaced, carrying hunk status and comments over to identical hunks
+    fn rediff_review(&mut self, order: Vec<PathBuf>) {
+        let previous = std::mem::take(&mut self.hunks);
+        let changed_on_disk: HashSet<PathBuf> = self
+            .overlay_diff_state
+            .proposed_changes
+            .drain(..)
+            .filter(|c| c.changed_on_disk)
+            .map(|c| c.file_path)
+            .collect();
+        for file in order {
+            let Some(change) = self.pending_changes.get(&file).cloned() else {
+                continue;
+            };
+            let old: Vec<Hunk> = previous
+                .iter()
+                .filter(|h| h.file_path == file)
+                .cloned()
+                .collect();
+            self.push_review_file(&change, &old, false);
+            if changed_on_disk.contains(&file) {
+                if let Some(last) = self.overlay_diff_state.proposed_changes.last_mut() {
+                    last.changed_on_disk = true;
+                }
+            }
+        }
+        self.sync_overlay_status();
+    }
+
+    /// Set (or with an empty text, clear) the selected hunk's comment
+    pub fn set_hunk_comment(&mut self, text: &str) -> Option<&Hunk> {
+        let text = text.trim();
+        let hunk = self.hunks.get_mut(self.selected_hunk)?;
+        hunk.comment = (!text.is_empty()).then(|| text.to_string());
+        self.sync_overlay_status();
+        self.hunks.get(self.selected_hunk)
+    }
+
+    /// Follow-up prompt asking the provider to redo the rejected hunks, with the
+    /// reviewer's comments on any hunk
+    pub fn fix_request_prompt(&self) -> anyhow::Result<String> {
+        use crate::diff::new_line_range;
+
+        if self.git_review.is_some() {
+            anyhow::bail!("Fix requests need a provider review, not a git review");
+        }
+        if let Some(base) = &self.overlay_diff_state.base {
+            anyhow::bail!(
+                "Hunks are compared with {}; switch back to the working tree first",
+                base.rev
+            );
+        }
+        if self.applied_review {
+            anyhow::bail!("This review has already been applied");
+        }
+        let flagged: Vec<&Hunk> = self
+            .hunks
+            .iter()
+            .filter(|h| h.status == HunkStatus::Rejected || h.comment.is_some())
+            .collect();
+        if flagged.is_empty() {
+            anyhow::bail!("Nothing to fix: reject or comment on hunks first");
+        }
+
+        let mut prompt = String::from(
+            "I reviewed your changes. Revise the hunks below: rejected ones were not \
+             acceptable as proposed, and my comments say what to change. Reply with the \
+             complete updated content of every file you change, keeping the changes I \
+             accepted.\n",
+        );
+        for hunk in flagged {
+            let verdict = match hunk.status {
+                HunkStatus::Rejected => "rejected",
+                HunkStatus::Accepted => "accepted",
+                HunkStatus::Pending => "not reviewed yet",
+            };
+            let lines = new_line_range(hunk)
+                .map(|(first, last)| format!(", lines {}-{}", first, last))
+                .unwrap_or_default();
+            prompt.push_str(&format!(
+                "\n{}{} ({})\n",
+                hunk.file_path.display(),
+                lines,
+                verdict
+            ));
+            if let Some(comment) = &hunk.comment {
+                prompt.push_str(&format!("Comment: {}\n", comment));
+            }
+            prompt.push_str(&format!("```diff\n@@ -{} +{} @@\n", hunk.old, hunk.new));
+            for change in &hunk.changes {
+                let sign = match change.tag {
+                    ChangeTag::Insert => '+',
+                    ChangeTag::Delete => '-',
+                    ChangeTag::Equal => ' ',
+                };
+                prompt.push(sign);
+                prompt.push_str(change.content.trim_end_matches('\n'));
+                prompt.push('\n');
+            }
+            prompt.push_str("```\n");
+        }
+        Ok(prompt)
+    }
+
+    /// First hunk of `file` starting at or below its saved scroll position,
+    /// falling back to the file's last hunk
+    fn first_visible_hunk(&self, file: &std::path::Path) -> Option<usize> {
+        let overlay = &self.overlay_diff_state;
+        let change_idx = overlay
+            .proposed_changes
+            .iter()
+            .position(|c| c.file_path == file)?;
+        let offset = overlay.scroll_offsets.get(file).copied().unwrap_or(0);
+
+        let mut last = None;
+        for (line_idx, dec) in overlay.proposed_changes[change_idx]
+            .line_decorations
+            .iter()
+            .enumerate()
+        {
+            let Some(id) = dec.hunk_id else {
+                continue;
+            };
+            if last == Some(id) {
+                continue;
+            }
+            if overlay.row_of(change_idx, line_idx) >= offset {
+                return Some(id);
+            }
+            last = Some(id);
+        }
+        last
+    }
+
+    /// Re-read a file under review after it changed on disk and re-diff it.
+    ///
+    /// For provider changes the disk content is the new original and the
+    /// proposed changes are carried over onto it; where they touch the lines
+    /// that changed they can't be, and the file is marked stale so none of it
+    /// is applied until the proposal is rejected or asked for again. For
+    /// working-tree git reviews the disk content is the new proposed content.
+    /// Hunks that still match keep their status; the rest are flagged as drifted.
+    /// Returns false if the file is not under review or is unchanged.
+    pub fn refresh_from_disk(
+        &mut self,
+        path: &std::path::Path,
+        disk_content: Option<String>,
+    ) -> bool {
+        self.refresh_file(path, disk_content, true)
+    }
+
+    /// [`Self::refresh_from_disk`]; `rebase` is false when the disk content
+    /// was written by the review itself and the proposal already contains it
+    fn refresh_file(
+        &mut self,
+        path: &std::path::Path,
+        disk_content: Option<String>,
+        rebase: bool,
+    ) -> bool {
+        use crate::git::DiffSource;
+
+        // A renamed file's original is at its old path; nothing is at the new
+        // one until it is applied
+        let renamed = self
+            .pending_changes
+            .values()
+            .find(|c| c.renamed_from.as_deref() == Some(path))
+            .map(|c| c.path.clone());
+        let path = match renamed {
+            Some(new_path) => new_path,
+            None if self
+                .pending_changes
+                .get(path)
+                .is_some_and(|c| c.renamed_from.is_some()) =>
+            {
+                return false
+            }
+            None => path.to_path_buf(),
+        };
+        // A stale proposal no longer starts from the original, so there is
+        // nothing to carry it over from
+        let already_stale = self.is_stale(&path);
+        let Some(change) = self.pending_changes.get_mut(&path) else {
+            return false;
+        };
+        let worktree_review = matches!(
+            self.git_review.as_ref().map(|r| r.source),
+            Some(DiffSource::WorkingTree)
+        );
+        if matches!(
+            self.git_review.as_ref().map(|r| r.source),
+            Some(DiffSource::Staged)
+        ) {
+            return false;
+        }
+
+        let mut stale = false;
+        if worktree_review {
+            let content = disk_content.unwrap_or_default();
+            if content == change.proposed_content {
+                return false;
+            }
+            change.proposed_content = content;
+        } else {
+            if disk_content == change.original_content {
+                return false;
+            }
+            if already_stale {
+                stale = true;
+            } else if rebase {
+                let merged = crate::diff::merge3(
+                    change.original_content.as_deref().unwrap_or_default(),
+                    disk_content.as_deref().unwrap_or_default(),
+                    &change.proposed_content,
+                );
+                stale = merged.is_none();
+                if let Some(merged) = merged {
+                    change.proposed_content = merged;
+                }
+            }
+            change.original_content = disk_content;
+        }
+
+        let previous = std::mem::take(&mut self.hunks);
+        let order: Vec<(PathBuf, bool, bool)> = self
+            .overlay_diff_state
+            .proposed_changes
+            .drain(..)
+            .map(|c| (c.file_path, c.changed_on_disk, c.stale))
+            .collect();
+
+        for (file, changed, was_stale) in order {
+            let Some(change) = self.pending_changes.get(&file).cloned() else {
+                continue;
+            };
+            let old: Vec<Hunk> = previous
+                .iter()
+                .filter(|h| h.file_path == file)
+                .cloned()
+                .collect();
+            self.push_review_file(&change, &old, changed || file == path);
+            if let Some(entry) = self.overlay_diff_state.proposed_changes.last_mut() {
+                entry.stale = if file == path { stale } else { was_stale };
+            }
+        }
+
+        self.sync_overlay_status();
+        let selected = self.selected_hunk.min(self.hunks.len().saturating_sub(1));
+        self.select_hunk(selected);
+        true
+    }
+
+    /// Diff one file and append its hunks and overlay entry.
+    ///
+    /// When `previous` hunks are given (a re-diff), statuses carry over to hunks
+    /// with identical changes and any other hunk is marked as drifted.
+    fn push_review_file(&mut self, change: &FileChange, previous: &[Hunk], changed_on_disk: bool) {
+        use crate::diff::{compute_hunks, diff_key, DiffJob, BACKGROUND_DIFF_BYTES};
+
+        let base = self.overlay_diff_state.base.as_ref();
+        let original = base
+            .and_then(|b| b.contents.get(&change.path))
+            .unwrap_or(&change.original_content)
+            .clone()
+            .unwrap_or_default();
+
+        // Large files are diffed off the UI thread and filled in by `diff_ready`
+        let key = diff_key(&original, &change.proposed_content);
+        let hunks = match self.diff_cache.get(&key) {
+            Some(hunks) => hunks.clone(),
+            None if original.len() + change.proposed_content.len() >= BACKGROUND_DIFF_BYTES => {
+                if !self.diffs_running.contains(&key)
+                    && !self.diff_jobs.iter().any(|j| j.key == key)
+                {
+                    self.diff_jobs.push(DiffJob {
+                        key,
+                        path: change.path.clone(),
+                        original: original.clone(),
+                        proposed: change.proposed_content.clone(),
+                    });
+                }
+                self.push_computing_file(change, original, key, changed_on_disk);
+                return;
+            }
+            None => {
+                let started = std::time::Instant::now();
+                let hunks = compute_hunks(&change.path, &original, &change.proposed_content);
+                self.record_diff_time(
+                    &change.path,
+                    &original,
+                    &change.proposed_content,
+                    started.elapsed(),
+                );
+                self.diff_cache.insert(key, hunks.clone());
+                hunks
+            }
+        };
+
+        let mut line_decorations = Vec::new();
+        let mut drifted_hunks = Vec::new();
+        let mut flagged_hunks = Vec::new();
+        let mut hunk_ranges = Vec::new();
+        let first_hunk = self.hunks.len();
+
+        for mut hunk in hunks {
+            hunk.id = self.hunks.len();
+            hunk.file_path = change.path.clone();
+            if !previous.is_empty() {
+                match previous.iter().find(|old| same_changes(old, &hunk)) {
+                    Some(old) => {
+                        hunk.status = old.status.clone();
+                        hunk.drifted = old.drifted;
+                        hunk.comment = old.comment.clone();
+                        hunk.auto_accepted = old.auto_accepted.clone();
+                    }
+                    None => hunk.drifted = changed_on_disk,
+                }
+            }
+            if hunk.drifted {
+                drifted_hunks.push(hunk.id);
+            }
+            let flags = self.guard.check(&hunk);
+            // Trivial hunks of a fresh review are accepted, unless a guard rule
+            // wants them looked at
+            if previous.is_empty() && flags.is_empty() && self.git_review.is_none() {
+                if let Some(rule) = self.auto_accept.rule_for(&hunk) {
+                    hunk.status = HunkStatus::Accepted;
+                    hunk.auto_accepted = Some(rule.to_string());
+                }
+            }
+            flagged_hunks.extend(flags.into_iter().map(|rule| (hunk.id, rule)));
+            hunk_ranges.push((hunk.id, hunk.old, hunk.new));
+
+            // Original lines before the one being decorated
+            let mut old_cursor = hunk.old.indices().start;
+            for line_change in &hunk.changes {
+                if let Some(old) = line_change.old_line_num {
+                    old_cursor = old + 1;
+                }
+                let text = line_change.content.without_newline();
+                let (decoration_type, original_text, new_text) = match line_change.tag {
+                    ChangeTag::Insert => (DecorationType::Addition, None, Some(text)),
+                    ChangeTag::Delete => (DecorationType::Deletion, Some(text), None),
+                    ChangeTag::Equal => (DecorationType::Context, Some(text.clone()), Some(text)),
+                };
+                line_decorations.push(LineDecoration {
+                    line_number: line_change
+                        .new_line_num
+                        .or(line_change.old_line_num)
+                        .map_or(0, |n| n + 1),
+                    old_line: old_cursor,
+                    decoration_type,
+                    original_text,
+                    new_text,
+                    accepted: None,
+                    hunk_id: Some(hunk.id),
+                });
+            }
+            self.hunks.push(hunk);
+        }
+
+        // First and last decoration of a hunk
+        let decorations_of = |id: usize| {
+            let mut idxs = line_decorations
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| d.hunk_id == Some(id))
+                .map(|(idx, _)| idx);
+            let first = idxs.next().unwrap_or(0);
+            (first, idxs.next_back().unwrap_or(first))
+        };
+        // A near-total rewrite is one folded group instead of a wall of hunks
+        let rewrite = crate::diff::rewrite_similarity(
+            &original,
+            &change.proposed_content,
+            &self.hunks[first_hunk..],
+            self.config.general.rewrite_similarity,
+        )
+        .filter(|_| self.hunks.len() > first_hunk);
+        let grouped = match rewrite {
+            Some(similarity) => vec![(
+                (first_hunk..self.hunks.len()).collect(),
+                format!(
+                    "file rewritten · {}% of lines kept",
+                    (similarity * 100.0).round()
+                ),
+            )],
+            None => crate::diff::group_hunks(
+                &self.hunks[first_hunk..],
+                &change.proposed_content,
+                self.config.general.group_gap_lines,
+            ),
+        };
+        let groups = grouped
+            .into_iter()
+            .map(|(hunk_ids, label): (Vec<usize>, String)| HunkGroup {
+                first_line: decorations_of(hunk_ids[0]).0,
+                last_line: decorations_of(hunk_ids[hunk_ids.len() - 1]).1,
+                hunk_ids,
+                label,
+                collapsed: rewrite.is_some(),
+            })
+            .collect();
+
+        self.overlay_diff_state
+            .proposed_changes
+            .push(ProposedChange {
+                id: self.overlay_diff_state.proposed_changes.len(),
+                file_path: change.path.clone(),
+                original_content: original,
+                proposed_content: change.proposed_content.clone(),
+                line_decorations,
+                status: ChangeStatus::Pending,
+                changed_on_disk,
+                drifted_hunks,
+                stale: false,
+                ignored: self.write_policy.is_ignored(&change.path),
+                skipped: self.skipped_files.contains(&change.path),
+                test_failures: Vec::new(),
+                groups,
+                comments: Vec::new(),
+                path_warning: self.path_warnings.get(&change.path).cloned(),
+                rewrite,
+                computing: None,
+                flagged_hunks,
+                auto_accepted: Vec::new(),
+                hunk_ranges,
+                edit_choices: self.edit_choices(&change.path),
+                renamed_from: change.renamed_from.clone(),
+            });
+        if self.hunks[first_hunk..]
+            .iter()
+            .any(|h| h.auto_accepted.is_some())
+        {
+            self.sync_overlay_status();
+        }
+    }
+
+    /// Placeholder for a file whose diff is still being computed
+    fn push_computing_file(
+        &mut self,
+        change: &FileChange,
+        original: String,
+        key: crate::diff::DiffKey,
+        changed_on_disk: bool,
+    ) {
+        self.overlay_diff_state
+            .proposed_changes
+            .push(ProposedChange {
+                id: self.overlay_diff_state.proposed_changes.len(),
+                file_path: change.path.clone(),
+                original_content: original,
+                proposed_content: change.proposed_content.clone(),
+                line_decorations: Vec::new(),
+                status: ChangeStatus::Pending,
+                changed_on_disk,
+                drifted_hunks: Vec::new(),
+                stale: false,
+                ignored: self.write_policy.is_ignored(&change.path),
+                skipped: self.skipped_files.contains(&change.path),
+                test_failures: Vec::new(),
+                groups: Vec::new(),
+                comments: Vec::new(),
+                path_warning: self.path_warnings.get(&change.path).cloned(),
+                rewrite: None,
+                computing: Some(key),
+                flagged_hunks: Vec::new(),
+                auto_accepted: Vec::new(),
+                hunk_ranges: Vec::new(),
+                edit_choices: self.edit_choices(&change.path),
+                renamed_from: change.renamed_from.clone(),
+            });
+    }
+
+    /// Terminal title describing the current activity
+    pub fn title(&self) -> String {
+        let activity = if let Some(batch) = self.batch.as_ref().filter(|b| !b.is_done()) {
+            format!("foreach {}/{}", batch.finished(), batch.total)
+        } else if self.mode == Mode::Processing {
+            match &self.provider {
+                Some(provider) => format!("running {}…", provider.name()),
+                None => "running…".to_string(),
+            }
+        } else if matches!(self.mode, Mode::DiffReview | Mode::Confirmation)
+            && !self.overlay_diff_state.proposed_changes.is_empty()
+        {
+            let files = self.overlay_diff_state.proposed_changes.len();
+            format!(
+                "reviewing {} file{}",
+                files,
+                if files == 1 { "" } else { "s" }
+            )
+        } else {
+            return "zcode".to_string();
+        };
+        format!("zcode: {}", activity)
+    }
+
+    /// Files of the review whose diff is still being computed
+    pub fn diffs_computing(&self) -> usize {
+        self.overlay_diff_state
+            .proposed_changes
+            .iter()
+            .filter(|c| c.computing.is_some())
+            .count()
+    }
+
+    /// Fill in a file diffed in the background, keeping the selected hunk.
+    /// Returns the file, or `None` when the review no longer shows that content.
+    pub fn diff_ready(
+        &mut self,
+        key: crate::diff::DiffKey,
+        hunks: Vec<Hunk>,
+        elapsed: std::time::Duration,
+    ) -> Option<PathBuf> {
+        self.diffs_running.remove(&key);
+        self.diff_cache.insert(key, hunks);
+        let change = self
+            .overlay_diff_state
+            .proposed_changes
+            .iter()
+            .find(|c| c.computing == Some(key))?;
+        let path = change.file_path.clone();
+        let (original, proposed) = (
+            change.original_content.clone(),
+            change.proposed_content.clone(),
+        );
+        self.record_diff_time(&path, &original, &proposed, elapsed);
+
+        // The selected hunk, as its file and position within the file
+        let selected = self.hunks.get(self.selected_hunk).map(|hunk| {
+            let nth = self.hunks[..self.selected_hunk]
+                .iter()
+                .filter(|h| h.file_path == hunk.file_path)
+                .count();
+            (hunk.file_path.clone(), nth)
+        });
+        let order: Vec<PathBuf> = self
+            .overlay_diff_state
+            .proposed_changes
+            .iter()
+            .map(|c| c.file_path.clone())
+            .collect();
+        self.rediff_review(order);
+        let idx = selected
+            .and_then(|(file, nth)| {
+                self.hunks
+                    .iter()
+                    .filter(|h| h.file_path == file)
+                    .nth(nth)
+                    .map(|h| h.id)
+            })
+            .unwrap_or(0);
+        self.select_hunk(idx);
+        Some(path)
+    }
+
+    /// Keep how long diffing `path` took for `:perf`, flagging slow files
+    fn record_diff_time(
+        &mut self,
+        path: &std::path::Path,
+        original: &str,
+        proposed: &str,
+        elapsed: std::time::Duration,
+    ) {
+        let lines = original.lines().count().max(proposed.lines().count());
+        let threshold = std::time::Duration::from_millis(self.config.general.slow_diff_ms);
+        if let Some(warning) = self.perf.record_diff(path, lines, elapsed, threshold) {
+            self.status_info.current_task = warning;
+        }
+    }
+
+    /// Toggle the `.zcodeignore` override for the selected hunk's file.
+    ///
+    /// Returns the file and whether writes to it are now allowed, or `None` if the
+    /// file is not matched by `.zcodeignore`.
+    pub fn toggle_ignore_override(&mut self) -> Option<(PathBuf, bool)> {
+        let path = self.hunks.get(self.selected_hunk)?.file_path.clone();
+        if !self.write_policy.matches(&path) {
+            return None;
+        }
+
+        let allowed = self.write_policy.toggle_override(&path);
+        for change in &mut self.overlay_diff_state.proposed_changes {
+            if change.file_path == path {
+                change.ignored = !allowed;
+            }
+        }
+        Some((path, allowed))
+    }
+
+    /// What applying will do to each file with accepted hunks, in review order
+    pub fn apply_summary(&self) -> Vec<FileSummary> {
+        let mut summary: Vec<FileSummary> = Vec::new();
+        for hunk in self.accepted_hunks() {
+            let idx = match summary.iter().position(|f| f.path == hunk.file_path) {
+                Some(idx) => idx,
+                None => {
+                    let change = self.pending_changes.get(&hunk.file_path);
+                    summary.push(FileSummary {
+                        path: hunk.file_path.clone(),
+                        change_type: change.map_or(ChangeType::Modify, |c| c.change_type.clone()),
+                        hunks: 0,
+                        added: 0,
+                        removed: 0,
+                        ignored: self.write_policy.is_ignored(&hunk.file_path),
+                        renamed_from: change.and_then(|c| c.renamed_from.clone()),
+                    });
+                    summary.len() - 1
+                }
+            };
+            let file = &mut summary[idx];
+            file.hunks += 1;
+            for change in &hunk.changes {
+                match change.tag {
+                    ChangeTag::Insert => file.added += 1,
+                    ChangeTag::Delete => file.removed += 1,
+                    ChangeTag::Equal => {}
+                }
+            }
+        }
+        summary
+    }
+
+    /// Files with accepted hunks, split into (to be written, skipped by `.zcodeignore`)
+    pub fn apply_plan(&self) -> (usize, usize) {
+        let files: HashSet<&PathBuf> = self.accepted_hunks().map(|h| &h.file_path).collect();
+        let skipped = files
+            .iter()
+            .filter(|path| self.write_policy.is_ignored(path))
+            .count();
+        (files.len() - skipped, skipped)
+    }
+
+    /// Accepted hunks that will be written and trip a guard rule, as
+    /// (file, hunk id, rule)
+    pub fn flagged_accepted(&self) -> Vec<(&PathBuf, usize, &str)> {
+        self.overlay_diff_state
+            .proposed_changes
+            .iter()
+            .filter(|change| !self.write_policy.is_ignored(&change.file_path) && !change.skipped)
+            .flat_map(|change| {
+                change
+                    .flagged_hunks
+                    .iter()
+                    .map(move |(id, rule)| (&change.file_path, *id, rule.as_str()))
+            })
+            .filter(|(_, id, _)| {
+                self.hunks
+                    .get(*id)
+                    .is_some_and(|h| h.status == HunkStatus::Accepted)
+            })
+            .collect()
+    }
+
+    /// Check the checklist's automatic items against the accepted hunks that
+    /// will be written
+    pub fn refresh_checklist(&mut self) {
+        let accepted: Vec<&Hunk> = self
+            .hunks
+            .iter()
+            .filter(|h| h.status == HunkStatus::Accepted)
+            .filter(|h| {
+                !self.write_policy.is_ignored(&h.file_path)
+                    && !self.skipped_files.contains(&h.file_path)
+            })
+            .collect();
+        crate::checklist::refresh(&mut self.checklist, &self.config.checklist, &accepted);
+    }
+
+    /// Parse each file the apply would write as it would be written
+    pub fn refresh_syntax_problems(&mut self) {
+        // A git review doesn't rewrite the accepted hunks
+        if self.git_review.is_some() {
+            self.syntax_problems.clear();
+            return;
+        }
+        let mut files: BTreeMap<&PathBuf, Vec<&Hunk>> = BTreeMap::new();
+        for hunk in self
+            .accepted_hunks()
+            .filter(|h| !self.write_policy.is_ignored(&h.file_path))
+        {
+            files.entry(&hunk.file_path).or_default().push(hunk);
+        }
+        let config = &self.config.syntax_check;
+        self.syntax_problems = files
+            .into_iter()
+            .filter_map(|(path, hunks)| {
+                let original = match self.pending_changes.get(path).map(|c| &c.change_type) {
+                    Some(ChangeType::Create) => None,
+                    _ => Some(std::fs::read_to_string(path).ok()?),
+                };
+                let content = crate::file_ops::reconstruct_file_content(
+                    original.as_deref().unwrap_or_default(),
+                    &hunks,
+                )
+                .ok()?;
+                if content.trim().is_empty() {
+                    return None;
+                }
+                crate::syntax::check_file(path, original.as_deref(), &content, config)
+            })
+            .collect();
+    }
+
+    /// Checklist items that keep the apply waiting
+    pub fn unchecked_required(&self) -> usize {
+        if !self.config.checklist.required {
+            return 0;
+        }
+        self.checklist.iter().filter(|e| !e.checked).count()
+    }
+
+    /// Why applying the accepted hunks needs confirmation: the guard rules
+    /// they trip, then the `confirm_on` rules they match. `confirm_before_apply`,
+    /// when set, replaces the `confirm_on` rules.
+    ///
+    /// Empty when the apply can go ahead without asking. Files skipped by
+    /// `.zcodeignore` are not considered since they will not be written.
+    pub fn confirmation_reasons(&self) -> Vec<String> {
+        use crate::config::ConfirmRule;
+
+        let warning = crate::ui::icons::Icons::for_set(self.config.display.icons).warning;
+        let mut reasons: Vec<String> = self
+            .flagged_accepted()
+            .into_iter()
+            .map(|(path, _, rule)| {
+                tf(
+                    "confirm.guard",
+                    &[
+                        ("icon", &warning),
+                        ("rule", &rule),
+                        ("path", &path.display()),
+                    ],
+                )
+            })
+            .collect();
+        reasons.dedup();
+
+        let general = &self.config.general;
+        match general.confirm_before_apply {
+            Some(true) => {
+                reasons.push(t("confirm.always").to_string());
+                return reasons;
+            }
+            Some(false) => return reasons,
+            None => {}
+        }
+        let Some(rules) = &general.confirm_on else {
+            reasons.push(t("confirm.always").to_string());
+            return reasons;
+        };
+
+        let accepted: Vec<&Hunk> = self
+            .accepted_hunks()
+            .filter(|h| !self.write_policy.is_ignored(&h.file_path))
+            .collect();
+        let files: BTreeMap<&PathBuf, Option<&ChangeType>> = accepted
+            .iter()
+            .map(|h| {
+                let change_type = self
+                    .pending_changes
+                    .get(&h.file_path)
+                    .map(|c| &c.change_type);
+                (&h.file_path, change_type)
+            })
+            .collect();
+
+        for rule in rules {
+            match rule {
+                ConfirmRule::Always if !accepted.is_empty() => {
+                    reasons.push(t("confirm.always").to_string());
+                }
+                ConfirmRule::Delete => reasons.extend(
+                    files
+                        .iter()
+                        .filter(|(_, t)| matches!(t, Some(ChangeType::Delete)))
+                        .map(|(path, _)| tf("confirm.deletes", &[("path", &path.display())])),
+                ),
+                ConfirmRule::Create => reasons.extend(
+                    files
+                        .iter()
+                        .filter(|(_, t)| matches!(t, Some(ChangeType::Create)))
+                        .map(|(path, _)| tf("confirm.creates", &[("path", &path.display())])),
+                ),
+                ConfirmRule::CreateOutsideSrc => reasons.extend(
+                    files
+                        .iter()
+                        .filter(|(path, t)| {
+                            matches!(t, Some(ChangeType::Create))
+                                && !path.components().any(|c| c.as_os_str() == "src")
+                        })
+                        .map(|(path, _)| {
+                            tf("confirm.creates_outside_src", &[("path", &path.display())])
+                        }),
+                ),
+                ConfirmRule::LargeChange => {
+                    let changed = accepted
+                        .iter()
+                        .flat_map(|h| &h.changes)
+                        .filter(|c| c.tag != ChangeTag::Equal)
+                        .count();
+                    if changed > general.large_change_lines {
+                        reasons.push(tf("confirm.large_change", &[("count", &changed)]));
+                    }
+                }
+                _ => {}
+            }
+        }
+        reasons
+    }
+
+    /// Accepted, rejected and pending hunks of the review, skipped files left out
+    pub fn review_progress(&self) -> ReviewProgress {
+        let mut progress = ReviewProgress::default();
+        for hunk in self
+            .hunks
+            .iter()
+            .filter(|h| !self.skipped_files.contains(&h.file_path))
+        {
+            match hunk.status {
+                HunkStatus::Accepted => progress.accepted += 1,
+                HunkStatus::Rejected => progress.rejected += 1,
+                HunkStatus::Pending => progress.pending += 1,
+            }
+        }
+        progress
+    }
+
+    /// Discard the current review without touching any files
+    pub fn clear_review(&mut self) {
+        self.pending_changes.clear();
+        self.hunks.clear();
+        self.git_review = None;
+        self.applied_review = false;
+        self.selected_hunk = 0;
+        self.overlay_diff_state = OverlayDiffState::default();
+        self.checklist.clear();
+        self.syntax_problems.clear();
+        self.reindent = None;
+        self.apply_branch = None;
+    }
+
+    /// Mark the accepted hunks that failing test locations point into.
+    ///
+    /// Returns how many hunks were marked.
+    pub fn record_test_failures(&mut self, failures: &[crate::test_results::TestFailure]) -> usize {
+        for change in &mut self.overlay_diff_state.proposed_changes {
+            change.test_failures.clear();
+        }
+
+        let mut marked = 0;
+        for hunk in self
+            .hunks
+            .iter()
+            .filter(|h| h.status == HunkStatus::Accepted)
+        {
+            let Some(change) = self
+                .overlay_diff_state
+                .proposed_changes
+                .iter_mut()
+                .find(|c| c.file_path == hunk.file_path)
+            else {
+                continue;
+            };
+            let before = change.test_failures.len();
+            for failure in failures
+                .iter()
+                .filter(|f| crate::test_results::failure_in_hunk(f, hunk))
+            {
+                let entry = (hunk.id, failure.test.clone());
+                if !change.test_failures.contains(&entry) {
+                    change.test_failures.push(entry);
+                }
+            }
+            if change.test_failures.len() > before {
+                marked += 1;
+            }
+        }
+        marked
+    }
+
+    /// Move the review cursor to a hunk, switching the overlay to its file
+    pub fn select_hunk(&mut self, idx: usize) {
+        let Some(hunk) = self.hunks.get(idx) else {
+            return;
+        };
+        self.selected_hunk = idx;
+
+        let overlay = &mut self.overlay_diff_state;
+        if let Some(change_idx) = overlay
+            .proposed_changes
+            .iter()
+            .position(|c| c.file_path == hunk.file_path)
+        {
+            overlay.current_change_idx = change_idx;
+            overlay.current_line_idx = overlay.proposed_changes[change_idx]
+                .line_decorations
+                .iter()
+                .position(|d| d.hunk_id == Some(idx))
+                .unwrap_or(0);
+            overlay.keep_cursor_visible();
+        }
+    }
+
+    /// The group a hunk belongs to, if any
+    pub fn group_of(&self, hunk_id: usize) -> Option<&HunkGroup> {
+        self.overlay_diff_state
+            .proposed_changes
+            .iter()
+            .flat_map(|c| &c.groups)
+            .find(|g| g.hunk_ids.contains(&hunk_id))
+    }
+
+    /// Move to the next or previous hunk; a collapsed group is a single stop
+    pub fn step_hunk(&mut self, forward: bool) {
+        // A folded group, or a skipped file, is one stop
+        let collapsed = |state: &Self, id: usize| {
+            let file = &state.hunks.get(id)?.file_path;
+            if state.skipped_files.contains(file) {
+                let first = state.hunks.iter().position(|h| &h.file_path == file)?;
+                let last = state.hunks.iter().rposition(|h| &h.file_path == file)?;
+                return Some((first, last));
+            }
+            state
+                .group_of(id)
+                .filter(|g| g.collapsed)
+                .map(|g| (g.hunk_ids[0], g.hunk_ids[g.hunk_ids.len() - 1]))
+        };
+        let current = self.selected_hunk;
+        let target = if forward {
+            collapsed(self, current).map_or(current, |(_, last)| last) + 1
+        } else {
+            let first = collapsed(self, current).map_or(current, |(first, _)| first);
+            let Some(prev) = first.checked_sub(1) else {
+                return;
+            };
+            collapsed(self, prev).map_or(prev, |(first, _)| first)
+        };
+        self.select_hunk(target);
+    }
+
+    /// Set the status of every hunk in the selected hunk's group (or just the
+    /// selected hunk when it is not grouped). Returns how many hunks changed.
+    pub fn set_group_status(&mut self, status: HunkStatus) -> usize {
+        let ids = self
+            .group_of(self.selected_hunk)
+            .map_or_else(|| vec![self.selected_hunk], |g| g.hunk_ids.clone());
+        for &id in &ids {
+            if let Some(hunk) = self.hunks.get_mut(id) {
+                hunk.status = status.clone();
+            }
+        }
+        self.sync_overlay_status();
+        ids.len()
+    }
+
+    /// Fold or unfold the selected hunk's group; returns whether it is now collapsed
+    pub fn toggle_group_collapsed(&mut self) -> Option<bool> {
+        let selected = self.selected_hunk;
+        let group = self
+            .overlay_diff_state
+            .proposed_changes
+            .iter_mut()
+            .flat_map(|c| &mut c.groups)
+            .find(|g| g.hunk_ids.contains(&selected))?;
+        group.collapsed = !group.collapsed;
+        let collapsed = group.collapsed;
+        let first = group.hunk_ids[0];
+        // Keep the cursor on the group's header
+        self.select_hunk(if collapsed { first } else { selected });
+        Some(collapsed)
+    }
+
+    /// Set a hunk's review status and mirror it onto the overlay decorations
+    pub fn set_hunk_status(&mut self, idx: usize, status: HunkStatus) {
+        if let Some(hunk) = self.hunks.get_mut(idx) {
+            hunk.status = status;
+            self.sync_overlay_status();
+        }
+    }
+
+    /// Set every hunk's review status at once
+    pub fn set_all_hunks_status(&mut self, status: HunkStatus) {
+        for hunk in &mut self.hunks {
+            hunk.status = status.clone();
+        }
+        self.sync_overlay_status();
+    }
+
+    fn sync_overlay_status(&mut self) {
+        // A hunk no longer accepted was decided on by the reviewer
+        for hunk in &mut self.hunks {
+            if hunk.status != HunkStatus::Accepted {
+                hunk.auto_accepted = None;
+            }
+        }
+        for change in &mut self.overlay_diff_state.proposed_changes {
+            let mut accepted = 0;
+            let mut rejected = 0;
+            let mut total = 0;
+
+            for hunk in self
+                .hunks
+                .iter()
+                .filter(|h| h.file_path == change.file_path)
+            {
+                total += 1;
+                match hunk.status {
+                    HunkStatus::Accepted => accepted += 1,
+                    HunkStatus::Rejected => rejected += 1,
+                    HunkStatus::Pending => {}
+                }
+            }
+
+            for dec in &mut change.line_decorations {
+                if dec.decoration_type == DecorationType::Context {
+                    continue;
+                }
+                dec.accepted = dec
+                    .hunk_id
+                    .and_then(|id| self.hunks.get(id))
+                    .and_then(|h| match h.status {
+                        HunkStatus::Accepted => Some(true),
+                        HunkStatus::Rejected => Some(false),
+                        HunkStatus::Pending => None,
+                    });
+            }
+
+            change.comments = self
+                .hunks
+                .iter()
+                .filter(|h| h.file_path == change.file_path)
+                .filter_map(|h| Some((h.id, h.comment.clone()?)))
+                .collect();
+            change.auto_accepted = self
+                .hunks
+                .iter()
+                .filter(|h| h.file_path == change.file_path)
+                .filter_map(|h| Some((h.id, h.auto_accepted.clone()?)))
+                .collect();
+
+            change.status = if total > 0 && accepted == total {
+                ChangeStatus::Accepted
+            } else if total > 0 && rejected == total {
+                ChangeStatus::Rejected
+            } else if accepted > 0 {
+                ChangeStatus::PartialAccept
+            } else {
+                ChangeStatus::Pending
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refresh_from_disk_keeps_matching_hunk_status() {
+        let path = PathBuf::from("src/lib.rs");
+        let original: String = (0..20).map(|i| format!("line {}\n", i)).collect();
+        let proposed = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 17\n", "line seventeen\n");
+
+        let mut state = State::default();
+        state.load_review(
+            vec![FileChange {
+                path: path.clone(),
+                original_content: Some(original.clone()),
+                proposed_content: proposed,
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        assert_eq!(state.hunks.len(), 2);
+        state.set_hunk_status(0, HunkStatus::Accepted);
+        state.set_hunk_status(1, HunkStatus::Rejected);
+
+        // Someone edits the lines around the second hunk on disk
+        let edited = original.replace("line 16\n", "line sixteen\n");
+        assert!(state.refresh_from_disk(&path, Some(edited.clone())));
+        assert!(!state.refresh_from_disk(&path, Some(edited.clone())));
+
+        // The proposal is carried over onto the edit rather than undoing it
+        assert!(!state.is_stale(&path));
+        assert_eq!(
+            state.pending_changes[&path].proposed_content,
+            edited
+                .replace("line 2\n", "line two\n")
+                .replace("line 17\n", "line seventeen\n")
+        );
+        assert_eq!(state.hunks.len(), 2);
+
+        assert_eq!(state.hunks[0].status, HunkStatus::Accepted);
+        assert!(!state.hunks[0].drifted);
+        assert!(state.hunks.iter().skip(1).all(|h| h.drifted));
+        assert!(state
+            .hunks
+            .iter()
+            .skip(1)
+            .all(|h| h.status == HunkStatus::Pending));
+
+        let overlay = &state.overlay_diff_state.proposed_changes[0];
+        assert!(overlay.changed_on_disk);
+        assert!(!overlay.drifted_hunks.is_empty());
+        assert!(!state.refresh_from_disk(std::path::Path::new("other.rs"), None));
+    }
+
+    #[test]
+    fn test_edit_under_the_proposal_makes_it_stale() {
+        let path = PathBuf::from("src/lib.rs");
+        let original: String = (0..20).map(|i| format!("line {}\n", i)).collect();
+        let proposed = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 17\n", "line seventeen\n");
+
+        let mut state = State::default();
+        state.load_review(
+            vec![FileChange {
+                path: path.clone(),
+                original_content: Some(original.clone()),
+                proposed_content: proposed.clone(),
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        state.set_hunk_status(0, HunkStatus::Accepted);
+
+        // The same line is changed on disk as the proposal changes
+        let edited = original.replace("line 17\n", "line 17 by hand\n");
+        assert!(state.refresh_from_disk(&path, Some(edited.clone())));
+        assert!(state.is_stale(&path));
+        assert_eq!(state.pending_changes[&path].proposed_content, proposed);
+
+        // Nothing of it is written, even the hunk accepted before the edit
+        let err = state.apply_changes().unwrap_err().to_string();
+        assert!(err.contains("changed on disk"), "{}", err);
+        assert!(state.apply_hunk(0).is_err());
+
+        // A later edit elsewhere doesn't make it fit again
+        let later = edited.replace("line 5\n", "line five\n");
+        assert!(state.refresh_from_disk(&path, Some(later)));
+        assert!(state.is_stale(&path));
+    }
+
+    #[test]
+    fn test_read_only_restores_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("journal.jsonl");
+        let file = dir.path().join("main.rs");
+        let backup = dir.path().join("main.rs.bak");
+        std::fs::write(&backup, "old\n").unwrap();
+        std::fs::write(&file, "new\n").unwrap();
+        let entry = crate::journal::JournalEntry::new(None, None, None, &[])
+            .succeeded("Applied".into(), &[(file.clone(), backup)]);
+
+        let deleted = dir.path().join("gone.rs");
+        std::fs::write(&deleted, "fn gone() {}\n").unwrap();
+        let trash = tempfile::tempdir().unwrap();
+        crate::file_ops::trash::Trash::new(trash.path())
+            .move_in(&deleted)
+            .unwrap();
+
+        let mut state = State::default();
+        state.config.general.read_only = true;
+        state.history = Some(HistoryView {
+            entries: vec![entry],
+            selected: 0,
+        });
+
+        let err = state.restore_history_entry(&journal).unwrap_err();
+        assert!(err.to_string().starts_with("Read-only"), "{}", err);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new\n");
+        assert!(!journal.exists());
+
+        assert!(state.undo_delete(trash.path(), dir.path()).is_err());
+        assert!(!deleted.exists());
+
+        // Both write once read-only is off
+        state.config.general.read_only = false;
+        assert!(state.restore_history_entry(&journal).unwrap().is_some());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "old\n");
+        assert_eq!(
+            state.undo_delete(trash.path(), dir.path()).unwrap().len(),
+            1
+        );
+        assert!(deleted.exists());
+    }
+
+    #[test]
+    fn test_line_numbers_follow_partial_accepts() {
+        let original: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+        let proposed = original
+            .replace("line 2\n", "line 2\na\nb\n")
+            .replace("line 20\n", "");
+
+        let mut state = State::default();
+        state.load_review(
+            vec![FileChange {
+                path: PathBuf::from("src/lib.rs"),
+                original_content: Some(original),
+                proposed_content: proposed,
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        assert_eq!(state.hunks.len(), 2);
+        let changed = |state: &State| {
+            let change = &state.overlay_diff_state.proposed_changes[0];
+            change
+                .line_decorations
+                .iter()
+                .zip(change.line_numbers())
+                .filter(|(d, _)| d.decoration_type != DecorationType::Context)
+                .map(|(_, numbers)| numbers)
+                .collect::<Vec<_>>()
+        };
+
+        // Pending hunks are numbered as proposed
+        assert_eq!(
+            changed(&state),
+            [(None, Some(3)), (None, Some(4)), (Some(20), None)]
+        );
+
+        // A rejected deletion keeps its line, after the accepted insertion
+        state.set_hunk_status(0, HunkStatus::Accepted);
+        state.set_hunk_status(1, HunkStatus::Rejected);
+        assert_eq!(
+            changed(&state),
+            [(None, Some(3)), (None, Some(4)), (Some(20), Some(22))]
+        );
+
+        state.set_hunk_status(0, HunkStatus::Rejected);
+        assert_eq!(
+            changed(&state),
+            [(None, None), (None, None), (Some(20), Some(20))]
+        );
+    }
+
+    #[test]
+    fn test_pick_edit_settles_overlapping_edits() {
+        let path = PathBuf::from("src/lib.rs");
+        let mut state = State::default();
+        state
+            .edit_conflicts
+            .insert(path.clone(), vec!["b\n".to_string(), "c\n".to_string()]);
+        state.load_review(
+            vec![FileChange {
+                path: path.clone(),
+                original_content: Some("a\n".to_string()),
+                proposed_content: "c\n".to_string(),
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        assert_eq!(state.overlay_diff_state.proposed_changes[0].edit_choices, 2);
+        assert!(state.pick_edit(3).is_err());
+
+        assert_eq!(state.pick_edit(1).unwrap(), path);
+        assert_eq!(state.pending_changes[&path].proposed_content, "b\n");
+        assert!(state.edit_conflicts.is_empty());
+        assert_eq!(state.overlay_diff_state.proposed_changes[0].edit_choices, 0);
+        assert!(state.pick_edit(1).is_err());
+    }
+
+    #[test]
+    fn test_fix_request_merges_into_review() {
+        let path = PathBuf::from("src/lib.rs");
+        let original: String = (0..20).map(|i| format!("line {}\n", i)).collect();
+        let change = |proposed: String| FileChange {
+            path: path.clone(),
+            original_content: Some(original.clone()),
+            proposed_content: proposed,
+            change_type: ChangeType::Modify,
+            renamed_from: None,
+        };
+        let first = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 17\n", "line 17!\n");
+
+        let mut state = State::default();
+        assert!(state.fix_request_prompt().is_err());
+        state.load_review(vec![change(first)], WritePolicy::default());
+        assert!(state.fix_request_prompt().is_err());
+        state.set_hunk_status(0, HunkStatus::Accepted);
+        state.set_hunk_status(1, HunkStatus::Rejected);
+        state.select_hunk(1);
+        state.set_hunk_comment("spell it out");
+
+        let prompt = state.fix_request_prompt().unwrap();
+        assert!(prompt.contains("src/lib.rs, lines 18-18 (rejected)"));
+        assert!(prompt.contains("Comment: spell it out"));
+        assert!(prompt.contains("```diff\n@@ -15,6 +15,6 @@\n"));
+        assert!(prompt.contains("+line 17!\n"));
+        assert!(!prompt.contains("line two"));
+
+        // The answer keeps the accepted hunk and redoes the rejected one
+        let revised = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 17\n", "line seventeen\n");
+        assert_eq!(state.merge_review(vec![change(revised.clone())]), 1);
+        assert_eq!(state.hunks.len(), 2);
+        assert_eq!(state.hunks[0].status, HunkStatus::Accepted);
+        assert_eq!(state.hunks[1].status, HunkStatus::Pending);
+        assert_eq!(state.hunks[1].comment, None);
+        assert_eq!(state.selected_hunk, 1);
+        assert_eq!(
+            state.pending_changes[&path].original_content.as_deref(),
+            Some(original.as_str())
+        );
+        assert_eq!(state.merge_review(vec![change(revised)]), 0);
+    }
+
+    #[test]
+    fn test_rename_review_file_keeps_review() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("build");
+        let existing = dir.path().join("build.py");
+        std::fs::write(&existing, "print(0)\n").unwrap();
+
+        let mut state = State::default();
+        state
+            .path_warnings
+            .insert(old.clone(), "path has no extension".to_string());
+        state.load_review(
+            vec![FileChange {
+                path: old.clone(),
+                original_content: None,
+                proposed_content: "print(1)\n".to_string(),
+                change_type: ChangeType::Create,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        assert!(state.overlay_diff_state.proposed_changes[0]
+            .path_warning
+            .is_some());
+        assert!(state.rename_review_file(old.clone()).is_err());
+
+        assert_eq!(state.rename_review_file(existing.clone()).unwrap(), old);
+        let change = &state.pending_changes[&existing];
+        assert_eq!(change.change_type, ChangeType::Modify);
+        assert_eq!(change.original_content.as_deref(), Some("print(0)\n"));
+        assert!(!state.pending_changes.contains_key(&old));
+        assert!(state.hunks.iter().all(|h| h.file_path == existing));
+        let overlay = &state.overlay_diff_state.proposed_changes[0];
+        assert_eq!(overlay.file_path, existing);
+        assert!(overlay.path_warning.is_none());
+    }
+
+    #[test]
+    fn test_confirmation_reasons_follow_confirm_on() {
+        use crate::config::ConfirmRule;
+
+        let mut state = State::default();
+        state.load_review(
+            vec![
+                FileChange {
+                    path: PathBuf::from("src/lib.rs"),
+                    original_content: Some("a\nb\n".to_string()),
+                    proposed_content: "a\nc\n".to_string(),
+                    change_type: ChangeType::Modify,
+                    renamed_from: None,
+                },
+                FileChange {
+                    path: PathBuf::from("notes.txt"),
+                    original_content: None,
+                    proposed_content: "hello\n".to_string(),
+                    change_type: ChangeType::Create,
+                    renamed_from: None,
+                },
+            ],
+            WritePolicy::default(),
+        );
+        let modify = state
+            .hunks
+            .iter()
+            .position(|h| h.file_path == Path::new("src/lib.rs"))
+            .unwrap();
+        state.set_hunk_status(modify, HunkStatus::Accepted);
+
+        // Unset: always confirm
+        assert!(!state.confirmation_reasons().is_empty());
+
+        state.config.general.confirm_on = Some(vec![
+            ConfirmRule::Delete,
+            ConfirmRule::CreateOutsideSrc,
+            ConfirmRule::LargeChange,
+        ]);
+        assert!(state.confirmation_reasons().is_empty());
+
+        let create = 1 - modify;
+        state.set_hunk_status(create, HunkStatus::Accepted);
+        assert_eq!(
+            state.confirmation_reasons(),
+            vec!["Creates notes.txt outside src/".to_string()]
+        );
+
+        state.config.general.confirm_on = Some(vec![ConfirmRule::LargeChange]);
+        state.config.general.large_change_lines = 2;
+        assert_eq!(
+            state.confirmation_reasons(),
+            vec!["Changes 3 lines".to_string()]
+        );
+
+        // confirm_before_apply, when set, overrides the rules either way
+        state.config.general.confirm_before_apply = Some(false);
+        assert!(state.confirmation_reasons().is_empty());
+        state.config.general.confirm_on = Some(Vec::new());
+        state.config.general.confirm_before_apply = Some(true);
+        assert_eq!(
+            state.confirmation_reasons(),
+            vec!["Confirmation is always required".to_string()]
+        );
+        state.config.general.confirm_before_apply = None;
+        assert!(state.confirmation_reasons().is_empty());
+
+        // The dialog lists each file with its accepted hunks and line delta
+        let summary = state.apply_summary();
+        assert_eq!(summary.len(), 2);
+        let lib = summary
+            .iter()
+            .find(|f| f.path == Path::new("src/lib.rs"))
+            .unwrap();
+        assert_eq!((lib.hunks, lib.added, lib.removed), (1, 1, 1));
+        let notes = summary
+            .iter()
+            .find(|f| f.path == Path::new("notes.txt"))
+            .unwrap();
+        assert_eq!(notes.change_type, ChangeType::Create);
+        assert_eq!((notes.added, notes.removed), (1, 0));
+    }
+
+    #[test]
+    fn test_guard_flags_hunks_and_leads_confirmation() {
+        let mut state = State::default();
+        state.config.general.confirm_on = Some(Vec::new());
+        state.config.display.icons = crate::config::IconSet::Unicode;
+        state.guard =
+            crate::guard::Guard::new(Path::new("/project"), &crate::guard::default_rules())
+                .unwrap();
+        state.load_review(
+            vec![
+                FileChange {
+                    path: PathBuf::from("src/lib.rs"),
+                    original_content: Some("a\n".to_string()),
+                    proposed_content: "b\n".to_string(),
+                    change_type: ChangeType::Modify,
+                    renamed_from: None,
+                },
+                FileChange {
+                    path: PathBuf::from("Cargo.lock"),
+                    original_content: Some("a\n".to_string()),
+                    proposed_content: "b\n".to_string(),
+                    change_type: ChangeType::Modify,
+                    renamed_from: None,
+                },
+            ],
+            WritePolicy::default(),
+        );
+        assert!(state.overlay_diff_state.proposed_changes[0]
+            .flagged_hunks
+            .is_empty());
+        assert_eq!(
+            state.overlay_diff_state.proposed_changes[1].flagged_hunks,
+            vec![(1, "lockfile".to_string())]
+        );
+
+        // Only accepted flagged hunks count
+        state.hunks[0].status = HunkStatus::Accepted;
+        assert!(state.confirmation_reasons().is_empty());
+        state.set_all_hunks_status(HunkStatus::Accepted);
+        assert_eq!(state.flagged_accepted().len(), 1);
+        assert_eq!(
+            state.confirmation_reasons(),
+            vec!["⚠ lockfile in Cargo.lock".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_title_follows_activity() {
+        let mut state = State::default();
+        assert_eq!(state.title(), "zcode");
+        state.load_review(
+            vec![FileChange {
+                path: PathBuf::from("a.rs"),
+                original_content: Some("a\n".to_string()),
+                proposed_content: "b\n".to_string(),
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        state.mode = Mode::DiffReview;
+        assert_eq!(state.title(), "zcode: reviewing 1 file");
+        state.mode = Mode::Processing;
+        assert_eq!(state.title(), "zcode: running…");
+        state.batch = Some(crate::batch::BatchRun::new(
+            "*.rs",
+            "docs",
+            vec!["a".into(), "b".into()],
+        ));
+        assert_eq!(state.title(), "zcode: foreach 0/2");
+    }
+
+    #[test]
+    fn test_large_files_are_diffed_in_the_background() {
+        let mut state = State::default();
+        let big: String = (0..40_000).map(|i| format!("line {}\n", i)).collect();
+        let changed = big.replace("line 20000\n", "changed\n");
+        state.load_review(
+            vec![
+                FileChange {
+                    path: PathBuf::from("small.rs"),
+                    original_content: Some("a\n".to_string()),
+                    proposed_content: "b\n".to_string(),
+                    change_type: ChangeType::Modify,
+                    renamed_from: None,
+                },
+                FileChange {
+                    path: PathBuf::from("big.txt"),
+                    original_content: Some(big.clone()),
+                    proposed_content: changed.clone(),
+                    change_type: ChangeType::Modify,
+                    renamed_from: None,
+                },
+            ],
+            WritePolicy::default(),
+        );
+
+        // The small file is reviewable while the big one waits for its job
+        assert_eq!(state.hunks.len(), 1);
+        assert_eq!(state.diffs_computing(), 1);
+        let job = state.diff_jobs.pop().unwrap();
+        assert_eq!(job.path, PathBuf::from("big.txt"));
+        state.set_hunk_status(0, HunkStatus::Accepted);
+
+        let hunks = crate::diff::compute_hunks(&job.path, &job.original, &job.proposed);
+        let path = state.diff_ready(job.key, hunks, std::time::Duration::from_millis(5));
+        assert_eq!(path, Some(PathBuf::from("big.txt")));
+        assert_eq!(state.diffs_computing(), 0);
+        assert_eq!(state.hunks.len(), 2);
+        assert_eq!(state.hunks[0].status, HunkStatus::Accepted);
+        assert_eq!(state.hunks[1].file_path, PathBuf::from("big.txt"));
+
+        // Loading the same content again hits the cache instead of queueing a job
+        state.load_review(
+            vec![FileChange {
+                path: PathBuf::from("big.txt"),
+                original_content: Some(big),
+                proposed_content: changed,
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        assert!(state.diff_jobs.is_empty());
+        assert_eq!(state.hunks.len(), 1);
+    }
+
+    fn chat_message(id: usize) -> ChatMessage {
+        ChatMessage {
+            id,
+            timestamp: Utc::now(),
+            is_user: false,
+            content: format!("reply {}", id),
+            token_count: None,
+            cost: None,
+            status: MessageStatus::Success,
+            associated_files: Vec::new(),
+            apply_summary: None,
+        }
+    }
+
+    #[test]
+    fn test_chat_follows_latest_unless_scrolled_up() {
+        let mut history = ChatHistory::new();
+        for id in 0..3 {
+            history.add_message(chat_message(id));
+        }
+        assert_eq!(history.visible_index(), Some(2));
+
+        history.scroll_up();
+        history.add_message(chat_message(3));
+        history.add_message(chat_message(4));
+        assert_eq!(history.visible_index(), Some(1));
+        assert_eq!(history.unseen, 2);
+
+        history.scroll_down();
+        history.scroll_down();
+        assert_eq!(history.visible_index(), Some(3));
+        history.scroll_down();
+        assert_eq!(history.anchor, None);
+        assert_eq!(history.unseen, 0);
+        assert_eq!(history.visible_index(), Some(4));
+    }
+
+    #[test]
+    fn test_replacing_review_keeps_file_and_scroll() {
+        let long: String = (0..60).map(|i| format!("line {}\n", i)).collect();
+        let review = |edit: &str| {
+            vec![
+                FileChange {
+                    path: PathBuf::from("a.rs"),
+                    original_content: Some("a\n".to_string()),
+                    proposed_content: "b\n".to_string(),
+                    change_type: ChangeType::Modify,
+                    renamed_from: None,
+                },
+                FileChange {
+                    path: PathBuf::from("b.rs"),
+                    original_content: Some(long.clone()),
+                    proposed_content: long
+                        .replace("line 5\n", "line five\n")
+                        .replace("line 50\n", edit),
+                    change_type: ChangeType::Modify,
+                    renamed_from: None,
+                },
+            ]
+        };
+
+        let mut state = State::default();
+        state.overlay_diff_state.viewport_height = 5;
+        state.load_review(review("line fifty\n"), WritePolicy::default());
+        let last = state.hunks.len() - 1;
+        state.select_hunk(last);
+        let offset = state.overlay_diff_state.scroll_offset();
+        assert!(offset > 0);
+
+        // A new response replaces the changes: stay on b.rs at the same place
+        state.load_review(review("line 50!\n"), WritePolicy::default());
+        let overlay = &state.overlay_diff_state;
+        assert_eq!(
+            overlay.proposed_changes[overlay.current_change_idx].file_path,
+            PathBuf::from("b.rs")
+        );
+        assert_eq!(overlay.scroll_offset(), offset);
+        assert_eq!(state.selected_hunk, state.hunks.len() - 1);
+    }
+
+    #[test]
+    fn test_cursor_stays_below_the_sticky_header() {
+        let long: String = (0..60).map(|i| format!("line {}\n", i)).collect();
+        let mut state = State::default();
+        state.overlay_diff_state.viewport_height = 5;
+        state.load_review(
+            vec![FileChange {
+                path: PathBuf::from("a.rs"),
+                original_content: Some(long.clone()),
+                proposed_content: long
+                    .replace("line 20\n", "line twenty\n")
+                    .replace("line 50\n", "line fifty\n"),
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        let overlay = &mut state.overlay_diff_state;
+        overlay.scroll_by(3);
+        overlay.current_line_idx = 2;
+        let row = overlay.row_of(0, 2);
+        assert_eq!(row, overlay.scroll_offset());
+
+        // The cursor's row would be under the header: scroll up one row
+        overlay.keep_cursor_visible();
+        assert_eq!(overlay.scroll_offset(), row - 1);
+    }
+
+    #[test]
+    fn test_wrapped_lines_take_extra_rows() {
+        let long = "x".repeat(25);
+        let mut state = State::default();
+        state.load_review(
+            vec![FileChange {
+                path: PathBuf::from("a.rs"),
+                original_content: Some("a\nb\n".to_string()),
+                proposed_content: format!("{}\nb\n", long),
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        let overlay = &mut state.overlay_diff_state;
+        let count = overlay.proposed_changes[0].line_decorations.len();
+        let unwrapped = overlay.row_of(0, count);
+
+        // Wrapping is on by default, but counts nothing until the view is drawn
+        assert!(overlay.wrap_lines);
+        overlay.viewport_width = crate::ui::overlay_diff::TEXT_OFFSET + 10;
+        // The 25-column line now takes three rows instead of one
+        assert_eq!(overlay.row_of(0, count), unwrapped + 2);
+        assert_eq!(
+            crate::ui::overlay_diff::wrap_text(&long, 10),
+            vec!["x".repeat(10), "x".repeat(10), "x".repeat(5)]
+        );
+        // Cut off again, it takes one
+        overlay.wrap_lines = false;
+        assert_eq!(overlay.row_of(0, count), unwrapped);
+    }
+
+    #[test]
+    fn test_output_viewer_search_wraps() {
+        let output = "starting\nwarning: a\nok\nWARNING: b\ndone";
+        let mut viewer = OutputViewer::new("out".to_string(), output);
+        viewer.scroll = 2;
+
+        viewer.search("warning");
+        assert_eq!(viewer.matches, vec![1, 3]);
+        assert_eq!(viewer.scroll, 3);
+
+        viewer.next_match(true);
+        assert_eq!(viewer.scroll, 1);
+        viewer.next_match(false);
+        assert_eq!(viewer.scroll, 3);
+
+        viewer.scroll_by(100);
+        assert_eq!(viewer.scroll, 4);
+        assert_eq!(viewer.text(), output);
+    }
+
+    #[test]
+    fn test_symbol_suggestion_is_inserted() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "fn config_path() {}
+",
+        )
+        .unwrap();
+
+        let mut state = State {
+            symbol_index: Some(SymbolIndex::build(dir.path())),
+            prompt_buffer: "explain #conf please".to_string(),
+            cursor_position: "explain #conf".len(),
+            ..Default::default()
+        };
+        state.refresh_symbol_suggestions();
+        assert_eq!(state.symbol_suggestions.len(), 1);
+
+        assert!(state.accept_symbol_suggestion());
+        assert_eq!(state.prompt_buffer, "explain #config_path  please");
+        assert_eq!(state.cursor_position, "explain #config_path ".len());
+        assert!(state.symbol_suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_select_provider_uses_its_config() {
+        let info = |name: &str, key: &str| ProviderInfo {
+            name: name.to_string(),
+            available: true,
+            cli_command: key.to_string(),
+            config_key: key.to_string(),
+        };
+        let mut state = State::default();
+        state.config.providers.insert(
+            "ollama".to_string(),
+            toml::from_str("path = \"/opt/bin/curl\"").unwrap(),
+        );
+        state.available_providers = vec![info("Claude Code", "claude"), info("Ollama", "ollama")];
+        state.mode = Mode::ProviderSelect;
+
+        assert!(!state.select_provider(2));
+        assert!(state.provider.is_none());
+        assert_eq!(state.mode, Mode::ProviderSelect);
+
+        assert!(state.select_provider(1));
+        let provider = state.provider.as_ref().unwrap();
+        assert_eq!(provider.name(), "Ollama");
+        assert_eq!(provider.cli_command(), "/opt/bin/curl");
+        assert_eq!(state.selected_provider_idx, 1);
+        assert_eq!(state.mode, Mode::PromptEntry);
+    }
+
+    #[test]
+    fn test_preferred_provider_order() {
+        let info = |name: &str, key: &str| ProviderInfo {
+            name: name.to_string(),
+            available: true,
+            cli_command: key.to_string(),
+            config_key: key.to_string(),
+        };
+        let cwd = Path::new("/work");
+        let mut state = State::default();
+        state.config.general.default_provider = Some("aider".to_string());
+        state.available_providers = vec![info("Claude Code", "claude"), info("Aider", "aider")];
+
+        // Detection still running: the default is found, nothing earlier to wait for
+        state.detection_state = DetectionState::InProgress;
+        assert_eq!(state.preferred_provider(cwd), Some(1));
+
+        // A resumed session's provider wins over the configured default
+        state.sessions.start_session("Claude Code", cwd);
+        assert_eq!(state.preferred_provider(cwd), Some(0));
+
+        // ...unless it is gone, in which case the default is used once detection ends
+        state.available_providers.remove(0);
+        assert_eq!(state.preferred_provider(cwd), None);
+        state.detection_state = DetectionState::Completed;
+        assert_eq!(state.preferred_provider(cwd), Some(0));
+    }
+
+    #[test]
+    fn test_apply_one_hunk_then_rebase_the_rest_on_an_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        let original: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, &original).unwrap();
+        let proposed = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "line eighteen\n");
+        let mut state = State::default();
+        state.load_review(
+            vec![FileChange {
+                path: path.clone(),
+                original_content: Some(original.clone()),
+                proposed_content: proposed,
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        assert_eq!(state.hunks.len(), 2);
+
+        let (written, line, _) = state.apply_hunk(0).unwrap();
+        assert_eq!((written.as_path(), line), (path.as_path(), 2));
+        let before = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(before, original.replace("line 2\n", "line two\n"));
+        assert_eq!(state.hunks.len(), 1);
+        assert!(!state.overlay_diff_state.proposed_changes[0].changed_on_disk);
+
+        // An edit elsewhere in the file keeps the remaining change on top
+        let after = before.replace("line 10\n", "line ten\n");
+        std::fs::write(&path, &after).unwrap();
+        assert!(state.rebase_on_edit(&path, &before, &after));
+        assert_eq!(
+            state.pending_changes[&path].proposed_content,
+            after.replace("line 18\n", "line eighteen\n")
+        );
+        assert_eq!(state.hunks.len(), 1);
+        assert!(!state.hunks[0].drifted);
+    }
+
+    #[test]
+    fn test_switching_sessions_keeps_the_review_of_the_one_left() {
+        let cwd = Path::new("/work");
+        let mut state = State::default();
+        let first = state.sessions.start_session("Claude Code", cwd);
+        let second = state.sessions.start_session("Claude Code", cwd);
+        state.load_review(
+            vec![FileChange {
+                path: PathBuf::from("a.rs"),
+                original_content: Some("a\n".to_string()),
+                proposed_content: "b\n".to_string(),
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        state.prompt_buffer = "draft".to_string();
+        state.mode = Mode::DiffReview;
+
+        state.switch_session(&first).unwrap();
+        assert_eq!(state.sessions.current_session_id, Some(first.clone()));
+        assert!(state.hunks.is_empty());
+        assert!(state.prompt_buffer.is_empty());
+        assert_eq!(state.mode, Mode::PromptEntry);
+
+        // The switcher lists the session just left first among the others
+        assert_eq!(state.open_session_switcher(), 2);
+        let switcher = state.session_switcher.as_ref().unwrap();
+        assert!(switcher.entries[0].current);
+        assert_eq!(switcher.selected_entry().unwrap().id, second);
+        assert!(switcher.selected_entry().unwrap().in_progress);
+
+        state.switch_session(&second).unwrap();
+        assert_eq!(state.hunks.len(), 1);
+        assert_eq!(state.prompt_buffer, "draft");
+        assert_eq!(state.mode, Mode::DiffReview);
+
+        state.mode = Mode::Processing;
+        assert!(state.switch_session(&first).is_err());
+    }
+
+    #[test]
+    fn test_grep_groups_hits_with_the_current_session_first() {
+        let cwd = Path::new("/work");
+        let mut sessions = crate::session::SessionManager::default();
+        let first = sessions.start_session("Claude Code", cwd);
+        let second = sessions.start_session("Claude Code", cwd);
+        let hit = |session: &str, id: usize| crate::chat_log::Hit {
+            session_id: session.to_string(),
+            message_id: id,
+            is_user: true,
+            timestamp: chrono::Utc::now(),
+            snippet: format!("message {}", id),
+        };
+
+        let mut grep = GrepView::new(
+            "message",
+            vec![
+                hit(&first, 1),
+                hit("gone", 2),
+                hit(&first, 3),
+                hit(&second, 4),
+            ],
+            &sessions,
+        );
+        let order: Vec<_> = grep.groups.iter().map(|g| g.session_id.clone()).collect();
+        assert_eq!(order, vec![second, first.clone()]);
+        assert_eq!(grep.len(), 3);
+
+        grep.select_by(5);
+        let selected = grep.selected_hit().unwrap();
+        assert_eq!(
+            (selected.session_id.as_str(), selected.message_id),
+            (first.as_str(), 3)
+        );
+    }
+
+    #[test]
+    fn test_trivial_hunks_are_auto_accepted_until_decided_on() {
+        let mut state = State::default();
+        state.config.auto_accept.whitespace = true;
+        state.auto_accept =
+            crate::auto_accept::AutoAccept::new(Path::new("/work"), &state.config.auto_accept)
+                .unwrap();
+        let original: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let proposed = original
+            .replace("line 2\n", "line  2\n")
+            .replace("line 18\n", "line eighteen\n");
+        state.load_review(
+            vec![FileChange {
+                path: PathBuf::from("a.txt"),
+                original_content: Some(original),
+                proposed_content: proposed,
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+
+        assert_eq!(state.hunks.len(), 2);
+        assert_eq!(state.hunks[0].status, HunkStatus::Accepted);
+        assert_eq!(state.hunks[1].status, HunkStatus::Pending);
+        let change = &state.overlay_diff_state.proposed_changes[0];
+        assert_eq!(change.auto_accepted, vec![(0, "whitespace".to_string())]);
+        assert!(change
+            .line_decorations
+            .iter()
+            .any(|d| d.hunk_id == Some(0) && d.accepted == Some(true)));
+
+        state.set_hunk_status(0, HunkStatus::Rejected);
+        state.set_hunk_status(0, HunkStatus::Accepted);
+        assert!(state.hunks[0].auto_accepted.is_none());
+        assert!(state.overlay_diff_state.proposed_changes[0]
+            .auto_accepted
+            .is_empty());
+    }
+
+    #[test]
+    fn test_folded_group_is_one_stop() {
+        let original: String = (0..40).map(|i| format!("line {}\n", i)).collect();
+        let proposed = original
+            .replace("line 5\n", "line five\n")
+            .replace("line 14\n", "line fourteen\n")
+            .replace("line 35\n", "line thirty-five\n");
+        let mut state = State::default();
+        state.load_review(
+            vec![FileChange {
+                path: PathBuf::from("a.txt"),
+                original_content: Some(original),
+                proposed_content: proposed,
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        assert_eq!(state.hunks.len(), 3);
+        assert_eq!(state.group_of(0).unwrap().hunk_ids, vec![0, 1]);
+        assert!(state.group_of(2).is_none());
+
+        state.select_hunk(1);
+        assert_eq!(state.toggle_group_collapsed(), Some(true));
+        assert_eq!(state.selected_hunk, 0);
+        assert_eq!(state.set_group_status(HunkStatus::Accepted), 2);
+        assert!(state.hunks[..2]
+            .iter()
+            .all(|h| h.status == HunkStatus::Accepted));
+
+        state.step_hunk(true);
+        assert_eq!(state.selected_hunk, 2);
+        state.step_hunk(false);
+        assert_eq!(state.selected_hunk, 0);
+    }
+
+    #[test]
+    fn test_reindent_is_previewed_then_accepted() {
+        let mut state = State::default();
+        state.load_review(
+            vec![FileChange {
+                path: PathBuf::from("src/lib.rs"),
+                original_content: Some("fn a() {\n    one();\n}\n".to_string()),
+                proposed_content: "fn a() {\n    one();\ntwo();\n}\n".to_string(),
+                change_type: ChangeType::Modify,
+                renamed_from: None,
+            }],
+            WritePolicy::default(),
+        );
+        assert_eq!(state.preview_reindent().unwrap().changed(), 1);
+        // Only the preview changed so far
+        assert_eq!(state.hunks[0].status, HunkStatus::Pending);
+
+        assert_eq!(
+            state.accept_reindent().unwrap(),
+            PathBuf::from("src/lib.rs")
+        );
+        assert_eq!(
+            state.pending_changes[Path::new("src/lib.rs")].proposed_content,
+            "fn a() {\n    one();\n    two();\n}\n"
+        );
+        assert_eq!(state.hunks[0].status, HunkStatus::Accepted);
+        assert!(state.reindent.is_none());
+        assert!(state.preview_reindent().is_err());
+    }
+
+    #[test]
+    fn test_skipped_file_is_left_out_and_stays_skipped() {
+        let change = |path: &str| FileChange {
+            path: PathBuf::from(path),
+            original_content: Some("a\n".to_string()),
+            proposed_content: "b\n".to_string(),
+            change_type: ChangeType::Modify,
+            renamed_from: None,
+        };
+        let mut state = State::default();
+        state.load_review(
+            vec![change("a.txt"), change("b.txt")],
+            WritePolicy::default(),
+        );
+        state.set_all_hunks_status(HunkStatus::Accepted);
+
+        assert_eq!(
+            state.toggle_skip_file(),
+            Some((PathBuf::from("a.txt"), true))
+        );
+        let files: Vec<_> = state.apply_summary().into_iter().map(|f| f.path).collect();
+        assert_eq!(files, [PathBuf::from("b.txt")]);
+        assert_eq!(state.review_progress().total(), 1);
+        assert!(state.overlay_diff_state.proposed_changes[0].skipped);
+
+        // A later review of the same file keeps it skipped
+        state.load_review(
+            vec![change("a.txt"), change("b.txt")],
+            WritePolicy::default(),
+        );
+        assert!(state.overlay_diff_state.proposed_changes[0].skipped);
+        state.select_hunk(0);
+        state.step_hunk(true);
+        assert_eq!(state.selected_hunk, 1);
+        state.step_hunk(false);
+        assert_eq!(
+            state.toggle_skip_file(),
+            Some((PathBuf::from("a.txt"), false))
+        );
+        assert_eq!(state.review_progress().total(), 2);
+    }
+
+    #[test]
+    fn test_prompt_mode_toggles_and_replies_show_as_explanations() {
+        let mut state = State::default();
+        state.set_prompt_mode(None);
+        assert_eq!(state.prompt_mode, PromptMode::Ask);
+        state.set_prompt_mode(Some(PromptMode::Ask));
+        assert_eq!(state.prompt_mode, PromptMode::Ask);
+        state.set_prompt_mode(None);
+        assert_eq!(state.prompt_mode, PromptMode::Edit);
+
+        state.chat_history.add_message(chat_message(7));
+        state.show_explanation();
+        assert_eq!(state.mode, Mode::MessageDetail);
+        assert_eq!(state.message_detail.as_ref().unwrap().message_id, 7);
+    }
+
+    #[test]
+    fn test_dialog_scroll_stays_in_text_and_selection_follows() {
+        let mut scroll = DialogScroll::default();
+        scroll.fit(30, 10, 60);
+        scroll.page_by(5);
+        assert_eq!(scroll.offset, 20);
+        scroll.scroll_by(-3);
+        assert_eq!(scroll.offset, 17);
+
+        // Selecting moves the selection's end, dragging the view with it
+        scroll.toggle_selection();
+        scroll.scroll_by(12);
+        assert_eq!(scroll.selected(), Some(17..30));
+        assert_eq!(scroll.offset, 20);
+        scroll.scroll_by(-15);
+        assert_eq!(scroll.selected(), Some(14..18));
+        assert_eq!(scroll.offset, 14);
+
+        // A shorter redraw pulls the view and selection back in
+        scroll.fit(8, 10, 60);
+        assert_eq!(scroll.offset, 0);
+        assert_eq!(scroll.selected(), Some(7..8));
+        scroll.toggle_selection();
+        assert_eq!(scroll.selected(), None);
     }
 }
@@ -0,0 +1,320 @@
+//! Workspace symbol index for `#symbol` prompt references
+//!
+//! Definitions are found with a line-based regex over source files (respecting
+//! `.gitignore`), which is cheap enough to build on first use and good enough to
+//! point a prompt at the right function or type. Typing `#Name` in the prompt
+//! completes against the index; on submit each reference is resolved to its
+//! definition and the snippet is attached as context.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::context::{estimate_tokens, ContextAttachment};
+use crate::diff::definition_regex;
+
+/// Stop indexing after this many files so huge trees stay responsive
+const MAX_FILES: usize = 5_000;
+/// Files larger than this are skipped (generated code, bundles)
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+/// Longest snippet attached for one symbol
+const MAX_SNIPPET_LINES: usize = 120;
+
+const SOURCE_EXTENSIONS: [&str; 18] = [
+    "rs", "py", "js", "jsx", "ts", "tsx", "go", "java", "kt", "rb", "c", "h", "cc", "cpp", "hpp",
+    "cs", "swift", "scala",
+];
+
+/// A named definition in the workspace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    /// Definition keyword, e.g. `fn`, `struct`, `class`
+    pub kind: String,
+    pub path: PathBuf,
+    /// 1-based line of the definition
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolIndex {
+    symbols: Vec<Symbol>,
+}
+
+impl SymbolIndex {
+    /// Index the source files under `root`
+    pub fn build(root: &Path) -> Self {
+        let re = definition_regex();
+        let mut symbols = Vec::new();
+
+        let files = ignore::WalkBuilder::new(root)
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| SOURCE_EXTENSIONS.contains(&e))
+            })
+            .filter(|entry| {
+                entry
+                    .metadata()
+                    .map(|m| m.len() <= MAX_FILE_BYTES)
+                    .unwrap_or(false)
+            })
+            .take(MAX_FILES);
+
+        for entry in files {
+            let Ok(content) = std::fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            let path = entry
+                .path()
+                .strip_prefix(root)
+                .unwrap_or(entry.path())
+                .to_path_buf();
+            symbols.extend(scan(&re, &path, &content));
+        }
+
+        symbols.sort_by(|a, b| a.name.cmp(&b.name).then(a.path.cmp(&b.path)));
+        Self { symbols }
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Symbols whose name starts with `prefix`; exact-case matches rank first
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<Symbol> {
+        let lower = prefix.to_lowercase();
+        let mut matches: Vec<&Symbol> = self
+            .symbols
+            .iter()
+            .filter(|s| s.name.to_lowercase().starts_with(&lower))
+            .collect();
+        matches.sort_by_key(|s| (!s.name.starts_with(prefix), s.name.len()));
+        matches.dedup_by(|a, b| a.name == b.name && a.path == b.path);
+        matches.into_iter().take(limit).cloned().collect()
+    }
+
+    /// The definition for `name`, preferring types over functions and other kinds
+    pub fn find(&self, name: &str) -> Option<&Symbol> {
+        self.symbols
+            .iter()
+            .filter(|s| s.name == name)
+            .min_by_key(|s| !matches!(s.kind.as_str(), "struct" | "enum" | "trait" | "class"))
+    }
+}
+
+fn scan(re: &Regex, path: &Path, content: &str) -> Vec<Symbol> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let caps = re.captures(line)?;
+            Some(Symbol {
+                name: caps[2].to_string(),
+                kind: caps[1].to_string(),
+                path: path.to_path_buf(),
+                line: idx + 1,
+            })
+        })
+        .collect()
+}
+
+/// Last line (0-based, inclusive) of the definition starting at `start`.
+///
+/// Brace languages end where the braces opened on the definition balance out;
+/// indentation languages end before the next line indented no deeper than the
+/// definition.
+pub fn definition_end(lines: &[&str], start: usize) -> usize {
+    let last = (start + MAX_SNIPPET_LINES - 1).min(lines.len().saturating_sub(1));
+    let first = lines[start];
+
+    if first.trim_end().ends_with(':') {
+        let indent = first.len() - first.trim_start().len();
+        let mut end = start;
+        for (idx, line) in lines.iter().enumerate().take(last + 1).skip(start + 1) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.len() - line.trim_start().len() <= indent {
+                break;
+            }
+            end = idx;
+        }
+        return end;
+    }
+
+    let mut depth = 0usize;
+    let mut opened = false;
+    for (idx, line) in lines.iter().enumerate().take(last + 1).skip(start) {
+        for c in line.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => depth = depth.saturating_sub(1),
+                ';' if !opened => return idx,
+                _ => {}
+            }
+        }
+        if opened && depth == 0 {
+            return idx;
+        }
+    }
+    if opened {
+        last
+    } else {
+        start
+    }
+}
+
+/// Load the definition of `symbol` (relative to `root`) as a context attachment
+pub fn load_snippet(root: &Path, symbol: &Symbol) -> Result<ContextAttachment> {
+    let full = root.join(&symbol.path);
+    let content = std::fs::read_to_string(&full)
+        .with_context(|| format!("Failed to read {}", full.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let start = symbol.line.saturating_sub(1);
+    if start >= lines.len() {
+        anyhow::bail!("{} changed since it was indexed", symbol.path.display());
+    }
+    let end = definition_end(&lines, start);
+    let snippet = lines[start..=end].join("\n");
+    Ok(ContextAttachment {
+        path: symbol.path.clone(),
+        lines: Some((start + 1, end + 1)),
+        tokens: estimate_tokens(&snippet),
+        content: snippet,
+    })
+}
+
+/// The `#word` being typed at `cursor`, as (byte offset of `#`, word after it)
+pub fn reference_at(text: &str, cursor: usize) -> Option<(usize, &str)> {
+    let before = text.get(..cursor)?;
+    let start = before
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|&i| before[i..].starts_with('#'))?;
+    if start > 0 && !before[..start].ends_with(char::is_whitespace) {
+        return None;
+    }
+    Some((start, &before[start + 1..]))
+}
+
+/// Identifiers used in `text`, calls (`name(`, `name!(`) before other uses,
+/// each in order of first appearance
+pub fn identifiers(text: &str) -> Vec<&str> {
+    let re = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").expect("valid identifier regex");
+    let mut calls = Vec::new();
+    let mut others = Vec::new();
+    for m in re.find_iter(text) {
+        let rest = text[m.end()..].trim_start_matches('!');
+        let bucket = if rest.starts_with('(') {
+            &mut calls
+        } else {
+            &mut others
+        };
+        if !bucket.contains(&m.as_str()) {
+            bucket.push(m.as_str());
+        }
+    }
+    others.retain(|name| !calls.contains(name));
+    calls.extend(others);
+    calls
+}
+
+/// Names referenced as `#Name` anywhere in `text`
+pub fn references(text: &str) -> Vec<String> {
+    let re = Regex::new(r"(?:^|\s)#([A-Za-z_][A-Za-z0-9_]*)").expect("valid reference regex");
+    let mut names: Vec<String> = Vec::new();
+    for caps in re.captures_iter(text) {
+        if !names.iter().any(|n| n == &caps[1]) {
+            names.push(caps[1].to_string());
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_finds_definitions() {
+        let source = "pub(crate) struct Foo {\n    a: u8,\n}\n\nasync fn bar() {}\nclass Baz:\n    pass\nfunc (r *Repo) Save() error {\n";
+        let names: Vec<_> = scan(&definition_regex(), Path::new("x"), source)
+            .into_iter()
+            .map(|s| (s.name, s.line))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Foo".to_string(), 1),
+                ("bar".to_string(), 5),
+                ("Baz".to_string(), 6),
+                ("Save".to_string(), 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_definition_end() {
+        let rust = ["fn a() {", "    if x {", "    }", "}", "fn b() {}"];
+        assert_eq!(definition_end(&rust, 0), 3);
+        assert_eq!(definition_end(&rust, 4), 4);
+        assert_eq!(definition_end(&["struct Unit;", "fn c() {}"], 0), 0);
+
+        let python = ["def a():", "    x = 1", "", "    return x", "def b():"];
+        assert_eq!(definition_end(&python, 0), 3);
+    }
+
+    #[test]
+    fn test_reference_parsing() {
+        assert_eq!(reference_at("explain #Sym", 12), Some((8, "Sym")));
+        assert_eq!(reference_at("#", 1), Some((0, "")));
+        assert_eq!(reference_at("issue#12", 8), None);
+        assert_eq!(reference_at("plain word", 10), None);
+        assert_eq!(
+            references("use #Foo and #bar, not a#b or #Foo"),
+            vec!["Foo", "bar"]
+        );
+    }
+
+    #[test]
+    fn test_identifiers_prefer_calls() {
+        assert_eq!(
+            identifiers("let total = sum(items) + println!(\"{}\", total);"),
+            vec!["sum", "println", "let", "total", "items"]
+        );
+    }
+
+    #[test]
+    fn test_index_complete_and_snippet() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "use x;\n\npub struct Config {\n    a: u8,\n}\n\nfn config_path() {}\n",
+        )
+        .unwrap();
+        let index = SymbolIndex::build(dir.path());
+        let names: Vec<_> = index
+            .complete("conf", 5)
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["config_path", "Config"]);
+
+        let snippet = load_snippet(dir.path(), index.find("Config").unwrap()).unwrap();
+        assert_eq!(snippet.lines, Some((3, 5)));
+        assert_eq!(snippet.content, "pub struct Config {\n    a: u8,\n}");
+    }
+}
@@ -0,0 +1,169 @@
+//! Syntax checks before applying
+//!
+//! When the apply confirmation opens, each file it would write is rebuilt from
+//! its accepted hunks and parsed: JSON and TOML here, other languages by the
+//! `[syntax_check]` command for their extension. A file is reported only when
+//! it parses now and would not after the apply, so a checker that can't make
+//! sense of a file out of its project (a `mod` whose file isn't there) stays
+//! quiet. Files without a checker, or whose command can't run, are skipped.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::SyntaxCheckConfig;
+use crate::executor::shell_quote;
+
+/// Lines of checker output kept in a problem
+const MESSAGE_LINES: usize = 3;
+
+/// A file the apply would leave unparseable
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub path: PathBuf,
+    /// The start of what the checker said
+    pub message: String,
+}
+
+/// Check `path` with its content after the apply, `original` being its
+/// content now (`None` for a new file)
+pub fn check_file(
+    path: &Path,
+    original: Option<&str>,
+    content: &str,
+    config: &SyntaxCheckConfig,
+) -> Option<Problem> {
+    if !config.enabled {
+        return None;
+    }
+    let Err(message) = parse(path, content, config)? else {
+        return None;
+    };
+    // Broken already, or the checker needs more than the file
+    if original.is_some_and(|original| !matches!(parse(path, original, config), Some(Ok(())))) {
+        return None;
+    }
+    Some(Problem {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+/// Whether `content` parses as the language of `path`. `None` when there is
+/// no checker for it or the checker didn't give an answer.
+fn parse(path: &Path, content: &str, config: &SyntaxCheckConfig) -> Option<Result<(), String>> {
+    let extension = path.extension()?.to_str()?;
+    if let Some(command) = config.commands.get(extension) {
+        return run_checker(
+            command,
+            path,
+            content,
+            Duration::from_millis(config.timeout_ms),
+        );
+    }
+    match extension {
+        "json" => Some(
+            serde_json::from_str::<serde_json::Value>(content)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        ),
+        "toml" => Some(
+            content
+                .parse::<toml::Table>()
+                .map(|_| ())
+                .map_err(|e| e.message().to_string()),
+        ),
+        _ => None,
+    }
+}
+
+/// Run `command` on a copy of `content` named like `path`
+fn run_checker(
+    command: &str,
+    path: &Path,
+    content: &str,
+    timeout: Duration,
+) -> Option<Result<(), String>> {
+    let dir = tempfile::tempdir().ok()?;
+    let file = dir.path().join(path.file_name()?);
+    std::fs::write(&file, content).ok()?;
+
+    let shell = command.replace("{file}", &shell_quote(&file.to_string_lossy()));
+    let output_path = dir.path().join(".zcode-syntax-output");
+    let output = std::fs::File::create(&output_path).ok()?;
+    let mut child = Command::new("sh")
+        .args(["-c", &shell])
+        .current_dir(dir.path())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(output)
+        .spawn()
+        .ok()?;
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().ok()? {
+            break status;
+        }
+        if started.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    // 126/127: the shell couldn't find or run the checker
+    match status.code() {
+        Some(0) => Some(Ok(())),
+        Some(126 | 127) | None => None,
+        Some(_) => {
+            let stderr = std::fs::read_to_string(&output_path).unwrap_or_default();
+            let copy = file.to_string_lossy();
+            let message: Vec<&str> = stderr
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .take(MESSAGE_LINES)
+                .collect();
+            Some(Err(message
+                .join(" ")
+                .replace(copy.as_ref(), &path.to_string_lossy())))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_files_broken_by_the_apply_are_reported() {
+        let config = SyntaxCheckConfig {
+            commands: [("sh".to_string(), "sh -n {file}".to_string())].into(),
+            ..SyntaxCheckConfig::default()
+        };
+        let path = Path::new("config/app.json");
+        let problem = check_file(path, Some("{\"a\": 1}"), "{\"a\": 1,", &config).unwrap();
+        assert_eq!(problem.path, path);
+        assert!(problem.message.contains("EOF"), "{}", problem.message);
+
+        // Already broken; new files; no checker
+        assert!(check_file(path, Some("{"), "{\"a\":", &config).is_none());
+        assert!(check_file(Path::new("a.toml"), None, "x = 1", &config).is_none());
+        assert!(check_file(Path::new("a.toml"), None, "x = ", &config).is_some());
+        assert!(check_file(Path::new("a.txt"), Some(""), "{", &config).is_none());
+
+        // A command per extension, fed a copy of the file
+        let script = Path::new("scripts/run.sh");
+        assert!(check_file(script, Some("echo hi\n"), "echo ok\n", &config).is_none());
+        let problem = check_file(script, Some("echo hi\n"), "if true; then\n", &config).unwrap();
+        assert!(!problem.message.is_empty());
+
+        // A checker that isn't installed says nothing
+        let missing = SyntaxCheckConfig {
+            commands: [("sh".to_string(), "zcode-no-such-checker {file}".to_string())].into(),
+            ..SyntaxCheckConfig::default()
+        };
+        assert!(check_file(script, Some(""), "if true; then\n", &missing).is_none());
+    }
+}
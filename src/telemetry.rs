@@ -0,0 +1,285 @@
+//! Opt-in usage metrics over OTLP
+//!
+//! With `[telemetry] enabled = true`, a build with the `telemetry` feature
+//! posts anonymous metrics to an OTLP/HTTP collector every `interval_secs`:
+//! how long prompts take per provider, how many reviews were applied and how
+//! many errors of each category were shown. Prompts, replies, paths and
+//! session ids are never sent. Metrics use delta temporality, so each export
+//! covers the time since the one before it.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::config::TelemetryConfig;
+
+/// Whether this build can export metrics at all
+pub const AVAILABLE: bool = cfg!(feature = "telemetry");
+
+/// Upper bounds of the prompt latency buckets, in milliseconds
+const LATENCY_BOUNDS_MS: [f64; 8] = [
+    1_000.0, 2_000.0, 5_000.0, 10_000.0, 20_000.0, 30_000.0, 60_000.0, 120_000.0,
+];
+
+/// Longest an export may hold up quitting
+const EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Default, Clone)]
+struct Latency {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    buckets: [u64; LATENCY_BOUNDS_MS.len() + 1],
+}
+
+#[derive(Debug)]
+pub struct Telemetry {
+    /// Unset when telemetry is off
+    config: Option<TelemetryConfig>,
+    latency: BTreeMap<String, Latency>,
+    /// Applied reviews by outcome ("ok", "failed")
+    applies: BTreeMap<&'static str, u64>,
+    errors: BTreeMap<&'static str, u64>,
+    period_start: SystemTime,
+    last_export: Instant,
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self::new(&TelemetryConfig::default())
+    }
+}
+
+impl Telemetry {
+    pub fn new(config: &TelemetryConfig) -> Self {
+        Self {
+            config: (AVAILABLE && config.enabled).then(|| config.clone()),
+            latency: BTreeMap::new(),
+            applies: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            period_start: SystemTime::now(),
+            last_export: Instant::now(),
+        }
+    }
+
+    /// Use `config` from now on, sending what was recorded under the old
+    /// one first
+    pub fn reconfigure(&mut self, config: &TelemetryConfig) {
+        let wanted = (AVAILABLE && config.enabled).then_some(config);
+        if self.config.as_ref() != wanted {
+            self.flush();
+            *self = Self::new(config);
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    pub fn record_prompt(&mut self, provider: &str, elapsed: Duration) {
+        if !self.enabled() {
+            return;
+        }
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        let latency = self.latency.entry(provider.to_string()).or_default();
+        if latency.count == 0 || ms < latency.min {
+            latency.min = ms;
+        }
+        latency.max = latency.max.max(ms);
+        latency.count += 1;
+        latency.sum += ms;
+        let bucket = LATENCY_BOUNDS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(LATENCY_BOUNDS_MS.len());
+        latency.buckets[bucket] += 1;
+    }
+
+    pub fn record_apply(&mut self, succeeded: bool) {
+        if self.enabled() {
+            let outcome = if succeeded { "ok" } else { "failed" };
+            *self.applies.entry(outcome).or_default() += 1;
+        }
+    }
+
+    /// Count an error shown to the user, e.g. "provider", "parse" or "apply"
+    pub fn record_error(&mut self, category: &'static str) {
+        if self.enabled() {
+            *self.errors.entry(category).or_default() += 1;
+        }
+    }
+
+    /// Send what was recorded once the export interval has passed. The
+    /// request runs on its own thread; a failed export drops its metrics.
+    pub fn export_due(&mut self) {
+        let Some(config) = &self.config else {
+            return;
+        };
+        if self.last_export.elapsed() < Duration::from_secs(config.interval_secs.max(1)) {
+            return;
+        }
+        let config = config.clone();
+        if let Some(payload) = self.take_payload(SystemTime::now()) {
+            std::thread::spawn(move || send(&config, &payload, None));
+        }
+    }
+
+    /// Send what is left before quitting
+    pub fn flush(&mut self) {
+        let Some(config) = self.config.clone() else {
+            return;
+        };
+        if let Some(payload) = self.take_payload(SystemTime::now()) {
+            send(&config, &payload, Some(EXIT_TIMEOUT));
+        }
+    }
+
+    /// The OTLP/JSON request for the metrics since the last export, which
+    /// starts a new period. `None` when nothing was recorded.
+    fn take_payload(&mut self, now: SystemTime) -> Option<Value> {
+        self.last_export = Instant::now();
+        if self.latency.is_empty() && self.applies.is_empty() && self.errors.is_empty() {
+            return None;
+        }
+        let start = std::mem::replace(&mut self.period_start, now);
+        let (start, now) = (nanos(start), nanos(now));
+
+        let point = |key: &str, value: &str| {
+            json!({
+                "attributes": [attribute(key, value)],
+                "startTimeUnixNano": start,
+                "timeUnixNano": now,
+            })
+        };
+        let counter = |name: &str, key: &str, counts: &BTreeMap<&'static str, u64>| {
+            let points: Vec<Value> = counts
+                .iter()
+                .map(|(value, count)| {
+                    let mut p = point(key, value);
+                    p["asInt"] = json!(count.to_string());
+                    p
+                })
+                .collect();
+            json!({
+                "name": name,
+                "sum": {
+                    "aggregationTemporality": 1,
+                    "isMonotonic": true,
+                    "dataPoints": points,
+                },
+            })
+        };
+
+        let latency: Vec<Value> = std::mem::take(&mut self.latency)
+            .into_iter()
+            .map(|(provider, l)| {
+                let mut p = point("provider", &provider);
+                p["count"] = json!(l.count.to_string());
+                p["sum"] = json!(l.sum);
+                p["min"] = json!(l.min);
+                p["max"] = json!(l.max);
+                p["bucketCounts"] = json!(l.buckets.iter().map(u64::to_string).collect::<Vec<_>>());
+                p["explicitBounds"] = json!(LATENCY_BOUNDS_MS);
+                p
+            })
+            .collect();
+        let mut metrics = Vec::new();
+        if !latency.is_empty() {
+            metrics.push(json!({
+                "name": "zcode.prompt.duration",
+                "unit": "ms",
+                "histogram": { "aggregationTemporality": 1, "dataPoints": latency },
+            }));
+        }
+        if !self.applies.is_empty() {
+            metrics.push(counter("zcode.applies", "outcome", &self.applies));
+        }
+        if !self.errors.is_empty() {
+            metrics.push(counter("zcode.errors", "category", &self.errors));
+        }
+        self.applies.clear();
+        self.errors.clear();
+
+        Some(json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [
+                        attribute("service.name", "zcode"),
+                        attribute("service.version", env!("CARGO_PKG_VERSION")),
+                    ],
+                },
+                "scopeMetrics": [{ "scope": { "name": "zcode" }, "metrics": metrics }],
+            }],
+        }))
+    }
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// OTLP/JSON writes 64-bit integers as strings
+fn nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+#[cfg(feature = "telemetry")]
+fn send(config: &TelemetryConfig, payload: &Value, timeout: Option<Duration>) {
+    let url = format!("{}/v1/metrics", config.endpoint.trim_end_matches('/'));
+    let mut request = ureq::post(&url)
+        .timeout(timeout.unwrap_or(Duration::from_secs(10)))
+        .set("Content-Type", "application/json");
+    for (name, value) in &config.headers {
+        request = request.set(name, value);
+    }
+    let _ = request.send_string(&payload.to_string());
+}
+
+#[cfg(not(feature = "telemetry"))]
+fn send(_config: &TelemetryConfig, _payload: &Value, _timeout: Option<Duration>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_are_exported_once_per_period() {
+        let config = TelemetryConfig {
+            enabled: true,
+            ..TelemetryConfig::default()
+        };
+        let mut telemetry = Telemetry {
+            config: Some(config.clone()),
+            ..Telemetry::new(&config)
+        };
+        telemetry.record_prompt("claude", Duration::from_millis(1500));
+        telemetry.record_prompt("claude", Duration::from_millis(500));
+        telemetry.record_apply(true);
+        telemetry.record_error("parse");
+        telemetry.record_error("parse");
+
+        let payload = telemetry.take_payload(SystemTime::now()).unwrap();
+        let metrics = &payload["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        let latency = &metrics[0]["histogram"]["dataPoints"][0];
+        assert_eq!(latency["attributes"][0]["value"]["stringValue"], "claude");
+        assert_eq!(latency["count"], "2");
+        assert_eq!(latency["min"], 500.0);
+        assert_eq!(latency["max"], 1500.0);
+        assert_eq!(latency["bucketCounts"][0], "1");
+        assert_eq!(latency["bucketCounts"][1], "1");
+        assert_eq!(metrics[1]["name"], "zcode.applies");
+        assert_eq!(metrics[2]["sum"]["dataPoints"][0]["asInt"], "2");
+
+        // Delta temporality: the next export starts from nothing
+        assert!(telemetry.take_payload(SystemTime::now()).is_none());
+
+        let mut off = Telemetry::default();
+        off.record_error("parse");
+        assert!(off.take_payload(SystemTime::now()).is_none());
+    }
+}
@@ -0,0 +1,313 @@
+//! Prompt templates with variables
+//!
+//! A template is a Markdown file in the project's `.zcode/templates/` or in
+//! `~/.config/zcode/templates/` (the project wins when both have one by that
+//! name). `:template <name>` fills in its variables through a small form and
+//! sends the result as the prompt.
+//!
+//! Variables are written `{{name}}` (required text), `{{name=default}}` (text
+//! with a default), `{{name:number}}` and `{{name:choice=a|b|c}}` (one of the
+//! listed values, the first being the default). A variable used several times
+//! is asked for once.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Project templates, relative to the project root
+pub const PROJECT_DIR: &str = ".zcode/templates";
+
+/// `{{name}}`, `{{name=default}}`, `{{name:kind}}` or `{{name:kind=spec}}`
+const VARIABLE: &str = r"\{\{\s*([A-Za-z_][A-Za-z0-9_-]*)\s*(?::\s*([a-z]+))?\s*(?:=([^}]*))?\}\}";
+
+/// Global templates directory
+pub fn global_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("templates")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariableKind {
+    Text,
+    Number,
+    Choice(Vec<String>),
+}
+
+/// A variable a template declares
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub kind: VariableKind,
+    pub default: Option<String>,
+}
+
+impl Variable {
+    /// Why `value` can't be used for this variable, if it can't
+    pub fn validate(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        match &self.kind {
+            _ if value.is_empty() => Some(format!("{} is required", self.name)),
+            VariableKind::Text => None,
+            VariableKind::Number if value.parse::<f64>().is_err() => {
+                Some(format!("{} must be a number", self.name))
+            }
+            VariableKind::Number => None,
+            VariableKind::Choice(choices) if !choices.iter().any(|c| c == value) => Some(format!(
+                "{} must be one of {}",
+                self.name,
+                choices.join(", ")
+            )),
+            VariableKind::Choice(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pub name: String,
+    pub body: String,
+}
+
+impl Template {
+    /// Load template `name` from the project under `root`, else the global directory
+    pub fn load(name: &str, root: &Path, global: &Path) -> Result<Self> {
+        let file = format!("{}.md", name);
+        let path = [root.join(PROJECT_DIR).join(&file), global.join(&file)]
+            .into_iter()
+            .find(|p| p.is_file())
+            .with_context(|| {
+                format!(
+                    "No template '{}' in {} or {}",
+                    name,
+                    PROJECT_DIR,
+                    global.display()
+                )
+            })?;
+        let body = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Self {
+            name: name.to_string(),
+            body,
+        })
+    }
+
+    /// The variables in the order they first appear
+    pub fn variables(&self) -> Vec<Variable> {
+        let re = Regex::new(VARIABLE).expect("valid regex");
+        let mut variables: Vec<Variable> = Vec::new();
+        for cap in re.captures_iter(&self.body) {
+            let name = cap[1].to_string();
+            if variables.iter().any(|v| v.name == name) {
+                continue;
+            }
+            let spec = cap.get(3).map(|m| m.as_str().trim().to_string());
+            let (kind, default) = match cap.get(2).map(|m| m.as_str()) {
+                Some("choice") => {
+                    let choices: Vec<String> = spec
+                        .unwrap_or_default()
+                        .split('|')
+                        .map(|c| c.trim().to_string())
+                        .filter(|c| !c.is_empty())
+                        .collect();
+                    let default = choices.first().cloned();
+                    (VariableKind::Choice(choices), default)
+                }
+                Some("number") => (VariableKind::Number, spec),
+                _ => (VariableKind::Text, spec),
+            };
+            variables.push(Variable {
+                name,
+                kind,
+                default: default.filter(|d| !d.is_empty()),
+            });
+        }
+        variables
+    }
+
+    /// The body with every variable replaced by its value
+    pub fn render(&self, values: &HashMap<String, String>) -> String {
+        let re = Regex::new(VARIABLE).expect("valid regex");
+        re.replace_all(&self.body, |cap: &regex::Captures| {
+            values.get(&cap[1]).cloned().unwrap_or_default()
+        })
+        .trim()
+        .to_string()
+    }
+}
+
+/// A variable being filled in on the template form
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormField {
+    pub variable: Variable,
+    pub value: String,
+    pub error: Option<String>,
+}
+
+/// The form asking for a template's variables before it is sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateForm {
+    pub template: Template,
+    pub fields: Vec<FormField>,
+    pub selected: usize,
+}
+
+impl TemplateForm {
+    /// A form with every field set to its variable's default
+    pub fn new(template: Template) -> Self {
+        let fields = template
+            .variables()
+            .into_iter()
+            .map(|variable| FormField {
+                value: variable.default.clone().unwrap_or_default(),
+                variable,
+                error: None,
+            })
+            .collect();
+        Self {
+            template,
+            fields,
+            selected: 0,
+        }
+    }
+
+    /// Move the selection by `delta` fields, wrapping around
+    pub fn select_by(&mut self, delta: isize) {
+        let len = self.fields.len() as isize;
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    pub fn selected_field(&mut self) -> Option<&mut FormField> {
+        self.fields.get_mut(self.selected)
+    }
+
+    /// Step the selected choice field to the next (or previous) choice
+    pub fn cycle_choice(&mut self, forward: bool) {
+        let Some(field) = self.selected_field() else {
+            return;
+        };
+        let VariableKind::Choice(choices) = &field.variable.kind else {
+            return;
+        };
+        if choices.is_empty() {
+            return;
+        }
+        let current = choices.iter().position(|c| *c == field.value);
+        let next = match (current, forward) {
+            (Some(i), true) => (i + 1) % choices.len(),
+            (Some(i), false) => (i + choices.len() - 1) % choices.len(),
+            (None, _) => 0,
+        };
+        field.value = choices[next].clone();
+        field.error = None;
+    }
+
+    /// The composed prompt, or `None` with the invalid fields marked and the
+    /// first of them selected
+    pub fn submit(&mut self) -> Option<String> {
+        for field in &mut self.fields {
+            field.error = field.variable.validate(&field.value);
+        }
+        if let Some(invalid) = self.fields.iter().position(|f| f.error.is_some()) {
+            self.selected = invalid;
+            return None;
+        }
+        let values = self
+            .fields
+            .iter()
+            .map(|f| (f.variable.name.clone(), f.value.trim().to_string()))
+            .collect();
+        Some(self.template.render(&values))
+    }
+}
+
+/// Names of the templates available under `root` and in `global`, sorted
+pub fn list(root: &Path, global: &Path) -> Vec<String> {
+    let mut names: Vec<String> = [root.join(PROJECT_DIR), global.to_path_buf()]
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| {
+            let path = entry.path();
+            (path.extension()? == "md").then(|| path.file_stem()?.to_str().map(str::to_string))?
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variables_are_declared_once_with_defaults() {
+        let template = Template {
+            name: "fix".to_string(),
+            body: "Fix {{ticket_id}} in a {{style:choice=concise|verbose}} way.\n\
+                   Keep it under {{lines:number=50}} lines. See {{ticket_id}}, {{reviewer=me}}."
+                .to_string(),
+        };
+        let variables = template.variables();
+        let names: Vec<&str> = variables.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["ticket_id", "style", "lines", "reviewer"]);
+        assert_eq!(variables[0].default, None);
+        assert_eq!(variables[1].default.as_deref(), Some("concise"));
+        assert_eq!(variables[2].default.as_deref(), Some("50"));
+
+        assert!(variables[0].validate("").is_some());
+        assert!(variables[1].validate("terse").is_some());
+        assert!(variables[1].validate("verbose").is_none());
+        assert!(variables[2].validate("many").is_some());
+
+        let values = HashMap::from([
+            ("ticket_id".to_string(), "ZC-7".to_string()),
+            ("style".to_string(), "verbose".to_string()),
+            ("lines".to_string(), "50".to_string()),
+            ("reviewer".to_string(), "me".to_string()),
+        ]);
+        assert_eq!(
+            template.render(&values),
+            "Fix ZC-7 in a verbose way.\nKeep it under 50 lines. See ZC-7, me."
+        );
+    }
+
+    #[test]
+    fn test_form_validates_before_composing() {
+        let template = Template {
+            name: "fix".to_string(),
+            body: "Fix {{ticket_id}}, {{style:choice=concise|verbose}}".to_string(),
+        };
+        let mut form = TemplateForm::new(template);
+        form.select_by(1);
+        form.cycle_choice(true);
+        assert_eq!(form.fields[1].value, "verbose");
+
+        assert_eq!(form.submit(), None);
+        assert_eq!(form.selected, 0);
+        assert!(form.fields[0].error.is_some());
+
+        form.fields[0].value = "ZC-7".to_string();
+        assert_eq!(form.submit().as_deref(), Some("Fix ZC-7, verbose"));
+    }
+
+    #[test]
+    fn test_project_templates_shadow_global_ones() {
+        let root = tempfile::tempdir().unwrap();
+        let global = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join(PROJECT_DIR)).unwrap();
+        std::fs::write(root.path().join(PROJECT_DIR).join("review.md"), "project").unwrap();
+        std::fs::write(global.path().join("review.md"), "global").unwrap();
+        std::fs::write(global.path().join("docs.md"), "global docs").unwrap();
+
+        let review = Template::load("review", root.path(), global.path()).unwrap();
+        assert_eq!(review.body, "project");
+        assert_eq!(list(root.path(), global.path()), ["docs", "review"]);
+        assert!(Template::load("missing", root.path(), global.path()).is_err());
+    }
+}
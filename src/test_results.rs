@@ -0,0 +1,147 @@
+//! Failing test locations from test runner output
+//!
+//! After changes are applied, `general.test_command` is run and its output is
+//! scanned for the file/line locations of failures. Locations that fall inside an
+//! applied hunk mark that hunk, so the review shows which changes likely broke
+//! which tests. Output from `cargo test`, pytest and jest is understood.
+
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::diff::new_line_range;
+use crate::state::Hunk;
+
+/// Lines of slack around a hunk when matching a failure location to it
+const NEAR_LINES: usize = 3;
+
+/// One location reported for a failing test
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestFailure {
+    pub test: String,
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+}
+
+/// Failure locations found in `output`, in order of appearance
+pub fn parse_failures(output: &str) -> Vec<TestFailure> {
+    // cargo: thread 'tests::name' panicked at src/lib.rs:10:5 (older: at 'msg', src/lib.rs:10:5)
+    let cargo = Regex::new(r"thread '([^']+)' panicked at (?:'.*', )?([^\s:]+):(\d+):\d+")
+        .expect("valid cargo regex");
+    // pytest: "____ test_name ____" section headers, then "path.py:12: AssertionError"
+    let pytest_header = Regex::new(r"^_{3,} (\S+) _{3,}$").expect("valid pytest regex");
+    let pytest_location = Regex::new(r"^([\w./-]+\.py):(\d+): ").expect("valid pytest regex");
+    // jest: "● Suite › test name", then "at fn (src/foo.test.js:12:5)" stack frames
+    let jest_header = Regex::new(r"^\s*● (.+)$").expect("valid jest regex");
+    let jest_location = Regex::new(r"^\s*at (?:.* \()?([^\s()]+\.(?:[cm]?[jt]sx?)):(\d+):\d+\)?$")
+        .expect("valid jest regex");
+
+    let mut failures: Vec<TestFailure> = Vec::new();
+    let mut current_test: Option<String> = None;
+    let mut push = |test: &str, path: &str, line: &str| {
+        let failure = TestFailure {
+            test: test.to_string(),
+            path: PathBuf::from(path),
+            line: line.parse().unwrap_or(0),
+        };
+        if failure.line > 0 && !failures.contains(&failure) {
+            failures.push(failure);
+        }
+    };
+
+    for line in output.lines() {
+        if let Some(caps) = cargo.captures(line) {
+            push(&caps[1], &caps[2], &caps[3]);
+        } else if let Some(caps) = pytest_header
+            .captures(line)
+            .or_else(|| jest_header.captures(line))
+        {
+            current_test = Some(caps[1].trim().to_string());
+        } else if let Some(test) = &current_test {
+            if let Some(caps) = pytest_location
+                .captures(line)
+                .or_else(|| jest_location.captures(line))
+            {
+                if !caps[1].contains("node_modules") {
+                    push(test, &caps[1], &caps[2]);
+                }
+            }
+        }
+    }
+    failures
+}
+
+/// Whether a failure location lies in (or right next to) `hunk`
+pub fn failure_in_hunk(failure: &TestFailure, hunk: &Hunk) -> bool {
+    same_file(&failure.path, &hunk.file_path)
+        && new_line_range(hunk).is_some_and(|(start, end)| {
+            failure.line + NEAR_LINES >= start && failure.line <= end + NEAR_LINES
+        })
+}
+
+/// Runners print paths relative to different roots (or absolute), so compare the
+/// trailing components
+fn same_file(a: &Path, b: &Path) -> bool {
+    a.ends_with(b) || b.ends_with(a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{ChangeTag, HunkRange, HunkStatus, LineChange};
+
+    #[test]
+    fn test_parse_runner_output() {
+        let cargo = "---- tests::adds stdout ----\nthread 'tests::adds' panicked at src/math.rs:42:9:\nassertion failed\n";
+        let pytest = "______ test_total ______\n\n    def test_total():\n>       assert total() == 3\n\ntests/test_cart.py:8: \n_ _ _\ncart/core.py:21: AssertionError\n";
+        let jest = "  ● Cart › adds items\n\n    at Object.<anonymous> (src/cart.test.js:12:5)\n    at add (/repo/node_modules/x/index.js:1:1)\n";
+
+        let all = parse_failures(&format!("{}{}{}", cargo, pytest, jest));
+        let found: Vec<_> = all
+            .iter()
+            .map(|f| (f.test.as_str(), f.path.to_str().unwrap(), f.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("tests::adds", "src/math.rs", 42),
+                ("test_total", "tests/test_cart.py", 8),
+                ("test_total", "cart/core.py", 21),
+                ("Cart › adds items", "src/cart.test.js", 12),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failure_matches_nearby_hunk() {
+        let change = |new: usize| LineChange {
+            tag: ChangeTag::Insert,
+            content: "".into(),
+            old_line_num: None,
+            new_line_num: Some(new),
+        };
+        let hunk = Hunk {
+            id: 0,
+            file_path: PathBuf::from("src/math.rs"),
+            old: HunkRange::default(),
+            new: HunkRange::default(),
+            changes: vec![change(39), change(40)],
+            status: HunkStatus::Accepted,
+            drifted: false,
+            comment: None,
+            auto_accepted: None,
+        };
+        let at = |path: &str, line: usize| TestFailure {
+            test: "t".to_string(),
+            path: PathBuf::from(path),
+            line,
+        };
+
+        assert_eq!(new_line_range(&hunk), Some((40, 41)));
+        assert!(failure_in_hunk(&at("/repo/src/math.rs", 42), &hunk));
+        assert!(failure_in_hunk(&at("src/math.rs", 37), &hunk));
+        assert!(!failure_in_hunk(&at("src/math.rs", 60), &hunk));
+        assert!(!failure_in_hunk(&at("src/other.rs", 40), &hunk));
+    }
+}
@@ -0,0 +1,67 @@
+// src/ui/activity.rs - Live timeline of provider steps while a prompt runs
+
+use std::time::Instant;
+
+use crate::activity::{format_elapsed, ActivityTimeline};
+use crate::executor::CommandLine;
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph},
+    Frame,
+};
+
+/// Rows the panel asks for: one per step, capped, plus the border
+pub fn panel_height(timeline: &ActivityTimeline, max_steps: usize) -> u16 {
+    timeline.steps.len().min(max_steps) as u16 + 2
+}
+
+/// One line showing the command being run, cut to the width of `area`
+pub fn render_command_preview(frame: &mut Frame, area: Rect, command: &CommandLine, theme: &Theme) {
+    let text = crate::ui::truncate_line(&command.preview(), area.width.saturating_sub(2) as usize);
+    let line = Line::from(vec![
+        Span::styled("$ ", theme.prompt_style),
+        Span::styled(text, theme.context_style),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+/// Render the most recent steps, oldest first, with the time each one took
+pub fn render_activity(frame: &mut Frame, area: Rect, timeline: &ActivityTimeline, theme: &Theme) {
+    let now = Instant::now();
+    let visible = area.height.saturating_sub(2) as usize;
+    let skip = timeline.steps.len().saturating_sub(visible);
+
+    let lines: Vec<Line> = timeline
+        .steps
+        .iter()
+        .skip(skip)
+        .map(|step| {
+            let (marker, style) = if step.is_running() {
+                (theme.icons.collapsed, theme.status_pending)
+            } else {
+                (theme.icons.accepted, theme.status_accepted)
+            };
+            let mut spans = vec![
+                Span::styled(format!("{} ", marker), style),
+                Span::styled(step.label.clone(), theme.normal_style),
+            ];
+            if let Some(detail) = &step.detail {
+                spans.push(Span::styled(format!("  {}", detail), theme.context_style));
+            }
+            spans.push(Span::styled(
+                format!("  {}", format_elapsed(step.elapsed(now))),
+                theme.context_style,
+            ));
+            Line::from(spans)
+        })
+        .collect();
+
+    let block = Block::default()
+        .title(" Activity ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
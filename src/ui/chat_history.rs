@@ -22,7 +22,12 @@ pub fn render_chat_history(
     let items: Vec<ListItem> = messages
         .iter()
         .map(|msg| {
-            let prefix = if msg.is_user { "> " } else { "◆ " };
+            let icons = theme.icons;
+            let prefix = if msg.is_user {
+                "> ".to_string()
+            } else {
+                format!("{} ", icons.assistant)
+            };
 
             let default_color = if msg.is_user {
                 theme.prompt_style.fg.unwrap_or(Color::Cyan)
@@ -31,10 +36,10 @@ pub fn render_chat_history(
             };
 
             let status_icon = match msg.status {
-                MessageStatus::Success => "✓ ",
-                MessageStatus::Error => "✗ ",
-                MessageStatus::Working => "⧳ ",
-                MessageStatus::Pending => "○ ",
+                MessageStatus::Success => icons.accepted,
+                MessageStatus::Error => icons.rejected,
+                MessageStatus::Working => icons.working,
+                MessageStatus::Pending => icons.pending,
             };
 
             let status_color = match msg.status {
@@ -51,18 +56,12 @@ pub fn render_chat_history(
             };
 
             // Format message with timestamp and status
-            let header = format!("[{}] {}{}", timestamp, status_icon, prefix);
+            let header = format!("[{}] {} {}", timestamp, status_icon, prefix);
             let content = format!("{}{}", msg.content, token_info);
 
             // Truncate long messages for display (can be expanded later)
-            let display_content = if content.len() > (area.width as usize).saturating_sub(10) {
-                format!(
-                    "{}...",
-                    &content[..(area.width as usize).saturating_sub(13)]
-                )
-            } else {
-                content
-            };
+            let display_content =
+                crate::ui::truncate_line(&content, (area.width as usize).saturating_sub(10));
 
             let line = Line::from(vec![
                 Span::styled(
@@ -2,6 +2,9 @@
 
 use ratatui::style::{Color, Modifier, Style};
 
+use crate::config::DisplayConfig;
+use crate::ui::icons::{Icons, UNICODE};
+
 #[derive(Debug, Clone)]
 pub struct Theme {
     // Diff colors
@@ -18,7 +21,16 @@ pub struct Theme {
     pub error_style: Style,
     pub prompt_style: Style,
     pub normal_style: Style,
+    /// Hints, counts and other secondary text
+    pub muted_style: Style,
+    /// Names and links to pick out from normal text
+    pub accent_style: Style,
     pub border_style: Style,
+    /// Border of the pane given focus in the wide layout
+    pub focused_border_style: Style,
+
+    /// Marker glyphs of the configured icon set
+    pub icons: &'static Icons,
 }
 
 impl Theme {
@@ -61,7 +73,11 @@ impl Theme {
                 .bg(Color::Rgb(22, 22, 22))
                 .add_modifier(Modifier::BOLD),
             normal_style: Style::default().fg(text_primary),
+            muted_style: Style::default().fg(text_muted),
+            accent_style: Style::default().fg(accent_blue),
             border_style: Style::default().fg(surface_border),
+            focused_border_style: Style::default().fg(accent_blue),
+            icons: &UNICODE,
         }
     }
 
@@ -85,11 +101,30 @@ impl Theme {
         self.status_accepted
     }
 
+    /// Style for the border of the focused pane
+    pub fn focused_border(&self) -> Style {
+        self.focused_border_style
+    }
+
     /// Style for rejected markers
     pub fn rejected_marker(&self) -> Style {
         self.status_rejected
     }
 
+    /// The theme of the `display` settings: `color_scheme` (`light`, else
+    /// dark) with the glyphs of `icons`
+    pub fn for_display(display: &DisplayConfig) -> Self {
+        let theme = if display.color_scheme == "light" {
+            Self::light()
+        } else {
+            Self::dark()
+        };
+        Self {
+            icons: Icons::for_set(display.icons),
+            ..theme
+        }
+    }
+
     pub fn light() -> Self {
         Self {
             added_style: Style::default()
@@ -122,7 +157,11 @@ impl Theme {
                 .fg(Color::Indexed(91)) // Dark purple
                 .add_modifier(Modifier::BOLD),
             normal_style: Style::default().fg(Color::Black),
+            muted_style: Style::default().fg(Color::Indexed(244)), // Mid gray
+            accent_style: Style::default().fg(Color::Indexed(25)), // Dark blue
             border_style: Style::default().fg(Color::Indexed(240)),
+            focused_border_style: Style::default().fg(Color::Indexed(25)), // Dark blue
+            icons: &UNICODE,
         }
     }
 }
@@ -0,0 +1,89 @@
+// src/ui/config_errors.rs - Settings of the config file that were left out
+
+use crate::config::ConfigProblem;
+use crate::i18n::{t, tf};
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use std::path::Path;
+
+/// Render every problem of the config file with its line and key
+pub fn render_config_errors(
+    frame: &mut Frame,
+    area: Rect,
+    problems: &[ConfigProblem],
+    selected: usize,
+    path: &Path,
+    theme: &Theme,
+) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(tf("config_errors.title", &[("path", &path.display())]))
+        .title_style(theme.error_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.error_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(3),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    let intro = Paragraph::new(tf("config_errors.intro", &[("count", &problems.len())]))
+        .style(theme.normal_style)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(intro, chunks[0]);
+
+    let items: Vec<ListItem> = problems
+        .iter()
+        .map(|problem| {
+            let line = problem
+                .line
+                .map_or_else(|| "    ".to_string(), |l| format!("{:>4}", l));
+            let mut spans = vec![Span::styled(format!("{} ", line), theme.context_style)];
+            if let Some(name) = problem.file.as_deref().and_then(Path::file_name) {
+                spans.push(Span::styled(
+                    format!("{}: ", name.to_string_lossy()),
+                    theme.context_style,
+                ));
+            }
+            spans.push(Span::styled(
+                problem
+                    .key
+                    .clone()
+                    .unwrap_or_else(|| t("config_errors.whole_file").to_string()),
+                theme.header_style,
+            ));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items).highlight_style(theme.selected_style);
+    let mut list_state = ListState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(list, chunks[1], &mut list_state);
+
+    // The full message of the selected problem, which names what is expected
+    if let Some(problem) = problems.get(selected) {
+        let message = Paragraph::new(problem.message.clone())
+            .style(theme.error_style)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(message, chunks[2]);
+    }
+
+    let footer = Paragraph::new(Line::from(Span::styled(
+        theme.icons.hints(t("config_errors.footer")),
+        theme.muted_style,
+    )));
+    frame.render_widget(footer, chunks[3]);
+}
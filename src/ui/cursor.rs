@@ -0,0 +1,123 @@
+// src/ui/cursor.rs - The terminal's own cursor in text inputs
+//
+// Inputs place the hardware cursor with `Frame::set_cursor_position` instead
+// of drawing a `│` into their text, so terminals and screen readers follow the
+// real insertion point. They wrap their text here rather than leaving it to
+// `Paragraph`, whose wrapping can't be asked where a byte ended up.
+
+use std::ops::Range;
+
+use crossterm::cursor::SetCursorStyle;
+use ratatui::layout::{Position, Rect};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::input::modes::InputMode;
+
+/// The cursor shape for an input in `mode`: a bar where keys type text, a
+/// block where they are commands
+pub fn shape(mode: InputMode) -> SetCursorStyle {
+    match mode {
+        InputMode::Insert | InputMode::Command | InputMode::Search => SetCursorStyle::SteadyBar,
+        _ => SetCursorStyle::SteadyBlock,
+    }
+}
+
+/// Byte ranges of `text`'s rows wrapped to `width` columns: at the last space
+/// that fits, or mid-word when a word is wider than a row. Newlines end a row
+/// and belong to none.
+pub fn wrap_rows(text: &str, width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut line_start = 0;
+    for line in text.split('\n') {
+        let mut start = line_start;
+        let mut used = 0;
+        // Where a row could end after a space, and the width up to it
+        let mut space: Option<(usize, usize)> = None;
+        for (idx, grapheme) in line.grapheme_indices(true) {
+            let at = line_start + idx;
+            let grapheme_width = grapheme.width();
+            while used > 0 && used + grapheme_width > width {
+                match space.take() {
+                    Some((end, end_used)) if end > start => {
+                        rows.push(start..end);
+                        start = end;
+                        used -= end_used;
+                    }
+                    _ => {
+                        rows.push(start..at);
+                        start = at;
+                        used = 0;
+                    }
+                }
+            }
+            used += grapheme_width;
+            if grapheme.chars().all(char::is_whitespace) {
+                space = Some((at + grapheme.len(), used));
+            }
+        }
+        rows.push(start..line_start + line.len());
+        line_start += line.len() + 1;
+    }
+    rows
+}
+
+/// Row and column of byte `cursor` in `rows` of `text`. A cursor at the end of
+/// a full row sits at the start of the next one.
+pub fn locate(text: &str, rows: &[Range<usize>], cursor: usize, width: usize) -> (usize, usize) {
+    let row = rows
+        .iter()
+        .rposition(|row| row.start <= cursor)
+        .unwrap_or(0);
+    let Some(range) = rows.get(row) else {
+        return (0, 0);
+    };
+    let column = text[range.start..cursor.min(range.end)].width();
+    if column >= width.max(1) {
+        (row + 1, 0)
+    } else {
+        (row, column)
+    }
+}
+
+/// First row to show in `height` rows so that `cursor_row` is among them
+pub fn scroll_to(cursor_row: usize, height: u16) -> usize {
+    cursor_row.saturating_sub((height as usize).saturating_sub(1))
+}
+
+/// The screen cell for `row`/`column` of `area` scrolled by `scroll` rows,
+/// kept inside `area`
+pub fn position(area: Rect, scroll: usize, row: usize, column: usize) -> Position {
+    let row = row
+        .saturating_sub(scroll)
+        .min(area.height.saturating_sub(1) as usize);
+    let column = column.min(area.width.saturating_sub(1) as usize);
+    Position::new(area.x + column as u16, area.y + row as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wraps_at_spaces_and_locates_the_cursor() {
+        let text = "fix the parser\nnow";
+        let rows = wrap_rows(text, 8);
+        let slices: Vec<&str> = rows.iter().map(|r| &text[r.clone()]).collect();
+        assert_eq!(slices, ["fix the ", "parser", "now"]);
+
+        assert_eq!(locate(text, &rows, 0, 8), (0, 0));
+        assert_eq!(locate(text, &rows, 5, 8), (0, 5));
+        // The start of a wrapped row, not the end of the one before
+        assert_eq!(locate(text, &rows, 8, 8), (1, 0));
+        assert_eq!(locate(text, &rows, 14, 8), (1, 6));
+        assert_eq!(locate(text, &rows, text.len(), 8), (2, 3));
+
+        // A word wider than a row is split; a full row puts the cursor below
+        let rows = wrap_rows("abcdefghij", 4);
+        assert_eq!(rows, [0..4, 4..8, 8..10]);
+        assert_eq!(locate("abcd", &wrap_rows("abcd", 4), 4, 4), (1, 0));
+        assert_eq!(wrap_rows("", 4).len(), 1);
+    }
+}
@@ -66,6 +66,26 @@ pub fn open_file_in_editor(
     Ok(())
 }
 
+/// Run an interactive command (e.g. a provider login) in the foreground.
+/// Suspends the TUI for the duration and returns whether the command succeeded.
+pub fn run_interactive_command(
+    terminal: &mut ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>,
+    program: &str,
+    args: &[String],
+) -> Result<bool> {
+    suspend_tui()?;
+    let status = Command::new(program).args(args).status();
+    resume_tui(terminal)?;
+
+    match status {
+        Ok(status) => Ok(status.success()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            anyhow::bail!("Command '{}' not found in PATH", program)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
 /// Check if a command exists in PATH
 fn command_exists(cmd: &str) -> bool {
     Command::new("which")
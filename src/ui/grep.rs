@@ -0,0 +1,79 @@
+// src/ui/grep.rs - Matches of a search across all sessions (:grep)
+
+use crate::i18n::{t, tf};
+use crate::state::GrepView;
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+/// Render the matches over `area`, grouped under their session's title
+pub fn render_grep(frame: &mut Frame, area: Rect, grep: &GrepView, theme: &Theme) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(tf("grep.title", &[("query", &grep.query)]))
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(inner);
+
+    // One heading row per session; the selected row skips over them
+    let mut items = Vec::new();
+    let mut selected_row = 0;
+    let mut hit_idx = 0;
+    for group in &grep.groups {
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled(
+                group.title.clone(),
+                theme.header_style.add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("  ({})", group.hits.len()), theme.context_style),
+        ])));
+        for hit in &group.hits {
+            if hit_idx == grep.selected {
+                selected_row = items.len();
+            }
+            hit_idx += 1;
+            let who = if hit.is_user {
+                t("grep.you")
+            } else {
+                t("grep.reply")
+            };
+            items.push(ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!(
+                        "  {} {:<5} ",
+                        hit.timestamp
+                            .with_timezone(&chrono::Local)
+                            .format("%m-%d %H:%M"),
+                        who
+                    ),
+                    theme.context_style,
+                ),
+                Span::styled(hit.snippet.clone(), theme.normal_style),
+            ])));
+        }
+    }
+
+    let list = List::new(items).highlight_style(theme.selected_style);
+    let mut list_state = ListState::default().with_selected(Some(selected_row));
+    frame.render_stateful_widget(list, chunks[0], &mut list_state);
+
+    let footer = Paragraph::new(Line::from(Span::styled(
+        theme.icons.hints(t("grep.footer")),
+        theme.muted_style,
+    )));
+    frame.render_widget(footer, chunks[1]);
+}
@@ -2,17 +2,20 @@
 
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::Modifier,
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Paragraph},
     Frame,
 };
 
-use crate::state::State;
+use crate::state::{Mode, ReviewProgress, State};
 use crate::ui::colors::Theme;
 use crate::ui::logo::render_logo_compact;
 use crate::ui::status_bar::format_tokens_cost;
 
+/// Width of the review gauge, borders included
+const GAUGE_WIDTH: u16 = 36;
+
 /// Render the top header bar inspired by OpenCode's layout.
 pub fn render_header(frame: &mut Frame, area: Rect, state: &State, theme: &Theme) {
     // Split into left (logo + session) and right (status indicators)
@@ -54,16 +57,23 @@ pub fn render_header(frame: &mut Frame, area: Rect, state: &State, theme: &Theme
         .map(|p| p.name().to_string())
         .unwrap_or_else(|| "No provider".into());
 
-    let session_line = Line::from(vec![
+    let mut session_spans = vec![
         Span::styled(
             session_title,
-            Style::default()
-                .fg(theme.normal_style.fg.unwrap_or(Color::White))
-                .add_modifier(Modifier::BOLD),
+            theme.normal_style.add_modifier(Modifier::BOLD),
         ),
         Span::raw("  "),
-        Span::styled(provider, Style::default().fg(Color::Rgb(120, 170, 255))),
-    ]);
+        Span::styled(provider, theme.accent_style),
+        Span::styled(" (Ctrl+P switch)", theme.muted_style),
+    ];
+    if let Some(preamble) = &state.preamble {
+        session_spans.push(Span::raw("  "));
+        session_spans.push(Span::styled(
+            format!("{} {}", theme.icons.note, preamble.label()),
+            theme.status_pending,
+        ));
+    }
+    let session_line = Line::from(session_spans);
 
     let session_block = Paragraph::new(session_line)
         .block(
@@ -74,7 +84,19 @@ pub fn render_header(frame: &mut Frame, area: Rect, state: &State, theme: &Theme
                 .title(" Project / Session "),
         )
         .alignment(ratatui::layout::Alignment::Left);
-    frame.render_widget(session_block, left_inner[1]);
+    // During review the session box makes room for the review's progress
+    let progress = state.review_progress();
+    let room = left_inner[1].width >= GAUGE_WIDTH + 20;
+    if state.mode == Mode::DiffReview && progress.total() > 0 && room {
+        let parts = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(10), Constraint::Length(GAUGE_WIDTH)])
+            .split(left_inner[1]);
+        frame.render_widget(session_block, parts[0]);
+        render_review_gauge(frame, parts[1], progress, theme);
+    } else {
+        frame.render_widget(session_block, left_inner[1]);
+    }
 
     // Right: status indicators
     let right = chunks[1];
@@ -98,3 +120,78 @@ pub fn render_header(frame: &mut Frame, area: Rect, state: &State, theme: &Theme
 
     frame.render_widget(status, right);
 }
+
+/// A bar of accepted, rejected and pending hunks with their counts
+fn render_review_gauge(frame: &mut Frame, area: Rect, progress: ReviewProgress, theme: &Theme) {
+    let counts = format!(
+        " {}{} {}{} {} left",
+        progress.accepted,
+        theme.icons.accepted,
+        progress.rejected,
+        theme.icons.rejected,
+        progress.pending
+    );
+    let width = (area.width.saturating_sub(2) as usize).saturating_sub(counts.chars().count());
+    let (accepted, rejected, pending) = gauge_cells(progress, width);
+    let line = Line::from(vec![
+        Span::styled(theme.icons.bar_full.repeat(accepted), theme.status_accepted),
+        Span::styled(theme.icons.bar_full.repeat(rejected), theme.status_rejected),
+        Span::styled(theme.icons.bar_empty.repeat(pending), theme.status_pending),
+        Span::styled(counts, theme.normal_style),
+    ]);
+
+    let gauge = Paragraph::new(line).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(theme.border_style)
+            .title(format!(" Review: {} hunks ", progress.total())),
+    );
+    frame.render_widget(gauge, area);
+}
+
+/// Cells of a `width` wide bar for each of the accepted, rejected and pending
+/// hunks; any hunk state present gets at least one cell
+fn gauge_cells(progress: ReviewProgress, width: usize) -> (usize, usize, usize) {
+    let total = progress.total();
+    if total == 0 || width == 0 {
+        return (0, 0, 0);
+    }
+    let share = |n: usize| {
+        if n == 0 {
+            0
+        } else {
+            (n * width / total).max(1)
+        }
+    };
+    let accepted = share(progress.accepted);
+    let rejected = share(progress.rejected).min(width - accepted.min(width));
+    let pending = width.saturating_sub(accepted + rejected);
+    // Rounding down leaves the slack to pending; give it back once all is decided
+    if progress.pending == 0 && pending > 0 {
+        if progress.rejected > 0 {
+            return (accepted, rejected + pending, 0);
+        }
+        return (accepted + pending, rejected, 0);
+    }
+    (accepted, rejected, pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gauge_cells_fill_the_width() {
+        let progress = |accepted, rejected, pending| ReviewProgress {
+            accepted,
+            rejected,
+            pending,
+        };
+        assert_eq!(gauge_cells(progress(0, 0, 12), 20), (0, 0, 20));
+        assert_eq!(gauge_cells(progress(3, 1, 8), 12), (3, 1, 8));
+        assert_eq!(gauge_cells(progress(1, 0, 99), 10), (1, 0, 9));
+        assert_eq!(gauge_cells(progress(2, 1, 0), 10), (6, 4, 0));
+        assert_eq!(gauge_cells(progress(0, 0, 0), 10), (0, 0, 0));
+    }
+}
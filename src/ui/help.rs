@@ -1,5 +1,6 @@
 // src/ui/help.rs - Context-sensitive help overlay
 
+use crate::i18n::t;
 use crate::state::Mode;
 use crate::ui::colors::Theme;
 use ratatui::{
@@ -17,7 +18,7 @@ pub fn render_help(frame: &mut Frame, area: Rect, mode: &Mode, theme: &Theme) {
     let paragraph = Paragraph::new(help_text)
         .block(
             Block::default()
-                .title(" Help ")
+                .title(t("help.title"))
                 .title_style(Style::default().fg(Color::White))
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
@@ -42,23 +43,23 @@ fn get_help_text(mode: &Mode) -> Vec<Line<'static>> {
 
     // Global keybindings section
     let mut lines = vec![
-        Line::from(Span::styled("Global Keybindings", header_style)),
+        Line::from(Span::styled(t("help.global"), header_style)),
         Line::from(""),
         Line::from(vec![
             Span::styled("  Tab     ", key_style),
-            Span::raw("Toggle chat history"),
+            Span::raw(t("help.toggle_chat")),
         ]),
         Line::from(vec![
             Span::styled("  ?       ", key_style),
-            Span::raw("Show this help"),
+            Span::raw(t("help.show_help")),
         ]),
         Line::from(vec![
             Span::styled("  :       ", key_style),
-            Span::raw("Enter command mode"),
+            Span::raw(t("help.command_mode")),
         ]),
         Line::from(vec![
             Span::styled("  Ctrl+C  ", key_style),
-            Span::raw("Quit"),
+            Span::raw(t("help.quit")),
         ]),
         Line::from(""),
     ];
@@ -67,170 +68,178 @@ fn get_help_text(mode: &Mode) -> Vec<Line<'static>> {
     match mode {
         Mode::ProviderSelect => {
             lines.extend(vec![
-                Line::from(Span::styled("Provider Selection", header_style)),
+                Line::from(Span::styled(t("help.provider_select"), header_style)),
                 Line::from(""),
                 Line::from(vec![
                     Span::styled("  j/k     ", key_style),
-                    Span::raw("Navigate up/down"),
+                    Span::raw(t("help.navigate")),
                 ]),
                 Line::from(vec![
                     Span::styled("  g/G     ", key_style),
-                    Span::raw("Jump to first/last"),
+                    Span::raw(t("help.jump_first_last")),
                 ]),
                 Line::from(vec![
                     Span::styled("  Enter   ", key_style),
-                    Span::raw("Select provider"),
+                    Span::raw(t("help.select_provider")),
                 ]),
                 Line::from(vec![
                     Span::styled("  q/Esc   ", key_style),
-                    Span::raw("Quit"),
+                    Span::raw(t("help.quit")),
                 ]),
             ]);
         }
         Mode::PromptEntry | Mode::Help => {
             lines.extend(vec![
-                Line::from(Span::styled("Prompt Entry", header_style)),
+                Line::from(Span::styled(t("help.prompt_entry"), header_style)),
                 Line::from(""),
                 Line::from(vec![
                     Span::styled("  Enter   ", key_style),
-                    Span::raw("Submit prompt"),
+                    Span::raw(t("help.submit_prompt")),
                 ]),
                 Line::from(vec![
                     Span::styled("  Esc     ", key_style),
-                    Span::raw("Back to provider selection"),
+                    Span::raw(t("help.back_to_providers")),
                 ]),
                 Line::from(""),
-                Line::from(Span::styled("Vi Navigation", header_style)),
+                Line::from(Span::styled(t("help.vi_navigation"), header_style)),
                 Line::from(""),
                 Line::from(vec![
                     Span::styled("  h/l     ", key_style),
-                    Span::raw("Move cursor left/right"),
+                    Span::raw(t("help.cursor_left_right")),
                 ]),
                 Line::from(vec![
                     Span::styled("  w/b     ", key_style),
-                    Span::raw("Word forward/backward"),
+                    Span::raw(t("help.word_forward_backward")),
                 ]),
                 Line::from(vec![
                     Span::styled("  0/$     ", key_style),
-                    Span::raw("Start/end of line"),
+                    Span::raw(t("help.line_start_end")),
                 ]),
                 Line::from(vec![
                     Span::styled("  Ctrl+U  ", key_style),
-                    Span::raw("Clear line"),
+                    Span::raw(t("help.clear_line")),
                 ]),
             ]);
         }
         Mode::DiffReview => {
             lines.extend(vec![
-                Line::from(Span::styled("Diff Review", header_style)),
+                Line::from(Span::styled(t("help.diff_review"), header_style)),
                 Line::from(""),
                 Line::from(vec![
                     Span::styled("  j/k     ", key_style),
-                    Span::raw("Next/previous line"),
+                    Span::raw(t("help.next_prev_line")),
                 ]),
                 Line::from(vec![
                     Span::styled("  J/K     ", key_style),
-                    Span::raw("Next/previous file"),
+                    Span::raw(t("help.next_prev_file")),
                 ]),
                 Line::from(""),
                 Line::from(vec![
                     Span::styled("  y       ", key_style),
-                    Span::raw("Accept current line"),
+                    Span::raw(t("help.accept_line")),
                 ]),
                 Line::from(vec![
                     Span::styled("  n       ", key_style),
-                    Span::raw("Reject current line"),
+                    Span::raw(t("help.reject_line")),
                 ]),
                 Line::from(vec![
                     Span::styled("  a       ", key_style),
-                    Span::raw("Accept all"),
+                    Span::raw(t("help.accept_all")),
                 ]),
                 Line::from(vec![
                     Span::styled("  r       ", key_style),
-                    Span::raw("Reject all"),
+                    Span::raw(t("help.reject_all")),
+                ]),
+                Line::from(vec![
+                    Span::styled("  o       ", key_style),
+                    Span::raw(t("help.apply_hunk_edit")),
+                ]),
+                Line::from(vec![
+                    Span::styled("  O       ", key_style),
+                    Span::raw(t("help.ignore_override")),
                 ]),
                 Line::from(vec![
                     Span::styled("  Enter   ", key_style),
-                    Span::raw("Apply accepted changes"),
+                    Span::raw(t("help.apply")),
                 ]),
                 Line::from(vec![
                     Span::styled("  q/Esc   ", key_style),
-                    Span::raw("Cancel and go back"),
+                    Span::raw(t("help.cancel_back")),
                 ]),
             ]);
         }
         Mode::Confirmation => {
             lines.extend(vec![
-                Line::from(Span::styled("Confirmation", header_style)),
+                Line::from(Span::styled(t("help.confirmation"), header_style)),
                 Line::from(""),
                 Line::from(vec![
                     Span::styled("  y/Enter ", key_style),
-                    Span::raw("Confirm action"),
+                    Span::raw(t("help.confirm_action")),
                 ]),
                 Line::from(vec![
                     Span::styled("  n/Esc   ", key_style),
-                    Span::raw("Cancel"),
+                    Span::raw(t("help.cancel")),
                 ]),
             ]);
         }
         Mode::ChatHistory => {
             lines.extend(vec![
-                Line::from(Span::styled("Chat History", header_style)),
+                Line::from(Span::styled(t("help.chat_history"), header_style)),
                 Line::from(""),
                 Line::from(vec![
                     Span::styled("  j/k     ", key_style),
-                    Span::raw("Scroll up/down"),
+                    Span::raw(t("help.scroll")),
                 ]),
                 Line::from(vec![
                     Span::styled("  g/G     ", key_style),
-                    Span::raw("Jump to top/bottom"),
+                    Span::raw(t("help.jump_top_bottom")),
                 ]),
                 Line::from(vec![
                     Span::styled("  /       ", key_style),
-                    Span::raw("Search messages"),
+                    Span::raw(t("help.search")),
                 ]),
                 Line::from(vec![
                     Span::styled("  n/N     ", key_style),
-                    Span::raw("Next/prev search result"),
+                    Span::raw(t("help.next_prev_result")),
                 ]),
                 Line::from(vec![
                     Span::styled("  Esc     ", key_style),
-                    Span::raw("Close chat history"),
+                    Span::raw(t("help.close_chat")),
                 ]),
             ]);
         }
         Mode::CommandMode => {
             lines.extend(vec![
-                Line::from(Span::styled("Command Mode", header_style)),
+                Line::from(Span::styled(t("help.command_mode_title"), header_style)),
                 Line::from(""),
                 Line::from(vec![
                     Span::styled("  Enter   ", key_style),
-                    Span::raw("Execute command"),
+                    Span::raw(t("help.execute_command")),
                 ]),
                 Line::from(vec![
                     Span::styled("  Esc     ", key_style),
-                    Span::raw("Cancel"),
+                    Span::raw(t("help.cancel")),
                 ]),
                 Line::from(""),
-                Line::from(Span::styled("Commands:", dim_style)),
+                Line::from(Span::styled(t("help.commands"), dim_style)),
                 Line::from(vec![
                     Span::styled("  :config ", key_style),
-                    Span::raw("Show configuration"),
+                    Span::raw(t("help.show_config")),
                 ]),
                 Line::from(vec![
                     Span::styled("  :help   ", key_style),
-                    Span::raw("Show help"),
+                    Span::raw(t("help.help_command")),
                 ]),
                 Line::from(vec![
                     Span::styled("  :quit   ", key_style),
-                    Span::raw("Quit application"),
+                    Span::raw(t("help.quit_app")),
                 ]),
             ]);
         }
         _ => {
             lines.extend(vec![
                 Line::from(""),
-                Line::from(Span::styled("Press ? to close help", dim_style)),
+                Line::from(Span::styled(t("help.close_help"), dim_style)),
             ]);
         }
     }
@@ -238,7 +247,7 @@ fn get_help_text(mode: &Mode) -> Vec<Line<'static>> {
     // Footer
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
-        "Press ? or Esc to close",
+        t("help.footer"),
         Style::default().fg(Color::DarkGray),
     )));
 
@@ -0,0 +1,128 @@
+// src/ui/history.rs - Journal of past applies (:history)
+
+use crate::i18n::{t, tf};
+use crate::state::HistoryView;
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+/// Render the apply history over `area`: the entries, then the selected one's files
+pub fn render_history(frame: &mut Frame, area: Rect, history: &HistoryView, theme: &Theme) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(t("history.title"))
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if history.entries.is_empty() {
+        let empty = Paragraph::new(t("history.empty")).style(theme.context_style);
+        frame.render_widget(empty, inner);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    let header = Row::new(
+        [
+            t("history.when"),
+            t("history.provider"),
+            t("history.files"),
+            t("history.hunks"),
+            t("history.result"),
+        ]
+        .into_iter()
+        .map(Cell::from),
+    )
+    .style(theme.header_style);
+
+    let rows = history.entries.iter().map(|entry| {
+        let result_style = if entry.success {
+            theme.status_accepted
+        } else {
+            theme.status_rejected
+        };
+        Row::new(vec![
+            Cell::from(
+                entry
+                    .timestamp
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+            ),
+            Cell::from(entry.provider.clone().unwrap_or_else(|| "-".to_string())),
+            Cell::from(entry.files.len().to_string()),
+            Cell::from(entry.hunks.len().to_string()),
+            Cell::from(Span::styled(entry.result.clone(), result_style)),
+        ])
+        .style(theme.normal_style)
+    });
+
+    let widths = [
+        Constraint::Length(16),
+        Constraint::Length(12),
+        Constraint::Length(5),
+        Constraint::Length(5),
+        Constraint::Min(20),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .row_highlight_style(theme.selected_style);
+    let mut table_state = TableState::default().with_selected(Some(history.selected));
+    frame.render_stateful_widget(table, chunks[0], &mut table_state);
+
+    if let Some(entry) = history.selected_entry() {
+        let mut lines = Vec::new();
+        if let Some(session) = &entry.session {
+            lines.push(Line::from(Span::styled(
+                tf("history.session", &[("session", session)]),
+                theme.context_style,
+            )));
+        }
+        for file in &entry.files {
+            let backed_up = entry.backups.iter().any(|b| &b.file == file);
+            let (marker, style) = if backed_up {
+                (theme.icons.current, theme.status_accepted)
+            } else {
+                (theme.icons.other, theme.context_style)
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{} ", marker), style),
+                Span::styled(file.display().to_string(), theme.normal_style),
+            ]));
+        }
+        let details = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_style(theme.border_style),
+        );
+        frame.render_widget(details, chunks[1]);
+    }
+
+    let restore_hint = match history.selected_entry() {
+        Some(entry) if entry.restorable() => t("history.restore"),
+        _ => t("history.nothing_to_restore"),
+    };
+    let footer = Paragraph::new(Line::from(Span::styled(
+        theme
+            .icons
+            .hints(&tf("history.footer", &[("restore", &restore_hint)])),
+        theme.muted_style,
+    )));
+    frame.render_widget(footer, chunks[2]);
+}
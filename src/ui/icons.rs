@@ -0,0 +1,258 @@
+// src/ui/icons.rs - Glyphs for markers and badges, by icon set
+
+use crate::config::IconSet;
+
+/// The glyphs the UI draws as markers, bars and separators. Each is one
+/// column wide except `arrow`, which is two in ASCII.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Icons {
+    /// An accepted hunk, a passing check, a finished step
+    pub accepted: &'static str,
+    /// A rejected hunk, a failed message or test
+    pub rejected: &'static str,
+    /// A hunk or message still waiting
+    pub pending: &'static str,
+    /// A message being worked on
+    pub working: &'static str,
+    /// Guard rules and other warnings
+    pub warning: &'static str,
+    /// A comment on a hunk, the project preamble
+    pub note: &'static str,
+    /// Replies of the assistant in the chat
+    pub assistant: &'static str,
+    /// A folded group or section, a step in progress
+    pub collapsed: &'static str,
+    /// An unfolded group
+    pub expanded: &'static str,
+    /// The current session, a recording macro
+    pub current: &'static str,
+    /// Another entry of a list of sessions or files
+    pub other: &'static str,
+    /// A session that kept a review or draft
+    pub kept: &'static str,
+    /// The selected entry of a list
+    pub pointer: &'static str,
+    /// List items and separators
+    pub bullet: &'static str,
+    /// A pinned file in the sidebar
+    pub pinned: &'static str,
+    /// A file in the sidebar that isn't pinned, a space in shown whitespace
+    pub dot: &'static str,
+    /// Filled and empty cells of a progress bar, and a drawn cursor
+    pub bar_full: &'static str,
+    pub bar_empty: &'static str,
+    /// Partly filled cells of a bar by eighths filled (the first is unused);
+    /// empty when a bar only draws whole cells
+    pub bar_partial: &'static [&'static str],
+    /// Between key hints and between the sides of a side-by-side diff
+    pub separator: &'static str,
+    /// A rename, the action a key runs, a tab in whitespace
+    pub arrow: &'static str,
+    /// The start of a row continuing a wrapped line
+    pub wrapped: &'static str,
+    /// Frames of the spinner shown while something runs
+    pub spinner: &'static [&'static str],
+}
+
+const BRAILLE_SPINNER: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const EIGHTHS: &[&str] = &["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+pub const UNICODE: Icons = Icons {
+    accepted: "✓",
+    rejected: "✗",
+    pending: "○",
+    working: "…",
+    warning: "⚠",
+    note: "✎",
+    assistant: "◆",
+    collapsed: "▸",
+    expanded: "▾",
+    current: "●",
+    other: "○",
+    kept: "◐",
+    pointer: "▷",
+    bullet: "•",
+    pinned: "▪",
+    dot: "·",
+    bar_full: "█",
+    bar_empty: "░",
+    bar_partial: EIGHTHS,
+    separator: "│",
+    arrow: "→",
+    wrapped: "↪",
+    spinner: BRAILLE_SPINNER,
+};
+
+/// Font Awesome glyphs of a patched Nerd Font
+pub const NERD_FONT: Icons = Icons {
+    accepted: "\u{f00c}",
+    rejected: "\u{f00d}",
+    pending: "\u{f10c}",
+    working: "\u{f252}",
+    warning: "\u{f071}",
+    note: "\u{f040}",
+    assistant: "\u{f544}",
+    collapsed: "\u{f0da}",
+    expanded: "\u{f0d7}",
+    current: "\u{f111}",
+    other: "\u{f10c}",
+    kept: "\u{f042}",
+    pointer: "\u{f054}",
+    bullet: "•",
+    pinned: "\u{f08d}",
+    dot: "·",
+    bar_full: "█",
+    bar_empty: "░",
+    bar_partial: EIGHTHS,
+    separator: "│",
+    arrow: "\u{f061}",
+    wrapped: "↪",
+    spinner: BRAILLE_SPINNER,
+};
+
+/// Plain ASCII; hunk states read as the keys that set them
+pub const ASCII: Icons = Icons {
+    accepted: "y",
+    rejected: "n",
+    pending: "?",
+    working: ".",
+    warning: "!",
+    note: "~",
+    assistant: "*",
+    collapsed: ">",
+    expanded: "v",
+    current: "*",
+    other: "o",
+    kept: "~",
+    pointer: ">",
+    bullet: "-",
+    pinned: "#",
+    dot: ".",
+    bar_full: "#",
+    bar_empty: ".",
+    bar_partial: &[],
+    separator: "|",
+    arrow: "->",
+    wrapped: "\\",
+    spinner: &["|", "/", "-", "\\"],
+};
+
+impl Icons {
+    /// Marker of a hunk accepted (`Some(true)`), rejected or still pending
+    pub fn status(&self, accepted: Option<bool>) -> &'static str {
+        match accepted {
+            Some(true) => self.accepted,
+            Some(false) => self.rejected,
+            None => self.pending,
+        }
+    }
+
+    /// Key hints with this set's separator. Hints are written with `│`
+    /// between them, in the code and in locale files.
+    pub fn hints(&self, text: &str) -> String {
+        text.replace('│', self.separator)
+    }
+
+    /// The glyphs of `set`; `auto` falls back to ASCII when the locale isn't UTF-8
+    pub fn for_set(set: IconSet) -> &'static Icons {
+        match set {
+            IconSet::Unicode => &UNICODE,
+            IconSet::NerdFont => &NERD_FONT,
+            IconSet::Ascii => &ASCII,
+            IconSet::Auto if locale_is_utf8(|name| std::env::var(name).ok()) => &UNICODE,
+            IconSet::Auto => &ASCII,
+        }
+    }
+}
+
+/// Whether the locale the terminal runs in encodes UTF-8. The first of
+/// `LC_ALL`, `LC_CTYPE` and `LANG` that is set decides, as in libc; Windows
+/// terminals always take Unicode.
+fn locale_is_utf8(var: impl Fn(&str) -> Option<String>) -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+    let Some(locale) = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| var(name).filter(|value| !value.is_empty()))
+    else {
+        return false;
+    };
+    let locale = locale.to_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_width::UnicodeWidthStr;
+
+    #[test]
+    fn test_locale_decides_the_fallback() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert!(locale_is_utf8(env(&[("LANG", "en_US.UTF-8")])));
+        assert!(locale_is_utf8(env(&[
+            ("LC_CTYPE", "C.utf8"),
+            ("LANG", "C")
+        ])));
+        assert!(!locale_is_utf8(env(&[
+            ("LC_ALL", "C"),
+            ("LANG", "en_US.UTF-8")
+        ])));
+        assert!(!locale_is_utf8(env(&[("LC_ALL", ""), ("LANG", "POSIX")])));
+        assert!(!locale_is_utf8(env(&[])));
+
+        // Markers are laid out as one column
+        for icons in [&UNICODE, &ASCII] {
+            assert_eq!(icons.accepted.width(), 1);
+            assert_eq!(icons.warning.width(), 1);
+            assert_eq!(icons.wrapped.width(), 1);
+        }
+    }
+
+    #[test]
+    fn test_ascii_set_is_ascii_and_tells_states_apart() {
+        let a = &ASCII;
+        let glyphs = [
+            a.accepted,
+            a.rejected,
+            a.pending,
+            a.working,
+            a.warning,
+            a.note,
+            a.assistant,
+            a.collapsed,
+            a.expanded,
+            a.current,
+            a.other,
+            a.kept,
+            a.pointer,
+            a.bullet,
+            a.pinned,
+            a.dot,
+            a.bar_full,
+            a.bar_empty,
+            a.separator,
+            a.arrow,
+            a.wrapped,
+        ];
+        assert!(glyphs
+            .iter()
+            .chain(a.spinner)
+            .chain(a.bar_partial)
+            .all(|g| g.is_ascii()));
+
+        // A hunk's state can't be mistaken for a fold marker
+        let markers = [a.accepted, a.rejected, a.pending, a.collapsed, a.expanded];
+        for (idx, marker) in markers.iter().enumerate() {
+            assert!(!markers[idx + 1..].contains(marker), "{} twice", marker);
+        }
+        assert_eq!(a.hints("j/k move │ q quit"), "j/k move | q quit");
+    }
+}
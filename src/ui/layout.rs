@@ -1,6 +1,22 @@
 // src/ui/layout.rs - Layout helper functions
 
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{LayoutConfig, SidebarPosition};
+
+/// Smallest terminal the UI is drawn in; below it a "please enlarge" screen
+/// is shown instead
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 15;
+
+/// Whether `area` is below the minimum size
+pub fn too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
 
 /// Responsive layout breakpoints (in columns).
 pub struct LayoutBreakpoints {
@@ -42,33 +58,143 @@ pub enum AppLayout {
     },
 }
 
+/// Smallest and largest chat share of the wide split, in percent
+const CHAT_RATIO_RANGE: (u16, u16) = (20, 80);
+
+/// Share of the wide split the focused pane takes, in percent
+pub const FOCUS_RATIO: u16 = 80;
+
+/// Percent the drawn split moves toward a new focus each tick
+const REFLOW_STEP: u16 = 6;
+
+/// A pane of the wide layout that can be given focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Chat,
+    Diff,
+}
+
 /// Computes responsive layouts for the app.
 pub struct LayoutManager {
     breakpoints: LayoutBreakpoints,
+    sidebar_width: u16,
+    input_max_height: u16,
+    sidebar_position: SidebarPosition,
+    chat_ratio: u16,
+    /// The pane widened to [`FOCUS_RATIO`], over `chat_ratio`
+    focus: Option<Pane>,
+    /// The chat share drawn, catching up with the target a step per tick
+    shown_ratio: u16,
 }
 
 impl LayoutManager {
     pub fn new(breakpoints: LayoutBreakpoints) -> Self {
-        Self { breakpoints }
+        let defaults = LayoutConfig::default();
+        Self {
+            breakpoints,
+            sidebar_width: defaults.sidebar_width,
+            input_max_height: defaults.input_max_height,
+            sidebar_position: defaults.sidebar_position,
+            chat_ratio: defaults.chat_ratio,
+            focus: None,
+            shown_ratio: defaults.chat_ratio,
+        }
+    }
+
+    pub fn from_config(config: &LayoutConfig) -> Self {
+        let chat_ratio = config
+            .chat_ratio
+            .clamp(CHAT_RATIO_RANGE.0, CHAT_RATIO_RANGE.1);
+        Self {
+            breakpoints: LayoutBreakpoints {
+                compact: config.normal_min_width,
+                wide: config.wide_min_width,
+            },
+            sidebar_width: config.sidebar_width,
+            input_max_height: config.input_max_height.max(3),
+            sidebar_position: config.sidebar_position,
+            chat_ratio,
+            focus: None,
+            shown_ratio: chat_ratio,
+        }
+    }
+
+    pub fn chat_ratio(&self) -> u16 {
+        self.chat_ratio
     }
 
-    pub fn compute(&self, area: Rect, sidebar_visible: bool) -> AppLayout {
-        // Base vertical split: header, content, input, status with spacing
+    /// Move the wide layout's chat/diff split by `delta` percent; returns the
+    /// new ratio. Moving the split by hand drops any focus.
+    pub fn adjust_chat_ratio(&mut self, delta: i16) -> u16 {
+        let ratio = (self.chat_ratio as i16 + delta)
+            .clamp(CHAT_RATIO_RANGE.0 as i16, CHAT_RATIO_RANGE.1 as i16);
+        self.chat_ratio = ratio as u16;
+        self.focus = None;
+        self.shown_ratio = self.chat_ratio;
+        self.chat_ratio
+    }
+
+    pub fn focus(&self) -> Option<Pane> {
+        self.focus
+    }
+
+    /// Widen `pane` to [`FOCUS_RATIO`] of the split, or with `None` go back to
+    /// the chosen split. The split reflows over the next few ticks.
+    pub fn set_focus(&mut self, focus: Option<Pane>) {
+        self.focus = focus;
+    }
+
+    /// Focus the chat, then the diff, then neither
+    pub fn cycle_focus(&mut self) -> Option<Pane> {
+        self.focus = match self.focus {
+            None => Some(Pane::Chat),
+            Some(Pane::Chat) => Some(Pane::Diff),
+            Some(Pane::Diff) => None,
+        };
+        self.focus
+    }
+
+    /// The chat share the split is heading for
+    fn target_ratio(&self) -> u16 {
+        match self.focus {
+            Some(Pane::Chat) => FOCUS_RATIO,
+            Some(Pane::Diff) => 100 - FOCUS_RATIO,
+            None => self.chat_ratio,
+        }
+    }
+
+    /// Move the drawn split a step toward its target; whether it moved
+    pub fn step(&mut self) -> bool {
+        let target = self.target_ratio();
+        if self.shown_ratio == target {
+            return false;
+        }
+        self.shown_ratio = if self.shown_ratio < target {
+            (self.shown_ratio + REFLOW_STEP).min(target)
+        } else {
+            self.shown_ratio.saturating_sub(REFLOW_STEP).max(target)
+        };
+        true
+    }
+
+    /// Lay out the screen; `input_lines` is how many lines the prompt holds
+    pub fn compute(&self, area: Rect, sidebar_visible: bool, input_lines: u16) -> AppLayout {
+        // The input grows with the prompt (plus its border) up to the configured height
+        let input_height = (input_lines.max(1) + 2).clamp(3, self.input_max_height);
         let vertical = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(3), // Header (compact)
-                Constraint::Min(8),    // Content (flexible, min 8)
-                Constraint::Min(3),    // Input (flexible 3-6 lines)
-                Constraint::Max(6),    // Cap multiline input
-                Constraint::Length(1), // Status bar
+                Constraint::Length(3),            // Header (compact)
+                Constraint::Min(8),               // Content (flexible, min 8)
+                Constraint::Length(input_height), // Input
+                Constraint::Length(1),            // Status bar
             ])
             .split(area);
 
         let header = vertical[0];
         let content_area = vertical[1];
         let input = vertical[2];
-        let status = vertical[4];
+        let status = vertical[3];
 
         if area.width < self.breakpoints.compact {
             AppLayout::Compact {
@@ -78,50 +204,93 @@ impl LayoutManager {
                 status,
             }
         } else if area.width < self.breakpoints.wide {
-            let sidebar = if sidebar_visible {
-                let chunks = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints([
-                        Constraint::Min(50),    // Content (flexible, min 50 cols)
-                        Constraint::Length(25), // Sidebar (fixed 25 cols)
-                    ])
-                    .split(content_area);
-                Some(chunks[1])
+            let (content, sidebar) = if sidebar_visible {
+                let (content, sidebar) = self.split_sidebar(
+                    content_area,
+                    Constraint::Min(50), // Content (flexible, min 50 cols)
+                );
+                (content, Some(sidebar))
             } else {
-                None
+                (content_area, None)
             };
 
             AppLayout::Normal {
                 header,
-                content: content_area,
+                content,
                 input,
                 status,
                 sidebar,
             }
         } else {
-            let horizontal = Layout::default()
+            let (main, sidebar) = self.split_sidebar(content_area, Constraint::Min(70));
+            let panes = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
-                    Constraint::Min(40),    // Chat (flexible, min 40 cols)
-                    Constraint::Min(30),    // Diff (flexible, min 30 cols)
-                    Constraint::Length(25), // Sidebar (fixed width)
+                    Constraint::Fill(self.shown_ratio),       // Chat
+                    Constraint::Fill(100 - self.shown_ratio), // Diff
                 ])
-                .split(content_area);
-
-            let chat = horizontal[0];
-            let diff = horizontal[1];
-            let sidebar = horizontal[2];
+                .split(main);
 
             AppLayout::Wide {
                 header,
-                chat,
-                diff,
+                chat: panes[0],
+                diff: panes[1],
                 input,
                 status,
                 sidebar,
             }
         }
     }
+
+    /// Split off the sidebar on the configured side: (content, sidebar)
+    fn split_sidebar(&self, area: Rect, content: Constraint) -> (Rect, Rect) {
+        let sidebar = Constraint::Length(self.sidebar_width);
+        match self.sidebar_position {
+            SidebarPosition::Right => {
+                let chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([content, sidebar])
+                    .split(area);
+                (chunks[0], chunks[1])
+            }
+            SidebarPosition::Left => {
+                let chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([sidebar, content])
+                    .split(area);
+                (chunks[1], chunks[0])
+            }
+        }
+    }
+}
+
+/// Where the chat/diff split chosen with `Ctrl+←/→` is remembered
+pub fn saved_layout_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("layout.json")
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedLayout {
+    chat_ratio: u16,
+}
+
+/// The split saved by an earlier run, if any
+pub fn load_chat_ratio(path: &Path) -> Option<u16> {
+    let json = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str::<SavedLayout>(&json)
+        .ok()
+        .map(|saved| saved.chat_ratio)
+}
+
+pub fn save_chat_ratio(path: &Path, chat_ratio: u16) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string(&SavedLayout { chat_ratio })?;
+    crate::file_ops::atomic_write(path, &json)
 }
 
 /// Create a standard three-section layout: header, content, footer
@@ -138,6 +307,26 @@ pub fn main_layout(area: Rect) -> (Rect, Rect, Rect) {
     (chunks[0], chunks[1], chunks[2])
 }
 
+/// Most of the screen, in percent each way, that a dialog sized to its text takes
+pub const DIALOG_MAX_PERCENT: u16 = 80;
+
+/// A centered dialog `width` columns wide for `fixed` rows plus `body` rows,
+/// no larger than [`DIALOG_MAX_PERCENT`] of `area`. Returns the dialog and how
+/// many of the body rows it shows; the rest are scrolled to.
+pub fn fit_dialog(area: Rect, width: u16, body: usize, fixed: u16) -> (Rect, usize) {
+    let max_width = area.width * DIALOG_MAX_PERCENT / 100;
+    let max_height = area.height * DIALOG_MAX_PERCENT / 100;
+    // Borders, the fixed rows and at least one body row
+    let least = (fixed + 3).min(area.height);
+    let height = u16::try_from(body)
+        .unwrap_or(u16::MAX)
+        .saturating_add(fixed + 2)
+        .min(max_height)
+        .max(least);
+    let dialog = centered_dialog(area, width.min(max_width), height);
+    (dialog, height.saturating_sub(fixed + 2) as usize)
+}
+
 /// Create a centered dialog/modal with specified width and height
 pub fn centered_dialog(area: Rect, width: u16, height: u16) -> Rect {
     let popup_layout = Layout::default()
@@ -273,3 +462,76 @@ pub fn chat_layout_with_status(area: Rect) -> (Rect, Rect, Rect) {
 
     (chunks[0], chunks[1], chunks[2])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_layout_follows_config() {
+        let config = LayoutConfig {
+            sidebar_width: 30,
+            sidebar_position: SidebarPosition::Left,
+            ..LayoutConfig::default()
+        };
+        let mut layout = LayoutManager::from_config(&config);
+        let area = Rect::new(0, 0, 230, 40);
+
+        let AppLayout::Wide {
+            chat,
+            sidebar,
+            input,
+            ..
+        } = layout.compute(area, true, 10)
+        else {
+            panic!("expected the wide layout");
+        };
+        assert_eq!((sidebar.x, sidebar.width), (0, 30));
+        assert_eq!(chat.x, 30);
+        assert_eq!(chat.width, 100);
+        assert_eq!(input.height, 6);
+
+        assert_eq!(layout.adjust_chat_ratio(100), 80);
+        let AppLayout::Wide { chat, diff, .. } = layout.compute(area, true, 1) else {
+            panic!("expected the wide layout");
+        };
+        assert_eq!((chat.width, diff.width), (160, 40));
+    }
+
+    #[test]
+    fn test_focused_pane_widens_over_a_few_ticks() {
+        let mut layout = LayoutManager::from_config(&LayoutConfig::default());
+        let area = Rect::new(0, 0, 230, 40);
+        let widths = |layout: &LayoutManager| {
+            let AppLayout::Wide { chat, diff, .. } = layout.compute(area, false, 1) else {
+                panic!("expected the wide layout");
+            };
+            (chat.width, diff.width)
+        };
+        let unfocused = widths(&layout);
+
+        assert_eq!(layout.cycle_focus(), Some(Pane::Chat));
+        assert!(layout.step());
+        assert!(widths(&layout).0 > unfocused.0 && widths(&layout).0 < 164);
+        while layout.step() {}
+        assert_eq!(widths(&layout), (164, 41));
+
+        layout.set_focus(Some(Pane::Diff));
+        while layout.step() {}
+        assert_eq!(widths(&layout), (41, 164));
+
+        // Back to the split chosen before
+        assert_eq!(layout.cycle_focus(), None);
+        while layout.step() {}
+        assert_eq!(widths(&layout), unfocused);
+    }
+
+    #[test]
+    fn test_chat_ratio_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zcode").join("layout.json");
+        assert_eq!(load_chat_ratio(&path), None);
+        save_chat_ratio(&path, 35).unwrap();
+        assert_eq!(load_chat_ratio(&path), Some(35));
+    }
+}
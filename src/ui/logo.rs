@@ -17,6 +17,12 @@ pub const LOGO: &str = r#" ________  ________  ________  ________  _______
    |\________\ \_______\ \_______\ \_______\ \_______\
     \|_______|\|_______|\|_______|\|_______|\|_______|"#;
 
+/// Smaller logo for splash and provider select screens too narrow for [`LOGO`]
+pub const LOGO_SMALL: &str = r#" ____ ___ ___  ___  ___ 
+|_  // __/ _ \|   \| __|
+ / /| (_| (_) | |) | _| 
+/___|\___\___/|___/|___|"#;
+
 /// Ultra-minimal single-line logo
 pub const LOGO_MINIMAL: &str = "ZCODE";
 
@@ -48,9 +54,27 @@ pub fn render_logo(frame: &mut Frame, area: Rect) {
     frame.render_widget(logo_paragraph, area);
 }
 
-/// Render the main ZCODE logo for splash/provider select screens
+/// The largest logo that fits in `width` x `height`
+pub fn logo_for(width: u16, height: u16) -> &'static str {
+    [LOGO, LOGO_SMALL]
+        .into_iter()
+        .find(|logo| {
+            let (w, h) = logo_size(logo);
+            w <= width && h <= height
+        })
+        .unwrap_or(LOGO_MINIMAL)
+}
+
+/// Columns and rows taken by `logo`
+pub fn logo_size(logo: &str) -> (u16, u16) {
+    let width = logo.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    (width as u16, logo.lines().count() as u16)
+}
+
+/// Render the main ZCODE logo for splash/provider select screens, in the
+/// largest variant that fits `area`
 pub fn render_logo_text(frame: &mut Frame, area: Rect) {
-    let lines: Vec<Line> = LOGO
+    let lines: Vec<Line> = logo_for(area.width, area.height)
         .lines()
         .map(|line| {
             Line::from(Span::styled(
@@ -144,3 +168,17 @@ pub fn render_logo_inline(frame: &mut Frame, area: Rect) {
         .alignment(Alignment::Left);
     frame.render_widget(logo, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logo_shrinks_with_the_area() {
+        assert_eq!(logo_for(80, 10), LOGO);
+        assert_eq!(logo_for(40, 10), LOGO_SMALL);
+        assert_eq!(logo_for(80, 5), LOGO_SMALL);
+        assert_eq!(logo_for(20, 10), LOGO_MINIMAL);
+        assert_eq!(logo_size(LOGO_SMALL), (24, 4));
+    }
+}
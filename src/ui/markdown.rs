@@ -0,0 +1,378 @@
+// src/ui/markdown.rs - Markdown rendering for chat messages
+
+use std::sync::OnceLock;
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme as SyntaxTheme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use unicode_width::UnicodeWidthStr;
+
+use crate::ui::colors::Theme;
+
+/// Render markdown into lines no wider than `width`.
+///
+/// Supports headings, bullet and numbered lists, block quotes, fenced code blocks
+/// (syntax highlighted when the language is known) and inline `code`, **bold** and
+/// *italic* spans. Anything else is rendered as wrapped paragraph text.
+pub fn render_markdown(source: &str, width: usize, theme: &Theme) -> Vec<Line<'static>> {
+    let width = width.max(8);
+    let mut lines = Vec::new();
+    let mut fence: Option<(String, Vec<&str>)> = None;
+
+    for raw in source.lines() {
+        let trimmed = raw.trim_start();
+
+        if let Some((lang, code)) = fence.as_mut() {
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                lines.extend(render_code_block(lang, code, width, theme));
+                fence = None;
+            } else {
+                code.push(raw);
+            }
+            continue;
+        }
+
+        if let Some(info) = trimmed
+            .strip_prefix("```")
+            .or_else(|| trimmed.strip_prefix("~~~"))
+        {
+            let lang = info.split_whitespace().next().unwrap_or("").to_string();
+            fence = Some((lang, Vec::new()));
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            // Collapse runs of blank lines
+            if lines.last().is_some_and(|l: &Line| !l.spans.is_empty()) {
+                lines.push(Line::default());
+            }
+            continue;
+        }
+
+        if let Some((level, text)) = heading(trimmed) {
+            let mut style = theme.normal_style.add_modifier(Modifier::BOLD);
+            if level == 1 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            let spans = inline_spans(text, style, theme);
+            lines.extend(wrap_spans(spans, width, Span::raw(""), Span::raw("")));
+            continue;
+        }
+
+        if let Some(text) = trimmed.strip_prefix('>') {
+            let style = theme.context_style.add_modifier(Modifier::ITALIC);
+            let spans = inline_spans(text.trim_start(), style, theme);
+            let bar = Span::styled("▎ ", theme.context_style);
+            lines.extend(wrap_spans(spans, width, bar.clone(), bar));
+            continue;
+        }
+
+        if let Some((marker, text)) = list_item(trimmed) {
+            let indent = " ".repeat((raw.len() - trimmed.len()).min(8));
+            let first = Span::styled(format!("{}{} ", indent, marker), theme.context_style);
+            let rest = Span::raw(" ".repeat(first.content.width()));
+            let spans = inline_spans(text, theme.normal_style, theme);
+            lines.extend(wrap_spans(spans, width, first, rest));
+            continue;
+        }
+
+        let spans = inline_spans(trimmed, theme.normal_style, theme);
+        lines.extend(wrap_spans(spans, width, Span::raw(""), Span::raw("")));
+    }
+
+    // An unterminated fence still shows its code
+    if let Some((lang, code)) = fence {
+        lines.extend(render_code_block(&lang, &code, width, theme));
+    }
+
+    while lines.last().is_some_and(|l| l.spans.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&level) {
+        line[level..]
+            .strip_prefix(' ')
+            .map(|text| (level, text.trim()))
+    } else {
+        None
+    }
+}
+
+fn list_item(line: &str) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(bullet) {
+            return Some(("•".to_string(), text));
+        }
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && digits <= 3 {
+        let rest = &line[digits..];
+        if let Some(text) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((format!("{}.", &line[..digits]), text));
+        }
+    }
+    None
+}
+
+/// Split a line into styled spans for `code`, **bold** and *italic* markup
+fn inline_spans(text: &str, base: Style, theme: &Theme) -> Vec<Span<'static>> {
+    let code_style = theme.prompt_style.remove_modifier(Modifier::BOLD);
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let (delim, style) = match c {
+            '`' => ("`", code_style),
+            '*' if rest.starts_with("**") => ("**", base.add_modifier(Modifier::BOLD)),
+            '*' => ("*", base.add_modifier(Modifier::ITALIC)),
+            _ => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+        };
+
+        let inner = &rest[delim.len()..];
+        match inner.find(delim) {
+            // Require content, and for emphasis no leading space (e.g. "a * b")
+            Some(end) if end > 0 && (delim == "`" || !inner.starts_with(' ')) => {
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), base));
+                }
+                spans.push(Span::styled(inner[..end].to_string(), style));
+                rest = &inner[end + delim.len()..];
+            }
+            _ => {
+                plain.push_str(delim);
+                rest = inner;
+            }
+        }
+    }
+
+    if !plain.is_empty() {
+        spans.push(Span::styled(plain, base));
+    }
+    spans
+}
+
+/// Greedy word wrap that keeps span styles; `first`/`rest` prefix the first and
+/// continuation lines (bullets, quote bars)
+fn wrap_spans(
+    spans: Vec<Span<'static>>,
+    width: usize,
+    first: Span<'static>,
+    rest: Span<'static>,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut current: Vec<Span<'static>> = vec![first.clone()];
+    let mut used = first.content.width();
+    let mut line_start = true;
+
+    let mut words: Vec<(String, Style)> = Vec::new();
+    for span in &spans {
+        for (i, word) in span.content.split(' ').enumerate() {
+            // Keep the separating space with the following word
+            let word = if i > 0 {
+                format!(" {}", word)
+            } else {
+                word.to_string()
+            };
+            if !word.is_empty() {
+                words.push((word, span.style));
+            }
+        }
+    }
+
+    for (word, style) in words {
+        let mut word = word;
+        let mut word_width = word.width();
+
+        if used + word_width > width && !line_start {
+            lines.push(Line::from(std::mem::take(&mut current)));
+            current.push(rest.clone());
+            used = rest.content.width();
+            word = word.trim_start().to_string();
+            word_width = word.width();
+        }
+
+        // Hard-break words longer than a whole line
+        while used + word_width > width && word.chars().count() > 1 {
+            let available = width.saturating_sub(used).max(1);
+            let (head, tail) = split_at_width(&word, available);
+            current.push(Span::styled(head, style));
+            lines.push(Line::from(std::mem::take(&mut current)));
+            current.push(rest.clone());
+            used = rest.content.width();
+            word = tail;
+            word_width = word.width();
+        }
+
+        if line_start {
+            word = word.trim_start().to_string();
+            word_width = word.width();
+        }
+        used += word_width;
+        line_start = false;
+        current.push(Span::styled(word, style));
+    }
+
+    lines.push(Line::from(current));
+    lines
+}
+
+fn split_at_width(word: &str, width: usize) -> (String, String) {
+    let (head, tail) = crate::ui::split_at_width(word, width);
+    (head.to_string(), tail.to_string())
+}
+
+fn syntax_assets() -> &'static (SyntaxSet, SyntaxTheme) {
+    static ASSETS: OnceLock<(SyntaxSet, SyntaxTheme)> = OnceLock::new();
+    ASSETS.get_or_init(|| {
+        let themes = ThemeSet::load_defaults();
+        let theme = themes.themes["base16-ocean.dark"].clone();
+        (SyntaxSet::load_defaults_newlines(), theme)
+    })
+}
+
+fn render_code_block(lang: &str, code: &[&str], width: usize, theme: &Theme) -> Vec<Line<'static>> {
+    let gutter = Span::styled("│ ", theme.border_style);
+    let mut lines = Vec::new();
+    if !lang.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("╭ ", theme.border_style),
+            Span::styled(lang.to_string(), theme.context_style),
+        ]));
+    }
+
+    let (syntaxes, syntax_theme) = syntax_assets();
+    let mut highlighter = (!lang.is_empty())
+        .then(|| syntaxes.find_syntax_by_token(lang))
+        .flatten()
+        .map(|syntax| HighlightLines::new(syntax, syntax_theme));
+
+    for raw in code {
+        let line = format!("{}\n", raw);
+        let spans: Vec<Span<'static>> = match highlighter
+            .as_mut()
+            .and_then(|h| h.highlight_line(&line, syntaxes).ok())
+        {
+            Some(ranges) => ranges
+                .into_iter()
+                .map(|(style, text)| {
+                    let fg = style.foreground;
+                    Span::styled(
+                        text.trim_end_matches('\n').to_string(),
+                        Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b)),
+                    )
+                })
+                .collect(),
+            None => vec![Span::styled(raw.to_string(), theme.normal_style)],
+        };
+
+        // Code keeps its whitespace: break long lines by width instead of words
+        let mut current = vec![gutter.clone()];
+        let mut used = 2;
+        for span in spans {
+            let mut text = span.content.into_owned();
+            while used + text.width() > width && !text.is_empty() {
+                let (head, tail) = split_at_width(&text, width.saturating_sub(used).max(1));
+                current.push(Span::styled(head, span.style));
+                lines.push(Line::from(std::mem::take(&mut current)));
+                current.push(gutter.clone());
+                used = 2;
+                text = tail;
+            }
+            used += text.width();
+            current.push(Span::styled(text, span.style));
+        }
+        lines.push(Line::from(current));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_wide_characters_wrap_by_width() {
+        use unicode_width::UnicodeWidthStr;
+        let theme = Theme::dark();
+        let lines = render_markdown("漢字漢字漢字漢字漢 👨‍👩‍👧👨‍👩‍👧👨‍👩‍👧", 9, &theme);
+        let rows: Vec<String> = lines.iter().map(text).collect();
+
+        // An odd width can't fit another 2-column character
+        assert_eq!(rows[0], "漢字漢字");
+        assert!(rows.iter().all(|row| row.width() <= 9), "{:?}", rows);
+        // Emoji sequences are never split
+        assert_eq!(rows.concat().matches("👨‍👩‍👧").count(), 3);
+        assert_eq!(crate::ui::truncate_line("漢字漢字", 5), "漢字…");
+    }
+
+    #[test]
+    fn test_block_elements() {
+        let theme = Theme::dark();
+        let source =
+            "# Title\n\n- first item\n2. second\n> quoted\n\n```rust\nfn main() {}\n```\nafter";
+        let lines: Vec<String> = render_markdown(source, 40, &theme)
+            .iter()
+            .map(text)
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "Title",
+                "",
+                "• first item",
+                "2. second",
+                "▎ quoted",
+                "",
+                "╭ rust",
+                "│ fn main() {}",
+                "after",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_code_and_bold_are_styled() {
+        let theme = Theme::dark();
+        let lines = render_markdown("call `run()` **now**", 40, &theme);
+        let spans = &lines[0].spans;
+
+        let code = spans.iter().find(|s| s.content == "run()").unwrap();
+        assert_eq!(code.style.bg, theme.prompt_style.bg);
+        let bold = spans.iter().find(|s| s.content.contains("now")).unwrap();
+        assert!(bold.style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(text(&lines[0]), "call run() now");
+    }
+
+    #[test]
+    fn test_wrapping_respects_width() {
+        let theme = Theme::dark();
+        let source = "- a list item whose text is much longer than the panel is wide";
+        let lines = render_markdown(source, 20, &theme);
+
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(line.width() <= 20, "too wide: {:?}", text(line));
+        }
+        // Continuation lines line up under the item text
+        assert!(text(&lines[1]).starts_with("  "));
+    }
+}
@@ -0,0 +1,219 @@
+// src/ui/message_detail.rs - One chat message in full, with its links
+
+use std::num::NonZeroU16;
+
+use crate::i18n::{t, tf};
+use crate::state::{ChatMessage, MessageDetail};
+use crate::ui::colors::Theme;
+use crate::ui::markdown::render_markdown;
+use ratatui::{
+    buffer::{Buffer, CellDiffOption},
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Most links listed below the message before the list scrolls with the selection
+const MAX_LINK_ROWS: usize = 6;
+
+/// Render `message` over `area`: its text, then its links with the selected one
+/// highlighted. Links are written as OSC 8 hyperlinks so they can be clicked.
+pub fn render_message_detail(
+    frame: &mut Frame,
+    area: Rect,
+    detail: &MessageDetail,
+    message: &ChatMessage,
+    theme: &Theme,
+) {
+    frame.render_widget(Clear, area);
+
+    let who = if message.is_user {
+        t("message.you")
+    } else {
+        t("message.assistant")
+    };
+    let block = Block::default()
+        .title(format!(
+            " {} · {} ",
+            who,
+            message
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+        ))
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let link_rows = detail.links.len().min(MAX_LINK_ROWS);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(if link_rows > 0 {
+                link_rows as u16 + 1
+            } else {
+                0
+            }),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    let width = inner.width as usize;
+    let body: Vec<Line> = if message.is_user {
+        message
+            .content
+            .lines()
+            .flat_map(|line| {
+                let wrapped = textwrap::wrap(line, width.max(1));
+                if wrapped.is_empty() {
+                    vec![Line::default()]
+                } else {
+                    wrapped
+                        .into_iter()
+                        .map(|chunk| Line::styled(chunk.into_owned(), theme.normal_style))
+                        .collect()
+                }
+            })
+            .collect()
+    } else {
+        render_markdown(&message.content, width, theme)
+    };
+    let scroll = detail
+        .scroll
+        .min(body.len().saturating_sub(chunks[0].height as usize));
+    let body: Vec<Line> = body.into_iter().skip(scroll).collect();
+    frame.render_widget(Paragraph::new(body).style(theme.normal_style), chunks[0]);
+
+    if link_rows > 0 {
+        render_links(frame, chunks[1], detail, theme);
+    }
+
+    frame.render_widget(footer(detail, theme), chunks[2]);
+}
+
+/// The key hints, or the prompt for the file to diff the selected block against
+fn footer<'a>(detail: &'a MessageDetail, theme: &Theme) -> Paragraph<'a> {
+    let dim = theme.muted_style;
+    if let Some(path) = &detail.diff_path {
+        let number = detail.selected_block.map_or(0, |idx| idx + 1);
+        return Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("{} ", tf("message.diff_against", &[("number", &number)])),
+                dim,
+            ),
+            Span::styled(format!("{}▏", path), theme.normal_style),
+            Span::styled(
+                format!("  {}", theme.icons.hints(t("message.diff_hint"))),
+                dim,
+            ),
+        ]));
+    }
+    let mut hints = Vec::new();
+    if !detail.links.is_empty() {
+        hints.push(t("message.link_hint").to_string());
+    }
+    if !detail.blocks.is_empty() {
+        let selected = match detail.selected_block {
+            Some(idx) => format!(" ({}/{})", idx + 1, detail.blocks.len()),
+            None => String::new(),
+        };
+        hints.push(tf("message.block_hint", &[("selected", &selected)]));
+    }
+    hints.push(t("message.scroll_hint").to_string());
+    Paragraph::new(Line::from(Span::styled(
+        theme.icons.hints(&hints.join(" │ ")),
+        dim,
+    )))
+}
+
+fn render_links(frame: &mut Frame, area: Rect, detail: &MessageDetail, theme: &Theme) {
+    let block = Block::default()
+        .title(t("message.links"))
+        .borders(Borders::TOP)
+        .border_style(theme.border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Keep the selected link within the rows shown
+    let rows = inner.height as usize;
+    let selected = detail.selected.unwrap_or(0);
+    let first = (selected + 1).saturating_sub(rows);
+    let gutter = detail.links.len().to_string().len();
+
+    for (row, (idx, url)) in detail
+        .links
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .enumerate()
+    {
+        let line_area = Rect::new(inner.x, inner.y + row as u16, inner.width, 1);
+        let style = if detail.selected == Some(idx) {
+            theme.selected_style
+        } else {
+            theme.added_style
+        };
+        let number = format!("{:>width$} ", idx + 1, width = gutter);
+        let url_width = line_area.width.saturating_sub(number.len() as u16);
+        let shown = crate::ui::truncate_line(url, url_width as usize);
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(number.clone(), theme.context_style),
+                Span::styled(shown.clone(), style),
+            ])),
+            line_area,
+        );
+        let url_area = Rect {
+            x: line_area.x + number.len() as u16,
+            width: url_width,
+            ..line_area
+        };
+        hyperlink(frame.buffer_mut(), url_area, url, &shown);
+    }
+}
+
+/// Turn the cells showing `text` at the start of `area` into an OSC 8 link to
+/// `url`. Each cell carries its own escape sequence so a partial redraw keeps
+/// the link intact; terminals join neighbouring cells with the same target.
+fn hyperlink(buf: &mut Buffer, area: Rect, url: &str, text: &str) {
+    let mut x = area.x;
+    for grapheme in text.graphemes(true) {
+        let width = unicode_width::UnicodeWidthStr::width(grapheme) as u16;
+        if width == 0 || x + width > area.right() {
+            break;
+        }
+        if let Some(cell) = buf.cell_mut((x, area.y)) {
+            cell.set_symbol(&crate::links::osc8(url, grapheme));
+            let width = NonZeroU16::new(width).unwrap_or(NonZeroU16::MIN);
+            cell.set_diff_option(CellDiffOption::ForcedWidth(width));
+        }
+        x += width;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hyperlink_wraps_each_cell_with_forced_width() {
+        let area = Rect::new(0, 0, 4, 1);
+        let mut buf = Buffer::empty(area);
+        hyperlink(&mut buf, area, "https://a.io", "abcdef");
+        assert_eq!(
+            buf[(0, 0)].symbol(),
+            "\x1b]8;;https://a.io\x1b\\a\x1b]8;;\x1b\\"
+        );
+        assert_eq!(
+            buf[(3, 0)].diff_option,
+            CellDiffOption::ForcedWidth(NonZeroU16::MIN)
+        );
+    }
+}
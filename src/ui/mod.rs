@@ -9,29 +9,46 @@
 //! - Confirmation dialogs
 //! - Error messages
 //! - ASCII logo splash screen
+//! - Markdown rendering for chat replies
 //!
 //! # Submodules
 //!
 //! - [`renderers`]: Ratatui-based rendering functions
 //! - [`colors`]: Color schemes and style definitions
+//! - [`icons`]: Marker glyphs: Unicode, Nerd Font or ASCII
 //! - [`layout`]: Layout helper functions
 //! - [`logo`]: ASCII logo rendering
 
+pub mod activity;
 pub mod chat_history;
 pub mod colors;
+pub mod config_errors;
+pub mod cursor;
 pub mod editor;
+pub mod grep;
 pub mod header;
 pub mod help;
+pub mod history;
+pub mod icons;
 pub mod layout;
 pub mod logo;
+pub mod markdown;
+pub mod message_detail;
+pub mod output_viewer;
 pub mod overlay_diff;
+pub mod perf;
 pub mod prompt_input;
+pub mod reindent;
 pub mod renderers;
 pub mod search;
 pub mod session_turn;
+pub mod sessions;
 pub mod sidebar;
 pub mod status_bar;
+pub mod template_form;
 pub mod theme;
+pub mod usage;
+pub mod which_key;
 pub mod widgets;
 
 pub use colors::Colors;
@@ -40,27 +57,34 @@ pub const RESET: &str = "\x1b[0m";
 pub const BOLD: &str = "\x1b[1m";
 pub const DIM: &str = "\x1b[2m";
 
+/// Cut `line` to `max_width` columns, ending in `…` when anything was cut.
+/// Grapheme clusters (emoji sequences, combining marks) are never split.
 pub fn truncate_line(line: &str, max_width: usize) -> String {
     use unicode_width::UnicodeWidthStr;
 
     if line.width() <= max_width {
         line.to_string()
     } else {
-        let mut result = String::new();
-        let mut width = 0;
+        let (kept, _) = split_at_width(line, max_width.saturating_sub(1));
+        format!("{}…", kept)
+    }
+}
 
-        for ch in line.chars() {
-            let ch_width = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
-            if width + ch_width + 1 > max_width {
-                result.push('…');
-                break;
-            }
-            result.push(ch);
-            width += ch_width;
-        }
+/// Split `text` after the graphemes that fit in `width` columns. At least one
+/// grapheme goes first, so a character wider than `width` still makes progress.
+pub fn split_at_width(text: &str, width: usize) -> (&str, &str) {
+    use unicode_segmentation::UnicodeSegmentation;
+    use unicode_width::UnicodeWidthStr;
 
-        result
+    let mut used = 0;
+    for (idx, grapheme) in text.grapheme_indices(true) {
+        let grapheme_width = grapheme.width();
+        if idx > 0 && used + grapheme_width > width {
+            return text.split_at(idx);
+        }
+        used += grapheme_width;
     }
+    (text, "")
 }
 
 pub fn center_text(text: &str, width: usize) -> String {
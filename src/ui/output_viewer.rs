@@ -0,0 +1,127 @@
+// src/ui/output_viewer.rs - Scrollable raw output of a failed provider run
+
+use crate::i18n::{t, tf};
+use crate::state::OutputViewer;
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Render the output viewer over `area`
+pub fn render_output_viewer(frame: &mut Frame, area: Rect, viewer: &OutputViewer, theme: &Theme) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!(" {} ", viewer.title))
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let gutter = viewer.lines.len().max(1).to_string().len();
+    let lines: Vec<Line> = viewer
+        .lines
+        .iter()
+        .enumerate()
+        .skip(viewer.scroll)
+        .take(chunks[0].height as usize)
+        .map(|(idx, text)| {
+            let mut spans = vec![Span::styled(
+                format!("{:>width$} ", idx + 1, width = gutter),
+                theme.muted_style,
+            )];
+            spans.extend(highlight(text, &viewer.query, theme));
+            Line::from(spans)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).style(theme.normal_style), chunks[0]);
+
+    let footer = match &viewer.input {
+        Some(input) => Line::from(vec![
+            Span::styled("/", theme.prompt_style),
+            Span::styled(input.clone(), theme.normal_style),
+        ]),
+        None => {
+            let mut spans = vec![Span::styled(
+                theme.icons.hints(t("output.hint")),
+                theme.muted_style,
+            )];
+            if !viewer.query.is_empty() {
+                let status = if viewer.matches.is_empty() {
+                    tf("output.no_matches", &[("query", &viewer.query)])
+                } else {
+                    tf(
+                        "output.match",
+                        &[
+                            ("current", &(viewer.current_match + 1)),
+                            ("count", &viewer.matches.len()),
+                        ],
+                    )
+                };
+                spans.push(Span::styled(format!("  {}", status), theme.status_pending));
+            }
+            Line::from(spans)
+        }
+    };
+    frame.render_widget(Paragraph::new(footer), chunks[1]);
+    if let Some(input) = &viewer.input {
+        let column = 1 + UnicodeWidthStr::width(input.as_str());
+        frame.set_cursor_position(crate::ui::cursor::position(chunks[1], 0, 0, column));
+    }
+}
+
+/// Split a line into spans, highlighting case-insensitive occurrences of `query`
+fn highlight(text: &str, query: &str, theme: &Theme) -> Vec<Span<'static>> {
+    let lower = text.to_lowercase();
+    let needle = query.to_lowercase();
+    // Lowercasing can change byte lengths; only highlight when offsets line up
+    if needle.is_empty() || lower.len() != text.len() {
+        return vec![Span::styled(text.to_string(), theme.normal_style)];
+    }
+
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for (start, _) in lower.match_indices(&needle) {
+        if start > last {
+            spans.push(Span::styled(
+                text[last..start].to_string(),
+                theme.normal_style,
+            ));
+        }
+        let end = start + needle.len();
+        spans.push(Span::styled(
+            text[start..end].to_string(),
+            theme.selected_style,
+        ));
+        last = end;
+    }
+    if last < text.len() {
+        spans.push(Span::styled(text[last..].to_string(), theme.normal_style));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_is_case_insensitive() {
+        let theme = Theme::dark();
+        let spans = highlight("Error: bad error", "error", &theme);
+        let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(texts, vec!["Error", ": bad ", "error"]);
+        assert_eq!(spans[0].style, theme.selected_style);
+    }
+}
@@ -1,29 +1,44 @@
 // src/ui/overlay_diff.rs - Overlay-based diff rendering (VSCode/Neovim style)
 
-use crate::state::{DecorationType, LineDecoration, OverlayDiffState, ProposedChange};
+use crate::state::{DecorationType, HunkGroup, LineDecoration, OverlayDiffState, ProposedChange};
 use crate::ui::colors::Theme;
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 use std::path::PathBuf;
 
-/// Render overlay-style diff preview
+/// Columns before a line's text without line numbers: gutter, marker and the +/- sign
+pub const TEXT_OFFSET: usize = 4;
+/// Columns of the old and new line numbers, when shown
+pub const NUMBER_COLUMNS: usize = 10;
+
+/// Columns before a line's text, with or without line numbers
+pub fn text_offset(line_numbers: bool) -> usize {
+    if line_numbers {
+        TEXT_OFFSET + NUMBER_COLUMNS
+    } else {
+        TEXT_OFFSET
+    }
+}
+
+/// Render overlay-style diff preview, framed with `border`
 pub fn render_overlay_diff(
     frame: &mut Frame,
     area: Rect,
     diff_state: &OverlayDiffState,
     theme: &Theme,
+    border: Style,
 ) {
     if diff_state.proposed_changes.is_empty() {
         let text = Paragraph::new("No changes to review")
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .border_style(theme.border_style)
+                    .border_style(border)
                     .title(" Diff Review "),
             )
             .style(theme.normal_style)
@@ -38,30 +53,158 @@ pub fn render_overlay_diff(
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown");
+    // A rename shows both paths, which may differ only in their directories
+    let file_name = match &current_change.renamed_from {
+        Some(from) => format!(
+            "{} {} {}",
+            from.display(),
+            theme.icons.arrow,
+            current_change.file_path.display()
+        ),
+        None => file_name.to_string(),
+    };
+    if diff_state.side_by_side && current_change.computing.is_none() && !current_change.skipped {
+        render_side_by_side(frame, area, diff_state, &file_name, theme, border);
+        return;
+    }
 
     // Build lines for display
     let mut lines = Vec::new();
 
     // File header
-    lines.push(Line::from(vec![Span::styled(
+    let mut header = vec![Span::styled(
         format!("┌─ {} ─", file_name),
         theme.header_style,
-    )]));
+    )];
+    if let Some(base) = &diff_state.base {
+        header.push(Span::styled(
+            format!(" vs {} · [b] working tree ", base.rev),
+            theme.status_pending,
+        ));
+    }
+    if current_change.rewrite.is_some() {
+        header.push(Span::styled(
+            " rewritten · [za] full diff · [s] side by side ",
+            theme.status_pending,
+        ));
+    }
+    if current_change.stale {
+        header.push(Span::styled(
+            " changed on disk under these changes · reject or ask again ",
+            theme.error_style,
+        ));
+    } else if current_change.changed_on_disk {
+        header.push(Span::styled(" file changed on disk ", theme.error_style));
+    }
+    if let Some(warning) = &current_change.path_warning {
+        header.push(Span::styled(
+            format!(" {} · [r] rename ", warning),
+            theme.status_pending,
+        ));
+    }
+    if current_change.edit_choices > 1 {
+        header.push(Span::styled(
+            format!(
+                " overlapping edits · :pick 1-{} ",
+                current_change.edit_choices
+            ),
+            theme.error_style,
+        ));
+    }
+    if current_change.ignored {
+        header.push(Span::styled(
+            " ignored by policy · [O] override ",
+            theme.status_pending,
+        ));
+    }
+    if current_change.skipped {
+        header.push(Span::styled(
+            " skipped · [X] include ",
+            theme.status_pending,
+        ));
+    }
+    if !current_change.test_failures.is_empty() {
+        let mut tests: Vec<&str> = current_change
+            .test_failures
+            .iter()
+            .map(|(_, test)| test.as_str())
+            .collect();
+        tests.sort_unstable();
+        tests.dedup();
+        header.push(Span::styled(
+            format!(" {} {} failing tests ", theme.icons.rejected, tests.len()),
+            theme.error_style,
+        ));
+    }
+    lines.push(Line::from(header));
+    if let Some(provenance) = &diff_state.provenance {
+        lines.push(Line::from(Span::styled(
+            format!("   {}", provenance.summary()),
+            theme.muted_style,
+        )));
+    }
+    if current_change.computing.is_some() {
+        let size = current_change
+            .original_content
+            .lines()
+            .count()
+            .max(current_change.proposed_content.lines().count());
+        lines.push(Line::from(Span::styled(
+            format!("   computing diff… ({} lines)", size),
+            theme.status_pending,
+        )));
+    }
+
+    // Lines belonging to the hunk under the cursor get a gutter marker
+    let selected_hunk = current_change
+        .line_decorations
+        .get(diff_state.current_line_idx)
+        .and_then(|d| d.hunk_id);
+
+    let numbered = diff_state.line_numbers;
+    let line_numbers = current_change.line_numbers();
+    // The old and new line number columns, when shown
+    let number_span = |old: Option<usize>, new: Option<usize>| {
+        let cell = |n: Option<usize>| n.map_or_else(|| "    ".to_string(), |n| format!("{:4}", n));
+        Span::styled(
+            if numbered {
+                format!("{} {} ", cell(old), cell(new))
+            } else {
+                String::new()
+            },
+            theme.muted_style,
+        )
+    };
+
+    // The hunk each row belongs to, for the sticky header
+    let mut row_hunks: Vec<Option<usize>> = Vec::new();
 
     // Render each line decoration
-    for (idx, dec) in current_change.line_decorations.iter().enumerate() {
-        let is_selected = idx == diff_state.current_line_idx;
-        let line_num = dec.line_number;
+    // A skipped file shows only its header
+    let decorations = if current_change.skipped {
+        &[][..]
+    } else {
+        &current_change.line_decorations[..]
+    };
+    for (idx, dec) in decorations.iter().enumerate() {
+        if let Some(group) = current_change.groups.iter().find(|g| g.first_line == idx) {
+            let selected = selected_hunk.is_some_and(|h| group.hunk_ids.contains(&h));
+            lines.push(group_header(group, current_change, selected, theme));
+        }
+        row_hunks.resize(lines.len(), None);
+        if current_change.group_at(idx).is_some_and(|g| g.collapsed) {
+            continue;
+        }
+
+        let is_selected = selected_hunk.is_some() && dec.hunk_id == selected_hunk;
+        let (old_num, new_num) = line_numbers[idx];
+        let lines_before = lines.len();
 
         match dec.decoration_type {
             DecorationType::Deletion => {
                 // Show original text with strikethrough
                 let original = dec.original_text.as_deref().unwrap_or("");
-                let marker = match dec.accepted {
-                    Some(true) => "✓",
-                    Some(false) => "✗",
-                    None => "○",
-                };
+                let marker = theme.icons.status(dec.accepted);
                 let marker_style = match dec.accepted {
                     Some(true) => theme.status_accepted,
                     Some(false) => theme.status_rejected,
@@ -69,10 +212,7 @@ pub fn render_overlay_diff(
                 };
 
                 let line = Line::from(vec![
-                    Span::styled(
-                        format!("{:4} ", line_num),
-                        Style::default().fg(Color::Indexed(242)),
-                    ),
+                    number_span(old_num, new_num),
                     Span::styled(format!("{} ", marker), marker_style),
                     Span::styled(
                         format!("-{}", original),
@@ -85,11 +225,7 @@ pub fn render_overlay_diff(
             DecorationType::Addition => {
                 // Show new text with green background
                 let new_text = dec.new_text.as_deref().unwrap_or("");
-                let marker = match dec.accepted {
-                    Some(true) => "✓",
-                    Some(false) => "✗",
-                    None => "○",
-                };
+                let marker = theme.icons.status(dec.accepted);
                 let marker_style = match dec.accepted {
                     Some(true) => theme.status_accepted,
                     Some(false) => theme.status_rejected,
@@ -97,7 +233,7 @@ pub fn render_overlay_diff(
                 };
 
                 let line = Line::from(vec![
-                    Span::styled(format!("    "), Style::default()),
+                    number_span(old_num, new_num),
                     Span::styled(format!("{} ", marker), marker_style),
                     Span::styled(format!("+{}", new_text), theme.added_style),
                 ]);
@@ -108,11 +244,7 @@ pub fn render_overlay_diff(
                 // Show both old (strikethrough) and new (green) on consecutive lines
                 let original = dec.original_text.as_deref().unwrap_or("");
                 let new_text = dec.new_text.as_deref().unwrap_or("");
-                let marker = match dec.accepted {
-                    Some(true) => "✓",
-                    Some(false) => "✗",
-                    None => "○",
-                };
+                let marker = theme.icons.status(dec.accepted);
                 let marker_style = match dec.accepted {
                     Some(true) => theme.status_accepted,
                     Some(false) => theme.status_rejected,
@@ -121,10 +253,7 @@ pub fn render_overlay_diff(
 
                 // Old line (strikethrough)
                 lines.push(Line::from(vec![
-                    Span::styled(
-                        format!("{:4} ", line_num),
-                        Style::default().fg(Color::Indexed(242)),
-                    ),
+                    number_span(old_num, None),
                     Span::styled(format!("{} ", marker), marker_style),
                     Span::styled(
                         format!("-{}", original),
@@ -134,8 +263,8 @@ pub fn render_overlay_diff(
 
                 // New line (green)
                 lines.push(Line::from(vec![
-                    Span::styled(format!("    "), Style::default()),
-                    Span::styled(format!("  "), Style::default()),
+                    number_span(None, new_num),
+                    Span::styled("  ", Style::default()),
                     Span::styled(format!("+{}", new_text), theme.added_style),
                 ]));
             }
@@ -144,10 +273,7 @@ pub fn render_overlay_diff(
                 if !diff_state.folded_unchanged {
                     let content = dec.original_text.as_deref().unwrap_or("");
                     let line = Line::from(vec![
-                        Span::styled(
-                            format!("{:4} ", line_num),
-                            Style::default().fg(Color::Indexed(242)),
-                        ),
+                        number_span(old_num, new_num),
                         Span::styled("  ", Style::default()),
                         Span::styled(format!(" {}", content), theme.context_style),
                     ]);
@@ -155,29 +281,386 @@ pub fn render_overlay_diff(
                 }
             }
         }
+
+        if diff_state.wrap_lines && diff_state.viewport_width > 0 {
+            let width = diff_state.text_width();
+            let rows: Vec<Line> = lines
+                .drain(lines_before..)
+                .flat_map(|line| wrap_row(line, width, numbered, theme))
+                .collect();
+            lines.extend(rows);
+        }
+
+        let drifted = dec
+            .hunk_id
+            .is_some_and(|id| current_change.drifted_hunks.contains(&id));
+        let failing = dec
+            .hunk_id
+            .is_some_and(|id| current_change.test_failures.iter().any(|(h, _)| *h == id));
+        let flagged = dec
+            .hunk_id
+            .is_some_and(|id| current_change.flagged_hunks.iter().any(|(h, _)| *h == id));
+        let gutter = if is_selected {
+            Span::styled("▌", theme.selected_style)
+        } else if failing {
+            Span::styled(theme.icons.rejected, theme.error_style)
+        } else if flagged {
+            Span::styled(theme.icons.warning, theme.error_style)
+        } else if drifted {
+            Span::styled("!", theme.status_pending)
+        } else {
+            Span::raw(" ")
+        };
+        for line in &mut lines[lines_before..] {
+            // Changes that will never be written are shown greyed out
+            if current_change.ignored {
+                for span in &mut line.spans {
+                    span.style = theme.muted_style;
+                }
+            }
+            line.spans.insert(0, gutter.clone());
+        }
+        row_hunks.resize(lines.len(), dec.hunk_id);
     }
 
     // Footer with keybindings
     lines.push(Line::from(""));
     lines.push(Line::from(vec![Span::styled(
-        "[y] Accept line │ [n] Reject line │ [a] Accept all │ [r] Reject all │ ",
+        theme.icons.hints(
+            "[y] Accept hunk │ [n] Reject hunk │ [Y] Accept all │ [N] Reject all │ [.] Repeat │ ",
+        ),
+        theme.prompt_style,
+    )]));
+    lines.push(Line::from(vec![Span::styled(
+        theme
+            .icons
+            .hints("[j/k] Next/Prev hunk │ [gd] Definition │ [Enter] Apply accepted │ [Esc] Back"),
         theme.prompt_style,
     )]));
     lines.push(Line::from(vec![Span::styled(
-        "[j/k] Navigate │ [J/K] Next/Prev file │ [Enter] Apply accepted",
+        theme.icons.hints("[c] Comment │ [r] Rename file │ [R] Request fixes │ [o] Apply hunk & edit │ [X] Skip file │ ]p / [p: Next/prev pending │ [w] Wrap │ [s] Side by side │ [l] Line numbers"),
         theme.prompt_style,
     )]));
+    if !current_change.groups.is_empty() {
+        lines.push(Line::from(vec![Span::styled(
+            theme
+                .icons
+                .hints("[gy/gn] Accept/reject group │ [za] Fold group"),
+            theme.prompt_style,
+        )]));
+    }
+
+    // Tests failing inside the hunk under the cursor
+    let failing_here: Vec<&str> = current_change
+        .test_failures
+        .iter()
+        .filter(|(id, _)| Some(*id) == selected_hunk)
+        .map(|(_, test)| test.as_str())
+        .collect();
+    // Where the hunk under the cursor sits on each side, as in a unified diff
+    let range_here = current_change
+        .hunk_ranges
+        .iter()
+        .find(|(id, _, _)| Some(*id) == selected_hunk)
+        .map(|(_, old, new)| format!("· @@ -{} +{} @@ ", old, new))
+        .unwrap_or_default();
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border)
+        .title(format!(" Diff Review - {} {}", file_name, range_here));
+    if let Some((_, comment)) = current_change
+        .comments
+        .iter()
+        .find(|(id, _)| Some(*id) == selected_hunk)
+    {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" {} {} ", theme.icons.note, comment),
+            theme.status_pending,
+        )));
+    }
+    let flagged_here: Vec<&str> = current_change
+        .flagged_hunks
+        .iter()
+        .filter(|(id, _)| Some(*id) == selected_hunk)
+        .map(|(_, rule)| rule.as_str())
+        .collect();
+    if !flagged_here.is_empty() {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" {} {} ", theme.icons.warning, flagged_here.join(", ")),
+            theme.error_style,
+        )));
+    }
+    if let Some((_, rule)) = current_change
+        .auto_accepted
+        .iter()
+        .find(|(id, _)| Some(*id) == selected_hunk)
+    {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" auto-accepted (rule: {}) ", rule),
+            theme.status_accepted,
+        )));
+    }
+    if !failing_here.is_empty() {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(
+                " {} fails {} ",
+                theme.icons.rejected,
+                failing_here.join(", ")
+            ),
+            theme.error_style,
+        )));
+    }
 
+    let offset = diff_state.scroll_offset();
+    let inner = block.inner(area);
     let paragraph = Paragraph::new(lines)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(theme.border_style)
-                .title(format!(" Diff Review - {} ", file_name)),
-        )
-        .wrap(Wrap { trim: false });
+        .block(block)
+        .scroll((offset as u16, 0));
 
     frame.render_widget(paragraph, area);
+
+    // Scrolled past the file header: keep the file and hunk in view on the
+    // top row, naming the last hunk that starts at or above the row below it
+    if offset > 0 && inner.height > 1 {
+        let top = row_hunks.iter().take(offset + 2).rev().find_map(|h| *h);
+        let sticky = Rect { height: 1, ..inner };
+        frame.render_widget(Clear, sticky);
+        frame.render_widget(
+            Paragraph::new(sticky_header(diff_state, current_change, top, theme)),
+            sticky,
+        );
+    }
+}
+
+/// The sticky header row: file path and position, then the hunk's position
+/// in the file and its status
+fn sticky_header<'a>(
+    diff_state: &OverlayDiffState,
+    change: &ProposedChange,
+    hunk_id: Option<usize>,
+    theme: &Theme,
+) -> Line<'a> {
+    let mut spans = vec![Span::styled(
+        format!(
+            "┌─ {} ({}/{})",
+            change.file_path.display(),
+            diff_state.current_change_idx + 1,
+            diff_state.proposed_changes.len()
+        ),
+        theme.header_style,
+    )];
+    let mut hunks: Vec<usize> = change
+        .line_decorations
+        .iter()
+        .filter_map(|d| d.hunk_id)
+        .collect();
+    hunks.dedup();
+    if let Some(id) = hunk_id {
+        let position = hunks.iter().position(|h| *h == id).unwrap_or(0);
+        let accepted = change
+            .line_decorations
+            .iter()
+            .find(|d| d.hunk_id == Some(id))
+            .and_then(|d| d.accepted);
+        let (status, style) = match accepted {
+            Some(true) => ("accepted", theme.status_accepted),
+            Some(false) => ("rejected", theme.status_rejected),
+            None => ("pending", theme.status_pending),
+        };
+        let status = format!("{} {}", theme.icons.status(accepted), status);
+        spans.push(Span::styled(
+            format!(" · hunk {}/{} · ", position + 1, hunks.len()),
+            theme.header_style,
+        ));
+        spans.push(Span::styled(status, style));
+    }
+    Line::from(spans)
+}
+
+/// The current file's original and proposed content in two columns, aligned
+/// line by line. Scrolls with the unified view's offset.
+fn render_side_by_side(
+    frame: &mut Frame,
+    area: Rect,
+    diff_state: &OverlayDiffState,
+    file_name: &str,
+    theme: &Theme,
+    border: Style,
+) {
+    let change = &diff_state.proposed_changes[diff_state.current_change_idx];
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border)
+        .title(format!(" Diff Review - {} · side by side ", file_name))
+        .title_bottom(Line::from(Span::styled(
+            theme
+                .icons
+                .hints(" [s] unified diff │ [y/n] accept/reject hunk │ [Enter] apply "),
+            theme.prompt_style,
+        )));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let column = inner.width.saturating_sub(1) / 2;
+    let left = Rect {
+        width: column,
+        ..inner
+    };
+    let divider = Rect {
+        x: inner.x + column,
+        width: 1,
+        ..inner
+    };
+    let right = Rect {
+        x: divider.x + 1,
+        width: inner.width.saturating_sub(column + 1),
+        ..inner
+    };
+
+    let rows = crate::diff::side_by_side(&change.original_content, &change.proposed_content);
+    let offset = diff_state
+        .scroll_offset()
+        .min(rows.len().saturating_sub(inner.height as usize));
+    let side = |original: bool, changed| {
+        rows.iter()
+            .skip(offset)
+            .take(inner.height as usize)
+            .map(|row| {
+                let differs = row.0.map(|(_, l)| l) != row.1.map(|(_, l)| l);
+                match if original { row.0 } else { row.1 } {
+                    Some((number, text)) => Line::from(vec![
+                        Span::styled(format!("{:4} ", number), theme.muted_style),
+                        Span::styled(
+                            text.to_string(),
+                            if differs {
+                                changed
+                            } else {
+                                theme.context_style
+                            },
+                        ),
+                    ]),
+                    None => Line::from(Span::styled("     ╱", theme.border_style)),
+                }
+            })
+            .collect::<Vec<Line>>()
+    };
+
+    frame.render_widget(Paragraph::new(side(true, theme.removed_style)), left);
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(theme.icons.separator);
+            inner.height as usize
+        ])
+        .style(theme.border_style),
+        divider,
+    );
+    frame.render_widget(Paragraph::new(side(false, theme.added_style)), right);
+}
+
+/// Split `text` into rows of at most `width` columns
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let (first, mut rest) = crate::ui::split_at_width(text, width);
+    let mut rows = vec![first.to_string()];
+    while !rest.is_empty() {
+        let (row, tail) = crate::ui::split_at_width(rest, width);
+        rows.push(row.to_string());
+        rest = tail;
+    }
+    rows
+}
+
+/// Soft-wrap a diff line whose last span is the signed text; continuation rows
+/// keep its style and line up under the first row's text
+fn wrap_row(
+    line: Line<'static>,
+    width: usize,
+    numbered: bool,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let mut prefix = line.spans;
+    let Some(text) = prefix.pop() else {
+        return vec![Line::from(prefix)];
+    };
+    let mut chars = text.content.chars();
+    let sign: String = chars.next().map(String::from).unwrap_or_default();
+    let rest: String = chars.collect();
+
+    wrap_text(&rest, width)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let (mut row, lead) = if idx == 0 {
+                (prefix.clone(), sign.as_str())
+            } else {
+                // The number and marker columns, with ↪ under the new line number
+                let wrapped = theme.icons.wrapped;
+                let columns = if numbered {
+                    format!("{:>width$}   ", wrapped, width = NUMBER_COLUMNS - 1)
+                } else {
+                    format!("{} ", wrapped)
+                };
+                let marker = Span::styled(columns, theme.muted_style);
+                (vec![marker], " ")
+            };
+            row.push(Span::styled(format!("{}{}", lead, chunk), text.style));
+            Line::from(row)
+        })
+        .collect()
+}
+
+/// Header row for a group of related hunks: fold state, label and review progress
+fn group_header(
+    group: &HunkGroup,
+    change: &ProposedChange,
+    selected: bool,
+    theme: &Theme,
+) -> Line<'static> {
+    let status_of = |id: usize| {
+        change
+            .line_decorations
+            .iter()
+            .find(|d| d.hunk_id == Some(id))
+            .and_then(|d| d.accepted)
+    };
+    let count = |status: Option<bool>| {
+        group
+            .hunk_ids
+            .iter()
+            .filter(|&&id| status_of(id) == status)
+            .count()
+    };
+
+    let gutter = if selected {
+        Span::styled("▌", theme.selected_style)
+    } else {
+        Span::raw(" ")
+    };
+    let icons = theme.icons;
+    let fold = if group.collapsed {
+        icons.collapsed
+    } else {
+        icons.expanded
+    };
+    Line::from(vec![
+        gutter,
+        Span::styled(format!("     {} {}", fold, group.label), theme.header_style),
+        Span::styled(
+            format!(" · {} hunks ", group.hunk_ids.len()),
+            theme.context_style,
+        ),
+        Span::styled(
+            format!("{}{} ", count(Some(true)), icons.accepted),
+            theme.status_accepted,
+        ),
+        Span::styled(
+            format!("{}{} ", count(Some(false)), icons.rejected),
+            theme.status_rejected,
+        ),
+        Span::styled(
+            format!("{}{}", count(None), icons.pending),
+            theme.status_pending,
+        ),
+    ])
 }
 
 /// Convert hunks to overlay decorations
@@ -195,7 +678,11 @@ pub fn convert_hunks_to_overlay(
 
     // Process each hunk
     for hunk in hunks {
+        let mut old_cursor = hunk.old.indices().start;
         for change in &hunk.changes {
+            if let Some(old) = change.old_line_num {
+                old_cursor = old + 1;
+            }
             let decoration_type = match change.tag {
                 ChangeTag::Insert => DecorationType::Addition,
                 ChangeTag::Delete => DecorationType::Deletion,
@@ -206,6 +693,7 @@ pub fn convert_hunks_to_overlay(
 
             let decoration = LineDecoration {
                 line_number: line_num,
+                old_line: old_cursor,
                 decoration_type,
                 original_text: if matches!(change.tag, ChangeTag::Delete | ChangeTag::Equal) {
                     Some(change.content.clone())
@@ -218,6 +706,7 @@ pub fn convert_hunks_to_overlay(
                     None
                 },
                 accepted: None, // Start as pending
+                hunk_id: Some(hunk.id),
             };
 
             line_decorations.push(decoration);
@@ -231,5 +720,21 @@ pub fn convert_hunks_to_overlay(
         proposed_content,
         line_decorations,
         status: crate::state::ChangeStatus::Pending,
+        changed_on_disk: false,
+        drifted_hunks: Vec::new(),
+        stale: false,
+        ignored: false,
+        skipped: false,
+        test_failures: Vec::new(),
+        groups: Vec::new(),
+        comments: Vec::new(),
+        path_warning: None,
+        rewrite: None,
+        computing: None,
+        flagged_hunks: Vec::new(),
+        auto_accepted: Vec::new(),
+        hunk_ranges: hunks.iter().map(|h| (h.id, h.old, h.new)).collect(),
+        edit_choices: 0,
+        renamed_from: None,
     }
 }
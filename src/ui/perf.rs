@@ -0,0 +1,176 @@
+// src/ui/perf.rs - Latest timings of the slow paths (:perf)
+
+use crate::i18n::{t, tf};
+use crate::perf::Perf;
+use crate::scheduler::{Scheduler, TaskKind};
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table},
+    Frame,
+};
+use std::time::Duration;
+
+/// Render the timings overlay over `area`, with the tasks `scheduler` runs
+pub fn render_perf(
+    frame: &mut Frame,
+    area: Rect,
+    perf: &Perf,
+    scheduler: &Scheduler,
+    theme: &Theme,
+) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(t("perf.title"))
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let label = |text: &'static str| Span::styled(format!("{:<10}", text), theme.context_style);
+    let provider = match &perf.provider {
+        Some(run) => tf(
+            "perf.provider_run",
+            &[
+                ("provider", &run.provider),
+                ("wall", &millis(run.wall)),
+                ("tokens", &run.tokens_out),
+                ("rate", &format!("{:.0}", run.tokens_per_second())),
+                ("kb", &(run.output_bytes / 1024)),
+            ],
+        ),
+        None => t("perf.no_prompt").to_string(),
+    };
+    let parse = perf
+        .parse
+        .map_or_else(|| t("perf.no_parse").to_string(), millis);
+    let frames = match perf.frame_stats() {
+        Some((latest, avg, max)) => tf(
+            "perf.frames",
+            &[
+                ("latest", &millis(latest)),
+                ("avg", &millis(avg)),
+                ("max", &millis(max)),
+                ("samples", &crate::perf::FRAME_SAMPLES),
+            ],
+        ),
+        None => "-".to_string(),
+    };
+    let limit = match scheduler.limit() {
+        0 => t("perf.no_limit").to_string(),
+        limit => tf("perf.limit", &[("limit", &limit)]),
+    };
+    let mut summary = vec![
+        Line::from(vec![label(t("perf.provider")), Span::raw(provider)]),
+        Line::from(vec![label(t("perf.parse")), Span::raw(parse)]),
+        Line::from(vec![label(t("perf.frame")), Span::raw(frames)]),
+        Line::from(vec![label(t("perf.tasks")), Span::raw(limit)]),
+    ];
+    // Kinds that ran at all, each on its own line
+    for (kind, stats) in scheduler.stats() {
+        if stats == Default::default() {
+            continue;
+        }
+        summary.push(Line::from(vec![
+            Span::raw("  "),
+            label(kind_label(kind)),
+            Span::raw(tf(
+                "perf.task_stats",
+                &[
+                    ("running", &stats.running),
+                    ("queued", &stats.queued),
+                    ("finished", &stats.finished),
+                    ("wait", &millis(stats.longest_wait)),
+                ],
+            )),
+        ]));
+    }
+
+    let warning_rows = perf.warnings.len().min(6) as u16;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(summary.len() as u16 + 1),
+            Constraint::Min(3),
+            Constraint::Length(if warning_rows > 0 {
+                warning_rows + 1
+            } else {
+                0
+            }),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+    frame.render_widget(Paragraph::new(summary).style(theme.normal_style), chunks[0]);
+
+    // Slowest files first
+    let mut diffs: Vec<_> = perf.diffs.iter().collect();
+    diffs.sort_by_key(|d| std::cmp::Reverse(d.elapsed));
+    let rows = diffs.iter().map(|d| {
+        Row::new(vec![
+            Cell::from(millis(d.elapsed)),
+            Cell::from(d.lines.to_string()),
+            Cell::from(d.path.display().to_string()),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Min(10),
+        ],
+    )
+    .header(
+        Row::new(vec![t("perf.diff"), t("perf.lines"), t("perf.file")])
+            .style(theme.header_style.add_modifier(Modifier::BOLD)),
+    )
+    .style(theme.normal_style);
+    frame.render_widget(table, chunks[1]);
+
+    if warning_rows > 0 {
+        let warnings: Vec<Line> = std::iter::once(Line::styled(
+            tf(
+                "perf.warnings",
+                &[("path", &crate::perf::log_path().display())],
+            ),
+            theme.status_pending,
+        ))
+        .chain(
+            perf.warnings
+                .iter()
+                .rev()
+                .take(warning_rows as usize)
+                .map(|w| Line::styled(w.clone(), theme.context_style)),
+        )
+        .collect();
+        frame.render_widget(Paragraph::new(warnings), chunks[2]);
+    }
+
+    let footer = Paragraph::new(Line::from(Span::styled(
+        t("perf.footer"),
+        theme.muted_style,
+    )));
+    frame.render_widget(footer, chunks[3]);
+}
+
+fn kind_label(kind: TaskKind) -> &'static str {
+    t(match kind {
+        TaskKind::Detection => "perf.detection",
+        TaskKind::Prompt => "perf.prompt",
+        TaskKind::Hook => "perf.hook",
+        TaskKind::Diff => "perf.diff",
+    })
+}
+
+fn millis(duration: Duration) -> String {
+    if duration >= Duration::from_secs(10) {
+        format!("{:.1} s", duration.as_secs_f64())
+    } else {
+        format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+    }
+}
@@ -5,14 +5,14 @@
 
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph},
     Frame,
 };
 
-use crate::state::State;
+use crate::state::{PromptMode, State};
 use crate::ui::colors::Theme;
+use crate::ui::cursor;
 
 pub fn render_prompt_input(frame: &mut Frame, area: Rect, state: &State, theme: &Theme) {
     // Apply a subtle gradient-like backdrop by layering a translucent block.
@@ -27,39 +27,48 @@ pub fn render_prompt_input(frame: &mut Frame, area: Rect, state: &State, theme:
         .constraints([Constraint::Min(3), Constraint::Length(2)])
         .split(container);
 
-    // Build display text with cursor marker
-    let mut display_text = state.prompt_buffer.clone();
-    if state.cursor_position <= display_text.len() {
-        display_text.insert(state.cursor_position, '│');
-    } else {
-        display_text.push('│');
-    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style)
+        .title(match state.prompt_mode {
+            PromptMode::Edit => " Prompt ",
+            PromptMode::Ask => " Ask (answers only, no edits) ",
+        })
+        .title_alignment(Alignment::Left);
+    let inner = block.inner(vertical[0]);
 
-    let show_placeholder = state.prompt_buffer.is_empty();
+    // Wrapped here so the terminal cursor can be put where the text is
+    let text = &state.prompt_buffer;
+    let width = inner.width as usize;
+    let rows = cursor::wrap_rows(text, width);
+    let at = crate::input::editing::clamp(text, state.cursor_position);
+    let (row, column) = cursor::locate(text, &rows, at, width);
+    let scroll = cursor::scroll_to(row, inner.height);
+
+    let show_placeholder = text.is_empty();
     let placeholder = "Ask anything… (Shift+Enter for newline)";
 
     let paragraph = Paragraph::new(if show_placeholder {
-        placeholder.into()
+        vec![Line::from(placeholder)]
     } else {
-        display_text
+        rows.iter()
+            .map(|range| Line::from(&text[range.clone()]))
+            .collect()
     })
-    .wrap(Wrap { trim: false })
+    .scroll((scroll as u16, 0))
     .style(if show_placeholder {
-        Style::default().fg(Color::Rgb(120, 120, 120))
+        theme.muted_style
     } else {
         theme.prompt_style
     })
     .alignment(Alignment::Left)
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(theme.border_style)
-            .title(" Prompt ")
-            .title_alignment(Alignment::Left),
-    );
+    .block(block);
 
     frame.render_widget(paragraph, vertical[0]);
+    if state.prompt_focused() {
+        frame.set_cursor_position(cursor::position(inner, scroll, row, column));
+    }
 
     // Footer row: provider/model info + send hint
     let provider = state
@@ -73,16 +82,47 @@ pub fn render_prompt_input(frame: &mut Frame, area: Rect, state: &State, theme:
         state.status_info.model.clone()
     };
 
+    // Context budget meter: prompt + attachments against the provider limit
+    let used = state.context_tokens_used();
+    let limit = state.context_token_limit();
+    let budget_style = if used > limit {
+        theme.error_style
+    } else if used * 4 > limit * 3 {
+        theme.status_pending
+    } else {
+        theme.status_accepted
+    };
+    let mut budget_text = format!(
+        "ctx {}/{}",
+        crate::context::format_tokens(used),
+        crate::context::format_tokens(limit)
+    );
+    if !state.context_attachments.is_empty() {
+        budget_text.push_str(&format!(" ({} files)", state.context_attachments.len()));
+    }
+    if used > limit {
+        // Providers that read the files themselves get them whole; only
+        // inlined contents are cut to fit
+        let reads_files = state
+            .provider
+            .as_ref()
+            .is_some_and(|p| p.accepts_context_files());
+        budget_text.push_str(if reads_files {
+            " over limit"
+        } else {
+            " truncating"
+        });
+    }
+
     let footer_line = Line::from(vec![
-        Span::styled("Agent ", Style::default().fg(Color::Rgb(160, 160, 160))),
-        Span::styled(provider, Style::default().fg(Color::Rgb(120, 170, 255))),
+        Span::styled("Agent ", theme.muted_style),
+        Span::styled(provider, theme.accent_style),
         Span::raw(" • "),
-        Span::styled(model, Style::default().fg(Color::Rgb(160, 200, 255))),
+        Span::styled(model, theme.accent_style),
+        Span::raw(" • "),
+        Span::styled(budget_text, budget_style),
         Span::raw("   "),
-        Span::styled(
-            "Ctrl+Enter send",
-            Style::default().fg(Color::Rgb(140, 140, 140)),
-        ),
+        Span::styled("Ctrl+A ask/edit • Ctrl+Enter send", theme.muted_style),
     ]);
 
     let footer = Paragraph::new(footer_line)
@@ -93,9 +133,60 @@ pub fn render_prompt_input(frame: &mut Frame, area: Rect, state: &State, theme:
                 .border_type(BorderType::Rounded)
                 .border_style(theme.border_style)
                 .title(" Compose ")
-                .title_style(Style::default().fg(Color::Rgb(160, 160, 160))),
+                .title_style(theme.muted_style),
         )
         .alignment(Alignment::Left);
 
     frame.render_widget(footer, vertical[1]);
+
+    if !state.symbol_suggestions.is_empty() {
+        render_symbol_suggestions(frame, vertical[0], state, theme);
+    }
+}
+
+/// `#symbol` completions, floating just above the input box
+fn render_symbol_suggestions(frame: &mut Frame, input: Rect, state: &State, theme: &Theme) {
+    let height = (state.symbol_suggestions.len() as u16 + 2).min(input.y);
+    if height < 3 {
+        return;
+    }
+    let area = Rect {
+        x: input.x,
+        y: input.y - height,
+        width: input.width.min(70),
+        height,
+    };
+
+    let items: Vec<ListItem> = state
+        .symbol_suggestions
+        .iter()
+        .enumerate()
+        .map(|(idx, symbol)| {
+            let style = if idx == state.symbol_selected {
+                theme.selected_style
+            } else {
+                theme.normal_style
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("#{}", symbol.name), style),
+                Span::styled(format!("  {} ", symbol.kind), theme.context_style),
+                Span::styled(
+                    format!("{}:{}", symbol.path.display(), symbol.line),
+                    theme.context_style,
+                ),
+            ]))
+        })
+        .collect();
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(theme.border_style)
+                .title(" Symbols (Tab to insert) "),
+        ),
+        area,
+    );
 }
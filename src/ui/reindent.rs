@@ -0,0 +1,83 @@
+// src/ui/reindent.rs - Preview of a hunk's added lines reindented by `=`
+
+use crate::reindent::Reindent;
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// Render the popup in the bottom-right corner of `area`, above the status
+/// and input rows: each added line as it will be written, its leading
+/// whitespace shown, with the line as proposed above the ones that change
+pub fn render_reindent(frame: &mut Frame, area: Rect, reindent: &Reindent, theme: &Theme) {
+    let whitespace = theme.muted_style;
+    let line = |prefix: &'static str, text: &str, style: Style| {
+        let text = text.trim_end_matches('\n');
+        let body = text.trim_start_matches([' ', '\t']);
+        let indent: String = text[..text.len() - body.len()]
+            .chars()
+            .map(|c| {
+                if c == '\t' {
+                    format!("{:<4}", theme.icons.arrow)
+                } else {
+                    theme.icons.dot.to_string()
+                }
+            })
+            .collect();
+        Line::from(vec![
+            Span::styled(prefix, style),
+            Span::styled(indent, whitespace),
+            Span::styled(body.to_string(), style),
+        ])
+    };
+    let mut lines = Vec::new();
+    for added in &reindent.lines {
+        if added.before == added.after {
+            lines.push(line("  ", &added.after, theme.normal_style));
+        } else {
+            lines.push(line("- ", &added.before, theme.removed_style));
+            lines.push(line("+ ", &added.after, theme.added_style));
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "= accept reindented · Esc keep as proposed",
+        whitespace,
+    )));
+
+    let title = format!(" Reindent {} lines ", reindent.changed());
+    let content_width = lines.iter().map(Line::width).max().unwrap_or(0);
+    let width = (content_width.max(title.len()) as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    if height < 3 {
+        return;
+    }
+    let popup = Rect::new(
+        area.right().saturating_sub(width + 1),
+        area.bottom().saturating_sub(height + 4),
+        width,
+        height,
+    );
+
+    frame.render_widget(Clear, popup);
+    let block = Block::default()
+        .title(title)
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+    frame.render_widget(
+        Paragraph::new(lines),
+        Rect {
+            x: inner.x + 1,
+            width: inner.width.saturating_sub(1),
+            ..inner
+        },
+    );
+}
@@ -1,16 +1,23 @@
 // src/ui/renderers.rs - Ratatui-based rendering functions
 
-use crate::state::{DetectionState, State};
+use crate::file_ops::trash::trash_root;
+use crate::file_ops::BackupSet;
+use crate::i18n::{t, tf};
+use crate::state::{ChangeType, DetectionState, State};
 use crate::ui::colors::Theme;
-use crate::ui::layout::{centered_dialog, main_layout};
-use crate::ui::logo::{centered_rect, render_logo_text};
+use crate::ui::cursor;
+use crate::ui::layout::{
+    centered_dialog, fit_dialog, main_layout, DIALOG_MAX_PERCENT, MIN_HEIGHT, MIN_WIDTH,
+};
+use crate::ui::logo::{centered_rect, logo_for, logo_size, render_logo_text};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::Modifier,
     text::{Line, Span},
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
+use unicode_width::UnicodeWidthStr;
 
 /// Render the splash screen with logo (full screen, centered)
 pub fn render_splash(frame: &mut Frame, theme: &Theme) {
@@ -19,14 +26,42 @@ pub fn render_splash(frame: &mut Frame, theme: &Theme) {
     // Clear the screen with dark background
     frame.render_widget(Clear, area);
 
-    // Center the logo
-    let logo_height = 10u16;
-    let logo_width = 70u16;
+    // Center the largest logo that fits
+    let (logo_width, logo_height) = logo_size(logo_for(area.width, area.height));
     let logo_area = centered_rect(area, logo_width, logo_height);
 
     render_logo_text(frame, logo_area);
 }
 
+/// Render the screen shown instead of the UI when the terminal is smaller
+/// than [`MIN_WIDTH`] x [`MIN_HEIGHT`]
+pub fn render_too_small(frame: &mut Frame, theme: &Theme) {
+    let area = frame.area();
+    frame.render_widget(Clear, area);
+
+    let text = tf(
+        "layout.too_small",
+        &[
+            ("need", &format!("{}x{}", MIN_WIDTH, MIN_HEIGHT)),
+            ("have", &format!("{}x{}", area.width, area.height)),
+        ],
+    );
+    let height = (text.chars().count() as u16)
+        .div_ceil(area.width.max(1))
+        .saturating_add(1)
+        .min(area.height);
+    let message = Paragraph::new(text)
+        .style(theme.normal_style)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    let message_area = Rect {
+        y: area.y + area.height.saturating_sub(height) / 2,
+        height,
+        ..area
+    };
+    frame.render_widget(message, message_area);
+}
+
 /// Render provider selection screen - OpenCode style with centered logo and dialog
 pub fn render_provider_select(frame: &mut Frame, state: &State, theme: &Theme) {
     let area = frame.area();
@@ -65,7 +100,7 @@ pub fn render_provider_select(frame: &mut Frame, state: &State, theme: &Theme) {
 
     if state.detection_state == DetectionState::InProgress {
         // Loading state with spinner
-        let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+        let spinner_chars = theme.icons.spinner;
         let frame_idx = (std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
@@ -75,15 +110,15 @@ pub fn render_provider_select(frame: &mut Frame, state: &State, theme: &Theme) {
 
         let loading_text = format!("{} Detecting AI providers...", spinner_chars[frame_idx]);
         let loading = Paragraph::new(loading_text)
-            .style(Style::default().fg(Color::Gray))
+            .style(theme.normal_style)
             .alignment(Alignment::Center)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_type(ratatui::widgets::BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::DarkGray))
+                    .border_style(theme.border_style)
                     .title(" Select Provider ")
-                    .title_style(Style::default().fg(Color::White)),
+                    .title_style(theme.normal_style),
             );
         frame.render_widget(loading, dialog_rect);
     } else if state.available_providers.is_empty() {
@@ -91,28 +126,25 @@ pub fn render_provider_select(frame: &mut Frame, state: &State, theme: &Theme) {
             Line::from(""),
             Line::from(Span::styled(
                 "No AI providers detected.",
-                Style::default().fg(Color::Gray),
+                theme.normal_style,
             )),
             Line::from(""),
+            Line::from(Span::styled("Please install one of:", theme.muted_style)),
             Line::from(Span::styled(
-                "Please install one of:",
-                Style::default().fg(Color::DarkGray),
+                format!("  {} Claude Code (claude)", theme.icons.bullet),
+                theme.muted_style,
             )),
             Line::from(Span::styled(
-                "  • Claude Code (claude)",
-                Style::default().fg(Color::DarkGray),
+                format!("  {} Aider (aider)", theme.icons.bullet),
+                theme.muted_style,
             )),
             Line::from(Span::styled(
-                "  • Aider (aider)",
-                Style::default().fg(Color::DarkGray),
+                format!("  {} GitHub Copilot CLI (copilot)", theme.icons.bullet),
+                theme.muted_style,
             )),
             Line::from(Span::styled(
-                "  • GitHub Copilot CLI (copilot)",
-                Style::default().fg(Color::DarkGray),
-            )),
-            Line::from(Span::styled(
-                "  • Kiro CLI (kiro)",
-                Style::default().fg(Color::DarkGray),
+                format!("  {} Kiro CLI (kiro)", theme.icons.bullet),
+                theme.muted_style,
             )),
         ];
         let text = Paragraph::new(no_providers)
@@ -121,9 +153,9 @@ pub fn render_provider_select(frame: &mut Frame, state: &State, theme: &Theme) {
                 Block::default()
                     .borders(Borders::ALL)
                     .border_type(ratatui::widgets::BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::DarkGray))
+                    .border_style(theme.border_style)
                     .title(" Select Provider ")
-                    .title_style(Style::default().fg(Color::White)),
+                    .title_style(theme.normal_style),
             );
         frame.render_widget(text, dialog_rect);
     } else {
@@ -134,14 +166,16 @@ pub fn render_provider_select(frame: &mut Frame, state: &State, theme: &Theme) {
             .enumerate()
             .map(|(idx, provider)| {
                 let is_selected = idx == state.selected_provider_idx;
-                let marker = if is_selected { "▷ " } else { "  " };
+                let marker = if is_selected {
+                    format!("{} ", theme.icons.pointer)
+                } else {
+                    "  ".to_string()
+                };
 
                 let style = if is_selected {
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD)
+                    theme.normal_style.add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::Gray)
+                    theme.normal_style
                 };
 
                 // Show provider name and command in parentheses
@@ -149,7 +183,7 @@ pub fn render_provider_select(frame: &mut Frame, state: &State, theme: &Theme) {
                 let line = Line::from(vec![
                     Span::styled(marker, style),
                     Span::styled(&provider.name, style),
-                    Span::styled(cmd_suffix, Style::default().fg(Color::DarkGray)),
+                    Span::styled(cmd_suffix, theme.muted_style),
                 ]);
                 ListItem::new(line)
             })
@@ -159,9 +193,15 @@ pub fn render_provider_select(frame: &mut Frame, state: &State, theme: &Theme) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(ratatui::widgets::BorderType::Rounded)
-                .border_style(Style::default().fg(Color::DarkGray))
+                .border_style(theme.border_style)
                 .title(" Select Provider ")
-                .title_style(Style::default().fg(Color::White)),
+                .title_style(theme.normal_style)
+                .title_bottom(Line::from(Span::styled(
+                    theme
+                        .icons
+                        .hints(" j/k move │ Enter use │ r rescan │ Esc back │ q quit "),
+                    theme.muted_style,
+                ))),
         );
 
         frame.render_widget(list, dialog_rect);
@@ -185,7 +225,7 @@ pub fn render_provider_select(frame: &mut Frame, state: &State, theme: &Theme) {
         state.status_info.session_cost
     );
     let status = Paragraph::new(status_text)
-        .style(Style::default().fg(Color::DarkGray))
+        .style(theme.muted_style)
         .alignment(Alignment::Left);
     frame.render_widget(status, status_area);
 }
@@ -214,35 +254,44 @@ pub fn render_prompt_entry(frame: &mut Frame, state: &State, theme: &Theme) {
     // Content - Prompt input
     let input_area = centered_dialog(content, content.width.saturating_sub(4), 8);
 
-    // Render prompt text with cursor
-    let mut display_text = state.prompt_buffer.clone();
-    if state.cursor_position < display_text.len() {
-        display_text.insert(state.cursor_position, '│');
-    } else {
-        display_text.push('│');
-    }
-
-    let input_paragraph = Paragraph::new(display_text)
+    // Render prompt text, with the terminal cursor in it
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.prompt_style)
+        .title(" Enter your prompt ");
+    let inner = block.inner(input_area);
+    let text = &state.prompt_buffer;
+    let width = inner.width as usize;
+    let rows = cursor::wrap_rows(text, width);
+    let at = crate::input::editing::clamp(text, state.cursor_position);
+    let (row, column) = cursor::locate(text, &rows, at, width);
+    let scroll = cursor::scroll_to(row, inner.height);
+    let lines: Vec<Line> = rows
+        .iter()
+        .map(|range| Line::from(&text[range.clone()]))
+        .collect();
+
+    let input_paragraph = Paragraph::new(lines)
         .style(theme.normal_style)
-        .wrap(Wrap { trim: false })
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(theme.prompt_style)
-                .title(" Enter your prompt "),
-        );
+        .scroll((scroll as u16, 0))
+        .block(block);
 
     frame.render_widget(input_paragraph, input_area);
+    frame.set_cursor_position(cursor::position(inner, scroll, row, column));
 
     // Footer
-    let footer_text = Paragraph::new("Enter: submit │ Esc: back │ Ctrl+C: quit")
-        .style(theme.header_style)
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(theme.border_style),
-        );
+    let footer_text = Paragraph::new(
+        theme
+            .icons
+            .hints("Enter: submit │ Esc: back │ Ctrl+C: quit"),
+    )
+    .style(theme.header_style)
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border_style),
+    );
     frame.render_widget(footer_text, footer);
 }
 
@@ -251,7 +300,7 @@ pub fn render_processing(frame: &mut Frame, theme: &Theme) {
     let area = frame.area();
     let loading_area = centered_dialog(area, 40, 5);
 
-    let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+    let spinner_chars = theme.icons.spinner;
     let frame_idx = (std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
@@ -272,46 +321,127 @@ pub fn render_processing(frame: &mut Frame, theme: &Theme) {
     frame.render_widget(paragraph, loading_area);
 }
 
-/// Render error screen - clean OpenCode style
-pub fn render_error(frame: &mut Frame, state: &State, theme: &Theme) {
+/// The error message wrapped to `width` columns, one string per dialog row
+pub fn error_rows(message: &str, width: usize) -> Vec<String> {
+    message
+        .lines()
+        .flat_map(|line| {
+            let wrapped = textwrap::wrap(line, width.max(1));
+            if wrapped.is_empty() {
+                vec![String::new()]
+            } else {
+                wrapped.into_iter().map(|row| row.into_owned()).collect()
+            }
+        })
+        .collect()
+}
+
+/// Render error screen - clean OpenCode style. The dialog grows with the
+/// message and scrolls what doesn't fit; returns the message's rows, how
+/// many show and the width they were wrapped to.
+pub fn render_error(frame: &mut Frame, state: &State, theme: &Theme) -> (usize, usize, usize) {
+    let Some(error) = &state.last_error else {
+        return (0, 0, 0);
+    };
     let area = frame.area();
-    let error_area = centered_dialog(area, 60, 10);
+    let hint = match (&state.login_retry, &state.failed_output) {
+        (Some(retry), _) => tf("error.login_hint", &[("command", &retry.command.join(" "))]),
+        (None, Some(_)) => theme.icons.hints(t("error.output_hint")),
+        (None, None) => t("error.dismiss_hint").to_string(),
+    };
+
+    // As wide as the longest line, within the screen
+    let max_inner = (area.width * DIALOG_MAX_PERCENT / 100).saturating_sub(4) as usize;
+    let natural = error
+        .message
+        .lines()
+        .chain([error.title.as_str(), hint.as_str()])
+        .map(UnicodeWidthStr::width)
+        .max()
+        .unwrap_or(0);
+    let inner_width = natural.clamp(56.min(max_inner), max_inner.max(1));
+    let rows = error_rows(&error.message, inner_width);
+    // Blank, title and blank above the message; scroll note and hint below
+    let (error_area, visible) = fit_dialog(area, inner_width as u16 + 4, rows.len(), 5);
 
-    // Clear background
     frame.render_widget(Clear, error_area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(ratatui::widgets::BorderType::Rounded)
+        .border_style(theme.error_style)
+        .title(t("error.title"))
+        .title_style(theme.error_style);
+    let inner = block.inner(error_area);
+    frame.render_widget(block, error_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(2),
+        ])
+        .split(inner);
 
-    if let Some(error) = &state.last_error {
-        let error_lines = vec![
+    frame.render_widget(
+        Paragraph::new(vec![
             Line::from(""),
-            Line::from(Span::styled(
-                &error.title,
-                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-            )),
-            Line::from(""),
-            Line::from(Span::styled(
-                error.message.clone(),
-                Style::default().fg(Color::White),
-            )),
-            Line::from(""),
-            Line::from(Span::styled(
-                "Press any key to continue...",
-                Style::default().fg(Color::DarkGray),
-            )),
-        ];
+            Line::from(Span::styled(&error.title, theme.error_style)),
+        ])
+        .alignment(Alignment::Center),
+        chunks[0],
+    );
 
-        let paragraph = Paragraph::new(error_lines)
-            .alignment(Alignment::Center)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_type(ratatui::widgets::BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::Red))
-                    .title(" Error ")
-                    .title_style(Style::default().fg(Color::Red)),
-            );
+    // One line is centered like the title; more read better from the left
+    let scroll = &state.error_scroll;
+    let offset = scroll.offset.min(rows.len().saturating_sub(visible));
+    let selected = scroll.selected().unwrap_or_default();
+    let body: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(visible)
+        .map(|(idx, row)| {
+            let style = if selected.contains(&idx) {
+                theme.normal_style.add_modifier(Modifier::REVERSED)
+            } else {
+                theme.normal_style
+            };
+            Line::from(Span::styled(row.as_str(), style))
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(body).alignment(if rows.len() > 1 {
+            Alignment::Left
+        } else {
+            Alignment::Center
+        }),
+        chunks[1],
+    );
 
-        frame.render_widget(paragraph, error_area);
-    }
+    let note = if scroll.selection.is_some() {
+        t("error.selecting").to_string()
+    } else if rows.len() > visible {
+        tf(
+            "error.scroll",
+            &[
+                ("first", &(offset + 1)),
+                ("last", &(offset + visible)),
+                ("count", &rows.len()),
+            ],
+        )
+    } else {
+        t("error.copy_hint").to_string()
+    };
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(Span::styled(note, theme.muted_style)),
+            Line::from(Span::styled(hint, theme.muted_style)),
+        ])
+        .alignment(Alignment::Center),
+        chunks[2],
+    );
+
+    (rows.len(), visible, inner_width)
 }
 
 /// Render diff review screen (stub for now)
@@ -348,68 +478,331 @@ pub fn render_diff_review(frame: &mut Frame, state: &State, theme: &Theme) {
     frame.render_widget(paragraph, content);
 
     // Footer
-    let footer_text = Paragraph::new("j/k: navigate │ a/r: accept/reject │ Enter: apply │ q: quit")
-        .style(theme.header_style)
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(theme.border_style),
-        );
+    let footer_text = Paragraph::new(
+        theme
+            .icons
+            .hints("j/k: navigate │ a/r: accept/reject │ Enter: apply │ q: quit"),
+    )
+    .style(theme.header_style)
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border_style),
+    );
     frame.render_widget(footer_text, footer);
 }
 
-/// Render confirmation dialog - clean OpenCode style
-pub fn render_confirmation(frame: &mut Frame, state: &State, theme: &Theme) {
+/// Render confirmation dialog - clean OpenCode style. The dialog grows with
+/// its text and scrolls what doesn't fit above the y/n row; returns the rows,
+/// how many show and the width of the text.
+pub fn render_confirmation(
+    frame: &mut Frame,
+    state: &State,
+    theme: &Theme,
+) -> (usize, usize, usize) {
     let area = frame.area();
-    let dialog_area = centered_dialog(area, 50, 8);
-
-    // Clear background
+    let max_inner = (area.width * DIALOG_MAX_PERCENT / 100).saturating_sub(4) as usize;
+    let (text, inner_width) = confirmation_text(state, theme, max_inner);
+
+    let answer = Line::from(vec![
+        Span::styled("y", theme.status_accepted),
+        Span::styled(t("confirm.yes"), theme.muted_style),
+        Span::styled("n", theme.status_rejected),
+        Span::styled(t("confirm.no"), theme.muted_style),
+        Span::styled("Esc", theme.status_pending),
+        Span::styled(t("confirm.cancel"), theme.muted_style),
+    ]);
+
+    // The y/n row and a scroll note stay put below the text
+    let (dialog_area, visible) = fit_dialog(area, inner_width as u16 + 4, text.len(), 2);
     frame.render_widget(Clear, dialog_area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(ratatui::widgets::BorderType::Rounded)
+        .border_style(theme.border_style)
+        .title(t("confirm.title"))
+        .title_style(theme.normal_style);
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(2)])
+        .split(inner);
+
+    let scroll = &state.confirmation_scroll;
+    let count = text.len();
+    let offset = scroll.offset.min(count.saturating_sub(visible));
+    let selected = scroll.selected().unwrap_or_default();
+    let body: Vec<Line> = text
+        .into_iter()
+        .enumerate()
+        .skip(offset)
+        .take(visible)
+        .map(|(idx, line)| {
+            if selected.contains(&idx) {
+                line.patch_style(Modifier::REVERSED)
+            } else {
+                line
+            }
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(body).alignment(Alignment::Center), chunks[0]);
+    let note = if scroll.selection.is_some() {
+        t("confirm.selecting").to_string()
+    } else if count > visible {
+        tf(
+            "confirm.scroll",
+            &[
+                ("first", &(offset + 1)),
+                ("last", &(offset + visible)),
+                ("count", &count),
+            ],
+        )
+    } else {
+        t("confirm.copy_hint").to_string()
+    };
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(Span::styled(note, theme.muted_style)),
+            answer,
+        ])
+        .alignment(Alignment::Center),
+        chunks[1],
+    );
+
+    (count, visible, inner_width)
+}
+
+/// The confirmation's rows, at most `max_inner` columns wide, and the width
+/// they were laid out to. Drawn by [`render_confirmation`]; copying selected
+/// rows lays them out again at the width recorded then.
+pub fn confirmation_text(
+    state: &State,
+    theme: &Theme,
+    max_inner: usize,
+) -> (Vec<Line<'static>>, usize) {
+    let files = state.apply_summary();
+
+    // Say which `confirm_on` rule triggered the dialog, if one did
+    let reasons = state.confirmation_reasons();
+    let detail = match reasons.as_slice() {
+        [] => t("confirm.modifies_files").to_string(),
+        [reason] => reason.clone(),
+        [reason, rest @ ..] => tf(
+            "confirm.more_reasons",
+            &[("reason", reason), ("count", &rest.len())],
+        ),
+    };
 
-    let text = vec![
+    // Which files will be written, and whether one failing undoes the others
+    let (to_write, skipped) = state.apply_plan();
+    let mut plan = tf("confirm.files_to_write", &[("count", &to_write)]);
+    if skipped > 0 {
+        plan.push_str(&tf("confirm.skipped", &[("count", &skipped)]));
+    }
+    plan.push_str(match state.config.general.apply_mode {
+        crate::config::ApplyMode::Atomic => t("confirm.atomic"),
+        crate::config::ApplyMode::PerFile => t("confirm.per_file"),
+    });
+
+    let mut text = vec![
         Line::from(""),
-        Line::from(Span::styled(
-            "Apply accepted changes?",
-            Style::default().fg(Color::White),
-        )),
+        Line::from(Span::styled(t("confirm.question"), theme.normal_style)),
         Line::from(""),
+        Line::from(Span::styled(detail, theme.muted_style)),
+        Line::from(Span::styled(plan, theme.muted_style)),
+    ];
+
+    // Flagged hunks are applied only after a second `y`
+    let flagged = state.flagged_accepted().len();
+    text.push(if flagged == 0 {
+        Line::from("")
+    } else if state.guard_confirmed {
         Line::from(Span::styled(
-            "This will modify the files on disk.",
-            Style::default().fg(Color::DarkGray),
-        )),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled(
-                "y",
-                Style::default()
-                    .fg(Color::Green)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled("/Yes  ", Style::default().fg(Color::DarkGray)),
-            Span::styled(
-                "n",
-                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            tf(
+                "confirm.flagged_again",
+                &[("icon", &theme.icons.warning), ("count", &flagged)],
             ),
-            Span::styled("/No  ", Style::default().fg(Color::DarkGray)),
-            Span::styled(
-                "Esc",
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+            theme.error_style,
+        ))
+    } else {
+        Line::from(Span::styled(
+            tf(
+                "confirm.flagged",
+                &[("icon", &theme.icons.warning), ("count", &flagged)],
             ),
-            Span::styled("/Cancel", Style::default().fg(Color::DarkGray)),
-        ]),
-    ];
+            theme.error_style,
+        ))
+    });
+    text.push(Line::from(""));
+
+    // Files that parse now but would not once written
+    if !state.syntax_problems.is_empty() {
+        for problem in &state.syntax_problems {
+            text.push(
+                Line::from(vec![
+                    Span::styled(
+                        tf(
+                            "confirm.syntax_error",
+                            &[
+                                ("icon", &theme.icons.rejected),
+                                ("path", &problem.path.display()),
+                            ],
+                        ),
+                        theme.error_style,
+                    ),
+                    Span::styled(format!(" {}", problem.message), theme.muted_style),
+                ])
+                .alignment(Alignment::Left),
+            );
+        }
+        text.push(Line::from(""));
+    }
 
-    let paragraph = Paragraph::new(text).alignment(Alignment::Center).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_type(ratatui::widgets::BorderType::Rounded)
-            .border_style(Style::default().fg(Color::DarkGray))
-            .title(" Confirmation ")
-            .title_style(Style::default().fg(Color::White)),
+    // The checklist, toggled by number; auto-checked items say so
+    if !state.checklist.is_empty() {
+        let unchecked = state.unchecked_required();
+        text.push(Line::from(Span::styled(
+            if unchecked > 0 {
+                tf("confirm.checklist_required", &[("count", &unchecked)])
+            } else {
+                t("confirm.checklist").to_string()
+            },
+            if unchecked > 0 {
+                theme.status_pending
+            } else {
+                theme.normal_style
+            },
+        )));
+        for (i, entry) in state.checklist.iter().enumerate() {
+            let (mark, mark_style) = if entry.checked {
+                ("[x]", theme.status_accepted)
+            } else {
+                ("[ ]", theme.muted_style)
+            };
+            let mut spans = vec![
+                Span::styled(format!("{} ", i + 1), theme.status_pending),
+                Span::styled(mark, mark_style),
+                Span::styled(format!(" {}", entry.label), theme.normal_style),
+            ];
+            if entry.checked && !entry.manual {
+                spans.push(Span::styled(
+                    format!(" {}", t("confirm.auto_checked")),
+                    theme.muted_style,
+                ));
+            }
+            text.push(Line::from(spans).alignment(Alignment::Left));
+        }
+        text.push(Line::from(""));
+    }
+
+    // One row per file: badge, path, accepted hunks and line delta
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let rows: Vec<_> = files
+        .iter()
+        .map(|file| {
+            let (badge, badge_style) = match file.change_type {
+                _ if file.renamed_from.is_some() => (t("confirm.renamed"), theme.accent_style),
+                ChangeType::Create => (t("confirm.created"), theme.status_accepted),
+                ChangeType::Modify => (t("confirm.modified"), theme.status_pending),
+                ChangeType::Delete => (t("confirm.deleted"), theme.status_rejected),
+            };
+            let counts = if file.ignored {
+                t("confirm.ignored").to_string()
+            } else {
+                format!(
+                    "{} +{} -{}",
+                    tf("confirm.hunks", &[("count", &file.hunks)]),
+                    file.added,
+                    file.removed
+                )
+            };
+            let relative = |path: &std::path::Path| {
+                path.strip_prefix(&cwd)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string()
+            };
+            let path = match &file.renamed_from {
+                Some(from) => format!(
+                    "{} {} {}",
+                    relative(from),
+                    theme.icons.arrow,
+                    relative(&file.path)
+                ),
+                None => relative(&file.path),
+            };
+            (badge, badge_style, path, counts, file.ignored)
+        })
+        .collect();
+    let file_lines_at = text.len();
+
+    // Totals and where the old contents end up
+    let written: Vec<_> = files.iter().filter(|f| !f.ignored).collect();
+    text.push(Line::from(Span::styled(
+        tf(
+            "confirm.total",
+            &[
+                ("added", &written.iter().map(|f| f.added).sum::<usize>()),
+                ("removed", &written.iter().map(|f| f.removed).sum::<usize>()),
+                ("files", &written.len()),
+            ],
+        ),
+        theme.normal_style,
+    )));
+    let backups = if state.config.general.create_backups {
+        tf(
+            "confirm.backups",
+            &[("path", &BackupSet::backup_dir().display())],
+        )
+    } else {
+        t("confirm.no_backups").to_string()
+    };
+    text.push(Line::from(Span::styled(backups, theme.muted_style)));
+    let deletes = written.iter().any(|f| f.change_type == ChangeType::Delete);
+    text.push(Line::from(if deletes && state.git_review.is_none() {
+        Span::styled(
+            tf("confirm.trash", &[("path", &trash_root().display())]),
+            theme.muted_style,
+        )
+    } else {
+        Span::raw("")
+    }));
+
+    // As wide as the widest line, paths included, within the screen
+    let natural = rows
+        .iter()
+        .map(|(badge, _, path, counts, _)| {
+            badge.len() + UnicodeWidthStr::width(path.as_str()) + counts.len() + 3
+        })
+        .chain(text.iter().map(Line::width))
+        .max()
+        .unwrap_or(0);
+    let inner_width = natural.clamp(56.min(max_inner), max_inner.max(1));
+    let file_lines = rows
+        .into_iter()
+        .map(|(badge, badge_style, path, counts, ignored)| {
+            let path_width = inner_width.saturating_sub(badge.len() + counts.len() + 3);
+            let path = crate::ui::truncate_line(&path, path_width);
+            let padding = path_width.saturating_sub(UnicodeWidthStr::width(path.as_str()));
+            let path_style = if ignored {
+                theme.muted_style
+            } else {
+                theme.normal_style
+            };
+            Line::from(vec![
+                Span::styled(format!("{} ", badge), badge_style),
+                Span::styled(path, path_style),
+                Span::raw(" ".repeat(padding + 1)),
+                Span::styled(counts, theme.muted_style),
+            ])
+            .alignment(Alignment::Left)
+        });
+    text.splice(
+        file_lines_at..file_lines_at,
+        file_lines.chain([Line::from("")]),
     );
 
-    frame.render_widget(paragraph, dialog_area);
+    (text, inner_width)
 }
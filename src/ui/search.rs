@@ -10,6 +10,7 @@ use ratatui::{
 };
 
 /// Search mode state
+#[derive(Default)]
 pub struct SearchState {
     pub query: String,
     pub cursor_pos: usize,
@@ -17,17 +18,6 @@ pub struct SearchState {
     pub matches: Vec<usize>,
 }
 
-impl Default for SearchState {
-    fn default() -> Self {
-        Self {
-            query: String::new(),
-            cursor_pos: 0,
-            current_match: None,
-            matches: Vec::new(),
-        }
-    }
-}
-
 impl SearchState {
     pub fn new() -> Self {
         Self::default()
@@ -142,6 +132,11 @@ pub fn render_search_input(
         .style(theme.prompt_style);
 
     frame.render_widget(paragraph, area);
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    let query = &search_state.query;
+    let typed = query.get(..search_state.cursor_pos).unwrap_or(query);
+    let column = 1 + unicode_width::UnicodeWidthStr::width(typed);
+    frame.set_cursor_position(crate::ui::cursor::position(inner, 0, 0, column));
 }
 
 /// Apply filter to chat history
@@ -4,24 +4,45 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
-use crate::state::{ChatMessage, MessageStatus};
+use crate::sections::{segment, SectionKind};
+use crate::state::{ChatHistory, MessageStatus};
 use crate::ui::colors::Theme;
+use crate::ui::markdown::render_markdown;
+
+/// Replies longer than this are collapsed until expanded
+pub const COLLAPSED_LINES: usize = 12;
 
 /// Render chat messages as a vertical list styled like OpenCode's SessionTurn.
+///
+/// Assistant replies are rendered as markdown and wrapped to the panel width;
+/// long replies, and the tool output and reasoning in them, are collapsed
+/// unless expanded. The view follows the latest
+/// message unless the user scrolled up, in which case a pill counts new ones.
 pub fn render_session_turns(
     frame: &mut Frame,
     area: Rect,
-    messages: &[ChatMessage],
+    history: &ChatHistory,
     theme: &Theme,
+    border: Style,
 ) {
+    let messages = &history.messages;
+    let expanded = &history.expanded;
+    // Borders plus the body indent under the header
+    let body_width = (area.width as usize).saturating_sub(2 + BODY_INDENT.len());
+
     let items: Vec<ListItem> = messages
         .iter()
         .map(|msg| {
-            let prefix = if msg.is_user { "› " } else { "◆ " };
+            let icons = theme.icons;
+            let prefix = if msg.is_user {
+                "› ".to_string()
+            } else {
+                format!("{} ", icons.assistant)
+            };
             let prefix_color = if msg.is_user {
                 Color::Rgb(120, 170, 255)
             } else {
@@ -29,10 +50,10 @@ pub fn render_session_turns(
             };
 
             let status_icon = match msg.status {
-                MessageStatus::Success => "✓ ",
-                MessageStatus::Error => "✗ ",
-                MessageStatus::Working => "… ",
-                MessageStatus::Pending => "○ ",
+                MessageStatus::Success => icons.accepted,
+                MessageStatus::Error => icons.rejected,
+                MessageStatus::Working => icons.working,
+                MessageStatus::Pending => icons.pending,
             };
 
             let status_color = match msg.status {
@@ -45,22 +66,47 @@ pub fn render_session_turns(
 
             let timestamp = msg.timestamp.format("%H:%M:%S").to_string();
 
-            let header = Line::from(vec![
+            let mut body = if msg.is_user {
+                let spans = msg
+                    .content
+                    .lines()
+                    .map(|line| Line::from(Span::styled(line.to_string(), theme.normal_style)));
+                wrap_plain(spans.collect(), body_width)
+            } else {
+                render_reply(&msg.content, expanded.contains(&msg.id), body_width, theme)
+            };
+
+            let hidden = body.len().saturating_sub(COLLAPSED_LINES);
+            if !msg.is_user && hidden > 0 && !expanded.contains(&msg.id) {
+                body.truncate(COLLAPSED_LINES);
+                body.push(Line::from(Span::styled(
+                    format!("… {} more lines (Ctrl+O to expand)", hidden),
+                    theme.muted_style,
+                )));
+            }
+
+            let mut lines = vec![Line::from(vec![
                 Span::styled(
                     format!("[{}] ", timestamp),
                     Style::default().fg(Color::Rgb(90, 90, 90)),
                 ),
-                Span::styled(status_icon, Style::default().fg(status_color)),
+                Span::styled(
+                    format!("{} ", status_icon),
+                    Style::default().fg(status_color),
+                ),
                 Span::styled(
                     prefix,
                     Style::default()
                         .fg(prefix_color)
                         .add_modifier(Modifier::BOLD),
                 ),
-                Span::styled(msg.content.clone(), theme.normal_style),
-            ]);
+            ])];
+            lines.extend(body.into_iter().map(|mut line| {
+                line.spans.insert(0, Span::raw(BODY_INDENT));
+                line
+            }));
 
-            ListItem::new(header).style(theme.normal_style)
+            ListItem::new(lines).style(theme.normal_style)
         })
         .collect();
 
@@ -69,20 +115,123 @@ pub fn render_session_turns(
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(theme.border_style)
-                .title(" Chat "),
+                .border_style(border)
+                .title(
+                    if history.older_available && history.visible_index() == Some(0) {
+                        " Chat (PgUp for older messages) "
+                    } else {
+                        " Chat "
+                    },
+                ),
         )
         .style(theme.normal_style);
 
-    frame.render_widget(list, area);
+    // Selecting the anchored message makes the list keep it in view
+    let mut list_state = ListState::default().with_selected(history.visible_index());
+    frame.render_stateful_widget(list, area, &mut list_state);
+
+    if history.unseen > 0 {
+        render_new_messages_pill(frame, area, history.unseen, theme);
+    }
+}
+
+/// "N new messages" marker on the bottom border of the chat panel
+fn render_new_messages_pill(frame: &mut Frame, area: Rect, unseen: usize, theme: &Theme) {
+    let label = format!(
+        " ↓ {} new message{} (PgDn) ",
+        unseen,
+        if unseen == 1 { "" } else { "s" }
+    );
+    let width = (label.chars().count() as u16).min(area.width.saturating_sub(2));
+    if width == 0 || area.height < 2 {
+        return;
+    }
+    let pill = Rect::new(
+        area.x + area.width.saturating_sub(width) / 2,
+        area.y + area.height - 1,
+        width,
+        1,
+    );
+    frame.render_widget(Paragraph::new(label).style(theme.selected_style), pill);
+}
+
+const BODY_INDENT: &str = "  ";
+
+/// An assistant reply, with its tool output and reasoning folded to one line
+/// each unless `expanded`
+fn render_reply(content: &str, expanded: bool, width: usize, theme: &Theme) -> Vec<Line<'static>> {
+    let sections = segment(content);
+    if sections.iter().all(|s| s.kind == SectionKind::Answer) {
+        return render_markdown(content, width, theme);
+    }
+
+    let dim = theme.muted_style;
+    let mut body = Vec::new();
+    for section in sections {
+        let label = match section.kind {
+            SectionKind::Answer => {
+                body.extend(render_markdown(
+                    section.text.trim_matches('\n'),
+                    width,
+                    theme,
+                ));
+                continue;
+            }
+            SectionKind::Tool => format!("tool: {}", section.title),
+            SectionKind::Reasoning => "reasoning".to_string(),
+        };
+        let lines = section.text.trim_end().lines().count();
+        if expanded {
+            body.push(Line::from(Span::styled(
+                format!("{} {}", theme.icons.expanded, label),
+                dim,
+            )));
+            let output = section
+                .text
+                .trim_end()
+                .lines()
+                .map(|line| Line::from(Span::styled(line.to_string(), dim)));
+            body.extend(wrap_plain(output.collect(), width));
+        } else {
+            body.push(Line::from(Span::styled(
+                format!(
+                    "{} {} · {} line{} (Ctrl+O to expand)",
+                    theme.icons.collapsed,
+                    label,
+                    lines,
+                    if lines == 1 { "" } else { "s" }
+                ),
+                dim,
+            )));
+        }
+    }
+    body
+}
+
+/// Wrap plain lines by character to `width`
+fn wrap_plain(lines: Vec<Line<'static>>, width: usize) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let mut wrapped = Vec::new();
+    for line in lines {
+        let style = line.spans.first().map(|s| s.style).unwrap_or_default();
+        let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        let chunks = textwrap::wrap(&text, width);
+        if chunks.is_empty() {
+            wrapped.push(Line::default());
+        }
+        for chunk in chunks {
+            wrapped.push(Line::from(Span::styled(chunk.into_owned(), style)));
+        }
+    }
+    wrapped
 }
 
 /// Empty state when there are no messages yet.
-pub fn render_empty_chat(frame: &mut Frame, area: Rect, theme: &Theme) {
+pub fn render_empty_chat(frame: &mut Frame, area: Rect, theme: &Theme, border: Style) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(theme.border_style)
+        .border_style(border)
         .title(" Chat ");
 
     let inner = block.inner(area);
@@ -123,10 +272,7 @@ pub fn render_empty_chat(frame: &mut Frame, area: Rect, theme: &Theme) {
             Span::raw(" to send"),
         ]),
         Line::from(""),
-        Line::from(Span::styled(
-            "Press ? for help",
-            Style::default().fg(Color::DarkGray),
-        )),
+        Line::from(Span::styled("Press ? for help", theme.muted_style)),
     ];
 
     let paragraph = Paragraph::new(lines)
@@ -0,0 +1,101 @@
+// src/ui/sessions.rs - Quick switcher of recent sessions (Ctrl+Tab, :sessions)
+
+use crate::i18n::{t, tf};
+use crate::state::SessionSwitcher;
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+/// Render the most recently used sessions over `area`, the selected one highlighted
+pub fn render_session_switcher(
+    frame: &mut Frame,
+    area: Rect,
+    switcher: &SessionSwitcher,
+    theme: &Theme,
+) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(t("sessions.title"))
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(inner);
+
+    let header = Row::new(
+        [
+            "",
+            t("sessions.last_used"),
+            t("sessions.provider"),
+            t("sessions.prompts"),
+            t("sessions.session_title"),
+        ]
+        .into_iter()
+        .map(|h| Cell::from(h.to_string())),
+    )
+    .style(theme.header_style);
+
+    let rows = switcher.entries.iter().enumerate().map(|(idx, entry)| {
+        // The session shown now, and sessions left with a review or draft
+        let marker = if entry.current {
+            Span::styled(theme.icons.current, theme.status_accepted)
+        } else if entry.in_progress {
+            Span::styled(theme.icons.kept, theme.status_pending)
+        } else {
+            Span::raw(" ")
+        };
+        Row::new(vec![
+            Cell::from(Line::from(vec![
+                Span::styled(format!("{} ", idx + 1), theme.context_style),
+                marker,
+            ])),
+            Cell::from(
+                entry
+                    .last_used
+                    .with_timezone(&chrono::Local)
+                    .format("%m-%d %H:%M")
+                    .to_string(),
+            ),
+            Cell::from(entry.provider.clone()),
+            Cell::from(entry.prompts.to_string()),
+            Cell::from(entry.title.clone()),
+        ])
+        .style(theme.normal_style)
+    });
+
+    let widths = [
+        Constraint::Length(4),
+        Constraint::Length(12),
+        Constraint::Length(14),
+        Constraint::Length(8),
+        Constraint::Min(20),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .row_highlight_style(theme.selected_style);
+    let mut table_state = TableState::default().with_selected(Some(switcher.selected));
+    frame.render_stateful_widget(table, chunks[0], &mut table_state);
+
+    let footer = Paragraph::new(Line::from(Span::styled(
+        theme.icons.hints(&tf(
+            "sessions.footer",
+            &[
+                ("current", &theme.icons.current),
+                ("kept", &theme.icons.kept),
+            ],
+        )),
+        theme.muted_style,
+    )));
+    frame.render_widget(footer, chunks[1]);
+}
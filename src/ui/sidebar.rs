@@ -1,23 +1,53 @@
 // src/ui/sidebar.rs - File preview sidebar
 
-use crate::state::SidebarState;
+use crate::state::{ChangeType, FileChange, SidebarState, SidebarView};
 use crate::ui::colors::Theme;
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Layout, Rect},
     style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-/// Render file preview sidebar
-pub fn render_sidebar(frame: &mut Frame, area: Rect, sidebar: &SidebarState, theme: &Theme) {
+/// Recent files remembered per session
+pub const RECENT_FILES: usize = 5;
+
+/// Rows of the file list above the preview
+const LISTED_FILES: usize = 6;
+
+/// Render file preview sidebar.
+///
+/// The pinned and recent files are listed at the top, the shown one
+/// highlighted. When the pinned file has a pending `change`, the preview shows
+/// it as selected by `sidebar.view` (current, proposed or diff), listed in the
+/// title.
+pub fn render_sidebar(
+    frame: &mut Frame,
+    area: Rect,
+    sidebar: &SidebarState,
+    change: Option<&FileChange>,
+    theme: &Theme,
+) {
     if !sidebar.visible {
         return;
     }
 
+    let files = listed_files(sidebar);
+    let area = if files.is_empty() {
+        area
+    } else {
+        let [list, preview] = Layout::vertical([
+            Constraint::Length(files.len().min(LISTED_FILES) as u16 + 2),
+            Constraint::Min(0),
+        ])
+        .areas(area);
+        render_file_list(frame, list, sidebar, &files, theme);
+        preview
+    };
+
     let file_path = match &sidebar.pinned_file {
         Some(path) => path,
         None => {
@@ -36,28 +66,137 @@ pub fn render_sidebar(frame: &mut Frame, area: Rect, sidebar: &SidebarState, the
         }
     };
 
-    // Read file content
-    let content = match fs::read_to_string(file_path) {
-        Ok(content) => content,
-        Err(_) => {
-            let paragraph = Paragraph::new("Failed to read file")
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .border_style(theme.border_style)
-                        .title(" File Preview "),
-                )
-                .style(theme.error_style)
-                .alignment(ratatui::layout::Alignment::Center);
-            frame.render_widget(paragraph, area);
-            return;
+    let file_name = sidebar
+        .current_file_indicator
+        .as_deref()
+        .unwrap_or_else(|| {
+            file_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("unknown")
+        });
+
+    let (lines, title) = match change {
+        Some(change) => (
+            change_lines(change, sidebar, theme),
+            view_title(file_name, sidebar.view, theme),
+        ),
+        None => {
+            // Read file content
+            let content = match crate::file_cache::read(file_path) {
+                Some(content) => content,
+                None => {
+                    let paragraph = Paragraph::new("Failed to read file")
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .border_style(theme.border_style)
+                                .title(" File Preview "),
+                        )
+                        .style(theme.error_style)
+                        .alignment(ratatui::layout::Alignment::Center);
+                    frame.render_widget(paragraph, area);
+                    return;
+                }
+            };
+            (
+                numbered_lines(&content, sidebar, theme),
+                Line::from(format!(" {} ", file_name)),
+            )
         }
     };
 
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style)
+                .title(title),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(paragraph, area);
+}
+
+/// The pinned files (`▪`) then the recent ones (`·`), by name, scrolled to
+/// keep the shown file in view
+fn render_file_list(
+    frame: &mut Frame,
+    area: Rect,
+    sidebar: &SidebarState,
+    files: &[PathBuf],
+    theme: &Theme,
+) {
+    let shown = sidebar
+        .pinned_file
+        .as_ref()
+        .and_then(|shown| files.iter().position(|f| f == shown));
+    let skip = shown.map_or(0, |idx| (idx + 1).saturating_sub(LISTED_FILES));
+    let lines: Vec<Line> = files
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .take(LISTED_FILES)
+        .map(|(idx, file)| {
+            let marker = if sidebar.pins.contains(file) {
+                theme.icons.pinned
+            } else {
+                theme.icons.dot
+            };
+            let style = if Some(idx) == shown {
+                theme.selected_style
+            } else if sidebar.pins.contains(file) {
+                theme.normal_style
+            } else {
+                theme.context_style
+            };
+            Line::styled(format!("{} {}", marker, file_label(file)), style)
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border_style)
+            .title(" Pinned · Recent ( ) "),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+/// The file's name, with its parent directory when it has one
+fn file_label(path: &Path) -> String {
+    let name = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().to_string(),
+    );
+    match path.parent().and_then(|p| p.file_name()) {
+        Some(parent) => format!("{}/{}", parent.to_string_lossy(), name),
+        None => name,
+    }
+}
+
+/// File name followed by the views, the active one highlighted
+fn view_title(file_name: &str, active: SidebarView, theme: &Theme) -> Line<'static> {
+    let mut spans = vec![Span::raw(format!(" {} ", file_name))];
+    for view in SidebarView::ALL {
+        let style = if view == active {
+            theme.selected_style
+        } else {
+            theme.context_style
+        };
+        spans.push(Span::styled(format!(" {} ", view.label()), style));
+    }
+    spans.push(Span::raw(" "));
+    Line::from(spans)
+}
+
+/// Numbered lines of `content`, with the highlighted lines marked
+fn numbered_lines(content: &str, sidebar: &SidebarState, theme: &Theme) -> Vec<Line<'static>> {
     // Build lines with syntax highlighting (basic for now, can enhance with syntect later)
-    let lines: Vec<Line> = content
+    content
         .lines()
         .enumerate()
+        .skip(sidebar.scroll_offset)
         .map(|(line_num, line)| {
             let line_num_str = format!("{:4} ", line_num + 1);
             let is_highlighted = sidebar.highlighted_lines.contains(&(line_num + 1));
@@ -73,38 +212,229 @@ pub fn render_sidebar(frame: &mut Frame, area: Rect, sidebar: &SidebarState, the
                 Span::styled(line.to_string(), style),
             ])
         })
-        .collect();
-
-    let file_name = file_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown");
+        .collect()
+}
 
-    let paragraph = Paragraph::new(lines)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(theme.border_style)
-                .title(format!(" {} ", file_name)),
-        )
-        .wrap(Wrap { trim: false });
+/// Preview of a file with a pending change in the selected view
+fn change_lines(change: &FileChange, sidebar: &SidebarState, theme: &Theme) -> Vec<Line<'static>> {
+    let original = change.original_content.as_deref();
+    match sidebar.view {
+        SidebarView::Current => match original {
+            Some(content) => numbered_lines(content, sidebar, theme),
+            None => vec![Line::styled(
+                "New file, not on disk yet",
+                theme.context_style,
+            )],
+        },
+        SidebarView::Proposed if change.change_type == ChangeType::Delete => {
+            vec![Line::styled("File will be deleted", theme.context_style)]
+        }
+        SidebarView::Proposed => numbered_lines(&change.proposed_content, sidebar, theme),
+        SidebarView::Diff => diff_lines(original.unwrap_or(""), &change.proposed_content, theme)
+            .into_iter()
+            .skip(sidebar.scroll_offset)
+            .collect(),
+    }
+}
 
-    frame.render_widget(paragraph, area);
+/// The proposed file with added lines green and removed lines struck through,
+/// numbered by their line in the proposed file
+fn diff_lines(original: &str, proposed: &str, theme: &Theme) -> Vec<Line<'static>> {
+    let diff = crate::diff::generate_diff(original, proposed);
+    diff.iter_all_changes()
+        .map(|change| {
+            let number = change
+                .new_index()
+                .map_or_else(|| "     ".to_string(), |idx| format!("{:4} ", idx + 1));
+            let text = change.value().trim_end_matches(['\n', '\r']).to_string();
+            let (marker, style) = match change.tag() {
+                similar::ChangeTag::Insert => ("+", theme.addition_style()),
+                similar::ChangeTag::Delete => ("-", theme.deletion_style()),
+                similar::ChangeTag::Equal => (" ", theme.normal_style),
+            };
+            Line::from(vec![
+                Span::styled(number, theme.context_style),
+                Span::styled(marker, style),
+                Span::styled(text, style),
+            ])
+        })
+        .collect()
 }
 
 /// Pin a file to the sidebar
 pub fn pin_file(sidebar: &mut SidebarState, file_path: PathBuf) {
     sidebar.pinned_file = Some(file_path);
     sidebar.visible = true;
+    sidebar.scroll_offset = 0;
+    sidebar.highlighted_lines.clear();
+    sidebar.current_file_indicator = None;
+}
+
+/// Switch the preview of a file with pending changes to the next view
+pub fn cycle_view(sidebar: &mut SidebarState) -> SidebarView {
+    sidebar.view = sidebar.view.next();
+    sidebar.view
 }
 
 /// Unpin the current file
 pub fn unpin_file(sidebar: &mut SidebarState) {
     sidebar.pinned_file = None;
+    sidebar.current_file_indicator = None;
     sidebar.visible = false;
 }
 
+/// Add a file to the pin list and show it
+pub fn add_pin(sidebar: &mut SidebarState, file_path: PathBuf) {
+    if !sidebar.pins.contains(&file_path) {
+        sidebar.pins.push(file_path.clone());
+    }
+    pin_file(sidebar, file_path);
+}
+
+/// Take the shown file off the pin list, showing the next listed file if
+/// there is one. Returns the file unpinned.
+pub fn remove_pin(sidebar: &mut SidebarState) -> Option<PathBuf> {
+    let shown = sidebar.pinned_file.clone()?;
+    let idx = sidebar.pins.iter().position(|p| *p == shown)?;
+    sidebar.pins.remove(idx);
+    let files = listed_files(sidebar);
+    match files.get(idx).or(files.first()) {
+        Some(next) => pin_file(sidebar, next.clone()),
+        None => unpin_file(sidebar),
+    }
+    Some(shown)
+}
+
+/// Show the file `delta` places along the listed files, wrapping around
+pub fn cycle_pin(sidebar: &mut SidebarState, delta: isize) -> Option<PathBuf> {
+    let files = listed_files(sidebar);
+    if files.is_empty() {
+        return None;
+    }
+    let next = match sidebar
+        .pinned_file
+        .as_ref()
+        .and_then(|shown| files.iter().position(|f| f == shown))
+    {
+        Some(idx) => (idx as isize + delta).rem_euclid(files.len() as isize) as usize,
+        None if delta < 0 => files.len() - 1,
+        None => 0,
+    };
+    pin_file(sidebar, files[next].clone());
+    Some(files[next].clone())
+}
+
+/// Put `files` at the top of the recent files, keeping the newest `RECENT_FILES`
+pub fn note_recent_files(sidebar: &mut SidebarState, files: &[PathBuf]) {
+    let mut recent = files.to_vec();
+    recent.append(&mut sidebar.recent_files);
+    let mut seen = std::collections::HashSet::new();
+    recent.retain(|f| seen.insert(f.clone()));
+    recent.truncate(RECENT_FILES);
+    sidebar.recent_files = recent;
+}
+
+/// Point the shown file, pins and recent files at the new paths of files
+/// renamed by an apply, given as (old, new) pairs
+pub fn follow_renames(sidebar: &mut SidebarState, renames: &[(PathBuf, PathBuf)]) {
+    // Pins are kept as typed; compare them resolved against the cwd
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let follow = |path: &PathBuf| {
+        renames
+            .iter()
+            .find(|(old, _)| cwd.join(old) == cwd.join(path))
+            .map_or_else(|| path.clone(), |(_, new)| new.clone())
+    };
+    sidebar.pinned_file = sidebar.pinned_file.as_ref().map(follow);
+    for files in [&mut sidebar.pins, &mut sidebar.recent_files] {
+        let mut seen = std::collections::HashSet::new();
+        *files = files
+            .iter()
+            .map(follow)
+            .filter(|f| seen.insert(f.clone()))
+            .collect();
+    }
+}
+
+/// The pins, then the recent files that aren't pinned
+pub fn listed_files(sidebar: &SidebarState) -> Vec<PathBuf> {
+    let recent = sidebar
+        .recent_files
+        .iter()
+        .filter(|f| !sidebar.pins.contains(f));
+    sidebar.pins.iter().chain(recent).cloned().collect()
+}
+
 /// Toggle sidebar visibility
 pub fn toggle_sidebar(sidebar: &mut SidebarState) {
     sidebar.visible = !sidebar.visible;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Modifier;
+
+    #[test]
+    fn test_diff_lines_mark_added_and_removed() {
+        let theme = Theme::dark();
+        let lines = diff_lines("a\nold\nc\n", "a\nnew\nc\n", &theme);
+        let text: Vec<String> = lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert_eq!(text, vec!["   1  a", "     -old", "   2 +new", "   3  c"]);
+        assert!(lines[1].spans[2]
+            .style
+            .add_modifier
+            .contains(Modifier::CROSSED_OUT));
+        assert_eq!(lines[2].spans[2].style, theme.addition_style());
+    }
+
+    #[test]
+    fn test_pins_and_recent_files_cycle_together() {
+        let mut sidebar = SidebarState::default();
+        let (a, b, c) = (
+            PathBuf::from("a.rs"),
+            PathBuf::from("b.rs"),
+            PathBuf::from("c.rs"),
+        );
+        add_pin(&mut sidebar, a.clone());
+        add_pin(&mut sidebar, b.clone());
+        note_recent_files(&mut sidebar, &[c.clone(), a.clone()]);
+        assert_eq!(listed_files(&sidebar), [a.clone(), b.clone(), c.clone()]);
+
+        assert_eq!(cycle_pin(&mut sidebar, 1), Some(c.clone()));
+        assert_eq!(cycle_pin(&mut sidebar, 1), Some(a.clone()));
+        assert_eq!(cycle_pin(&mut sidebar, -1), Some(c.clone()));
+
+        // Recent files aren't unpinned; unpinning shows the next listed file
+        assert_eq!(remove_pin(&mut sidebar), None);
+        sidebar.pinned_file = Some(a.clone());
+        assert_eq!(remove_pin(&mut sidebar), Some(a.clone()));
+        assert_eq!(sidebar.pinned_file, Some(b.clone()));
+        assert_eq!(listed_files(&sidebar), [b, c, a]);
+
+        let many: Vec<PathBuf> = (0..8).map(|i| PathBuf::from(format!("{}.rs", i))).collect();
+        note_recent_files(&mut sidebar, &many);
+        assert_eq!(sidebar.recent_files, many[..RECENT_FILES]);
+    }
+
+    #[test]
+    fn test_renamed_files_are_followed() {
+        let mut sidebar = SidebarState::default();
+        let (old, new, other) = (
+            PathBuf::from("old.rs"),
+            PathBuf::from("src/new.rs"),
+            PathBuf::from("b.rs"),
+        );
+        add_pin(&mut sidebar, PathBuf::from("./old.rs"));
+        note_recent_files(&mut sidebar, &[other.clone(), new.clone(), old.clone()]);
+
+        follow_renames(&mut sidebar, &[(old, new.clone())]);
+        assert_eq!(sidebar.pinned_file, Some(new.clone()));
+        assert_eq!(sidebar.recent_files, [other.clone(), new.clone()]);
+        // The pin and the recent file at the new path are one entry
+        assert_eq!(listed_files(&sidebar), [new, other]);
+    }
+}
@@ -1,10 +1,10 @@
 // src/ui/status_bar.rs - Real-time status bar rendering
 
+use crate::i18n::{t, tf};
 use crate::state::StatusInfo;
 use crate::ui::colors::Theme;
 use ratatui::{
     layout::{Alignment, Rect},
-    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Gauge, Paragraph},
     Frame,
@@ -13,7 +13,7 @@ use ratatui::{
 /// Render status bar with real-time information - minimal OpenCode style
 pub fn render_status_bar(frame: &mut Frame, area: Rect, status: &StatusInfo, theme: &Theme) {
     let status_text = if status.is_working {
-        let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+        let spinner_chars = theme.icons.spinner;
         let frame_idx = (std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
@@ -28,8 +28,14 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, status: &StatusInfo, the
         };
 
         format!(
-            "{} Working{} | Tokens: {} | Cost ${:.4}",
-            spinner_chars[frame_idx], progress, status.tokens_sent, status.session_cost
+            "{} {}{} | {}: {} | {} ${:.4}",
+            spinner_chars[frame_idx],
+            t("status.working"),
+            progress,
+            t("status.tokens"),
+            status.tokens_sent,
+            t("status.cost"),
+            status.session_cost
         )
     } else {
         // Ready state - minimal format matching the images
@@ -40,14 +46,37 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, status: &StatusInfo, the
         };
 
         format!(
-            "Ready | provider {} | Tokens: {} | Cost ${:.4}",
-            provider_display, status.tokens_sent, status.session_cost
+            "{} | {} {} | {}: {} | {} ${:.4}",
+            t("status.ready"),
+            t("status.provider"),
+            provider_display,
+            t("status.tokens"),
+            status.tokens_sent,
+            t("status.cost"),
+            status.session_cost
         )
     };
 
-    let style = Style::default().fg(Color::DarkGray);
+    let style = theme.muted_style;
 
-    let paragraph = Paragraph::new(status_text)
+    // A macro being recorded is easy to forget, so it leads in red
+    let mut spans = Vec::new();
+    if let Some(register) = status.recording_macro {
+        spans.push(Span::styled(
+            format!(
+                "{} {} ",
+                theme.icons.current,
+                tf("status.recording", &[("register", &register)])
+            ),
+            theme.error_style,
+        ));
+    }
+    spans.push(Span::raw(status_text));
+    if status.telemetry {
+        spans.push(Span::raw(format!(" | {}", t("status.telemetry"))));
+    }
+
+    let paragraph = Paragraph::new(Line::from(spans))
         .style(style)
         .alignment(Alignment::Left)
         .block(Block::default().borders(Borders::NONE));
@@ -63,10 +92,13 @@ pub fn render_cost_estimate(
     estimated_cost: f64,
     theme: &Theme,
 ) {
-    let text = format!(
-        "Est. cost: ~${:.4} ({} tokens) │ Press Enter to send",
-        estimated_cost, estimated_tokens
-    );
+    let text = theme.icons.hints(&tf(
+        "status.estimate",
+        &[
+            ("cost", &format!("{:.4}", estimated_cost)),
+            ("tokens", &estimated_tokens),
+        ],
+    ));
 
     let paragraph = Paragraph::new(text)
         .block(
@@ -0,0 +1,99 @@
+// src/ui/template_form.rs - Form filling in a prompt template's variables
+
+use crate::i18n::{t, tf};
+use crate::templates::{TemplateForm, VariableKind};
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+/// Render one row per variable (two when it has an error) and the key hints
+pub fn render_template_form(frame: &mut Frame, area: Rect, form: &TemplateForm, theme: &Theme) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(tf("template.title", &[("name", &form.template.name)]))
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let label_width = form
+        .fields
+        .iter()
+        .map(|f| f.variable.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = Vec::new();
+    if form.fields.is_empty() {
+        lines.push(Line::from(Span::styled(
+            t("template.no_variables"),
+            theme.context_style,
+        )));
+    }
+    for (idx, field) in form.fields.iter().enumerate() {
+        let selected = idx == form.selected;
+        let label_style = if selected {
+            theme.header_style.add_modifier(Modifier::BOLD)
+        } else {
+            theme.context_style
+        };
+        let mut spans = vec![
+            Span::styled(
+                if selected {
+                    format!("{} ", theme.icons.pointer)
+                } else {
+                    "  ".to_string()
+                },
+                label_style,
+            ),
+            Span::styled(
+                format!("{:<width$}  ", field.variable.name, width = label_width),
+                label_style,
+            ),
+        ];
+        match &field.variable.kind {
+            VariableKind::Choice(choices) => {
+                for choice in choices {
+                    let style = if *choice == field.value {
+                        theme.selected_style
+                    } else {
+                        theme.context_style
+                    };
+                    spans.push(Span::styled(format!(" {} ", choice), style));
+                }
+            }
+            VariableKind::Text | VariableKind::Number => {
+                spans.push(Span::styled(field.value.clone(), theme.normal_style));
+                if selected {
+                    spans.push(Span::styled(theme.icons.bar_full, theme.prompt_style));
+                }
+            }
+        }
+        lines.push(Line::from(spans));
+        if let Some(error) = &field.error {
+            lines.push(Line::from(Span::styled(
+                format!("    {:<width$}{}", "", error, width = label_width),
+                theme.error_style,
+            )));
+        }
+    }
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), chunks[0]);
+
+    let footer = Paragraph::new(Line::from(Span::styled(
+        theme.icons.hints(t("template.footer")),
+        theme.muted_style,
+    )));
+    frame.render_widget(footer, chunks[1]);
+}
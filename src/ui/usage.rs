@@ -0,0 +1,195 @@
+// src/ui/usage.rs - Session cost and usage dashboard (:usage)
+
+use crate::context::format_tokens;
+use crate::i18n::t;
+use crate::session::{SessionManager, UsageStats};
+use crate::ui::colors::Theme;
+use crate::ui::icons::Icons;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table},
+    Frame,
+};
+
+/// Sessions listed in the recent sessions table
+const RECENT_SESSIONS: usize = 10;
+/// Width of the token bars, in cells
+const BAR_WIDTH: usize = 12;
+
+/// Render the usage dashboard over `area`
+pub fn render_usage(frame: &mut Frame, area: Rect, sessions: &SessionManager, theme: &Theme) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(t("usage.title"))
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if sessions.sessions.is_empty() {
+        let empty = Paragraph::new(t("usage.empty")).style(theme.context_style);
+        frame.render_widget(empty, inner);
+        return;
+    }
+
+    let by_provider = sessions.usage_by_provider();
+    let recent = sessions.usage_by_session(RECENT_SESSIONS);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(by_provider.len() as u16 + 3),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    // Bars are scaled against the busiest row, ignoring the total
+    let provider_max = by_provider
+        .iter()
+        .take(by_provider.len() - 1)
+        .map(total_tokens)
+        .max()
+        .unwrap_or(0);
+    render_table(
+        frame,
+        chunks[0],
+        t("usage.by_provider"),
+        &by_provider,
+        provider_max,
+        true,
+        theme,
+    );
+
+    let session_max = recent.iter().map(total_tokens).max().unwrap_or(0);
+    render_table(
+        frame,
+        chunks[1],
+        t("usage.recent"),
+        &recent,
+        session_max,
+        false,
+        theme,
+    );
+
+    let footer = Paragraph::new(Line::from(Span::styled(
+        theme.icons.hints(t("usage.footer")),
+        theme.muted_style,
+    )));
+    frame.render_widget(footer, chunks[2]);
+}
+
+fn render_table(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    rows: &[UsageStats],
+    max_tokens: usize,
+    last_is_total: bool,
+    theme: &Theme,
+) {
+    let header = Row::new(
+        [
+            title,
+            t("usage.sessions"),
+            t("usage.prompts"),
+            t("usage.tokens_in"),
+            t("usage.tokens_out"),
+            t("usage.cost"),
+            t("usage.files"),
+            t("usage.accepted"),
+            t("usage.tokens"),
+        ]
+        .into_iter()
+        .map(|h| Cell::from(h.to_string())),
+    )
+    .style(theme.header_style);
+
+    let body = rows.iter().enumerate().map(|(idx, stats)| {
+        let is_total = last_is_total && idx == rows.len() - 1;
+        let style = if is_total {
+            theme.normal_style.add_modifier(Modifier::BOLD)
+        } else {
+            theme.normal_style
+        };
+        let bar = if is_total {
+            String::new()
+        } else {
+            usage_bar(total_tokens(stats), max_tokens, BAR_WIDTH, theme.icons)
+        };
+
+        Row::new(vec![
+            Cell::from(stats.label.clone()),
+            Cell::from(stats.sessions.to_string()),
+            Cell::from(stats.prompts.to_string()),
+            Cell::from(format_tokens(stats.tokens_in)),
+            Cell::from(format_tokens(stats.tokens_out)),
+            Cell::from(format!("${:.2}", stats.cost)),
+            Cell::from(stats.files_changed.to_string()),
+            Cell::from(
+                stats
+                    .accept_ratio()
+                    .map_or("-".to_string(), |r| format!("{:.0}%", r * 100.0)),
+            ),
+            Cell::from(Span::styled(bar, theme.status_accepted)),
+        ])
+        .style(style)
+    });
+
+    let widths = [
+        Constraint::Min(16),
+        Constraint::Length(8),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(8),
+        Constraint::Length(5),
+        Constraint::Length(8),
+        Constraint::Length(BAR_WIDTH as u16),
+    ];
+
+    frame.render_widget(Table::new(body, widths).header(header), area);
+}
+
+fn total_tokens(stats: &UsageStats) -> usize {
+    stats.tokens_in + stats.tokens_out
+}
+
+/// Horizontal bar of `width` cells filled in proportion to `value / max`,
+/// using eighth-block characters for the partial cell when `icons` has them
+pub fn usage_bar(value: usize, max: usize, width: usize, icons: &Icons) -> String {
+    if max == 0 || width == 0 {
+        return String::new();
+    }
+
+    let eighths = (value.min(max) * width * 8).div_ceil(max);
+    let (full, partial) = (eighths / 8, eighths % 8);
+    let mut bar = icons.bar_full.repeat(full);
+    if let Some(glyph) = icons.bar_partial.get(partial) {
+        bar.push_str(glyph);
+    }
+    bar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::icons::{ASCII, UNICODE};
+
+    #[test]
+    fn test_usage_bar() {
+        assert_eq!(usage_bar(10, 10, 4, &UNICODE), "████");
+        assert_eq!(usage_bar(5, 10, 4, &UNICODE), "██");
+        assert_eq!(usage_bar(1, 16, 2, &UNICODE), "▏");
+        assert_eq!(usage_bar(0, 10, 4, &UNICODE), "");
+        assert_eq!(usage_bar(5, 0, 4, &UNICODE), "");
+        // ASCII draws whole cells only
+        assert_eq!(usage_bar(5, 16, 2, &ASCII), "");
+        assert_eq!(usage_bar(12, 16, 2, &ASCII), "#");
+    }
+}
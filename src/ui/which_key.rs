@@ -0,0 +1,68 @@
+// src/ui/which_key.rs - Keys that can follow an unfinished key sequence
+
+use crate::state::WhichKey;
+use crate::ui::colors::Theme;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Render the popup in the bottom-right corner of `area`, above the status
+/// and input rows, one next key per line
+pub fn render_which_key(frame: &mut Frame, area: Rect, which_key: &WhichKey, theme: &Theme) {
+    let key_width = which_key
+        .entries
+        .iter()
+        .map(|(key, _)| key.width())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Line> = which_key
+        .entries
+        .iter()
+        .map(|(key, action)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<width$}", key, width = key_width),
+                    theme.added_style,
+                ),
+                Span::styled(format!(" {} ", theme.icons.arrow), theme.muted_style),
+                Span::styled(action.clone(), theme.normal_style),
+            ])
+        })
+        .collect();
+
+    let title = format!(" {} ", which_key.prefix.concat());
+    let content_width = lines.iter().map(Line::width).max().unwrap_or(0);
+    let width = (content_width.max(title.width()) as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    if height < 3 {
+        return;
+    }
+    let popup = Rect::new(
+        area.right().saturating_sub(width + 1),
+        area.bottom().saturating_sub(height + 4),
+        width,
+        height,
+    );
+
+    frame.render_widget(Clear, popup);
+    let block = Block::default()
+        .title(title)
+        .title_style(theme.normal_style)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border_style);
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+    frame.render_widget(
+        Paragraph::new(lines),
+        Rect {
+            x: inner.x + 1,
+            width: inner.width.saturating_sub(1),
+            ..inner
+        },
+    );
+}
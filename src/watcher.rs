@@ -0,0 +1,120 @@
+//! Filesystem watching for files under review and the config files
+//!
+//! While a diff is being reviewed, the files it touches may be edited outside
+//! zcode (another editor, a formatter, `git checkout`). The watcher reports those
+//! edits so the review can be re-diffed against what is actually on disk. The
+//! config file and the project's `.zcode.toml` are watched the same way, so
+//! settings apply as soon as they are saved.
+//!
+//! Parent directories are watched rather than the files themselves: most editors
+//! save by writing a temp file and renaming it over the original, which would
+//! silently drop a watch placed on the file.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::events::AppEvent;
+
+/// Watches a set of files and emits an event (`AppEvent::FileChanged` for a
+/// review) when one changes
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl FileWatcher {
+    pub fn new(
+        files: BTreeSet<PathBuf>,
+        tx: UnboundedSender<AppEvent>,
+        to_event: fn(PathBuf) -> AppEvent,
+    ) -> Result<Self> {
+        // (absolute path reported by notify, path as known to the review)
+        let targets: Vec<(PathBuf, PathBuf)> =
+            files.iter().map(|p| (absolute(p), p.clone())).collect();
+
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            for path in &event.paths {
+                // Whatever changed is read again from disk next time
+                crate::file_cache::invalidate(path);
+                if let Some((_, original)) = targets.iter().find(|(abs, _)| abs == path) {
+                    let _ = tx.send(to_event(original.clone()));
+                }
+            }
+        })
+        .context("Failed to start file watcher")?;
+
+        let mut dirs = BTreeSet::new();
+        for file in &files {
+            let dir = absolute(file)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| PathBuf::from("."));
+            // New files may live in directories that don't exist yet
+            if dir.is_dir() && dirs.insert(dir.clone()) {
+                watcher
+                    .watch(&dir, RecursiveMode::NonRecursive)
+                    .with_context(|| format!("Failed to watch {}", dir.display()))?;
+            }
+        }
+
+        Ok(Self { watcher, dirs })
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        for dir in &self.dirs {
+            let _ = self.watcher.unwatch(dir);
+        }
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    // Watch events report canonical paths (e.g. /private/var on macOS)
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => dir.canonicalize().map(|dir| dir.join(name)).unwrap_or(path),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_reports_changes_to_watched_files_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let watched = dir.path().join("watched.rs");
+        let other = dir.path().join("other.rs");
+        std::fs::write(&watched, "fn a() {}\n").unwrap();
+        std::fs::write(&other, "fn b() {}\n").unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let _watcher =
+            FileWatcher::new(BTreeSet::from([watched.clone()]), tx, AppEvent::FileChanged).unwrap();
+
+        std::fs::write(&other, "fn b2() {}\n").unwrap();
+        std::fs::write(&watched, "fn a2() {}\n").unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no file change reported")
+            .unwrap();
+        match event {
+            AppEvent::FileChanged(path) => assert_eq!(path, watched),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+}
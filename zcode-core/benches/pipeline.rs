@@ -0,0 +1,130 @@
+//! Parsing, diffing and reconstructing at three sizes
+//!
+//! `cargo bench -p zcode-core` runs them all; `cargo bench -p zcode-core --
+//! parse_unified_diff/small` one. Compare against a saved run with
+//! `--save-baseline main` then `--baseline main` (see CONTRIBUTING.md).
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::path::Path;
+use zcode_core::file_ops::reconstruct_file_content;
+use zcode_core::{diff, parsers, HunkStatus};
+
+/// Lines of the original file: a reply touching one function, a module, a
+/// generated file
+const SIZES: [(&str, usize); 3] = [("small", 40), ("medium", 800), ("huge", 20_000)];
+
+/// A Rust-looking file of `lines` lines
+fn original(lines: usize) -> String {
+    (0..lines)
+        .map(|i| match i % 8 {
+            0 => format!("fn item_{}() -> usize {{\n", i),
+            7 => "}\n".to_string(),
+            _ => format!("    let value_{} = {} * 2; // keep\n", i, i),
+        })
+        .collect()
+}
+
+/// `original` with every tenth line rewritten
+fn proposed(original: &str) -> String {
+    original
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i % 10 == 5 {
+                format!("{} // changed\n", line)
+            } else {
+                format!("{}\n", line)
+            }
+        })
+        .collect()
+}
+
+/// A unified diff of `original` to `proposed`, as a provider would reply
+fn unified_diff(original: &str, proposed: &str) -> String {
+    let body = diff::generate_diff(original, proposed)
+        .unified_diff()
+        .context_radius(3)
+        .header("a/bench.rs", "b/bench.rs")
+        .to_string();
+    format!("Here is the change:\n\n{}", body)
+}
+
+/// The same change as whole files in fenced code blocks, split in four
+fn code_blocks(proposed: &str) -> String {
+    let lines: Vec<&str> = proposed.split_inclusive('\n').collect();
+    lines
+        .chunks(lines.len().div_ceil(4))
+        .enumerate()
+        .map(|(i, chunk)| {
+            format!(
+                "```rust\n// file: src/part_{}.rs\n{}```\n\n",
+                i,
+                chunk.concat()
+            )
+        })
+        .collect()
+}
+
+fn bench_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_unified_diff");
+    for (name, lines) in SIZES {
+        let original = original(lines);
+        let reply = unified_diff(&original, &proposed(&original));
+        group.throughput(Throughput::Bytes(reply.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &reply, |b, reply| {
+            b.iter(|| parsers::parse_unified_diff(black_box(reply)).unwrap())
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("parse_code_blocks");
+    for (name, lines) in SIZES {
+        let reply = code_blocks(&proposed(&original(lines)));
+        group.throughput(Throughput::Bytes(reply.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &reply, |b, reply| {
+            b.iter(|| parsers::parse_code_blocks(black_box(reply)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_diffing(c: &mut Criterion) {
+    let path = Path::new("bench.rs");
+    let mut group = c.benchmark_group("generate_diff_extract_hunks");
+    for (name, lines) in SIZES {
+        let original = original(lines);
+        let proposed = proposed(&original);
+        group.throughput(Throughput::Bytes(original.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &(original, proposed),
+            |b, (original, proposed)| {
+                b.iter(|| {
+                    let diff = diff::generate_diff(black_box(original), black_box(proposed));
+                    diff::extract_hunks(path, &diff)
+                })
+            },
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("reconstruct_file_content");
+    for (name, lines) in SIZES {
+        let original = original(lines);
+        let mut hunks = diff::compute_hunks(path, &original, &proposed(&original));
+        for hunk in &mut hunks {
+            hunk.status = HunkStatus::Accepted;
+        }
+        let hunks: Vec<_> = hunks.iter().collect();
+        group.throughput(Throughput::Bytes(original.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &original,
+            |b, original| b.iter(|| reconstruct_file_content(black_box(original), &hunks).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parsing, bench_diffing);
+criterion_main!(benches);
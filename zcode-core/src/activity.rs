@@ -0,0 +1,164 @@
+//! Live timeline of what a provider is doing while a prompt runs
+//!
+//! Providers with structured output report each step as it happens (thinking,
+//! a tool call on some file, writing the answer). Output lines are streamed
+//! from the running process, turned into [`ActivityEvent`]s by the provider and
+//! collected here, so the Processing screen can show which step is running and
+//! how long each one took instead of an anonymous spinner.
+
+use std::time::{Duration, Instant};
+
+/// A change to the timeline reported by a provider
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActivityEvent {
+    /// A step began. Steps with an `id` (tool calls) run until `Finished` with
+    /// that id; steps without one (thinking, writing) end when the next begins.
+    Started {
+        id: Option<String>,
+        label: String,
+        detail: Option<String>,
+    },
+    /// The tool call with this id completed
+    Finished { id: String },
+    /// More detail about the step that is running (e.g. the file a tool opened)
+    Detail(String),
+    /// The run is over; everything still running is finished
+    Done,
+}
+
+#[derive(Debug, Clone)]
+pub struct ActivityStep {
+    pub id: Option<String>,
+    pub label: String,
+    pub detail: Option<String>,
+    pub started: Instant,
+    pub finished: Option<Instant>,
+}
+
+impl ActivityStep {
+    /// Time spent in the step so far (or in total, once finished)
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        self.finished
+            .unwrap_or(now)
+            .saturating_duration_since(self.started)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.finished.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ActivityTimeline {
+    pub steps: Vec<ActivityStep>,
+}
+
+impl ActivityTimeline {
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// The most recently started step that is still running
+    pub fn current(&self) -> Option<&ActivityStep> {
+        self.steps.iter().rev().find(|s| s.is_running())
+    }
+
+    pub fn apply(&mut self, event: ActivityEvent, now: Instant) {
+        match event {
+            ActivityEvent::Started { id, label, detail } => {
+                // Thinking/writing phases end as soon as anything else happens
+                for step in self.steps.iter_mut().filter(|s| s.id.is_none()) {
+                    step.finished.get_or_insert(now);
+                }
+                self.steps.push(ActivityStep {
+                    id,
+                    label,
+                    detail,
+                    started: now,
+                    finished: None,
+                });
+            }
+            ActivityEvent::Finished { id } => {
+                if let Some(step) = self
+                    .steps
+                    .iter_mut()
+                    .rev()
+                    .find(|s| s.id.as_deref() == Some(id.as_str()) && s.is_running())
+                {
+                    step.finished = Some(now);
+                }
+            }
+            ActivityEvent::Detail(detail) => {
+                if let Some(step) = self.steps.iter_mut().rev().find(|s| s.is_running()) {
+                    step.detail = Some(detail);
+                }
+            }
+            ActivityEvent::Done => {
+                for step in &mut self.steps {
+                    step.finished.get_or_insert(now);
+                }
+            }
+        }
+    }
+}
+
+/// Compact duration for the timeline, e.g. "0.4s", "12s", "2m05s"
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 10 {
+        format!("{:.1}s", elapsed.as_secs_f64())
+    } else if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started(id: Option<&str>, label: &str) -> ActivityEvent {
+        ActivityEvent::Started {
+            id: id.map(str::to_string),
+            label: label.to_string(),
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn test_phases_end_when_next_step_starts() {
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let mut timeline = ActivityTimeline::default();
+
+        timeline.apply(started(None, "Thinking"), at(0));
+        timeline.apply(started(Some("t1"), "Read"), at(500));
+        timeline.apply(ActivityEvent::Detail("src/main.rs".into()), at(510));
+        assert_eq!(
+            timeline.steps[0].elapsed(at(900)),
+            Duration::from_millis(500)
+        );
+        assert_eq!(timeline.current().unwrap().label, "Read");
+        assert_eq!(timeline.steps[1].detail.as_deref(), Some("src/main.rs"));
+
+        timeline.apply(ActivityEvent::Finished { id: "t1".into() }, at(1500));
+        assert!(timeline.current().is_none());
+        assert_eq!(timeline.steps[1].elapsed(at(9000)), Duration::from_secs(1));
+
+        timeline.apply(started(None, "Writing response"), at(1600));
+        timeline.apply(ActivityEvent::Done, at(2000));
+        assert!(timeline.steps.iter().all(|s| !s.is_running()));
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(420)), "0.4s");
+        assert_eq!(format_elapsed(Duration::from_secs(12)), "12s");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2m05s");
+    }
+}
@@ -0,0 +1,103 @@
+//! Settings the pipeline reads
+//!
+//! The types here are deserialized from the `[providers.*]` tables and the
+//! `apply_mode` and `symlinks` keys of a frontend's config file.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Configuration for a specific AI provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderConfig {
+    /// Whether this provider is enabled (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Optional custom path to the provider's CLI tool
+    pub path: Option<String>,
+    /// Optional custom name for the provider
+    pub name: Option<String>,
+    /// Optional parser type (unified_diff, code_blocks, json)
+    pub parser: Option<String>,
+    /// Optional parser chain, tried in order until one finds changes. Takes
+    /// precedence over `parser`; on a built-in provider either replaces its own.
+    pub parsers: Option<Vec<String>>,
+    /// Optional argument template for custom providers. Placeholders: {prompt},
+    /// {files} (context files) and {session} (resumed session id).
+    /// Example: ["-p", "{prompt}", "--json"]
+    pub args_template: Option<Vec<String>>,
+    /// Optional arguments appended only when resuming a session (custom providers)
+    /// Example: ["--resume", "{session}"]
+    pub session_args: Option<Vec<String>>,
+    /// Optional arguments appended to prompts sent in ask mode (custom providers),
+    /// e.g. a read-only flag. Without them the prompt asks for no edits itself.
+    pub ask_args: Option<Vec<String>>,
+    /// Optional regex whose first capture group is the session id in the output
+    pub session_regex: Option<String>,
+    /// Optional environment variables set for the provider command
+    pub env: Option<HashMap<String, String>>,
+    /// Optional model name for providers that serve several models (e.g. Ollama)
+    pub model: Option<String>,
+    /// Optional HTTP endpoint for server-backed providers (e.g. "http://localhost:11434")
+    pub endpoint: Option<String>,
+    /// Optional system prompt sent ahead of every request
+    pub system_prompt: Option<String>,
+    /// Optional token budget for the prompt plus attached context files
+    pub context_token_limit: Option<usize>,
+    /// Optional output the parser reads (stdout, stderr, merged)
+    pub output: Option<String>,
+    /// Optional sandbox (worktree, copy) the provider runs in, so the files
+    /// it edits itself reach the project only through the review
+    pub sandbox: Option<crate::sandbox::SandboxProfile>,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: None,
+            name: None,
+            parser: None,
+            parsers: None,
+            args_template: None,
+            session_args: None,
+            ask_args: None,
+            session_regex: None,
+            env: None,
+            model: None,
+            endpoint: None,
+            system_prompt: None,
+            context_token_limit: None,
+            output: None,
+            sandbox: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// How applying several files reacts to one of them failing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyMode {
+    /// All files are written or none are (the failure rolls back the rest)
+    #[default]
+    Atomic,
+    /// Each file is written independently; failures are rolled back and
+    /// reported on their own while the other files keep their changes
+    PerFile,
+}
+
+/// How writing a file that is a symlink treats the link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkMode {
+    /// Write the file the link points to, keeping the link
+    #[default]
+    Follow,
+    /// Replace the link with a regular file holding the new content
+    Replace,
+}
@@ -0,0 +1,525 @@
+//! Diff generation and hunk extraction
+//!
+//! This module handles generating diffs between original and proposed code,
+//! then extracting them into discrete hunks that can be individually accepted or rejected.
+//!
+//! # Algorithm
+//!
+//! Uses the Patience algorithm for high-quality diffs that are similar to what git produces.
+//! This algorithm is especially good for code because it identifies moving blocks efficiently.
+//!
+//! # Large files
+//!
+//! Hunks are cached by the content of both sides, so re-diffing an unchanged file
+//! is free. Files of at least [`BACKGROUND_DIFF_BYTES`] are diffed on the blocking
+//! pool as [`DiffJob`]s while the review shows a placeholder for them.
+
+use regex::Regex;
+use similar::{Algorithm, TextDiff};
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::types::{ChangeTag, Hunk, HunkRange, HunkStatus, LineChange, LineText};
+
+/// Generate a diff between two texts
+pub fn generate_diff<'a>(original: &'a str, proposed: &'a str) -> TextDiff<'a, 'a, 'a, str> {
+    TextDiff::configure()
+        .algorithm(Algorithm::Patience)
+        .timeout(Duration::from_secs(5))
+        .diff_lines(original, proposed)
+}
+
+/// Extract hunks from a diff
+pub fn extract_hunks<'a>(
+    file_path: &std::path::Path,
+    diff: &TextDiff<'a, 'a, 'a, str>,
+) -> Vec<Hunk> {
+    let mut hunks = Vec::new();
+    // Each side once, with where its lines start; every line of every hunk
+    // points into these
+    let side = |slices: &[&str]| {
+        let buffer: Arc<str> = Arc::from(slices.concat());
+        let starts: Vec<usize> = slices
+            .iter()
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.len();
+                Some(start)
+            })
+            .collect();
+        (buffer, starts)
+    };
+    let (old_buffer, old_starts) = side(diff.old_slices());
+    let (new_buffer, new_starts) = side(diff.new_slices());
+
+    for (hunk_idx, ops) in diff.grouped_ops(3).iter().enumerate() {
+        let (Some(first), Some(last)) = (ops.first(), ops.last()) else {
+            continue;
+        };
+        // Like a unified diff header: where the group starts and ends on each side
+        let old = HunkRange::from_indices(first.old_range().start..last.old_range().end);
+        let new = HunkRange::from_indices(first.new_range().start..last.new_range().end);
+        let mut changes = Vec::new();
+
+        for op in ops {
+            for change in diff.iter_changes(op) {
+                let tag = match change.tag() {
+                    similar::ChangeTag::Delete => ChangeTag::Delete,
+                    similar::ChangeTag::Insert => ChangeTag::Insert,
+                    similar::ChangeTag::Equal => ChangeTag::Equal,
+                };
+
+                let (buffer, start) = match (change.old_index(), change.new_index()) {
+                    (Some(old), _) => (&old_buffer, old_starts[old]),
+                    (None, Some(new)) => (&new_buffer, new_starts[new]),
+                    (None, None) => continue,
+                };
+                let len = change.value().len();
+                changes.push(LineChange {
+                    tag,
+                    content: LineText::slice(buffer, start..start + len),
+                    old_line_num: change.old_index(),
+                    new_line_num: change.new_index(),
+                });
+            }
+        }
+
+        if !changes.is_empty() {
+            hunks.push(Hunk {
+                id: hunk_idx,
+                file_path: file_path.to_path_buf(),
+                old,
+                new,
+                changes,
+                status: HunkStatus::Pending,
+                drifted: false,
+                comment: None,
+                auto_accepted: None,
+            });
+        }
+    }
+
+    hunks
+}
+
+/// Size of both sides together from which a file is diffed in the background
+pub const BACKGROUND_DIFF_BYTES: usize = 512 * 1024;
+
+/// Diffs kept in a [`DiffCache`]
+const CACHED_DIFFS: usize = 64;
+
+/// Identifies a diff by the content of both sides
+pub type DiffKey = (u64, u64);
+
+pub fn diff_key(original: &str, proposed: &str) -> DiffKey {
+    let hash = |text: &str| {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        hasher.finish()
+    };
+    (hash(original), hash(proposed))
+}
+
+/// Diff `original` against `proposed` and extract the hunks of `path`
+pub fn compute_hunks(path: &std::path::Path, original: &str, proposed: &str) -> Vec<Hunk> {
+    extract_hunks(path, &generate_diff(original, proposed))
+}
+
+/// Recently computed hunks by content, oldest evicted first
+#[derive(Debug, Default)]
+pub struct DiffCache {
+    entries: HashMap<DiffKey, Vec<Hunk>>,
+    order: VecDeque<DiffKey>,
+}
+
+impl DiffCache {
+    pub fn get(&self, key: &DiffKey) -> Option<&Vec<Hunk>> {
+        self.entries.get(key)
+    }
+
+    pub fn insert(&mut self, key: DiffKey, hunks: Vec<Hunk>) {
+        if self.entries.insert(key, hunks).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > CACHED_DIFFS {
+            if let Some(old) = self.order.pop_front() {
+                self.entries.remove(&old);
+            }
+        }
+    }
+}
+
+/// A large file waiting to be diffed off the UI thread
+#[derive(Debug, Clone)]
+pub struct DiffJob {
+    pub key: DiffKey,
+    pub path: PathBuf,
+    pub original: String,
+    pub proposed: String,
+}
+
+/// 1-based line range a hunk covers in the new file: its inserted lines, or the
+/// surrounding context for a pure deletion
+pub fn new_line_range(hunk: &Hunk) -> Option<(usize, usize)> {
+    let inserts = hunk.changes.iter().any(|c| c.tag == ChangeTag::Insert);
+    let lines = hunk
+        .changes
+        .iter()
+        .filter(|c| !inserts || c.tag == ChangeTag::Insert)
+        .filter_map(|c| c.new_line_num);
+    let first = lines.clone().min()?;
+    let last = lines.max()?;
+    Some((first + 1, last + 1))
+}
+
+/// Matches a definition line; group 1 is the keyword, group 2 the name
+pub fn definition_regex() -> Regex {
+    Regex::new(
+        r"^\s*(?:(?:pub(?:\([^)]*\))?|export|default|async|unsafe|static|abstract|public|private|protected|final|data)\s+)*(fn|struct|enum|trait|mod|type|const|class|def|interface|function|func)\s+(?:\([^)]*\)\s*)?([A-Za-z_][A-Za-z0-9_]*)",
+    )
+    .expect("valid definition regex")
+}
+
+/// Cluster one file's hunks into logical changes.
+///
+/// Consecutive hunks join a group when at most `max_gap` unchanged lines separate
+/// them or when they sit in the same definition (function, type, class) of
+/// `proposed`. Returns the hunk ids of each group of two or more hunks, with a
+/// label: the enclosing definition, or the line range.
+pub fn group_hunks(hunks: &[Hunk], proposed: &str, max_gap: usize) -> Vec<(Vec<usize>, String)> {
+    let definition = definition_regex();
+    let lines: Vec<&str> = proposed.lines().collect();
+    let enclosing = |line: usize| {
+        lines
+            .iter()
+            .take(line)
+            .rposition(|l| definition.is_match(l))
+    };
+
+    let mut groups: Vec<(Vec<usize>, String)> = Vec::new();
+    let mut current: Vec<(usize, usize, usize, Option<usize>)> = Vec::new();
+    let mut flush = |current: &mut Vec<(usize, usize, usize, Option<usize>)>| {
+        if current.len() > 1 {
+            let ids = current.iter().map(|h| h.0).collect();
+            let shared = current[0]
+                .3
+                .filter(|d| current.iter().all(|h| h.3 == Some(*d)));
+            let label = match shared {
+                Some(def) => lines[def]
+                    .trim()
+                    .trim_end_matches('{')
+                    .trim_end()
+                    .to_string(),
+                None => format!("lines {}-{}", current[0].1, current[current.len() - 1].2),
+            };
+            groups.push((ids, label));
+        }
+        current.clear();
+    };
+
+    for hunk in hunks {
+        let Some((start, end)) = new_line_range(hunk) else {
+            continue;
+        };
+        let def = enclosing(start);
+        if let Some(&(_, _, prev_end, prev_def)) = current.last() {
+            let gap = start.saturating_sub(prev_end + 1);
+            if gap > max_gap && (def.is_none() || def != prev_def) {
+                flush(&mut current);
+            }
+        }
+        current.push((hunk.id, start, end, def));
+    }
+    flush(&mut current);
+    groups
+}
+
+/// Lines a file needs before replacing most of them counts as a rewrite
+pub const REWRITE_MIN_LINES: usize = 20;
+
+/// How much of `original` survives in `proposed` (0.0-1.0) given the `hunks`
+/// between them, if that is below `threshold` so the change is better read as
+/// a rewrite than hunk by hunk
+pub fn rewrite_similarity(
+    original: &str,
+    proposed: &str,
+    hunks: &[Hunk],
+    threshold: f32,
+) -> Option<f32> {
+    let old_lines = original.lines().count();
+    let new_lines = proposed.lines().count();
+    if old_lines < REWRITE_MIN_LINES || new_lines == 0 {
+        return None;
+    }
+    let deleted = hunks
+        .iter()
+        .flat_map(|h| &h.changes)
+        .filter(|c| c.tag == ChangeTag::Delete)
+        .count();
+    let kept = old_lines.saturating_sub(deleted);
+    let similarity = 2.0 * kept as f32 / (old_lines + new_lines) as f32;
+    (similarity < threshold).then_some(similarity)
+}
+
+/// One row of a side-by-side view: the original line and the proposed line
+/// shown next to it, each with its 1-based line number
+pub type SideBySideRow<'a> = (Option<(usize, &'a str)>, Option<(usize, &'a str)>);
+
+/// Align `original` and `proposed` into side-by-side rows. Replaced lines are
+/// paired up; lines only on one side leave the other empty.
+pub fn side_by_side<'a>(original: &'a str, proposed: &'a str) -> Vec<SideBySideRow<'a>> {
+    let old: Vec<&str> = original.lines().collect();
+    let new: Vec<&str> = proposed.lines().collect();
+    let mut rows = Vec::new();
+    for op in generate_diff(original, proposed).ops() {
+        let (_, old_range, new_range) = op.as_tag_tuple();
+        let len = old_range.len().max(new_range.len());
+        for i in 0..len {
+            let left = old_range
+                .clone()
+                .nth(i)
+                .and_then(|n| old.get(n).map(|l| (n + 1, *l)));
+            let right = new_range
+                .clone()
+                .nth(i)
+                .and_then(|n| new.get(n).map(|l| (n + 1, *l)));
+            rows.push((left, right));
+        }
+    }
+    rows
+}
+
+/// Combine two edits of `base`, `ours` and `theirs`, line by line. Returns
+/// `None` when both change the same lines or insert at the same place.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> Option<String> {
+    let ours = line_edits(base, ours);
+    let theirs = line_edits(base, theirs);
+
+    let mut edits = ours.clone();
+    for edit in theirs {
+        if ours.contains(&edit) {
+            continue;
+        }
+        let (range, _) = &edit;
+        let clash = ours.iter().any(|(other, _)| {
+            let overlap = range.start < other.end && other.start < range.end;
+            let same_spot = range.start == other.start && (range.is_empty() || other.is_empty());
+            overlap || same_spot
+        });
+        if clash {
+            return None;
+        }
+        edits.push(edit);
+    }
+    edits.sort_by_key(|(range, _)| (range.start, range.end));
+
+    let lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let mut merged = String::new();
+    let mut next = 0;
+    for (range, replacement) in edits {
+        merged.push_str(&lines[next..range.start].concat());
+        merged.push_str(&replacement.concat());
+        next = range.end;
+    }
+    merged.push_str(&lines[next..].concat());
+    Some(merged)
+}
+
+/// The base lines `other` replaces, with what it replaces them by
+fn line_edits<'a>(base: &str, other: &'a str) -> Vec<(std::ops::Range<usize>, Vec<&'a str>)> {
+    let lines: Vec<&str> = other.split_inclusive('\n').collect();
+    generate_diff(base, other)
+        .ops()
+        .iter()
+        .map(|op| op.as_tag_tuple())
+        .filter(|(tag, _, _)| *tag != similar::DiffTag::Equal)
+        .map(|(_, old, new)| (old, lines[new].to_vec()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_generate_diff_identical() {
+        let original = "line 1\nline 2\nline 3";
+        let proposed = "line 1\nline 2\nline 3";
+
+        let diff = generate_diff(original, proposed);
+        let hunks = extract_hunks(&PathBuf::from("test.txt"), &diff);
+
+        // Identical content should produce no hunks
+        assert_eq!(hunks.len(), 0);
+    }
+
+    #[test]
+    fn test_generate_diff_single_change() {
+        let original = "line 1\nline 2\nline 3";
+        let proposed = "line 1\nmodified line\nline 3";
+
+        let diff = generate_diff(original, proposed);
+        let hunks = extract_hunks(&PathBuf::from("test.txt"), &diff);
+
+        assert!(!hunks.is_empty());
+        // Should have changes (at least insert and/or delete)
+        let has_changes = hunks[0]
+            .changes
+            .iter()
+            .any(|c| c.tag == ChangeTag::Insert || c.tag == ChangeTag::Delete);
+        assert!(has_changes);
+    }
+
+    #[test]
+    fn test_generate_diff_addition() {
+        let original = "line 1\nline 3";
+        let proposed = "line 1\nline 2\nline 3";
+
+        let diff = generate_diff(original, proposed);
+        let hunks = extract_hunks(&PathBuf::from("test.txt"), &diff);
+
+        assert!(!hunks.is_empty());
+        // Should have an insertion
+        let has_insert = hunks[0].changes.iter().any(|c| c.tag == ChangeTag::Insert);
+        assert!(has_insert);
+    }
+
+    #[test]
+    fn test_generate_diff_deletion() {
+        let original = "line 1\nline 2\nline 3";
+        let proposed = "line 1\nline 3";
+
+        let diff = generate_diff(original, proposed);
+        let hunks = extract_hunks(&PathBuf::from("test.txt"), &diff);
+
+        assert!(!hunks.is_empty());
+        // Should have a deletion
+        let has_delete = hunks[0].changes.iter().any(|c| c.tag == ChangeTag::Delete);
+        assert!(has_delete);
+    }
+
+    #[test]
+    fn test_hunk_has_correct_file_path() {
+        let original = "line 1";
+        let proposed = "line 1\nline 2";
+
+        let file_path = PathBuf::from("src/main.rs");
+        let diff = generate_diff(original, proposed);
+        let hunks = extract_hunks(&file_path, &diff);
+
+        assert!(!hunks.is_empty());
+        assert_eq!(hunks[0].file_path, file_path);
+    }
+
+    #[test]
+    fn test_hunk_status_is_pending() {
+        let original = "a";
+        let proposed = "b";
+
+        let diff = generate_diff(original, proposed);
+        let hunks = extract_hunks(&PathBuf::from("test.txt"), &diff);
+
+        assert!(!hunks.is_empty());
+        assert_eq!(hunks[0].status, HunkStatus::Pending);
+    }
+
+    #[test]
+    fn test_hunk_lines_point_into_one_copy_of_each_side() {
+        let hunks = compute_hunks(&PathBuf::from("test.txt"), "a\nb\nc\n", "a\nB\nC\nc\n");
+        let text = |tag: ChangeTag| -> Vec<&str> {
+            hunks[0]
+                .changes
+                .iter()
+                .filter(|c| c.tag != tag)
+                .map(|c| c.content.as_str())
+                .collect()
+        };
+        assert_eq!(text(ChangeTag::Insert), ["a\n", "b\n", "c\n"]);
+        assert_eq!(text(ChangeTag::Delete), ["a\n", "B\n", "C\n", "c\n"]);
+
+        // Consecutive lines of a side are adjacent in its buffer
+        let inserted: Vec<&str> = hunks[0]
+            .changes
+            .iter()
+            .filter(|c| c.tag == ChangeTag::Insert)
+            .map(|c| c.content.as_str())
+            .collect();
+        assert_eq!(inserted[1].as_ptr(), inserted[0][2..].as_ptr());
+        assert_eq!(hunks[0].changes[0].content.without_newline(), "a");
+    }
+
+    #[test]
+    fn test_group_hunks_by_gap_and_definition() {
+        let original: String = (1..=60).map(|i| format!("line {}\n", i)).collect();
+        let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+        lines[0] = "fn first() {".to_string();
+        lines[40] = "fn second() {".to_string();
+        let original = lines.join("\n") + "\n";
+
+        // Edits at 10 and 30 share `first`; the edit at 50 is in `second`
+        let mut proposed = lines.clone();
+        for i in [9, 29, 49] {
+            proposed[i] = format!("changed {}", i + 1);
+        }
+        let proposed = proposed.join("\n") + "\n";
+
+        let diff = generate_diff(&original, &proposed);
+        let mut hunks = extract_hunks(&PathBuf::from("a.rs"), &diff);
+        for (id, hunk) in hunks.iter_mut().enumerate() {
+            hunk.id = id;
+        }
+        assert_eq!(hunks.len(), 3);
+
+        let groups = group_hunks(&hunks, &proposed, 5);
+        assert_eq!(groups, vec![(vec![0, 1], "fn first()".to_string())]);
+
+        // A large enough gap joins everything, labelled by line range
+        let groups = group_hunks(&hunks, &proposed, 20);
+        assert_eq!(groups, vec![(vec![0, 1, 2], "lines 10-50".to_string())]);
+    }
+
+    #[test]
+    fn test_merge3_combines_separate_edits_and_refuses_overlaps() {
+        let base = "a\nb\nc\nd\ne\n";
+        let ours = "a\nB\nc\nd\ne\n";
+        let theirs = "a\nb\nc\nd\nE\nf\n";
+        assert_eq!(
+            merge3(base, ours, theirs).as_deref(),
+            Some("a\nB\nc\nd\nE\nf\n")
+        );
+        // The same change on both sides is taken once
+        assert_eq!(merge3(base, ours, ours).as_deref(), Some(ours));
+        assert_eq!(merge3(base, ours, "a\nbee\nc\nd\ne\n"), None);
+    }
+
+    #[test]
+    fn test_rewrite_detection_and_side_by_side_rows() {
+        let similarity = |original: &str, proposed: &str| {
+            let hunks = compute_hunks(&PathBuf::from("a.rs"), original, proposed);
+            rewrite_similarity(original, proposed, &hunks, 0.3)
+        };
+        let original: String = (1..=30).map(|i| format!("old {}\n", i)).collect();
+        let rewritten: String = (1..=25).map(|i| format!("new {}\n", i)).collect();
+        assert_eq!(similarity(&original, &rewritten), Some(0.0));
+
+        let edited = original.replace("old 5\n", "five\n");
+        assert_eq!(similarity(&original, &edited), None);
+        // Small files are always reviewed hunk by hunk
+        assert_eq!(similarity("a\n", "b\n"), None);
+
+        let rows = side_by_side("a\nb\nc\n", "a\nB\nX\nc\n");
+        assert_eq!(
+            rows,
+            vec![
+                (Some((1, "a")), Some((1, "a"))),
+                (Some((2, "b")), Some((2, "B"))),
+                (None, Some((3, "X"))),
+                (Some((3, "c")), Some((4, "c"))),
+            ]
+        );
+    }
+}
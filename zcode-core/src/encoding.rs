@@ -0,0 +1,54 @@
+// zcode-core/src/encoding.rs - Provider output that isn't valid UTF-8
+
+use std::borrow::Cow;
+
+/// Stands in for each invalid byte sequence of [`decode_marked`] output. A
+/// noncharacter, so it doesn't turn up in real text, and unlike U+FFFD it
+/// can't be confused with a replacement character the provider really wrote.
+pub const INVALID: char = '\u{FDD0}';
+
+/// `bytes` as text, each sequence that isn't UTF-8 replaced by [`INVALID`].
+///
+/// Parsing this instead of lossily converted text shows which file bodies
+/// came from broken output ([`is_intact`]) rather than writing replacement
+/// characters to disk.
+pub fn decode_marked(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            text.push(INVALID);
+        }
+    }
+    text
+}
+
+/// Whether `text` has none of the bytes [`decode_marked`] couldn't decode
+pub fn is_intact(text: &str) -> bool {
+    !text.contains(INVALID)
+}
+
+/// `text` for display, with the undecodable bytes shown as U+FFFD the way
+/// lossy conversion shows them
+pub fn for_display(text: &str) -> Cow<'_, str> {
+    if is_intact(text) {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.replace(INVALID, "\u{FFFD}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_bytes_are_marked_apart_from_real_replacement_chars() {
+        let bytes = b"ok \xEF\xBF\xBD then \xFF\xFE bad";
+        let text = decode_marked(bytes);
+        assert_eq!(text, format!("ok \u{FFFD} then {0}{0} bad", INVALID));
+        assert!(!is_intact(&text));
+        assert!(is_intact(&decode_marked("ok \u{FFFD}".as_bytes())));
+        assert_eq!(for_display(&text), String::from_utf8_lossy(bytes));
+    }
+}
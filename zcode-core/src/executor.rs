@@ -0,0 +1,549 @@
+// zcode-core/src/executor.rs - Async command execution using tokio
+
+use anyhow::Result;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Which output stream a chunk was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    Stdout,
+    Stderr,
+}
+
+/// A chunk of process output, tagged with its stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputChunk {
+    pub stream: StreamKind,
+    pub data: Vec<u8>,
+}
+
+/// How process output is captured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptureMode {
+    /// Keep stdout and stderr in separate buffers
+    #[default]
+    Separate,
+    /// Additionally record a transcript of both streams in the order they arrived
+    Merged,
+}
+
+/// Which output a provider's parser should receive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputSource {
+    #[default]
+    Stdout,
+    Stderr,
+    /// Both streams interleaved in arrival order
+    Merged,
+}
+
+impl OutputSource {
+    /// Parse a config value ("stdout", "stderr" or "merged")
+    pub fn from_config(value: &str) -> Option<Self> {
+        match value {
+            "stdout" => Some(Self::Stdout),
+            "stderr" => Some(Self::Stderr),
+            "merged" | "both" => Some(Self::Merged),
+            _ => None,
+        }
+    }
+
+    /// Capture mode needed to produce this output
+    pub fn capture_mode(self) -> CaptureMode {
+        match self {
+            Self::Merged => CaptureMode::Merged,
+            Self::Stdout | Self::Stderr => CaptureMode::Separate,
+        }
+    }
+}
+
+/// A provider invocation, kept so it can be previewed and copied
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandLine {
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    /// Written to the command's standard input; left inherited when `None`
+    pub stdin: Option<String>,
+}
+
+impl CommandLine {
+    /// Shell syntax that runs exactly this command, environment and input included
+    pub fn to_shell(&self) -> String {
+        let line = self.render(shell_quote);
+        match &self.stdin {
+            Some(input) => format!("printf '%s' {} | {}", shell_quote(input), line),
+            None => line,
+        }
+    }
+
+    /// Single-line version for the screen: environment values (which may be
+    /// credentials) and the input are hidden and newlines shown as `↵`
+    pub fn preview(&self) -> String {
+        let line = self.render(|_| "…".to_string()).replace('\n', "↵");
+        match self.stdin {
+            Some(_) => format!("{} < …", line),
+            None => line,
+        }
+    }
+
+    fn render(&self, env_value: impl Fn(&str) -> String) -> String {
+        let env = self
+            .env
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, env_value(value)));
+        let words = std::iter::once(shell_quote(&self.program))
+            .chain(self.args.iter().map(|arg| shell_quote(arg)));
+        env.chain(words).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Quote `word` for a POSIX shell, leaving plain words as they are
+pub fn shell_quote(word: &str) -> String {
+    let plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// Result of a command execution
+#[derive(Debug, Clone)]
+pub struct CommandResult {
+    pub exit_code: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// Both streams in arrival order (only recorded with `CaptureMode::Merged`)
+    pub transcript: Vec<OutputChunk>,
+    pub context: BTreeMap<String, String>,
+}
+
+impl CommandResult {
+    /// Output as text for the given source.
+    ///
+    /// Falls back to stdout followed by stderr if a merged transcript was requested
+    /// but not captured.
+    pub fn output(&self, source: OutputSource) -> String {
+        String::from_utf8_lossy(&self.bytes(source)).into_owned()
+    }
+
+    /// Output for parsing, with bytes that aren't UTF-8 marked
+    /// ([`crate::encoding::decode_marked`]) instead of replaced
+    pub fn output_marked(&self, source: OutputSource) -> String {
+        crate::encoding::decode_marked(&self.bytes(source))
+    }
+
+    fn bytes(&self, source: OutputSource) -> Cow<'_, [u8]> {
+        match source {
+            OutputSource::Stdout => Cow::Borrowed(&self.stdout),
+            OutputSource::Stderr => Cow::Borrowed(&self.stderr),
+            OutputSource::Merged if self.transcript.is_empty() => {
+                let mut bytes = self.stdout.clone();
+                bytes.extend_from_slice(&self.stderr);
+                Cow::Owned(bytes)
+            }
+            OutputSource::Merged => Cow::Owned(
+                self.transcript
+                    .iter()
+                    .flat_map(|chunk| chunk.data.iter().copied())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Execute a command asynchronously and return the result
+pub async fn execute_command(
+    command: &str,
+    args: &[String],
+    context: BTreeMap<String, String>,
+) -> Result<CommandResult> {
+    execute_command_with(command, args, &[], context, CaptureMode::Separate).await
+}
+
+/// Execute a command with extra environment variables, capturing its output
+/// with the given mode
+pub async fn execute_command_with(
+    command: &str,
+    args: &[String],
+    env: &[(String, String)],
+    context: BTreeMap<String, String>,
+    capture: CaptureMode,
+) -> Result<CommandResult> {
+    let command = CommandLine {
+        program: command.to_string(),
+        args: args.to_vec(),
+        env: env.to_vec(),
+        stdin: None,
+    };
+    execute_command_streaming(&command, context, capture, None, None).await
+}
+
+/// Like [`execute_command_with`] for a whole [`CommandLine`], additionally
+/// sending each complete stdout line to `lines` while the command runs, in
+/// `dir` when given
+pub async fn execute_command_streaming(
+    command: &CommandLine,
+    context: BTreeMap<String, String>,
+    capture: CaptureMode,
+    lines: Option<UnboundedSender<String>>,
+    dir: Option<&Path>,
+) -> Result<CommandResult> {
+    let mut cmd = Command::new(&command.program);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    if command.stdin.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
+    let program = &command.program;
+    let mut child = cmd
+        .args(&command.args)
+        .envs(command.env.iter().map(|(k, v)| (k, v)))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            // Provide better context for command not found errors
+            if e.kind() == std::io::ErrorKind::NotFound {
+                anyhow::anyhow!("Command '{}' not found in PATH", program)
+            } else {
+                anyhow::anyhow!("Failed to execute '{}': {}", program, e)
+            }
+        })?;
+
+    // Written from its own task so a command that answers before reading all
+    // of its input can't block on a full output pipe; dropping it closes stdin
+    if let (Some(input), Some(mut pipe)) = (command.stdin.clone(), child.stdin.take()) {
+        tokio::spawn(async move {
+            let _ = pipe.write_all(input.as_bytes()).await;
+        });
+    }
+
+    let mut stdout = child.stdout.take().expect("Failed to capture stdout");
+    let mut stderr = child.stderr.take().expect("Failed to capture stderr");
+
+    // Read both streams from one task so chunks are recorded in arrival order
+    let reader = tokio::spawn(async move {
+        let mut stdout_bytes = Vec::new();
+        let mut stderr_bytes = Vec::new();
+        let mut transcript: Vec<OutputChunk> = Vec::new();
+        // Start of the stdout line not yet sent to `lines`
+        let mut line_start = 0;
+        let mut stdout_buf = [0u8; 8192];
+        let mut stderr_buf = [0u8; 8192];
+        let (mut stdout_open, mut stderr_open) = (true, true);
+
+        while stdout_open || stderr_open {
+            let (stream, data) = tokio::select! {
+                read = stdout.read(&mut stdout_buf), if stdout_open => match read {
+                    Ok(n) if n > 0 => (StreamKind::Stdout, &stdout_buf[..n]),
+                    _ => {
+                        stdout_open = false;
+                        continue;
+                    }
+                },
+                read = stderr.read(&mut stderr_buf), if stderr_open => match read {
+                    Ok(n) if n > 0 => (StreamKind::Stderr, &stderr_buf[..n]),
+                    _ => {
+                        stderr_open = false;
+                        continue;
+                    }
+                },
+            };
+
+            match stream {
+                StreamKind::Stdout => stdout_bytes.extend_from_slice(data),
+                StreamKind::Stderr => stderr_bytes.extend_from_slice(data),
+            }
+            if let (StreamKind::Stdout, Some(lines)) = (stream, &lines) {
+                while let Some(end) = stdout_bytes[line_start..].iter().position(|&b| b == b'\n') {
+                    let line = &stdout_bytes[line_start..line_start + end];
+                    let _ = lines.send(String::from_utf8_lossy(line).into_owned());
+                    line_start += end + 1;
+                }
+            }
+            if capture == CaptureMode::Merged {
+                // Coalesce consecutive reads from the same stream
+                match transcript.last_mut() {
+                    Some(last) if last.stream == stream => last.data.extend_from_slice(data),
+                    _ => transcript.push(OutputChunk {
+                        stream,
+                        data: data.to_vec(),
+                    }),
+                }
+            }
+        }
+
+        (stdout_bytes, stderr_bytes, transcript)
+    });
+
+    // Wait for process to complete
+    let status = child.wait().await?;
+    let (stdout_bytes, stderr_bytes, transcript) = reader.await?;
+
+    Ok(CommandResult {
+        exit_code: status.code(),
+        stdout: stdout_bytes,
+        stderr: stderr_bytes,
+        transcript,
+        context,
+    })
+}
+
+/// Run a provider's detection: `command` with its
+/// [`detection_args`](crate::providers::AIProvider::detection_args)
+pub async fn execute_provider_detection(
+    command: &str,
+    args: Vec<String>,
+    provider_id: &str,
+    display_name: &str,
+    config_key: &str,
+) -> Result<CommandResult> {
+    let mut context = BTreeMap::new();
+    context.insert("provider_id".to_string(), provider_id.to_string());
+    context.insert("display_name".to_string(), display_name.to_string());
+    context.insert("cli_command".to_string(), command.to_string());
+    context.insert("config_key".to_string(), config_key.to_string());
+
+    execute_command(command, &args, context).await
+}
+
+/// Execute AI provider prompt command, streaming stdout lines to `lines`.
+/// It runs in `dir` (a [`crate::sandbox::Sandbox`]) when given.
+pub async fn execute_provider_prompt(
+    command: &CommandLine,
+    provider_name: &str,
+    capture: CaptureMode,
+    lines: Option<UnboundedSender<String>>,
+    dir: Option<&Path>,
+) -> Result<CommandResult> {
+    let mut context = BTreeMap::new();
+    context.insert("request_type".to_string(), "prompt_execution".to_string());
+    context.insert("provider".to_string(), provider_name.to_string());
+
+    execute_command_streaming(command, context, capture, lines, dir).await
+}
+
+/// Run the configured test command through the shell
+pub async fn execute_test_command(command: &str) -> Result<CommandResult> {
+    let mut context = BTreeMap::new();
+    context.insert("test_command".to_string(), command.to_string());
+    execute_shell(command, "test_run", context).await
+}
+
+/// Run `command` through the shell, tagging the result with `request_type`
+/// and the command itself
+pub async fn execute_shell_command(command: &str, request_type: &str) -> Result<CommandResult> {
+    let mut context = BTreeMap::new();
+    context.insert("command".to_string(), command.to_string());
+    execute_shell(command, request_type, context).await
+}
+
+async fn execute_shell(
+    command: &str,
+    request_type: &str,
+    mut context: BTreeMap<String, String>,
+) -> Result<CommandResult> {
+    context.insert("request_type".to_string(), request_type.to_string());
+    let args = ["-c".to_string(), command.to_string()];
+    execute_command_with("sh", &args, &[], context, CaptureMode::Merged).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_execute_command() {
+        let result = execute_command("echo", &["hello".to_string()], BTreeMap::new())
+            .await
+            .unwrap();
+
+        assert_eq!(result.exit_code, Some(0));
+        assert!(String::from_utf8_lossy(&result.stdout).contains("hello"));
+    }
+
+    #[tokio::test]
+    async fn test_provider_detection() {
+        // Test with a command that should exist
+        let result =
+            execute_provider_detection("echo", Vec::new(), "test", "Test Provider", "test")
+                .await
+                .unwrap();
+
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(result.context.get("provider_id").unwrap(), "test");
+    }
+
+    #[tokio::test]
+    async fn test_command_line_round_trips_through_shell() {
+        let command = CommandLine {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                "printf '%s|' \"$Q\" \"$1\"".to_string(),
+                "it's".to_string(),
+                "a b\nc".to_string(),
+            ],
+            env: vec![("Q".to_string(), "secret value".to_string())],
+            stdin: None,
+        };
+        let shell = command.to_shell();
+        let result = execute_command("sh", &["-c".to_string(), shell], BTreeMap::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&result.stdout),
+            "secret value|a b\nc|"
+        );
+
+        let preview = command.preview();
+        assert!(preview.starts_with("Q=… sh -c "));
+        assert!(!preview.contains("secret") && !preview.contains('\n'));
+    }
+
+    #[tokio::test]
+    async fn test_env_is_passed_to_command() {
+        let result = execute_command_with(
+            "sh",
+            &["-c".to_string(), "echo $ZCODE_TEST_VAR".to_string()],
+            &[("ZCODE_TEST_VAR".to_string(), "from-config".to_string())],
+            BTreeMap::new(),
+            CaptureMode::Separate,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8_lossy(&result.stdout).trim(),
+            "from-config"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merged_capture_preserves_order() {
+        let script = "echo one; sleep 0.1; echo two >&2; sleep 0.1; echo three";
+        let result = execute_command_with(
+            "sh",
+            &["-c".to_string(), script.to_string()],
+            &[],
+            BTreeMap::new(),
+            CaptureMode::Merged,
+        )
+        .await
+        .unwrap();
+
+        let streams: Vec<StreamKind> = result.transcript.iter().map(|c| c.stream).collect();
+        assert_eq!(
+            streams,
+            vec![StreamKind::Stdout, StreamKind::Stderr, StreamKind::Stdout]
+        );
+        assert_eq!(result.output(OutputSource::Merged), "one\ntwo\nthree\n");
+        assert_eq!(result.output(OutputSource::Stdout), "one\nthree\n");
+        assert_eq!(result.output(OutputSource::Stderr), "two\n");
+    }
+
+    #[tokio::test]
+    async fn test_provider_prompt_runs_in_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("marker"), "").unwrap();
+        let command = CommandLine {
+            program: "ls".to_string(),
+            args: Vec::new(),
+            env: Vec::new(),
+            stdin: None,
+        };
+        let result = execute_provider_prompt(
+            &command,
+            "test",
+            CaptureMode::Separate,
+            None,
+            Some(dir.path()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.output(OutputSource::Stdout), "marker\n");
+    }
+
+    #[tokio::test]
+    async fn test_stdin_carries_input_too_large_for_an_argument() {
+        // Over Linux's 128 KiB limit on a single argument
+        let input = "x".repeat(200 * 1024);
+        let command = CommandLine {
+            program: "wc".to_string(),
+            args: vec!["-c".to_string()],
+            env: Vec::new(),
+            stdin: Some(input.clone()),
+        };
+        let result =
+            execute_command_streaming(&command, BTreeMap::new(), CaptureMode::Separate, None, None)
+                .await
+                .unwrap();
+        assert_eq!(
+            result.output(OutputSource::Stdout).trim(),
+            input.len().to_string()
+        );
+
+        let command = CommandLine {
+            program: "cat".to_string(),
+            args: Vec::new(),
+            env: Vec::new(),
+            stdin: Some("it's\nfed".to_string()),
+        };
+        let shell = command.to_shell();
+        let result = execute_command("sh", &["-c".to_string(), shell], BTreeMap::new())
+            .await
+            .unwrap();
+        assert_eq!(result.output(OutputSource::Stdout), "it's\nfed");
+        assert_eq!(command.preview(), "cat < …");
+    }
+
+    #[tokio::test]
+    async fn test_stdout_lines_are_streamed() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let script = "printf 'one\\ntw'; sleep 0.1; printf 'o\\nthree'";
+        let command = CommandLine {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: Vec::new(),
+            stdin: None,
+        };
+        let result = execute_command_streaming(
+            &command,
+            BTreeMap::new(),
+            CaptureMode::Separate,
+            Some(tx),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut lines = Vec::new();
+        while let Ok(line) = rx.try_recv() {
+            lines.push(line);
+        }
+        // The unterminated last line stays in the captured output only
+        assert_eq!(lines, vec!["one", "two"]);
+        assert_eq!(result.output(OutputSource::Stdout), "one\ntwo\nthree");
+    }
+
+    #[tokio::test]
+    async fn test_separate_capture_skips_transcript() {
+        let result = execute_command("echo", &["hello".to_string()], BTreeMap::new())
+            .await
+            .unwrap();
+        assert!(result.transcript.is_empty());
+        assert_eq!(result.output(OutputSource::Merged), "hello\n");
+    }
+}
@@ -0,0 +1,138 @@
+//! File contents read once and reused while the file is unchanged
+//!
+//! Parsing a reply looks up the original of every file it names, often more
+//! than once (a file in several code blocks, the merge of edits to one file),
+//! and the sidebar previews a file on every frame. [`read`] keeps each file's
+//! content with the modification time and length it was read at; a later read
+//! costs one `stat` while those still match. Writes through
+//! [`crate::file_ops::atomic_write`] drop the entry, and the TUI's file watcher
+//! calls [`invalidate`] for edits made behind its back.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Files kept at most; past it the cache starts over
+const MAX_ENTRIES: usize = 1024;
+
+struct Entry {
+    modified: SystemTime,
+    len: u64,
+    content: Arc<str>,
+}
+
+/// Contents by absolute path, valid while the modification time and length
+/// are what they were when read
+#[derive(Default)]
+pub struct FileCache {
+    entries: Mutex<HashMap<PathBuf, Entry>>,
+}
+
+impl FileCache {
+    /// The content of `path`, from the cache when the file hasn't changed
+    /// since it was read. `None` when it can't be read as UTF-8 text.
+    pub fn read(&self, path: &Path) -> Option<Arc<str>> {
+        let key = key(path);
+        let Ok(metadata) = std::fs::metadata(&key) else {
+            self.invalidate(&key);
+            return None;
+        };
+        // Without a modification time there is nothing to check an entry by
+        let Ok(modified) = metadata.modified() else {
+            return std::fs::read_to_string(&key).ok().map(Arc::from);
+        };
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get(&key) {
+            if entry.modified == modified && entry.len == metadata.len() {
+                return Some(entry.content.clone());
+            }
+        }
+        let Ok(content) = std::fs::read_to_string(&key) else {
+            entries.remove(&key);
+            return None;
+        };
+        let content: Arc<str> = Arc::from(content);
+        if entries.len() >= MAX_ENTRIES {
+            entries.clear();
+        }
+        entries.insert(
+            key,
+            Entry {
+                modified,
+                len: metadata.len(),
+                content: content.clone(),
+            },
+        );
+        Some(content)
+    }
+
+    /// Forget `path`, so the next read goes to disk
+    pub fn invalidate(&self, path: &Path) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(&key(path));
+    }
+
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+/// The cache shared by the parsers, file operations and the TUI
+pub fn shared() -> &'static FileCache {
+    static CACHE: OnceLock<FileCache> = OnceLock::new();
+    CACHE.get_or_init(FileCache::default)
+}
+
+/// [`FileCache::read`] on the [`shared`] cache
+pub fn read(path: &Path) -> Option<Arc<str>> {
+    shared().read(path)
+}
+
+/// [`read`] as an owned string, for fields like
+/// [`FileChange::original_content`](crate::FileChange::original_content)
+pub fn read_string(path: &Path) -> Option<String> {
+    read(path).map(|content| content.to_string())
+}
+
+/// [`FileCache::invalidate`] on the [`shared`] cache
+pub fn invalidate(path: &Path) {
+    shared().invalidate(path);
+}
+
+/// Relative and absolute spellings of a path share an entry
+fn key(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_are_reused_until_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "one\n").unwrap();
+        let cache = FileCache::default();
+
+        let first = cache.read(&path).unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.read(&path).unwrap()));
+
+        // A different length is noticed even within the mtime's granularity
+        std::fs::write(&path, "three\n").unwrap();
+        assert_eq!(&*cache.read(&path).unwrap(), "three\n");
+
+        let cached = cache.read(&path).unwrap();
+        cache.invalidate(&path);
+        assert!(!Arc::ptr_eq(&cached, &cache.read(&path).unwrap()));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(cache.read(&path).is_none());
+        std::fs::write(&path, [0xff, 0xfe]).unwrap();
+        assert!(cache.read(&path).is_none());
+    }
+}
@@ -0,0 +1,679 @@
+// zcode-core/src/file_ops/apply.rs - Hunk application logic with transaction model
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::reconstruct::splice_lines;
+use super::trash::{trash_root, Trash};
+use super::{atomic_write_with, reconstruct_file_content, BackupSet, WritePolicy};
+use crate::config::{ApplyMode, SymlinkMode};
+use crate::types::{ChangeType, FileChange, Hunk, HunkStatus};
+
+/// How an apply writes files; by default without backups, all or nothing,
+/// through symlinks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ApplyOptions {
+    /// Back every file up before writing it, to roll back a failed apply
+    pub create_backups: bool,
+    pub apply_mode: ApplyMode,
+    pub symlinks: SymlinkMode,
+}
+
+/// Result of applying hunks to files
+#[derive(Debug, Clone)]
+pub struct ApplyResult {
+    /// Files that were successfully modified
+    pub files_modified: Vec<PathBuf>,
+    /// Backups that were created, as (file, backup) pairs
+    pub backups_created: Vec<(PathBuf, PathBuf)>,
+    /// Number of hunks applied
+    pub hunks_applied: usize,
+    /// Files with accepted changes that were skipped by `.zcodeignore`
+    pub files_ignored: Vec<PathBuf>,
+    /// Files that failed to apply (per-file mode only) and why; each was
+    /// restored from its backup
+    pub files_failed: Vec<(PathBuf, String)>,
+    /// Files deleted by the changes; they were moved to `trash_dir`
+    pub files_deleted: Vec<PathBuf>,
+    /// Where this apply's deleted files went (restorable with `:undo`)
+    pub trash_dir: Option<PathBuf>,
+    /// Files moved to a new path before being written there, as (old, new)
+    /// pairs; the new paths are in `files_modified`
+    pub files_renamed: Vec<(PathBuf, PathBuf)>,
+}
+
+impl ApplyResult {
+    /// One-line summary for the status bar
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Applied {} hunks to {} files",
+            self.hunks_applied,
+            self.files_modified.len()
+        );
+        if let Some(dir) = self
+            .trash_dir
+            .as_ref()
+            .filter(|_| !self.files_deleted.is_empty())
+        {
+            summary.push_str(&format!(
+                ", deleted {} (moved to {}, :undo restores)",
+                self.files_deleted.len(),
+                dir.display()
+            ));
+        }
+        if !self.files_renamed.is_empty() {
+            summary.push_str(&format!(", renamed {}", self.files_renamed.len()));
+        }
+        if !self.files_failed.is_empty() {
+            summary.push_str(&format!(", {} failed", self.files_failed.len()));
+        }
+        if !self.files_ignored.is_empty() {
+            summary.push_str(&format!(
+                " ({} skipped by .zcodeignore)",
+                self.files_ignored.len()
+            ));
+        }
+        summary
+    }
+
+    /// Per-file breakdown of what was written, what failed and what was skipped
+    pub fn report(&self) -> String {
+        let mut lines = Vec::new();
+        for path in &self.files_modified {
+            match self.files_renamed.iter().find(|(_, new)| new == path) {
+                Some((old, _)) => lines.push(format!("✓ {} → {}", old.display(), path.display())),
+                None => lines.push(format!("✓ {}", path.display())),
+            }
+        }
+        for path in &self.files_deleted {
+            lines.push(format!("✓ {} (deleted)", path.display()));
+        }
+        for (path, error) in &self.files_failed {
+            lines.push(format!("✗ {}: {}", path.display(), error));
+        }
+        for path in &self.files_ignored {
+            lines.push(format!("- {} (skipped by .zcodeignore)", path.display()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Apply all accepted hunks to their respective files
+pub fn apply_accepted_hunks(
+    hunks: &[&Hunk],
+    pending_changes: &HashMap<PathBuf, FileChange>,
+    options: &ApplyOptions,
+    policy: &WritePolicy,
+) -> Result<ApplyResult> {
+    // Filter to only accepted hunks
+    let accepted_hunks: Vec<_> = hunks
+        .iter()
+        .filter(|h| h.status == HunkStatus::Accepted)
+        .copied()
+        .collect();
+
+    if accepted_hunks.is_empty() {
+        return Err(anyhow!("No accepted hunks to apply"));
+    }
+
+    // Group hunks by file, leaving out files the write policy refuses
+    let mut hunks_by_file: BTreeMap<PathBuf, Vec<&Hunk>> = BTreeMap::new();
+    let mut files_ignored = Vec::new();
+    for hunk in &accepted_hunks {
+        // A rename also removes its old path, which the policy must allow
+        let resolved = match renamed_from(pending_changes, &hunk.file_path) {
+            Some(from) => policy
+                .resolve(from)
+                .and_then(|_| policy.resolve(&hunk.file_path)),
+            None => policy.resolve(&hunk.file_path),
+        };
+        match resolved {
+            Ok(path) => hunks_by_file.entry(path).or_default().push(hunk),
+            Err(_) if !files_ignored.contains(&hunk.file_path) => {
+                files_ignored.push(hunk.file_path.clone())
+            }
+            Err(_) => {}
+        }
+    }
+
+    if hunks_by_file.is_empty() {
+        return Err(anyhow!(
+            "All accepted hunks are in files ignored by {}",
+            super::policy::IGNORE_FILE
+        ));
+    }
+    if options.apply_mode == ApplyMode::PerFile {
+        return Ok(apply_each_file(
+            &hunks_by_file,
+            pending_changes,
+            options,
+            files_ignored,
+        ));
+    }
+    let hunks_applied = hunks_by_file.values().map(Vec::len).sum();
+
+    // Prepare files to modify
+    let files_to_modify: Vec<PathBuf> = hunks_by_file.keys().cloned().collect();
+
+    // A read-only filesystem fails the apply before any file is touched
+    for file_path in &files_to_modify {
+        super::ensure_writable(file_path.parent().unwrap_or(Path::new(".")))?;
+    }
+
+    // Create backups for all files (transaction model); a renamed file's
+    // content is still at its old path
+    let backup_set = if options.create_backups {
+        let sources: Vec<PathBuf> = files_to_modify
+            .iter()
+            .map(|path| {
+                renamed_from(pending_changes, path)
+                    .unwrap_or(path)
+                    .to_path_buf()
+            })
+            .collect();
+        BackupSet::create(&sources).context("Failed to create backups")?
+    } else {
+        BackupSet {
+            backups: HashMap::new(),
+            timestamp: String::new(),
+        }
+    };
+
+    let backups_created = backup_set.pairs();
+
+    // Apply changes to all files
+    let mut trash = Trash::new(&trash_root());
+    let mut files_renamed = Vec::new();
+    let files_modified = match apply_all_files(
+        &hunks_by_file,
+        pending_changes,
+        options.symlinks,
+        &mut trash,
+        &mut files_renamed,
+    ) {
+        Ok(modified) => modified,
+        Err(e) => {
+            // Rollback on failure; deleted files first so backups don't block
+            // them, then renamed files back to where their backups go
+            let _ = trash.restore_all();
+            for (from, to) in files_renamed.iter().rev() {
+                let _ = fs::rename(to, from);
+            }
+            if options.create_backups {
+                let _ = backup_set.restore_all();
+            }
+            return Err(e).context("Failed to apply hunks");
+        }
+    };
+    let files_deleted: Vec<PathBuf> = trash.entries().iter().map(|e| e.original.clone()).collect();
+
+    Ok(ApplyResult {
+        files_modified,
+        backups_created,
+        hunks_applied,
+        files_ignored,
+        files_failed: Vec::new(),
+        trash_dir: (!files_deleted.is_empty()).then(|| trash.dir().to_path_buf()),
+        files_deleted,
+        files_renamed,
+    })
+}
+
+/// Apply each file on its own: a failure restores just that file's backup and
+/// is recorded, while the remaining files still apply
+fn apply_each_file(
+    hunks_by_file: &BTreeMap<PathBuf, Vec<&Hunk>>,
+    pending_changes: &HashMap<PathBuf, FileChange>,
+    options: &ApplyOptions,
+    files_ignored: Vec<PathBuf>,
+) -> ApplyResult {
+    let mut result = ApplyResult {
+        files_modified: Vec::new(),
+        backups_created: Vec::new(),
+        hunks_applied: 0,
+        files_ignored,
+        files_failed: Vec::new(),
+        files_deleted: Vec::new(),
+        trash_dir: None,
+        files_renamed: Vec::new(),
+    };
+    let mut trash = Trash::new(&trash_root());
+
+    for (file_path, hunks) in hunks_by_file {
+        let backup_set = if options.create_backups {
+            let source = renamed_from(pending_changes, file_path).unwrap_or(file_path);
+            match BackupSet::create(&[source.to_path_buf()]) {
+                Ok(set) => Some(set),
+                Err(e) => {
+                    let error = format!("{:#}", e.context("Failed to create backup"));
+                    result.files_failed.push((file_path.clone(), error));
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+
+        match apply_file(
+            file_path,
+            hunks,
+            pending_changes,
+            options.symlinks,
+            &mut trash,
+            &mut result.files_renamed,
+        ) {
+            Ok(deleted) => {
+                if !deleted {
+                    result.files_modified.push(file_path.clone());
+                }
+                result.hunks_applied += hunks.len();
+                if let Some(set) = backup_set {
+                    result.backups_created.extend(set.pairs());
+                }
+            }
+            Err(e) => {
+                let mut error = format!("{:#}", e);
+                if let Some(set) = &backup_set {
+                    if let Err(restore) = set.restore_all() {
+                        error.push_str(&format!(" (rollback failed: {:#})", restore));
+                    }
+                }
+                result.files_failed.push((file_path.clone(), error));
+            }
+        }
+    }
+
+    result.files_deleted = trash.entries().iter().map(|e| e.original.clone()).collect();
+    if !result.files_deleted.is_empty() {
+        result.trash_dir = Some(trash.dir().to_path_buf());
+    }
+    result
+}
+
+/// Apply changes to all affected files
+fn apply_all_files(
+    hunks_by_file: &BTreeMap<PathBuf, Vec<&Hunk>>,
+    pending_changes: &HashMap<PathBuf, FileChange>,
+    symlinks: SymlinkMode,
+    trash: &mut Trash,
+    renamed: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<Vec<PathBuf>> {
+    let mut files_modified = Vec::new();
+
+    for (file_path, hunks) in hunks_by_file {
+        if !apply_file(file_path, hunks, pending_changes, symlinks, trash, renamed)? {
+            files_modified.push(file_path.clone());
+        }
+    }
+
+    Ok(files_modified)
+}
+
+/// The path a pending change renames `path` from, if it does
+fn renamed_from<'a>(
+    pending_changes: &'a HashMap<PathBuf, FileChange>,
+    path: &Path,
+) -> Option<&'a Path> {
+    pending_changes.get(path)?.renamed_from.as_deref()
+}
+
+/// Reconstruct one file from its accepted hunks and write it.
+///
+/// A deletion whose hunks are all accepted (nothing of the file is left) moves
+/// the file into `trash` instead; returns whether that happened. A renamed
+/// file is moved to its new path and written there, recorded in `renamed`.
+fn apply_file(
+    file_path: &PathBuf,
+    hunks: &[&Hunk],
+    pending_changes: &HashMap<PathBuf, FileChange>,
+    symlinks: SymlinkMode,
+    trash: &mut Trash,
+    renamed: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<bool> {
+    let change_type = pending_changes.get(file_path).map(|c| &c.change_type);
+    let renamed_from = renamed_from(pending_changes, file_path);
+
+    // Reconstruct file content
+    let new_content = if change_type == Some(&ChangeType::Create) {
+        // New file creation
+        reconstruct_file_content("", hunks).context(format!(
+            "Failed to reconstruct new file: {}",
+            file_path.display()
+        ))?
+    } else {
+        // Existing file modification
+        let source = renamed_from.unwrap_or(file_path);
+        let original = fs::read_to_string(source)
+            .context(format!("Failed to read file: {}", source.display()))?;
+
+        reconstruct_file_content(&original, hunks).context(format!(
+            "Failed to reconstruct file: {}",
+            file_path.display()
+        ))?
+    };
+
+    if change_type == Some(&ChangeType::Delete) && new_content.trim().is_empty() {
+        trash
+            .move_in(file_path)
+            .context(format!("Failed to delete file: {}", file_path.display()))?;
+        return Ok(true);
+    }
+
+    if let Some(from) = renamed_from {
+        rename_and_write(from, file_path, &new_content, symlinks)?;
+        renamed.push((from.to_path_buf(), file_path.clone()));
+        return Ok(false);
+    }
+
+    // Write file atomically
+    atomic_write_with(file_path, &new_content, symlinks)
+        .context(format!("Failed to write file: {}", file_path.display()))?;
+    Ok(false)
+}
+
+/// Move `from` to `to`, then write `content` there, as one step: a failed
+/// write moves the file back
+fn rename_and_write(from: &Path, to: &Path, content: &str, symlinks: SymlinkMode) -> Result<()> {
+    if to.exists() {
+        return Err(anyhow!(
+            "Failed to rename {}: {} already exists",
+            from.display(),
+            to.display()
+        ));
+    }
+    if let Some(parent) = to.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .context(format!("Failed to create directory: {}", parent.display()))?;
+    }
+    fs::rename(from, to).context(format!(
+        "Failed to rename {} to {}",
+        from.display(),
+        to.display()
+    ))?;
+    crate::file_cache::invalidate(from);
+    if let Err(e) = atomic_write_with(to, content, symlinks) {
+        let _ = fs::rename(to, from);
+        return Err(e).context(format!("Failed to write file: {}", to.display()));
+    }
+    Ok(())
+}
+
+/// Apply a single hunk to its file as it is on disk, leaving the file's other
+/// hunks unapplied. Fails if the lines the hunk replaces have changed since
+/// it was diffed.
+pub fn apply_one_hunk(
+    hunk: &Hunk,
+    options: &ApplyOptions,
+    policy: &WritePolicy,
+) -> Result<ApplyResult> {
+    let file_path = policy.resolve(&hunk.file_path)?;
+    let original = if file_path.exists() {
+        fs::read_to_string(&file_path)
+            .context(format!("Failed to read file: {}", file_path.display()))?
+    } else {
+        String::new()
+    };
+    let new_content = splice_hunk(&original, hunk).ok_or_else(|| {
+        anyhow!(
+            "{} changed since the hunk was diffed",
+            hunk.file_path.display()
+        )
+    })?;
+
+    let backup_set = if options.create_backups && file_path.exists() {
+        BackupSet::create(std::slice::from_ref(&file_path)).context("Failed to create backup")?
+    } else {
+        BackupSet {
+            backups: HashMap::new(),
+            timestamp: String::new(),
+        }
+    };
+    atomic_write_with(&file_path, &new_content, options.symlinks)
+        .context(format!("Failed to write file: {}", file_path.display()))?;
+
+    Ok(ApplyResult {
+        files_modified: vec![file_path],
+        backups_created: backup_set.pairs(),
+        hunks_applied: 1,
+        files_ignored: Vec::new(),
+        files_failed: Vec::new(),
+        files_deleted: Vec::new(),
+        trash_dir: None,
+        files_renamed: Vec::new(),
+    })
+}
+
+/// Replace the old lines of `hunk` in `content` with its new ones, or `None`
+/// if the old lines aren't there any more
+fn splice_hunk(content: &str, hunk: &Hunk) -> Option<String> {
+    let mut lines: Vec<&str> = content.split_inclusive('\n').collect();
+    splice_lines(&mut lines, hunk).ok()?;
+    Some(lines.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChangeTag, HunkRange, LineChange};
+
+    fn create_test_hunk(
+        file_path: PathBuf,
+        start_line: usize,
+        changes: Vec<(ChangeTag, String)>,
+    ) -> Hunk {
+        Hunk {
+            id: 0,
+            file_path,
+            old: HunkRange::from_indices(
+                start_line
+                    ..start_line
+                        + changes
+                            .iter()
+                            .filter(|(t, _)| *t != ChangeTag::Insert)
+                            .count(),
+            ),
+            new: HunkRange::from_indices(
+                start_line
+                    ..start_line
+                        + changes
+                            .iter()
+                            .filter(|(t, _)| *t != ChangeTag::Delete)
+                            .count(),
+            ),
+            changes: changes
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (tag, content))| {
+                    let old_line = if tag == ChangeTag::Delete {
+                        Some(start_line + idx)
+                    } else {
+                        None
+                    };
+                    let new_line = if tag != ChangeTag::Delete {
+                        Some(start_line + idx)
+                    } else {
+                        None
+                    };
+                    LineChange {
+                        tag,
+                        content: content.into(),
+                        old_line_num: old_line,
+                        new_line_num: new_line,
+                    }
+                })
+                .collect(),
+            status: HunkStatus::Accepted,
+            drifted: false,
+            comment: None,
+            auto_accepted: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_result_structure() {
+        let result = ApplyResult {
+            files_modified: vec![PathBuf::from("test.txt")],
+            backups_created: vec![(PathBuf::from("test.txt"), PathBuf::from("/backup/test.txt"))],
+            hunks_applied: 1,
+            files_ignored: Vec::new(),
+            files_failed: Vec::new(),
+            files_deleted: Vec::new(),
+            trash_dir: None,
+            files_renamed: Vec::new(),
+        };
+
+        assert_eq!(result.files_modified.len(), 1);
+        assert_eq!(result.backups_created.len(), 1);
+        assert_eq!(result.hunks_applied, 1);
+    }
+
+    #[test]
+    fn test_no_accepted_hunks() {
+        let mut hunk = create_test_hunk(
+            PathBuf::from("test.txt"),
+            1,
+            vec![(ChangeTag::Insert, "line".to_string())],
+        );
+        hunk.status = HunkStatus::Pending; // Mark as not accepted
+
+        let options = ApplyOptions::default();
+        let pending_changes = HashMap::new();
+
+        let result = apply_accepted_hunks(
+            &[&hunk],
+            &pending_changes,
+            &options,
+            &WritePolicy::default(),
+        );
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("No accepted hunks"));
+    }
+
+    #[test]
+    fn test_ignored_files_are_never_written() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(super::super::policy::IGNORE_FILE),
+            "*.lock\n",
+        )
+        .unwrap();
+        let policy = WritePolicy::load(dir.path()).unwrap();
+
+        let locked = dir.path().join("Cargo.lock");
+        let source = dir.path().join("lib.rs");
+        fs::write(&locked, "old\n").unwrap();
+        fs::write(&source, "old\n").unwrap();
+
+        let hunks: Vec<Hunk> = [&locked, &source]
+            .into_iter()
+            .map(|path| {
+                create_test_hunk(
+                    path.clone(),
+                    0,
+                    vec![
+                        (ChangeTag::Delete, "old\n".to_string()),
+                        (ChangeTag::Insert, "new\n".to_string()),
+                    ],
+                )
+            })
+            .collect();
+        let hunk_refs: Vec<&Hunk> = hunks.iter().collect();
+
+        let options = ApplyOptions::default();
+        let result = apply_accepted_hunks(&hunk_refs, &HashMap::new(), &options, &policy).unwrap();
+
+        assert_eq!(result.files_modified, vec![source.clone()]);
+        assert_eq!(result.files_ignored, vec![locked.clone()]);
+        assert_eq!(fs::read_to_string(&locked).unwrap(), "old\n");
+    }
+
+    #[test]
+    fn test_per_file_mode_isolates_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let present = dir.path().join("lib.rs");
+        let missing = dir.path().join("gone.rs");
+        fs::write(&present, "old\n").unwrap();
+
+        let hunks: Vec<Hunk> = [&missing, &present]
+            .into_iter()
+            .map(|path| {
+                create_test_hunk(
+                    path.clone(),
+                    0,
+                    vec![
+                        (ChangeTag::Delete, "old\n".to_string()),
+                        (ChangeTag::Insert, "new\n".to_string()),
+                    ],
+                )
+            })
+            .collect();
+        let hunk_refs: Vec<&Hunk> = hunks.iter().collect();
+        let policy = WritePolicy::load(dir.path()).unwrap();
+
+        let mut options = ApplyOptions::default();
+        assert!(apply_accepted_hunks(&hunk_refs, &HashMap::new(), &options, &policy).is_err());
+        assert_eq!(fs::read_to_string(&present).unwrap(), "old\n");
+
+        options.apply_mode = ApplyMode::PerFile;
+        let result = apply_accepted_hunks(&hunk_refs, &HashMap::new(), &options, &policy).unwrap();
+        assert_eq!(result.files_modified, vec![present.clone()]);
+        assert_eq!(result.files_failed.len(), 1);
+        assert_eq!(result.files_failed[0].0, missing);
+        assert_eq!(result.hunks_applied, 1);
+        assert!(fs::read_to_string(&present).unwrap().contains("new"));
+        assert!(result.summary().ends_with("to 1 files, 1 failed"));
+    }
+
+    #[test]
+    fn test_renamed_file_moves_then_patches_and_rolls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.rs");
+        let new = dir.path().join("src/new.rs");
+        let missing = dir.path().join("z_missing.rs");
+        fs::write(&old, "old\n").unwrap();
+        let edit = |path: &PathBuf| {
+            create_test_hunk(
+                path.clone(),
+                0,
+                vec![
+                    (ChangeTag::Delete, "old\n".to_string()),
+                    (ChangeTag::Insert, "new\n".to_string()),
+                ],
+            )
+        };
+        let hunks = [edit(&new), edit(&missing)];
+        let pending: HashMap<PathBuf, FileChange> = [(
+            new.clone(),
+            FileChange {
+                path: new.clone(),
+                original_content: Some("old\n".to_string()),
+                proposed_content: "new\n".to_string(),
+                change_type: ChangeType::Modify,
+                renamed_from: Some(old.clone()),
+            },
+        )]
+        .into();
+        let policy = WritePolicy::load(dir.path()).unwrap();
+        let options = ApplyOptions::default();
+
+        // A later file failing moves the renamed file back (its content
+        // comes back from backups, which these options don't make)
+        let both: Vec<&Hunk> = hunks.iter().collect();
+        assert!(apply_accepted_hunks(&both, &pending, &options, &policy).is_err());
+        assert!(old.exists());
+        assert!(!new.exists());
+        fs::write(&old, "old\n").unwrap();
+
+        let result = apply_accepted_hunks(&[&hunks[0]], &pending, &options, &policy).unwrap();
+        assert_eq!(fs::read_to_string(&new).unwrap(), "new\n");
+        assert!(!old.exists());
+        assert_eq!(result.files_renamed, vec![(old.clone(), new.clone())]);
+        assert!(result.report().contains("old.rs → "));
+        assert!(result.summary().ends_with("to 1 files, renamed 1"));
+    }
+}
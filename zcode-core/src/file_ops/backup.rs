@@ -1,9 +1,10 @@
-// src/file_ops/backup.rs - Backup set management
+// zcode-core/src/file_ops/backup.rs - Backup set management
 
 use anyhow::{anyhow, Context, Result};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use super::atomic_write;
 
@@ -19,8 +20,18 @@ pub struct BackupSet {
 impl BackupSet {
     /// Create backups for the given files
     pub fn create(files: &[PathBuf]) -> Result<Self> {
+        static CREATED: AtomicU32 = AtomicU32::new(0);
+
         let mut backups = HashMap::new();
-        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S_%3f").to_string();
+        // The PID keeps backups from concurrent instances apart, the counter
+        // sets created in the same millisecond
+        let timestamp = format!(
+            "{}_{}_{}",
+            chrono::Utc::now().format("%Y%m%d_%H%M%S_%3f"),
+            std::process::id(),
+            CREATED.fetch_add(1, Ordering::Relaxed)
+        );
+        let _lock = crate::lock::lock_exclusive(&Self::backup_dir())?;
 
         for file_path in files {
             // Read original content
@@ -39,13 +50,17 @@ impl BackupSet {
         Ok(BackupSet { backups, timestamp })
     }
 
-    /// Generate backup path for a file
-    fn backup_path(original_path: &PathBuf, timestamp: &str) -> Result<PathBuf> {
-        let backup_dir = dirs::cache_dir()
+    /// Directory all backup sets are written to
+    pub fn backup_dir() -> PathBuf {
+        dirs::cache_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("zcode")
-            .join("backups");
+            .join("backups")
+    }
 
+    /// Generate backup path for a file
+    fn backup_path(original_path: &Path, timestamp: &str) -> Result<PathBuf> {
+        let backup_dir = Self::backup_dir();
         fs::create_dir_all(&backup_dir)?;
 
         let filename = original_path
@@ -80,7 +95,7 @@ impl BackupSet {
     }
 
     /// Restore a single file from its backup
-    fn restore_single(original_path: &PathBuf, backup_path: &PathBuf) -> Result<()> {
+    fn restore_single(original_path: &Path, backup_path: &Path) -> Result<()> {
         let backup_content = fs::read_to_string(backup_path)
             .context(format!("Failed to read backup: {}", backup_path.display()))?;
 
@@ -95,6 +110,17 @@ impl BackupSet {
         self.backups.values().cloned().collect()
     }
 
+    /// (file, backup) pairs, ordered by file
+    pub fn pairs(&self) -> Vec<(PathBuf, PathBuf)> {
+        let mut pairs: Vec<_> = self
+            .backups
+            .iter()
+            .map(|(file, backup)| (file.clone(), backup.clone()))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
     /// Get the backup path for a specific file
     pub fn get_backup(&self, original_path: &PathBuf) -> Option<&PathBuf> {
         self.backups.get(original_path)
@@ -127,7 +153,7 @@ mod tests {
         fs::write(&test_file, "original content").unwrap();
 
         // Create backup set
-        let backup_set = BackupSet::create(&[test_file.clone()]).unwrap();
+        let backup_set = BackupSet::create(std::slice::from_ref(&test_file)).unwrap();
 
         // Verify backup was created
         assert_eq!(backup_set.backups.len(), 1);
@@ -149,7 +175,7 @@ mod tests {
         fs::write(&test_file, "original content").unwrap();
 
         // Create backup
-        let backup_set = BackupSet::create(&[test_file.clone()]).unwrap();
+        let backup_set = BackupSet::create(std::slice::from_ref(&test_file)).unwrap();
 
         // Modify the original file
         fs::write(&test_file, "modified content").unwrap();
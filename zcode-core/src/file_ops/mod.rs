@@ -0,0 +1,334 @@
+//! Safe file operations with atomic writes and rollback support
+//!
+//! This module provides robust file modification capabilities:
+//!
+//! - **Atomic writes**: Files are written safely using temp files and atomic renames
+//! - **Backup management**: Automatic backups before any modifications
+//! - **Rollback support**: Restore from backups if any operation fails
+//! - **Transaction semantics**: All-or-nothing guarantees across multiple files
+//! - **Hunk application**: Intelligent application of code hunks to files
+//!
+//! # Submodules
+//!
+//! - [`apply`]: Orchestrates the complete file modification pipeline
+//! - [`backup`]: Manages backup creation and restoration
+//! - [`policy`]: Decides which paths may be written (`.zcodeignore`)
+//! - [`reconstruct`]: Applies hunks to file content
+//! - [`trash`]: Keeps deleted files (with a manifest) so `:undo` can restore them
+
+pub mod apply;
+pub mod backup;
+pub mod policy;
+pub mod reconstruct;
+pub mod trash;
+
+pub use apply::{apply_accepted_hunks, apply_one_hunk, ApplyOptions, ApplyResult};
+pub use backup::BackupSet;
+pub use policy::WritePolicy;
+pub use reconstruct::reconstruct_file_content;
+
+// Re-export common utilities
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::config::SymlinkMode;
+
+/// Symlinks followed before giving up on a chain of them
+const MAX_LINKS: usize = 40;
+
+/// Atomic file write using temp file + rename, following symlinks
+pub fn atomic_write(path: &Path, content: &str) -> Result<()> {
+    atomic_write_with(path, content, SymlinkMode::Follow)
+}
+
+/// Atomic file write using temp file + rename.
+///
+/// A file being replaced keeps its permissions, and its owner and (on Linux)
+/// extended attributes where the process may set them. A symlink is written
+/// through to its target, or replaced by a regular file, as `symlinks` says.
+pub fn atomic_write_with(path: &Path, content: &str, symlinks: SymlinkMode) -> Result<()> {
+    let is_link = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    let target = match symlinks {
+        SymlinkMode::Follow if is_link => link_target(path)?,
+        _ => path.to_path_buf(),
+    };
+    // The file whose content is replaced, through the link if there is one
+    let original = fs::metadata(path).ok().filter(|m| m.is_file());
+    let dir = target.parent().unwrap_or(Path::new("."));
+
+    // Ensure directory exists
+    fs::create_dir_all(dir)?;
+    ensure_writable(dir)?;
+
+    // Create temp file in same directory (required for atomic rename)
+    let mut builder = tempfile::Builder::new();
+    #[cfg(unix)]
+    {
+        // New files get the usual mode, less the umask, instead of 0600
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+    let mut temp = builder
+        .tempfile_in(dir)
+        .context("Failed to create temp file")?;
+
+    // Write content
+    temp.write_all(content.as_bytes())
+        .context("Failed to write to temp file")?;
+
+    if let Some(original) = &original {
+        preserve_metadata(temp.as_file(), path, original)
+            .context("Failed to keep the file's permissions")?;
+    }
+
+    // Fsync to ensure data is on disk
+    temp.as_file()
+        .sync_all()
+        .context("Failed to sync temp file")?;
+
+    // Atomic rename
+    temp.persist(&target)
+        .context("Failed to persist temp file")?;
+    crate::file_cache::invalidate(path);
+    crate::file_cache::invalidate(&target);
+
+    // Fsync directory for metadata durability
+    if let Ok(dir_file) = fs::File::open(dir) {
+        let _ = dir_file.sync_all();
+    }
+
+    Ok(())
+}
+
+/// The file a symlink finally points to, which need not exist yet
+fn link_target(path: &Path) -> Result<PathBuf> {
+    let mut target = path.to_path_buf();
+    for _ in 0..MAX_LINKS {
+        let link = fs::read_link(&target)
+            .with_context(|| format!("Failed to read symlink {}", target.display()))?;
+        target = target.parent().unwrap_or(Path::new(".")).join(link);
+        if !fs::symlink_metadata(&target).is_ok_and(|m| m.file_type().is_symlink()) {
+            return Ok(target);
+        }
+    }
+    anyhow::bail!("Too many levels of symlinks at {}", path.display())
+}
+
+/// Give `temp` the permissions of the file it replaces, and its owner and
+/// extended attributes where the process may set them
+fn preserve_metadata(temp: &fs::File, original_path: &Path, original: &fs::Metadata) -> Result<()> {
+    temp.set_permissions(original.permissions())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let temp_meta = temp.metadata()?;
+        if (temp_meta.uid(), temp_meta.gid()) != (original.uid(), original.gid()) {
+            // Only root may give a file away; a group we're in is still kept
+            let _ = std::os::unix::fs::fchown(temp, Some(original.uid()), Some(original.gid()));
+            let _ = std::os::unix::fs::fchown(temp, None, Some(original.gid()));
+        }
+    }
+    #[cfg(target_os = "linux")]
+    xattr::copy(original_path, temp);
+    Ok(())
+}
+
+/// Fail early when `dir` (or the nearest of its ancestors that exists) is on a
+/// read-only filesystem, before anything has been written
+pub fn ensure_writable(dir: &Path) -> Result<()> {
+    let Some(existing) = dir.ancestors().find(|d| d.exists()) else {
+        return Ok(());
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let Ok(c_path) = std::ffi::CString::new(existing.as_os_str().as_bytes()) else {
+            return Ok(());
+        };
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: `c_path` is a valid C string and `stat` a valid out pointer
+        let read_only = unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } == 0
+            && stat.f_flag & libc::ST_RDONLY != 0;
+        if read_only {
+            anyhow::bail!("{} is on a read-only filesystem", existing.display());
+        }
+    }
+    Ok(())
+}
+
+/// Copying extended attributes (SELinux labels, quarantine flags, user tags)
+/// onto a replacement file; attributes that can't be read or set are skipped
+#[cfg(target_os = "linux")]
+mod xattr {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    pub fn copy(from: &Path, to: &std::fs::File) {
+        let Ok(path) = CString::new(from.as_os_str().as_bytes()) else {
+            return;
+        };
+        let Some(names) = read(|buf, len| unsafe {
+            // SAFETY: `buf` holds `len` bytes, or is null with `len` 0
+            libc::listxattr(path.as_ptr(), buf.cast(), len)
+        }) else {
+            return;
+        };
+        for name in names.split(|b| *b == 0).filter(|n| !n.is_empty()) {
+            let Ok(name) = CString::new(name) else {
+                continue;
+            };
+            let value = read(|buf, len| unsafe {
+                // SAFETY: as above
+                libc::getxattr(path.as_ptr(), name.as_ptr(), buf.cast(), len)
+            });
+            if let Some(value) = value {
+                // SAFETY: `value` is a live buffer of the length given
+                unsafe {
+                    libc::fsetxattr(
+                        to.as_raw_fd(),
+                        name.as_ptr(),
+                        value.as_ptr().cast(),
+                        value.len(),
+                        0,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Call a size-then-fill libc function, growing the buffer if it raced
+    fn read(call: impl Fn(*mut u8, usize) -> isize) -> Option<Vec<u8>> {
+        for _ in 0..3 {
+            let len = usize::try_from(call(std::ptr::null_mut(), 0)).ok()?;
+            let mut buf = vec![0u8; len];
+            if let Ok(read) = usize::try_from(call(buf.as_mut_ptr(), buf.len())) {
+                buf.truncate(read);
+                return Some(buf);
+            }
+        }
+        None
+    }
+}
+
+/// Create a backup file
+pub fn create_backup(original_path: &Path, content: &str) -> Result<PathBuf> {
+    let backup_dir = dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("backups");
+
+    fs::create_dir_all(&backup_dir)?;
+    let _lock = crate::lock::lock_exclusive(&backup_dir)?;
+
+    let timestamp = format!(
+        "{}_{}",
+        chrono::Utc::now().format("%Y%m%d_%H%M%S"),
+        std::process::id()
+    );
+    let filename = original_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let backup_path = backup_dir.join(format!("{}_{}", timestamp, filename));
+
+    atomic_write(&backup_path, content)?;
+
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_atomic_write_creates_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_file = temp_dir.path().join("test.txt");
+
+        atomic_write(&test_file, "test content").unwrap();
+
+        assert!(test_file.exists());
+        let content = fs::read_to_string(&test_file).unwrap();
+        assert_eq!(content, "test content");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_keeps_an_executable_script_executable() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let script = temp_dir.path().join("build.sh");
+        fs::write(&script, "#!/bin/sh\necho old\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o750)).unwrap();
+
+        atomic_write(&script, "#!/bin/sh\necho new\n").unwrap();
+
+        let mode = fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+        assert_eq!(
+            fs::read_to_string(&script).unwrap(),
+            "#!/bin/sh\necho new\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_follows_or_replaces_symlinks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let target = temp_dir.path().join("shared").join("config.toml");
+        let link = temp_dir.path().join("config.toml");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink("shared/config.toml", &link).unwrap();
+
+        atomic_write_with(&link, "followed", SymlinkMode::Follow).unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "followed");
+
+        atomic_write_with(&link, "replaced", SymlinkMode::Replace).unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_file());
+        assert_eq!(fs::read_to_string(&link).unwrap(), "replaced");
+        assert_eq!(fs::read_to_string(&target).unwrap(), "followed");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_atomic_write_keeps_extended_attributes() {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("tagged.txt");
+        fs::write(&file, "old").unwrap();
+        let path = CString::new(file.as_os_str().as_bytes()).unwrap();
+        let name = CString::new("user.zcode.test").unwrap();
+        let set =
+            unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), b"kept".as_ptr().cast(), 4, 0) };
+        if set != 0 {
+            // The temp directory's filesystem has no user attributes
+            return;
+        }
+
+        atomic_write(&file, "new").unwrap();
+
+        let mut value = [0u8; 16];
+        let len = unsafe {
+            libc::getxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_mut_ptr().cast(),
+                value.len(),
+            )
+        };
+        assert_eq!(len, 4);
+        assert_eq!(&value[..4], b"kept");
+    }
+}
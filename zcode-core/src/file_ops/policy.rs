@@ -0,0 +1,161 @@
+// zcode-core/src/file_ops/policy.rs - Write policy from .zcodeignore
+
+use anyhow::{anyhow, Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the ignore file, read from the project root
+pub const IGNORE_FILE: &str = ".zcodeignore";
+
+/// Which files AI changes may be written to.
+///
+/// Paths matched by `.zcodeignore` (gitignore syntax) are never written unless
+/// the user overrides them for the current review.
+#[derive(Debug, Clone, Default)]
+pub struct WritePolicy {
+    root: PathBuf,
+    rules: Option<Gitignore>,
+    overrides: HashSet<PathBuf>,
+}
+
+impl WritePolicy {
+    /// Load `.zcodeignore` from `root`; a missing file allows everything
+    pub fn load(root: &Path) -> Result<Self> {
+        let ignore_file = root.join(IGNORE_FILE);
+        if !ignore_file.is_file() {
+            return Ok(Self {
+                root: root.to_path_buf(),
+                ..Self::default()
+            });
+        }
+
+        let mut builder = GitignoreBuilder::new(root);
+        if let Some(err) = builder.add(&ignore_file) {
+            return Err(err).with_context(|| format!("Failed to read {}", ignore_file.display()));
+        }
+        let rules = builder
+            .build()
+            .with_context(|| format!("Invalid patterns in {}", ignore_file.display()))?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            rules: Some(rules),
+            overrides: HashSet::new(),
+        })
+    }
+
+    /// Load `.zcodeignore` from the current directory
+    pub fn discover() -> Result<Self> {
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        Self::load(&cwd)
+    }
+
+    /// Whether `.zcodeignore` matches the path, regardless of overrides
+    pub fn matches(&self, path: &Path) -> bool {
+        let Some(rules) = &self.rules else {
+            return false;
+        };
+        let Some(relative) = self.relative(path) else {
+            return false;
+        };
+        rules
+            .matched_path_or_any_parents(&relative, false)
+            .is_ignore()
+    }
+
+    /// Whether writes to the path are currently blocked
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.matches(path) && !self.overrides.contains(path)
+    }
+
+    /// Allow (or stop allowing) writes to an ignored path for this review.
+    /// Returns whether the path is now overridden.
+    pub fn toggle_override(&mut self, path: &Path) -> bool {
+        if self.overrides.remove(path) {
+            false
+        } else {
+            self.overrides.insert(path.to_path_buf());
+            true
+        }
+    }
+
+    /// Resolve the path a change may be written to, refusing ignored paths
+    pub fn resolve(&self, path: &Path) -> Result<PathBuf> {
+        if self.is_ignored(path) {
+            return Err(anyhow!(
+                "{} is ignored by {} (override it in the review to apply)",
+                path.display(),
+                IGNORE_FILE
+            ));
+        }
+        Ok(path.to_path_buf())
+    }
+
+    /// Path relative to the root, or `None` if it lies outside it
+    fn relative(&self, path: &Path) -> Option<PathBuf> {
+        let relative = if path.is_absolute() {
+            path.strip_prefix(&self.root).ok()?.to_path_buf()
+        } else {
+            path.components()
+                .filter(|c| !matches!(c, Component::CurDir))
+                .collect()
+        };
+
+        if relative.as_os_str().is_empty()
+            || relative
+                .components()
+                .any(|c| matches!(c, Component::ParentDir))
+        {
+            return None;
+        }
+        Some(relative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn policy_with(patterns: &str) -> (tempfile::TempDir, WritePolicy) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(IGNORE_FILE), patterns).unwrap();
+        let policy = WritePolicy::load(dir.path()).unwrap();
+        (dir, policy)
+    }
+
+    #[test]
+    fn test_gitignore_syntax() {
+        let (dir, policy) = policy_with("*.lock\nmigrations/\n/secrets.toml\n!keep.lock\n");
+
+        assert!(policy.matches(Path::new("Cargo.lock")));
+        assert!(policy.matches(Path::new("./db/migrations/001.sql")));
+        assert!(policy.matches(&dir.path().join("secrets.toml")));
+        assert!(!policy.matches(Path::new("config/secrets.toml")));
+        assert!(!policy.matches(Path::new("keep.lock")));
+        assert!(!policy.matches(Path::new("src/main.rs")));
+        assert!(!policy.matches(Path::new("../outside.lock")));
+        assert!(!policy.matches(Path::new("/elsewhere/Cargo.lock")));
+    }
+
+    #[test]
+    fn test_override_allows_writes() {
+        let (_dir, mut policy) = policy_with("*.lock\n");
+        let path = Path::new("Cargo.lock");
+
+        assert!(policy.resolve(path).is_err());
+        assert!(policy.toggle_override(path));
+        assert!(policy.matches(path));
+        assert_eq!(policy.resolve(path).unwrap(), path);
+        assert!(!policy.toggle_override(path));
+        assert!(policy.is_ignored(path));
+    }
+
+    #[test]
+    fn test_missing_ignore_file_allows_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = WritePolicy::load(dir.path()).unwrap();
+        assert!(!policy.is_ignored(Path::new("anything.lock")));
+    }
+}
@@ -0,0 +1,240 @@
+// zcode-core/src/file_ops/reconstruct.rs - File content reconstruction from hunks
+
+use crate::types::{ChangeTag, Hunk, HunkStatus};
+use anyhow::{bail, Result};
+
+/// Reconstruct file content by applying accepted hunks to original content.
+///
+/// Each hunk replaces the lines of its old range with its new side, the
+/// bottom hunk first so the ranges above it still hold.
+pub fn reconstruct_file_content(original: &str, hunks: &[&Hunk]) -> Result<String> {
+    let mut lines: Vec<&str> = original.split_inclusive('\n').collect();
+
+    let mut accepted_hunks: Vec<&Hunk> = hunks
+        .iter()
+        .copied()
+        .filter(|h| h.status == HunkStatus::Accepted)
+        .collect();
+    accepted_hunks.sort_by_key(|h| std::cmp::Reverse(h.old.start));
+
+    for hunk in accepted_hunks {
+        splice_lines(&mut lines, hunk)?;
+    }
+
+    Ok(lines.concat())
+}
+
+/// Replace the old range of `hunk` in `lines` (each with its line ending)
+/// with the hunk's new lines. Fails if the old lines aren't there.
+pub(crate) fn splice_lines<'a>(lines: &mut Vec<&'a str>, hunk: &'a Hunk) -> Result<()> {
+    let range = hunk.old.indices();
+    let old: Vec<&str> = hunk
+        .changes
+        .iter()
+        .filter(|c| c.tag != ChangeTag::Insert)
+        .map(|c| c.content.as_str())
+        .collect();
+    if lines.get(range.clone()) != Some(&old[..]) {
+        bail!(
+            "Hunk at line {} of {} doesn't match the file",
+            hunk.old.start,
+            hunk.file_path.display()
+        );
+    }
+
+    let new = hunk
+        .changes
+        .iter()
+        .filter(|c| c.tag != ChangeTag::Delete)
+        .map(|c| c.content.as_str());
+    lines.splice(range, new);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::compute_hunks;
+    use crate::types::{ChangeTag, HunkRange, LineChange};
+
+    /// An accepted hunk replacing the old lines at 0-based `first` with `changes`
+    fn create_test_hunk(first: usize, changes: Vec<(ChangeTag, &str)>) -> Hunk {
+        let old_len = changes
+            .iter()
+            .filter(|(t, _)| *t != ChangeTag::Insert)
+            .count();
+        let new_len = changes
+            .iter()
+            .filter(|(t, _)| *t != ChangeTag::Delete)
+            .count();
+        let (mut old_idx, mut new_idx) = (first, first);
+        Hunk {
+            id: 0,
+            file_path: std::path::PathBuf::from("test.txt"),
+            old: HunkRange::from_indices(first..first + old_len),
+            new: HunkRange::from_indices(first..first + new_len),
+            changes: changes
+                .into_iter()
+                .map(|(tag, content)| {
+                    let old_line = (tag != ChangeTag::Insert).then(|| {
+                        old_idx += 1;
+                        old_idx - 1
+                    });
+                    let new_line = (tag != ChangeTag::Delete).then(|| {
+                        new_idx += 1;
+                        new_idx - 1
+                    });
+                    LineChange {
+                        tag,
+                        content: content.into(),
+                        old_line_num: old_line,
+                        new_line_num: new_line,
+                    }
+                })
+                .collect(),
+            status: HunkStatus::Accepted,
+            drifted: false,
+            comment: None,
+            auto_accepted: None,
+        }
+    }
+
+    #[test]
+    fn test_no_hunks() {
+        let original = "line 1\nline 2\nline 3";
+        let result = reconstruct_file_content(original, &[]).unwrap();
+        assert_eq!(result, original);
+    }
+
+    #[test]
+    fn test_single_insertion() {
+        let original = "line 1\nline 3";
+        let hunk = create_test_hunk(1, vec![(ChangeTag::Insert, "line 2\n")]);
+
+        let result = reconstruct_file_content(original, &[&hunk]).unwrap();
+        assert_eq!(result, "line 1\nline 2\nline 3");
+    }
+
+    #[test]
+    fn test_single_deletion() {
+        let original = "line 1\nline 2\nline 3";
+        let hunk = create_test_hunk(1, vec![(ChangeTag::Delete, "line 2\n")]);
+
+        let result = reconstruct_file_content(original, &[&hunk]).unwrap();
+        assert_eq!(result, "line 1\nline 3");
+    }
+
+    #[test]
+    fn test_multiple_changes_in_hunk() {
+        let original = "line 1\nline 3";
+        let hunk = create_test_hunk(
+            1,
+            vec![
+                (ChangeTag::Insert, "line 2a\n"),
+                (ChangeTag::Insert, "line 2b\n"),
+            ],
+        );
+
+        let result = reconstruct_file_content(original, &[&hunk]).unwrap();
+        assert_eq!(result, "line 1\nline 2a\nline 2b\nline 3");
+    }
+
+    #[test]
+    fn test_multiple_hunks() {
+        // One hunk above another: applying the lower one first keeps the upper range valid
+        let original = "line 1\nline 2\nline 4\nline 5";
+        let upper = create_test_hunk(
+            0,
+            vec![
+                (ChangeTag::Delete, "line 1\n"),
+                (ChangeTag::Insert, "line 0\n"),
+            ],
+        );
+        let lower = create_test_hunk(2, vec![(ChangeTag::Insert, "line 3\n")]);
+
+        let result = reconstruct_file_content(original, &[&upper, &lower]).unwrap();
+        assert_eq!(result, "line 0\nline 2\nline 3\nline 4\nline 5");
+    }
+
+    #[test]
+    fn test_empty_file_insertion() {
+        let original = "";
+        let hunk = create_test_hunk(0, vec![(ChangeTag::Insert, "line 1")]);
+
+        let result = reconstruct_file_content(original, &[&hunk]).unwrap();
+        assert_eq!(result, "line 1");
+    }
+
+    #[test]
+    fn test_mismatched_hunk_is_refused() {
+        let hunk = create_test_hunk(1, vec![(ChangeTag::Delete, "line 2\n")]);
+        assert!(reconstruct_file_content("line 1\nother\n", &[&hunk]).is_err());
+    }
+
+    /// Small deterministic generator so the round trips are reproducible
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    #[test]
+    fn test_random_edits_round_trip() {
+        let mut rng = XorShift(0x5eed_1154);
+        for _ in 0..300 {
+            let count = rng.below(30);
+            let original: Vec<String> = (0..count)
+                .map(|i| format!("line {}\n", rng.below(8) * 100 + i))
+                .collect();
+            let mut proposed = original.clone();
+            for _ in 0..rng.below(6) {
+                let at = rng.below(proposed.len() + 1);
+                match rng.below(3) {
+                    0 => proposed.insert(at, format!("new {}\n", rng.below(1000))),
+                    1 if at < proposed.len() => {
+                        proposed.remove(at);
+                    }
+                    _ if at < proposed.len() => proposed[at] = format!("edit {}\n", at),
+                    _ => {}
+                }
+            }
+            let (original, proposed) = (original.concat(), proposed.concat());
+
+            let mut hunks = compute_hunks(std::path::Path::new("f"), &original, &proposed);
+            for hunk in &hunks {
+                let old: String =
+                    original.split_inclusive('\n').collect::<Vec<_>>()[hunk.old.indices()].concat();
+                let new: String =
+                    proposed.split_inclusive('\n').collect::<Vec<_>>()[hunk.new.indices()].concat();
+                let side = |skip: ChangeTag| -> String {
+                    hunk.changes
+                        .iter()
+                        .filter(|c| c.tag != skip)
+                        .map(|c| c.content.as_str())
+                        .collect()
+                };
+                assert_eq!(old, side(ChangeTag::Insert));
+                assert_eq!(new, side(ChangeTag::Delete));
+            }
+
+            let refs: Vec<&Hunk> = hunks.iter().collect();
+            assert_eq!(
+                reconstruct_file_content(&original, &refs).unwrap(),
+                original
+            );
+            for hunk in &mut hunks {
+                hunk.status = HunkStatus::Accepted;
+            }
+            let refs: Vec<&Hunk> = hunks.iter().collect();
+            assert_eq!(
+                reconstruct_file_content(&original, &refs).unwrap(),
+                proposed
+            );
+        }
+    }
+}
@@ -0,0 +1,193 @@
+// zcode-core/src/file_ops/trash.rs - Deleted files are moved aside instead of removed
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Manifest written into each batch directory, listing where files came from
+const MANIFEST: &str = "manifest.json";
+/// A batch's manifest is renamed to this once `:undo` has restored it
+const RESTORED_MANIFEST: &str = "manifest.restored.json";
+
+/// One deleted file and where it was moved to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub original: PathBuf,
+    pub trashed: PathBuf,
+}
+
+/// The files deleted by one apply, kept in their own directory under the trash
+#[derive(Debug, Clone)]
+pub struct Trash {
+    dir: PathBuf,
+    entries: Vec<TrashEntry>,
+}
+
+/// Directory all trash batches are written to
+pub fn trash_root() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("trash")
+}
+
+impl Trash {
+    /// A new, still empty batch under `root`
+    pub fn new(root: &Path) -> Self {
+        // Timestamped so batches sort by age; the PID keeps instances apart
+        let name = format!(
+            "{}_{}",
+            chrono::Utc::now().format("%Y%m%d_%H%M%S_%3f"),
+            std::process::id()
+        );
+        Self {
+            dir: root.join(name),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Directory this batch's files are moved into
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn entries(&self) -> &[TrashEntry] {
+        &self.entries
+    }
+
+    /// Move `path` into the batch and record it in the manifest
+    pub fn move_in(&mut self, path: &Path) -> Result<PathBuf> {
+        let original = std::path::absolute(path)
+            .with_context(|| format!("Failed to resolve {}", path.display()))?;
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+
+        let file_name = original
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let trashed = self
+            .dir
+            .join(format!("{}_{}", self.entries.len(), file_name));
+        move_file(&original, &trashed)?;
+
+        self.entries.push(TrashEntry {
+            original: original.clone(),
+            trashed: trashed.clone(),
+        });
+        // A file the manifest doesn't know about could never be restored
+        if let Err(e) = self.write_manifest() {
+            self.entries.pop();
+            move_file(&trashed, &original)?;
+            return Err(e);
+        }
+        Ok(trashed)
+    }
+
+    /// Put every file of the batch back (rollback of a failed apply)
+    pub fn restore_all(&self) -> Result<()> {
+        let errors: Vec<String> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                restore_entry(entry)
+                    .err()
+                    .map(|e| format!("{}: {:#}", entry.original.display(), e))
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(anyhow!(
+                "Failed to restore deleted files: {}",
+                errors.join("; ")
+            ));
+        }
+        let _ = fs::rename(self.dir.join(MANIFEST), self.dir.join(RESTORED_MANIFEST));
+        Ok(())
+    }
+
+    fn write_manifest(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.entries)?;
+        super::atomic_write(&self.dir.join(MANIFEST), &json)
+            .context("Failed to write trash manifest")
+    }
+}
+
+/// Restore the most recent batch under `root` that deleted files inside
+/// `project`. Returns the restored paths.
+pub fn restore_latest(root: &Path, project: &Path) -> Result<Vec<PathBuf>> {
+    let project = std::path::absolute(project)?;
+    let mut batches: Vec<PathBuf> = match fs::read_dir(root) {
+        Ok(dirs) => dirs
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|dir| dir.join(MANIFEST).is_file())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    batches.sort();
+
+    for dir in batches.into_iter().rev() {
+        let json = fs::read_to_string(dir.join(MANIFEST))
+            .with_context(|| format!("Failed to read manifest in {}", dir.display()))?;
+        let entries: Vec<TrashEntry> = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid manifest in {}", dir.display()))?;
+        if !entries.iter().any(|e| e.original.starts_with(&project)) {
+            continue;
+        }
+
+        let batch = Trash { dir, entries };
+        batch.restore_all()?;
+        return Ok(batch.entries.into_iter().map(|e| e.original).collect());
+    }
+    Err(anyhow!("No deleted files to restore"))
+}
+
+fn restore_entry(entry: &TrashEntry) -> Result<()> {
+    if entry.original.exists() {
+        return Err(anyhow!("a file already exists there"));
+    }
+    if let Some(parent) = entry.original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    move_file(&entry.trashed, &entry.original)
+}
+
+/// Rename, falling back to copy + remove when the trash is on another filesystem
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)
+        .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+    fs::remove_file(from).with_context(|| format!("Failed to remove {}", from.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_and_restore_latest() {
+        let project = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let file = project.path().join("src").join("old.rs");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "fn old() {}\n").unwrap();
+
+        let mut trash = Trash::new(root.path());
+        let trashed = trash.move_in(&file).unwrap();
+        assert!(!file.exists());
+        assert!(trashed.starts_with(trash.dir()));
+        assert!(trash.dir().join(MANIFEST).is_file());
+
+        let elsewhere = tempfile::tempdir().unwrap();
+        assert!(restore_latest(root.path(), elsewhere.path()).is_err());
+
+        let restored = restore_latest(root.path(), project.path()).unwrap();
+        assert_eq!(restored, vec![std::path::absolute(&file).unwrap()]);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn old() {}\n");
+        // A batch is only restored once
+        assert!(restore_latest(root.path(), project.path()).is_err());
+    }
+}
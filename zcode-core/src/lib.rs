@@ -0,0 +1,76 @@
+//! The diff/parse/apply core of ZCode
+//!
+//! Everything between a prompt and the files on disk, with no terminal UI:
+//!
+//! 1. A provider ([`providers::create_provider`]) builds the command for a
+//!    [`PromptRequest`], which [`executor`] runs.
+//! 2. Its output is parsed into [`FileChange`]s ([`parsers`]).
+//! 3. Each change is diffed into [`Hunk`]s ([`diff::compute_hunks`]) to be
+//!    accepted or rejected.
+//! 4. The accepted hunks are written ([`file_ops::apply_accepted_hunks`]):
+//!    atomically, with backups and rollback, never to a path the
+//!    [`file_ops::WritePolicy`] (`.zcodeignore`) refuses.
+//!
+//! Output that isn't valid UTF-8 is parsed with the bad bytes marked
+//! ([`encoding`]), so a file body built from them is refused rather than
+//! written with replacement characters.
+//!
+//! Agentic providers that edit files themselves can be run in a
+//! [`sandbox::Sandbox`], whose edits come back as [`FileChange`]s for step 3.
+//!
+//! The TUI is one consumer of this crate; editor plugins and CI bots can drive
+//! the same pipeline:
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use zcode_core::file_ops::{apply_accepted_hunks, ApplyOptions, WritePolicy};
+//! use zcode_core::{diff, parsers, HunkStatus};
+//!
+//! let dir = tempfile::tempdir()?;
+//! let path = dir.path().join("greet.py");
+//! std::fs::write(&path, "print('hi')\n")?;
+//!
+//! let reply = format!(
+//!     "--- {0}\n+++ {0}\n@@ -1 +1 @@\n-print('hi')\n+print('hello')\n",
+//!     path.display()
+//! );
+//! let changes = parsers::parse_unified_diff(&reply)?;
+//! let change = &changes[0];
+//! let original = std::fs::read_to_string(&change.path)?;
+//! let mut hunks = diff::compute_hunks(&change.path, &original, &change.proposed_content);
+//! for hunk in &mut hunks {
+//!     hunk.status = HunkStatus::Accepted;
+//! }
+//!
+//! let hunks: Vec<_> = hunks.iter().collect();
+//! let result = apply_accepted_hunks(
+//!     &hunks,
+//!     &HashMap::new(),
+//!     &ApplyOptions::default(),
+//!     &WritePolicy::load(dir.path())?,
+//! )?;
+//! assert_eq!(result.files_modified, [path.clone()]);
+//! assert_eq!(std::fs::read_to_string(&path)?, "print('hello')\n");
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod activity;
+pub mod config;
+pub mod diff;
+pub mod encoding;
+pub mod executor;
+pub mod file_cache;
+pub mod file_ops;
+pub mod lock;
+pub mod parsers;
+pub mod preamble;
+pub mod providers;
+pub mod sandbox;
+pub mod sections;
+pub mod types;
+
+pub use providers::AIProvider;
+pub use types::{
+    ChangeTag, ChangeType, FileChange, Hunk, HunkRange, HunkStatus, LineChange, LineText,
+    PromptMode, PromptRequest, ProviderResponse,
+};
@@ -0,0 +1,68 @@
+//! Advisory locks on shared files
+//!
+//! Writes to a file other processes may write too (the backup directory, a
+//! frontend's session store) happen under a lock on a sibling `.lock` file.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// Advisory lock guarding a shared file; released on drop
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+fn open_lock_file(path: &Path) -> Result<File> {
+    let lock_path = lock_path(path);
+    if let Some(dir) = lock_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open lock file {}", lock_path.display()))
+}
+
+/// Block until no other instance is writing `path`, then hold it exclusively
+pub fn lock_exclusive(path: &Path) -> Result<FileLock> {
+    let file = open_lock_file(path)?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {}", path.display()))?;
+    Ok(FileLock { _file: file })
+}
+
+/// Block until no other instance is writing `path`, allowing concurrent readers
+pub fn lock_shared(path: &Path) -> Result<FileLock> {
+    let file = open_lock_file(path)?;
+    file.lock_shared()
+        .with_context(|| format!("Failed to lock {}", path.display()))?;
+    Ok(FileLock { _file: file })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::TryLockError;
+
+    #[test]
+    fn test_exclusive_lock_blocks_other_writers() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("sessions.json");
+
+        let guard = lock_exclusive(&target).unwrap();
+        let other = File::open(lock_path(&target)).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+
+        drop(guard);
+        assert!(other.try_lock().is_ok());
+    }
+}
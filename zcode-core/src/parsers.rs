@@ -0,0 +1,1552 @@
+//! Parsing AI provider outputs into code changes
+//!
+//! This module handles parsing the diverse output formats from different AI tools
+//! and converting them into a common `FileChange` representation.
+//!
+//! # Supported Formats
+//!
+//! - **Unified diff**: Standard diff format (used by git, Aider, etc.)
+//! - **Code blocks**: Markdown-style code blocks with file path annotations
+//! - **Claude JSON**: Claude's JSON response format
+//! - **Ollama chat**: Ollama `/api/chat` responses wrapping code blocks
+//! - **Copilot suggestions**: `gh copilot suggest` blocks and their menus
+//! - **JSON changes**: Custom JSON array format for file changes
+//! - **Regex-based**: Custom regex patterns for custom outputs
+//!
+//! # Fixture Corpus
+//!
+//! Real provider outputs live under `tests/fixtures/<provider>/*.txt`, each with a
+//! sibling `.json` file holding the expected `FileChange` list. Outputs that fail to
+//! parse can be captured with `:report-parse-failure`, which writes a redacted copy
+//! into the corpus directory for later triage.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::file_cache;
+use crate::types::{ChangeType, FileChange, HunkRange};
+
+/// Parse standard unified diff format (used by Aider, git, etc.)
+///
+/// Hunks are placed by their `@@ -a,b +c,d @@` headers and read for exactly
+/// the lines the header counts, so text after a hunk isn't taken for context.
+/// When the file is on disk and every hunk matches it, the change holds the
+/// whole file before and after; otherwise it holds the hunks' lines only.
+/// `new file mode`/`--- /dev/null` make a creation, `deleted file mode`/
+/// `+++ /dev/null` a deletion, and `\ No newline at end of file` drops the
+/// line ending of the line before it. Hunks without headers are read up to
+/// the next file.
+pub fn parse_unified_diff(input: &str) -> Result<Vec<FileChange>> {
+    let header = cached_regex(&HUNK_HEADER, r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@");
+    let mut files: Vec<DiffFile> = Vec::new();
+    // Lines still expected on each side of the current hunk
+    let mut remaining: Option<(usize, usize)> = None;
+
+    for line in input.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+
+        if let Some((old_left, new_left)) = remaining.as_mut() {
+            let hunk = files
+                .last_mut()
+                .and_then(|f| f.hunks.last_mut())
+                .context("Hunk outside a file")?;
+            if text.starts_with('\\') {
+                hunk.strip_last_newline();
+                continue;
+            }
+            // Some tools strip the space off empty context lines
+            let (side, content) = match line.split_at_checked(1) {
+                _ if text.is_empty() => (DiffSide::Both, line),
+                Some((" ", content)) => (DiffSide::Both, content),
+                Some(("-", content)) => (DiffSide::Old, content),
+                Some(("+", content)) => (DiffSide::New, content),
+                _ => (DiffSide::Neither, line),
+            };
+            let fits = match side {
+                DiffSide::Both => *old_left > 0 && *new_left > 0,
+                DiffSide::Old => *old_left > 0,
+                DiffSide::New => *new_left > 0,
+                DiffSide::Neither => false,
+            };
+            if fits {
+                hunk.push(side, content);
+                if side != DiffSide::New {
+                    *old_left -= 1;
+                }
+                if side != DiffSide::Old {
+                    *new_left -= 1;
+                }
+                if (*old_left, *new_left) == (0, 0) {
+                    remaining = None;
+                }
+                continue;
+            }
+            remaining = None;
+        }
+
+        if text.starts_with('\\') {
+            // "\ No newline at end of file" for the line before
+            if let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) {
+                hunk.strip_last_newline();
+            }
+        } else if let Some(paths) = text.strip_prefix("diff --git ") {
+            let (old, new) = paths.split_once(" b/").unwrap_or((paths, paths));
+            let (old, new) = (diff_path(old), diff_path(new));
+            files.push(DiffFile {
+                renamed: old != new,
+                old_path: Some(old),
+                new_path: Some(new),
+                ..DiffFile::default()
+            });
+        } else if text.starts_with("--- ") && !files.last().is_some_and(DiffFile::awaits_paths) {
+            // A diff without `diff --git` lines starts each file here
+            let path = diff_path_field(&text[4..]);
+            files.push(DiffFile {
+                old_path: path.clone(),
+                new_path: path,
+                seen_old: true,
+                ..DiffFile::default()
+            });
+        } else if let Some(file) = files.last_mut().filter(|f| f.hunks.is_empty()) {
+            if let Some(rest) = text.strip_prefix("--- ") {
+                file.old_path = diff_path_field(rest);
+                file.seen_old = true;
+            } else if let Some(rest) = text.strip_prefix("+++ ") {
+                file.new_path = diff_path_field(rest);
+                file.seen_new = true;
+            } else if text.starts_with("new file mode") {
+                file.old_path = None;
+            } else if text.starts_with("deleted file mode") {
+                file.new_path = None;
+            } else if let Some(path) = text.strip_prefix("rename from ") {
+                file.old_path = Some(PathBuf::from(path));
+                file.renamed = true;
+            } else if let Some(path) = text.strip_prefix("rename to ") {
+                file.new_path = Some(PathBuf::from(path));
+                file.renamed = true;
+            } else if let Some(cap) = header.captures(text) {
+                file.start_hunk(&cap, &mut remaining);
+            } else if file.seen_new {
+                file.headerless_line(line);
+            }
+        } else if let Some(file) = files.last_mut() {
+            if let Some(cap) = header.captures(text) {
+                file.start_hunk(&cap, &mut remaining);
+            } else if file.hunks.last().is_some_and(|h| h.header.is_none()) {
+                file.headerless_line(line);
+            }
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .filter_map(DiffFile::into_change)
+        .collect())
+}
+
+static HUNK_HEADER: OnceLock<Regex> = OnceLock::new();
+static ANNOTATED_BLOCK: OnceLock<Regex> = OnceLock::new();
+
+/// `pattern` compiled on first use. Compiling takes longer than parsing a
+/// reply of a few KB, so the parsers' fixed patterns are only built once.
+fn cached_regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid regex"))
+}
+
+/// Which side of a diff a hunk line belongs to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DiffSide {
+    Old,
+    New,
+    Both,
+    Neither,
+}
+
+/// One hunk of a unified diff, with its lines (and their endings) on each
+/// side, borrowed from the reply rather than copied line by line
+#[derive(Debug, Default)]
+struct DiffHunk<'a> {
+    /// The `@@` header's ranges, if the hunk had one
+    header: Option<(HunkRange, HunkRange)>,
+    old_lines: Vec<&'a str>,
+    new_lines: Vec<&'a str>,
+    last: Option<DiffSide>,
+}
+
+impl<'a> DiffHunk<'a> {
+    fn push(&mut self, side: DiffSide, content: &'a str) {
+        if side != DiffSide::New {
+            self.old_lines.push(content);
+        }
+        if side != DiffSide::Old {
+            self.new_lines.push(content);
+        }
+        self.last = Some(side);
+    }
+
+    fn strip_last_newline(&mut self) {
+        let strip = |lines: &mut Vec<&'a str>| {
+            if let Some(line) = lines.last_mut() {
+                *line = line.trim_end_matches(['\n', '\r']);
+            }
+        };
+        match self.last {
+            Some(DiffSide::Old) => strip(&mut self.old_lines),
+            Some(DiffSide::New) => strip(&mut self.new_lines),
+            Some(DiffSide::Both) => {
+                strip(&mut self.old_lines);
+                strip(&mut self.new_lines);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One file of a unified diff; a `None` path is `/dev/null`
+#[derive(Debug, Default)]
+struct DiffFile<'a> {
+    old_path: Option<PathBuf>,
+    new_path: Option<PathBuf>,
+    seen_old: bool,
+    seen_new: bool,
+    /// Whether `rename` lines or a `diff --git` line named two paths; other
+    /// diffs with differing `---`/`+++` paths (`x.orig`, `old/x`) edit one file
+    renamed: bool,
+    hunks: Vec<DiffHunk<'a>>,
+}
+
+impl<'a> DiffFile<'a> {
+    /// Whether this file's `---` line is still to come
+    fn awaits_paths(&self) -> bool {
+        !self.seen_old && self.hunks.is_empty()
+    }
+
+    fn start_hunk(&mut self, cap: &regex::Captures, remaining: &mut Option<(usize, usize)>) {
+        let number = |i: usize| cap.get(i).map_or(1, |m| m.as_str().parse().unwrap_or(0));
+        let old = HunkRange {
+            start: number(1),
+            len: number(2),
+        };
+        let new = HunkRange {
+            start: number(3),
+            len: number(4),
+        };
+        *remaining = (old.len + new.len > 0).then_some((old.len, new.len));
+        self.hunks.push(DiffHunk {
+            header: Some((old, new)),
+            ..DiffHunk::default()
+        });
+    }
+
+    /// A line of a hunk without a header: prefixed by its side, or context
+    fn headerless_line(&mut self, line: &'a str) {
+        if self.hunks.last().is_none_or(|h| h.header.is_some()) {
+            self.hunks.push(DiffHunk::default());
+        }
+        let hunk = self.hunks.last_mut().expect("hunk just pushed");
+        match line.split_at_checked(1) {
+            Some(("-", content)) => hunk.push(DiffSide::Old, content),
+            Some(("+", content)) => hunk.push(DiffSide::New, content),
+            Some((" ", content)) => hunk.push(DiffSide::Both, content),
+            _ => hunk.push(DiffSide::Both, line),
+        }
+    }
+
+    fn into_change(self) -> Option<FileChange> {
+        let path = self.new_path.clone().or(self.old_path.clone())?;
+        let change_type = match (&self.old_path, &self.new_path) {
+            (None, _) => ChangeType::Create,
+            (_, None) => ChangeType::Delete,
+            _ => ChangeType::Modify,
+        };
+        let renamed_from = self.old_path.clone().filter(|old| {
+            self.renamed && change_type == ChangeType::Modify && self.new_path.as_ref() != Some(old)
+        });
+        let side = |lines: for<'h> fn(&'h DiffHunk<'a>) -> &'h Vec<&'a str>| -> String {
+            self.hunks.iter().flat_map(lines).copied().collect()
+        };
+        let proposed = |proposed: String| match change_type {
+            ChangeType::Delete => String::new(),
+            _ => proposed,
+        };
+
+        let on_disk = self
+            .old_path
+            .as_ref()
+            .and_then(|p| file_cache::read_string(p));
+        let (original_content, proposed_content) = match change_type {
+            ChangeType::Create => (None, side(|h| &h.new_lines)),
+            _ => match on_disk.and_then(|disk| Some((patch_lines(&disk, &self.hunks)?, disk))) {
+                Some((patched, disk)) => (Some(disk), proposed(patched)),
+                None => (
+                    Some(side(|h| &h.old_lines)),
+                    proposed(side(|h| &h.new_lines)),
+                ),
+            },
+        };
+        Some(FileChange {
+            path,
+            original_content,
+            proposed_content,
+            change_type,
+            renamed_from,
+        })
+    }
+}
+
+/// `content` with each hunk's old lines replaced by its new ones, or `None`
+/// if a hunk's old lines can't be found. A hunk is looked for where its header
+/// puts it, then anywhere after the previous hunk.
+fn patch_lines(content: &str, hunks: &[DiffHunk<'_>]) -> Option<String> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut patched = String::new();
+    let mut next = 0;
+    for hunk in hunks {
+        let matches_at = |at: usize| {
+            lines
+                .get(at..at + hunk.old_lines.len())
+                .is_some_and(|window| window == hunk.old_lines.as_slice())
+        };
+        let at = match hunk.header {
+            Some((old, _)) if old.indices().start >= next && matches_at(old.indices().start) => {
+                old.indices().start
+            }
+            _ if hunk.old_lines.is_empty() => return None,
+            _ => (next..lines.len()).find(|at| matches_at(*at))?,
+        };
+        patched.extend(lines[next..at].iter().copied());
+        patched.extend(hunk.new_lines.iter().copied());
+        next = at + hunk.old_lines.len();
+    }
+    patched.extend(lines[next..].iter().copied());
+    Some(patched)
+}
+
+/// Path of a `diff --git` side, without its `a/`/`b/` prefix
+fn diff_path(path: &str) -> PathBuf {
+    let path = path.trim();
+    PathBuf::from(
+        path.strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path),
+    )
+}
+
+/// Path of a `---`/`+++` line, `None` for `/dev/null`. A tab separates any
+/// timestamp from the path.
+fn diff_path_field(field: &str) -> Option<PathBuf> {
+    let path = field.split('\t').next().unwrap_or(field).trim();
+    (path != "/dev/null").then(|| diff_path(path))
+}
+
+/// Parse markdown-style code blocks with file paths
+pub fn parse_code_blocks(input: &str) -> Result<Vec<FileChange>> {
+    let re = cached_regex(
+        &ANNOTATED_BLOCK,
+        r"(?s)```(?:\w+)?\s*\n?(?://|#|<!--)\s*(?:file:|path:)?\s*([^\n]+)\n(.+?)```",
+    );
+
+    let mut changes = Vec::new();
+
+    for cap in re.captures_iter(input) {
+        let path = PathBuf::from(cap[1].trim());
+        let content = cap[2].to_string();
+
+        // Try to read original file to determine change type
+        let (original, change_type) = if path.exists() {
+            (file_cache::read_string(&path), ChangeType::Modify)
+        } else {
+            (None, ChangeType::Create)
+        };
+
+        changes.push(FileChange {
+            path,
+            original_content: original,
+            proposed_content: content,
+            change_type,
+            renamed_from: None,
+        });
+    }
+
+    Ok(changes)
+}
+
+/// A fenced code block of a reply
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    /// The fence's language, empty when it names none
+    pub language: String,
+    /// The file a first-line comment names (`// src/main.rs`), as in
+    /// [`parse_code_blocks`]
+    pub path: Option<PathBuf>,
+    /// The block's lines, without that comment
+    pub content: String,
+}
+
+/// Every fenced code block of `input`, in order
+pub fn code_blocks(input: &str) -> Vec<CodeBlock> {
+    static FENCE: OnceLock<Regex> = OnceLock::new();
+    static ANNOTATION: OnceLock<Regex> = OnceLock::new();
+    let fence = cached_regex(&FENCE, r"(?s)```([\w+#.-]*)[ \t]*\n(.*?)```");
+    let annotation = cached_regex(
+        &ANNOTATION,
+        r"^(?://|#|<!--)\s*(?:file:|path:)?\s*([^\s]+?)\s*(?:-->)?$",
+    );
+    fence
+        .captures_iter(input)
+        .map(|cap| {
+            let body = &cap[2];
+            let (first, rest) = body.split_once('\n').unwrap_or((body, ""));
+            // Only a comment that looks like a path names the file
+            let path = annotation
+                .captures(first.trim())
+                .map(|c| PathBuf::from(&c[1]))
+                .filter(|p| p.extension().is_some() || p.components().count() > 1);
+            CodeBlock {
+                language: cap[1].to_string(),
+                content: if path.is_some() { rest } else { body }.to_string(),
+                path,
+            }
+        })
+        .collect()
+}
+
+/// Shell commands suggested in fenced `sh`/`bash` blocks.
+///
+/// Blocks whose first line is a comment are file annotations for
+/// [`parse_code_blocks`] and are left out. `$ ` prompts are dropped; in
+/// `console` blocks only the prompted lines are commands.
+pub fn shell_suggestions(input: &str) -> Vec<String> {
+    static SHELL_BLOCK: OnceLock<Regex> = OnceLock::new();
+    let re = cached_regex(
+        &SHELL_BLOCK,
+        r"(?s)```(sh|bash|zsh|shell|console)[ \t]*\n(.*?)```",
+    );
+    let mut commands = Vec::new();
+    for cap in re.captures_iter(input) {
+        let body = cap[2].trim();
+        if body.is_empty() || ["#", "//", "<!--"].iter().any(|c| body.starts_with(c)) {
+            continue;
+        }
+        let console = &cap[1] == "console";
+        let lines: Vec<&str> = body
+            .lines()
+            .filter_map(|line| match line.strip_prefix("$ ") {
+                Some(command) => Some(command),
+                None if console => None,
+                None => Some(line),
+            })
+            .collect();
+        let command = lines.join("\n").trim().to_string();
+        if !command.is_empty() && !commands.contains(&command) {
+            commands.push(command);
+        }
+    }
+    commands
+}
+
+/// The final result object of a Claude Code run.
+///
+/// Accepts both `--output-format json` (one object) and `stream-json` (one event
+/// per line, ending with a `"type": "result"` event).
+pub fn claude_result_json(input: &str) -> Option<serde_json::Value> {
+    if let Ok(json) = serde_json::from_str::<serde_json::Value>(input) {
+        return Some(json);
+    }
+    input
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|json| json.get("type").and_then(|t| t.as_str()) == Some("result"))
+}
+
+/// Parse Claude Code's JSON output format
+pub fn parse_claude_json(input: &str) -> Result<Vec<FileChange>> {
+    let response = claude_result_json(input).context("Failed to parse Claude JSON response")?;
+
+    let mut changes = Vec::new();
+
+    // Claude embeds tool uses in the result
+    if let Some(result) = response.get("result").and_then(|r| r.as_str()) {
+        // Parse tool use patterns from result text
+        static EDIT: OnceLock<Regex> = OnceLock::new();
+        let edit_pattern = cached_regex(&EDIT, r"(?s)Editing\s+`?([^`\n]+)`?.*?```\w*\n(.+?)```");
+
+        for cap in edit_pattern.captures_iter(result) {
+            let path = PathBuf::from(cap[1].trim());
+            let content = cap[2].to_string();
+
+            let original = if path.exists() {
+                file_cache::read_string(&path)
+            } else {
+                None
+            };
+
+            let change_type = if original.is_some() {
+                ChangeType::Modify
+            } else {
+                ChangeType::Create
+            };
+
+            changes.push(FileChange {
+                path,
+                original_content: original,
+                proposed_content: content,
+                change_type,
+                renamed_from: None,
+            });
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Parse a non-streaming Ollama `/api/chat` response
+///
+/// The assistant message is expected to contain code blocks in the format
+/// accepted by [`parse_code_blocks`].
+pub fn parse_ollama_chat(input: &str) -> Result<Vec<FileChange>> {
+    let response: serde_json::Value =
+        serde_json::from_str(input).context("Failed to parse Ollama chat response")?;
+
+    if let Some(error) = response.get("error").and_then(|e| e.as_str()) {
+        anyhow::bail!("Ollama returned an error: {}", error);
+    }
+
+    let content = response
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_str())
+        .context("Ollama response has no message content")?;
+
+    parse_code_blocks(content)
+}
+
+/// Parse `gh copilot suggest`-style output.
+///
+/// Besides code fences, Copilot prints its suggestion as an indented block
+/// under a `# Suggestion:` heading, followed by an interactive "Select an
+/// option" menu. A suggestion block whose first line names a file
+/// (`# file: path`, `// path`) is a change to that file; any other one is a
+/// shell command, see [`copilot_command_suggestions`].
+pub fn parse_copilot(input: &str) -> Result<Vec<FileChange>> {
+    let clean = strip_ansi(input);
+    let mut changes = parse_code_blocks(&clean)?;
+
+    for block in copilot_blocks(&clean, "Suggestion") {
+        let Some((path, content)) = file_block(&block) else {
+            continue;
+        };
+        let (original, change_type) = if path.exists() {
+            (file_cache::read_string(&path), ChangeType::Modify)
+        } else {
+            (None, ChangeType::Create)
+        };
+        changes.push(FileChange {
+            path,
+            original_content: original,
+            proposed_content: content,
+            change_type,
+            renamed_from: None,
+        });
+    }
+    Ok(changes)
+}
+
+/// Shell commands Copilot suggested (suggestion blocks that aren't files)
+pub fn copilot_command_suggestions(input: &str) -> Vec<String> {
+    copilot_blocks(&strip_ansi(input), "Suggestion")
+        .into_iter()
+        .filter(|block| file_block(block).is_none())
+        .collect()
+}
+
+/// Copilot's reply without its banner and interactive menus
+pub fn copilot_response_text(input: &str) -> String {
+    let clean = strip_ansi(input);
+    let mut text = Vec::new();
+    let mut in_menu = false;
+    for line in clean.lines() {
+        if line.starts_with("? ") {
+            in_menu = true;
+            continue;
+        }
+        // Menu entries are indented; the menu ends at the next unindented line
+        if in_menu && (line.starts_with(' ') || line.starts_with('>') || line.is_empty()) {
+            continue;
+        }
+        in_menu = false;
+        text.push(line);
+    }
+    text.join("\n").trim().to_string()
+}
+
+/// Terminal escape sequences (colors, cursor movement) taken out of `input`
+fn strip_ansi(input: &str) -> String {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    let ansi = cached_regex(&ANSI, r"\x1b\[[0-9;?]*[A-Za-z]|\x1b\][^\x07]*\x07");
+    ansi.replace_all(input, "").replace('\r', "")
+}
+
+/// Indented blocks under `# <heading>:` lines, dedented
+fn copilot_blocks(input: &str, heading: &str) -> Vec<String> {
+    let marker = format!("# {}:", heading);
+    let mut blocks = Vec::new();
+    let mut lines = input.lines().peekable();
+    while let Some(line) = lines.next() {
+        if line.trim() != marker {
+            continue;
+        }
+        let mut body = Vec::new();
+        while let Some(next) = lines.next_if(|l| l.trim().is_empty() || l.starts_with(' ')) {
+            body.push(next);
+        }
+        while body.last().is_some_and(|l| l.trim().is_empty()) {
+            body.pop();
+        }
+        let indent = body
+            .iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let block: Vec<&str> = body
+            .iter()
+            .skip_while(|l| l.trim().is_empty())
+            .map(|l| l.get(indent..).unwrap_or(""))
+            .collect();
+        if !block.is_empty() {
+            blocks.push(block.join("\n"));
+        }
+    }
+    blocks
+}
+
+/// The file a suggestion block's first line names, and the rest of the block
+fn file_block(block: &str) -> Option<(PathBuf, String)> {
+    static FILE_HEADER: OnceLock<Regex> = OnceLock::new();
+    let header = cached_regex(
+        &FILE_HEADER,
+        r"^(?://|#)\s*(?:file:|path:)\s*(\S+)$|^//\s*(\S+\.\w+)$",
+    );
+    let (first, rest) = block.split_once('\n')?;
+    let caps = header.captures(first.trim())?;
+    let path = caps.get(1).or_else(|| caps.get(2))?.as_str();
+    let mut content = rest.to_string();
+    content.push('\n');
+    Some((PathBuf::from(path), content))
+}
+
+/// Parse JSON changes format
+pub fn parse_json_changes(input: &str) -> Result<Vec<FileChange>> {
+    let json: serde_json::Value = serde_json::from_str(input)?;
+
+    let mut changes = Vec::new();
+
+    if let Some(files) = json.as_array() {
+        for file_obj in files {
+            if let (Some(path_str), Some(content_str)) = (
+                file_obj.get("path").and_then(|p| p.as_str()),
+                file_obj.get("content").and_then(|c| c.as_str()),
+            ) {
+                let path = PathBuf::from(path_str);
+
+                let original = if path.exists() {
+                    file_cache::read_string(&path)
+                } else {
+                    None
+                };
+
+                let change_type = if original.is_some() {
+                    ChangeType::Modify
+                } else {
+                    ChangeType::Create
+                };
+
+                changes.push(FileChange {
+                    path,
+                    original_content: original,
+                    proposed_content: content_str.to_string(),
+                    change_type,
+                    renamed_from: None,
+                });
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Parse with custom regex pattern
+pub fn parse_with_regex(input: &str, pattern: &str) -> Result<Vec<FileChange>> {
+    let re = Regex::new(pattern)?;
+    let mut changes = Vec::new();
+
+    for cap in re.captures_iter(input) {
+        if cap.len() >= 3 {
+            let path = PathBuf::from(cap[1].trim());
+            let content = cap[2].to_string();
+
+            let original = if path.exists() {
+                file_cache::read_string(&path)
+            } else {
+                None
+            };
+
+            let change_type = if original.is_some() {
+                ChangeType::Modify
+            } else {
+                ChangeType::Create
+            };
+
+            changes.push(FileChange {
+                path,
+                original_content: original,
+                proposed_content: content,
+                change_type,
+                renamed_from: None,
+            });
+        }
+    }
+
+    Ok(changes)
+}
+
+/// File extensions a code fence's language tag stands for, preferred first.
+/// `None` for tags that say nothing about the file type ("text", "diff", ...).
+pub fn language_extensions(language: &str) -> Option<&'static [&'static str]> {
+    let extensions: &'static [&'static str] = match language.to_lowercase().as_str() {
+        "rust" | "rs" => &["rs"],
+        "python" | "py" | "python3" => &["py", "pyi"],
+        "typescript" | "ts" => &["ts", "mts", "cts"],
+        "tsx" => &["tsx"],
+        "javascript" | "js" => &["js", "mjs", "cjs"],
+        "jsx" => &["jsx"],
+        "go" | "golang" => &["go"],
+        "java" => &["java"],
+        "kotlin" | "kt" => &["kt", "kts"],
+        "swift" => &["swift"],
+        "c" => &["c", "h"],
+        "cpp" | "c++" | "cxx" => &["cpp", "cc", "cxx", "hpp", "hh", "h"],
+        "csharp" | "cs" | "c#" => &["cs"],
+        "ruby" | "rb" => &["rb"],
+        "php" => &["php"],
+        "lua" => &["lua"],
+        "zig" => &["zig"],
+        "haskell" | "hs" => &["hs"],
+        "elixir" => &["ex", "exs"],
+        "scala" => &["scala"],
+        "bash" | "sh" | "shell" | "zsh" => &["sh", "bash", "zsh"],
+        "toml" => &["toml"],
+        "yaml" | "yml" => &["yaml", "yml"],
+        "json" => &["json"],
+        "markdown" | "md" => &["md", "markdown"],
+        "html" => &["html", "htm"],
+        "css" => &["css"],
+        "scss" => &["scss"],
+        "sql" => &["sql"],
+        _ => return None,
+    };
+    Some(extensions)
+}
+
+/// Language tag of the code fence each file's content came from.
+///
+/// Covers fences whose first line names the file (`// path/to/file`) and the
+/// "Editing `path`" form used in Claude's replies.
+pub fn fence_languages(input: &str) -> Vec<(PathBuf, String)> {
+    let patterns = [
+        r"```([\w+#-]+)[ \t]*\n(?://|#|<!--)\s*(?:file:|path:)?\s*([^\n]+)\n",
+        r"(?s)Editing\s+`?([^`\n]+)`?.*?```([\w+#-]+)[ \t]*\n",
+    ];
+    let mut languages = Vec::new();
+    for (idx, pattern) in patterns.iter().enumerate() {
+        let Ok(re) = Regex::new(pattern) else {
+            continue;
+        };
+        for cap in re.captures_iter(input) {
+            let (language, path) = if idx == 0 {
+                (&cap[1], &cap[2])
+            } else {
+                (&cap[2], &cap[1])
+            };
+            languages.push((
+                PathBuf::from(path.trim().trim_end_matches("-->").trim()),
+                language.to_string(),
+            ));
+        }
+    }
+    languages
+}
+
+/// Check new files' paths against the language of the fence they came from.
+///
+/// A new file without an extension is given the language's; one whose extension
+/// the language contradicts (`.ts` holding rust) is left alone but reported.
+/// Returns a note per affected file, keyed by its (possibly renamed) path.
+pub fn check_extensions(changes: &mut [FileChange], output: &str) -> Vec<(PathBuf, String)> {
+    let languages = fence_languages(output);
+    let mut notes = Vec::new();
+
+    for change in changes
+        .iter_mut()
+        .filter(|c| c.change_type == ChangeType::Create)
+    {
+        let Some((_, language)) = languages.iter().find(|(path, _)| *path == change.path) else {
+            continue;
+        };
+        let Some(extensions) = language_extensions(language) else {
+            continue;
+        };
+
+        match change.path.extension().and_then(|e| e.to_str()) {
+            None => {
+                change.path.set_extension(extensions[0]);
+                notes.push((
+                    change.path.clone(),
+                    format!(".{} added from the {} code block", extensions[0], language),
+                ));
+            }
+            Some(ext) if !extensions.contains(&ext.to_lowercase().as_str()) => notes.push((
+                change.path.clone(),
+                format!("path says .{} but the code block is {}", ext, language),
+            )),
+            Some(_) => {}
+        }
+    }
+    notes
+}
+
+/// What [`validate_changes`] kept and what it dropped
+#[derive(Debug, Default)]
+pub struct Validation {
+    pub changes: Vec<FileChange>,
+    /// Changes that leave their file as it is
+    pub no_ops: usize,
+    /// Changes without a usable path
+    pub unnamed: usize,
+    /// Earlier changes to a file that a later one replaced
+    pub duplicates: usize,
+    /// Later changes to a file combined with its earlier ones
+    pub merged: usize,
+    /// Files changed more than once in overlapping places, with each change's
+    /// proposed content in order; the last one is reviewed until one is picked
+    pub conflicts: Vec<(PathBuf, Vec<String>)>,
+    /// Paths that were tidied (quotes, `./`, `dir/..`)
+    pub normalized: usize,
+    /// Files whose proposed content came from output that wasn't UTF-8
+    /// ([`crate::encoding`]); left out rather than written with the bad bytes
+    /// replaced
+    pub not_utf8: Vec<PathBuf>,
+}
+
+impl Validation {
+    /// Whether anything was dropped or rewritten
+    pub fn changed_anything(&self) -> bool {
+        self.no_ops + self.unnamed + self.duplicates + self.merged + self.normalized > 0
+            || !self.conflicts.is_empty()
+            || !self.not_utf8.is_empty()
+    }
+
+    /// One-line account, e.g. "3 changes, 1 skipped as no-op"
+    pub fn summary(&self) -> String {
+        let count =
+            |n: usize, one: &str, many: &str| format!("{} {}", n, if n == 1 { one } else { many });
+        let mut summary = count(self.changes.len(), "change", "changes");
+        if self.no_ops > 0 {
+            summary.push_str(&format!(", {} skipped as no-op", self.no_ops));
+        }
+        if self.duplicates > 0 {
+            summary.push_str(&format!(
+                ", {} replaced by a later change to the same file",
+                self.duplicates
+            ));
+        }
+        if self.merged > 0 {
+            summary.push_str(&format!(
+                ", {} merged into an earlier change to the same file",
+                self.merged
+            ));
+        }
+        if !self.conflicts.is_empty() {
+            summary.push_str(&format!(
+                ", {} with overlapping edits to pick from (:pick)",
+                count(self.conflicts.len(), "file", "files")
+            ));
+        }
+        if self.unnamed > 0 {
+            summary.push_str(&format!(
+                ", {} without a path",
+                count(self.unnamed, "change", "changes")
+            ));
+        }
+        if self.normalized > 0 {
+            summary.push_str(&format!(
+                ", {} normalized",
+                count(self.normalized, "path", "paths")
+            ));
+        }
+        if !self.not_utf8.is_empty() {
+            let paths: Vec<String> = self
+                .not_utf8
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+            summary.push_str(&format!(
+                ", {} left out as the reply wasn't valid UTF-8 there ({})",
+                count(self.not_utf8.len(), "file", "files"),
+                paths.join(", ")
+            ));
+        }
+        summary
+    }
+}
+
+/// Sanity pass over parsed changes before they are reviewed.
+///
+/// Paths are tidied, changes without one are dropped, and changes whose
+/// proposed content matches the original (or the file on disk) are skipped.
+/// A file changed more than once gets one change in the place of its first:
+/// a later change written on top of an earlier one (or deleting the file)
+/// replaces it, changes to separate lines are merged, and changes to the same
+/// lines are kept as a conflict for the reviewer to pick from. Changes whose
+/// content has bytes that weren't UTF-8 ([`crate::encoding::INVALID`]) are
+/// refused.
+pub fn validate_changes(changes: Vec<FileChange>) -> Validation {
+    let mut validation = Validation::default();
+    let mut kept: Vec<FileChange> = Vec::new();
+
+    for mut change in changes {
+        let Some(path) = normalize_path(&change.path) else {
+            validation.unnamed += 1;
+            continue;
+        };
+        if path != change.path {
+            validation.normalized += 1;
+            change.path = path;
+        }
+        change.renamed_from = change
+            .renamed_from
+            .as_deref()
+            .and_then(normalize_path)
+            .filter(|from| *from != change.path);
+        if !crate::encoding::is_intact(&change.proposed_content) {
+            validation.not_utf8.push(change.path);
+            continue;
+        }
+        let Some(earlier) = kept.iter_mut().find(|c| c.path == change.path) else {
+            kept.push(change);
+            continue;
+        };
+        if let Some((_, alternatives)) = validation
+            .conflicts
+            .iter_mut()
+            .find(|(path, _)| *path == change.path)
+        {
+            alternatives.push(change.proposed_content.clone());
+            earlier.proposed_content = change.proposed_content;
+            continue;
+        }
+
+        let built_on_earlier =
+            change.original_content.as_deref() == Some(earlier.proposed_content.as_str());
+        let base = earlier
+            .original_content
+            .clone()
+            .or_else(|| file_cache::read_string(&change.path))
+            .unwrap_or_default();
+        if built_on_earlier
+            || earlier.change_type == ChangeType::Delete
+            || change.change_type == ChangeType::Delete
+        {
+            validation.duplicates += 1;
+            if built_on_earlier {
+                change.original_content = earlier.original_content.take();
+                change.change_type = earlier.change_type.clone();
+            }
+            *earlier = change;
+        } else if let Some(merged) =
+            crate::diff::merge3(&base, &earlier.proposed_content, &change.proposed_content)
+        {
+            validation.merged += 1;
+            earlier.proposed_content = merged;
+        } else {
+            validation.conflicts.push((
+                change.path.clone(),
+                vec![
+                    earlier.proposed_content.clone(),
+                    change.proposed_content.clone(),
+                ],
+            ));
+            earlier.proposed_content = change.proposed_content;
+        }
+    }
+
+    for change in kept {
+        let original = match change.change_type {
+            ChangeType::Delete => None,
+            _ => change
+                .original_content
+                .clone()
+                .or_else(|| file_cache::read_string(&change.path)),
+        };
+        if original.as_deref() == Some(change.proposed_content.as_str()) {
+            validation.no_ops += 1;
+        } else {
+            validation.changes.push(change);
+        }
+    }
+    validation
+}
+
+/// `path` without surrounding whitespace, quotes or backticks, `.` components
+/// and `dir/..` pairs; `None` if nothing is left
+fn normalize_path(path: &Path) -> Option<PathBuf> {
+    let text = path.to_string_lossy();
+    let text = text
+        .trim()
+        .trim_matches(|c| matches!(c, '`' | '"' | '\''))
+        .trim();
+    let mut normalized = PathBuf::new();
+    for component in Path::new(text).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    let names_file = normalized
+        .components()
+        .next_back()
+        .is_some_and(|c| matches!(c, Component::Normal(_)));
+    names_file.then_some(normalized)
+}
+
+/// Environment variable overriding where `:report-parse-failure` writes fixtures
+pub const FIXTURE_DIR_ENV: &str = "ZCODE_FIXTURE_DIR";
+
+/// Directory that reported parse failures are written to.
+///
+/// Defaults to `~/.local/share/zcode/fixtures`; point `ZCODE_FIXTURE_DIR` at a
+/// checkout's `tests/fixtures` to add reports to the corpus directly.
+pub fn fixture_corpus_dir() -> PathBuf {
+    match std::env::var_os(FIXTURE_DIR_ENV) {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("zcode")
+            .join("fixtures"),
+    }
+}
+
+/// Corpus subdirectory name for a provider display name
+/// (e.g. "GitHub Copilot CLI" -> "copilot")
+pub fn fixture_slug(provider_name: &str) -> String {
+    let lower = provider_name.to_lowercase();
+    let words: Vec<&str> = lower
+        .split_whitespace()
+        .filter(|w| !matches!(*w, "github" | "cli" | "code"))
+        .collect();
+
+    if words.is_empty() {
+        "unknown".to_string()
+    } else {
+        words.join("-")
+    }
+}
+
+/// Strip secrets and machine-specific details from provider output
+pub fn redact_provider_output(output: &str) -> String {
+    let mut redacted = output.to_string();
+
+    if let Some(home) = dirs::home_dir() {
+        let home = home.to_string_lossy();
+        if home.len() > 1 {
+            redacted = redacted.replace(home.as_ref(), "~");
+        }
+    }
+
+    let patterns = [
+        (r"sk-[A-Za-z0-9_-]{16,}", "<redacted-key>"),
+        (r"gh[pousr]_[A-Za-z0-9]{20,}", "<redacted-token>"),
+        (r"AKIA[0-9A-Z]{16}", "<redacted-key>"),
+        (
+            r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+            "<redacted-email>",
+        ),
+        (
+            r#""session_id"\s*:\s*"[^"]*""#,
+            r#""session_id": "<redacted>""#,
+        ),
+    ];
+    for (pattern, replacement) in patterns {
+        if let Ok(re) = Regex::new(pattern) {
+            redacted = re.replace_all(&redacted, replacement).into_owned();
+        }
+    }
+
+    redacted
+}
+
+/// Write a redacted provider output into `<corpus_dir>/<provider>/` for triage.
+///
+/// Reports have no expected `.json` yet, so the fixture runner skips them until
+/// someone fills one in. Returns the path of the new fixture.
+pub fn report_parse_failure(
+    corpus_dir: &Path,
+    provider_name: &str,
+    output: &str,
+) -> Result<PathBuf> {
+    let dir = corpus_dir.join(fixture_slug(provider_name));
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create fixture directory {}", dir.display()))?;
+
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    let mut path = dir.join(format!("unparsed-{}.txt", stamp));
+    let mut suffix = 1;
+    while path.exists() {
+        path = dir.join(format!("unparsed-{}-{}.txt", stamp, suffix));
+        suffix += 1;
+    }
+
+    fs::write(&path, redact_provider_output(output))
+        .with_context(|| format!("Failed to write fixture {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_unified_diff_single_file() {
+        let input = r#"--- a/test.txt
++++ b/test.txt
+line 1
+-old line
++new line
+line 3"#;
+
+        let result = parse_unified_diff(input).unwrap();
+        assert!(!result.is_empty());
+        assert_eq!(result[0].path, PathBuf::from("test.txt"));
+        assert!(result[0].original_content.is_some());
+        assert!(!result[0].proposed_content.is_empty());
+    }
+
+    #[test]
+    fn test_parse_unified_diff_multiple_files() {
+        let input = r#"--- a/file1.txt
++++ b/file1.txt
+-old
++new
+--- a/file2.txt
++++ b/file2.txt
+-line1
++line2"#;
+
+        let result = parse_unified_diff(input).unwrap();
+        assert!(!result.is_empty());
+        // At least file1 should be present
+        assert!(result.iter().any(|c| c.path == Path::new("file1.txt")));
+    }
+
+    #[test]
+    fn test_parse_unified_diff_reads_hunks_by_their_headers() {
+        let input = "--- a/missing/lib.rs\n+++ b/missing/lib.rs\n@@ -1,2 +1,2 @@\n a\n-b\n+B\n@@ -9,2 +9,3 @@ fn tail()\n y\n+inserted\n z\nThat's all.\n";
+        let result = parse_unified_diff(input).unwrap();
+        assert_eq!(result.len(), 1);
+        // Not on disk, so only the hunks' lines, without the prose after them
+        assert_eq!(result[0].original_content.as_deref(), Some("a\nb\ny\nz\n"));
+        assert_eq!(result[0].proposed_content, "a\nB\ny\ninserted\nz\n");
+
+        let hunks = [
+            DiffHunk {
+                header: Some((
+                    HunkRange { start: 2, len: 1 },
+                    HunkRange { start: 2, len: 1 },
+                )),
+                old_lines: vec!["b\n"],
+                new_lines: vec!["B\n"],
+                last: None,
+            },
+            DiffHunk {
+                header: Some((
+                    HunkRange { start: 4, len: 0 },
+                    HunkRange { start: 5, len: 1 },
+                )),
+                old_lines: Vec::new(),
+                new_lines: vec!["e\n"],
+                last: None,
+            },
+        ];
+        assert_eq!(
+            patch_lines("a\nb\nc\nd\n", &hunks).as_deref(),
+            Some("a\nB\nc\nd\ne\n")
+        );
+        assert_eq!(patch_lines("a\nc\n", &hunks), None);
+    }
+
+    #[test]
+    fn test_check_extensions_from_fence_language() {
+        let output = "```python\n# scripts/build_index\nprint(1)\n```\n\n```rust\n// web/app.ts\nfn main() {}\n```\n```text\n# NOTES\nhi\n```\n";
+        let mut changes = parse_code_blocks(output).unwrap();
+        assert_eq!(changes.len(), 3);
+
+        let notes = check_extensions(&mut changes, output);
+        assert_eq!(changes[0].path, PathBuf::from("scripts/build_index.py"));
+        assert_eq!(changes[1].path, PathBuf::from("web/app.ts"));
+        assert_eq!(changes[2].path, PathBuf::from("NOTES"));
+        assert_eq!(
+            notes,
+            vec![
+                (
+                    PathBuf::from("scripts/build_index.py"),
+                    ".py added from the python code block".to_string()
+                ),
+                (
+                    PathBuf::from("web/app.ts"),
+                    "path says .ts but the code block is rust".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_changes_drops_no_ops_and_duplicates() {
+        let change = |path: &str, original: Option<&str>, proposed: &str| FileChange {
+            path: PathBuf::from(path),
+            original_content: original.map(str::to_string),
+            proposed_content: proposed.to_string(),
+            change_type: ChangeType::Modify,
+            renamed_from: None,
+        };
+        let validation = validate_changes(vec![
+            change("./src/lib.rs", Some("a\n"), "b\n"),
+            change("src/main.rs", Some("same\n"), "same\n"),
+            change("  ", None, "x\n"),
+            change("`src/util/../lib.rs`", Some("a\n"), "c\n"),
+            change("src/new.rs", Some(""), "fn new() {}\n"),
+            change("src/merge.rs", Some("a\nb\nc\nd\n"), "A\nb\nc\nd\n"),
+            change("src/merge.rs", Some("a\nb\nc\nd\n"), "a\nb\nc\nD\n"),
+            change("src/merge.rs", Some("A\nb\nc\nD\n"), "A\nB\nc\nD\n"),
+        ]);
+
+        let paths: Vec<_> = validation.changes.iter().map(|c| c.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("src/lib.rs"),
+                PathBuf::from("src/new.rs"),
+                PathBuf::from("src/merge.rs")
+            ]
+        );
+        // Both lib.rs changes rewrite its only line: the reviewer picks one
+        assert_eq!(validation.changes[0].proposed_content, "c\n");
+        assert_eq!(
+            validation.conflicts,
+            vec![(
+                PathBuf::from("src/lib.rs"),
+                vec!["b\n".to_string(), "c\n".to_string()]
+            )]
+        );
+        // Separate lines merge, and a change made on top of the result replaces it
+        assert_eq!(
+            validation.changes[2].original_content.as_deref(),
+            Some("a\nb\nc\nd\n")
+        );
+        assert_eq!(validation.changes[2].proposed_content, "A\nB\nc\nD\n");
+        assert_eq!(
+            validation.summary(),
+            "3 changes, 1 skipped as no-op, 1 replaced by a later change to the same file, \
+             1 merged into an earlier change to the same file, \
+             1 file with overlapping edits to pick from (:pick), \
+             1 change without a path, 2 paths normalized"
+        );
+
+        let clean = validate_changes(vec![change("src/lib.rs", Some("a\n"), "b\n")]);
+        assert!(!clean.changed_anything());
+        assert_eq!(clean.summary(), "1 change");
+    }
+
+    #[test]
+    fn test_validate_changes_refuses_content_that_was_not_utf8() {
+        let reply = crate::encoding::decode_marked(
+            b"--- a/ok.txt\n+++ b/ok.txt\n-a\n+caf\xC3\xA9\n\
+              --- a/bad.txt\n+++ b/bad.txt\n-a\n+caf\xE9\n",
+        );
+        let validation = validate_changes(parse_unified_diff(&reply).unwrap());
+
+        assert_eq!(validation.changes.len(), 1);
+        assert_eq!(validation.changes[0].proposed_content, "café\n");
+        assert_eq!(validation.not_utf8, [PathBuf::from("bad.txt")]);
+        assert!(validation.changed_anything());
+        assert!(validation
+            .summary()
+            .ends_with("1 file left out as the reply wasn't valid UTF-8 there (bad.txt)"));
+    }
+
+    #[test]
+    fn test_claude_result_from_stream() {
+        let stream = "{\"type\":\"system\",\"subtype\":\"init\"}\n{\"type\":\"assistant\",\"message\":{}}\n{\"type\":\"result\",\"session_id\":\"abc\",\"result\":\"done\"}\n";
+        let result = claude_result_json(stream).unwrap();
+        assert_eq!(result["session_id"], "abc");
+        assert_eq!(
+            claude_result_json("{\"result\":\"one\"}").unwrap()["result"],
+            "one"
+        );
+        assert!(claude_result_json("not json").is_none());
+    }
+
+    #[test]
+    fn test_parse_unified_diff_deletion() {
+        let input =
+            "--- a/old.rs\n+++ /dev/null\n-fn old() {}\n--- a/kept.rs\n+++ b/kept.rs\n-a\n+b";
+
+        let result = parse_unified_diff(input).unwrap();
+        assert_eq!(result[0].change_type, ChangeType::Delete);
+        assert!(result[0].proposed_content.is_empty());
+        assert_eq!(result[1].change_type, ChangeType::Modify);
+    }
+
+    #[test]
+    fn test_parse_unified_diff_rename() {
+        let input = "diff --git a/src/old_name.rs b/src/new_name.rs\nsimilarity index 90%\nrename from src/old_name.rs\nrename to src/new_name.rs\n--- a/src/old_name.rs\n+++ b/src/new_name.rs\n@@ -1 +1 @@\n-a\n+b\n--- x.rs.orig\n+++ x.rs\n@@ -1 +1 @@\n-a\n+b\n";
+
+        let result = parse_unified_diff(input).unwrap();
+        assert_eq!(result[0].path, PathBuf::from("src/new_name.rs"));
+        assert_eq!(result[0].change_type, ChangeType::Modify);
+        assert_eq!(
+            result[0].renamed_from,
+            Some(PathBuf::from("src/old_name.rs"))
+        );
+        assert_eq!(result[0].proposed_content, "b\n");
+        // Differing `---`/`+++` paths alone aren't a rename
+        assert_eq!(result[1].renamed_from, None);
+    }
+
+    #[test]
+    fn test_parse_code_blocks_basic() {
+        let input = r#"
+```rust
+// file: src/main.rs
+fn main() {
+    println!("Hello");
+}
+```
+"#;
+
+        let result = parse_code_blocks(input).unwrap();
+        assert!(!result.is_empty());
+        // Path extraction may fail if file doesn't exist, but shouldn't panic
+    }
+
+    #[test]
+    fn test_parse_json_changes_empty() {
+        let input = "[]";
+        let result = parse_json_changes(input).unwrap();
+        assert_eq!(result.len(), 0);
+    }
+
+    #[test]
+    fn test_parse_json_changes_single() {
+        let input = r#"[{"path": "test.txt", "content": "hello world"}]"#;
+        let result = parse_json_changes(input).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, PathBuf::from("test.txt"));
+        assert_eq!(result[0].proposed_content, "hello world");
+    }
+
+    #[test]
+    fn test_parse_with_regex_valid_pattern() {
+        let input = "file:src/test.rs;content:fn main() {}";
+        let pattern = r"file:([^;]+);content:(.+)";
+        let result = parse_with_regex(input, pattern).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, PathBuf::from("src/test.rs"));
+    }
+
+    #[test]
+    fn test_parse_with_regex_invalid_pattern() {
+        let input = "some content";
+        let pattern = "[invalid";
+        let result = parse_with_regex(input, pattern);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_claude_json_empty_response() {
+        let input = r#"{"result": ""}"#;
+        let result = parse_claude_json(input).unwrap();
+        assert_eq!(result.len(), 0);
+    }
+
+    #[test]
+    fn test_change_type_detection() {
+        // Create file doesn't exist - should be Create type
+        let change = FileChange {
+            path: PathBuf::from("/nonexistent/path.txt"),
+            original_content: None,
+            proposed_content: "new content".to_string(),
+            change_type: ChangeType::Create,
+            renamed_from: None,
+        };
+        assert_eq!(change.change_type, ChangeType::Create);
+
+        // Modify file has original - should be Modify type
+        let change = FileChange {
+            path: PathBuf::from("/existing/path.txt"),
+            original_content: Some("old content".to_string()),
+            proposed_content: "new content".to_string(),
+            change_type: ChangeType::Modify,
+            renamed_from: None,
+        };
+        assert_eq!(change.change_type, ChangeType::Modify);
+    }
+
+    #[test]
+    fn test_fixture_corpus() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let mut checked = 0;
+        let mut failures = Vec::new();
+
+        for provider_dir in fs::read_dir(&root).unwrap() {
+            let provider_dir = provider_dir.unwrap().path();
+            if !provider_dir.is_dir() {
+                continue;
+            }
+            let provider_name = provider_dir
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+            let provider = crate::providers::create_provider(&provider_name, None)
+                .unwrap_or_else(|| panic!("No provider for fixture dir '{}'", provider_name));
+
+            for entry in fs::read_dir(&provider_dir).unwrap() {
+                let fixture = entry.unwrap().path();
+                if fixture.extension().and_then(|e| e.to_str()) != Some("txt") {
+                    continue;
+                }
+                // Reported failures without an expectation yet are pending triage
+                let expected_path = fixture.with_extension("json");
+                if !expected_path.exists() {
+                    continue;
+                }
+
+                let output = fs::read_to_string(&fixture).unwrap();
+                let expected: Vec<FileChange> =
+                    serde_json::from_str(&fs::read_to_string(&expected_path).unwrap()).unwrap();
+
+                match provider.parse_file_changes(&output) {
+                    Ok(actual) if actual == expected => {}
+                    Ok(actual) => failures.push(format!(
+                        "{}: expected {:#?}, got {:#?}",
+                        fixture.display(),
+                        expected,
+                        actual
+                    )),
+                    Err(e) => failures.push(format!("{}: parse error: {}", fixture.display(), e)),
+                }
+                checked += 1;
+            }
+        }
+
+        assert!(checked > 0, "no fixtures found under {}", root.display());
+        assert!(
+            failures.is_empty(),
+            "fixture mismatches:\n{}",
+            failures.join("\n")
+        );
+    }
+
+    #[test]
+    fn test_parse_ollama_chat_error() {
+        let input = r#"{"error":"model 'codellama' not found"}"#;
+        let err = parse_ollama_chat(input).unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_parse_ollama_chat_missing_message() {
+        assert!(parse_ollama_chat(r#"{"done":true}"#).is_err());
+    }
+
+    #[test]
+    fn test_fixture_slug() {
+        assert_eq!(fixture_slug("Claude Code"), "claude");
+        assert_eq!(fixture_slug("GitHub Copilot CLI"), "copilot");
+        assert_eq!(fixture_slug("Kiro CLI"), "kiro");
+        assert_eq!(fixture_slug("My Custom AI"), "my-custom-ai");
+    }
+
+    #[test]
+    fn test_code_blocks_with_and_without_paths() {
+        let reply = "Try this:\n```rust\n// src/lib.rs\npub fn a() {}\n```\nor run\n\
+                     ```\n# install first\nmake\n```\n";
+        let blocks = code_blocks(reply);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].language, "rust");
+        assert_eq!(blocks[0].path, Some(PathBuf::from("src/lib.rs")));
+        assert_eq!(blocks[0].content, "pub fn a() {}\n");
+        // A comment that isn't a path stays in the block
+        assert_eq!(blocks[1].language, "");
+        assert_eq!(blocks[1].path, None);
+        assert_eq!(blocks[1].content, "# install first\nmake\n");
+    }
+
+    #[test]
+    fn test_shell_suggestions_skip_file_blocks() {
+        let reply = "Run:\n```bash\n$ cargo test\n```\n\n```sh\n# scripts/build.sh\nmake\n```\n\n```console\n$ ls\nsrc\n```\n```rust\nfn main() {}\n```\n";
+        assert_eq!(
+            shell_suggestions(reply),
+            vec!["cargo test".to_string(), "ls".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_copilot_suggestions_drop_the_menu() {
+        let output = "\x1b[1m# Suggestion:\x1b[0m\n\n  git rm --cached .env\n\n? Select an option\n> Copy command to clipboard\n  Exit\n";
+        assert_eq!(
+            copilot_command_suggestions(output),
+            vec!["git rm --cached .env".to_string()]
+        );
+        assert!(parse_copilot(output).unwrap().is_empty());
+        assert!(!copilot_response_text(output).contains("Select an option"));
+    }
+
+    #[test]
+    fn test_redact_provider_output() {
+        let input = r#"{"session_id":"abc-123","result":"key sk-ant-REDACTED from me@example.com"}"#;
+        let redacted = redact_provider_output(input);
+        assert!(!redacted.contains("abc-123"));
+        assert!(!redacted.contains("sk-ant-api03"));
+        assert!(!redacted.contains("me@example.com"));
+        assert!(redacted.contains("<redacted-key>"));
+    }
+
+    #[test]
+    fn test_report_parse_failure_writes_redacted_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let first =
+            report_parse_failure(dir.path(), "Claude Code", "token sk-abcdefghijklmnopqrstu")
+                .unwrap();
+        let second = report_parse_failure(dir.path(), "Claude Code", "again").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(first.parent().unwrap(), dir.path().join("claude"));
+        let written = fs::read_to_string(&first).unwrap();
+        assert_eq!(written, "token <redacted-key>");
+    }
+}
@@ -0,0 +1,142 @@
+//! Standing instructions sent with every prompt
+//!
+//! A preamble comes from `general.preamble` in the global config and/or the
+//! project's `.zcode/preamble.md`; when both exist the project text follows the
+//! global one. Providers with a native hook receive it as a system prompt
+//! (`claude --append-system-prompt`, an Aider conventions file read with
+//! `--read`); for the rest it is prepended to the prompt.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Project preamble, relative to the project root
+pub const PROJECT_FILE: &str = ".zcode/preamble.md";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preamble {
+    pub text: String,
+    /// Where the text came from ("global", "project"), for the indicator
+    pub sources: Vec<&'static str>,
+    /// Copy of `text` on disk for providers that read instructions from a file
+    pub file: Option<PathBuf>,
+}
+
+/// Path of the project preamble under `root`
+pub fn project_path(root: &Path) -> PathBuf {
+    root.join(PROJECT_FILE)
+}
+
+/// Directory holding the on-disk copies handed to file-based providers
+pub fn conventions_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zcode")
+        .join("preambles")
+}
+
+impl Preamble {
+    /// Combine the global preamble with the project file under `root`.
+    /// Returns `None` when neither has any text.
+    pub fn load(global: Option<&str>, root: &Path) -> Result<Option<Self>> {
+        let path = project_path(root);
+        let project = if path.is_file() {
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+        } else {
+            String::new()
+        };
+
+        let mut parts = Vec::new();
+        let mut sources = Vec::new();
+        for (source, text) in [("global", global.unwrap_or("")), ("project", &project)] {
+            if !text.trim().is_empty() {
+                parts.push(text.trim());
+                sources.push(source);
+            }
+        }
+        if parts.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            text: parts.join("\n\n"),
+            sources,
+            file: None,
+        }))
+    }
+
+    /// Write the text to `dir` (named by its hash, so instances in different
+    /// projects never clobber each other) and remember the path
+    pub fn write_file(&mut self, dir: &Path) -> Result<&Path> {
+        let mut hasher = DefaultHasher::new();
+        self.text.hash(&mut hasher);
+        let path = dir.join(format!("preamble-{:016x}.md", hasher.finish()));
+
+        if !path.is_file() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            std::fs::write(&path, &self.text)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(self.file.insert(path))
+    }
+
+    /// Short description for the header, e.g. "preamble (global+project)"
+    pub fn label(&self) -> String {
+        format!("preamble ({})", self.sources.join("+"))
+    }
+}
+
+/// Create an empty project preamble under `root` if there is none yet
+pub fn ensure_project_file(root: &Path) -> Result<PathBuf> {
+    let path = project_path(root);
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, "")
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_combines_global_and_project() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Preamble::load(Some("  "), dir.path()).unwrap(), None);
+
+        let path = ensure_project_file(dir.path()).unwrap();
+        assert_eq!(Preamble::load(None, dir.path()).unwrap(), None);
+
+        std::fs::write(&path, "Use tabs.\n").unwrap();
+        let preamble = Preamble::load(Some("Be terse."), dir.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(preamble.text, "Be terse.\n\nUse tabs.");
+        assert_eq!(preamble.label(), "preamble (global+project)");
+
+        let project_only = Preamble::load(None, dir.path()).unwrap().unwrap();
+        assert_eq!(project_only.sources, vec!["project"]);
+    }
+
+    #[test]
+    fn test_write_file_is_named_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut a = Preamble::load(Some("one"), dir.path()).unwrap().unwrap();
+        let mut b = Preamble::load(Some("two"), dir.path()).unwrap().unwrap();
+
+        let path_a = a.write_file(dir.path()).unwrap().to_path_buf();
+        let path_b = b.write_file(dir.path()).unwrap().to_path_buf();
+        assert_ne!(path_a, path_b);
+        assert_eq!(std::fs::read_to_string(&path_a).unwrap(), "one");
+        assert_eq!(a.file, Some(path_a));
+    }
+}
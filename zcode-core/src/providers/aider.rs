@@ -1,11 +1,11 @@
-// src/providers/aider.rs - Aider provider implementation
+// zcode-core/src/providers/aider.rs - Aider provider implementation
 
 use anyhow::Result;
 
 use super::{AIProvider, ParserType};
 use crate::config::ProviderConfig;
 use crate::parsers::parse_unified_diff;
-use crate::state::{FileChange, PromptRequest};
+use crate::types::{FileChange, PromptMode, PromptRequest};
 
 #[derive(Debug, Clone)]
 pub struct AiderProvider {
@@ -45,6 +45,20 @@ impl AIProvider for AiderProvider {
     }
 
     fn build_execute_args(&self, request: &PromptRequest) -> Vec<String> {
+        // The preamble is read as a conventions file; inline it if that couldn't be written
+        let preamble_file = request.preamble.as_ref().and_then(|p| p.file.as_ref());
+        let message = match &request.preamble {
+            Some(preamble) if preamble_file.is_none() => {
+                format!("{}\n\n{}", preamble.text, request.prompt)
+            }
+            _ => request.prompt.clone(),
+        };
+        // `/ask` answers without proposing edits
+        let message = match request.mode {
+            PromptMode::Ask => format!("/ask {}", message),
+            PromptMode::Edit => message,
+        };
+
         let mut args = vec![
             "--model".to_string(),
             self.model.clone(),
@@ -53,9 +67,14 @@ impl AIProvider for AiderProvider {
             "--yes".to_string(),    // Auto-confirm
             "--no-git".to_string(), // Don't auto-commit
             "--message".to_string(),
-            request.prompt.clone(),
+            message,
         ];
 
+        if let Some(file) = preamble_file {
+            args.push("--read".to_string());
+            args.push(file.to_string_lossy().to_string());
+        }
+
         // Add files to context
         for file in &request.context_files {
             args.push(file.to_string_lossy().to_string());
@@ -75,4 +94,21 @@ impl AIProvider for AiderProvider {
     fn supports_sessions(&self) -> bool {
         false
     }
+
+    /// Aider sends its own map of the repository
+    fn reads_repository(&self) -> bool {
+        true
+    }
+
+    fn accepts_context_files(&self) -> bool {
+        true
+    }
+
+    fn supports_preamble(&self) -> bool {
+        true
+    }
+
+    fn supports_ask(&self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,129 @@
+// zcode-core/src/providers/amazon_q.rs - Kiro CLI provider (formerly Amazon Q Developer)
+
+use anyhow::Result;
+
+use super::{AIProvider, ParserType};
+use crate::activity::ActivityEvent;
+use crate::config::ProviderConfig;
+use crate::parsers::parse_code_blocks;
+use crate::sections::final_answer;
+use crate::types::{FileChange, PromptRequest};
+
+#[derive(Debug, Clone, Default)]
+pub struct AmazonQProvider {
+    /// Custom CLI path (if specified in config)
+    pub cli_path: Option<String>,
+}
+
+impl AmazonQProvider {
+    pub fn new(config: Option<&ProviderConfig>) -> Self {
+        Self {
+            cli_path: config.and_then(|c| c.path.clone()),
+        }
+    }
+}
+
+impl AIProvider for AmazonQProvider {
+    fn name(&self) -> &str {
+        "Kiro CLI"
+    }
+
+    fn cli_command(&self) -> &str {
+        self.cli_path.as_deref().unwrap_or("kiro")
+    }
+
+    fn build_execute_args(&self, request: &PromptRequest) -> Vec<String> {
+        vec![
+            "chat".to_string(),
+            "--no-interactive".to_string(),
+            request.prompt.clone(),
+        ]
+    }
+
+    fn parse_file_changes(&self, output: &str) -> Result<Vec<FileChange>> {
+        // Code a tool read or wrote along the way isn't part of the answer
+        parse_code_blocks(&final_answer(output))
+    }
+
+    fn parser_type(&self) -> ParserType {
+        ParserType::CodeBlocks
+    }
+
+    fn supports_sessions(&self) -> bool {
+        true
+    }
+
+    fn reads_repository(&self) -> bool {
+        true
+    }
+
+    fn parse_activity(&self, line: &str) -> Vec<ActivityEvent> {
+        parse_tool_line(line).into_iter().collect()
+    }
+
+    fn login_command(&self, output: &str) -> Option<Vec<String>> {
+        super::login_hint(output)
+            .or_else(|| Some(vec![self.cli_command().to_string(), "login".to_string()]))
+    }
+}
+
+/// Tool progress in Kiro's plain output: "Using tool: fs_read", followed by
+/// lines such as "Reading file: src/main.rs" and "● Completed in 0.1s"
+fn parse_tool_line(line: &str) -> Option<ActivityEvent> {
+    let ansi = regex::Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").ok()?;
+    let line = ansi.replace_all(line, "");
+    let line = line
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .trim();
+
+    if let Some(rest) = line.strip_prefix("Using tool:") {
+        let name = rest.split_whitespace().next()?;
+        return Some(ActivityEvent::Started {
+            id: Some("tool".to_string()),
+            label: name.to_string(),
+            detail: None,
+        });
+    }
+    if line.starts_with("Completed in") || line.starts_with("Failed in") {
+        return Some(ActivityEvent::Finished {
+            id: "tool".to_string(),
+        });
+    }
+    [
+        "Reading file:",
+        "Reading directory:",
+        "Path:",
+        "Searching for:",
+    ]
+    .iter()
+    .find_map(|prefix| line.strip_prefix(prefix))
+    .map(|target| ActivityEvent::Detail(target.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_lines_become_activity() {
+        assert_eq!(
+            parse_tool_line("\x1b[1m🛠️  Using tool: fs_read\x1b[0m (trusted)"),
+            Some(ActivityEvent::Started {
+                id: Some("tool".to_string()),
+                label: "fs_read".to_string(),
+                detail: None,
+            })
+        );
+        assert_eq!(
+            parse_tool_line(" ⋮  ● Reading file: src/main.rs, all lines"),
+            Some(ActivityEvent::Detail("src/main.rs, all lines".to_string()))
+        );
+        assert_eq!(
+            parse_tool_line(" - Completed in 0.2s"),
+            Some(ActivityEvent::Finished {
+                id: "tool".to_string()
+            })
+        );
+        assert_eq!(parse_tool_line("Here is the change:"), None);
+    }
+}
@@ -0,0 +1,286 @@
+// zcode-core/src/providers/claude.rs - Claude Code provider implementation
+
+use anyhow::Result;
+
+use super::{AIProvider, ParserType, Usage};
+use crate::activity::ActivityEvent;
+use crate::config::ProviderConfig;
+use crate::parsers::{claude_result_json, parse_claude_json};
+use crate::types::{FileChange, PromptMode, PromptRequest};
+
+#[derive(Debug, Clone, Default)]
+pub struct ClaudeProvider {
+    pub session_id: Option<String>,
+    /// Custom CLI path (if specified in config)
+    pub cli_path: Option<String>,
+}
+
+impl ClaudeProvider {
+    pub fn new(config: Option<&ProviderConfig>) -> Self {
+        Self {
+            session_id: None,
+            cli_path: config.and_then(|c| c.path.clone()),
+        }
+    }
+
+    pub fn with_session(mut self, session_id: String) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+}
+
+impl AIProvider for ClaudeProvider {
+    fn name(&self) -> &str {
+        "Claude Code"
+    }
+
+    fn cli_command(&self) -> &str {
+        self.cli_path.as_deref().unwrap_or("claude")
+    }
+
+    fn build_execute_args(&self, request: &PromptRequest) -> Vec<String> {
+        let mut args = vec![
+            "-p".to_string(),
+            request.prompt.clone(),
+            // One event per line, so progress can be shown while it runs
+            "--output-format".to_string(),
+            "stream-json".to_string(),
+            "--verbose".to_string(),
+            "--allowedTools".to_string(),
+            // Asking only reads
+            match request.mode {
+                PromptMode::Ask => "Read".to_string(),
+                PromptMode::Edit => "Read,Edit,Write".to_string(),
+            },
+        ];
+
+        if let Some(preamble) = &request.preamble {
+            args.push("--append-system-prompt".to_string());
+            args.push(preamble.text.clone());
+        }
+
+        if let Some(ref session) = request.session_id.as_ref().or(self.session_id.as_ref()) {
+            args.push("--resume".to_string());
+            args.push(session.to_string());
+        }
+
+        args
+    }
+
+    fn parse_file_changes(&self, output: &str) -> Result<Vec<FileChange>> {
+        parse_claude_json(output)
+    }
+
+    fn parser_type(&self) -> ParserType {
+        ParserType::ClaudeJson
+    }
+
+    fn supports_sessions(&self) -> bool {
+        true
+    }
+
+    fn reads_repository(&self) -> bool {
+        true
+    }
+
+    fn supports_preamble(&self) -> bool {
+        true
+    }
+
+    fn supports_ask(&self) -> bool {
+        true
+    }
+
+    fn login_command(&self, output: &str) -> Option<Vec<String>> {
+        // Claude prints "Please run /login"; starting the REPL with it opens the login flow
+        super::login_hint(output)
+            .or_else(|| Some(vec![self.cli_command().to_string(), "/login".to_string()]))
+    }
+
+    fn extract_session_id(&self, stdout: &str) -> Option<String> {
+        // Parse session ID from Claude's JSON response
+        claude_result_json(stdout)?
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    }
+
+    fn response_text(&self, output: &str) -> String {
+        claude_result_json(output)
+            .and_then(|json| json.get("result")?.as_str().map(str::to_string))
+            .unwrap_or_else(|| output.to_string())
+    }
+
+    fn parse_activity(&self, line: &str) -> Vec<ActivityEvent> {
+        parse_stream_event(line)
+    }
+
+    fn extract_usage(&self, stdout: &str) -> Option<Usage> {
+        let json = claude_result_json(stdout)?;
+        let usage = json.get("usage");
+        let tokens = |key: &str| {
+            usage
+                .and_then(|u| u.get(key))
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+        };
+
+        let tokens_in = [
+            "input_tokens",
+            "cache_creation_input_tokens",
+            "cache_read_input_tokens",
+        ]
+        .iter()
+        .filter_map(|key| tokens(key))
+        .reduce(|a, b| a + b);
+
+        Some(Usage {
+            tokens_in,
+            tokens_out: tokens("output_tokens"),
+            cost: json.get("total_cost_usd").and_then(|v| v.as_f64()),
+        })
+    }
+}
+
+/// Timeline events in one `stream-json` line
+fn parse_stream_event(line: &str) -> Vec<ActivityEvent> {
+    let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+        return Vec::new();
+    };
+    let content = || {
+        event
+            .get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let phase = |label: &str| ActivityEvent::Started {
+        id: None,
+        label: label.to_string(),
+        detail: None,
+    };
+
+    match event.get("type").and_then(|t| t.as_str()) {
+        Some("system") => vec![phase("Starting session")],
+        Some("assistant") => content()
+            .iter()
+            .filter_map(|item| match item.get("type")?.as_str()? {
+                "thinking" => Some(phase("Thinking")),
+                "text" => Some(phase("Writing response")),
+                "tool_use" => Some(ActivityEvent::Started {
+                    id: item.get("id")?.as_str().map(str::to_string),
+                    label: item.get("name")?.as_str()?.to_string(),
+                    detail: item.get("input").and_then(tool_target),
+                }),
+                _ => None,
+            })
+            .collect(),
+        Some("user") => content()
+            .iter()
+            .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("tool_result"))
+            .filter_map(|item| {
+                Some(ActivityEvent::Finished {
+                    id: item.get("tool_use_id")?.as_str()?.to_string(),
+                })
+            })
+            .collect(),
+        Some("result") => vec![ActivityEvent::Done],
+        _ => Vec::new(),
+    }
+}
+
+/// What a tool call works on: a file, search pattern, command or URL
+fn tool_target(input: &serde_json::Value) -> Option<String> {
+    const MAX_CHARS: usize = 60;
+    let target = [
+        "file_path",
+        "path",
+        "notebook_path",
+        "pattern",
+        "command",
+        "url",
+    ]
+    .iter()
+    .find_map(|key| input.get(key).and_then(|v| v.as_str()))?;
+    let first_line = target.lines().next().unwrap_or_default();
+    if first_line.chars().count() > MAX_CHARS {
+        let cut: String = first_line.chars().take(MAX_CHARS).collect();
+        Some(format!("{}…", cut))
+    } else {
+        Some(first_line.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_usage() {
+        let provider = ClaudeProvider::default();
+        let stdout = r#"{"type":"result","result":"done","total_cost_usd":0.0123,"usage":{"input_tokens":12,"cache_read_input_tokens":300,"output_tokens":45}}"#;
+
+        let usage = provider.extract_usage(stdout).unwrap();
+        assert_eq!(usage.tokens_in, Some(312));
+        assert_eq!(usage.tokens_out, Some(45));
+        assert_eq!(usage.cost, Some(0.0123));
+        assert_eq!(provider.extract_usage("not json"), None);
+    }
+
+    #[test]
+    fn test_stream_events_become_activity() {
+        let tool_use = r#"{"type":"assistant","message":{"content":[{"type":"thinking","thinking":"hm"},{"type":"tool_use","id":"toolu_1","name":"Edit","input":{"file_path":"src/main.rs","old_string":"a"}}]}}"#;
+        assert_eq!(
+            parse_stream_event(tool_use),
+            vec![
+                ActivityEvent::Started {
+                    id: None,
+                    label: "Thinking".to_string(),
+                    detail: None,
+                },
+                ActivityEvent::Started {
+                    id: Some("toolu_1".to_string()),
+                    label: "Edit".to_string(),
+                    detail: Some("src/main.rs".to_string()),
+                },
+            ]
+        );
+
+        let tool_result = r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"ok"}]}}"#;
+        assert_eq!(
+            parse_stream_event(tool_result),
+            vec![ActivityEvent::Finished {
+                id: "toolu_1".to_string()
+            }]
+        );
+        assert_eq!(
+            parse_stream_event(r#"{"type":"result","result":"done"}"#),
+            vec![ActivityEvent::Done]
+        );
+        assert!(parse_stream_event("plain text").is_empty());
+    }
+
+    #[test]
+    fn test_preamble_is_appended_to_system_prompt() {
+        let request = PromptRequest {
+            prompt: "fix it".to_string(),
+            context_files: Vec::new(),
+            session_id: None,
+            working_directory: std::path::PathBuf::from("."),
+            preamble: Some(crate::preamble::Preamble {
+                text: "Be terse.".to_string(),
+                sources: vec!["global"],
+                file: None,
+            }),
+            mode: PromptMode::Edit,
+        };
+        let args = ClaudeProvider::default().build_execute_args(&request);
+        let at = args
+            .iter()
+            .position(|a| a == "--append-system-prompt")
+            .unwrap();
+        assert_eq!(args[at + 1], "Be terse.");
+        assert_eq!(args[1], "fix it");
+    }
+}
@@ -1,11 +1,13 @@
-// src/providers/copilot.rs - GitHub Copilot CLI provider
+// zcode-core/src/providers/copilot.rs - GitHub Copilot CLI provider
 
 use anyhow::Result;
 
 use super::{AIProvider, ParserType};
 use crate::config::ProviderConfig;
-use crate::parsers::parse_code_blocks;
-use crate::state::{FileChange, PromptRequest};
+use crate::parsers::{
+    copilot_command_suggestions, copilot_response_text, parse_copilot, shell_suggestions,
+};
+use crate::types::{FileChange, PromptRequest};
 
 #[derive(Debug, Clone, Default)]
 pub struct CopilotProvider {
@@ -40,7 +42,7 @@ impl AIProvider for CopilotProvider {
     }
 
     fn parse_file_changes(&self, output: &str) -> Result<Vec<FileChange>> {
-        parse_code_blocks(output)
+        parse_copilot(output)
     }
 
     fn parser_type(&self) -> ParserType {
@@ -50,4 +52,23 @@ impl AIProvider for CopilotProvider {
     fn supports_sessions(&self) -> bool {
         false
     }
+
+    fn response_text(&self, output: &str) -> String {
+        copilot_response_text(output)
+    }
+
+    fn command_suggestions(&self, output: &str) -> Vec<String> {
+        let mut commands = copilot_command_suggestions(output);
+        for command in shell_suggestions(&copilot_response_text(output)) {
+            if !commands.contains(&command) {
+                commands.push(command);
+            }
+        }
+        commands
+    }
+
+    fn login_command(&self, output: &str) -> Option<Vec<String>> {
+        super::login_hint(output)
+            .or_else(|| Some(vec![self.cli_command().to_string(), "/login".to_string()]))
+    }
 }
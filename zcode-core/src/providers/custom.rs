@@ -0,0 +1,362 @@
+// zcode-core/src/providers/custom.rs - Custom/user-configurable provider
+//
+// A custom provider is a declarative tool spec in config.toml, so a new CLI agent
+// can be integrated without code changes:
+//
+//   [providers.mytool]
+//   path = "mytool"
+//   args_template = ["run", "--message", "{prompt}", "{files}"]
+//   session_args = ["--resume", "{session}"]
+//   ask_args = ["--read-only"]
+//   parsers = ["unified_diff", "code_blocks"]
+//   session_regex = "session: (\\w+)"
+//   env = { MYTOOL_NO_COLOR = "1" }
+
+use super::{AIProvider, ParserType};
+use crate::config::ProviderConfig;
+use crate::executor::OutputSource;
+use crate::types::{FileChange, PromptMode, PromptRequest};
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
+
+/// Placeholders recognised in `args_template` and `session_args`
+const PLACEHOLDERS: [&str; 3] = ["{prompt}", "{files}", "{session}"];
+
+#[derive(Debug, Clone)]
+pub struct CustomProvider {
+    /// Display name for the provider
+    pub display_name: String,
+    /// CLI command or path to execute
+    pub command: String,
+    /// Template for command arguments; see `PLACEHOLDERS`
+    pub args_template: Vec<String>,
+    /// Arguments appended only when resuming a session
+    pub session_args: Vec<String>,
+    /// Arguments appended only to prompts sent in ask mode
+    pub ask_args: Vec<String>,
+    /// Parsers tried in order until one finds changes
+    pub parsers: Vec<ParserType>,
+    /// Which output stream(s) the parser reads
+    pub output: OutputSource,
+    /// Pulls the session id out of the output (first capture group)
+    pub session_regex: Option<Regex>,
+    /// Extra environment variables for the command
+    pub env: Vec<(String, String)>,
+}
+
+impl CustomProvider {
+    /// Create a custom provider from configuration, validating the spec
+    ///
+    /// # Arguments
+    /// * `name` - The config key name (used as fallback display name)
+    /// * `path` - The CLI command or path
+    /// * `config` - Additional configuration options
+    pub fn from_config(name: &str, path: &str, config: &ProviderConfig) -> Result<Self> {
+        let display_name = config.name.clone().unwrap_or_else(|| name.to_string());
+
+        if path.trim().is_empty() {
+            bail!("`path` is empty; set it to the CLI command to run");
+        }
+
+        let args_template = config
+            .args_template
+            .clone()
+            .unwrap_or_else(|| vec!["{prompt}".to_string()]);
+        check_placeholders("args_template", &args_template)?;
+        if !args_template.iter().any(|a| a.contains("{prompt}")) {
+            bail!("`args_template` never passes the prompt; add \"{{prompt}}\" to one argument");
+        }
+
+        let session_args = config.session_args.clone().unwrap_or_default();
+        check_placeholders("session_args", &session_args)?;
+        let ask_args = config.ask_args.clone().unwrap_or_default();
+        check_placeholders("ask_args", &ask_args)?;
+
+        let parsers = super::format::configured_parsers(config)?
+            .unwrap_or_else(|| vec![ParserType::CodeBlocks]);
+
+        let output = match config.output.as_deref() {
+            Some(value) => OutputSource::from_config(value).ok_or_else(|| {
+                anyhow!(
+                    "unknown `output` \"{}\"; expected stdout, stderr or merged",
+                    value
+                )
+            })?,
+            None => OutputSource::default(),
+        };
+
+        let session_regex = config
+            .session_regex
+            .as_deref()
+            .map(|pattern| {
+                let re = Regex::new(pattern)
+                    .with_context(|| format!("invalid `session_regex` \"{}\"", pattern))?;
+                if re.captures_len() < 2 {
+                    bail!(
+                        "`session_regex` \"{}\" needs a capture group around the session id",
+                        pattern
+                    );
+                }
+                Ok(re)
+            })
+            .transpose()?;
+        if !session_args.is_empty() && session_regex.is_none() {
+            bail!("`session_args` is set but there is no `session_regex` to find the session id");
+        }
+
+        let mut env: Vec<(String, String)> =
+            config.env.clone().unwrap_or_default().into_iter().collect();
+        env.sort();
+        if let Some((key, _)) = env
+            .iter()
+            .find(|(k, _)| k.is_empty() || k.contains('=') || k.contains('\0'))
+        {
+            bail!("invalid environment variable name \"{}\" in `env`", key);
+        }
+
+        Ok(Self {
+            display_name,
+            command: path.to_string(),
+            args_template,
+            session_args,
+            ask_args,
+            parsers,
+            output,
+            session_regex,
+            env,
+        })
+    }
+}
+
+/// Reject `{...}` placeholders we don't know, which are almost always typos
+fn check_placeholders(field: &str, args: &[String]) -> Result<()> {
+    let re = Regex::new(r"\{[A-Za-z_]+\}").expect("valid placeholder regex");
+    for (idx, arg) in args.iter().enumerate() {
+        for found in re.find_iter(arg) {
+            if !PLACEHOLDERS.contains(&found.as_str()) {
+                bail!(
+                    "unknown placeholder {} in `{}[{}]`; expected one of {}",
+                    found.as_str(),
+                    field,
+                    idx,
+                    PLACEHOLDERS.join(", ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Expand one template argument. An argument that is exactly `{files}` becomes one
+/// argument per file; arguments whose placeholders have no value are dropped.
+fn expand(arg: &str, request: &PromptRequest, out: &mut Vec<String>) {
+    if arg == "{files}" {
+        out.extend(
+            request
+                .context_files
+                .iter()
+                .map(|p| p.to_string_lossy().into_owned()),
+        );
+        return;
+    }
+
+    let files = request
+        .context_files
+        .iter()
+        .map(|p| p.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let session = request.session_id.as_deref().unwrap_or("");
+    if (arg.contains("{files}") && files.is_empty())
+        || (arg.contains("{session}") && session.is_empty())
+    {
+        return;
+    }
+
+    // One pass, so placeholder-like text inside the prompt is left alone
+    let re = Regex::new(r"\{(prompt|files|session)\}").expect("valid placeholder regex");
+    let expanded = re.replace_all(arg, |caps: &regex::Captures| match &caps[1] {
+        "prompt" => request.prompt.clone(),
+        "files" => files.clone(),
+        _ => session.to_string(),
+    });
+    out.push(expanded.into_owned());
+}
+
+impl AIProvider for CustomProvider {
+    fn name(&self) -> &str {
+        &self.display_name
+    }
+
+    fn cli_command(&self) -> &str {
+        &self.command
+    }
+
+    fn build_execute_args(&self, request: &PromptRequest) -> Vec<String> {
+        let mut args = Vec::new();
+        for arg in &self.args_template {
+            expand(arg, request, &mut args);
+        }
+        if request.session_id.is_some() {
+            for arg in &self.session_args {
+                expand(arg, request, &mut args);
+            }
+        }
+        if request.mode == PromptMode::Ask {
+            for arg in &self.ask_args {
+                expand(arg, request, &mut args);
+            }
+        }
+        args
+    }
+
+    fn parse_file_changes(&self, output: &str) -> Result<Vec<FileChange>> {
+        super::format::parse_chain(&self.parsers, output, output)
+    }
+
+    fn parser_type(&self) -> ParserType {
+        self.parsers[0].clone()
+    }
+
+    fn parsers(&self) -> Vec<ParserType> {
+        self.parsers.clone()
+    }
+
+    fn output_source(&self) -> OutputSource {
+        self.output
+    }
+
+    fn supports_sessions(&self) -> bool {
+        self.session_regex.is_some()
+    }
+
+    fn supports_ask(&self) -> bool {
+        !self.ask_args.is_empty()
+    }
+
+    fn accepts_context_files(&self) -> bool {
+        self.args_template
+            .iter()
+            .chain(&self.session_args)
+            .any(|a| a.contains("{files}"))
+    }
+
+    fn extract_session_id(&self, stdout: &str) -> Option<String> {
+        let captures = self.session_regex.as_ref()?.captures(stdout)?;
+        Some(captures.get(1)?.as_str().to_string())
+    }
+
+    fn env(&self) -> Vec<(String, String)> {
+        self.env.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn spec(edit: impl FnOnce(&mut ProviderConfig)) -> Result<CustomProvider> {
+        let mut config = ProviderConfig::default();
+        edit(&mut config);
+        CustomProvider::from_config("mytool", "mytool", &config)
+    }
+
+    fn request(session: Option<&str>, files: &[&str]) -> PromptRequest {
+        PromptRequest {
+            prompt: "fix it".to_string(),
+            context_files: files.iter().map(PathBuf::from).collect(),
+            session_id: session.map(str::to_string),
+            working_directory: PathBuf::from("."),
+            preamble: None,
+            mode: PromptMode::Edit,
+        }
+    }
+
+    #[test]
+    fn test_placeholders_expand() {
+        let provider = spec(|c| {
+            c.args_template = Some(vec![
+                "-m".into(),
+                "{prompt}".into(),
+                "{files}".into(),
+                "--resume={session}".into(),
+            ]);
+            c.session_args = Some(vec!["--continue".into(), "{session}".into()]);
+            c.session_regex = Some(r"session: (\w+)".into());
+        })
+        .unwrap();
+        assert!(provider.accepts_context_files());
+
+        assert_eq!(
+            provider.build_execute_args(&request(None, &[])),
+            vec!["-m", "fix it"]
+        );
+        assert_eq!(
+            provider.build_execute_args(&request(Some("abc"), &["a.rs", "b.rs"])),
+            vec![
+                "-m",
+                "fix it",
+                "a.rs",
+                "b.rs",
+                "--resume=abc",
+                "--continue",
+                "abc"
+            ]
+        );
+        assert_eq!(
+            provider.extract_session_id("done\nsession: s42\n"),
+            Some("s42".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ask_args_only_in_ask_mode() {
+        let plain = spec(|_| {}).unwrap();
+        assert!(!plain.supports_ask());
+
+        let provider = spec(|c| c.ask_args = Some(vec!["--read-only".into()])).unwrap();
+        assert!(provider.supports_ask());
+        assert_eq!(
+            provider.build_execute_args(&request(None, &[])),
+            vec!["fix it"]
+        );
+        let ask = PromptRequest {
+            mode: PromptMode::Ask,
+            ..request(None, &[])
+        };
+        assert_eq!(
+            provider.build_execute_args(&ask),
+            vec!["fix it", "--read-only"]
+        );
+    }
+
+    #[test]
+    fn test_parser_chain_falls_through() {
+        let provider = spec(|c| {
+            c.parsers = Some(vec!["unified_diff".into(), "code_blocks".into()]);
+        })
+        .unwrap();
+        let output = "Here you go:\n```rust\n// src/lib.rs\nfn a() {}\n```\n";
+        let changes = provider.parse_file_changes(output).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, PathBuf::from("src/lib.rs"));
+    }
+
+    #[test]
+    fn test_invalid_specs_are_rejected() {
+        let err = |edit: fn(&mut ProviderConfig)| format!("{:#}", spec(edit).unwrap_err());
+
+        assert!(err(|c| c.args_template = Some(vec!["{promt}".into()])).contains("{promt}"));
+        assert!(err(|c| c.args_template = Some(vec!["--yes".into()])).contains("{prompt}"));
+        assert!(err(|c| c.parsers = Some(vec!["yaml".into()])).contains("yaml"));
+        assert!(err(|c| c.session_regex = Some("(".into())).contains("session_regex"));
+        assert!(err(|c| c.session_regex = Some("id".into())).contains("capture group"));
+        assert!(err(|c| c.output = Some("stdin".into())).contains("output"));
+        assert!(err(|c| c.session_args = Some(vec!["{session}".into()])).contains("session_regex"));
+        assert!(err(|c| {
+            c.env = Some([("A=B".to_string(), "1".to_string())].into());
+        })
+        .contains("A=B"));
+    }
+}
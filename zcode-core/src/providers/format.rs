@@ -0,0 +1,263 @@
+//! Parser modes: which parser reads a provider's output
+//!
+//! Every provider has a parser for the format its CLI replies in. A
+//! `parser`/`parsers` entry in the provider's config replaces it, which keeps a
+//! built-in provider working when an update to its CLI moves the output to
+//! another format. [`detect_format_change`] notices such a move: a reply the
+//! configured parsers don't recognise at all, that another parser reads.
+//!
+//!   [providers.claude]
+//!   parser = "code_blocks"
+
+use anyhow::{bail, Result};
+
+use super::{AIProvider, ParserType, Usage};
+use crate::activity::ActivityEvent;
+use crate::config::ProviderConfig;
+use crate::executor::OutputSource;
+use crate::types::{FileChange, PromptRequest};
+
+/// The chain set by `parsers`, or `parser` as a chain of one; `None` when the
+/// config sets neither
+pub fn configured_parsers(config: &ProviderConfig) -> Result<Option<Vec<ParserType>>> {
+    let names = match (&config.parsers, &config.parser) {
+        (Some(chain), _) => chain.clone(),
+        (None, Some(single)) => vec![single.clone()],
+        (None, None) => return Ok(None),
+    };
+    if names.is_empty() {
+        bail!("`parsers` is empty; list at least one of unified_diff, code_blocks, json");
+    }
+    names
+        .iter()
+        .map(|name| ParserType::from_name(name))
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+/// Changes read by the first of `parsers` that finds any. The json parser
+/// reads `output`, the others `text`: the reply without any JSON around it.
+///
+/// Only an error if no parser could read the output at all.
+pub fn parse_chain(parsers: &[ParserType], output: &str, text: &str) -> Result<Vec<FileChange>> {
+    let mut last_err = None;
+    let mut any_ok = false;
+    for parser in parsers {
+        match parser.parse(input_for(parser, output, text)) {
+            Ok(changes) if !changes.is_empty() => return Ok(changes),
+            Ok(_) => any_ok = true,
+            Err(e) => last_err = Some(e),
+        }
+    }
+    match last_err {
+        Some(e) if !any_ok => Err(e),
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn input_for<'a>(parser: &ParserType, output: &'a str, text: &'a str) -> &'a str {
+    match parser {
+        ParserType::ClaudeJson => output,
+        _ => text,
+    }
+}
+
+/// Whether `parser` finds its format in the output at all, changes or not
+fn recognizes(parser: &ParserType, output: &str, text: &str) -> bool {
+    match parser {
+        ParserType::ClaudeJson => {
+            crate::parsers::claude_result_json(output).is_some_and(|json| json.is_object())
+        }
+        ParserType::UnifiedDiff => text
+            .lines()
+            .any(|line| line.starts_with("@@ ") || line.starts_with("+++ ")),
+        ParserType::CodeBlocks => text.contains("```"),
+    }
+}
+
+/// A reply in another format than the provider's parsers read
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatChange {
+    /// The parser the provider is set up with
+    pub configured: ParserType,
+    /// The parser whose format the reply is in
+    pub detected: ParserType,
+    /// Changes the detected parser reads from the reply
+    pub changes: usize,
+}
+
+/// The format `output` seems to have moved to, when `provider`'s parsers don't
+/// recognise it: JSON, or another format whose parser finds changes in it.
+/// `None` for replies the parsers recognise, such as an explanation with a
+/// code block in it.
+pub fn detect_format_change(provider: &dyn AIProvider, output: &str) -> Option<FormatChange> {
+    let configured = provider.parsers();
+    let text = provider.response_text(output);
+    if configured.iter().any(|p| recognizes(p, output, &text)) {
+        return None;
+    }
+    ParserType::ALL
+        .iter()
+        .filter(|p| !configured.contains(p))
+        .find_map(|parser| {
+            let changes = parser
+                .parse(input_for(parser, output, &text))
+                .map_or(0, |c| c.len());
+            let json = *parser == ParserType::ClaudeJson && recognizes(parser, output, &text);
+            (changes > 0 || json).then(|| FormatChange {
+                configured: configured[0].clone(),
+                detected: parser.clone(),
+                changes,
+            })
+        })
+}
+
+/// A built-in provider read with the parsers set in its config
+pub struct ParserOverride {
+    inner: Box<dyn AIProvider>,
+    parsers: Vec<ParserType>,
+}
+
+impl ParserOverride {
+    pub fn new(inner: Box<dyn AIProvider>, parsers: Vec<ParserType>) -> Self {
+        Self { inner, parsers }
+    }
+}
+
+impl AIProvider for ParserOverride {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn cli_command(&self) -> &str {
+        self.inner.cli_command()
+    }
+
+    fn build_execute_args(&self, request: &PromptRequest) -> Vec<String> {
+        self.inner.build_execute_args(request)
+    }
+
+    fn parse_file_changes(&self, output: &str) -> Result<Vec<FileChange>> {
+        parse_chain(&self.parsers, output, &self.inner.response_text(output))
+    }
+
+    fn parser_type(&self) -> ParserType {
+        self.parsers[0].clone()
+    }
+
+    fn parsers(&self) -> Vec<ParserType> {
+        self.parsers.clone()
+    }
+
+    fn output_source(&self) -> OutputSource {
+        self.inner.output_source()
+    }
+
+    fn supports_sessions(&self) -> bool {
+        self.inner.supports_sessions()
+    }
+
+    fn detection_args(&self) -> Vec<String> {
+        self.inner.detection_args()
+    }
+
+    fn accepts_context_files(&self) -> bool {
+        self.inner.accepts_context_files()
+    }
+
+    fn reads_repository(&self) -> bool {
+        self.inner.reads_repository()
+    }
+
+    fn supports_preamble(&self) -> bool {
+        self.inner.supports_preamble()
+    }
+
+    fn supports_ask(&self) -> bool {
+        self.inner.supports_ask()
+    }
+
+    fn parse_activity(&self, line: &str) -> Vec<ActivityEvent> {
+        self.inner.parse_activity(line)
+    }
+
+    fn response_text(&self, output: &str) -> String {
+        self.inner.response_text(output)
+    }
+
+    fn command_suggestions(&self, output: &str) -> Vec<String> {
+        self.inner.command_suggestions(output)
+    }
+
+    fn extract_session_id(&self, stdout: &str) -> Option<String> {
+        self.inner.extract_session_id(stdout)
+    }
+
+    fn extract_usage(&self, stdout: &str) -> Option<Usage> {
+        self.inner.extract_usage(stdout)
+    }
+
+    fn env(&self) -> Vec<(String, String)> {
+        self.inner.env()
+    }
+
+    fn stdin(&self, request: &PromptRequest) -> Option<String> {
+        self.inner.stdin(request)
+    }
+
+    fn is_auth_error(&self, output: &str) -> bool {
+        self.inner.is_auth_error(output)
+    }
+
+    fn login_command(&self, output: &str) -> Option<Vec<String>> {
+        self.inner.login_command(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::create_provider;
+
+    #[test]
+    fn test_detects_replies_in_another_format_and_overrides_the_parser() {
+        let claude = create_provider("claude", None).unwrap();
+        let blocks = "Done.\n```rust\n// src/lib.rs\nfn a() {}\n```\n";
+        assert_eq!(
+            detect_format_change(claude.as_ref(), blocks),
+            Some(FormatChange {
+                configured: ParserType::ClaudeJson,
+                detected: ParserType::CodeBlocks,
+                changes: 1,
+            })
+        );
+        // Claude's own JSON with an example in it is an explanation
+        let json =
+            r#"{"type":"result","result":"Like this:\n```rust\n// src/lib.rs\nfn a() {}\n```"}"#;
+        assert_eq!(detect_format_change(claude.as_ref(), json), None);
+        assert_eq!(detect_format_change(claude.as_ref(), "No idea."), None);
+
+        let aider = create_provider("aider", None).unwrap();
+        let detected = detect_format_change(aider.as_ref(), json).unwrap();
+        assert_eq!(
+            (detected.detected, detected.changes),
+            (ParserType::ClaudeJson, 0)
+        );
+
+        let config = ProviderConfig {
+            parser: Some("code_blocks".to_string()),
+            ..ProviderConfig::default()
+        };
+        let claude = create_provider("claude", Some(&config)).unwrap();
+        assert_eq!(claude.name(), "Claude Code");
+        assert_eq!(claude.parsers(), [ParserType::CodeBlocks]);
+        assert_eq!(claude.parse_file_changes(blocks).unwrap().len(), 1);
+        assert_eq!(detect_format_change(claude.as_ref(), blocks), None);
+
+        let config = ProviderConfig {
+            parsers: Some(vec!["yaml".to_string()]),
+            ..ProviderConfig::default()
+        };
+        assert!(configured_parsers(&config).is_err());
+    }
+}
@@ -0,0 +1,453 @@
+//! AI provider abstraction and implementations
+//!
+//! This module defines the `AIProvider` trait that all AI tools must implement,
+//! along with concrete implementations for popular tools:
+//!
+//! - **Claude**: Anthropic's Claude AI via the official CLI
+//! - **Aider**: The Aider AI code assistant
+//! - **Copilot**: GitHub Copilot CLI
+//! - **Kiro**: AWS's Kiro CLI (formerly Amazon Q Developer)
+//! - **Ollama**: Local models served by an Ollama server
+//! - **Custom**: Any other CLI, described declaratively in config.toml
+//!
+//! # Command Execution
+//!
+//! Providers build command arguments via `build_execute_args()` which are then
+//! executed asynchronously using `tokio::process::Command` by the
+//! [`executor`](crate::executor) module.
+//!
+//! # Adding a New Provider
+//!
+//! Most CLIs can be added without code as a custom provider spec (see
+//! `custom.rs`). For tools that need more than that:
+//! 1. Create a new module (e.g., `custom.rs`)
+//! 2. Implement the `AIProvider` trait
+//! 3. Add it to the match statement in `create_provider()`
+
+pub mod aider;
+pub mod amazon_q;
+pub mod claude;
+pub mod copilot;
+pub mod custom;
+pub mod format;
+pub mod ollama;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::activity::ActivityEvent;
+use crate::config::ProviderConfig;
+use crate::executor::OutputSource;
+use crate::types::{FileChange, PromptRequest};
+
+/// Parser type for interpreting provider output
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParserType {
+    /// Claude's JSON output format
+    ClaudeJson,
+    /// Standard unified diff format (used by Aider)
+    UnifiedDiff,
+    /// Markdown code blocks (used by Copilot, Kiro, Ollama)
+    CodeBlocks,
+}
+
+impl ParserType {
+    /// Every parser, in the order output formats are tried when sniffing one
+    pub const ALL: [ParserType; 3] = [
+        ParserType::ClaudeJson,
+        ParserType::UnifiedDiff,
+        ParserType::CodeBlocks,
+    ];
+
+    /// The parser a `parser`/`parsers` config value names
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "unified_diff" | "diff" => Ok(ParserType::UnifiedDiff),
+            "code_blocks" => Ok(ParserType::CodeBlocks),
+            "json" | "claude_json" => Ok(ParserType::ClaudeJson),
+            other => Err(anyhow::anyhow!(
+                "unknown parser \"{}\"; expected unified_diff, code_blocks or json",
+                other
+            )),
+        }
+    }
+
+    /// The name config uses for it
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParserType::ClaudeJson => "json",
+            ParserType::UnifiedDiff => "unified_diff",
+            ParserType::CodeBlocks => "code_blocks",
+        }
+    }
+
+    /// The output format it reads, for messages
+    pub fn format(&self) -> &'static str {
+        match self {
+            ParserType::ClaudeJson => "JSON",
+            ParserType::UnifiedDiff => "unified diffs",
+            ParserType::CodeBlocks => "code blocks",
+        }
+    }
+
+    pub fn parse(&self, output: &str) -> Result<Vec<FileChange>> {
+        match self {
+            ParserType::UnifiedDiff => crate::parsers::parse_unified_diff(output),
+            ParserType::CodeBlocks => crate::parsers::parse_code_blocks(output),
+            ParserType::ClaudeJson => crate::parsers::parse_claude_json(output),
+        }
+    }
+}
+
+/// Token usage and cost reported by a provider for one run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Usage {
+    pub tokens_in: Option<usize>,
+    pub tokens_out: Option<usize>,
+    pub cost: Option<f64>,
+}
+
+/// Put ahead of an ask-mode prompt for providers without an ask mode of their own
+pub const ASK_INSTRUCTION: &str =
+    "Answer the question below. Explain only: do not edit, create or delete any files.";
+
+/// Core trait that all AI providers must implement.
+///
+/// Providers specify command arguments via `build_execute_args()` which are then
+/// executed asynchronously by the [`executor`](crate::executor) module.
+pub trait AIProvider: Send + Sync {
+    /// Human-readable name for the provider
+    fn name(&self) -> &str;
+
+    /// Get the CLI command name
+    fn cli_command(&self) -> &str;
+
+    /// Build command arguments for execution
+    ///
+    /// Returns a vector of arguments (NOT including the command itself).
+    /// The executor will prepend the cli_command() when executing.
+    fn build_execute_args(&self, request: &PromptRequest) -> Vec<String>;
+
+    /// Text written to the command's standard input, for requests too large
+    /// to pass as an argument
+    fn stdin(&self, _request: &PromptRequest) -> Option<String> {
+        None
+    }
+
+    /// The whole command for `request`: program, arguments, environment and input
+    fn command_line(&self, request: &PromptRequest) -> crate::executor::CommandLine {
+        crate::executor::CommandLine {
+            program: self.cli_command().to_string(),
+            args: self.build_execute_args(request),
+            env: self.env(),
+            stdin: self.stdin(request),
+        }
+    }
+
+    /// Parse file changes from provider output (see `output_source()`)
+    fn parse_file_changes(&self, output: &str) -> Result<Vec<FileChange>>;
+
+    /// Get the parser type for this provider
+    fn parser_type(&self) -> ParserType;
+
+    /// Every parser tried on the output, in order
+    fn parsers(&self) -> Vec<ParserType> {
+        vec![self.parser_type()]
+    }
+
+    /// Which output the parser receives: stdout (default), stderr, or both
+    /// streams merged in the order they were written
+    fn output_source(&self) -> OutputSource {
+        OutputSource::Stdout
+    }
+
+    /// Whether this provider supports session continuity
+    fn supports_sessions(&self) -> bool;
+
+    /// Arguments to `cli_command()` that succeed when the provider can be used
+    fn detection_args(&self) -> Vec<String> {
+        vec!["--version".to_string()]
+    }
+
+    /// Whether context files are passed as paths for the provider to read itself.
+    /// Otherwise their (budgeted) contents are inlined into the prompt.
+    fn accepts_context_files(&self) -> bool {
+        false
+    }
+
+    /// Whether the provider explores the working directory on its own. Others
+    /// can be sent a project summary with the prompt.
+    fn reads_repository(&self) -> bool {
+        false
+    }
+
+    /// Whether the preamble is passed natively (as a system prompt or
+    /// instructions file). Otherwise it is prepended to the prompt.
+    fn supports_preamble(&self) -> bool {
+        false
+    }
+
+    /// Whether ask mode is passed natively (Aider's `/ask`, Claude without its
+    /// edit tools). Otherwise [`ASK_INSTRUCTION`] is prepended to the prompt.
+    fn supports_ask(&self) -> bool {
+        false
+    }
+
+    /// Timeline events in one line of output, read while the command runs.
+    /// Providers without structured progress output report nothing.
+    fn parse_activity(&self, _line: &str) -> Vec<ActivityEvent> {
+        Vec::new()
+    }
+
+    /// The reply to show in the chat, for providers whose output wraps it
+    fn response_text(&self, output: &str) -> String {
+        output.to_string()
+    }
+
+    /// Shell commands the reply suggests running, not those its tools ran
+    fn command_suggestions(&self, output: &str) -> Vec<String> {
+        crate::parsers::shell_suggestions(&crate::sections::final_answer(
+            &self.response_text(output),
+        ))
+    }
+
+    /// Extract session ID from stdout (if supported)
+    fn extract_session_id(&self, _stdout: &str) -> Option<String> {
+        None
+    }
+
+    /// Token usage and cost reported in the output (if supported).
+    /// Without it, usage is estimated from the prompt and output text.
+    fn extract_usage(&self, _stdout: &str) -> Option<Usage> {
+        None
+    }
+
+    /// Extra environment variables for the provider command
+    fn env(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// Whether a failed run's output means the user needs to log in
+    fn is_auth_error(&self, output: &str) -> bool {
+        looks_like_auth_error(output)
+    }
+
+    /// Interactive command (program followed by args) that logs the user in.
+    ///
+    /// Defaults to a command quoted in the error itself, e.g. "please run `claude login`".
+    fn login_command(&self, output: &str) -> Option<Vec<String>> {
+        login_hint(output)
+    }
+}
+
+/// Common phrases provider CLIs print when credentials are missing or expired
+pub fn looks_like_auth_error(output: &str) -> bool {
+    let lower = output.to_lowercase();
+    [
+        "not logged in",
+        "please log in",
+        "please login",
+        "run `/login`",
+        "login required",
+        "authentication required",
+        "authentication failed",
+        "invalid api key",
+        "unauthorized",
+        "credentials have expired",
+        "token has expired",
+    ]
+    .iter()
+    .any(|phrase| lower.contains(phrase))
+}
+
+/// Extract a backticked login command such as "please run `claude login`"
+pub fn login_hint(output: &str) -> Option<Vec<String>> {
+    let re = regex::Regex::new(r"(?i)run\s+`([^`]*\blog ?in\b[^`]*)`").ok()?;
+    let command = re.captures(output)?.get(1)?.as_str();
+    let parts: Vec<String> = command.split_whitespace().map(str::to_string).collect();
+    // Slash commands like `/login` only make sense inside the provider's own REPL
+    if parts.is_empty() || parts[0].starts_with('/') {
+        None
+    } else {
+        Some(parts)
+    }
+}
+
+/// Config keys of the built-in providers; any other `[providers.*]` entry is custom
+pub const BUILTIN_CONFIG_KEYS: [&str; 6] = ["claude", "aider", "copilot", "q", "kiro", "ollama"];
+
+/// Validate every custom provider spec of the `[providers.*]` tables, removing
+/// the invalid ones. A built-in provider is kept without a parser override
+/// that names an unknown parser.
+///
+/// Returns one message per removed provider or override explaining what is wrong.
+pub fn remove_invalid_custom_providers(
+    providers: &mut HashMap<String, ProviderConfig>,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    providers.retain(|key, provider| {
+        if BUILTIN_CONFIG_KEYS.contains(&key.as_str()) {
+            if let Err(e) = format::configured_parsers(provider) {
+                problems.push(format!("[providers.{}]: {:#}", key, e));
+                provider.parser = None;
+                provider.parsers = None;
+            }
+            return true;
+        }
+        if !provider.enabled {
+            return true;
+        }
+        let Some(path) = &provider.path else {
+            problems.push(format!("[providers.{}]: `path` is required", key));
+            return false;
+        };
+        match custom::CustomProvider::from_config(key, path, provider) {
+            Ok(_) => true,
+            Err(e) => {
+                problems.push(format!("[providers.{}]: {:#}", key, e));
+                false
+            }
+        }
+    });
+    problems.sort();
+    problems
+}
+
+/// Factory function to create a provider by name
+///
+/// For built-in providers, config is optional; a `parser`/`parsers` in it
+/// replaces the provider's own parser. For custom providers, config must be
+/// provided with at least a `path` specified.
+pub fn create_provider(name: &str, config: Option<&ProviderConfig>) -> Option<Box<dyn AIProvider>> {
+    let builtin: Box<dyn AIProvider> = match name.to_lowercase().as_str() {
+        "claude" | "claude code" => Box::new(claude::ClaudeProvider::new(config)),
+        "aider" => Box::new(aider::AiderProvider::new(config)),
+        "copilot" | "github copilot" | "github copilot cli" => {
+            Box::new(copilot::CopilotProvider::new(config))
+        }
+        "amazon q" | "amazon q developer" | "q" | "kiro" | "kiro cli" => {
+            Box::new(amazon_q::AmazonQProvider::new(config))
+        }
+        "ollama" => Box::new(ollama::OllamaProvider::new(config)),
+        _ => {
+            // Try to create custom provider from config
+            // (invalid specs are reported and removed when the config is loaded)
+            let config = config?;
+            let provider = custom::CustomProvider::from_config(name, config.path.as_ref()?, config);
+            return provider.ok().map(|p| Box::new(p) as Box<dyn AIProvider>);
+        }
+    };
+    match config.map(format::configured_parsers) {
+        Some(Ok(Some(parsers))) if parsers != builtin.parsers() => {
+            Some(Box::new(format::ParserOverride::new(builtin, parsers)))
+        }
+        _ => Some(builtin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_custom_providers_are_removed() {
+        let mut providers: HashMap<String, ProviderConfig> = toml::from_str(
+            r#"
+            [good]
+            path = "good-cli"
+            args_template = ["--ask", "{prompt}"]
+
+            [typo]
+            path = "typo-cli"
+            args_template = ["{promt}"]
+
+            [claude]
+            args_template = ["{anything}"]
+            "#,
+        )
+        .unwrap();
+
+        let problems = remove_invalid_custom_providers(&mut providers);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("[providers.typo]"));
+        assert!(providers.contains_key("good"));
+        assert!(providers.contains_key("claude"));
+        assert!(!providers.contains_key("typo"));
+    }
+
+    #[test]
+    fn test_looks_like_auth_error() {
+        assert!(looks_like_auth_error(
+            "Error: Not logged in. Please run `claude login`"
+        ));
+        assert!(looks_like_auth_error("Invalid API key · Please run /login"));
+        assert!(!looks_like_auth_error("error: file not found"));
+    }
+
+    #[test]
+    fn test_login_hint() {
+        assert_eq!(
+            login_hint("Not logged in. Please run `claude login` first."),
+            Some(vec!["claude".to_string(), "login".to_string()])
+        );
+        assert_eq!(login_hint("Invalid API key · Please run `/login`"), None);
+        assert_eq!(login_hint("unauthorized"), None);
+    }
+
+    #[test]
+    fn test_ollama_is_detected_through_its_server() {
+        let config: ProviderConfig = toml::from_str(
+            r#"
+            endpoint = "http://gpu-box:11434/"
+            parser = "code_blocks"
+            "#,
+        )
+        .unwrap();
+        let ollama = create_provider("ollama", Some(&config)).unwrap();
+        assert_eq!(ollama.cli_command(), "curl");
+        let args = ollama.detection_args();
+        assert_eq!(args.last().unwrap(), "http://gpu-box:11434/api/version");
+        assert!(args.contains(&"--fail".to_string()));
+
+        let claude = create_provider("claude", None).unwrap();
+        assert_eq!(claude.detection_args(), vec!["--version".to_string()]);
+    }
+
+    #[test]
+    fn test_ollama_sends_the_prompt_on_stdin() {
+        let ollama = create_provider("ollama", None).unwrap();
+        let request = PromptRequest {
+            prompt: "fix it".repeat(40_000),
+            context_files: Vec::new(),
+            session_id: None,
+            working_directory: std::path::PathBuf::from("."),
+            preamble: None,
+            mode: crate::types::PromptMode::Edit,
+        };
+        let command = ollama.command_line(&request);
+        assert!(command.args.iter().all(|arg| !arg.contains("fix it")));
+        assert!(command.args.ends_with(&[
+            "--data-binary".to_string(),
+            "@-".to_string(),
+            "http://localhost:11434/api/chat".to_string(),
+        ]));
+        let body: serde_json::Value = serde_json::from_str(&command.stdin.unwrap()).unwrap();
+        assert_eq!(body["messages"][1]["content"], request.prompt);
+    }
+
+    #[test]
+    fn test_builtin_login_commands() {
+        let claude = create_provider("claude", None).unwrap();
+        assert_eq!(
+            claude.login_command("Invalid API key · Please run /login"),
+            Some(vec!["claude".to_string(), "/login".to_string()])
+        );
+
+        let kiro = create_provider("kiro", None).unwrap();
+        assert_eq!(
+            kiro.login_command("not logged in"),
+            Some(vec!["kiro".to_string(), "login".to_string()])
+        );
+    }
+}
@@ -0,0 +1,132 @@
+// zcode-core/src/providers/ollama.rs - Local models served by Ollama
+
+use anyhow::Result;
+
+use super::{AIProvider, ParserType};
+use crate::config::ProviderConfig;
+use crate::parsers::parse_ollama_chat;
+use crate::types::{FileChange, PromptRequest};
+
+pub const DEFAULT_ENDPOINT: &str = "http://localhost:11434";
+pub const DEFAULT_MODEL: &str = "llama3.1";
+/// How long detection waits for the server to answer
+const DETECTION_TIMEOUT_SECS: u64 = 2;
+
+/// Instructs the model to answer in the code block format `parse_code_blocks` understands
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You are a coding assistant that edits files. \
+For every file you create or change, reply with one fenced code block containing the \
+complete new file contents. The first line inside the block must be a comment naming the \
+file relative to the project root, e.g. `// file: src/main.rs` or `# file: app.py`. \
+Do not use diffs or partial snippets.";
+
+/// Talks to a local Ollama server's `/api/chat` endpoint.
+///
+/// Requests are sent with `curl` so they run through the same async executor as
+/// the CLI-based providers. The `path` config option overrides the curl binary.
+/// It is detected by asking the server for its version, so it is listed only
+/// while curl is installed and the server answers.
+#[derive(Debug, Clone)]
+pub struct OllamaProvider {
+    pub cli_path: Option<String>,
+    pub endpoint: String,
+    pub model: String,
+    pub system_prompt: String,
+}
+
+impl OllamaProvider {
+    pub fn new(config: Option<&ProviderConfig>) -> Self {
+        Self {
+            cli_path: config.and_then(|c| c.path.clone()),
+            endpoint: config
+                .and_then(|c| c.endpoint.clone())
+                .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string()),
+            model: config
+                .and_then(|c| c.model.clone())
+                .unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            system_prompt: config
+                .and_then(|c| c.system_prompt.clone())
+                .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string()),
+        }
+    }
+
+    /// `/api/chat` request body: the system prompt (with the preamble) and the prompt
+    fn chat_body(&self, request: &PromptRequest) -> String {
+        let system = match &request.preamble {
+            Some(preamble) => format!("{}\n\n{}", self.system_prompt, preamble.text),
+            None => self.system_prompt.clone(),
+        };
+        serde_json::json!({
+            "model": self.model,
+            "stream": false,
+            "messages": [
+                { "role": "system", "content": system },
+                { "role": "user", "content": request.prompt },
+            ],
+        })
+        .to_string()
+    }
+
+    fn chat_url(&self) -> String {
+        format!("{}/api/chat", self.endpoint.trim_end_matches('/'))
+    }
+
+    fn version_url(&self) -> String {
+        format!("{}/api/version", self.endpoint.trim_end_matches('/'))
+    }
+}
+
+impl AIProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        "Ollama"
+    }
+
+    fn cli_command(&self) -> &str {
+        self.cli_path.as_deref().unwrap_or("curl")
+    }
+
+    // The body goes on stdin: with context inlined it can pass the kernel's
+    // limit on one argument, and arguments are visible to others in `ps`
+    fn build_execute_args(&self, _request: &PromptRequest) -> Vec<String> {
+        vec![
+            "--silent".to_string(),
+            "--show-error".to_string(),
+            "--fail".to_string(),
+            "--header".to_string(),
+            "Content-Type: application/json".to_string(),
+            "--data-binary".to_string(),
+            "@-".to_string(),
+            self.chat_url(),
+        ]
+    }
+
+    fn stdin(&self, request: &PromptRequest) -> Option<String> {
+        Some(self.chat_body(request))
+    }
+
+    fn parse_file_changes(&self, output: &str) -> Result<Vec<FileChange>> {
+        parse_ollama_chat(output)
+    }
+
+    fn parser_type(&self) -> ParserType {
+        ParserType::CodeBlocks
+    }
+
+    fn supports_sessions(&self) -> bool {
+        false
+    }
+
+    fn detection_args(&self) -> Vec<String> {
+        vec![
+            "--silent".to_string(),
+            "--show-error".to_string(),
+            "--fail".to_string(),
+            "--max-time".to_string(),
+            DETECTION_TIMEOUT_SECS.to_string(),
+            self.version_url(),
+        ]
+    }
+
+    fn supports_preamble(&self) -> bool {
+        true
+    }
+}
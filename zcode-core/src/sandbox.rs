@@ -0,0 +1,415 @@
+// zcode-core/src/sandbox.rs - Agentic providers run on a copy of the project
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::hash::{DefaultHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::types::{ChangeType, FileChange};
+
+/// Where a provider with `sandbox` set in its config runs (`[providers.*]`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxProfile {
+    /// A detached `git worktree` of HEAD, brought up to the working tree
+    Worktree,
+    /// A plain copy of the project's files in a temporary directory
+    Copy,
+}
+
+/// A throwaway copy of a project for a provider to edit. What it changes comes
+/// back as [`FileChange`]s to review ([`Sandbox::changes`]); the project
+/// itself is never written. The copy is removed when the sandbox is dropped.
+pub struct Sandbox {
+    root: PathBuf,
+    profile: SandboxProfile,
+    // Holds the copy; removed on drop
+    _dir: tempfile::TempDir,
+    /// The copy, or the worktree of the whole repository
+    checkout: PathBuf,
+    /// Where `root` is in the checkout; a worktree started from a
+    /// subdirectory of the repository runs in the same subdirectory
+    workdir: PathBuf,
+    /// Hash of every file the copy started with, by relative path
+    baseline: BTreeMap<PathBuf, u64>,
+}
+
+impl Sandbox {
+    /// Copy the files of `root` that git doesn't ignore (dotfiles included,
+    /// `.git` left out) into a new sandbox
+    pub fn create(root: &Path, profile: SandboxProfile) -> Result<Self> {
+        let mut sandbox = Self::new(root, profile)?;
+        sandbox.fill(|_, _| {})?;
+        Ok(sandbox)
+    }
+
+    /// An empty sandbox for `root`, its directory known but nothing copied
+    /// yet. [`Sandbox::fill`] copies the project, which takes a while on large
+    /// ones and can run elsewhere.
+    pub fn new(root: &Path, profile: SandboxProfile) -> Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("zcode-sandbox-")
+            .tempdir()
+            .context("Failed to create the sandbox directory")?;
+        let checkout = dir.path().join("project");
+        let workdir = match profile {
+            SandboxProfile::Worktree => checkout.join(repository_subdir(root)?),
+            SandboxProfile::Copy => checkout.clone(),
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            profile,
+            _dir: dir,
+            checkout,
+            workdir,
+            baseline: BTreeMap::new(),
+        })
+    }
+
+    /// Check out and copy the project, calling `progress` with the number of
+    /// files copied so far and the number to copy
+    pub fn fill(&mut self, progress: impl FnMut(usize, usize)) -> Result<()> {
+        if self.profile == SandboxProfile::Worktree {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(&self.root)
+                .args(["worktree", "add", "--detach", "--quiet"])
+                .arg(&self.checkout)
+                .arg("HEAD")
+                .output()
+                .context("Failed to run git")?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "git worktree add failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+        }
+        fs::create_dir_all(&self.workdir)
+            .with_context(|| format!("Failed to create {}", self.workdir.display()))?;
+        self.populate(progress)
+    }
+
+    /// Directory the provider runs in
+    pub fn path(&self) -> &Path {
+        &self.workdir
+    }
+
+    pub fn profile(&self) -> SandboxProfile {
+        self.profile
+    }
+
+    /// Bring the copy to the project's current files, recording each one
+    fn populate(&mut self, mut progress: impl FnMut(usize, usize)) -> Result<()> {
+        let files = project_files(&self.root)?;
+        for (done, rel) in files.iter().enumerate() {
+            progress(done, files.len());
+            let from = self.root.join(rel);
+            let to = self.workdir.join(rel);
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            let bytes =
+                fs::read(&from).with_context(|| format!("Failed to read {}", from.display()))?;
+            // A worktree already has the committed version of most files
+            if fs::read(&to).ok().as_deref() != Some(bytes.as_slice()) {
+                fs::write(&to, &bytes)
+                    .with_context(|| format!("Failed to write {}", to.display()))?;
+            }
+            self.baseline.insert(rel.clone(), hash(&bytes));
+        }
+        progress(files.len(), files.len());
+
+        // Files committed but deleted from the working tree
+        if self.profile == SandboxProfile::Worktree {
+            let wanted: BTreeSet<&PathBuf> = files.iter().collect();
+            for rel in project_files(&self.workdir)? {
+                if !wanted.contains(&rel) {
+                    fs::remove_file(self.workdir.join(&rel))
+                        .with_context(|| format!("Failed to remove {}", rel.display()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The provider's edits as changes to the project, paths relative to it.
+    ///
+    /// Files are compared with what the sandbox started with, so project
+    /// files edited meanwhile aren't reported; `original_content` is the
+    /// project's current text. Files that aren't UTF-8 are left out.
+    pub fn changes(&self) -> Result<Vec<FileChange>> {
+        let mut changes = Vec::new();
+        let mut present = BTreeSet::new();
+
+        for rel in project_files(&self.workdir)? {
+            let bytes = fs::read(self.workdir.join(&rel))
+                .with_context(|| format!("Failed to read {}", rel.display()))?;
+            let unchanged = self.baseline.get(&rel) == Some(&hash(&bytes));
+            present.insert(rel.clone());
+            if unchanged {
+                continue;
+            }
+            let Ok(proposed) = String::from_utf8(bytes) else {
+                continue;
+            };
+            let original = fs::read_to_string(self.root.join(&rel)).ok();
+            if original.as_deref() == Some(proposed.as_str()) {
+                continue;
+            }
+            changes.push(FileChange {
+                change_type: if original.is_some() {
+                    ChangeType::Modify
+                } else {
+                    ChangeType::Create
+                },
+                path: rel,
+                original_content: original,
+                proposed_content: proposed,
+                renamed_from: None,
+            });
+        }
+
+        for rel in self.baseline.keys().filter(|rel| !present.contains(*rel)) {
+            if let Ok(original) = fs::read_to_string(self.root.join(rel)) {
+                changes.push(FileChange {
+                    path: rel.clone(),
+                    original_content: Some(original),
+                    proposed_content: String::new(),
+                    change_type: ChangeType::Delete,
+                    renamed_from: None,
+                });
+            }
+        }
+        Ok(changes)
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        // Best effort: a stale entry is also dropped by the next `git worktree prune`
+        if self.profile == SandboxProfile::Worktree {
+            let _ = Command::new("git")
+                .arg("-C")
+                .arg(&self.root)
+                .args(["worktree", "remove", "--force"])
+                .arg(&self.checkout)
+                .output();
+        }
+    }
+}
+
+/// The changes a sandboxed provider's reply describes, with the edits it made
+/// in the sandbox ([`Sandbox::changes`]) in place of any for the same file
+pub fn merge_changes(parsed: Vec<FileChange>, edited: Vec<FileChange>) -> Vec<FileChange> {
+    let mut changes: Vec<FileChange> = parsed
+        .into_iter()
+        .filter(|change| !edited.iter().any(|e| e.path == change.path))
+        .collect();
+    changes.extend(edited);
+    changes
+}
+
+/// Where `dir` is in its git repository: empty at the top, else the path
+/// from the top (`git rev-parse --show-toplevel`) down to it
+fn repository_subdir(dir: &Path) -> Result<PathBuf> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "A worktree sandbox needs a git repository: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let top = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    // Compared resolved, as git resolves symlinks in the path it prints
+    let top = top.canonicalize().unwrap_or(top);
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    Ok(dir
+        .strip_prefix(&top)
+        .map(Path::to_path_buf)
+        .unwrap_or_default())
+}
+
+/// Regular files under `root` that git doesn't ignore, relative to it
+fn project_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let walk = ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+    for entry in walk {
+        let entry = entry.context("Failed to list the project's files")?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if let Ok(rel) = entry.path().strip_prefix(root) {
+            files.push(rel.to_path_buf());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_sandbox_reports_edits_without_touching_project() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn a() {}\n").unwrap();
+        fs::write(root.join("old.txt"), "gone\n").unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+
+        let sandbox = Sandbox::create(root, SandboxProfile::Copy).unwrap();
+        let dir = sandbox.path().to_path_buf();
+        assert_eq!(
+            fs::read_to_string(dir.join("src/lib.rs")).unwrap(),
+            "pub fn a() {}\n"
+        );
+        fs::write(dir.join("src/lib.rs"), "pub fn b() {}\n").unwrap();
+        fs::write(dir.join("new.txt"), "hi\n").unwrap();
+        fs::remove_file(dir.join("old.txt")).unwrap();
+        fs::create_dir(dir.join("target")).unwrap();
+        fs::write(dir.join("target/out"), "build\n").unwrap();
+
+        let mut changes = sandbox.changes().unwrap();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        let summary: Vec<(&Path, &ChangeType)> = changes
+            .iter()
+            .map(|c| (c.path.as_path(), &c.change_type))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Path::new("new.txt"), &ChangeType::Create),
+                (Path::new("old.txt"), &ChangeType::Delete),
+                (Path::new("src/lib.rs"), &ChangeType::Modify),
+            ]
+        );
+        assert_eq!(changes[2].proposed_content, "pub fn b() {}\n");
+        assert_eq!(
+            fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "pub fn a() {}\n"
+        );
+
+        drop(sandbox);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_worktree_sandbox_starts_from_working_tree() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        git(&["init", "-q"]);
+        fs::write(root.join("kept.txt"), "committed\n").unwrap();
+        fs::write(root.join("removed.txt"), "committed\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+        fs::write(root.join("kept.txt"), "uncommitted\n").unwrap();
+        fs::remove_file(root.join("removed.txt")).unwrap();
+
+        let sandbox = Sandbox::create(root, SandboxProfile::Worktree).unwrap();
+        let dir = sandbox.path().to_path_buf();
+        assert_eq!(
+            fs::read_to_string(dir.join("kept.txt")).unwrap(),
+            "uncommitted\n"
+        );
+        assert!(!dir.join("removed.txt").exists());
+        assert!(sandbox.changes().unwrap().is_empty());
+
+        drop(sandbox);
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["worktree", "list"])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_worktree_sandbox_from_a_subdirectory_keeps_paths_relative_to_it() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        git(&["init", "-q"]);
+        fs::create_dir(root.join("app")).unwrap();
+        fs::write(root.join("top.txt"), "top\n").unwrap();
+        fs::write(root.join("app/main.rs"), "fn main() {}\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+        fs::write(root.join("app/main.rs"), "fn main() { run() }\n").unwrap();
+
+        let mut seen = Vec::new();
+        let mut sandbox = Sandbox::new(&root.join("app"), SandboxProfile::Worktree).unwrap();
+        sandbox
+            .fill(|done, total| seen.push((done, total)))
+            .unwrap();
+        assert_eq!(seen.last(), Some(&(1, 1)));
+
+        // The provider runs in `app` of the checkout, with the working tree's file
+        let dir = sandbox.path().to_path_buf();
+        assert!(dir.ends_with("project/app"));
+        assert_eq!(
+            fs::read_to_string(dir.join("main.rs")).unwrap(),
+            "fn main() { run() }\n"
+        );
+        fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+        let changes = sandbox.changes().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, Path::new("main.rs"));
+        assert_eq!(
+            changes[0].original_content.as_deref(),
+            Some("fn main() { run() }\n")
+        );
+
+        drop(sandbox);
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["worktree", "list"])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+    }
+}
@@ -0,0 +1,201 @@
+//! Splitting agentic replies into sections
+//!
+//! Agentic CLIs print their tool calls and reasoning along with the answer.
+//! A reply is split into reasoning (`<thinking>` and `<reasoning>` blocks),
+//! tool output (a tool call line such as Kiro's "Using tool: fs_read" or
+//! Claude's "● Read(src/main.rs)", with the indented lines under it) and the
+//! answer, which is everything else. The chat collapses the first two, and the
+//! code-block parsers only read the answer so code a tool printed isn't taken
+//! for a proposed change.
+
+use regex::Regex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind {
+    Reasoning,
+    Tool,
+    Answer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub kind: SectionKind,
+    /// The tool called, or "reasoning"; empty for the answer
+    pub title: String,
+    pub text: String,
+}
+
+impl Section {
+    fn new(kind: SectionKind, title: &str) -> Self {
+        Self {
+            kind,
+            title: title.to_string(),
+            text: String::new(),
+        }
+    }
+}
+
+/// Split `reply` into its sections, in order. Sections hold whole lines.
+pub fn segment(reply: &str) -> Vec<Section> {
+    let ansi = Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").expect("valid regex");
+    let mut sections: Vec<Section> = Vec::new();
+    let mut reasoning_end: Option<&str> = None;
+    let mut in_fence = false;
+
+    for line in reply.split_inclusive('\n') {
+        let plain = ansi.replace_all(line, "");
+        let trimmed = plain.trim();
+        let current = sections.last().map(|s| s.kind);
+
+        if let Some(end) = reasoning_end {
+            if trimmed.ends_with(end) {
+                reasoning_end = None;
+            }
+            push_line(&mut sections, SectionKind::Reasoning, "reasoning", line);
+            continue;
+        }
+        if !in_fence {
+            if let Some(end) = ["thinking", "reasoning"]
+                .iter()
+                .find(|tag| trimmed.starts_with(&format!("<{}>", tag)))
+            {
+                let end = if *end == "thinking" {
+                    "</thinking>"
+                } else {
+                    "</reasoning>"
+                };
+                sections.push(Section::new(SectionKind::Reasoning, "reasoning"));
+                if !trimmed.ends_with(end) {
+                    reasoning_end = Some(end);
+                }
+                push_line(&mut sections, SectionKind::Reasoning, "reasoning", line);
+                continue;
+            }
+            if let Some(tool) = tool_call(&plain) {
+                sections.push(Section::new(SectionKind::Tool, &tool));
+                push_line(&mut sections, SectionKind::Tool, &tool, line);
+                continue;
+            }
+            if current == Some(SectionKind::Tool) && continues_tool(&plain) {
+                push_line(&mut sections, SectionKind::Tool, "", line);
+                continue;
+            }
+        }
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+        }
+        push_line(&mut sections, SectionKind::Answer, "", line);
+    }
+
+    // Blank lines that closed a tool's output belong to the answer after it
+    for i in 1..sections.len() {
+        if sections[i - 1].kind != SectionKind::Tool || sections[i].kind != SectionKind::Answer {
+            continue;
+        }
+        let kept = sections[i - 1].text.trim_end().len();
+        let kept = sections[i - 1].text[kept..]
+            .find('\n')
+            .map_or(sections[i - 1].text.len(), |n| kept + n + 1);
+        let blank = sections[i - 1].text.split_off(kept);
+        sections[i].text.insert_str(0, &blank);
+    }
+    sections
+}
+
+/// The answer part of `reply`, without its tool output and reasoning.
+/// A reply with neither is returned unchanged.
+pub fn final_answer(reply: &str) -> String {
+    let sections = segment(reply);
+    if sections.iter().all(|s| s.kind == SectionKind::Answer) {
+        return reply.to_string();
+    }
+    sections
+        .iter()
+        .filter(|s| s.kind == SectionKind::Answer)
+        .map(|s| s.text.as_str())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn push_line(sections: &mut Vec<Section>, kind: SectionKind, title: &str, line: &str) {
+    match sections.last_mut() {
+        Some(section) if section.kind == kind => section.text.push_str(line),
+        _ => {
+            let mut section = Section::new(kind, title);
+            section.text.push_str(line);
+            sections.push(section);
+        }
+    }
+}
+
+/// The tool a line calls, if it is a tool call line
+fn tool_call(line: &str) -> Option<String> {
+    // Claude Code: "● Read(src/main.rs)" or "⏺ Bash(cargo test)"
+    let claude = Regex::new(r"^[●⏺]\s*([A-Z][A-Za-z]*)\((.*)\)\s*$").expect("valid regex");
+    if let Some(cap) = claude.captures(line.trim_end()) {
+        return Some(format!("{} {}", &cap[1], &cap[2]).trim().to_string());
+    }
+    // Kiro and similar: "🛠️  Using tool: fs_read (trusted)"
+    let line = line.trim_start_matches(|c: char| !c.is_alphanumeric());
+    ["Using tool:", "Running tool:"]
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(str::to_string)
+}
+
+/// Whether a line after a tool call is still that tool's output
+fn continues_tool(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty()
+        || line.starts_with([' ', '\t'])
+        || ["⎿", "⋮", "●", "↳"].iter().any(|m| trimmed.starts_with(m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_output_and_reasoning_are_set_apart() {
+        let reply = "<thinking>\nThe route table needs a handler.\n</thinking>\n\
+                     🛠️  Using tool: fs_read (trusted)\n \
+                     ⋮\n \
+                     ● Reading file: src/routes.ts, all lines\n    \
+                     ```ts\n    export const routes = {};\n    ```\n \
+                     ● Completed in 0.1s\n\n\
+                     Here is the change:\n\n\
+                     ```ts\n// file: src/routes.ts\n  export const routes = { a };\n```\n";
+        let sections = segment(reply);
+        let kinds: Vec<SectionKind> = sections.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                SectionKind::Reasoning,
+                SectionKind::Tool,
+                SectionKind::Answer
+            ]
+        );
+        assert_eq!(sections[1].title, "fs_read");
+        assert!(sections[1].text.ends_with("Completed in 0.1s\n"));
+        let whole: String = sections.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(whole, reply);
+
+        assert_eq!(
+            final_answer(reply),
+            "Here is the change:\n\n```ts\n// file: src/routes.ts\n  export const routes = { a };\n```"
+        );
+        assert_eq!(final_answer("Just an answer\n"), "Just an answer\n");
+    }
+
+    #[test]
+    fn test_claude_tool_calls_end_at_the_next_unindented_line() {
+        let reply = "● Bash(cargo test)\n  ⎿  test result: ok. 3 passed\n\nAll tests pass.\n";
+        let sections = segment(reply);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].title, "Bash cargo test");
+        assert_eq!(sections[1].text, "\nAll tests pass.\n");
+        assert_eq!(final_answer(reply), "All tests pass.");
+    }
+}
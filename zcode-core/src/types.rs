@@ -0,0 +1,243 @@
+//! The data passed along the pipeline
+//!
+//! A [`PromptRequest`] goes to a provider, whose output is parsed into
+//! [`FileChange`]s. Each change is diffed into [`Hunk`]s, which are accepted or
+//! rejected and then applied.
+
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// Request to send to an AI provider
+#[derive(Debug, Clone)]
+pub struct PromptRequest {
+    pub prompt: String,
+    pub context_files: Vec<PathBuf>,
+    pub session_id: Option<String>,
+    pub working_directory: PathBuf,
+    pub preamble: Option<crate::preamble::Preamble>,
+    pub mode: PromptMode,
+}
+
+/// Whether a prompt asks for edits or only for an answer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptMode {
+    /// File changes to review
+    #[default]
+    Edit,
+    /// An explanation; the reply is shown, never reviewed
+    Ask,
+}
+
+/// Raw response from an AI provider
+#[derive(Debug, Clone)]
+pub struct ProviderResponse {
+    pub raw_output: String,
+    pub exit_code: i32,
+    pub stderr: String,
+}
+
+/// Parsed file change from provider output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub original_content: Option<String>,
+    pub proposed_content: String,
+    pub change_type: ChangeType,
+    /// The file's path before the change renames it to `path`; its content
+    /// there is `original_content`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renamed_from: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ChangeType {
+    Create,
+    Modify,
+    Delete,
+}
+
+/// A diff hunk for review
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub id: usize,
+    pub file_path: PathBuf,
+    /// Lines the hunk replaces in the original, context included
+    pub old: HunkRange,
+    /// Lines it becomes in the proposed file, context included
+    pub new: HunkRange,
+    pub changes: Vec<LineChange>,
+    pub status: HunkStatus,
+    /// Set when the file changed on disk during review and this hunk no longer
+    /// matches what was originally proposed
+    pub drifted: bool,
+    /// Reviewer's note, sent back to the provider with a fix request
+    pub comment: Option<String>,
+    /// Auto-accept rule that accepted the hunk; cleared by any later decision
+    pub auto_accepted: Option<String>,
+}
+
+/// The lines one side of a hunk covers, as in a unified diff header
+/// (`@@ -start,len +start,len @@`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HunkRange {
+    /// 1-based first line; for an empty range, the line it follows (0 for the
+    /// start of the file)
+    pub start: usize,
+    pub len: usize,
+}
+
+impl HunkRange {
+    /// The range of the 0-based line indices `indices`
+    pub fn from_indices(indices: std::ops::Range<usize>) -> Self {
+        let len = indices.len();
+        let start = if len > 0 {
+            indices.start + 1
+        } else {
+            indices.start
+        };
+        Self { start, len }
+    }
+
+    /// 0-based indices of the lines covered (empty at the insertion point
+    /// for an empty range)
+    pub fn indices(&self) -> std::ops::Range<usize> {
+        let first = if self.len > 0 {
+            self.start - 1
+        } else {
+            self.start
+        };
+        first..first + self.len
+    }
+}
+
+impl std::fmt::Display for HunkRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.start, self.len)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HunkStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone)]
+pub struct LineChange {
+    pub tag: ChangeTag,
+    pub content: LineText,
+    pub old_line_num: Option<usize>,
+    pub new_line_num: Option<usize>,
+}
+
+/// One line of a file: a range of a buffer shared by every line cut from it.
+///
+/// Diffing a file holds each side once; the hunks, their copies in caches and
+/// undo history, and the review's decorations only point into it.
+#[derive(Clone)]
+pub struct LineText {
+    buffer: Arc<str>,
+    range: Range<usize>,
+}
+
+impl LineText {
+    /// `range` of `buffer`, which must fall on char boundaries
+    pub fn slice(buffer: &Arc<str>, range: Range<usize>) -> Self {
+        debug_assert!(buffer.get(range.clone()).is_some());
+        Self {
+            buffer: Arc::clone(buffer),
+            range,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.buffer[self.range.clone()]
+    }
+
+    /// The same line without its line ending, sharing the buffer
+    pub fn without_newline(&self) -> Self {
+        let len = self.as_str().trim_end_matches('\n').len();
+        Self {
+            buffer: Arc::clone(&self.buffer),
+            range: self.range.start..self.range.start + len,
+        }
+    }
+}
+
+impl std::ops::Deref for LineText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for LineText {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for LineText {
+    fn from(text: &str) -> Self {
+        Self {
+            range: 0..text.len(),
+            buffer: Arc::from(text),
+        }
+    }
+}
+
+impl From<String> for LineText {
+    fn from(text: String) -> Self {
+        Self {
+            range: 0..text.len(),
+            buffer: Arc::from(text),
+        }
+    }
+}
+
+impl PartialEq for LineText {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for LineText {}
+
+impl PartialEq<str> for LineText {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for LineText {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl std::fmt::Debug for LineText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl std::fmt::Display for LineText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeTag {
+    Equal,
+    Insert,
+    Delete,
+}
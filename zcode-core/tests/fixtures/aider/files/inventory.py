@@ -0,0 +1,25 @@
+"""Fixture inventory used by the unified diff corpus."""
+
+import json
+
+
+class Inventory:
+    def __init__(self):
+        self.items = {}
+
+    def add(self, name, count):
+        self.items[name] = count
+
+    def remove(self, name):
+        del self.items[name]
+
+    def total(self):
+        return sum(self.items.values())
+
+    def names(self):
+        return sorted(self.items)
+
+
+def load(path):
+    with open(path) as f:
+        return json.load(f)